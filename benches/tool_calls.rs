@@ -0,0 +1,158 @@
+//! Tool call latency and throughput, on the mock chain backends and, when
+//! `ANVIL_RPC_URL` is set, against a local Anvil node.
+//!
+//! Each tool is measured calling `execute` directly and through the server's
+//! dispatch path (schema validation, concurrency budget, panic containment,
+//! response trimming), so the cost of each layer shows up on its own.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ethereum_trading_mcp_server::config::ConcurrencyLimit;
+use ethereum_trading_mcp_server::ethereum::mock::{MockEthereumClient, MockUniswapRouter};
+use ethereum_trading_mcp_server::ethereum::EthereumClient;
+use ethereum_trading_mcp_server::mcp::{ConcurrencyLimiter, McpServer};
+use ethereum_trading_mcp_server::tools::{GetBalanceTool, GetTokenPriceTool, Tool};
+use ethers::types::Address;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const WALLET: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+const UNI: &str = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+// Anvil's first prefunded account
+const ANVIL_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const ANVIL_ACCOUNT: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+const ANVIL_CHAIN_ID: u64 = 31337;
+
+// Calls in flight at once in the throughput benchmarks
+const CONCURRENCY: [usize; 3] = [1, 16, 64];
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime")
+}
+
+/// A server that never queues, so throughput is bounded by the tools alone
+fn server(tools: Vec<Arc<dyn Tool>>) -> McpServer {
+    let limiter = ConcurrencyLimiter::new(|_| ConcurrencyLimit {
+        max_concurrency: CONCURRENCY[CONCURRENCY.len() - 1],
+        queue_depth: 0,
+    });
+    McpServer::with_tools(tools, limiter)
+}
+
+fn mock_tools() -> Vec<Arc<dyn Tool>> {
+    let wallet: Address = WALLET.parse().unwrap();
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(5, 0)),
+    );
+    let uniswap = Arc::new(MockUniswapRouter::new().with_price(
+        UNI.parse().unwrap(),
+        USDC.parse().unwrap(),
+        Decimal::new(10, 0),
+    ));
+    vec![
+        Arc::new(GetBalanceTool::new(client.clone())),
+        Arc::new(GetTokenPriceTool::new(client, uniswap).unwrap()),
+    ]
+}
+
+/// Time one call of each tool, directly and through dispatch
+fn bench_latency(
+    c: &mut Criterion,
+    group_name: &str,
+    rt: &Runtime,
+    tools: &[Arc<dyn Tool>],
+    calls: &[(&str, Value)],
+) {
+    let server = server(tools.to_vec());
+    let mut group = c.benchmark_group(group_name);
+    for (name, params) in calls {
+        let tool = tools.iter().find(|tool| tool.name() == *name).unwrap();
+        group.bench_with_input(BenchmarkId::new("execute", name), params, |b, params| {
+            b.to_async(rt)
+                .iter(|| async { tool.execute(params.clone()).await.unwrap() })
+        });
+        group.bench_with_input(BenchmarkId::new("dispatch", name), params, |b, params| {
+            b.to_async(rt)
+                .iter(|| async { server.dispatch(name, params.clone()).await.unwrap() })
+        });
+    }
+    group.finish();
+}
+
+/// Calls completed per second with several dispatched at once
+fn bench_throughput(
+    c: &mut Criterion,
+    group_name: &str,
+    rt: &Runtime,
+    tools: &[Arc<dyn Tool>],
+    (name, params): (&str, Value),
+) {
+    let server = server(tools.to_vec());
+    let mut group = c.benchmark_group(group_name);
+    for in_flight in CONCURRENCY {
+        group.throughput(Throughput::Elements(in_flight as u64));
+        group.bench_with_input(
+            BenchmarkId::new(name, in_flight),
+            &in_flight,
+            |b, &in_flight| {
+                b.to_async(rt).iter(|| async {
+                    let calls = (0..in_flight).map(|_| server.dispatch(name, params.clone()));
+                    for result in join_all(calls).await {
+                        result.unwrap();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn mock_benches(c: &mut Criterion) {
+    let rt = runtime();
+    let tools = mock_tools();
+    let calls = [
+        ("get_balance", json!({ "address": WALLET })),
+        (
+            "get_token_price",
+            json!({ "token_address": UNI, "quote_currency": "USD" }),
+        ),
+    ];
+    bench_latency(c, "mock_latency", &rt, &tools, &calls);
+    bench_throughput(c, "mock_throughput", &rt, &tools, calls[0].clone());
+}
+
+fn anvil_benches(c: &mut Criterion) {
+    let Ok(rpc_url) = std::env::var("ANVIL_RPC_URL") else {
+        eprintln!("ANVIL_RPC_URL is not set; skipping the Anvil benchmarks");
+        return;
+    };
+    let rt = runtime();
+    let client = Arc::new(
+        rt.block_on(EthereumClient::new(&rpc_url, ANVIL_KEY, ANVIL_CHAIN_ID))
+            .expect("Anvil client"),
+    );
+    let account: Address = ANVIL_ACCOUNT.parse().unwrap();
+
+    // The bare RPC round trip the tools build on
+    c.bench_function("anvil_rpc/get_eth_balance", |b| {
+        b.to_async(&rt)
+            .iter(|| async { client.get_eth_balance(account).await.unwrap() })
+    });
+
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(client))];
+    let call = ("get_balance", json!({ "address": ANVIL_ACCOUNT }));
+    bench_latency(c, "anvil_latency", &rt, &tools, std::slice::from_ref(&call));
+    bench_throughput(c, "anvil_throughput", &rt, &tools, call);
+}
+
+criterion_group!(benches, mock_benches, anvil_benches);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+//! Feeds raw bytes through the server's dispatch path as a tool name and JSON
+//! arguments. The server catches tool panics so the session survives them; this
+//! target fails on any it catches.
+
+#![no_main]
+
+use ethereum_trading_mcp_server_fuzz::{runtime, server, tools};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, json)) = data.split_first() else {
+        return;
+    };
+    let Ok(params) = serde_json::from_slice::<serde_json::Value>(json) else {
+        return;
+    };
+    let tools = tools();
+    let name = tools[selector as usize % tools.len()].name();
+    if let Err(error) = runtime().block_on(server().dispatch(name, params)) {
+        let code = error.data.as_ref().and_then(|data| data["code"].as_str());
+        assert_ne!(code, Some("tool_panicked"), "{}", error.message);
+    }
+});
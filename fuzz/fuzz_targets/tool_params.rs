@@ -0,0 +1,16 @@
+//! Calls each tool's own parameter handling directly, skipping the server's schema
+//! check, with arguments built from the names its schema declares. Any panic is a
+//! bug: tools must turn bad input into errors.
+
+#![no_main]
+
+use ethereum_trading_mcp_server_fuzz::{runtime, ToolCall};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|call: ToolCall| {
+    let (tool, params) = call.resolve();
+    let _ = tool.confirmation_prompt(&params);
+    if let Ok(result) = runtime().block_on(tool.execute(params.clone())) {
+        let _ = tool.explain(&params, &result);
+    }
+});
@@ -0,0 +1,360 @@
+//! Shared setup for the fuzz targets: every tool the server registers, built on
+//! the mock chain backends, and a generator of tool arguments that leans towards
+//! the inputs agents get wrong (bad addresses, odd amounts, wrong types).
+
+use ethereum_trading_mcp_server::config::ConcurrencyLimit;
+use ethereum_trading_mcp_server::ethereum::mock::{
+    MockEns, MockEthereumClient, MockFxRates, MockGovernance, MockLockSource, MockPoolDiscovery,
+    MockSignatures, MockStreamSource, MockTokenEvents, MockUniswapRouter, MockUniswapV3, MockVenue,
+    MockYieldSource,
+};
+use ethereum_trading_mcp_server::ethereum::{
+    ChainClients, EnsSource, GovernanceSource, LockSource, PriceVenue, StreamSource, YieldSource,
+};
+use ethereum_trading_mcp_server::mcp::{ConcurrencyLimiter, McpServer};
+use ethereum_trading_mcp_server::tools::*;
+use ethers::types::Address;
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use serde_json::{Map, Number, Value};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Runtime the targets drive tools on. The clock is paused, so tools that wait
+/// (e.g. `check_payment` polling) return at once instead of stalling the fuzzer.
+pub fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .expect("tokio runtime")
+    })
+}
+
+/// Every tool the server registers, with execution enabled so write paths run
+/// against the mock client
+pub fn tools() -> &'static [Arc<dyn Tool>] {
+    static TOOLS: OnceLock<Vec<Arc<dyn Tool>>> = OnceLock::new();
+    TOOLS.get_or_init(build_tools)
+}
+
+/// A server around [`tools`] with no limits beyond the defaults
+pub fn server() -> &'static McpServer {
+    static SERVER: OnceLock<McpServer> = OnceLock::new();
+    SERVER.get_or_init(|| {
+        let limiter = ConcurrencyLimiter::new(|_| ConcurrencyLimit {
+            max_concurrency: 1,
+            queue_depth: 0,
+        });
+        McpServer::with_tools(tools().to_vec(), limiter)
+            .with_scam_list(Arc::new(ScamList::new(Vec::new())))
+            .with_max_response_bytes(Some(64 * 1024))
+    })
+}
+
+fn build_tools() -> Vec<Arc<dyn Tool>> {
+    let client = Arc::new(MockEthereumClient::new());
+    let uniswap = Arc::new(MockUniswapRouter::new());
+    let uniswap_v3 = Arc::new(MockUniswapV3::new());
+    let discovery = Arc::new(MockPoolDiscovery::new());
+    let token_events = Arc::new(MockTokenEvents::new());
+    let chains = Arc::new(ChainClients::new().with_client(client.clone()));
+    let book = Arc::new(AddressBook::new(Vec::new()).expect("address book"));
+    let scams = Arc::new(ScamList::new(Vec::new()));
+    let recipients = Arc::new(RecipientHistory::new(token_events.clone(), 10_000));
+    let schedules = Arc::new(PaymentSchedules::new());
+    let payment_requests = Arc::new(PaymentRequests::new());
+    let quotes = Arc::new(QuoteStore::new(Duration::from_secs(60)));
+    let ens: Arc<dyn EnsSource> = Arc::new(MockEns::new());
+    let governance: Arc<dyn GovernanceSource> = Arc::new(MockGovernance::new("governor"));
+    let snapshot: Arc<dyn GovernanceSource> = Arc::new(MockGovernance::new("snapshot"));
+    let venues: Vec<Arc<dyn PriceVenue>> = vec![
+        Arc::new(MockVenue::new("Uniswap V2")),
+        Arc::new(MockVenue::new("Curve")),
+    ];
+    let yield_sources: Vec<Arc<dyn YieldSource>> =
+        vec![Arc::new(MockYieldSource::new("sDAI", 100_000))];
+    let lock_sources: Vec<Arc<dyn LockSource>> = vec![Arc::new(MockLockSource::new("Unicrypt"))];
+    let stream_sources: Vec<Arc<dyn StreamSource>> =
+        vec![Arc::new(MockStreamSource::new("Sablier"))];
+    let sablier = Address::repeat_byte(0x5a);
+    let disperse = Address::repeat_byte(0xd1);
+
+    vec![
+        Arc::new(GetBalanceTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(GetBalanceAllChainsTool::new(chains.clone()).with_address_book(book.clone())),
+        Arc::new(GetPortfolioTool::new(chains).with_address_book(book.clone())),
+        Arc::new(
+            GetTokenPriceTool::new(client.clone(), uniswap.clone())
+                .expect("get_token_price tool")
+                .with_fx_rates(Arc::new(MockFxRates::new())),
+        ),
+        Arc::new(
+            SwapTokensTool::new(client.clone(), uniswap.clone()).with_quote_store(quotes.clone()),
+        ),
+        Arc::new(RecommendSlippageTool::new(client.clone(), uniswap.clone())),
+        Arc::new(GetMarketStatsTool::new(
+            client.clone(),
+            uniswap.clone(),
+            uniswap_v3.clone(),
+        )),
+        Arc::new(GetPoolLiquidityProfileTool::new(client.clone(), uniswap_v3)),
+        Arc::new(ScanArbitrageTool::new(client.clone(), venues)),
+        Arc::new(CompareYieldsTool::new(
+            client.clone(),
+            yield_sources,
+            Arc::new(MockVenue::new("Uniswap V2")),
+        )),
+        Arc::new(
+            CheckTokenLocksTool::new(client.clone(), lock_sources, token_events.clone())
+                .with_address_book(book.clone()),
+        ),
+        Arc::new(
+            ExecutePlanTool::new(client.clone(), true)
+                .with_address_book(book.clone())
+                .with_recipient_history(recipients.clone())
+                .with_scam_list(scams.clone()),
+        ),
+        Arc::new(
+            BatchTransferTool::new(client.clone(), disperse, true)
+                .with_address_book(book.clone())
+                .with_recipient_history(recipients.clone())
+                .with_scam_list(scams.clone()),
+        ),
+        Arc::new(
+            ListStreamsTool::new(client.clone(), stream_sources).with_address_book(book.clone()),
+        ),
+        Arc::new(
+            CreateStreamTool::new(client.clone(), sablier, true)
+                .with_address_book(book.clone())
+                .with_recipient_history(recipients)
+                .with_scam_list(scams.clone()),
+        ),
+        Arc::new(CancelStreamTool::new(client.clone(), sablier, true)),
+        Arc::new(GetGovernanceProposalsTool::new(
+            governance.clone(),
+            snapshot.clone(),
+        )),
+        Arc::new(
+            GetVotingPowerTool::new(client.clone(), governance.clone(), snapshot)
+                .with_address_book(book.clone()),
+        ),
+        Arc::new(GetDelegationTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(DelegateVotesTool::new(client.clone(), true).with_address_book(book.clone())),
+        Arc::new(CastVoteTool::new(client.clone(), governance, true)),
+        Arc::new(GetEnsNameTool::new(client.clone(), ens.clone()).with_address_book(book.clone())),
+        Arc::new(
+            ListEnsNamesTool::new(client.clone(), ens.clone()).with_address_book(book.clone()),
+        ),
+        Arc::new(RenewEnsNameTool::new(client.clone(), ens, true)),
+        Arc::new(ExecuteSwapTool::new(
+            client.clone(),
+            uniswap,
+            quotes,
+            true,
+            100,
+        )),
+        Arc::new(DiagnoseWalletTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(BuildTransactionTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(CallContractTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(ReadStorageTool::new(client.clone()).with_address_book(book.clone())),
+        Arc::new(
+            InspectBytecodeTool::new(client.clone(), Arc::new(MockSignatures::new()))
+                .with_address_book(book.clone()),
+        ),
+        Arc::new(
+            CreatePaymentRequestTool::new(client.clone(), payment_requests.clone())
+                .with_address_book(book.clone()),
+        ),
+        Arc::new(CheckPaymentTool::new(
+            client.clone(),
+            token_events.clone(),
+            payment_requests,
+        )),
+        Arc::new(
+            SchedulePaymentTool::new(client.clone(), schedules.clone(), true)
+                .with_address_book(book.clone())
+                .with_scam_list(scams.clone()),
+        ),
+        Arc::new(ListScheduledPaymentsTool::new(schedules.clone())),
+        Arc::new(PauseScheduledPaymentTool::new(schedules.clone())),
+        Arc::new(ResumeScheduledPaymentTool::new(schedules)),
+        Arc::new(AddContactTool::new(book.clone())),
+        Arc::new(ListContactsTool::new(book)),
+        Arc::new(ImportSignedTransactionTool::new(client.clone(), true).with_scam_list(scams)),
+        Arc::new(FindPoolsTool::new(discovery.clone())),
+        Arc::new(
+            MonitorNewPairsTool::new(
+                client.clone(),
+                discovery.clone(),
+                vec![Address::repeat_byte(0xee)],
+            )
+            .unwrap(),
+        ),
+        Arc::new(MonitorTokenRisksTool::new(client, discovery, token_events)),
+    ]
+}
+
+/// Strings agents send where tools expect addresses, amounts, symbols and names
+const INTERESTING_STRINGS: &[&str] = &[
+    "",
+    " ",
+    "0x",
+    "0x0",
+    "0xZZ",
+    "0x0000000000000000000000000000000000000000",
+    "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+    "0xd8da6bf26964af9d7eed9e03e53415d37aa96045",
+    "0xD8DA6BF26964AF9D7EED9E03E53415D37AA96045",
+    "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA9604",
+    "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA960450",
+    "d8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+    "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+    "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+    "vitalik.eth",
+    ".eth",
+    "ETH",
+    "WETH",
+    "USDC",
+    "0",
+    "-0",
+    "-1",
+    "1",
+    "0.5",
+    "1.",
+    ".1",
+    "1e18",
+    "1E-30",
+    "NaN",
+    "inf",
+    "0.000000000000000000000000000001",
+    "79228162514264337593543950335",
+    "79228162514264337593543950336",
+    "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+    "115792089237316195423570985008687907853269984665640564039457584007913129639936",
+    "99999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "1,000",
+    "1_000",
+    "0x1",
+    "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    "2024-01-01T00:00:00Z",
+    "P1D",
+    "*/5 * * * *",
+    "\u{0}",
+    "💥",
+    "ℯ𝓉𝒽",
+];
+
+/// A tool call: which tool, and its arguments keyed mostly by the names its schema lists
+#[derive(Debug, Arbitrary)]
+pub struct ToolCall {
+    pub tool: u8,
+    pub params: Vec<(Key, Param)>,
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Key {
+    /// One of the tool's declared properties, by index
+    Declared(u8),
+    Other(String),
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Param {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Interesting(u8),
+    Address([u8; 20]),
+    Hex(Vec<u8>),
+    /// A decimal string `mantissa` scaled down by `scale` places
+    Amount(u128, u8),
+    Text(String),
+    List(Vec<Param>),
+    Object(Vec<(Key, Param)>),
+}
+
+impl Param {
+    fn to_value(&self, declared: &[String]) -> Value {
+        match self {
+            Param::Null => Value::Null,
+            Param::Bool(flag) => Value::Bool(*flag),
+            Param::Int(n) => Value::from(*n),
+            Param::Uint(n) => Value::from(*n),
+            Param::Float(n) => Number::from_f64(*n).map_or(Value::Null, Value::Number),
+            Param::Interesting(index) => {
+                Value::from(INTERESTING_STRINGS[*index as usize % INTERESTING_STRINGS.len()])
+            }
+            Param::Address(bytes) => Value::from(format!("{:?}", Address::from(*bytes))),
+            Param::Hex(bytes) => Value::from(format!("0x{}", ethers::utils::hex::encode(bytes))),
+            Param::Amount(mantissa, scale) => Value::from(amount_string(*mantissa, *scale)),
+            Param::Text(text) => Value::from(text.as_str()),
+            Param::List(items) => {
+                Value::Array(items.iter().map(|item| item.to_value(declared)).collect())
+            }
+            Param::Object(fields) => Value::Object(object(fields, declared)),
+        }
+    }
+}
+
+fn amount_string(mantissa: u128, scale: u8) -> String {
+    let digits = mantissa.to_string();
+    let scale = scale as usize % 40;
+    if scale == 0 {
+        return digits;
+    }
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - scale);
+    format!("{}.{}", whole, fraction)
+}
+
+fn object(fields: &[(Key, Param)], declared: &[String]) -> Map<String, Value> {
+    fields
+        .iter()
+        .map(|(key, param)| {
+            let key = match key {
+                Key::Declared(index) if !declared.is_empty() => {
+                    declared[*index as usize % declared.len()].clone()
+                }
+                Key::Declared(_) => "value".to_string(),
+                Key::Other(name) => name.clone(),
+            };
+            (key, param.to_value(declared))
+        })
+        .collect()
+}
+
+/// Property names a schema declares, at any depth, so nested objects such as
+/// plan steps and recipients get plausible keys too
+pub fn declared_properties(schema: &Value) -> Vec<String> {
+    fn collect(schema: &Value, names: &mut Vec<String>) {
+        match schema {
+            Value::Object(fields) => {
+                if let Some(Value::Object(properties)) = fields.get("properties") {
+                    names.extend(properties.keys().cloned());
+                }
+                fields.values().for_each(|field| collect(field, names));
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, names)),
+            _ => {}
+        }
+    }
+    let mut names = Vec::new();
+    collect(schema, &mut names);
+    names.sort();
+    names.dedup();
+    names
+}
+
+impl ToolCall {
+    /// The tool this call targets and its arguments
+    pub fn resolve(&self) -> (&'static Arc<dyn Tool>, Value) {
+        let tools = tools();
+        let tool = &tools[self.tool as usize % tools.len()];
+        let declared = declared_properties(&tool.input_schema());
+        (tool, Value::Object(object(&self.params, &declared)))
+    }
+}
@@ -0,0 +1,408 @@
+//! Load-test harness for the streamable HTTP transport.
+//!
+//! Opens one MCP session per simulated client and fires tool calls at the
+//! server concurrently, then reports how many succeeded, how many were turned
+//! away with `rate_limited` or `saturated`, and the latency distribution. Any
+//! other outcome (transport failures, unexpected errors) makes it exit non-zero.
+//!
+//! Without `--url` it starts its own server on 127.0.0.1 with a mock
+//! `get_balance` tool that takes `--latency-ms` to answer, so the connection
+//! cap, rate limit and queue can be exercised without a chain.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethereum_trading_mcp_server::config::{ApiToken, ConcurrencyLimit};
+use ethereum_trading_mcp_server::ethereum::mock::MockEthereumClient;
+use ethereum_trading_mcp_server::mcp::http::{serve_listener, LimitedListener};
+use ethereum_trading_mcp_server::mcp::{Authenticator, ConcurrencyLimiter, McpServer, RateLimit};
+use ethereum_trading_mcp_server::tools::{GetBalanceTool, PermissionTier, Tool, ToolCategory};
+use ethers::types::Address;
+use futures::stream::{self, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+const WALLET: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+const USAGE: &str = "\
+Usage: load-test [OPTIONS]
+
+Target:
+  --url <URL>              MCP endpoint to load, e.g. http://127.0.0.1:8080/mcp
+                           (default: start a mock server in-process)
+  --token <TOKEN>          Bearer token; repeat to spread clients across tokens
+
+Load:
+  --calls <N>              Tool calls to make in total (default 128)
+  --clients <N>            MCP sessions the calls are spread across (default 4)
+  --tool <NAME>            Tool to call (default get_balance)
+  --args <JSON>            Tool arguments (default {\"address\": <vitalik.eth>})
+
+Mock server (without --url):
+  --latency-ms <MS>        How long each mock call takes (default 50)
+  --max-concurrency <N>    Calls run at once (default 16)
+  --queue-depth <N>        Calls waiting for a slot before `saturated` (default 64)
+  --max-connections <N>    Open HTTP connections, 0 for no cap (default 256)
+  --rate-limit <N>         Calls per minute per client, 0 for none (default 0)
+  --burst <N>              Calls a client may make at once (default: --rate-limit)
+";
+
+struct Options {
+    url: Option<String>,
+    tokens: Vec<String>,
+    calls: usize,
+    clients: usize,
+    tool: String,
+    args: Value,
+    latency: Duration,
+    limit: ConcurrencyLimit,
+    max_connections: usize,
+    rate_limit: u32,
+    burst: Option<u32>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Options {
+            url: None,
+            tokens: Vec::new(),
+            calls: 128,
+            clients: 4,
+            tool: "get_balance".to_string(),
+            args: json!({ "address": WALLET }),
+            latency: Duration::from_millis(50),
+            limit: ConcurrencyLimit {
+                max_concurrency: 16,
+                queue_depth: 64,
+            },
+            max_connections: 256,
+            rate_limit: 0,
+            burst: None,
+        };
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            let value = args
+                .next()
+                .with_context(|| format!("{} needs a value", flag))?;
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .with_context(|| format!("Invalid {}: {}", flag, value))
+            };
+            match flag.as_str() {
+                "--url" => options.url = Some(value.clone()),
+                "--token" => options.tokens.push(value.clone()),
+                "--calls" => options.calls = number()?,
+                "--clients" => options.clients = number()?.max(1),
+                "--tool" => options.tool = value.clone(),
+                "--args" => {
+                    options.args = serde_json::from_str(&value).context("Invalid --args JSON")?
+                }
+                "--latency-ms" => options.latency = Duration::from_millis(number()? as u64),
+                "--max-concurrency" => options.limit.max_concurrency = number()?.max(1),
+                "--queue-depth" => options.limit.queue_depth = number()?,
+                "--max-connections" => options.max_connections = number()?,
+                "--rate-limit" => options.rate_limit = number()? as u32,
+                "--burst" => options.burst = Some(number()? as u32),
+                other => anyhow::bail!("Unknown option {}\n\n{}", other, USAGE),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Answers like the wrapped tool, after a fixed delay standing in for RPC time
+struct DelayedTool {
+    inner: Arc<dyn Tool>,
+    delay: Duration,
+}
+
+#[async_trait]
+impl Tool for DelayedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn input_schema(&self) -> Value {
+        self.inner.input_schema()
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        tokio::time::sleep(self.delay).await;
+        self.inner.execute(params).await
+    }
+
+    fn category(&self) -> ToolCategory {
+        self.inner.category()
+    }
+}
+
+/// Start the mock server, giving each client its own token so the rate limit
+/// applies per client. Returns the endpoint and the tokens.
+async fn spawn_mock_server(options: &Options) -> Result<(String, Vec<String>)> {
+    let wallet: Address = WALLET.parse()?;
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(5, 0)),
+    );
+    let tool: Arc<dyn Tool> = Arc::new(DelayedTool {
+        inner: Arc::new(GetBalanceTool::new(client)),
+        delay: options.latency,
+    });
+
+    let tokens: Vec<String> = (1..=options.clients)
+        .map(|client| format!("load-test-{}", client))
+        .collect();
+    let api_tokens = tokens
+        .iter()
+        .map(|token| ApiToken {
+            token: token.as_str().into(),
+            tier: PermissionTier::Admin,
+        })
+        .collect();
+
+    let limit = options.limit;
+    let mut server = McpServer::with_tools(vec![tool], ConcurrencyLimiter::new(|_| limit))
+        .with_authenticator(Authenticator::new(api_tokens));
+    if options.rate_limit > 0 {
+        server = server.with_rate_limit(RateLimit {
+            calls_per_minute: options.rate_limit,
+            burst: options.burst.unwrap_or(options.rate_limit).max(1),
+        });
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to bind a local port")?;
+    let addr = listener.local_addr()?;
+    let max_connections = (options.max_connections > 0).then_some(options.max_connections);
+    let listener = LimitedListener::new(listener, max_connections);
+    tokio::spawn(serve_listener(server, listener, std::future::pending()));
+
+    eprintln!(
+        "Mock server on http://{}/mcp: {}ms per call, {} at once, queue {}, {} connections, {}",
+        addr,
+        options.latency.as_millis(),
+        limit.max_concurrency,
+        limit.queue_depth,
+        max_connections.map_or("uncapped".to_string(), |max| max.to_string()),
+        match options.rate_limit {
+            0 => "no rate limit".to_string(),
+            rate => format!("{} calls/min per client", rate),
+        },
+    );
+    Ok((format!("http://{}/mcp", addr), tokens))
+}
+
+/// One MCP session over streamable HTTP
+struct Session {
+    http: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    id: Option<String>,
+}
+
+impl Session {
+    async fn open(http: reqwest::Client, url: String, token: Option<String>) -> Result<Self> {
+        let mut session = Session {
+            http,
+            url,
+            token,
+            id: None,
+        };
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "load-test", "version": env!("CARGO_PKG_VERSION") }
+            }
+        });
+        let response = session.post(&initialize).await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("initialize failed with HTTP {}", status);
+        }
+        session.id = response
+            .headers()
+            .get("mcp-session-id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string);
+        read_response(response, 0).await?;
+
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        let status = session.post(&initialized).await?.status();
+        if !status.is_success() {
+            anyhow::bail!("notifications/initialized failed with HTTP {}", status);
+        }
+        Ok(session)
+    }
+
+    async fn post(&self, body: &Value) -> Result<reqwest::Response> {
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(id) = &self.id {
+            request = request.header("Mcp-Session-Id", id);
+        }
+        request.send().await.context("Request failed")
+    }
+
+    /// Make one tool call and classify how it ended
+    async fn call(&self, id: u64, tool: &str, args: &Value) -> String {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": tool, "arguments": args }
+        });
+        let response = match self.post(&request).await {
+            Ok(response) => response,
+            Err(_) => return "transport".to_string(),
+        };
+        if !response.status().is_success() {
+            return format!("http_{}", response.status().as_u16());
+        }
+        match read_response(response, id).await {
+            Ok(message) => outcome(&message),
+            Err(_) => "transport".to_string(),
+        }
+    }
+}
+
+/// The JSON-RPC message answering request `id`, from a JSON or SSE body
+async fn read_response(response: reqwest::Response, id: u64) -> Result<Value> {
+    let body = response.text().await.context("Failed to read response")?;
+    let messages: Vec<Value> = match serde_json::from_str(&body) {
+        Ok(message) => vec![message],
+        Err(_) => body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .filter_map(|data| serde_json::from_str(data.trim()).ok())
+            .collect(),
+    };
+    messages
+        .into_iter()
+        .find(|message| message["id"] == json!(id))
+        .with_context(|| format!("No response to request {}", id))
+}
+
+fn outcome(message: &Value) -> String {
+    if let Some(error) = message.get("error") {
+        return match error["data"]["code"].as_str() {
+            Some(code) => code.to_string(),
+            None => format!("jsonrpc_{}", error["code"]),
+        };
+    }
+    if message["result"]["isError"] == json!(true) {
+        return "tool_error".to_string();
+    }
+    "ok".to_string()
+}
+
+fn percentile(sorted: &[Duration], quantile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    sorted[((sorted.len() - 1) as f64 * quantile).round() as usize]
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    let (url, tokens) = match &options.url {
+        Some(url) => (url.clone(), options.tokens.clone()),
+        None => spawn_mock_server(&options).await?,
+    };
+
+    // Close each connection after its call so every call arrives like a separate
+    // client; idle keep-alive connections would otherwise hold server slots
+    let http = reqwest::Client::builder()
+        .pool_max_idle_per_host(0)
+        .build()?;
+    let mut sessions = Vec::with_capacity(options.clients);
+    for client in 0..options.clients {
+        let token = (!tokens.is_empty()).then(|| tokens[client % tokens.len()].clone());
+        let session = Session::open(http.clone(), url.clone(), token)
+            .await
+            .with_context(|| format!("Client {} could not open a session", client + 1))?;
+        sessions.push(session);
+    }
+
+    eprintln!(
+        "Making {} {} calls across {} clients",
+        options.calls, options.tool, options.clients
+    );
+    let started = Instant::now();
+    let results: Vec<(String, Duration)> = stream::iter(0..options.calls)
+        .map(|call| {
+            let session = &sessions[call % sessions.len()];
+            let (tool, args) = (&options.tool, &options.args);
+            async move {
+                let sent = Instant::now();
+                let outcome = session.call(call as u64 + 1, tool, args).await;
+                (outcome, sent.elapsed())
+            }
+        })
+        .buffer_unordered(options.calls.max(1))
+        .collect()
+        .await;
+    let elapsed = started.elapsed();
+
+    let mut outcomes: BTreeMap<String, usize> = BTreeMap::new();
+    for (outcome, _) in &results {
+        *outcomes.entry(outcome.clone()).or_default() += 1;
+    }
+    let mut latencies: Vec<Duration> = results
+        .iter()
+        .filter(|(outcome, _)| outcome == "ok")
+        .map(|(_, latency)| *latency)
+        .collect();
+    latencies.sort();
+
+    println!("calls:      {}", results.len());
+    for (outcome, count) in &outcomes {
+        println!("{:<11} {}", format!("{}:", outcome), count);
+    }
+    println!(
+        "latency ok: p50 {:?}, p95 {:?}, p99 {:?}, max {:?}",
+        percentile(&latencies, 0.50),
+        percentile(&latencies, 0.95),
+        percentile(&latencies, 0.99),
+        latencies.last().copied().unwrap_or_default(),
+    );
+    println!(
+        "elapsed:    {:?} ({:.0} calls/s)",
+        elapsed,
+        results.len() as f64 / elapsed.as_secs_f64()
+    );
+
+    // Turning calls away under load is expected; anything else is a failure
+    let unexpected: usize = outcomes
+        .iter()
+        .filter(|(outcome, _)| !matches!(outcome.as_str(), "ok" | "rate_limited" | "saturated"))
+        .map(|(_, count)| count)
+        .sum();
+    if unexpected > 0 {
+        anyhow::bail!("{} calls ended unexpectedly", unexpected);
+    }
+    Ok(())
+}
@@ -1,28 +1,1358 @@
+use crate::ethereum::escalation::{DEFAULT_BUMP_PERCENT, DEFAULT_ESCALATION_AFTER_BLOCKS};
+use crate::ethereum::{
+    AwsCredentials, ChainIdCheck, GasEscalation, GasSponsorConfig, KmsConfig, OptionsVenueKind,
+    PerpVenueKind, WalletConnectConfig, DEFAULT_ENTRY_POINT, DEFAULT_ETHERSCAN_URL,
+    DEFAULT_FOUR_BYTE_URL, DEFAULT_RESERVOIR_URL, DEFAULT_SNAPSHOT_HUB_URL,
+    DEFAULT_WALLETCONNECT_REQUEST_TIMEOUT,
+};
+use crate::logging::LogFormat;
+use crate::mcp::chat::ChatBot;
+use crate::mcp::http::DEFAULT_HTTP_MAX_CONNECTIONS;
+use crate::mcp::messages::Locale;
+use crate::mcp::policy::Policy;
+use crate::mcp::rate_limit::RateLimit;
+use crate::mcp::response_limit::DEFAULT_MAX_RESPONSE_BYTES;
+use crate::mcp::second_factor::Totp;
+use crate::mcp::webhooks::DEFAULT_WEBHOOK_RETRIES;
+use crate::secrets::{register_secret, SecretString};
+use crate::supervisor::{RestartPolicy, DEFAULT_MAX_RESTART_BACKOFF, DEFAULT_RESTART_BACKOFF};
+use crate::telemetry::{OtlpConfig, DEFAULT_OTEL_SERVICE_NAME};
+use crate::tools::approval_strategy::{ApprovalDefaults, DEFAULT_APPROVAL_BUFFER_PERCENT};
+use crate::tools::batch_transfer::{
+    BatchLimits, DEFAULT_DISPERSE_CONTRACT, DEFAULT_MAX_BATCH_RECIPIENTS,
+};
+use crate::tools::dex_stats::{
+    DEFAULT_DEX_STATS_POOLS, DEFAULT_DEX_STATS_REFRESH, MAX_DEX_STATS_POOLS,
+};
+use crate::tools::event_bus::DEFAULT_EVENT_BUS_CAPACITY;
+use crate::tools::gas_reserve::{GasReserve, DEFAULT_GAS_RESERVE_ETH};
+use crate::tools::multichain::DEFAULT_CHAIN_QUERY_CONCURRENCY;
+use crate::tools::pool_share::{PoolShareLimit, DEFAULT_MAX_POOL_SHARE_PERCENT};
+use crate::tools::price_sanity::DEFAULT_MAX_PRICE_DEVIATION_PERCENT;
+use crate::tools::quotes::{DEFAULT_MAX_QUOTE_MOVE_BPS, DEFAULT_QUOTE_TTL};
+use crate::tools::recipients::DEFAULT_RECIPIENT_LOOKBACK_BLOCKS;
+use crate::tools::route_objective::RouteObjective;
+use crate::tools::scam_list::{ScamFeed, DEFAULT_SCAM_LIST_REFRESH, DEFAULT_SCAM_LIST_URL};
+use crate::tools::scheduler::DEFAULT_SCHEDULE_TICK;
+use crate::tools::slippage::{SlippageDefaults, DEFAULT_SLIPPAGE_PERCENT};
+use crate::tools::trade_ledger::AccountingMethod;
+use crate::tools::watch_list::{
+    DEFAULT_WATCH_FEED_SIZE, DEFAULT_WATCH_POLL, MAX_WATCHED_ADDRESSES,
+};
+use crate::tools::{PermissionTier, ToolCategory};
 use anyhow::{Context, Result};
+use ethers::types::Address;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
+const DEFAULT_HTTP_BIND: &str = "127.0.0.1:8080";
+
+// Variables of the `.env` file, consulted when the real environment does not set them.
+// Kept apart from the process environment, which cannot be changed safely once
+// other threads run.
+static ENV_FILE: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+// Shorter bearer tokens are guessable; an empty one would match a bare `Bearer ` header
+const MIN_AUTH_TOKEN_LEN: usize = 16;
+
+#[derive(Debug, Clone, Default)]
 pub struct Config {
     pub eth_rpc_url: String,
-    pub private_key: String,
+    /// Endpoint `simulate_bundle` runs on, such as a fork; defaults to ETH_RPC_URL
+    pub simulation_rpc_url: Option<String>,
+    /// WebSocket endpoint new blocks are followed on, so watchers run on every block
+    pub eth_ws_url: Option<SecretString>,
+    /// Signs transactions locally; optional when a WalletConnect wallet signs
+    pub private_key: Option<SecretString>,
+    /// Route transactions to a mobile wallet over WalletConnect instead of the local key
+    pub walletconnect: Option<WalletConnectConfig>,
+    /// Sign with a key held in AWS or Google Cloud KMS instead of the local key
+    pub kms: Option<KmsConfig>,
+    /// Forwarder or paymaster paying for the wallet's gas; `None` pays from the wallet
+    pub gas_sponsor: Option<GasSponsorConfig>,
     pub chain_id: u64,
+    /// How a mismatch between CHAIN_ID and the endpoint's eth_chainId is handled
+    pub chain_id_check: ChainIdCheck,
+    /// Per-category overrides; categories not listed use `ConcurrencyLimit::default_for`
+    pub tool_limits: HashMap<ToolCategory, ConcurrencyLimit>,
+    /// Cap on a serialized tool result in bytes; `None` disables the cap
+    pub max_response_bytes: Option<usize>,
+    /// Allow tools to sign and send transactions; when false they only simulate
+    pub execution_enabled: bool,
+    pub transport: Transport,
+    /// Bearer tokens accepted over HTTP; empty leaves HTTP unauthenticated
+    pub auth_tokens: Vec<ApiToken>,
+    /// Open HTTP connections allowed at once; `None` leaves them uncapped
+    pub http_max_connections: Option<usize>,
+    /// File the HTTP service writes its process id to while running
+    pub pid_file: Option<PathBuf>,
+    /// Tool calls allowed per HTTP client; `None` leaves clients unlimited
+    pub rate_limit: Option<RateLimit>,
+    /// Key that signs tool results; `None` leaves results unsigned
+    pub attestation_key: Option<SecretString>,
+    /// Base32 TOTP secret; when set, calls that send transactions need a current code
+    pub totp_secret: Option<SecretString>,
+    /// Rules every tool call is checked against; `None` allows every call
+    pub policy: Option<Policy>,
+    /// How long a swap_tokens quote can be passed to execute_swap
+    pub quote_ttl: Duration,
+    /// Largest output move since a quote that execute_swap re-quotes through
+    pub max_quote_move_bps: u32,
+    /// Slippage tolerance swap_tokens applies when a call passes none
+    pub slippage: SlippageDefaults,
+    /// Largest share of a pool execute_swap sells through; `None` leaves swaps unbounded
+    pub pool_share_limit: Option<PoolShareLimit>,
+    /// What swap_tokens ranks routes by when a call doesn't choose
+    pub route_objective: RouteObjective,
+    /// How much swaps approve routers for by default
+    pub approvals: ApprovalDefaults,
+    /// Default fee escalation for sent transactions; `None` waits without bumping
+    pub gas_escalation: Option<GasEscalation>,
+    /// Extra ERC-4626 vaults compared by compare_yields, as (name, address)
+    pub yield_vaults: Vec<(String, Address)>,
+    /// Base tokens monitor_new_pairs watches; empty uses its defaults
+    pub monitor_base_tokens: Vec<Address>,
+    /// Tokens get_token_price routes through, after WETH, when there is no direct pool
+    pub price_intermediaries: Vec<Address>,
+    /// Percent a DEX price may stray from its Chainlink feed before it is flagged;
+    /// `None` skips the cross-check
+    pub max_price_deviation: Option<rust_decimal::Decimal>,
+    /// Address book entries as (name, address)
+    pub contacts: Vec<(String, Address)>,
+    /// File contacts added at runtime are saved to; `None` keeps them in memory
+    pub address_book_path: Option<PathBuf>,
+    /// Ask the user before live transfers to addresses outside the address book
+    pub confirm_unknown_recipients: bool,
+    /// Blocks of transfer logs searched for earlier payments to a recipient
+    pub recipient_history_blocks: u64,
+    /// Refuse live transfers to first-time recipients unless the call confirms them
+    pub confirm_new_recipients: bool,
+    /// ETH live transactions must leave in the wallet for future gas
+    pub gas_reserve: GasReserve,
+    /// Language of tool descriptions and error messages
+    pub locale: Locale,
+    /// Whether stderr logs are text or JSON lines
+    pub log_format: LogFormat,
+    /// OTLP collector tool and RPC spans are exported to; `None` exports nothing
+    pub otlp: Option<OtlpConfig>,
+    /// Alternate names for tools, as `(alias, tool)` pairs
+    pub tool_aliases: Vec<(String, String)>,
+    /// Whether list_tools also lists each alias as a tool
+    pub list_tool_aliases: bool,
+    /// RPC endpoints of additional chains read by the cross-chain tools, as `(chain_id, url)`
+    pub chain_rpc_urls: Vec<(u64, String)>,
+    /// Chains the cross-chain tools query at once
+    pub chain_query_concurrency: usize,
+    /// Disperse-compatible contract batch_transfer pays through
+    pub disperse_contract: Address,
+    /// Bounds on batch_transfer payments
+    pub batch_limits: BatchLimits,
+    /// File scheduled payments are saved to; `None` keeps them in memory
+    pub schedules_path: Option<PathBuf>,
+    /// JSON lines file every scheduled payment execution is appended to
+    pub schedule_audit_log: Option<PathBuf>,
+    /// How often the scheduler checks for due payments
+    pub schedule_tick: Duration,
+    /// File executed swaps are recorded to for tax reports; `None` keeps them in memory
+    pub trade_ledger_path: Option<PathBuf>,
+    /// How generate_tax_report matches disposals to lots when a call doesn't choose
+    pub tax_accounting_method: AccountingMethod,
+    /// Extra governors the governance tools accept by name, as (name, address)
+    pub governors: Vec<(String, Address)>,
+    /// Snapshot hub serving off-chain proposals and voting power
+    pub snapshot_hub_url: String,
+    /// 4byte directory that names function selectors found in bytecode
+    pub four_byte_url: String,
+    /// Reservoir API serving NFT collection floor prices
+    pub reservoir_url: String,
+    pub reservoir_api_key: Option<SecretString>,
+    /// Etherscan-compatible API serving the wallet's transaction history
+    pub etherscan_url: String,
+    pub etherscan_api_key: Option<SecretString>,
+    /// Beacon node API validators are looked up on; `None` leaves out get_validator_info
+    pub beacon_api_url: Option<SecretString>,
+    /// Perpetuals exchange the perp tools and get_portfolio read from
+    pub perp_venue: PerpVenueKind,
+    pub perp_api_url: String,
+    /// Perpetuals exchanges get_funding_rates compares
+    pub funding_rate_venues: Vec<PerpVenueKind>,
+    /// Options exchange get_option_chains reads from
+    pub options_venue: OptionsVenueKind,
+    pub options_api_url: String,
+    /// Scam address lists fetched periodically; empty disables the scam list
+    pub scam_list_feeds: Vec<ScamFeed>,
+    /// How often the scam lists are fetched again
+    pub scam_list_refresh: Duration,
+    /// Whether calls may pass `override_scam_list` to pay listed addresses
+    pub allow_scam_list_override: bool,
+    /// Addresses watched from startup, with their labels
+    pub watch_addresses: Vec<(String, Address)>,
+    /// How often watched addresses are checked for activity
+    pub watch_poll: Duration,
+    /// Events kept in the watch feed
+    pub watch_feed_size: usize,
+    /// Pools get_dex_stats reports on, with their names; empty disables it
+    pub dex_stats_pools: Vec<(String, Address)>,
+    /// How often the pools' volume and TVL are read again
+    pub dex_stats_refresh: Duration,
+    /// Events the event bus holds for subscribers that fall behind
+    pub event_bus_capacity: usize,
+    /// When background jobs that crash are restarted
+    pub task_restart: RestartPolicy,
+    /// Endpoints alerts, transactions and policy violations are POSTed to; empty
+    /// disables webhooks
+    pub webhook_urls: Vec<SecretString>,
+    /// Alert topics delivered to webhooks; empty delivers every topic
+    pub webhook_topics: Vec<String>,
+    /// Key webhook payloads are signed with
+    pub webhook_secret: Option<SecretString>,
+    /// Retries of a failed webhook delivery
+    pub webhook_max_retries: u32,
+    /// Telegram bot alerts are posted through, and the chat it posts to
+    pub telegram: Option<ChatBot>,
+    /// Discord bot alerts are posted through, and the channel it posts to
+    pub discord: Option<ChatBot>,
+    /// Alert topics posted to chat; empty posts every topic
+    pub chat_topics: Vec<String>,
+}
+
+/// How the server talks to MCP clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transport {
+    #[default]
+    Stdio,
+    /// Streamable HTTP at `/mcp`
+    Http { bind: SocketAddr },
+}
+
+/// A client credential and the tier it grants
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiToken {
+    pub token: SecretString,
+    pub tier: PermissionTier,
+}
+
+/// Concurrency budget for one tool category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyLimit {
+    /// Calls allowed to run at the same time
+    pub max_concurrency: usize,
+    /// Calls allowed to wait for a slot before new calls are rejected
+    pub queue_depth: usize,
+}
+
+impl ConcurrencyLimit {
+    pub fn default_for(category: ToolCategory) -> Self {
+        let (max_concurrency, queue_depth) = match category {
+            ToolCategory::Read => (16, 64),
+            ToolCategory::Pricing => (8, 32),
+            ToolCategory::Simulation => (4, 16),
+            ToolCategory::Heavy => (2, 8),
+        };
+        Self {
+            max_concurrency,
+            queue_depth,
+        }
+    }
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
-        let eth_rpc_url = env::var("ETH_RPC_URL").context("ETH_RPC_URL not set in environment")?;
+        let eth_rpc_url = secret_var("ETH_RPC_URL")?
+            .context("ETH_RPC_URL not set in environment (or ETH_RPC_URL_FILE)")?;
+        // Provider keys often sit in the URL's path or query
+        register_secret(&SecretString::new(eth_rpc_url.as_str()));
+        let eth_ws_url = match secret_var("ETH_WS_URL")? {
+            Some(url) if !url.trim().is_empty() => {
+                let url = url.trim();
+                if !(url.starts_with("ws://") || url.starts_with("wss://")) {
+                    anyhow::bail!("ETH_WS_URL must be a ws:// or wss:// URL");
+                }
+                let url = SecretString::new(url);
+                register_secret(&url);
+                Some(url)
+            }
+            _ => None,
+        };
 
-        let private_key = env::var("PRIVATE_KEY").context("PRIVATE_KEY not set in environment")?;
+        let (walletconnect, kms) = match env_var("SIGNER") {
+            Ok(value) => match value.trim() {
+                "local" => (None, None),
+                "walletconnect" => (Some(parse_walletconnect()?), None),
+                "aws_kms" => (None, Some(parse_aws_kms()?)),
+                "gcp_kms" => (None, Some(parse_gcp_kms()?)),
+                other => anyhow::bail!(
+                    "Invalid SIGNER: {} (expected local, walletconnect, aws_kms or gcp_kms)",
+                    other
+                ),
+            },
+            Err(_) => (None, None),
+        };
+        let private_key = match secret_var("PRIVATE_KEY")? {
+            Some(value) => {
+                let key = SecretString::new(value);
+                register_secret(&key);
+                Some(key)
+            }
+            None if walletconnect.is_some() || kms.is_some() => None,
+            None => anyhow::bail!("PRIVATE_KEY not set in environment (or PRIVATE_KEY_FILE)"),
+        };
 
-        let chain_id = env::var("CHAIN_ID")
+        let gas_sponsor = match env_var("GAS_SPONSOR") {
+            Ok(value) => match value.trim() {
+                "" | "none" => None,
+                "forwarder" | "paymaster" if walletconnect.is_some() || kms.is_some() => anyhow::bail!(
+                    "GAS_SPONSOR needs the local signer: sponsored calls are signed with PRIVATE_KEY"
+                ),
+                "forwarder" => Some(parse_forwarder()?),
+                "paymaster" => Some(parse_paymaster()?),
+                other => anyhow::bail!(
+                    "Invalid GAS_SPONSOR: {} (expected none, forwarder or paymaster)",
+                    other
+                ),
+            },
+            Err(_) => None,
+        };
+
+        let chain_id = env_var("CHAIN_ID")
             .unwrap_or_else(|_| "1".to_string())
             .parse()
             .context("Invalid CHAIN_ID")?;
 
+        let mut tool_limits = HashMap::new();
+        for category in ToolCategory::ALL {
+            let default = ConcurrencyLimit::default_for(category);
+            let suffix = category.as_str().to_uppercase();
+            let limit = ConcurrencyLimit {
+                max_concurrency: parse_env_or(
+                    &format!("TOOL_CONCURRENCY_{}", suffix),
+                    default.max_concurrency,
+                )?,
+                queue_depth: parse_env_or(
+                    &format!("TOOL_QUEUE_DEPTH_{}", suffix),
+                    default.queue_depth,
+                )?,
+            };
+            if limit.max_concurrency == 0 {
+                anyhow::bail!("TOOL_CONCURRENCY_{} must be at least 1", suffix);
+            }
+            tool_limits.insert(category, limit);
+        }
+
+        // 0 disables the cap
+        let max_response_bytes = parse_env_or("MAX_RESPONSE_BYTES", DEFAULT_MAX_RESPONSE_BYTES)?;
+        let max_response_bytes = (max_response_bytes > 0).then_some(max_response_bytes);
+
+        let execution_enabled = parse_env_or("ENABLE_EXECUTION", false)?;
+
+        let transport = match env_var("MCP_TRANSPORT")
+            .unwrap_or_else(|_| "stdio".to_string())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "stdio" => Transport::Stdio,
+            "http" => Transport::Http {
+                bind: parse_env_or(
+                    "MCP_HTTP_BIND",
+                    DEFAULT_HTTP_BIND
+                        .parse()
+                        .context("Invalid default HTTP bind address")?,
+                )?,
+            },
+            other => anyhow::bail!("Invalid MCP_TRANSPORT: {} (expected stdio or http)", other),
+        };
+
+        // 0 disables the cap
+        let http_max_connections =
+            parse_env_or("MCP_HTTP_MAX_CONNECTIONS", DEFAULT_HTTP_MAX_CONNECTIONS)?;
+        let http_max_connections = (http_max_connections > 0).then_some(http_max_connections);
+        let pid_file = env_var("PID_FILE")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+
+        // Off unless a rate is set; the burst defaults to a minute's worth of calls
+        let calls_per_minute: u32 = parse_env_or("MCP_RATE_LIMIT_PER_MINUTE", 0)?;
+        let rate_limit = if calls_per_minute > 0 {
+            let burst = parse_env_or("MCP_RATE_LIMIT_BURST", calls_per_minute)?;
+            if burst == 0 {
+                anyhow::bail!("MCP_RATE_LIMIT_BURST must be at least 1");
+            }
+            Some(RateLimit {
+                calls_per_minute,
+                burst,
+            })
+        } else {
+            None
+        };
+
+        let auth_tokens = match secret_var("MCP_AUTH_TOKENS")? {
+            Some(value) => parse_auth_tokens(&value)?,
+            None => Vec::new(),
+        };
+        // Without tokens every HTTP client is admin, which is only safe on this machine
+        if let Transport::Http { bind } = &transport {
+            if auth_tokens.is_empty() && !bind.ip().is_loopback() {
+                anyhow::bail!(
+                    "MCP_AUTH_TOKENS must be set to serve HTTP on {}; without it every client gets the admin tier",
+                    bind
+                );
+            }
+        }
+
+        // A dedicated key keeps the trading key out of the attestation path
+        let attestation_key = match secret_var("ATTESTATION_KEY")? {
+            Some(value) => {
+                let key = SecretString::new(value);
+                register_secret(&key);
+                Some(key)
+            }
+            None if parse_env_or("ATTEST_RESPONSES", false)? => Some(
+                private_key
+                    .clone()
+                    .context("ATTEST_RESPONSES needs PRIVATE_KEY or ATTESTATION_KEY")?,
+            ),
+            None => None,
+        };
+
+        let totp_secret = match secret_var("TOTP_SECRET")? {
+            Some(value) => {
+                let secret = SecretString::new(value);
+                register_secret(&secret);
+                Totp::new(secret.expose_secret()).context("Invalid TOTP_SECRET")?;
+                Some(secret)
+            }
+            None => None,
+        };
+
+        let policy = match env_var("POLICY_FILE") {
+            Ok(path) if !path.trim().is_empty() => Some(Policy::load(Path::new(path.trim()))?),
+            _ => None,
+        };
+
+        let quote_ttl =
+            Duration::from_secs(parse_env_or("QUOTE_TTL_SECS", DEFAULT_QUOTE_TTL.as_secs())?);
+        let max_quote_move_bps = parse_env_or("QUOTE_MAX_MOVE_BPS", DEFAULT_MAX_QUOTE_MOVE_BPS)?;
+
+        // 0 disables the limit
+        let max_pool_share: rust_decimal::Decimal =
+            parse_env_or("MAX_POOL_SHARE_PERCENT", DEFAULT_MAX_POOL_SHARE_PERCENT)?;
+        if max_pool_share.is_sign_negative() || max_pool_share > rust_decimal::Decimal::ONE_HUNDRED
+        {
+            anyhow::bail!("MAX_POOL_SHARE_PERCENT must be between 0 and 100");
+        }
+        let pool_share_limit = if max_pool_share.is_zero() {
+            None
+        } else {
+            Some(PoolShareLimit {
+                max_percent: max_pool_share,
+                action: parse_env_or("POOL_SHARE_ACTION", Default::default())?,
+            })
+        };
+
+        let route_objective = parse_env_or("ROUTE_OBJECTIVE", RouteObjective::default())?;
+
+        let approval_buffer: rust_decimal::Decimal = parse_env_or(
+            "APPROVAL_BUFFER_PERCENT",
+            DEFAULT_APPROVAL_BUFFER_PERCENT.into(),
+        )?;
+        if approval_buffer.is_sign_negative() {
+            anyhow::bail!("APPROVAL_BUFFER_PERCENT must not be negative");
+        }
+        let approvals = ApprovalDefaults {
+            strategy: parse_env_or("APPROVAL_STRATEGY", Default::default())?,
+            buffer_percent: approval_buffer,
+        };
+
+        let slippage = SlippageDefaults {
+            default_percent: slippage_percent(
+                "SLIPPAGE_DEFAULT_PERCENT",
+                parse_env_or("SLIPPAGE_DEFAULT_PERCENT", DEFAULT_SLIPPAGE_PERCENT)?,
+            )?,
+            pairs: match env_var("SLIPPAGE_PAIRS") {
+                Ok(value) => parse_slippage_pairs(&value)?,
+                Err(_) => Vec::new(),
+            },
+            tokens: match env_var("SLIPPAGE_TOKENS") {
+                Ok(value) => parse_slippage_tokens(&value)?,
+                Err(_) => Vec::new(),
+            },
+        };
+
+        // Setting a ceiling turns escalation on
+        let gas_escalation = match env_var("GAS_ESCALATION_MAX_FEE_GWEI") {
+            Ok(value) => {
+                let gwei: rust_decimal::Decimal = value.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid GAS_ESCALATION_MAX_FEE_GWEI: {}", value)
+                })?;
+                let policy = GasEscalation {
+                    after_blocks: parse_env_or(
+                        "GAS_ESCALATION_AFTER_BLOCKS",
+                        DEFAULT_ESCALATION_AFTER_BLOCKS,
+                    )?,
+                    bump_percent: parse_env_or(
+                        "GAS_ESCALATION_BUMP_PERCENT",
+                        DEFAULT_BUMP_PERCENT,
+                    )?,
+                    max_fee_per_gas: crate::tools::units::to_base_units(gwei, 9)
+                        .context("Invalid GAS_ESCALATION_MAX_FEE_GWEI")?,
+                };
+                policy.validate()?;
+                Some(policy)
+            }
+            Err(_) => None,
+        };
+
+        let chain_id_check = parse_env_or("CHAIN_ID_CHECK", ChainIdCheck::default())?;
+
+        let yield_vaults = match env_var("YIELD_VAULTS") {
+            Ok(value) => parse_named_addresses("YIELD_VAULTS", &value)?,
+            Err(_) => Vec::new(),
+        };
+
+        let monitor_base_tokens = match env_var("MONITOR_BASE_TOKENS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(|token| {
+                    token
+                        .parse()
+                        .with_context(|| format!("Invalid MONITOR_BASE_TOKENS address: {}", token))
+                })
+                .collect::<Result<_>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        let price_intermediaries = match env_var("PRICE_INTERMEDIARIES") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(|token| {
+                    token
+                        .parse()
+                        .with_context(|| format!("Invalid PRICE_INTERMEDIARIES address: {}", token))
+                })
+                .collect::<Result<_>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        // 0 disables the cross-check
+        let max_price_deviation: rust_decimal::Decimal = parse_env_or(
+            "MAX_PRICE_DEVIATION_PERCENT",
+            DEFAULT_MAX_PRICE_DEVIATION_PERCENT,
+        )?;
+        if max_price_deviation.is_sign_negative() {
+            anyhow::bail!("MAX_PRICE_DEVIATION_PERCENT must not be negative");
+        }
+        let max_price_deviation = (!max_price_deviation.is_zero()).then_some(max_price_deviation);
+
+        let contacts = match env_var("CONTACTS") {
+            Ok(value) => parse_named_addresses("CONTACTS", &value)?,
+            Err(_) => Vec::new(),
+        };
+        let address_book_path = env_var("ADDRESS_BOOK_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let confirm_unknown_recipients = parse_env_or("CONFIRM_UNKNOWN_RECIPIENTS", false)?;
+        let recipient_history_blocks = parse_env_or(
+            "RECIPIENT_HISTORY_BLOCKS",
+            DEFAULT_RECIPIENT_LOOKBACK_BLOCKS,
+        )?;
+        let confirm_new_recipients = parse_env_or("CONFIRM_NEW_RECIPIENTS", false)?;
+
+        let gas_reserve = GasReserve {
+            min_balance: crate::tools::units::to_base_units(
+                parse_env_or("GAS_RESERVE_ETH", DEFAULT_GAS_RESERVE_ETH)?,
+                18,
+            )
+            .context("Invalid GAS_RESERVE_ETH")?,
+            allow_override: parse_env_or("ALLOW_GAS_RESERVE_OVERRIDE", false)?,
+        };
+
+        let locale = parse_env_or("LOCALE", Locale::default())?;
+        let log_format = parse_env_or("LOG_FORMAT", LogFormat::default())?;
+
+        // Named as in the OpenTelemetry SDKs, so existing deployment settings carry over
+        let otlp = match env_var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Ok(endpoint) if !endpoint.trim().is_empty() => Some(OtlpConfig {
+                endpoint: endpoint.trim().to_string(),
+                service_name: env_var("OTEL_SERVICE_NAME")
+                    .unwrap_or_else(|_| DEFAULT_OTEL_SERVICE_NAME.to_string()),
+                headers: match secret_var("OTEL_EXPORTER_OTLP_HEADERS")? {
+                    Some(value) => parse_otlp_headers(&value)?,
+                    None => Vec::new(),
+                },
+            }),
+            _ => None,
+        };
+
+        let tool_aliases = match env_var("TOOL_ALIASES") {
+            Ok(value) => parse_tool_aliases(&value)?,
+            Err(_) => Vec::new(),
+        };
+        let list_tool_aliases = parse_env_or("LIST_TOOL_ALIASES", false)?;
+
+        let chain_rpc_urls = match secret_var("CHAIN_RPC_URLS")? {
+            Some(value) => parse_chain_rpc_urls(&value, chain_id)?,
+            None => Vec::new(),
+        };
+        let chain_query_concurrency =
+            parse_env_or("CHAIN_QUERY_CONCURRENCY", DEFAULT_CHAIN_QUERY_CONCURRENCY)?;
+        if chain_query_concurrency == 0 {
+            anyhow::bail!("CHAIN_QUERY_CONCURRENCY must be at least 1");
+        }
+
+        let disperse_contract = parse_env_or(
+            "DISPERSE_CONTRACT",
+            DEFAULT_DISPERSE_CONTRACT
+                .parse()
+                .context("Invalid default Disperse contract address")?,
+        )?;
+        let batch_limits = BatchLimits {
+            max_recipients: parse_env_or("BATCH_MAX_RECIPIENTS", DEFAULT_MAX_BATCH_RECIPIENTS)?,
+            max_eth_per_recipient: match env_var("BATCH_MAX_ETH_PER_RECIPIENT") {
+                Ok(value) => Some(value.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid BATCH_MAX_ETH_PER_RECIPIENT: {}", value)
+                })?),
+                Err(_) => None,
+            },
+        };
+        if batch_limits.max_recipients == 0 {
+            anyhow::bail!("BATCH_MAX_RECIPIENTS must be at least 1");
+        }
+
+        let schedules_path = env_var("SCHEDULES_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let schedule_audit_log = env_var("SCHEDULE_AUDIT_LOG")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let schedule_tick = Duration::from_secs(parse_env_or(
+            "SCHEDULE_TICK_SECS",
+            DEFAULT_SCHEDULE_TICK.as_secs(),
+        )?);
+        if schedule_tick.is_zero() {
+            anyhow::bail!("SCHEDULE_TICK_SECS must be at least 1");
+        }
+        let trade_ledger_path = env_var("TRADE_LEDGER_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let tax_accounting_method =
+            parse_env_or("TAX_ACCOUNTING_METHOD", AccountingMethod::default())?;
+
+        let governors = match env_var("GOVERNORS") {
+            Ok(value) => parse_named_addresses("GOVERNORS", &value)?,
+            Err(_) => Vec::new(),
+        };
+        let snapshot_hub_url = env_var("SNAPSHOT_HUB_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_SNAPSHOT_HUB_URL.to_string());
+        // Fork endpoints can carry an API key in the URL
+        let simulation_rpc_url = secret_var("SIMULATION_RPC_URL")?
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(url) = &simulation_rpc_url {
+            register_secret(&SecretString::new(url.as_str()));
+        }
+        let four_byte_url = env_var("FOUR_BYTE_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_FOUR_BYTE_URL.to_string());
+        let reservoir_url = env_var("RESERVOIR_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_RESERVOIR_URL.to_string());
+        let reservoir_api_key = secret_var("RESERVOIR_API_KEY")?
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| {
+                let key = SecretString::new(key.trim());
+                register_secret(&key);
+                key
+            });
+        let etherscan_url = env_var("ETHERSCAN_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_ETHERSCAN_URL.to_string());
+        let etherscan_api_key = secret_var("ETHERSCAN_API_KEY")?
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| {
+                let key = SecretString::new(key.trim());
+                register_secret(&key);
+                key
+            });
+        let beacon_api_url = match secret_var("BEACON_API_URL")? {
+            Some(url) if !url.trim().is_empty() => {
+                let url = url.trim();
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    anyhow::bail!("BEACON_API_URL must be an http:// or https:// URL");
+                }
+                let url = SecretString::new(url);
+                register_secret(&url);
+                Some(url)
+            }
+            _ => None,
+        };
+        let perp_venue = parse_env_or("PERP_VENUE", PerpVenueKind::default())?;
+        let perp_api_url = env_var("PERP_API_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| perp_venue.default_url().to_string());
+        let funding_rate_venues = match env_var("FUNDING_RATE_VENUES") {
+            Ok(value) => {
+                let mut venues = Vec::new();
+                for venue in value.split(',').filter(|venue| !venue.trim().is_empty()) {
+                    let venue: PerpVenueKind = venue.parse()?;
+                    if !venues.contains(&venue) {
+                        venues.push(venue);
+                    }
+                }
+                venues
+            }
+            Err(_) => vec![PerpVenueKind::Hyperliquid, PerpVenueKind::Gmx],
+        };
+        let options_venue = parse_env_or("OPTIONS_VENUE", OptionsVenueKind::default())?;
+        let options_api_url = env_var("OPTIONS_API_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| options_venue.default_url().to_string());
+
+        let scam_list_feeds = match env_var("SCAM_LIST_URLS") {
+            Ok(value) => parse_scam_feeds(&value)?,
+            Err(_) => vec![ScamFeed {
+                name: "scamsniffer".to_string(),
+                url: DEFAULT_SCAM_LIST_URL.to_string(),
+            }],
+        };
+        let scam_list_refresh = Duration::from_secs(parse_env_or(
+            "SCAM_LIST_REFRESH_SECS",
+            DEFAULT_SCAM_LIST_REFRESH.as_secs(),
+        )?);
+        if scam_list_refresh.is_zero() {
+            anyhow::bail!("SCAM_LIST_REFRESH_SECS must be at least 1");
+        }
+        let allow_scam_list_override = parse_env_or("ALLOW_SCAM_LIST_OVERRIDE", false)?;
+
+        let watch_addresses = match env_var("WATCH_ADDRESSES") {
+            Ok(value) => parse_named_addresses("WATCH_ADDRESSES", &value)?,
+            Err(_) => Vec::new(),
+        };
+        if watch_addresses.len() > MAX_WATCHED_ADDRESSES {
+            anyhow::bail!(
+                "WATCH_ADDRESSES lists {} addresses; at most {} can be watched",
+                watch_addresses.len(),
+                MAX_WATCHED_ADDRESSES
+            );
+        }
+        let watch_poll = Duration::from_secs(parse_env_or(
+            "WATCH_POLL_SECS",
+            DEFAULT_WATCH_POLL.as_secs(),
+        )?);
+        if watch_poll.is_zero() {
+            anyhow::bail!("WATCH_POLL_SECS must be at least 1");
+        }
+        let watch_feed_size = parse_env_or("WATCH_FEED_SIZE", DEFAULT_WATCH_FEED_SIZE)?;
+        if watch_feed_size == 0 {
+            anyhow::bail!("WATCH_FEED_SIZE must be at least 1");
+        }
+
+        // Only mainnet has default pools
+        let dex_stats_pools = match env_var("DEX_STATS_POOLS") {
+            Ok(value) => parse_named_addresses("DEX_STATS_POOLS", &value)?,
+            Err(_) if chain_id == 1 => DEFAULT_DEX_STATS_POOLS
+                .iter()
+                .map(|(name, address)| {
+                    Ok((
+                        name.to_string(),
+                        address.parse().context("Invalid default DEX stats pool")?,
+                    ))
+                })
+                .collect::<Result<_>>()?,
+            Err(_) => Vec::new(),
+        };
+        if dex_stats_pools.len() > MAX_DEX_STATS_POOLS {
+            anyhow::bail!(
+                "DEX_STATS_POOLS lists {} pools; at most {} can be tracked",
+                dex_stats_pools.len(),
+                MAX_DEX_STATS_POOLS
+            );
+        }
+        let dex_stats_refresh = Duration::from_secs(parse_env_or(
+            "DEX_STATS_REFRESH_SECS",
+            DEFAULT_DEX_STATS_REFRESH.as_secs(),
+        )?);
+        if dex_stats_refresh.is_zero() {
+            anyhow::bail!("DEX_STATS_REFRESH_SECS must be at least 1");
+        }
+        let event_bus_capacity = parse_env_or("EVENT_BUS_CAPACITY", DEFAULT_EVENT_BUS_CAPACITY)?;
+        if event_bus_capacity == 0 {
+            anyhow::bail!("EVENT_BUS_CAPACITY must be at least 1");
+        }
+
+        let task_restart = RestartPolicy {
+            backoff: DEFAULT_RESTART_BACKOFF,
+            max_backoff: Duration::from_secs(parse_env_or(
+                "TASK_RESTART_MAX_BACKOFF_SECS",
+                DEFAULT_MAX_RESTART_BACKOFF.as_secs(),
+            )?)
+            .max(DEFAULT_RESTART_BACKOFF),
+            // Unset restarts forever
+            max_restarts: env_var("TASK_MAX_RESTARTS")
+                .ok()
+                .map(|value| value.trim().parse())
+                .transpose()
+                .context("Invalid TASK_MAX_RESTARTS")?,
+        };
+
+        // Chat webhook URLs carry their credentials in the path
+        let webhook_urls = match secret_var("WEBHOOK_URLS")? {
+            Some(value) => parse_webhook_urls(&value)?,
+            None => Vec::new(),
+        };
+        let webhook_topics = env_var("WEBHOOK_TOPICS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|topic| topic.trim().to_string())
+                    .filter(|topic| !topic.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let webhook_secret = secret_var("WEBHOOK_SECRET")?
+            .filter(|secret| !secret.trim().is_empty())
+            .map(|secret| {
+                let secret = SecretString::new(secret.trim());
+                register_secret(&secret);
+                secret
+            });
+        let webhook_max_retries = parse_env_or("WEBHOOK_MAX_RETRIES", DEFAULT_WEBHOOK_RETRIES)?;
+
+        let telegram = parse_chat_bot("TELEGRAM_BOT_TOKEN", "TELEGRAM_CHAT_ID")?;
+        let discord = parse_chat_bot("DISCORD_BOT_TOKEN", "DISCORD_CHANNEL_ID")?;
+        let chat_topics = env_var("CHAT_TOPICS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|topic| topic.trim().to_string())
+                    .filter(|topic| !topic.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             eth_rpc_url,
+            simulation_rpc_url,
+            eth_ws_url,
             private_key,
+            walletconnect,
+            kms,
+            gas_sponsor,
             chain_id,
+            chain_id_check,
+            tool_limits,
+            max_response_bytes,
+            execution_enabled,
+            transport,
+            auth_tokens,
+            http_max_connections,
+            pid_file,
+            rate_limit,
+            attestation_key,
+            totp_secret,
+            policy,
+            quote_ttl,
+            max_quote_move_bps,
+            slippage,
+            pool_share_limit,
+            route_objective,
+            approvals,
+            gas_escalation,
+            yield_vaults,
+            monitor_base_tokens,
+            price_intermediaries,
+            max_price_deviation,
+            contacts,
+            address_book_path,
+            confirm_unknown_recipients,
+            recipient_history_blocks,
+            confirm_new_recipients,
+            gas_reserve,
+            locale,
+            log_format,
+            otlp,
+            tool_aliases,
+            list_tool_aliases,
+            chain_rpc_urls,
+            chain_query_concurrency,
+            disperse_contract,
+            batch_limits,
+            schedules_path,
+            schedule_audit_log,
+            schedule_tick,
+            trade_ledger_path,
+            tax_accounting_method,
+            governors,
+            snapshot_hub_url,
+            four_byte_url,
+            reservoir_url,
+            reservoir_api_key,
+            etherscan_url,
+            etherscan_api_key,
+            beacon_api_url,
+            perp_venue,
+            perp_api_url,
+            funding_rate_venues,
+            options_venue,
+            options_api_url,
+            scam_list_feeds,
+            scam_list_refresh,
+            allow_scam_list_override,
+            watch_addresses,
+            watch_poll,
+            watch_feed_size,
+            dex_stats_pools,
+            dex_stats_refresh,
+            event_bus_capacity,
+            task_restart,
+            webhook_urls,
+            webhook_topics,
+            webhook_secret,
+            webhook_max_retries,
+            telegram,
+            discord,
+            chat_topics,
         })
     }
+
+    /// Concurrency budget for a tool category
+    pub fn tool_limit(&self, category: ToolCategory) -> ConcurrencyLimit {
+        self.tool_limits
+            .get(&category)
+            .copied()
+            .unwrap_or_else(|| ConcurrencyLimit::default_for(category))
+    }
+}
+
+/// Comma-separated http(s) URLs
+fn parse_webhook_urls(value: &str) -> Result<Vec<SecretString>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| {
+            let parsed = reqwest::Url::parse(url)
+                .ok()
+                .filter(|parsed| matches!(parsed.scheme(), "http" | "https"));
+            if parsed.is_none() {
+                anyhow::bail!("WEBHOOK_URLS entries must be http(s) URLs");
+            }
+            let url = SecretString::new(url);
+            register_secret(&url);
+            Ok(url)
+        })
+        .collect()
+}
+
+/// A bot token from `token_key` and the chat it posts to from `chat_key`, both or
+/// neither set
+fn parse_chat_bot(token_key: &str, chat_key: &str) -> Result<Option<ChatBot>> {
+    let token = secret_var(token_key)?.filter(|token| !token.trim().is_empty());
+    let chat_id = env_var(chat_key)
+        .ok()
+        .map(|chat_id| chat_id.trim().to_string())
+        .filter(|chat_id| !chat_id.is_empty());
+    match (token, chat_id) {
+        (Some(token), Some(chat_id)) => {
+            let token = SecretString::new(token.trim());
+            register_secret(&token);
+            Ok(Some(ChatBot { token, chat_id }))
+        }
+        (None, None) => Ok(None),
+        _ => anyhow::bail!("{} and {} must be set together", token_key, chat_key),
+    }
+}
+
+/// WalletConnect settings, all required once SIGNER=walletconnect
+fn parse_walletconnect() -> Result<WalletConnectConfig> {
+    let required = |key: &str| -> Result<String> {
+        secret_var(key)?
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .with_context(|| format!("{} is required with SIGNER=walletconnect", key))
+    };
+    let request_timeout = Duration::from_secs(parse_env_or(
+        "WALLETCONNECT_REQUEST_TIMEOUT_SECS",
+        DEFAULT_WALLETCONNECT_REQUEST_TIMEOUT.as_secs(),
+    )?);
+    if request_timeout.is_zero() {
+        anyhow::bail!("WALLETCONNECT_REQUEST_TIMEOUT_SECS must be at least 1");
+    }
+    Ok(WalletConnectConfig {
+        bridge_url: required("WALLETCONNECT_BRIDGE_URL")?,
+        project_id: required("WALLETCONNECT_PROJECT_ID")?,
+        address: required("WALLET_ADDRESS")?
+            .parse()
+            .context("Invalid WALLET_ADDRESS")?,
+        request_timeout,
+    })
+}
+
+/// A required setting of the KMS signer chosen by SIGNER
+fn required_kms_setting(key: &str, signer: &str) -> Result<String> {
+    secret_var(key)?
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .with_context(|| format!("{} is required with SIGNER={}", key, signer))
+}
+
+/// An optional secret setting, kept out of logs
+fn optional_secret(key: &str) -> Result<Option<SecretString>> {
+    Ok(secret_var(key)?
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| {
+            let secret = SecretString::new(value.trim());
+            register_secret(&secret);
+            secret
+        }))
+}
+
+/// AWS KMS key and credentials, required once SIGNER=aws_kms. Credentials are the
+/// standard AWS_* variables.
+fn parse_aws_kms() -> Result<KmsConfig> {
+    let region = match env_var("AWS_REGION").or_else(|_| env_var("AWS_DEFAULT_REGION")) {
+        Ok(region) if !region.trim().is_empty() => region.trim().to_string(),
+        _ => anyhow::bail!("AWS_REGION is required with SIGNER=aws_kms"),
+    };
+    let secret_access_key =
+        SecretString::new(required_kms_setting("AWS_SECRET_ACCESS_KEY", "aws_kms")?);
+    register_secret(&secret_access_key);
+    Ok(KmsConfig::Aws {
+        key_id: required_kms_setting("KMS_KEY_ID", "aws_kms")?,
+        region,
+        credentials: AwsCredentials {
+            access_key_id: required_kms_setting("AWS_ACCESS_KEY_ID", "aws_kms")?,
+            secret_access_key,
+            session_token: optional_secret("AWS_SESSION_TOKEN")?,
+        },
+        endpoint: kms_endpoint(),
+    })
+}
+
+/// Google Cloud KMS key version, required once SIGNER=gcp_kms. Without
+/// GCP_ACCESS_TOKEN, tokens come from the metadata server of the instance.
+fn parse_gcp_kms() -> Result<KmsConfig> {
+    Ok(KmsConfig::Gcp {
+        key_name: required_kms_setting("KMS_KEY_NAME", "gcp_kms")?,
+        access_token: optional_secret("GCP_ACCESS_TOKEN")?,
+        endpoint: kms_endpoint(),
+    })
+}
+
+fn kms_endpoint() -> Option<String> {
+    env_var("KMS_ENDPOINT")
+        .ok()
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// A required setting of the gas sponsor chosen by GAS_SPONSOR
+fn required_sponsor_setting(key: &str, mode: &str) -> Result<String> {
+    secret_var(key)?
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .with_context(|| format!("{} is required with GAS_SPONSOR={}", key, mode))
+}
+
+/// ERC-2771 forwarder settings, required once GAS_SPONSOR=forwarder
+fn parse_forwarder() -> Result<GasSponsorConfig> {
+    let relayer_key = SecretString::new(required_sponsor_setting(
+        "RELAYER_PRIVATE_KEY",
+        "forwarder",
+    )?);
+    register_secret(&relayer_key);
+    Ok(GasSponsorConfig::Forwarder {
+        forwarder: required_sponsor_setting("TRUSTED_FORWARDER", "forwarder")?
+            .parse()
+            .context("Invalid TRUSTED_FORWARDER")?,
+        relayer_key,
+    })
+}
+
+/// ERC-4337 paymaster settings; the bundler defaults to the paymaster's endpoint
+fn parse_paymaster() -> Result<GasSponsorConfig> {
+    let paymaster_url = required_sponsor_setting("PAYMASTER_URL", "paymaster")?;
+    Ok(GasSponsorConfig::Paymaster {
+        account: required_sponsor_setting("SMART_ACCOUNT", "paymaster")?
+            .parse()
+            .context("Invalid SMART_ACCOUNT")?,
+        entry_point: env_var("ENTRY_POINT")
+            .unwrap_or_else(|_| DEFAULT_ENTRY_POINT.to_string())
+            .trim()
+            .parse()
+            .context("Invalid ENTRY_POINT")?,
+        bundler_url: env_var("BUNDLER_URL").unwrap_or_else(|_| paymaster_url.clone()),
+        paymaster_url,
+    })
+}
+
+/// Replace the `.env` variables `env_var` falls back to
+pub fn store_env_file(vars: BTreeMap<String, String>) {
+    *ENV_FILE.write().unwrap_or_else(|e| e.into_inner()) = vars;
+}
+
+/// `key` from the environment, or else from the `.env` file
+pub(crate) fn env_var(key: &str) -> Result<String, env::VarError> {
+    match env::var(key) {
+        Err(env::VarError::NotPresent) => ENV_FILE
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+            .ok_or(env::VarError::NotPresent),
+        result => result,
+    }
+}
+
+/// `key` from the environment, or read from the file `{key}_FILE` names, as mounted
+/// for Docker and Kubernetes secrets. Setting both is refused rather than guessing
+/// which one is current.
+pub(crate) fn secret_var(key: &str) -> Result<Option<String>> {
+    let file_key = format!("{}_FILE", key);
+    match (env_var(key), env_var(&file_key)) {
+        (Ok(_), Ok(_)) => anyhow::bail!(
+            "Both {} and {} are set; set {} to a file holding the value, or {} to the value itself, not both",
+            key,
+            file_key,
+            file_key,
+            key
+        ),
+        (Ok(value), Err(_)) => Ok(Some(value)),
+        (Err(_), Ok(path)) => {
+            let value = std::fs::read_to_string(path.trim()).with_context(|| {
+                format!("Failed to read {} from {} (set by {})", key, path.trim(), file_key)
+            })?;
+            // Secret files usually end with a newline the value does not include
+            Ok(Some(value.trim_end_matches(['\r', '\n']).to_string()))
+        }
+        (Err(_), Err(_)) => Ok(crate::vault::secret(key)),
+    }
+}
+
+fn parse_env_or<T: std::str::FromStr>(key: &str, default: T) -> Result<T> {
+    match env_var(key) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid {}: {}", key, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Parse `name=value` headers separated by commas. Values are kept secret, since
+/// collectors usually take an API key this way.
+fn parse_otlp_headers(value: &str) -> Result<Vec<(String, SecretString)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, value) = entry.split_once('=').with_context(|| {
+                format!(
+                    "Invalid OTEL_EXPORTER_OTLP_HEADERS entry {}: expected name=value",
+                    entry
+                )
+            })?;
+            let value = SecretString::new(value.trim());
+            register_secret(&value);
+            Ok((name.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// Parse `name:address` pairs separated by commas, as set in `key`
+fn parse_named_addresses(key: &str, value: &str) -> Result<Vec<(String, Address)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, address) = entry.split_once(':').with_context(|| {
+                format!("Invalid {} entry {}: expected name:address", key, entry)
+            })?;
+            let address = address
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid {} address: {}", key, address))?;
+            Ok((name.trim().to_string(), address))
+        })
+        .collect()
+}
+
+/// `percent` as a slippage tolerance set in `key`, which must lie within 0-100
+fn slippage_percent(key: &str, percent: f64) -> Result<f64> {
+    if !(0.0..=100.0).contains(&percent) {
+        anyhow::bail!("Invalid {}: {} is not between 0 and 100", key, percent);
+    }
+    Ok(percent)
+}
+
+/// Parse `token_a/token_b:percent` entries separated by commas
+fn parse_slippage_pairs(value: &str) -> Result<Vec<(Address, Address, f64)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || {
+                format!(
+                    "Invalid SLIPPAGE_PAIRS entry {}: expected token_a/token_b:percent",
+                    entry
+                )
+            };
+            let (pair, percent) = entry.rsplit_once(':').with_context(invalid)?;
+            let (a, b) = pair.split_once('/').with_context(invalid)?;
+            let a: Address = a.trim().parse().with_context(invalid)?;
+            let b: Address = b.trim().parse().with_context(invalid)?;
+            let percent = percent.trim().parse().ok().with_context(invalid)?;
+            Ok((a, b, slippage_percent("SLIPPAGE_PAIRS", percent)?))
+        })
+        .collect()
+}
+
+/// Parse `token:percent` entries separated by commas
+fn parse_slippage_tokens(value: &str) -> Result<Vec<(Address, f64)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || {
+                format!(
+                    "Invalid SLIPPAGE_TOKENS entry {}: expected token:percent",
+                    entry
+                )
+            };
+            let (token, percent) = entry.rsplit_once(':').with_context(invalid)?;
+            let token: Address = token.trim().parse().with_context(invalid)?;
+            let percent = percent.trim().parse().ok().with_context(invalid)?;
+            Ok((token, slippage_percent("SLIPPAGE_TOKENS", percent)?))
+        })
+        .collect()
+}
+
+/// Parse `alias:tool` pairs separated by commas
+fn parse_tool_aliases(value: &str) -> Result<Vec<(String, String)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (alias, tool) = entry.split_once(':').with_context(|| {
+                format!("Invalid TOOL_ALIASES entry {}: expected alias:tool", entry)
+            })?;
+            let (alias, tool) = (alias.trim(), tool.trim());
+            if alias.is_empty() || tool.is_empty() {
+                anyhow::bail!("Invalid TOOL_ALIASES entry {}: expected alias:tool", entry);
+            }
+            Ok((alias.to_string(), tool.to_string()))
+        })
+        .collect()
+}
+
+/// Parse `chain_id=url` pairs separated by commas. The primary chain is served by
+/// ETH_RPC_URL, so it cannot be listed again. Each URL is registered as a secret.
+fn parse_chain_rpc_urls(value: &str, primary_chain_id: u64) -> Result<Vec<(u64, String)>> {
+    let mut chains: Vec<(u64, String)> = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (chain_id, url) = entry
+            .split_once('=')
+            .context("Invalid CHAIN_RPC_URLS entry: expected chain_id=url")?;
+        let chain_id: u64 = chain_id
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CHAIN_RPC_URLS chain id: {}", chain_id))?;
+        if chain_id == primary_chain_id || chains.iter().any(|(id, _)| *id == chain_id) {
+            anyhow::bail!("CHAIN_RPC_URLS lists chain {} more than once", chain_id);
+        }
+        let url = url.trim().to_string();
+        register_secret(&SecretString::new(url.as_str()));
+        chains.push((chain_id, url));
+    }
+    Ok(chains)
+}
+
+/// Parse scam list feeds separated by commas, each `name=url` or a bare URL named after
+/// its host. `none` lists no feeds.
+fn parse_scam_feeds(value: &str) -> Result<Vec<ScamFeed>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let mut feeds: Vec<ScamFeed> = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let feed = match entry.split_once('=') {
+            Some((name, url)) if !name.contains("://") => ScamFeed {
+                name: name.trim().to_string(),
+                url: url.trim().to_string(),
+            },
+            _ => ScamFeed::from_url(entry),
+        };
+        if feed.name.is_empty() || !feed.url.starts_with("http") {
+            anyhow::bail!(
+                "Invalid SCAM_LIST_URLS entry {}: expected name=url or an http(s) URL",
+                entry
+            );
+        }
+        if feeds.iter().any(|known| known.name == feed.name) {
+            anyhow::bail!("SCAM_LIST_URLS names {} more than once", feed.name);
+        }
+        feeds.push(feed);
+    }
+    Ok(feeds)
+}
+
+/// Parse `token:tier` pairs separated by commas. Errors name the entry, never the token.
+fn parse_auth_tokens(value: &str) -> Result<Vec<ApiToken>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .enumerate()
+        .map(|(index, entry)| {
+            let (token, tier) = entry.rsplit_once(':').with_context(|| {
+                format!(
+                    "Invalid MCP_AUTH_TOKENS entry {}: expected token:tier",
+                    index + 1
+                )
+            })?;
+            let tier = tier
+                .parse()
+                .with_context(|| format!("Invalid MCP_AUTH_TOKENS entry {}", index + 1))?;
+            let token = token.trim();
+            if token.len() < MIN_AUTH_TOKEN_LEN {
+                anyhow::bail!(
+                    "Invalid MCP_AUTH_TOKENS entry {}: token must be at least {} characters",
+                    index + 1,
+                    MIN_AUTH_TOKEN_LEN
+                );
+            }
+            let token = SecretString::new(token);
+            register_secret(&token);
+            Ok(ApiToken { token, tier })
+        })
+        .collect()
 }
@@ -0,0 +1,174 @@
+use crate::secrets::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Mainnet slot time
+pub const SECONDS_PER_SLOT: u64 = 12;
+/// Epoch of a validator that has not exited or cannot withdraw yet
+pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
+/// A validator as the beacon chain records it at some slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validator {
+    pub index: u64,
+    /// BLS public key, 0x hex
+    pub pubkey: String,
+    /// Beacon API status, such as `active_ongoing` or `withdrawal_done`
+    pub status: String,
+    pub balance_gwei: u64,
+    pub effective_balance_gwei: u64,
+    pub slashed: bool,
+    pub withdrawal_credentials: H256,
+    pub activation_epoch: u64,
+    pub exit_epoch: u64,
+}
+
+impl Validator {
+    /// Execution address balances are withdrawn to; None for BLS (0x00) credentials
+    pub fn withdrawal_address(&self) -> Option<Address> {
+        let credentials = self.withdrawal_credentials.as_bytes();
+        matches!(credentials[0], 0x01 | 0x02).then(|| Address::from_slice(&credentials[12..]))
+    }
+}
+
+/// Source of beacon-chain validator state
+#[async_trait]
+pub trait BeaconSource: Send + Sync {
+    /// Endpoint reported with the figures, without path or credentials
+    fn name(&self) -> &str;
+
+    /// Slot of the head block
+    async fn head_slot(&self) -> Result<u64>;
+
+    /// Validator `id`, an index or 0x pubkey, at `slot` (default: head)
+    async fn validator(&self, id: &str, slot: Option<u64>) -> Result<Validator>;
+}
+
+/// Validator state from a standard beacon node API (`/eth/v1/beacon/...`)
+pub struct BeaconApi {
+    http: reqwest::Client,
+    url: SecretString,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconHeader {
+    header: BeaconSignedHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconSignedHeader {
+    message: BeaconHeaderMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconHeaderMessage {
+    #[serde(deserialize_with = "quoted")]
+    slot: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconValidator {
+    #[serde(deserialize_with = "quoted")]
+    index: u64,
+    #[serde(deserialize_with = "quoted")]
+    balance: u64,
+    status: String,
+    validator: BeaconValidatorRecord,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconValidatorRecord {
+    pubkey: String,
+    withdrawal_credentials: H256,
+    #[serde(deserialize_with = "quoted")]
+    effective_balance: u64,
+    slashed: bool,
+    #[serde(deserialize_with = "quoted")]
+    activation_epoch: u64,
+    #[serde(deserialize_with = "quoted")]
+    exit_epoch: u64,
+}
+
+/// Beacon APIs send 64-bit numbers as decimal strings
+fn quoted<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u64, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+impl BeaconApi {
+    pub fn new(url: SecretString) -> Self {
+        let endpoint = reqwest::Url::parse(url.expose_secret())
+            .ok()
+            .and_then(|url| Some(format!("{}://{}", url.scheme(), url.host_str()?)))
+            .unwrap_or_else(|| "beacon".to_string());
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: SecretString::new(url.expose_secret().trim_end_matches('/')),
+            endpoint,
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response: BeaconResponse<T> = self
+            .http
+            .get(format!("{}{}", self.url.expose_secret(), path))
+            .send()
+            .await
+            .context("Failed to reach the beacon API")?
+            .error_for_status()
+            .context("Beacon API request failed")?
+            .json()
+            .await
+            .context("Invalid beacon API response")?;
+        Ok(response.data)
+    }
+}
+
+#[async_trait]
+impl BeaconSource for BeaconApi {
+    fn name(&self) -> &str {
+        &self.endpoint
+    }
+
+    async fn head_slot(&self) -> Result<u64> {
+        let head: BeaconHeader = self.get("/eth/v1/beacon/headers/head").await?;
+        Ok(head.header.message.slot)
+    }
+
+    async fn validator(&self, id: &str, slot: Option<u64>) -> Result<Validator> {
+        let state = slot.map_or_else(|| "head".to_string(), |slot| slot.to_string());
+        let found: BeaconValidator = self
+            .get(&format!(
+                "/eth/v1/beacon/states/{}/validators/{}",
+                state, id
+            ))
+            .await
+            .with_context(|| format!("Failed to read validator {}", id))?;
+        Ok(Validator {
+            index: found.index,
+            pubkey: found.validator.pubkey,
+            status: found.status,
+            balance_gwei: found.balance,
+            effective_balance_gwei: found.validator.effective_balance,
+            slashed: found.validator.slashed,
+            withdrawal_credentials: found.validator.withdrawal_credentials,
+            activation_epoch: found.validator.activation_epoch,
+            exit_epoch: found.validator.exit_epoch,
+        })
+    }
+}
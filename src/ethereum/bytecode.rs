@@ -0,0 +1,323 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::types::Address;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Public signature database used to name function selectors
+pub const DEFAULT_FOUR_BYTE_URL: &str = "https://www.4byte.directory";
+
+// Lookups in flight at once; a large contract has a hundred or more selectors
+const MAX_PARALLEL_LOOKUPS: usize = 8;
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const OP_EQ: u8 = 0x14;
+pub const OP_DUP2: u8 = 0x81;
+pub const OP_PUSH1: u8 = 0x60;
+pub const OP_PUSH4: u8 = 0x63;
+pub const OP_PUSH32: u8 = 0x7f;
+pub const OP_CREATE: u8 = 0xf0;
+pub const OP_CALLCODE: u8 = 0xf2;
+pub const OP_DELEGATECALL: u8 = 0xf4;
+pub const OP_CREATE2: u8 = 0xf5;
+pub const OP_SELFDESTRUCT: u8 = 0xff;
+
+/// Mnemonic of `opcode`, or None for bytes no fork has assigned
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    Some(match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH_MNEMONICS[(opcode - OP_PUSH1) as usize],
+        0x80..=0x8f => DUP_MNEMONICS[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP_MNEMONICS[(opcode - 0x90) as usize],
+        0xa0 => "LOG0",
+        0xa1 => "LOG1",
+        0xa2 => "LOG2",
+        0xa3 => "LOG3",
+        0xa4 => "LOG4",
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    })
+}
+
+const PUSH_MNEMONICS: [&str; 32] = [
+    "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+    "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+    "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+    "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+];
+
+const DUP_MNEMONICS: [&str; 16] = [
+    "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+    "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+];
+
+const SWAP_MNEMONICS: [&str; 16] = [
+    "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+    "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+];
+
+/// One decoded instruction; PUSH data is carried as `immediate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    pub immediate: Vec<u8>,
+}
+
+impl Instruction {
+    /// Assembly text, e.g. `PUSH4 0xa9059cbb`; unassigned bytes show as `UNKNOWN(0x0c)`
+    pub fn text(&self) -> String {
+        let name = mnemonic(self.opcode)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("UNKNOWN(0x{:02x})", self.opcode));
+        if self.immediate.is_empty() {
+            name
+        } else {
+            format!("{} 0x{}", name, ethers::utils::hex::encode(&self.immediate))
+        }
+    }
+}
+
+/// Decode `code` into instructions, skipping over PUSH data; a PUSH cut off by the end
+/// of the code keeps the bytes that are there
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let width = match opcode {
+            OP_PUSH1..=OP_PUSH32 => (opcode - OP_PUSH1 + 1) as usize,
+            _ => 0,
+        };
+        let end = (pc + 1 + width).min(code.len());
+        instructions.push(Instruction {
+            pc,
+            opcode,
+            immediate: code[pc + 1..end].to_vec(),
+        });
+        pc += 1 + width;
+    }
+    instructions
+}
+
+/// `code` without the CBOR metadata solc appends, whose length is in the last two
+/// bytes; its hash bytes would otherwise decode as bogus instructions
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    if code.len() < 2 {
+        return code;
+    }
+    let length = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    let Some(start) = code.len().checked_sub(length + 2) else {
+        return code;
+    };
+    // A CBOR map of one to five entries opens the metadata
+    match code.get(start) {
+        Some(0xa1..=0xa5) if length > 0 => &code[..start],
+        _ => code,
+    }
+}
+
+/// Function selectors the dispatcher compares calldata against, in code order.
+/// Solidity and Vyper emit `PUSH4 selector EQ`, or `PUSH4 selector DUP2 EQ` under via-IR.
+pub fn function_selectors(code: &[u8]) -> Vec<[u8; 4]> {
+    let instructions = disassemble(strip_metadata(code));
+    let mut selectors: Vec<[u8; 4]> = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        if instruction.opcode != OP_PUSH4 {
+            continue;
+        }
+        // Code that ends mid-PUSH4 has a short immediate
+        let Ok(selector) = <[u8; 4]>::try_from(&instruction.immediate[..]) else {
+            continue;
+        };
+        let next = instructions.get(index + 1).map(|next| next.opcode);
+        let after = instructions.get(index + 2).map(|after| after.opcode);
+        let compared = next == Some(OP_EQ) || (next == Some(OP_DUP2) && after == Some(OP_EQ));
+        if compared && !selectors.contains(&selector) {
+            selectors.push(selector);
+        }
+    }
+    selectors
+}
+
+/// Target of an EIP-1167 minimal proxy (clone), whose whole code is a fixed delegatecall
+pub fn minimal_proxy_target(code: &[u8]) -> Option<Address> {
+    const PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+    const SUFFIX: [u8; 15] = [
+        0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+    ];
+    (code.len() == PREFIX.len() + 20 + SUFFIX.len()
+        && code.starts_with(&PREFIX)
+        && code.ends_with(&SUFFIX))
+    .then(|| Address::from_slice(&code[PREFIX.len()..PREFIX.len() + 20]))
+}
+
+/// Names text signatures for function selectors
+#[async_trait]
+pub trait SignatureSource: Send + Sync {
+    /// Known signatures for each selector, most likely first; selectors nobody has
+    /// submitted are left out
+    async fn lookup(&self, selectors: &[[u8; 4]]) -> Result<HashMap<[u8; 4], Vec<String>>>;
+}
+
+/// The 4byte directory's public API
+pub struct FourByteDirectory {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FourByteResponse {
+    results: Vec<FourByteSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FourByteSignature {
+    id: u64,
+    text_signature: String,
+}
+
+impl FourByteDirectory {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(LOOKUP_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn signatures(http: reqwest::Client, url: String) -> Result<Vec<String>> {
+        let mut response: FourByteResponse = http
+            .get(url)
+            .send()
+            .await
+            .context("Failed to reach the 4byte directory")?
+            .error_for_status()
+            .context("4byte directory request failed")?
+            .json()
+            .await
+            .context("Invalid 4byte directory response")?;
+        // The first submission is usually the real one; later ones are often crafted collisions
+        response.results.sort_by_key(|signature| signature.id);
+        Ok(response
+            .results
+            .into_iter()
+            .map(|signature| signature.text_signature)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl SignatureSource for FourByteDirectory {
+    async fn lookup(&self, selectors: &[[u8; 4]]) -> Result<HashMap<[u8; 4], Vec<String>>> {
+        let permits = Arc::new(Semaphore::new(MAX_PARALLEL_LOOKUPS));
+        let mut lookups = JoinSet::new();
+        for selector in selectors {
+            let selector = *selector;
+            let url = format!(
+                "{}/api/v1/signatures/?hex_signature=0x{}",
+                self.url,
+                ethers::utils::hex::encode(selector)
+            );
+            let http = self.http.clone();
+            let permits = Arc::clone(&permits);
+            lookups.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                Ok::<_, anyhow::Error>((selector, Self::signatures(http, url).await?))
+            });
+        }
+        let mut found = HashMap::new();
+        while let Some(lookup) = lookups.join_next().await {
+            let (selector, signatures) = lookup.context("Signature lookup panicked")??;
+            if !signatures.is_empty() {
+                found.insert(selector, signatures);
+            }
+        }
+        Ok(found)
+    }
+}
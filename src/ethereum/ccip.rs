@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use ethers::abi::{self, Token};
+use ethers::prelude::*;
+use ethers::providers::call_raw::{spoof, RawCall};
+use ethers::providers::RpcError;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::hex;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+// EIP-3668 asks clients to bound how many lookups one call may chain
+const MAX_CCIP_REDIRECTS: usize = 4;
+
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The revert an EIP-3668 contract raises to ask for data from an off-chain gateway
+#[derive(Debug, Clone, PartialEq, EthError)]
+#[etherror(
+    name = "OffchainLookup",
+    abi = "OffchainLookup(address,string[],bytes,bytes4,bytes)"
+)]
+pub struct OffchainLookup {
+    pub sender: Address,
+    pub urls: Vec<String>,
+    pub call_data: Bytes,
+    pub callback_function: [u8; 4],
+    pub extra_data: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayResponse {
+    data: Bytes,
+}
+
+/// Contract reads that follow EIP-3668 CCIP-Read: an `OffchainLookup` revert is
+/// answered by fetching from the gateways it names and calling its callback
+pub struct CcipRead {
+    provider: Arc<Provider<Http>>,
+    http: reqwest::Client,
+}
+
+impl CcipRead {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self {
+            provider,
+            http: reqwest::Client::builder()
+                .timeout(GATEWAY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// `eth_call` `data` on `to`, resolving off-chain lookups along the way
+    pub async fn call(&self, to: Address, data: Bytes) -> Result<Bytes> {
+        let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        self.call_with(&tx, None, None).await
+    }
+
+    /// `eth_call` `tx` at `block` with a state override set, resolving off-chain lookups;
+    /// callbacks run at the same block and with the same overrides
+    pub async fn call_with(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        state: Option<&spoof::State>,
+    ) -> Result<Bytes> {
+        let to = match tx.to() {
+            Some(NameOrAddress::Address(to)) => *to,
+            _ => anyhow::bail!("Contract calls need a contract address"),
+        };
+        let mut tx = tx.clone();
+        for _ in 0..=MAX_CCIP_REDIRECTS {
+            let mut call = self.provider.call_raw(&tx);
+            if let Some(block) = block {
+                call = call.block(block);
+            }
+            if let Some(state) = state {
+                call = call.state(state);
+            }
+            let error = match call.await {
+                Ok(output) => return Ok(output),
+                Err(error) => error,
+            };
+            let Some(lookup) = RpcError::as_error_response(&error)
+                .and_then(|response| response.as_revert_data())
+                .and_then(|revert| OffchainLookup::decode_with_selector(revert.as_ref()))
+            else {
+                return Err(error).context("Contract call failed");
+            };
+            // A lookup raised by a contract the call passed through cannot be answered safely
+            if lookup.sender != to {
+                anyhow::bail!(
+                    "OffchainLookup sender {:?} is not the called contract {:?}",
+                    lookup.sender,
+                    to
+                );
+            }
+            let response = self.fetch(&lookup).await?;
+            let mut callback = lookup.callback_function.to_vec();
+            callback.extend(abi::encode(&[
+                Token::Bytes(response.to_vec()),
+                Token::Bytes(lookup.extra_data.to_vec()),
+            ]));
+            tx.set_data(callback.into());
+        }
+        anyhow::bail!(
+            "Contract {:?} chained more than {} off-chain lookups",
+            to,
+            MAX_CCIP_REDIRECTS
+        )
+    }
+
+    /// Ask each gateway in turn; a client error (4xx) ends the search, as the spec requires
+    async fn fetch(&self, lookup: &OffchainLookup) -> Result<Bytes> {
+        let sender = format!("{:?}", lookup.sender);
+        let call_data = format!("0x{}", hex::encode(&lookup.call_data));
+        let mut last_error = anyhow::anyhow!("OffchainLookup names no gateway");
+        for url in &lookup.urls {
+            let request = if url.contains("{data}") {
+                self.http.get(
+                    url.replace("{sender}", &sender)
+                        .replace("{data}", &call_data),
+                )
+            } else {
+                self.http
+                    .post(url.replace("{sender}", &sender))
+                    .json(&serde_json::json!({ "data": call_data, "sender": sender }))
+            };
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = anyhow::Error::new(e).context(format!("Gateway {} failed", url));
+                    continue;
+                }
+            };
+            let status = response.status();
+            if status.is_client_error() {
+                anyhow::bail!("Gateway {} rejected the lookup: {}", url, status);
+            }
+            if !status.is_success() {
+                last_error = anyhow::anyhow!("Gateway {} failed: {}", url, status);
+                continue;
+            }
+            let body: GatewayResponse = response
+                .json()
+                .await
+                .with_context(|| format!("Invalid response from gateway {}", url))?;
+            return Ok(body.data);
+        }
+        Err(last_error)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::ethereum::{EthereumClientTrait, UniswapRouterTrait, UniswapV2Router};
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// Chains with a known name and native gas token; others are reported by id
+const KNOWN_CHAINS: &[(u64, &str, &str)] = &[
+    (1, "ethereum", "ETH"),
+    (10, "optimism", "ETH"),
+    (56, "bsc", "BNB"),
+    (100, "gnosis", "xDAI"),
+    (137, "polygon", "POL"),
+    (8453, "base", "ETH"),
+    (42161, "arbitrum", "ETH"),
+    (43114, "avalanche", "AVAX"),
+    (59144, "linea", "ETH"),
+    (534352, "scroll", "ETH"),
+    (11155111, "sepolia", "ETH"),
+];
+
+/// Uniswap V2 deployment on a chain, with the tokens its USD prices are read through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V2Deployment {
+    pub chain_id: u64,
+    pub router: &'static str,
+    pub factory: &'static str,
+    /// Wrapped form of the chain's native token
+    pub wrapped_native: &'static str,
+    /// Dollar stablecoin prices are quoted in
+    pub usd_token: &'static str,
+    pub usd_decimals: u8,
+}
+
+const UNISWAP_V2_DEPLOYMENTS: &[V2Deployment] = &[
+    V2Deployment {
+        chain_id: 1,
+        router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D",
+        factory: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f",
+        wrapped_native: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        usd_token: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        usd_decimals: 6,
+    },
+    V2Deployment {
+        chain_id: 10,
+        router: "0x4A7b5Da61326A6379179b40d00F57E5bbDC962c2",
+        factory: "0x0c3c1c532F1e39EdF36BE9Fe0bE1410313E074Bf",
+        wrapped_native: "0x4200000000000000000000000000000000000006",
+        usd_token: "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85",
+        usd_decimals: 6,
+    },
+    V2Deployment {
+        chain_id: 56,
+        router: "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24",
+        factory: "0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6",
+        wrapped_native: "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c",
+        // Binance-Peg USDT uses 18 decimals
+        usd_token: "0x55d398326f99059fF775485246999027B3197955",
+        usd_decimals: 18,
+    },
+    V2Deployment {
+        chain_id: 137,
+        router: "0xedf6066a2b290C185783862C7F4776A2C8077AD1",
+        factory: "0x9e5A52f57b3038F1B8EeE45F28b3C1967e22799C",
+        wrapped_native: "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270",
+        usd_token: "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359",
+        usd_decimals: 6,
+    },
+    V2Deployment {
+        chain_id: 8453,
+        router: "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24",
+        factory: "0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6",
+        wrapped_native: "0x4200000000000000000000000000000000000006",
+        usd_token: "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        usd_decimals: 6,
+    },
+    V2Deployment {
+        chain_id: 42161,
+        router: "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24",
+        factory: "0xf1D7CC64Fb4452F05c498126312eBE29f30Fbcf9",
+        wrapped_native: "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        usd_token: "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+        usd_decimals: 6,
+    },
+];
+
+/// Uniswap V2 deployment on `chain_id`, if there is a known one
+pub fn uniswap_v2_deployment(chain_id: u64) -> Option<&'static V2Deployment> {
+    UNISWAP_V2_DEPLOYMENTS
+        .iter()
+        .find(|deployment| deployment.chain_id == chain_id)
+}
+
+/// How holdings on one chain are priced in USD: through a V2 router on that chain,
+/// quoting against its wrapped native token and a dollar stablecoin
+#[derive(Clone)]
+pub struct ChainPricing {
+    pub venue: String,
+    pub router: Arc<dyn UniswapRouterTrait>,
+    pub wrapped_native: Address,
+    pub usd_token: Address,
+    pub usd_decimals: u8,
+}
+
+impl ChainPricing {
+    /// Pricing through the known Uniswap V2 deployment on `chain_id`, if it has one
+    pub fn uniswap_v2(chain_id: u64, provider: Arc<Provider<Http>>) -> Result<Option<Self>> {
+        let Some(deployment) = uniswap_v2_deployment(chain_id) else {
+            return Ok(None);
+        };
+        let address = |address: &str, role: &str| -> Result<Address> {
+            address.parse().with_context(|| {
+                format!("Invalid Uniswap V2 {} address for chain {}", role, chain_id)
+            })
+        };
+        let router = UniswapV2Router::with_addresses(
+            provider,
+            "uniswap_v2",
+            address(deployment.router, "router")?,
+            address(deployment.factory, "factory")?,
+        );
+        Ok(Some(Self {
+            venue: "uniswap_v2".to_string(),
+            router: Arc::new(router),
+            wrapped_native: address(deployment.wrapped_native, "wrapped native token")?,
+            usd_token: address(deployment.usd_token, "USD token")?,
+            usd_decimals: deployment.usd_decimals,
+        }))
+    }
+}
+
+/// Name of `chain_id`, e.g. `arbitrum`, or `chain-<id>` when unknown
+pub fn chain_name(chain_id: u64) -> String {
+    KNOWN_CHAINS
+        .iter()
+        .find(|(id, _, _)| *id == chain_id)
+        .map(|(_, name, _)| name.to_string())
+        .unwrap_or_else(|| format!("chain-{}", chain_id))
+}
+
+/// Symbol of the token `chain_id` pays gas in; ETH when unknown
+pub fn native_symbol(chain_id: u64) -> &'static str {
+    KNOWN_CHAINS
+        .iter()
+        .find(|(id, _, _)| *id == chain_id)
+        .map(|(_, _, symbol)| *symbol)
+        .unwrap_or("ETH")
+}
+
+/// One client per configured chain, keyed by chain id. The primary chain signs;
+/// the others are used for reads.
+#[derive(Clone, Default)]
+pub struct ChainClients {
+    clients: BTreeMap<u64, Arc<dyn EthereumClientTrait>>,
+    pricing: BTreeMap<u64, ChainPricing>,
+}
+
+impl ChainClients {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `client` under the chain it reports, replacing any client for that chain
+    pub fn with_client(mut self, client: Arc<dyn EthereumClientTrait>) -> Self {
+        self.clients.insert(client.get_chain_id(), client);
+        self
+    }
+
+    /// Price holdings on `chain_id` through `pricing`
+    pub fn with_pricing(mut self, chain_id: u64, pricing: ChainPricing) -> Self {
+        self.pricing.insert(chain_id, pricing);
+        self
+    }
+
+    pub fn pricing(&self, chain_id: u64) -> Option<&ChainPricing> {
+        self.pricing.get(&chain_id)
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<&Arc<dyn EthereumClientTrait>> {
+        self.clients.get(&chain_id)
+    }
+
+    /// Clients in chain id order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &Arc<dyn EthereumClientTrait>)> {
+        self.clients.iter().map(|(id, client)| (*id, client))
+    }
+
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.clients.keys().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+}
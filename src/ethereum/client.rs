@@ -1,9 +1,96 @@
+use crate::ethereum::ccip::CcipRead;
+use crate::ethereum::escalation::{FeeReplacement, GasEscalation};
+use crate::ethereum::kms::{KmsConfig, KmsSigner};
+use crate::ethereum::simulation::SimulatedCall;
+use crate::ethereum::sponsor::{GasSponsor, Sponsorship};
+use crate::ethereum::walletconnect::WalletConnectSigner;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::prelude::*;
+use ethers::providers::call_raw::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+
+// Typical gas for an ERC20 approve that sets a fresh allowance
+const DEFAULT_APPROVE_GAS: u64 = 46_000;
+
+// How often a pending transaction is checked while escalating
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Allowance check and cost of the approval a spend would need
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalSimulation {
+    pub current_allowance: U256,
+    /// True when the current allowance does not cover the amount
+    pub approval_required: bool,
+    pub gas_estimate: U256,
+    /// Token implements EIP-2612 `permit`, so a signature can replace the approve transaction
+    pub supports_permit: bool,
+}
+
+/// Where an ERC20Votes holder's voting power goes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Delegation {
+    /// `None` until the holder delegates, leaving its tokens without votes
+    pub delegate: Option<Address>,
+    /// Votes delegated to the holder, in whole tokens
+    pub votes: Decimal,
+}
+
+/// Result of a signed and mined transaction
+#[derive(Debug, Clone, Default)]
+pub struct TransactionOutcome {
+    pub tx_hash: H256,
+    /// Receipt status was 1
+    pub success: bool,
+    pub gas_used: U256,
+    /// Block the transaction was mined in, when the receipt reports it
+    pub block_number: Option<u64>,
+    /// Price per gas the transaction paid, when the receipt reports it
+    pub effective_gas_price: Option<U256>,
+    /// Fee bumps sent before the transaction was mined, oldest first
+    pub replacements: Vec<FeeReplacement>,
+    /// Set when a forwarder or paymaster paid for the gas instead of the wallet
+    pub sponsorship: Option<Sponsorship>,
+}
+
+/// Number and timestamp of a block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub number: u64,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+/// Base fees of a run of consecutive blocks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BaseFeeHistory {
+    pub oldest_block: u64,
+    /// Base fee of each block from `oldest_block` on, in wei
+    pub base_fees: Vec<U256>,
+    /// Base fee the block after the newest one will charge
+    pub next_base_fee: U256,
+    /// Share of each block's gas target used, from 0 to 1
+    pub gas_used_ratios: Vec<f64>,
+}
+
+/// A transaction from the wallet still waiting in the mempool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PendingTransaction {
+    pub hash: H256,
+    pub nonce: u64,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Bytes,
+    /// Legacy gas price, or the max fee for EIP-1559 transactions
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Held back by a nonce gap rather than waiting for inclusion
+    pub queued: bool,
+}
 
 /// Trait for Ethereum client operations
 #[async_trait]
@@ -21,46 +108,322 @@ pub trait EthereumClientTrait: Send + Sync {
     /// Get token symbol
     async fn get_token_symbol(&self, token_address: Address) -> Result<String>;
 
+    /// Get token decimals
+    async fn get_token_decimals(&self, token_address: Address) -> Result<u8>;
+
+    /// Get the latest block number
+    async fn get_block_number(&self) -> Result<u64>;
+
+    /// Get the latest block's number and timestamp
+    async fn get_latest_block(&self) -> Result<BlockInfo>;
+
+    /// Number and timestamp of the block `tag` names, e.g. the safe or finalized head
+    async fn get_block(&self, tag: BlockNumber) -> Result<BlockInfo>;
+
+    /// ETH balance of `address` at `block` (default: latest)
+    async fn get_eth_balance_at(&self, address: Address, block: Option<u64>) -> Result<Decimal>;
+
+    /// ERC20 balance and decimals of `wallet_address` at `block` (default: latest)
+    async fn get_token_balance_at(
+        &self,
+        token_address: Address,
+        wallet_address: Address,
+        block: Option<u64>,
+    ) -> Result<(Decimal, u8)>;
+
+    /// Get the current gas price in wei
+    async fn get_gas_price(&self) -> Result<U256>;
+
+    /// Base fees of the `block_count` blocks up to and including `newest_block`
+    async fn get_base_fee_history(
+        &self,
+        newest_block: u64,
+        block_count: u64,
+    ) -> Result<BaseFeeHistory>;
+
+    /// Check `owner`'s allowance for `spender` and estimate the approve needed to spend `amount`
+    async fn simulate_approval(
+        &self,
+        token_address: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<ApprovalSimulation>;
+
+    /// Estimate gas for a transaction without sending it
+    async fn estimate_transaction_gas(&self, tx: &TypedTransaction) -> Result<U256>;
+
+    /// Sign a transaction with the configured wallet, send it and wait for the receipt,
+    /// re-sending with higher fees under `escalation` while it stays unmined
+    async fn send_transaction(
+        &self,
+        tx: TypedTransaction,
+        escalation: Option<GasEscalation>,
+    ) -> Result<TransactionOutcome>;
+
+    /// Whether `send_transaction` would have a sponsor pay for `tx`'s gas
+    async fn gas_sponsored(&self, tx: &TypedTransaction) -> bool;
+
+    /// Suggested EIP-1559 fees as `(max_fee_per_gas, max_priority_fee_per_gas)`
+    async fn estimate_fees(&self) -> Result<(U256, U256)>;
+
+    /// Broadcast a transaction signed elsewhere, returning its hash without waiting
+    async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256>;
+
+    /// Chain the client signs for
+    fn get_chain_id(&self) -> u64;
+
+    /// Nonces of `address` as of the latest block and including pending transactions
+    async fn get_nonces(&self, address: Address) -> Result<(u64, u64)>;
+
+    /// Mempool transactions sent by `address`, or `None` when the node does not
+    /// expose its transaction pool
+    async fn get_pending_transactions(
+        &self,
+        address: Address,
+    ) -> Result<Option<Vec<PendingTransaction>>>;
+
+    /// Whether contract code is deployed at `address`
+    async fn has_code(&self, address: Address) -> Result<bool>;
+
+    /// `account`'s delegate and votes on an ERC20Votes (or Compound-style) governance token
+    async fn get_delegation(&self, token_address: Address, account: Address) -> Result<Delegation>;
+
+    /// Deployed code of `address` at `block` (default: latest); empty for accounts
+    async fn get_code(&self, address: Address, block: Option<u64>) -> Result<Bytes>;
+
+    /// Raw storage word at `slot` of `address`, at `block` (default: latest)
+    async fn get_storage(&self, address: Address, slot: H256, block: Option<u64>) -> Result<H256>;
+
+    /// `eth_call` `tx` at `block` (default: latest) with an optional state override set,
+    /// following EIP-3668 off-chain lookups
+    async fn call_contract(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        overrides: Option<&spoof::State>,
+    ) -> Result<Bytes>;
+
+    /// Run `txs` in order on top of the latest block without sending them, tracing
+    /// their events and ETH transfers
+    async fn simulate_bundle(&self, txs: &[TypedTransaction]) -> Result<Vec<SimulatedCall>>;
+
     /// Get wallet address
     fn get_wallet_address(&self) -> Address;
+
+    /// RPC endpoint reported alongside results, without path or credentials
+    fn rpc_endpoint(&self) -> String;
+}
+
+/// What to do when the RPC endpoint's `eth_chainId` differs from the configured chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChainIdCheck {
+    /// Refuse to start or sign
+    #[default]
+    Strict,
+    /// Log a warning and carry on
+    Warn,
+    Off,
+}
+
+impl std::str::FromStr for ChainIdCheck {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict" => Ok(ChainIdCheck::Strict),
+            "warn" => Ok(ChainIdCheck::Warn),
+            "off" => Ok(ChainIdCheck::Off),
+            other => anyhow::bail!(
+                "Unknown chain id check: {} (expected strict, warn or off)",
+                other
+            ),
+        }
+    }
+}
+
+/// The RPC endpoint serves a different chain than the one configured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainIdMismatch {
+    pub endpoint: String,
+    pub expected: u64,
+    pub actual: U256,
+}
+
+impl std::fmt::Display for ChainIdMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RPC endpoint {} is on chain {} but CHAIN_ID is {}",
+            self.endpoint, self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ChainIdMismatch {}
+
+/// What signs the transactions a client sends
+enum TxSigner {
+    Local(LocalWallet),
+    /// The user's mobile wallet, which approves each transaction
+    WalletConnect(Arc<WalletConnectSigner>),
+    /// A key held in a cloud KMS
+    Kms(KmsSigner),
 }
 
 pub struct EthereumClient {
     provider: Arc<Provider<Http>>,
-    wallet: LocalWallet,
+    ccip: CcipRead,
+    signer: TxSigner,
     chain_id: u64,
+    rpc_endpoint: String,
+    chain_id_check: ChainIdCheck,
+    sponsor: Option<Arc<GasSponsor>>,
+    /// Endpoint bundles are simulated on, when not the main one
+    simulation: Option<Arc<Provider<Http>>>,
 }
 
 impl EthereumClient {
     pub async fn new(rpc_url: &str, private_key: &str, chain_id: u64) -> Result<Self> {
-        let provider =
-            Provider::<Http>::try_from(rpc_url).context("Failed to connect to Ethereum RPC")?;
-
         let wallet = private_key
             .parse::<LocalWallet>()
             .context("Failed to parse private key")?
             .with_chain_id(chain_id);
+        Self::connect(rpc_url, chain_id, TxSigner::Local(wallet))
+    }
+
+    /// A client whose transactions are approved in the wallet paired through `signer`
+    pub async fn walletconnect(
+        rpc_url: &str,
+        signer: Arc<WalletConnectSigner>,
+        chain_id: u64,
+    ) -> Result<Self> {
+        Self::connect(rpc_url, chain_id, TxSigner::WalletConnect(signer))
+    }
+
+    /// A client whose transactions are signed by a key in a cloud KMS
+    pub async fn kms(rpc_url: &str, config: &KmsConfig, chain_id: u64) -> Result<Self> {
+        let signer = KmsSigner::connect(config, chain_id)
+            .await
+            .context("Failed to set up the KMS signer")?;
+        tracing::info!("Signing with KMS key for {:?}", signer.address());
+        Self::connect(rpc_url, chain_id, TxSigner::Kms(signer))
+    }
+
+    fn connect(rpc_url: &str, chain_id: u64, signer: TxSigner) -> Result<Self> {
+        let provider =
+            Provider::<Http>::try_from(rpc_url).context("Failed to connect to Ethereum RPC")?;
+
+        // Hosted RPC URLs often carry an API key in the path or query
+        let rpc_endpoint = match provider.url().host_str() {
+            Some(host) => format!("{}://{}", provider.url().scheme(), host),
+            None => "unknown".to_string(),
+        };
 
+        let provider = Arc::new(provider);
         Ok(Self {
-            provider: Arc::new(provider),
-            wallet,
+            ccip: CcipRead::new(Arc::clone(&provider)),
+            provider,
+            signer,
             chain_id,
+            rpc_endpoint,
+            chain_id_check: ChainIdCheck::default(),
+            sponsor: None,
+            simulation: None,
         })
     }
 
+    pub fn with_chain_id_check(mut self, chain_id_check: ChainIdCheck) -> Self {
+        self.chain_id_check = chain_id_check;
+        self
+    }
+
+    /// Simulate bundles on `rpc_url`, such as a fork or a node with `eth_simulateV1`,
+    /// instead of the main endpoint
+    pub fn with_simulation_rpc(mut self, rpc_url: &str) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(rpc_url)
+            .context("Failed to connect to the simulation RPC")?;
+        self.simulation = Some(Arc::new(provider));
+        Ok(self)
+    }
+
+    /// Have `sponsor` pay for the gas of the transactions it covers. Under a paymaster
+    /// the sponsor's smart account becomes the wallet address.
+    pub fn with_gas_sponsor(mut self, sponsor: GasSponsor) -> Self {
+        self.sponsor = Some(Arc::new(sponsor));
+        self
+    }
+
+    /// Compare the endpoint's `eth_chainId` with the configured chain, failing or
+    /// warning on mismatch according to the `ChainIdCheck` mode
+    pub async fn verify_chain_id(&self) -> Result<()> {
+        if self.chain_id_check == ChainIdCheck::Off {
+            return Ok(());
+        }
+        let remote = self
+            .provider
+            .get_chainid()
+            .await
+            .with_context(|| format!("Failed to get chain id from {}", self.rpc_endpoint))?;
+        if remote == U256::from(self.chain_id) {
+            return Ok(());
+        }
+
+        let mismatch = ChainIdMismatch {
+            endpoint: self.rpc_endpoint.clone(),
+            expected: self.chain_id,
+            actual: remote,
+        };
+        match self.chain_id_check {
+            ChainIdCheck::Strict => Err(mismatch.into()),
+            _ => {
+                tracing::warn!("{}", mismatch);
+                Ok(())
+            }
+        }
+    }
+
     pub fn get_provider(&self) -> Arc<Provider<Http>> {
         Arc::clone(&self.provider)
     }
 
-    pub fn get_wallet(&self) -> &LocalWallet {
-        &self.wallet
+    /// The local signing key; `None` when a WalletConnect wallet or KMS signs
+    pub fn get_wallet(&self) -> Option<&LocalWallet> {
+        match &self.signer {
+            TxSigner::Local(wallet) => Some(wallet),
+            TxSigner::WalletConnect(_) | TxSigner::Kms(_) => None,
+        }
+    }
+
+    fn wallet_address(&self) -> Address {
+        if let Some(account) = self.sponsor.as_ref().and_then(|sponsor| sponsor.account()) {
+            return account;
+        }
+        match &self.signer {
+            TxSigner::Local(wallet) => wallet.address(),
+            TxSigner::WalletConnect(signer) => signer.address(),
+            TxSigner::Kms(signer) => signer.address(),
+        }
+    }
+
+    pub fn get_chain_id(&self) -> u64 {
+        self.chain_id
     }
 
     /// Get ETH balance for an address
     pub async fn get_eth_balance(&self, address: Address) -> Result<Decimal> {
+        self.get_eth_balance_at(address, None).await
+    }
+
+    /// ETH balance of `address` at `block` (default: latest)
+    pub async fn get_eth_balance_at(
+        &self,
+        address: Address,
+        block: Option<u64>,
+    ) -> Result<Decimal> {
         let balance = self
             .provider
-            .get_balance(address, None)
+            .get_balance(address, block.map(|block| BlockId::from(U64::from(block))))
             .await
             .context("Failed to get ETH balance")?;
 
@@ -77,6 +440,17 @@ impl EthereumClient {
         &self,
         token_address: Address,
         wallet_address: Address,
+    ) -> Result<(Decimal, u8)> {
+        self.get_token_balance_at(token_address, wallet_address, None)
+            .await
+    }
+
+    /// ERC20 balance and decimals of `wallet_address` at `block` (default: latest)
+    pub async fn get_token_balance_at(
+        &self,
+        token_address: Address,
+        wallet_address: Address,
+        block: Option<u64>,
     ) -> Result<(Decimal, u8)> {
         // ERC20 ABI for balanceOf and decimals
         abigen!(
@@ -90,8 +464,11 @@ impl EthereumClient {
 
         let contract = ERC20::new(token_address, Arc::clone(&self.provider));
 
-        let balance: U256 = contract
-            .balance_of(wallet_address)
+        let mut balance_of = contract.balance_of(wallet_address);
+        if let Some(block) = block {
+            balance_of = balance_of.block(block);
+        }
+        let balance: U256 = balance_of
             .call()
             .await
             .context("Failed to get token balance")?;
@@ -127,6 +504,556 @@ impl EthereumClient {
 
         Ok(symbol)
     }
+
+    /// Get the latest block number
+    pub async fn get_block_number(&self) -> Result<u64> {
+        let block = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?;
+        Ok(block.as_u64())
+    }
+
+    /// Base fees of the `block_count` blocks up to and including `newest_block`
+    pub async fn get_base_fee_history(
+        &self,
+        newest_block: u64,
+        block_count: u64,
+    ) -> Result<BaseFeeHistory> {
+        let history = self
+            .provider
+            .fee_history(block_count, BlockNumber::Number(newest_block.into()), &[])
+            .await
+            .context("Failed to get fee history")?;
+        // One entry more than blocks: the fee of the block after the newest
+        let mut base_fees = history.base_fee_per_gas;
+        let next_base_fee = base_fees.pop().unwrap_or_default();
+        Ok(BaseFeeHistory {
+            oldest_block: history.oldest_block.as_u64(),
+            base_fees,
+            next_base_fee,
+            gas_used_ratios: history.gas_used_ratio,
+        })
+    }
+
+    /// Get the latest block's number and timestamp
+    pub async fn get_latest_block(&self) -> Result<BlockInfo> {
+        self.get_block(BlockNumber::Latest).await
+    }
+
+    /// Number and timestamp of the block `tag` names, e.g. the safe or finalized head
+    pub async fn get_block(&self, tag: BlockNumber) -> Result<BlockInfo> {
+        let block = self
+            .provider
+            .get_block(tag)
+            .await
+            .with_context(|| format!("Failed to get {} block", tag))?
+            .with_context(|| format!("{} block not found", tag))?;
+        Ok(BlockInfo {
+            number: block.number.unwrap_or_default().as_u64(),
+            timestamp: block.timestamp.as_u64(),
+        })
+    }
+
+    /// Get the current gas price in wei
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        self.provider
+            .get_gas_price()
+            .await
+            .context("Failed to get gas price")
+    }
+
+    /// Get token decimals
+    pub async fn get_token_decimals(&self, token_address: Address) -> Result<u8> {
+        abigen!(
+            ERC20,
+            r#"[
+                function decimals() external view returns (uint8)
+            ]"#
+        );
+
+        let contract = ERC20::new(token_address, Arc::clone(&self.provider));
+        let decimals = contract
+            .decimals()
+            .call()
+            .await
+            .context("Failed to get token decimals")?;
+
+        Ok(decimals)
+    }
+
+    /// Check `owner`'s allowance for `spender` and estimate the approve needed to spend `amount`
+    pub async fn simulate_approval(
+        &self,
+        token_address: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<ApprovalSimulation> {
+        abigen!(
+            ERC20,
+            r#"[
+                function allowance(address owner, address spender) external view returns (uint256)
+                function approve(address spender, uint256 amount) external returns (bool)
+                function nonces(address owner) external view returns (uint256)
+                function DOMAIN_SEPARATOR() external view returns (bytes32)
+            ]"#
+        );
+
+        let contract = ERC20::new(token_address, Arc::clone(&self.provider));
+        let current_allowance = contract
+            .allowance(owner, spender)
+            .call()
+            .await
+            .context("Failed to get token allowance")?;
+
+        let approval_required = current_allowance < amount;
+        let gas_estimate = if approval_required {
+            contract
+                .approve(spender, amount)
+                .from(owner)
+                .estimate_gas()
+                .await
+                .unwrap_or(U256::from(DEFAULT_APPROVE_GAS))
+        } else {
+            U256::zero()
+        };
+
+        let supports_permit = contract.nonces(owner).call().await.is_ok()
+            && contract.domain_separator().call().await.is_ok();
+
+        Ok(ApprovalSimulation {
+            current_allowance,
+            approval_required,
+            gas_estimate,
+            supports_permit,
+        })
+    }
+
+    /// Estimate gas for a transaction without sending it
+    pub async fn estimate_transaction_gas(&self, tx: &TypedTransaction) -> Result<U256> {
+        self.provider
+            .estimate_gas(tx, None)
+            .await
+            .context("Failed to estimate gas")
+    }
+
+    /// Sign a transaction with the configured wallet, send it and wait for the receipt
+    pub async fn send_transaction(
+        &self,
+        tx: TypedTransaction,
+        escalation: Option<GasEscalation>,
+    ) -> Result<TransactionOutcome> {
+        // The endpoint may have changed behind a load balancer since startup
+        self.verify_chain_id().await?;
+
+        let wallet = match &self.signer {
+            TxSigner::Local(wallet) => wallet,
+            TxSigner::WalletConnect(signer) => {
+                if escalation.is_some() {
+                    tracing::warn!(
+                        "Fee escalation is off over WalletConnect: every bump would need another approval"
+                    );
+                }
+                return self.send_through_wallet(signer, tx).await;
+            }
+            TxSigner::Kms(signer) => return self.send_signed(signer.clone(), tx, escalation).await,
+        };
+        if let Some(sponsor) = &self.sponsor {
+            if sponsor.covers(&tx).await {
+                if escalation.is_some() {
+                    tracing::warn!(
+                        "Fee escalation is off for sponsored transactions: the sponsor sets the fees"
+                    );
+                }
+                return sponsor.send(wallet, tx).await;
+            }
+        }
+        self.send_signed(wallet.clone(), tx, escalation).await
+    }
+
+    /// Sign `tx` with `signer`, send it and wait for the receipt
+    async fn send_signed<S: Signer + 'static>(
+        &self,
+        signer: S,
+        tx: TypedTransaction,
+        escalation: Option<GasEscalation>,
+    ) -> Result<TransactionOutcome> {
+        let signer = SignerMiddleware::new(Arc::clone(&self.provider), signer);
+        if let Some(policy) = escalation {
+            return self.send_with_escalation(&signer, tx, policy).await;
+        }
+
+        let pending = signer
+            .send_transaction(tx, None)
+            .await
+            .context("Failed to send transaction")?;
+        let tx_hash = pending.tx_hash();
+        let receipt = pending
+            .await
+            .context("Failed to wait for transaction receipt")?
+            .with_context(|| format!("Transaction {:?} was dropped", tx_hash))?;
+
+        Ok(TransactionOutcome {
+            tx_hash,
+            success: receipt.status == Some(U64::one()),
+            gas_used: receipt.gas_used.unwrap_or_default(),
+            block_number: receipt.block_number.map(|block| block.as_u64()),
+            effective_gas_price: receipt.effective_gas_price,
+            replacements: Vec::new(),
+            sponsorship: None,
+        })
+    }
+
+    /// Fill `tx` and have the paired wallet sign and send it, then wait for the receipt
+    async fn send_through_wallet(
+        &self,
+        signer: &WalletConnectSigner,
+        mut tx: TypedTransaction,
+    ) -> Result<TransactionOutcome> {
+        tx.set_from(signer.address());
+        self.provider
+            .fill_transaction(&mut tx, None)
+            .await
+            .context("Failed to prepare transaction")?;
+        let tx_hash = signer.send_transaction(&tx).await?;
+        let receipt = ethers::providers::PendingTransaction::new(tx_hash, self.provider.as_ref())
+            .await
+            .context("Failed to wait for transaction receipt")?
+            .with_context(|| format!("Transaction {:?} was dropped", tx_hash))?;
+
+        Ok(TransactionOutcome {
+            tx_hash,
+            success: receipt.status == Some(U64::one()),
+            gas_used: receipt.gas_used.unwrap_or_default(),
+            block_number: receipt.block_number.map(|block| block.as_u64()),
+            effective_gas_price: receipt.effective_gas_price,
+            replacements: Vec::new(),
+            sponsorship: None,
+        })
+    }
+
+    /// Suggested EIP-1559 fees as `(max_fee_per_gas, max_priority_fee_per_gas)`
+    pub async fn estimate_fees(&self) -> Result<(U256, U256)> {
+        self.provider
+            .estimate_eip1559_fees(None)
+            .await
+            .context("Failed to estimate fees")
+    }
+
+    /// Broadcast a transaction signed elsewhere, returning its hash without waiting
+    pub async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256> {
+        self.verify_chain_id().await?;
+        let pending = self
+            .provider
+            .send_raw_transaction(raw)
+            .await
+            .context("Failed to broadcast signed transaction")?;
+        Ok(pending.tx_hash())
+    }
+
+    /// Nonces of `address` as of the latest block and including pending transactions
+    pub async fn get_nonces(&self, address: Address) -> Result<(u64, u64)> {
+        let latest = self
+            .provider
+            .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+            .await
+            .context("Failed to get latest nonce")?;
+        let pending = self
+            .provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .context("Failed to get pending nonce")?;
+        Ok((latest.as_u64(), pending.as_u64()))
+    }
+
+    /// Whether contract code is deployed at `address`
+    pub async fn has_code(&self, address: Address) -> Result<bool> {
+        let code = self
+            .provider
+            .get_code(address, None)
+            .await
+            .context("Failed to get contract code")?;
+        Ok(!code.is_empty())
+    }
+
+    /// Deployed code of `address` at `block` (default: latest)
+    pub async fn get_code(&self, address: Address, block: Option<u64>) -> Result<Bytes> {
+        self.provider
+            .get_code(address, block.map(|block| BlockId::from(U64::from(block))))
+            .await
+            .context("Failed to get contract code")
+    }
+
+    /// Raw storage word at `slot` of `address`, at `block` (default: latest)
+    pub async fn get_storage(
+        &self,
+        address: Address,
+        slot: H256,
+        block: Option<u64>,
+    ) -> Result<H256> {
+        self.provider
+            .get_storage_at(
+                address,
+                slot,
+                block.map(|block| BlockId::from(U64::from(block))),
+            )
+            .await
+            .context("Failed to read storage")
+    }
+
+    /// `account`'s delegate and votes on an ERC20Votes (or Compound-style) governance token
+    pub async fn get_delegation(
+        &self,
+        token_address: Address,
+        account: Address,
+    ) -> Result<Delegation> {
+        abigen!(
+            ERC20Votes,
+            r#"[
+                function delegates(address account) external view returns (address)
+                function getVotes(address account) external view returns (uint256)
+                function getCurrentVotes(address account) external view returns (uint96)
+                function decimals() external view returns (uint8)
+            ]"#
+        );
+
+        let contract = ERC20Votes::new(token_address, Arc::clone(&self.provider));
+        let delegate = contract
+            .delegates(account)
+            .call()
+            .await
+            .context("Failed to get delegate; is this an ERC20Votes token?")?;
+        let votes = match contract.get_votes(account).call().await {
+            Ok(votes) => votes,
+            Err(_) => U256::from(
+                contract
+                    .get_current_votes(account)
+                    .call()
+                    .await
+                    .context("Failed to get votes")?,
+            ),
+        };
+        let decimals = contract
+            .decimals()
+            .call()
+            .await
+            .context("Failed to get token decimals")?;
+
+        Ok(Delegation {
+            delegate: Some(delegate).filter(|delegate| !delegate.is_zero()),
+            votes: Decimal::from_str(&votes.to_string())?
+                / Decimal::from(10u64.pow(decimals as u32)),
+        })
+    }
+
+    /// Mempool transactions sent by `address`, via geth's `txpool_contentFrom`
+    pub async fn get_pending_transactions(
+        &self,
+        address: Address,
+    ) -> Result<Option<Vec<PendingTransaction>>> {
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        struct TxpoolContentFrom {
+            #[serde(default)]
+            pending: std::collections::BTreeMap<String, Transaction>,
+            #[serde(default)]
+            queued: std::collections::BTreeMap<String, Transaction>,
+        }
+
+        // Most hosted endpoints do not expose the txpool namespace
+        let content: TxpoolContentFrom =
+            match self.provider.request("txpool_contentFrom", [address]).await {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::debug!("txpool_contentFrom unavailable: {}", e);
+                    return Ok(None);
+                }
+            };
+
+        let to_pending = |tx: Transaction, queued: bool| PendingTransaction {
+            hash: tx.hash,
+            nonce: tx.nonce.as_u64(),
+            to: tx.to,
+            value: tx.value,
+            input: tx.input,
+            max_fee_per_gas: tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default(),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            queued,
+        };
+        let mut transactions: Vec<_> = content
+            .pending
+            .into_values()
+            .map(|tx| to_pending(tx, false))
+            .chain(content.queued.into_values().map(|tx| to_pending(tx, true)))
+            .collect();
+        transactions.sort_by_key(|tx| tx.nonce);
+        Ok(Some(transactions))
+    }
+
+    /// Run `txs` in order with `eth_simulateV1`, with ETH transfers traced as logs.
+    /// Validation is off, so nonces, fees and the senders' ETH for gas are not checked.
+    pub async fn simulate_bundle(&self, txs: &[TypedTransaction]) -> Result<Vec<SimulatedCall>> {
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        struct SimulatedBlock {
+            calls: Vec<CallResult>,
+        }
+
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CallResult {
+            status: U64,
+            gas_used: U256,
+            #[serde(default)]
+            return_data: Bytes,
+            #[serde(default)]
+            logs: Vec<Log>,
+            #[serde(default)]
+            error: Option<CallError>,
+        }
+
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        struct CallError {
+            message: String,
+        }
+
+        let calls: Vec<serde_json::Value> = txs
+            .iter()
+            .map(|tx| {
+                let mut call = serde_json::json!({
+                    "value": tx.value().copied().unwrap_or_default(),
+                    "input": tx.data().cloned().unwrap_or_default(),
+                });
+                if let Some(from) = tx.from() {
+                    call["from"] = serde_json::json!(from);
+                }
+                if let Some(to) = tx.to_addr() {
+                    call["to"] = serde_json::json!(to);
+                }
+                if let Some(gas) = tx.gas() {
+                    call["gas"] = serde_json::json!(gas);
+                }
+                call
+            })
+            .collect();
+        let request = serde_json::json!({
+            "blockStateCalls": [{ "calls": calls }],
+            "traceTransfers": true,
+            "validation": false,
+        });
+        let provider = self.simulation.as_ref().unwrap_or(&self.provider);
+        let blocks: Vec<SimulatedBlock> = provider
+            .request("eth_simulateV1", (request, BlockNumber::Latest))
+            .await
+            .context(
+                "Failed to simulate the bundle; the endpoint must support eth_simulateV1 (see SIMULATION_RPC_URL)",
+            )?;
+        let block = blocks
+            .into_iter()
+            .next()
+            .context("The simulation returned no block")?;
+
+        Ok(block
+            .calls
+            .into_iter()
+            .map(|call| SimulatedCall {
+                success: call.status.as_u64() == 1,
+                gas_used: call.gas_used,
+                return_data: call.return_data,
+                error: call.error.map(|error| error.message),
+                logs: call.logs,
+            })
+            .collect())
+    }
+
+    /// Send `tx`, replacing it at the same nonce with higher fees every
+    /// `after_blocks` blocks until one of the versions is mined
+    async fn send_with_escalation<S: Signer + 'static>(
+        &self,
+        signer: &SignerMiddleware<Arc<Provider<Http>>, S>,
+        mut tx: TypedTransaction,
+        policy: GasEscalation,
+    ) -> Result<TransactionOutcome> {
+        signer
+            .fill_transaction(&mut tx, None)
+            .await
+            .context("Failed to prepare transaction")?;
+        let nonce = tx.nonce().copied().unwrap_or_default();
+
+        let mut hashes = vec![signer
+            .send_transaction(tx.clone(), None)
+            .await
+            .context("Failed to send transaction")?
+            .tx_hash()];
+        let mut replacements = Vec::new();
+        let mut last_sent_block = self.get_block_number().await?;
+
+        loop {
+            // Any of the versions can be the one that lands
+            for hash in hashes.iter().rev() {
+                let receipt = self
+                    .provider
+                    .get_transaction_receipt(*hash)
+                    .await
+                    .context("Failed to fetch transaction receipt")?;
+                if let Some(receipt) = receipt {
+                    return Ok(TransactionOutcome {
+                        tx_hash: *hash,
+                        success: receipt.status == Some(U64::one()),
+                        gas_used: receipt.gas_used.unwrap_or_default(),
+                        block_number: receipt.block_number.map(|block| block.as_u64()),
+                        effective_gas_price: receipt.effective_gas_price,
+                        replacements,
+                        sponsorship: None,
+                    });
+                }
+            }
+
+            let mined_nonce = self
+                .provider
+                .get_transaction_count(self.wallet_address(), None)
+                .await
+                .context("Failed to fetch account nonce")?;
+            if mined_nonce > nonce {
+                anyhow::bail!(
+                    "Nonce {} was used by a transaction other than {:?}",
+                    nonce,
+                    hashes
+                );
+            }
+
+            let block = self.get_block_number().await?;
+            if block >= last_sent_block + policy.after_blocks {
+                let mut replacement = tx.clone();
+                if policy.bump(&mut replacement) {
+                    match signer.send_transaction(replacement.clone(), None).await {
+                        Ok(pending) => {
+                            let fee = GasEscalation::current_fee(&replacement);
+                            tracing::warn!(
+                                "Transaction {:?} not mined after {} blocks; replaced by {:?} at {} wei/gas",
+                                hashes.last().unwrap(),
+                                policy.after_blocks,
+                                pending.tx_hash(),
+                                fee
+                            );
+                            hashes.push(pending.tx_hash());
+                            replacements.push(FeeReplacement {
+                                tx_hash: pending.tx_hash(),
+                                max_fee_per_gas: fee,
+                                block_number: block,
+                            });
+                            tx = replacement;
+                        }
+                        // Usually "replacement underpriced" near the ceiling; keep waiting
+                        Err(e) => tracing::warn!("Fee replacement rejected: {}", e),
+                    }
+                }
+                last_sent_block = block;
+            }
+
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
 }
 
 #[async_trait]
@@ -147,7 +1074,135 @@ impl EthereumClientTrait for EthereumClient {
         self.get_token_symbol(token_address).await
     }
 
+    async fn get_token_decimals(&self, token_address: Address) -> Result<u8> {
+        self.get_token_decimals(token_address).await
+    }
+
+    async fn get_block_number(&self) -> Result<u64> {
+        self.get_block_number().await
+    }
+
+    async fn get_latest_block(&self) -> Result<BlockInfo> {
+        self.get_latest_block().await
+    }
+
+    async fn get_block(&self, tag: BlockNumber) -> Result<BlockInfo> {
+        self.get_block(tag).await
+    }
+
+    async fn get_eth_balance_at(&self, address: Address, block: Option<u64>) -> Result<Decimal> {
+        self.get_eth_balance_at(address, block).await
+    }
+
+    async fn get_token_balance_at(
+        &self,
+        token_address: Address,
+        wallet_address: Address,
+        block: Option<u64>,
+    ) -> Result<(Decimal, u8)> {
+        self.get_token_balance_at(token_address, wallet_address, block)
+            .await
+    }
+
+    async fn get_base_fee_history(
+        &self,
+        newest_block: u64,
+        block_count: u64,
+    ) -> Result<BaseFeeHistory> {
+        self.get_base_fee_history(newest_block, block_count).await
+    }
+
+    async fn get_gas_price(&self) -> Result<U256> {
+        self.get_gas_price().await
+    }
+
+    async fn simulate_approval(
+        &self,
+        token_address: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<ApprovalSimulation> {
+        self.simulate_approval(token_address, owner, spender, amount)
+            .await
+    }
+
+    async fn estimate_transaction_gas(&self, tx: &TypedTransaction) -> Result<U256> {
+        self.estimate_transaction_gas(tx).await
+    }
+
+    async fn send_transaction(
+        &self,
+        tx: TypedTransaction,
+        escalation: Option<GasEscalation>,
+    ) -> Result<TransactionOutcome> {
+        self.send_transaction(tx, escalation).await
+    }
+
+    async fn gas_sponsored(&self, tx: &TypedTransaction) -> bool {
+        match (&self.sponsor, &self.signer) {
+            (Some(sponsor), TxSigner::Local(_)) => sponsor.covers(tx).await,
+            _ => false,
+        }
+    }
+
+    async fn estimate_fees(&self) -> Result<(U256, U256)> {
+        self.estimate_fees().await
+    }
+
+    async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256> {
+        self.send_raw_transaction(raw).await
+    }
+
+    fn get_chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    async fn get_nonces(&self, address: Address) -> Result<(u64, u64)> {
+        self.get_nonces(address).await
+    }
+
+    async fn get_pending_transactions(
+        &self,
+        address: Address,
+    ) -> Result<Option<Vec<PendingTransaction>>> {
+        self.get_pending_transactions(address).await
+    }
+
+    async fn has_code(&self, address: Address) -> Result<bool> {
+        self.has_code(address).await
+    }
+
+    async fn get_delegation(&self, token_address: Address, account: Address) -> Result<Delegation> {
+        self.get_delegation(token_address, account).await
+    }
+
+    async fn get_code(&self, address: Address, block: Option<u64>) -> Result<Bytes> {
+        self.get_code(address, block).await
+    }
+
+    async fn get_storage(&self, address: Address, slot: H256, block: Option<u64>) -> Result<H256> {
+        self.get_storage(address, slot, block).await
+    }
+
+    async fn call_contract(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        overrides: Option<&spoof::State>,
+    ) -> Result<Bytes> {
+        self.ccip.call_with(tx, block, overrides).await
+    }
+
+    async fn simulate_bundle(&self, txs: &[TypedTransaction]) -> Result<Vec<SimulatedCall>> {
+        self.simulate_bundle(txs).await
+    }
+
     fn get_wallet_address(&self) -> Address {
-        self.wallet.address()
+        self.wallet_address()
+    }
+
+    fn rpc_endpoint(&self) -> String {
+        self.rpc_endpoint.clone()
     }
 }
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::sync::Arc;
+
+// EigenLayer core contracts on Ethereum mainnet
+pub const EIGENLAYER_STRATEGY_MANAGER: &str = "0x858646372CC42E1A627fcE94aa7A7033e7CF075A";
+pub const EIGENLAYER_DELEGATION_MANAGER: &str = "0x39053D51B77DC0d36036Fc1fCc8Cb819df8Ef37A";
+pub const EIGENLAYER_EIGENPOD_MANAGER: &str = "0x91E677b07F7AF907ec9a428aafA9fc14a0d3A338";
+/// Pseudo-strategy holding natively restaked ETH, one share per wei
+pub const BEACON_CHAIN_ETH_STRATEGY: &str = "0xbeaC0eeEeeeeEEeEeEEEEeeEEeEeeeEeeEEBEaC0";
+
+abigen!(
+    IStrategyManager,
+    r#"[
+        function getDeposits(address staker) external view returns (address[] strategies, uint256[] shares)
+    ]"#
+);
+
+abigen!(
+    IDelegationManager,
+    r#"[
+        struct Withdrawal { address staker; address delegatedTo; address withdrawer; uint256 nonce; uint32 startBlock; address[] strategies; uint256[] scaledShares; }
+        function delegatedTo(address staker) external view returns (address)
+        function getWithdrawableShares(address staker, address[] strategies) external view returns (uint256[] withdrawableShares, uint256[] depositShares)
+        function getQueuedWithdrawals(address staker) external view returns (Withdrawal[] withdrawals, uint256[][] shares)
+        function minWithdrawalDelayBlocks() external view returns (uint32)
+    ]"#
+);
+
+abigen!(
+    IEigenPodManager,
+    r#"[
+        function ownerToPod(address podOwner) external view returns (address)
+        function podOwnerDepositShares(address podOwner) external view returns (int256)
+    ]"#
+);
+
+abigen!(
+    IStrategy,
+    r#"[
+        function underlyingToken() external view returns (address)
+        function sharesToUnderlyingView(uint256 amountShares) external view returns (uint256)
+    ]"#
+);
+
+/// Shares of one strategy and what they redeem for
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyShares {
+    pub strategy: Address,
+    /// Token the strategy holds; None for natively restaked ETH
+    pub token: Option<Address>,
+    /// Shares that can be withdrawn, after any slashing
+    pub shares: U256,
+    /// Base units of the token the shares redeem for
+    pub underlying: U256,
+}
+
+/// A strategy deposit, and how much of it slashing has taken
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestakedDeposit {
+    pub withdrawable: StrategyShares,
+    /// Shares deposited, before slashing
+    pub deposit_shares: U256,
+}
+
+/// A withdrawal queued out of EigenLayer, completable after the withdrawal delay
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedWithdrawal {
+    pub withdrawer: Address,
+    /// Operator the shares were delegated to when queued
+    pub delegated_to: Option<Address>,
+    pub start_block: u64,
+    pub strategies: Vec<StrategyShares>,
+}
+
+/// A staker's EigenLayer deposits, delegation and pending withdrawals
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestakingPosition {
+    pub deposits: Vec<RestakedDeposit>,
+    /// Operator the staker delegates to
+    pub operator: Option<Address>,
+    /// EigenPod natively restaked validators withdraw to
+    pub eigen_pod: Option<Address>,
+    pub queued_withdrawals: Vec<QueuedWithdrawal>,
+    /// Blocks a queued withdrawal waits before it can be completed
+    pub withdrawal_delay_blocks: u64,
+}
+
+/// A restaking protocol whose positions can be read by staker
+#[async_trait]
+pub trait RestakingSource: Send + Sync {
+    /// Short identifier such as `eigenlayer`
+    fn name(&self) -> &str;
+
+    async fn position(&self, staker: Address) -> Result<RestakingPosition>;
+}
+
+/// Positions read from EigenLayer's strategy, delegation and EigenPod managers
+pub struct EigenLayerContracts {
+    provider: Arc<Provider<Http>>,
+    strategies: IStrategyManager<Provider<Http>>,
+    delegation: IDelegationManager<Provider<Http>>,
+    pods: IEigenPodManager<Provider<Http>>,
+    beacon_strategy: Address,
+}
+
+impl EigenLayerContracts {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        let address = |address: &str, name: &str| {
+            address
+                .parse::<Address>()
+                .with_context(|| format!("Invalid EigenLayer {} address", name))
+        };
+        Ok(Self {
+            strategies: IStrategyManager::new(
+                address(EIGENLAYER_STRATEGY_MANAGER, "strategy manager")?,
+                Arc::clone(&provider),
+            ),
+            delegation: IDelegationManager::new(
+                address(EIGENLAYER_DELEGATION_MANAGER, "delegation manager")?,
+                Arc::clone(&provider),
+            ),
+            pods: IEigenPodManager::new(
+                address(EIGENLAYER_EIGENPOD_MANAGER, "EigenPod manager")?,
+                Arc::clone(&provider),
+            ),
+            beacon_strategy: address(BEACON_CHAIN_ETH_STRATEGY, "beacon chain strategy")?,
+            provider,
+        })
+    }
+
+    /// What `shares` of `strategy` redeem for, and in which token
+    async fn redeem(&self, strategy: Address, shares: U256) -> Result<StrategyShares> {
+        if strategy == self.beacon_strategy {
+            return Ok(StrategyShares {
+                strategy,
+                token: None,
+                shares,
+                underlying: shares,
+            });
+        }
+        let contract = IStrategy::new(strategy, Arc::clone(&self.provider));
+        let token = contract
+            .underlying_token()
+            .call()
+            .await
+            .with_context(|| format!("Failed to read the token of strategy {:?}", strategy))?;
+        let underlying = contract
+            .shares_to_underlying_view(shares)
+            .call()
+            .await
+            .with_context(|| format!("Failed to value shares of strategy {:?}", strategy))?;
+        Ok(StrategyShares {
+            strategy,
+            token: Some(token),
+            shares,
+            underlying,
+        })
+    }
+}
+
+fn non_zero(address: Address) -> Option<Address> {
+    (!address.is_zero()).then_some(address)
+}
+
+#[async_trait]
+impl RestakingSource for EigenLayerContracts {
+    fn name(&self) -> &str {
+        "eigenlayer"
+    }
+
+    async fn position(&self, staker: Address) -> Result<RestakingPosition> {
+        let (mut strategies, _) = self
+            .strategies
+            .get_deposits(staker)
+            .call()
+            .await
+            .context("Failed to read EigenLayer deposits")?;
+        let pod_shares = self
+            .pods
+            .pod_owner_deposit_shares(staker)
+            .call()
+            .await
+            .context("Failed to read restaked ETH shares")?;
+        if pod_shares > I256::zero() {
+            strategies.push(self.beacon_strategy);
+        }
+
+        let mut deposits = Vec::new();
+        if !strategies.is_empty() {
+            let (withdrawable, deposited) = self
+                .delegation
+                .get_withdrawable_shares(staker, strategies.clone())
+                .call()
+                .await
+                .context("Failed to read withdrawable shares")?;
+            for ((strategy, withdrawable), deposit_shares) in
+                strategies.into_iter().zip(withdrawable).zip(deposited)
+            {
+                deposits.push(RestakedDeposit {
+                    withdrawable: self.redeem(strategy, withdrawable).await?,
+                    deposit_shares,
+                });
+            }
+        }
+
+        let operator = self
+            .delegation
+            .delegated_to(staker)
+            .call()
+            .await
+            .context("Failed to read the delegated operator")?;
+        let eigen_pod = self
+            .pods
+            .owner_to_pod(staker)
+            .call()
+            .await
+            .context("Failed to read the EigenPod")?;
+
+        let (withdrawals, shares) = self
+            .delegation
+            .get_queued_withdrawals(staker)
+            .call()
+            .await
+            .context("Failed to read queued withdrawals")?;
+        let mut queued_withdrawals = Vec::new();
+        for ((_, delegated_to, withdrawer, _, start_block, withdrawn, _), shares) in
+            withdrawals.into_iter().zip(shares)
+        {
+            let mut strategies = Vec::new();
+            for (strategy, shares) in withdrawn.into_iter().zip(shares) {
+                strategies.push(self.redeem(strategy, shares).await?);
+            }
+            queued_withdrawals.push(QueuedWithdrawal {
+                withdrawer,
+                delegated_to: non_zero(delegated_to),
+                start_block: start_block as u64,
+                strategies,
+            });
+        }
+        let withdrawal_delay_blocks = self
+            .delegation
+            .min_withdrawal_delay_blocks()
+            .call()
+            .await
+            .context("Failed to read the withdrawal delay")?;
+
+        Ok(RestakingPosition {
+            deposits,
+            operator: non_zero(operator),
+            eigen_pod: non_zero(eigen_pod),
+            queued_withdrawals,
+            withdrawal_delay_blocks: withdrawal_delay_blocks as u64,
+        })
+    }
+}
@@ -0,0 +1,543 @@
+use crate::ethereum::ccip::CcipRead;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::{self, AbiDecode, AbiEncode, ParamType, Token};
+use ethers::prelude::*;
+use ethers::utils::{hex, keccak256};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// ENS contracts on Ethereum mainnet
+const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+const ENS_BASE_REGISTRAR: &str = "0x57f1887a8BF19b14fC0dF6Fd9B2acc9Af147eA85";
+const ENS_NAME_WRAPPER: &str = "0xD4416b13d2b3a9aBae7AcD5D6C2BbDBE25686401";
+const ENS_LEGACY_CONTROLLER: &str = "0x283Af0B28c62C092C9727F1Ee09c02CA627EB7F5";
+
+/// The .eth registrar controller that registers and renews names
+pub const ENS_ETH_REGISTRAR_CONTROLLER: &str = "0x253553366Da8546fC250F225fe3d25d0C782303b";
+
+/// How long an expired .eth name stays renewable by anyone before it is released
+pub const ENS_GRACE_PERIOD_SECS: u64 = 90 * 86_400;
+
+// Registrar and name wrapper deployments; their whole transfer history is searched
+const BASE_REGISTRAR_DEPLOY_BLOCK: u64 = 9_380_410;
+const NAME_WRAPPER_DEPLOY_BLOCK: u64 = 16_925_608;
+
+// ENSIP-10 wildcard resolvers answer `resolve(name, data)` for names below them
+const EXTENDED_RESOLVER_INTERFACE: [u8; 4] = [0x90, 0x61, 0xb9, 0x23];
+
+// Bounds the names one query reads back
+const MAX_NAMES_PER_QUERY: usize = 100;
+
+abigen!(
+    IEnsRegistry,
+    r#"[
+        function owner(bytes32 node) external view returns (address)
+        function resolver(bytes32 node) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IEnsResolver,
+    r#"[
+        function addr(bytes32 node) external view returns (address)
+        function name(bytes32 node) external view returns (string)
+        function resolve(bytes name, bytes data) external view returns (bytes)
+        function supportsInterface(bytes4 interfaceID) external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    IEnsBaseRegistrar,
+    r#"[
+        function nameExpires(uint256 id) external view returns (uint256)
+        function ownerOf(uint256 tokenId) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IEnsNameWrapper,
+    r#"[
+        function ownerOf(uint256 id) external view returns (address)
+        function names(bytes32 node) external view returns (bytes)
+    ]"#
+);
+
+abigen!(
+    IEnsController,
+    r#"[
+        struct Price { uint256 base; uint256 premium; }
+        function rentPrice(string name, uint256 duration) external view returns (Price price)
+    ]"#
+);
+
+/// Ownership and registration of an ENS name
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EnsName {
+    /// Normalized name; labels that could not be recovered read `[labelhash]`
+    pub name: String,
+    pub node: H256,
+    /// Controls the name's records: the registry owner, or the wrapped name's owner
+    pub owner: Option<Address>,
+    /// Holds the .eth registration, and with it the right to reclaim the name
+    pub registrant: Option<Address>,
+    /// Resolver serving the name: its own, or a wildcard resolver of a parent
+    pub resolver: Option<Address>,
+    /// Address the name resolves to, fetched through CCIP-Read for off-chain resolvers
+    pub address: Option<Address>,
+    /// Registration expiry of a .eth second-level name; subnames do not expire here
+    pub expires: Option<u64>,
+    /// Held by the name wrapper as an ERC1155 token
+    pub wrapped: bool,
+}
+
+impl EnsName {
+    /// End of the grace period after expiry, when the name is released
+    pub fn grace_ends(&self) -> Option<u64> {
+        self.expires
+            .filter(|expires| *expires > 0)
+            .map(|expires| expires.saturating_add(ENS_GRACE_PERIOD_SECS))
+    }
+}
+
+/// Lowercase `name`, taking a bare label as a .eth name. Only ASCII is case-folded;
+/// names needing full UTS-46 normalization must be passed already normalized.
+pub fn normalize_name(name: &str) -> Result<String> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    if name.is_empty() {
+        anyhow::bail!("ENS name is empty");
+    }
+    if name.split('.').any(|label| label.is_empty()) {
+        anyhow::bail!("Invalid ENS name: {}", name);
+    }
+    if name.chars().any(char::is_whitespace) {
+        anyhow::bail!("ENS names cannot contain whitespace: {}", name);
+    }
+    Ok(if name.contains('.') {
+        name
+    } else {
+        format!("{}.eth", name)
+    })
+}
+
+/// The label of a second-level .eth name, the part registered with the .eth registrar
+pub fn eth_label(name: &str) -> Option<&str> {
+    name.strip_suffix(".eth")
+        .filter(|label| !label.is_empty() && !label.contains('.'))
+}
+
+pub fn labelhash(label: &str) -> H256 {
+    H256::from(keccak256(label.as_bytes()))
+}
+
+/// EIP-137 namehash of a normalized name
+pub fn namehash(name: &str) -> H256 {
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(H256::zero(), |node, label| {
+            child_node(node, labelhash(label))
+        })
+}
+
+fn child_node(parent: H256, label: H256) -> H256 {
+    let mut packed = [0u8; 64];
+    packed[..32].copy_from_slice(parent.as_bytes());
+    packed[32..].copy_from_slice(label.as_bytes());
+    H256::from(keccak256(packed))
+}
+
+/// A name in the DNS wire format the name wrapper stores: length-prefixed labels
+fn decode_dns_name(encoded: &[u8]) -> Option<String> {
+    let mut labels = Vec::new();
+    let mut rest = encoded;
+    loop {
+        let (&len, tail) = rest.split_first()?;
+        if len == 0 {
+            break;
+        }
+        let label = tail.get(..len as usize)?;
+        labels.push(String::from_utf8(label.to_vec()).ok()?);
+        rest = &tail[len as usize..];
+    }
+    (!labels.is_empty()).then(|| labels.join("."))
+}
+
+/// Encode a name in DNS wire format, as `resolve` takes it
+fn encode_dns_name(name: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(name.len() + 2);
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        encoded.push(label.len().min(255) as u8);
+        encoded.extend(&label.as_bytes()[..label.len().min(255)]);
+    }
+    encoded.push(0);
+    encoded
+}
+
+/// Reads ENS names and .eth registrations
+#[async_trait]
+pub trait EnsSource: Send + Sync {
+    /// Ownership and expiry of a normalized name, registered or not
+    async fn lookup(&self, name: &str) -> Result<EnsName>;
+
+    /// Names `owner` holds: .eth registrations and wrapped names
+    async fn names_owned_by(&self, owner: Address) -> Result<Vec<EnsName>>;
+
+    /// The name `address` has set as its reverse record, if any
+    async fn primary_name(&self, address: Address) -> Result<Option<String>>;
+
+    /// Wei the controller charges to renew a .eth `label` for `duration_secs`
+    async fn rent_price(&self, label: &str, duration_secs: u64) -> Result<U256>;
+}
+
+/// ENS registry, .eth registrar and name wrapper on Ethereum mainnet
+pub struct EnsContracts {
+    provider: Arc<Provider<Http>>,
+    ccip: CcipRead,
+    registry: IEnsRegistry<Provider<Http>>,
+    registrar: IEnsBaseRegistrar<Provider<Http>>,
+    wrapper: IEnsNameWrapper<Provider<Http>>,
+    controller: IEnsController<Provider<Http>>,
+}
+
+impl EnsContracts {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            registry: IEnsRegistry::new(
+                ENS_REGISTRY
+                    .parse::<Address>()
+                    .context("Invalid ENS registry address")?,
+                Arc::clone(&provider),
+            ),
+            registrar: IEnsBaseRegistrar::new(
+                ENS_BASE_REGISTRAR
+                    .parse::<Address>()
+                    .context("Invalid ENS base registrar address")?,
+                Arc::clone(&provider),
+            ),
+            wrapper: IEnsNameWrapper::new(
+                ENS_NAME_WRAPPER
+                    .parse::<Address>()
+                    .context("Invalid ENS name wrapper address")?,
+                Arc::clone(&provider),
+            ),
+            controller: IEnsController::new(
+                ENS_ETH_REGISTRAR_CONTROLLER
+                    .parse::<Address>()
+                    .context("Invalid ENS controller address")?,
+                Arc::clone(&provider),
+            ),
+            ccip: CcipRead::new(Arc::clone(&provider)),
+            provider,
+        })
+    }
+
+    /// The resolver for `name` per ENSIP-10: its own, else the closest parent's, flagged
+    /// `false` as it must then be asked through `resolve`
+    async fn find_resolver(&self, name: &str, node: H256) -> Result<Option<(Address, bool)>> {
+        let mut node = node;
+        let mut rest = name;
+        let mut exact = true;
+        loop {
+            let resolver = self
+                .registry
+                .resolver(node.0)
+                .call()
+                .await
+                .context("Failed to read ENS resolver")?;
+            if !resolver.is_zero() {
+                return Ok(Some((resolver, exact)));
+            }
+            let Some((_, parent)) = rest.split_once('.') else {
+                return Ok(None);
+            };
+            rest = parent;
+            node = namehash(parent);
+            exact = false;
+        }
+    }
+
+    /// The address `resolver` gives `name`, following off-chain lookups. Resolvers that
+    /// revert or whose gateway fails leave the name unresolved.
+    async fn resolve(
+        &self,
+        name: &str,
+        node: H256,
+        resolver: Address,
+        exact: bool,
+    ) -> Option<Address> {
+        let contract = IEnsResolver::new(resolver, Arc::clone(&self.provider));
+        let extended = contract
+            .supports_interface(EXTENDED_RESOLVER_INTERFACE)
+            .call()
+            .await
+            .unwrap_or(false);
+        let query = AddrCall { node: node.0 }.encode();
+        let output = if extended {
+            let call = ResolveCall {
+                name: encode_dns_name(name).into(),
+                data: query.into(),
+            };
+            let output = self.ccip.call(resolver, call.encode().into()).await.ok()?;
+            ResolveReturn::decode(&output).ok()?.0
+        } else if exact {
+            self.ccip.call(resolver, query.into()).await.ok()?
+        } else {
+            return None;
+        };
+        Some(AddrReturn::decode(&output).ok()?.0).filter(|address| !address.is_zero())
+    }
+
+    /// Read `name` at `node`; `label` is set for .eth second-level names
+    async fn describe(&self, name: String, node: H256, label: Option<H256>) -> Result<EnsName> {
+        let mut owner = self
+            .registry
+            .owner(node.0)
+            .call()
+            .await
+            .context("Failed to read ENS owner")?;
+        let wrapped = owner == self.wrapper.address();
+        if wrapped {
+            owner = self
+                .wrapper
+                .owner_of(U256::from_big_endian(node.as_bytes()))
+                .call()
+                .await
+                .context("Failed to read wrapped ENS owner")?;
+        }
+        let (resolver, address) = match self.find_resolver(&name, node).await? {
+            Some((resolver, exact)) => (
+                Some(resolver),
+                self.resolve(&name, node, resolver, exact).await,
+            ),
+            None => (None, None),
+        };
+
+        let (registrant, expires) = match label {
+            Some(label) => {
+                let id = U256::from_big_endian(label.as_bytes());
+                let expires = self
+                    .registrar
+                    .name_expires(id)
+                    .call()
+                    .await
+                    .context("Failed to read ENS expiry")?;
+                // ownerOf reverts once the registration has expired
+                let registrant = match self.registrar.owner_of(id).call().await {
+                    Ok(registrant) if registrant == self.wrapper.address() => Some(owner),
+                    Ok(registrant) => Some(registrant),
+                    Err(_) => None,
+                };
+                (registrant, Some(expires.low_u64()))
+            }
+            None => (None, None),
+        };
+
+        Ok(EnsName {
+            name,
+            node,
+            owner: Some(owner).filter(|owner| !owner.is_zero()),
+            registrant,
+            resolver,
+            address,
+            expires,
+            wrapped,
+        })
+    }
+
+    /// Labels of .eth registrations, from the registration events of current and legacy controllers
+    async fn label_names(&self, labels: &[H256]) -> Result<HashMap<H256, String>> {
+        if labels.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let filter = Filter::new()
+            .address(vec![
+                self.controller.address(),
+                ENS_LEGACY_CONTROLLER
+                    .parse::<Address>()
+                    .context("Invalid ENS legacy controller address")?,
+            ])
+            .topic0(vec![
+                H256::from(keccak256(
+                    "NameRegistered(string,bytes32,address,uint256,uint256,uint256)",
+                )),
+                H256::from(keccak256(
+                    "NameRegistered(string,bytes32,address,uint256,uint256)",
+                )),
+            ])
+            .topic1(labels.to_vec())
+            .from_block(BASE_REGISTRAR_DEPLOY_BLOCK);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch .eth registrations")?;
+        let mut names = HashMap::new();
+        for log in logs {
+            // The plain-text label leads the data of both controller versions
+            let (Some(label), Ok(fields)) = (
+                log.topics.get(1),
+                abi::decode(&[ParamType::String], &log.data),
+            ) else {
+                continue;
+            };
+            if let Some(Token::String(name)) = fields.into_iter().next() {
+                if labelhash(&name) == *label {
+                    names.insert(*label, name);
+                }
+            }
+        }
+        Ok(names)
+    }
+}
+
+#[async_trait]
+impl EnsSource for EnsContracts {
+    async fn lookup(&self, name: &str) -> Result<EnsName> {
+        let label = eth_label(name).map(labelhash);
+        self.describe(name.to_string(), namehash(name), label).await
+    }
+
+    async fn names_owned_by(&self, owner: Address) -> Result<Vec<EnsName>> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?
+            .as_u64();
+        let transfer = H256::from(keccak256("Transfer(address,address,uint256)"));
+        let filter = Filter::new()
+            .address(self.registrar.address())
+            .topic0(transfer)
+            .topic2(H256::from(owner))
+            .from_block(BASE_REGISTRAR_DEPLOY_BLOCK)
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch .eth name transfers")?;
+        let mut labels: Vec<H256> = Vec::new();
+        for log in logs.iter().rev() {
+            if labels.len() == MAX_NAMES_PER_QUERY {
+                break;
+            }
+            let Some(label) = log.topics.get(3) else {
+                continue;
+            };
+            if labels.contains(label) {
+                continue;
+            }
+            // Transferred away since, or expired
+            match self
+                .registrar
+                .owner_of(U256::from_big_endian(label.as_bytes()))
+                .call()
+                .await
+            {
+                Ok(current) if current == owner => labels.push(*label),
+                _ => continue,
+            }
+        }
+
+        let names = self.label_names(&labels).await?;
+        let eth = namehash("eth");
+        let mut owned = Vec::new();
+        for label in labels {
+            let name = match names.get(&label) {
+                Some(name) => format!("{}.eth", name),
+                None => format!("[{}].eth", hex::encode(label.as_bytes())),
+            };
+            owned.push(
+                self.describe(name, child_node(eth, label), Some(label))
+                    .await?,
+            );
+        }
+
+        // Wrapped names are ERC1155 tokens whose id is the name's node
+        let transfer_single = H256::from(keccak256(
+            "TransferSingle(address,address,address,uint256,uint256)",
+        ));
+        let filter = Filter::new()
+            .address(self.wrapper.address())
+            .topic0(transfer_single)
+            .topic3(H256::from(owner))
+            .from_block(NAME_WRAPPER_DEPLOY_BLOCK)
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch wrapped name transfers")?;
+        let mut nodes: Vec<H256> = Vec::new();
+        for log in logs.iter().rev() {
+            if owned.len() == MAX_NAMES_PER_QUERY {
+                break;
+            }
+            let Some(id) = log.data.get(..32) else {
+                continue;
+            };
+            let node = H256::from_slice(id);
+            if nodes.contains(&node) {
+                continue;
+            }
+            nodes.push(node);
+            match self
+                .wrapper
+                .owner_of(U256::from_big_endian(id))
+                .call()
+                .await
+            {
+                Ok(current) if current == owner => {}
+                _ => continue,
+            }
+            let encoded = self
+                .wrapper
+                .names(node.0)
+                .call()
+                .await
+                .context("Failed to read wrapped name")?;
+            let Some(name) = decode_dns_name(&encoded) else {
+                continue;
+            };
+            let label = eth_label(&name).map(labelhash);
+            owned.push(self.describe(name, node, label).await?);
+        }
+        Ok(owned)
+    }
+
+    async fn primary_name(&self, address: Address) -> Result<Option<String>> {
+        let reverse = format!("{}.addr.reverse", hex::encode(address.as_bytes()));
+        let node = namehash(&reverse);
+        let resolver = self
+            .registry
+            .resolver(node.0)
+            .call()
+            .await
+            .context("Failed to read reverse resolver")?;
+        if resolver.is_zero() {
+            return Ok(None);
+        }
+        let name = IEnsResolver::new(resolver, Arc::clone(&self.provider))
+            .name(node.0)
+            .call()
+            .await
+            .unwrap_or_default();
+        if name.is_empty() {
+            return Ok(None);
+        }
+        // A reverse record is only a claim until the name resolves back to the address
+        let forward = self.lookup(&name).await?;
+        Ok((forward.address == Some(address)).then_some(name))
+    }
+
+    async fn rent_price(&self, label: &str, duration_secs: u64) -> Result<U256> {
+        let (base, premium) = self
+            .controller
+            .rent_price(label.to_string(), U256::from(duration_secs))
+            .call()
+            .await
+            .context("Failed to read ENS renewal price")?;
+        Ok(base.saturating_add(premium))
+    }
+}
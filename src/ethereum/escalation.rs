@@ -0,0 +1,88 @@
+use anyhow::Result;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::Serialize;
+
+/// Nodes reject same-nonce replacements that raise fees by less than this
+pub const MIN_BUMP_PERCENT: u64 = 10;
+
+pub const DEFAULT_ESCALATION_AFTER_BLOCKS: u64 = 3;
+pub const DEFAULT_BUMP_PERCENT: u64 = 15;
+
+/// Re-send a transaction with higher fees when it sits unmined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasEscalation {
+    /// Blocks to wait for inclusion before each replacement
+    pub after_blocks: u64,
+    /// Fee increase per replacement, in percent
+    pub bump_percent: u64,
+    /// Fees are never raised above this, in wei per gas
+    pub max_fee_per_gas: U256,
+}
+
+impl GasEscalation {
+    pub fn validate(&self) -> Result<()> {
+        if self.after_blocks == 0 {
+            anyhow::bail!("Gas escalation after_blocks must be at least 1");
+        }
+        if self.bump_percent < MIN_BUMP_PERCENT {
+            anyhow::bail!(
+                "Gas escalation bump_percent must be at least {} for nodes to accept replacements",
+                MIN_BUMP_PERCENT
+            );
+        }
+        if self.max_fee_per_gas.is_zero() {
+            anyhow::bail!("Gas escalation fee ceiling must be positive");
+        }
+        Ok(())
+    }
+
+    /// Raise `tx`'s fees by one step, capped at the ceiling. Returns false once the
+    /// fees are already at the ceiling.
+    pub fn bump(&self, tx: &mut TypedTransaction) -> bool {
+        match tx {
+            TypedTransaction::Eip1559(inner) => {
+                let max_fee = inner.max_fee_per_gas.unwrap_or_default();
+                if max_fee >= self.max_fee_per_gas {
+                    return false;
+                }
+                let next_max_fee = self.raise(max_fee);
+                let priority_fee = inner.max_priority_fee_per_gas.unwrap_or_default();
+                inner.max_fee_per_gas = Some(next_max_fee);
+                inner.max_priority_fee_per_gas = Some(self.raise(priority_fee).min(next_max_fee));
+                true
+            }
+            _ => {
+                let gas_price = tx.gas_price().unwrap_or_default();
+                if gas_price >= self.max_fee_per_gas {
+                    return false;
+                }
+                tx.set_gas_price(self.raise(gas_price));
+                true
+            }
+        }
+    }
+
+    /// The fee the transaction currently bids, for reporting
+    pub fn current_fee(tx: &TypedTransaction) -> U256 {
+        match tx {
+            TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas.unwrap_or_default(),
+            _ => tx.gas_price().unwrap_or_default(),
+        }
+    }
+
+    fn raise(&self, fee: U256) -> U256 {
+        // Round up so small fees still move
+        let raised = (fee * (100 + self.bump_percent) + 99) / 100;
+        raised.min(self.max_fee_per_gas)
+    }
+}
+
+/// A fee-bumped replacement sent while waiting for inclusion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeeReplacement {
+    pub tx_hash: H256,
+    /// Fee bid of the replacement, in wei per gas
+    pub max_fee_per_gas: U256,
+    pub block_number: u64,
+}
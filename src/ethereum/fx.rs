@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// FX feeds only move on deviation while markets are closed, so allow a weekend of silence
+const MAX_FX_AGE_SECS: u64 = 72 * 60 * 60;
+
+// Chainlink `<currency>/USD` aggregators on Ethereum mainnet
+const CHAINLINK_FX_FEEDS: &[(&str, &str)] = &[
+    ("EUR", "0xb49f677943BC038e9857d61E7d053CaA2C1734C1"),
+    ("GBP", "0x5c0Ab2d9b5a7ed9f470386e82BB36A3613cDd4b5"),
+    ("JPY", "0xBcE206caE7f0ec07b545EddE332A47C2F75bbeb3"),
+    ("CHF", "0x449d117117838fFA61263B61dA6301AA2a88B13A"),
+    ("AUD", "0x77F9710E7d0A19669A13c055F62cd80d313dF022"),
+    ("CAD", "0xa34317DB73e77d453b1B8d04550c44D10e981C8e"),
+];
+
+abigen!(
+    IChainlinkAggregator,
+    r#"[
+        function decimals() external view returns (uint8)
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
+
+/// Value of one unit of a fiat currency in USD
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxRate {
+    pub currency: String,
+    pub usd_per_unit: Decimal,
+    /// Unix seconds of the source's last update
+    pub updated_at: u64,
+}
+
+/// A source of fiat exchange rates against USD
+#[async_trait]
+pub trait FxRateSource: Send + Sync {
+    /// ISO 4217 codes this source can convert to
+    fn supported_currencies(&self) -> Vec<String>;
+
+    /// Rate for an ISO 4217 `currency` code, in any case
+    async fn usd_rate(&self, currency: &str) -> Result<FxRate>;
+}
+
+/// FX rates read from Chainlink price feeds
+pub struct ChainlinkFxRates {
+    provider: Arc<Provider<Http>>,
+}
+
+impl ChainlinkFxRates {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl FxRateSource for ChainlinkFxRates {
+    fn supported_currencies(&self) -> Vec<String> {
+        CHAINLINK_FX_FEEDS
+            .iter()
+            .map(|(code, _)| code.to_string())
+            .collect()
+    }
+
+    async fn usd_rate(&self, currency: &str) -> Result<FxRate> {
+        let currency = currency.to_uppercase();
+        let feed = CHAINLINK_FX_FEEDS
+            .iter()
+            .find(|(code, _)| *code == currency)
+            .map(|(_, feed)| feed.parse::<Address>())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unsupported fiat currency: {}. Supported: {}",
+                    currency,
+                    self.supported_currencies().join(", ")
+                )
+            })?
+            .with_context(|| format!("Invalid Chainlink {} feed address", currency))?;
+
+        let aggregator = IChainlinkAggregator::new(feed, Arc::clone(&self.provider));
+        let decimals = aggregator
+            .decimals()
+            .call()
+            .await
+            .context("Failed to read FX feed decimals")?;
+        let (_, answer, _, updated_at, _) = aggregator
+            .latest_round_data()
+            .call()
+            .await
+            .with_context(|| format!("Failed to read the {}/USD feed", currency))?;
+
+        if answer <= I256::zero() {
+            anyhow::bail!("{}/USD feed returned a non-positive rate", currency);
+        }
+        let updated_at = updated_at.as_u64();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if now.saturating_sub(updated_at) > MAX_FX_AGE_SECS {
+            anyhow::bail!(
+                "{}/USD feed is stale: last updated {} seconds ago",
+                currency,
+                now - updated_at
+            );
+        }
+
+        let usd_per_unit = Decimal::from_i128_with_scale(answer.as_i128(), decimals as u32);
+        Ok(FxRate {
+            currency,
+            usd_per_unit,
+            updated_at,
+        })
+    }
+}
@@ -0,0 +1,571 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::{self, ParamType, Token};
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Governor contracts on Ethereum mainnet that can be named instead of addressed
+pub const DEFAULT_GOVERNORS: &[(&str, &str)] = &[
+    ("uniswap", "0x408ED6354d4973f66138C91495F2f2FCbd8724C3"),
+    ("compound", "0xc0Da02939E1441F497fd74F78cE7Decb17B66529"),
+    ("ens", "0x323A76393544d5ecca80cd6ef2A560C6a395b7E3"),
+];
+
+/// Snapshot's public hub, serving off-chain proposals and voting power
+pub const DEFAULT_SNAPSHOT_HUB_URL: &str = "https://hub.snapshot.org";
+
+/// Blocks of proposal creations searched by default, about a year
+pub const DEFAULT_PROPOSAL_LOOKBACK_BLOCKS: u64 = 2_600_000;
+
+abigen!(
+    IGovernor,
+    r#"[
+        function state(uint256 proposalId) external view returns (uint8)
+        function proposalVotes(uint256 proposalId) external view returns (uint256 againstVotes, uint256 forVotes, uint256 abstainVotes)
+        function proposals(uint256 proposalId) external view returns (uint256 id, address proposer, uint256 eta, uint256 startBlock, uint256 endBlock, uint256 forVotes, uint256 againstVotes, uint256 abstainVotes, bool canceled, bool executed)
+        function proposalSnapshot(uint256 proposalId) external view returns (uint256)
+        function hasVoted(uint256 proposalId, address account) external view returns (bool)
+        function getReceipt(uint256 proposalId, address voter) external view returns (bool hasVoted, uint8 support, uint96 votes)
+        function CLOCK_MODE() external view returns (string)
+        function token() external view returns (address)
+        function comp() external view returns (address)
+        function uni() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IVotesToken,
+    r#"[
+        function getVotes(address account) external view returns (uint256)
+        function getCurrentVotes(address account) external view returns (uint96)
+        function getPastVotes(address account, uint256 timepoint) external view returns (uint256)
+        function getPriorVotes(address account, uint256 blockNumber) external view returns (uint96)
+        function delegates(address account) external view returns (address)
+        function balanceOf(address account) external view returns (uint256)
+        function decimals() external view returns (uint8)
+        function symbol() external view returns (string)
+    ]"#
+);
+
+/// Where a proposal stands; `Closed` is Snapshot's single state for finished votes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalState {
+    Pending,
+    Active,
+    Canceled,
+    Defeated,
+    Succeeded,
+    Queued,
+    Expired,
+    Executed,
+    Closed,
+}
+
+impl ProposalState {
+    /// The Governor `state()` value, shared by OpenZeppelin and Bravo governors
+    fn from_governor(state: u8) -> Option<Self> {
+        Some(match state {
+            0 => Self::Pending,
+            1 => Self::Active,
+            2 => Self::Canceled,
+            3 => Self::Defeated,
+            4 => Self::Succeeded,
+            5 => Self::Queued,
+            6 => Self::Expired,
+            7 => Self::Executed,
+            _ => return None,
+        })
+    }
+}
+
+/// What a proposal's `start` and `end` count in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteClock {
+    Block,
+    Timestamp,
+}
+
+/// Votes cast for one choice, in whole tokens or Snapshot voting power
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoteTally {
+    pub choice: String,
+    pub votes: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Proposal {
+    /// Governor proposal ids are decimal integers; Snapshot ids are hashes
+    pub id: String,
+    pub proposer: Address,
+    pub title: String,
+    pub description: String,
+    pub state: ProposalState,
+    pub start: u64,
+    pub end: u64,
+    pub clock: VoteClock,
+    /// In the order choices are voted by: against, for, abstain on a Governor
+    pub tallies: Vec<VoteTally>,
+}
+
+/// A voter's weight in a DAO, now or at a proposal's snapshot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VotingPower {
+    pub votes: Decimal,
+    /// Governance token, when votes come from one
+    pub symbol: Option<String>,
+    /// Whom the voter's tokens are delegated to; only delegated tokens count as votes
+    pub delegate: Option<Address>,
+    pub balance: Option<Decimal>,
+    /// Whether the voter already voted on the proposal asked about
+    pub has_voted: Option<bool>,
+}
+
+/// A DAO voting system whose proposals and voting power can be read
+#[async_trait]
+pub trait GovernanceSource: Send + Sync {
+    /// `governor` for on-chain Governor contracts or `snapshot`
+    fn name(&self) -> &str;
+
+    /// Latest proposals of `dao`, newest first: a governor address or a Snapshot space
+    async fn proposals(&self, dao: &str, active_only: bool, limit: usize) -> Result<Vec<Proposal>>;
+
+    /// `voter`'s voting power in `dao`, at `proposal`'s snapshot when given
+    async fn voting_power(
+        &self,
+        dao: &str,
+        voter: Address,
+        proposal: Option<&str>,
+    ) -> Result<VotingPower>;
+}
+
+/// The first line of a proposal description, without Markdown heading marks
+fn title_of(description: &str) -> String {
+    description
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn whole_tokens(value: U256, decimals: u8) -> Decimal {
+    Decimal::from_str_exact(&value.to_string())
+        .ok()
+        .and_then(|value| value.checked_div(Decimal::from(10u64.pow(decimals.min(18) as u32))))
+        .unwrap_or_default()
+        .normalize()
+}
+
+/// OpenZeppelin Governor and Compound GovernorBravo contracts, whose proposals are
+/// found through their creation events
+pub struct GovernorContracts {
+    provider: Arc<Provider<Http>>,
+    lookback_blocks: u64,
+}
+
+impl GovernorContracts {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self {
+            provider,
+            lookback_blocks: DEFAULT_PROPOSAL_LOOKBACK_BLOCKS,
+        }
+    }
+
+    /// Search proposal creations over the last `blocks` blocks
+    pub fn with_lookback_blocks(mut self, blocks: u64) -> Self {
+        self.lookback_blocks = blocks;
+        self
+    }
+
+    fn governor(&self, dao: &str) -> Result<IGovernor<Provider<Http>>> {
+        let address: Address = dao.trim().parse().context("Invalid governor address")?;
+        Ok(IGovernor::new(address, Arc::clone(&self.provider)))
+    }
+
+    /// The governance token: `token()` on OpenZeppelin governors, `comp()` or `uni()` on Bravo ones
+    async fn token(
+        &self,
+        governor: &IGovernor<Provider<Http>>,
+    ) -> Result<IVotesToken<Provider<Http>>> {
+        let token = match governor.token().call().await {
+            Ok(token) => token,
+            Err(_) => match governor.comp().call().await {
+                Ok(token) => token,
+                Err(_) => governor
+                    .uni()
+                    .call()
+                    .await
+                    .context("Failed to find the governor's voting token")?,
+            },
+        };
+        Ok(IVotesToken::new(token, Arc::clone(&self.provider)))
+    }
+}
+
+#[async_trait]
+impl GovernanceSource for GovernorContracts {
+    fn name(&self) -> &str {
+        "governor"
+    }
+
+    async fn proposals(&self, dao: &str, active_only: bool, limit: usize) -> Result<Vec<Proposal>> {
+        let governor = self.governor(dao)?;
+        let decimals = match self.token(&governor).await {
+            Ok(token) => token.decimals().call().await.unwrap_or(18),
+            Err(_) => 18,
+        };
+        let clock = match governor.clock_mode().call().await {
+            Ok(mode) if mode.contains("timestamp") => VoteClock::Timestamp,
+            _ => VoteClock::Block,
+        };
+
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?
+            .as_u64();
+        let filter = Filter::new()
+            .address(governor.address())
+            .topic0(H256::from(keccak256(
+                "ProposalCreated(uint256,address,address[],uint256[],string[],bytes[],uint256,uint256,string)",
+            )))
+            .from_block(head.saturating_sub(self.lookback_blocks))
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch proposals")?;
+
+        let mut proposals = Vec::new();
+        for log in logs.iter().rev() {
+            if proposals.len() == limit {
+                break;
+            }
+            // Nothing is indexed: id, proposer, targets, values, signatures, calldatas,
+            // start, end and description are all in the data
+            let Ok(fields) = abi::decode(
+                &[
+                    ParamType::Uint(256),
+                    ParamType::Address,
+                    ParamType::Array(Box::new(ParamType::Address)),
+                    ParamType::Array(Box::new(ParamType::Uint(256))),
+                    ParamType::Array(Box::new(ParamType::String)),
+                    ParamType::Array(Box::new(ParamType::Bytes)),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::String,
+                ],
+                &log.data,
+            ) else {
+                continue;
+            };
+            let (
+                Token::Uint(id),
+                Token::Address(proposer),
+                Token::Uint(start),
+                Token::Uint(end),
+                Token::String(description),
+            ) = (
+                fields[0].clone(),
+                fields[1].clone(),
+                fields[6].clone(),
+                fields[7].clone(),
+                fields[8].clone(),
+            )
+            else {
+                continue;
+            };
+
+            let state = governor
+                .state(id)
+                .call()
+                .await
+                .context("Failed to read proposal state")?;
+            let Some(state) = ProposalState::from_governor(state) else {
+                continue;
+            };
+            if active_only && state != ProposalState::Active {
+                continue;
+            }
+            let (against, support, abstain) = match governor.proposal_votes(id).call().await {
+                Ok(votes) => votes,
+                Err(_) => {
+                    let (_, _, _, _, _, support, against, abstain, _, _) = governor
+                        .proposals(id)
+                        .call()
+                        .await
+                        .context("Failed to read proposal votes")?;
+                    (against, support, abstain)
+                }
+            };
+            let tallies = [("against", against), ("for", support), ("abstain", abstain)]
+                .into_iter()
+                .map(|(choice, votes)| VoteTally {
+                    choice: choice.to_string(),
+                    votes: whole_tokens(votes, decimals),
+                })
+                .collect();
+            proposals.push(Proposal {
+                id: id.to_string(),
+                proposer,
+                title: title_of(&description),
+                description,
+                state,
+                start: start.low_u64(),
+                end: end.low_u64(),
+                clock,
+                tallies,
+            });
+        }
+        Ok(proposals)
+    }
+
+    async fn voting_power(
+        &self,
+        dao: &str,
+        voter: Address,
+        proposal: Option<&str>,
+    ) -> Result<VotingPower> {
+        let governor = self.governor(dao)?;
+        let token = self.token(&governor).await?;
+        let decimals = token.decimals().call().await.unwrap_or(18);
+
+        let (votes, has_voted) = match proposal {
+            Some(proposal) => {
+                let id = U256::from_dec_str(proposal.trim()).context("Invalid proposal id")?;
+                let timepoint = match governor.proposal_snapshot(id).call().await {
+                    Ok(timepoint) => timepoint,
+                    Err(_) => {
+                        governor
+                            .proposals(id)
+                            .call()
+                            .await
+                            .context("Failed to read the proposal")?
+                            .3
+                    }
+                };
+                let votes = match token.get_past_votes(voter, timepoint).call().await {
+                    Ok(votes) => votes,
+                    Err(_) => U256::from(
+                        token
+                            .get_prior_votes(voter, timepoint)
+                            .call()
+                            .await
+                            .context("Failed to read past votes")?,
+                    ),
+                };
+                let has_voted = match governor.has_voted(id, voter).call().await {
+                    Ok(voted) => Some(voted),
+                    Err(_) => governor
+                        .get_receipt(id, voter)
+                        .call()
+                        .await
+                        .ok()
+                        .map(|receipt| receipt.0),
+                };
+                (votes, has_voted)
+            }
+            None => {
+                let votes = match token.get_votes(voter).call().await {
+                    Ok(votes) => votes,
+                    Err(_) => U256::from(
+                        token
+                            .get_current_votes(voter)
+                            .call()
+                            .await
+                            .context("Failed to read votes")?,
+                    ),
+                };
+                (votes, None)
+            }
+        };
+
+        Ok(VotingPower {
+            votes: whole_tokens(votes, decimals),
+            symbol: token.symbol().call().await.ok(),
+            delegate: token
+                .delegates(voter)
+                .call()
+                .await
+                .ok()
+                .filter(|delegate| !delegate.is_zero()),
+            balance: token
+                .balance_of(voter)
+                .call()
+                .await
+                .ok()
+                .map(|balance| whole_tokens(balance, decimals)),
+            has_voted,
+        })
+    }
+}
+
+/// Off-chain proposals and voting power from a Snapshot hub's GraphQL API
+pub struct SnapshotHub {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotProposal {
+    id: String,
+    title: String,
+    #[serde(default)]
+    body: String,
+    author: String,
+    state: String,
+    start: u64,
+    end: u64,
+    choices: Vec<String>,
+    #[serde(default)]
+    scores: Vec<f64>,
+}
+
+impl SnapshotHub {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn query(&self, query: &str, variables: Value) -> Result<Value> {
+        let response: Value = self
+            .http
+            .post(format!("{}/graphql", self.url))
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .context("Failed to reach the Snapshot hub")?
+            .error_for_status()
+            .context("Snapshot hub request failed")?
+            .json()
+            .await
+            .context("Invalid Snapshot hub response")?;
+        if let Some(message) = response
+            .pointer("/errors/0/message")
+            .and_then(Value::as_str)
+        {
+            anyhow::bail!("Snapshot hub error: {}", message);
+        }
+        Ok(response["data"].clone())
+    }
+}
+
+#[async_trait]
+impl GovernanceSource for SnapshotHub {
+    fn name(&self) -> &str {
+        "snapshot"
+    }
+
+    async fn proposals(&self, dao: &str, active_only: bool, limit: usize) -> Result<Vec<Proposal>> {
+        let data = self
+            .query(
+                "query Proposals($space: String!, $first: Int!, $state: String!) {
+                    proposals(first: $first, where: { space: $space, state: $state }, orderBy: \"created\", orderDirection: desc) {
+                        id title body author state start end choices scores
+                    }
+                }",
+                json!({
+                    "space": dao.trim(),
+                    "first": limit,
+                    "state": if active_only { "active" } else { "all" },
+                }),
+            )
+            .await?;
+        let proposals: Vec<SnapshotProposal> = serde_json::from_value(data["proposals"].clone())
+            .context("Invalid Snapshot proposals")?;
+        proposals
+            .into_iter()
+            .map(|proposal| {
+                let state = match proposal.state.as_str() {
+                    "pending" => ProposalState::Pending,
+                    "active" => ProposalState::Active,
+                    _ => ProposalState::Closed,
+                };
+                // Choices nobody has voted for yet may have no score
+                let tallies = proposal
+                    .choices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, choice)| {
+                        let score = proposal.scores.get(i).copied().unwrap_or_default();
+                        let votes = Decimal::from_f64(score).with_context(|| {
+                            format!("Snapshot score {} for {} is out of range", score, choice)
+                        })?;
+                        Ok(VoteTally {
+                            choice: choice.clone(),
+                            votes: votes.round_dp(4).normalize(),
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Proposal {
+                    proposer: proposal.author.parse().context("Invalid Snapshot author")?,
+                    title: proposal.title,
+                    description: proposal.body,
+                    id: proposal.id,
+                    state,
+                    start: proposal.start,
+                    end: proposal.end,
+                    clock: VoteClock::Timestamp,
+                    tallies,
+                })
+            })
+            .collect()
+    }
+
+    async fn voting_power(
+        &self,
+        dao: &str,
+        voter: Address,
+        proposal: Option<&str>,
+    ) -> Result<VotingPower> {
+        let voter = format!("{:?}", voter);
+        let data = match proposal {
+            Some(proposal) => {
+                self.query(
+                    "query Power($voter: String!, $space: String!, $proposal: String!) {
+                        vp(voter: $voter, space: $space, proposal: $proposal) { vp }
+                        votes(where: { proposal: $proposal, voter: $voter }) { id }
+                    }",
+                    json!({ "voter": voter, "space": dao.trim(), "proposal": proposal.trim() }),
+                )
+                .await?
+            }
+            None => {
+                self.query(
+                    "query Power($voter: String!, $space: String!) {
+                        vp(voter: $voter, space: $space) { vp }
+                    }",
+                    json!({ "voter": voter, "space": dao.trim() }),
+                )
+                .await?
+            }
+        };
+        let votes = data
+            .pointer("/vp/vp")
+            .and_then(Value::as_f64)
+            .context("Snapshot hub returned no voting power")?;
+        Ok(VotingPower {
+            votes: Decimal::from_f64(votes)
+                .with_context(|| format!("Snapshot voting power {} is out of range", votes))?
+                .round_dp(4)
+                .normalize(),
+            has_voted: proposal.map(|_| {
+                data["votes"]
+                    .as_array()
+                    .is_some_and(|votes| !votes.is_empty())
+            }),
+            ..VotingPower::default()
+        })
+    }
+}
@@ -0,0 +1,281 @@
+use crate::secrets::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Etherscan's multichain API, which takes the chain as a parameter
+pub const DEFAULT_ETHERSCAN_URL: &str = "https://api.etherscan.io/v2/api";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+// Largest page the account API serves
+const PAGE_SIZE: usize = 1_000;
+
+/// A transaction an account sent or received, as recorded by an indexer
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalTransaction {
+    pub hash: H256,
+    pub block_number: u64,
+    /// Unix seconds
+    pub timestamp: u64,
+    pub from: Address,
+    /// None for contract creations
+    pub to: Option<Address>,
+    pub value: U256,
+    pub gas_used: U256,
+    /// Price per gas actually paid
+    pub gas_price: U256,
+    /// First four bytes of the calldata; None for plain ETH transfers
+    pub selector: Option<[u8; 4]>,
+    /// Signature of the called function when the indexer knows it
+    pub function_name: Option<String>,
+    pub success: bool,
+}
+
+impl HistoricalTransaction {
+    /// Wei paid for gas
+    pub fn fee(&self) -> U256 {
+        self.gas_used.saturating_mul(self.gas_price)
+    }
+}
+
+/// A beacon-chain withdrawal credited to an execution address
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconWithdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: Address,
+    pub amount_gwei: u64,
+    pub block_number: u64,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+/// Past transactions of an account, beyond what the node's logs reveal
+#[async_trait]
+pub trait TransactionHistorySource: Send + Sync {
+    /// Short name reported with the results, such as `etherscan`
+    fn name(&self) -> &str;
+
+    /// Transactions `account` sent or received at or after `since` (unix seconds),
+    /// newest first, at most `limit` of them
+    async fn transactions(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<HistoricalTransaction>>;
+
+    /// Beacon-chain withdrawals credited to `account` at or after `since` (unix
+    /// seconds), newest first, at most `limit` of them
+    async fn withdrawals(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<BeaconWithdrawal>>;
+}
+
+/// Account history from an Etherscan-compatible API
+pub struct EtherscanHistory {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<SecretString>,
+    chain_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EtherscanResponse {
+    status: String,
+    message: String,
+    result: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EtherscanTransaction {
+    hash: H256,
+    block_number: String,
+    time_stamp: String,
+    from: Address,
+    #[serde(default)]
+    to: String,
+    value: String,
+    gas_used: String,
+    gas_price: String,
+    #[serde(default)]
+    input: String,
+    #[serde(default)]
+    function_name: String,
+    #[serde(default)]
+    is_error: String,
+}
+
+impl EtherscanTransaction {
+    fn parse(self) -> Result<HistoricalTransaction> {
+        let number = |field: &str, value: &str| -> Result<U256> {
+            U256::from_dec_str(value).with_context(|| format!("Invalid {}: {}", field, value))
+        };
+        let input = ethers::utils::hex::decode(self.input.trim_start_matches("0x"))
+            .context("Invalid transaction input")?;
+        Ok(HistoricalTransaction {
+            hash: self.hash,
+            block_number: self.block_number.parse().context("Invalid block number")?,
+            timestamp: self.time_stamp.parse().context("Invalid timestamp")?,
+            from: self.from,
+            to: self.to.parse().ok(),
+            value: number("value", &self.value)?,
+            gas_used: number("gasUsed", &self.gas_used)?,
+            gas_price: number("gasPrice", &self.gas_price)?,
+            selector: input.get(..4).map(|selector| selector.try_into().unwrap()),
+            function_name: Some(self.function_name).filter(|name| !name.is_empty()),
+            success: self.is_error != "1",
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EtherscanWithdrawal {
+    withdrawal_index: String,
+    validator_index: String,
+    address: Address,
+    /// Gwei
+    amount: String,
+    block_number: String,
+    timestamp: String,
+}
+
+impl EtherscanWithdrawal {
+    fn parse(self) -> Result<BeaconWithdrawal> {
+        let number = |field: &str, value: &str| -> Result<u64> {
+            value
+                .parse()
+                .with_context(|| format!("Invalid {}: {}", field, value))
+        };
+        Ok(BeaconWithdrawal {
+            index: number("withdrawalIndex", &self.withdrawal_index)?,
+            validator_index: number("validatorIndex", &self.validator_index)?,
+            address: self.address,
+            amount_gwei: number("amount", &self.amount)?,
+            block_number: number("blockNumber", &self.block_number)?,
+            timestamp: number("timestamp", &self.timestamp)?,
+        })
+    }
+}
+
+impl EtherscanHistory {
+    pub fn new(url: &str, api_key: Option<SecretString>, chain_id: u64) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+            chain_id,
+        }
+    }
+
+    /// Page `page` of `account`'s records from the account API's `action`
+    async fn page<T: serde::de::DeserializeOwned>(
+        &self,
+        action: &str,
+        account: Address,
+        page: usize,
+    ) -> Result<Vec<T>> {
+        let mut query = vec![
+            ("chainid", self.chain_id.to_string()),
+            ("module", "account".to_string()),
+            ("action", action.to_string()),
+            ("address", format!("{:?}", account)),
+            ("sort", "desc".to_string()),
+            ("page", page.to_string()),
+            ("offset", PAGE_SIZE.to_string()),
+        ];
+        if let Some(key) = &self.api_key {
+            query.push(("apikey", key.expose_secret().to_string()));
+        }
+        let response: EtherscanResponse = self
+            .http
+            .get(&self.url)
+            .query(&query)
+            .send()
+            .await
+            .context("Failed to reach the transaction history API")?
+            .error_for_status()
+            .context("Transaction history request failed")?
+            .json()
+            .await
+            .context("Invalid transaction history response")?;
+        match response.result {
+            serde_json::Value::Array(_) => serde_json::from_value(response.result)
+                .context("Invalid transaction in history response"),
+            // An account without transactions is reported as an error with no result
+            _ if response.message.starts_with("No transactions") => Ok(Vec::new()),
+            result => anyhow::bail!(
+                "Transaction history API refused the request ({}): {}",
+                response.status,
+                result.as_str().unwrap_or(&response.message)
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionHistorySource for EtherscanHistory {
+    fn name(&self) -> &str {
+        "etherscan"
+    }
+
+    async fn transactions(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<HistoricalTransaction>> {
+        let mut found = Vec::new();
+        for page in 1.. {
+            let batch: Vec<EtherscanTransaction> = self.page("txlist", account, page).await?;
+            let last_page = batch.len() < PAGE_SIZE;
+            for transaction in batch {
+                let transaction = transaction.parse()?;
+                // Newest first, so the rest are older still
+                if transaction.timestamp < since || found.len() >= limit {
+                    return Ok(found);
+                }
+                found.push(transaction);
+            }
+            if last_page {
+                break;
+            }
+        }
+        Ok(found)
+    }
+
+    async fn withdrawals(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<BeaconWithdrawal>> {
+        let mut found = Vec::new();
+        for page in 1.. {
+            let batch: Vec<EtherscanWithdrawal> =
+                self.page("txsBeaconWithdrawal", account, page).await?;
+            let last_page = batch.len() < PAGE_SIZE;
+            for withdrawal in batch {
+                let withdrawal = withdrawal.parse()?;
+                if withdrawal.timestamp < since || found.len() >= limit {
+                    return Ok(found);
+                }
+                found.push(withdrawal);
+            }
+            if last_page {
+                break;
+            }
+        }
+        Ok(found)
+    }
+}
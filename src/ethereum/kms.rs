@@ -0,0 +1,546 @@
+use crate::secrets::{register_secret, SecretString};
+use crate::time::civil_from_days;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use data_encoding::{BASE64, HEXLOWER};
+use ethers::core::k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey};
+use ethers::core::k256::sha2::{Digest, Sha256};
+use ethers::prelude::*;
+use ethers::signers::to_eip155_v;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::utils::{hash_message, public_key_to_address};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const KMS_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Where GCE, GKE and Cloud Run hand out the attached service account's tokens
+const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+// Tokens are refreshed this long before they expire
+const GCP_TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// AWS credentials KMS requests are signed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: SecretString,
+    /// Set for temporary credentials, such as an assumed role's
+    pub session_token: Option<SecretString>,
+}
+
+/// A secp256k1 key held in a cloud KMS, which signs without the key ever leaving it
+#[derive(Debug, Clone, PartialEq)]
+pub enum KmsConfig {
+    /// An `ECC_SECG_P256K1` key in AWS KMS
+    Aws {
+        /// Key id, ARN or alias
+        key_id: String,
+        region: String,
+        credentials: AwsCredentials,
+        /// Overrides `https://kms.{region}.amazonaws.com`, such as for a VPC endpoint
+        endpoint: Option<String>,
+    },
+    /// An `EC_SIGN_SECP256K1_SHA256` key version in Google Cloud KMS
+    Gcp {
+        /// `projects/…/locations/…/keyRings/…/cryptoKeys/…/cryptoKeyVersions/…`
+        key_name: String,
+        /// OAuth token; `None` fetches the attached service account's from the metadata server
+        access_token: Option<SecretString>,
+        /// Overrides `https://cloudkms.googleapis.com`
+        endpoint: Option<String>,
+    },
+}
+
+/// A KMS request that failed, or a signature that does not match the key
+#[derive(Debug)]
+pub struct KmsError(anyhow::Error);
+
+impl std::fmt::Display for KmsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl std::error::Error for KmsError {}
+
+impl From<anyhow::Error> for KmsError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backend {
+    Aws {
+        key_id: String,
+        region: String,
+        credentials: AwsCredentials,
+        endpoint: String,
+    },
+    Gcp {
+        key_name: String,
+        endpoint: String,
+        token: GcpToken,
+    },
+}
+
+/// A fixed token, or the metadata server's, cached until shortly before it expires
+#[derive(Debug, Clone)]
+enum GcpToken {
+    Fixed(SecretString),
+    Metadata(Arc<Mutex<Option<(SecretString, Instant)>>>),
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Signs transactions and messages with a key held in AWS or Google Cloud KMS.
+/// KMS returns DER-encoded ECDSA signatures; they are normalized to low-s and given
+/// the recovery id that yields the key's address.
+#[derive(Debug, Clone)]
+pub struct KmsSigner {
+    kms: KmsClient,
+    public_key: VerifyingKey,
+    address: Address,
+    chain_id: u64,
+}
+
+impl KmsSigner {
+    /// Look up the key's public key, and with it the address it signs for
+    pub async fn connect(config: &KmsConfig, chain_id: u64) -> Result<Self> {
+        let kms = KmsClient::new(config)?;
+        let spki = kms
+            .public_key_der()
+            .await
+            .context("Failed to read the KMS key's public key")?;
+        let public_key = public_key_from_spki(&spki)?;
+        Ok(Self {
+            kms,
+            address: public_key_to_address(&public_key),
+            public_key,
+            chain_id,
+        })
+    }
+
+    /// Have KMS sign `digest`; `v` of the result is the recovery id, 0 or 1
+    pub async fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let der = self.kms.sign(digest).await?;
+        rsv_from_der(&der, digest, &self.public_key)
+    }
+}
+
+/// Requests to the KMS holding the key
+#[derive(Debug, Clone)]
+struct KmsClient {
+    backend: Backend,
+    http: reqwest::Client,
+}
+
+impl KmsClient {
+    fn new(config: &KmsConfig) -> Result<Self> {
+        let backend = match config {
+            KmsConfig::Aws {
+                key_id,
+                region,
+                credentials,
+                endpoint,
+            } => Backend::Aws {
+                key_id: key_id.clone(),
+                region: region.clone(),
+                credentials: credentials.clone(),
+                endpoint: endpoint
+                    .clone()
+                    .unwrap_or_else(|| format!("https://kms.{}.amazonaws.com", region)),
+            },
+            KmsConfig::Gcp {
+                key_name,
+                access_token,
+                endpoint,
+            } => Backend::Gcp {
+                key_name: key_name.clone(),
+                endpoint: endpoint
+                    .clone()
+                    .unwrap_or_else(|| "https://cloudkms.googleapis.com".to_string()),
+                token: match access_token {
+                    Some(token) => GcpToken::Fixed(token.clone()),
+                    None => GcpToken::Metadata(Arc::default()),
+                },
+            },
+        };
+        let http = reqwest::Client::builder()
+            .timeout(KMS_REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to build the KMS HTTP client")?;
+
+        Ok(Self { backend, http })
+    }
+
+    /// DER `SubjectPublicKeyInfo` of the key
+    async fn public_key_der(&self) -> Result<Vec<u8>> {
+        match &self.backend {
+            Backend::Aws { key_id, .. } => {
+                let response = self
+                    .aws_request("TrentService.GetPublicKey", json!({ "KeyId": key_id }))
+                    .await?;
+                let key = response["PublicKey"]
+                    .as_str()
+                    .context("KMS returned no public key")?;
+                BASE64
+                    .decode(key.as_bytes())
+                    .context("KMS returned a malformed public key")
+            }
+            Backend::Gcp {
+                key_name, endpoint, ..
+            } => {
+                let url = format!("{}/v1/{}/publicKey", endpoint, key_name);
+                let response = self.gcp_request(self.http.get(url)).await?;
+                let pem = response["pem"]
+                    .as_str()
+                    .context("KMS returned no public key")?;
+                let body: String = pem
+                    .lines()
+                    .filter(|line| !line.starts_with("-----"))
+                    .collect();
+                BASE64
+                    .decode(body.trim().as_bytes())
+                    .context("KMS returned a malformed public key")
+            }
+        }
+    }
+
+    /// DER-encoded ECDSA signature of `digest`
+    async fn sign(&self, digest: H256) -> Result<Vec<u8>> {
+        let der = match &self.backend {
+            Backend::Aws { key_id, .. } => {
+                let response = self
+                    .aws_request(
+                        "TrentService.Sign",
+                        json!({
+                            "KeyId": key_id,
+                            "Message": BASE64.encode(digest.as_bytes()),
+                            "MessageType": "DIGEST",
+                            "SigningAlgorithm": "ECDSA_SHA_256",
+                        }),
+                    )
+                    .await?;
+                response["Signature"].as_str().map(str::to_string)
+            }
+            Backend::Gcp {
+                key_name, endpoint, ..
+            } => {
+                let url = format!("{}/v1/{}:asymmetricSign", endpoint, key_name);
+                // KMS signs the 32 bytes as given, so a keccak hash passes as the digest
+                let request = self.http.post(url).json(&json!({
+                    "digest": { "sha256": BASE64.encode(digest.as_bytes()) }
+                }));
+                let response = self.gcp_request(request).await?;
+                response["signature"].as_str().map(str::to_string)
+            }
+        };
+        BASE64
+            .decode(der.context("KMS returned no signature")?.as_bytes())
+            .context("KMS returned a malformed signature")
+    }
+
+    async fn aws_request(&self, target: &str, body: Value) -> Result<Value> {
+        let Backend::Aws {
+            region,
+            credentials,
+            endpoint,
+            ..
+        } = &self.backend
+        else {
+            anyhow::bail!("AWS request on a non-AWS KMS backend");
+        };
+        let body = body.to_string();
+        let host = reqwest::Url::parse(endpoint)
+            .context("Invalid KMS endpoint")?
+            .host_str()
+            .context("KMS endpoint has no host")?
+            .to_string();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let signed = sign_aws_request(credentials, region, &host, target, &body, now);
+
+        let mut request = self
+            .http
+            .post(endpoint)
+            .header("content-type", "application/x-amz-json-1.1")
+            .header("x-amz-date", &signed.amz_date)
+            .header("x-amz-target", target)
+            .header("authorization", signed.authorization)
+            .body(body);
+        if let Some(token) = &credentials.session_token {
+            request = request.header("x-amz-security-token", token.expose_secret());
+        }
+        read_response(request.send().await.context("KMS request failed")?).await
+    }
+
+    async fn gcp_request(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let token = self.gcp_token().await?;
+        let response = request
+            .bearer_auth(token.expose_secret())
+            .send()
+            .await
+            .context("KMS request failed")?;
+        read_response(response).await
+    }
+
+    async fn gcp_token(&self) -> Result<SecretString> {
+        let Backend::Gcp { token, .. } = &self.backend else {
+            anyhow::bail!("GCP request on a non-GCP KMS backend");
+        };
+        let cache = match token {
+            GcpToken::Fixed(token) => return Ok(token.clone()),
+            GcpToken::Metadata(cache) => cache,
+        };
+        let mut cached = cache.lock().await;
+        if let Some((token, expires)) = cached.as_ref() {
+            if Instant::now() + GCP_TOKEN_MARGIN < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let fetched: MetadataToken = self
+            .http
+            .get(GCP_METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("No GCP access token: set GCP_ACCESS_TOKEN outside Google Cloud")?
+            .json()
+            .await
+            .context("The metadata server returned a malformed token")?;
+        let token = SecretString::new(fetched.access_token);
+        register_secret(&token);
+        *cached = Some((
+            token.clone(),
+            Instant::now() + Duration::from_secs(fetched.expires_in),
+        ));
+        Ok(token)
+    }
+}
+
+/// KMS's JSON response, or its error message
+async fn read_response(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        let message = body["message"]
+            .as_str()
+            .or_else(|| body["Message"].as_str())
+            .or_else(|| body["error"]["message"].as_str())
+            .unwrap_or("no details");
+        anyhow::bail!("KMS refused the request ({}): {}", status, message);
+    }
+    Ok(body)
+}
+
+/// The secp256k1 key of a DER `SubjectPublicKeyInfo`, whose last 65 bytes are the
+/// uncompressed point
+pub fn public_key_from_spki(spki: &[u8]) -> Result<VerifyingKey> {
+    let point = spki
+        .len()
+        .checked_sub(65)
+        .map(|start| &spki[start..])
+        .filter(|point| point[0] == 0x04)
+        .context("The KMS key is not an uncompressed secp256k1 public key")?;
+    VerifyingKey::from_sec1_bytes(point).context("The KMS key is not a secp256k1 key")
+}
+
+/// Read one DER INTEGER from the front of `der`, returning it and the rest
+fn der_integer(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    if tag != 0x02 || len as usize > rest.len() || len > 33 {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len as usize);
+    // A leading zero only keeps the integer positive
+    let value = match value {
+        [0, tail @ ..] => tail,
+        value => value,
+    };
+    (value.len() <= 32).then_some((value, rest))
+}
+
+fn left_pad(value: &[u8]) -> [u8; 32] {
+    let mut padded = [0u8; 32];
+    padded[32 - value.len()..].copy_from_slice(value);
+    padded
+}
+
+/// Turn KMS's DER `SEQUENCE { r INTEGER, s INTEGER }` over `digest` into an r, s, v
+/// signature: s is normalized to the lower half of the curve order, as Ethereum
+/// requires, and v is the recovery id, 0 or 1, under which it recovers `key`
+pub fn rsv_from_der(der: &[u8], digest: H256, key: &VerifyingKey) -> Result<Signature> {
+    let parsed = (|| {
+        let (&tag, rest) = der.split_first()?;
+        let (&len, body) = rest.split_first()?;
+        if tag != 0x30 || len as usize != body.len() {
+            return None;
+        }
+        let (r, rest) = der_integer(body)?;
+        let (s, rest) = der_integer(rest)?;
+        rest.is_empty().then_some((left_pad(r), left_pad(s)))
+    })();
+    let (r, s) = parsed.context("KMS returned a signature that is not DER-encoded ECDSA")?;
+    let signature =
+        EcdsaSignature::from_scalars(r, s).context("KMS returned an invalid ECDSA signature")?;
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    let recovery_id = (0u8..=1)
+        .filter_map(RecoveryId::from_byte)
+        .find(|id| {
+            VerifyingKey::recover_from_prehash(digest.as_bytes(), &signature, *id)
+                .is_ok_and(|recovered| recovered == *key)
+        })
+        .context("The KMS signature does not recover to the key's address")?;
+    Ok(Signature {
+        r: U256::from_big_endian(&signature.r().to_bytes()),
+        s: U256::from_big_endian(&signature.s().to_bytes()),
+        v: u8::from(recovery_id) as u64,
+    })
+}
+
+/// Headers of a SigV4-signed KMS request
+struct SignedAwsRequest {
+    amz_date: String,
+    authorization: String,
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Sign a KMS JSON request to `host` at unix time `now` with AWS Signature Version 4
+fn sign_aws_request(
+    credentials: &AwsCredentials,
+    region: &str,
+    host: &str,
+    target: &str,
+    body: &str,
+    now: u64,
+) -> SignedAwsRequest {
+    let (year, month, day) = civil_from_days((now / 86_400) as i64);
+    let seconds = now % 86_400;
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let amz_date = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host.to_string()),
+        ("x-amz-date", amz_date.clone()),
+        ("x-amz-target", target.to_string()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.expose_secret().to_string()));
+    }
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers,
+        signed_headers,
+        HEXLOWER.encode(&Sha256::digest(body.as_bytes()))
+    );
+
+    let scope = format!("{}/{}/kms/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        HEXLOWER.encode(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let secret = format!("AWS4{}", credentials.secret_access_key.expose_secret());
+    let key = ["kms", "aws4_request"].iter().fold(
+        hmac_sha256(&hmac_sha256(secret.as_bytes(), &date), region),
+        |key, part| hmac_sha256(&key, part),
+    );
+    let signature = HEXLOWER.encode(&hmac_sha256(&key, &string_to_sign));
+
+    SignedAwsRequest {
+        authorization: format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+        amz_date,
+    }
+}
+
+#[async_trait]
+impl Signer for KmsSigner {
+    type Error = KmsError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let mut signature = self.sign_digest(hash_message(message)).await?;
+        signature.v += 27;
+        Ok(signature)
+    }
+
+    /// Signs with the transaction's chain id, or the signer's when it has none, and
+    /// sets v as EIP-155 prescribes for that chain
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or(self.chain_id);
+        let mut tx = tx.clone();
+        tx.set_chain_id(chain_id);
+        let mut signature = self.sign_digest(tx.sighash()).await?;
+        signature.v = to_eip155_v(signature.v as u8, chain_id);
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        let digest = payload
+            .encode_eip712()
+            .map_err(|e| anyhow::anyhow!("Failed to encode typed data: {}", e))?;
+        let mut signature = self.sign_digest(H256::from(digest)).await?;
+        signature.v += 27;
+        Ok(signature)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
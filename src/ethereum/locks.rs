@@ -0,0 +1,423 @@
+use crate::ethereum::streams::{PaymentStream, StreamFlow};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use serde::Serialize;
+use std::sync::Arc;
+
+// Lockers on Ethereum mainnet
+const UNICRYPT_V2_LOCKER: &str = "0x663A5C229c09b049E36dCc11a9B0d4a8Eb9db214";
+const TEAM_FINANCE_LOCKER: &str = "0xE2fE530C047f2d85298b07D9333C05737f1435fB";
+
+/// Sablier V2.1 LockupLinear on Ethereum mainnet, which create_stream streams through
+pub const SABLIER_V2_LOCKUP_LINEAR: &str = "0xAFb979d9afAd1aD27C5eFf4E27226E3AB9e5dCC9";
+
+/// Blocks of Sablier stream creations searched by default, about a year
+pub const DEFAULT_STREAM_LOOKBACK_BLOCKS: u64 = 2_600_000;
+
+// Bounds the calls one query makes against a locker
+const MAX_LOCKS_PER_QUERY: usize = 100;
+
+abigen!(
+    IUnicryptLocker,
+    r#"[
+        function getNumLocksForToken(address lpToken) external view returns (uint256)
+        function tokenLocks(address lpToken, uint256 index) external view returns (uint256 lockDate, uint256 amount, uint256 initialAmount, uint256 unlockDate, uint256 lockID, address owner)
+        function getUserNumLockedTokens(address user) external view returns (uint256)
+        function getUserLockedTokenAtIndex(address user, uint256 index) external view returns (address)
+        function getUserNumLocksForToken(address user, address lpToken) external view returns (uint256)
+        function getUserLockForTokenAtIndex(address user, address lpToken, uint256 index) external view returns (uint256, uint256, uint256, uint256, uint256, address)
+    ]"#
+);
+
+abigen!(
+    ITeamFinanceLocker,
+    r#"[
+        function getDepositsByTokenAddress(address token) external view returns (uint256[])
+        function getDepositsByWithdrawalAddress(address withdrawalAddress) external view returns (uint256[])
+        function lockedToken(uint256 id) external view returns (address tokenAddress, address withdrawalAddress, uint256 tokenAmount, uint256 unlockTime, bool withdrawn)
+    ]"#
+);
+
+abigen!(
+    ISablierLockupLinear,
+    r#"[
+        function getWithdrawnAmount(uint256 streamId) external view returns (uint128)
+        function statusOf(uint256 streamId) external view returns (uint8)
+    ]"#
+);
+
+/// How a lock releases its tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Release {
+    /// Everything at once at `unlock_at`
+    Cliff { unlock_at: u64 },
+    /// Linearly from `start` to `end`, with nothing claimable before `cliff`
+    Linear { start: u64, cliff: u64, end: u64 },
+}
+
+/// Tokens held by a locker or vesting contract on someone's behalf
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenLock {
+    pub locker: String,
+    pub lock_id: String,
+    pub token: Address,
+    /// Who can withdraw the tokens once they unlock
+    pub owner: Address,
+    pub deposited: U256,
+    pub withdrawn: U256,
+    pub release: Release,
+}
+
+impl TokenLock {
+    /// Tokens of the deposit released by time `at`, withdrawn or not
+    pub fn unlocked_at(&self, at: u64) -> U256 {
+        match self.release {
+            Release::Cliff { unlock_at } if at >= unlock_at => self.deposited,
+            Release::Cliff { .. } => U256::zero(),
+            Release::Linear { cliff, .. } if at < cliff => U256::zero(),
+            Release::Linear { end, .. } if at >= end => self.deposited,
+            Release::Linear { start, end, .. } => {
+                self.deposited * U256::from(at.saturating_sub(start)) / U256::from(end - start)
+            }
+        }
+    }
+
+    /// Tokens still locked at time `at`
+    pub fn locked_at(&self, at: u64) -> U256 {
+        self.deposited.saturating_sub(self.unlocked_at(at))
+    }
+
+    /// When the last of the deposit unlocks
+    pub fn fully_unlocked_at(&self) -> u64 {
+        match self.release {
+            Release::Cliff { unlock_at } => unlock_at,
+            Release::Linear { end, .. } => end,
+        }
+    }
+}
+
+/// A locker or vesting contract whose positions can be listed by token and by owner
+#[async_trait]
+pub trait LockSource: Send + Sync {
+    /// Short identifier such as `unicrypt` or `sablier`
+    fn name(&self) -> &str;
+
+    /// Open locks of `token`
+    async fn locks_for_token(&self, token: Address) -> Result<Vec<TokenLock>>;
+
+    /// Open locks `owner` can withdraw from
+    async fn locks_for_owner(&self, owner: Address) -> Result<Vec<TokenLock>>;
+}
+
+/// Unicrypt's V2 liquidity locker, which holds Uniswap V2 LP tokens
+pub struct UnicryptLocker {
+    contract: IUnicryptLocker<Provider<Http>>,
+}
+
+impl UnicryptLocker {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            contract: IUnicryptLocker::new(
+                UNICRYPT_V2_LOCKER
+                    .parse::<Address>()
+                    .context("Invalid Unicrypt locker address")?,
+                provider,
+            ),
+        })
+    }
+
+    fn lock(
+        &self,
+        lp_token: Address,
+        (_, amount, initial_amount, unlock_date, lock_id, owner): (
+            U256,
+            U256,
+            U256,
+            U256,
+            U256,
+            Address,
+        ),
+    ) -> Option<TokenLock> {
+        if amount.is_zero() {
+            return None;
+        }
+        Some(TokenLock {
+            locker: self.name().to_string(),
+            lock_id: lock_id.to_string(),
+            token: lp_token,
+            owner,
+            deposited: initial_amount,
+            withdrawn: initial_amount.saturating_sub(amount),
+            release: Release::Cliff {
+                unlock_at: unlock_date.low_u64(),
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl LockSource for UnicryptLocker {
+    fn name(&self) -> &str {
+        "unicrypt"
+    }
+
+    async fn locks_for_token(&self, token: Address) -> Result<Vec<TokenLock>> {
+        let count = self
+            .contract
+            .get_num_locks_for_token(token)
+            .call()
+            .await
+            .context("Failed to count Unicrypt locks")?;
+        let mut locks = Vec::new();
+        for index in 0..count.low_u64().min(MAX_LOCKS_PER_QUERY as u64) {
+            let lock = self
+                .contract
+                .token_locks(token, index.into())
+                .call()
+                .await
+                .context("Failed to read Unicrypt lock")?;
+            locks.extend(self.lock(token, lock));
+        }
+        Ok(locks)
+    }
+
+    async fn locks_for_owner(&self, owner: Address) -> Result<Vec<TokenLock>> {
+        let tokens = self
+            .contract
+            .get_user_num_locked_tokens(owner)
+            .call()
+            .await
+            .context("Failed to count Unicrypt locked tokens")?;
+        let mut locks = Vec::new();
+        for index in 0..tokens.low_u64() {
+            let token = self
+                .contract
+                .get_user_locked_token_at_index(owner, index.into())
+                .call()
+                .await
+                .context("Failed to read Unicrypt locked token")?;
+            let count = self
+                .contract
+                .get_user_num_locks_for_token(owner, token)
+                .call()
+                .await
+                .context("Failed to count Unicrypt locks")?;
+            for lock_index in 0..count.low_u64() {
+                if locks.len() == MAX_LOCKS_PER_QUERY {
+                    return Ok(locks);
+                }
+                let lock = self
+                    .contract
+                    .get_user_lock_for_token_at_index(owner, token, lock_index.into())
+                    .call()
+                    .await
+                    .context("Failed to read Unicrypt lock")?;
+                locks.extend(self.lock(token, lock));
+            }
+        }
+        Ok(locks)
+    }
+}
+
+/// Team Finance's token locker
+pub struct TeamFinanceLocker {
+    contract: ITeamFinanceLocker<Provider<Http>>,
+}
+
+impl TeamFinanceLocker {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            contract: ITeamFinanceLocker::new(
+                TEAM_FINANCE_LOCKER
+                    .parse::<Address>()
+                    .context("Invalid Team Finance locker address")?,
+                provider,
+            ),
+        })
+    }
+
+    async fn deposits(&self, ids: Vec<U256>) -> Result<Vec<TokenLock>> {
+        let mut locks = Vec::new();
+        // Latest deposits first; older ones are likelier to be withdrawn
+        for id in ids.into_iter().rev().take(MAX_LOCKS_PER_QUERY) {
+            let (token, owner, amount, unlock_time, withdrawn) = self
+                .contract
+                .locked_token(id)
+                .call()
+                .await
+                .context("Failed to read Team Finance lock")?;
+            if withdrawn || amount.is_zero() {
+                continue;
+            }
+            locks.push(TokenLock {
+                locker: self.name().to_string(),
+                lock_id: id.to_string(),
+                token,
+                owner,
+                deposited: amount,
+                withdrawn: U256::zero(),
+                release: Release::Cliff {
+                    unlock_at: unlock_time.low_u64(),
+                },
+            });
+        }
+        Ok(locks)
+    }
+}
+
+#[async_trait]
+impl LockSource for TeamFinanceLocker {
+    fn name(&self) -> &str {
+        "team_finance"
+    }
+
+    async fn locks_for_token(&self, token: Address) -> Result<Vec<TokenLock>> {
+        let ids = self
+            .contract
+            .get_deposits_by_token_address(token)
+            .call()
+            .await
+            .context("Failed to list Team Finance locks")?;
+        self.deposits(ids).await
+    }
+
+    async fn locks_for_owner(&self, owner: Address) -> Result<Vec<TokenLock>> {
+        let ids = self
+            .contract
+            .get_deposits_by_withdrawal_address(owner)
+            .call()
+            .await
+            .context("Failed to list Team Finance locks")?;
+        self.deposits(ids).await
+    }
+}
+
+/// Sablier V2.1 linear vesting streams, found through their creation events
+pub struct SablierStreams {
+    provider: Arc<Provider<Http>>,
+    contract: ISablierLockupLinear<Provider<Http>>,
+    lookback_blocks: u64,
+}
+
+impl SablierStreams {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            contract: ISablierLockupLinear::new(
+                SABLIER_V2_LOCKUP_LINEAR
+                    .parse::<Address>()
+                    .context("Invalid Sablier lockup address")?,
+                Arc::clone(&provider),
+            ),
+            provider,
+            lookback_blocks: DEFAULT_STREAM_LOOKBACK_BLOCKS,
+        })
+    }
+
+    /// Search stream creations over the last `blocks` blocks
+    pub fn with_lookback_blocks(mut self, blocks: u64) -> Self {
+        self.lookback_blocks = blocks;
+        self
+    }
+
+    /// Open streams created with `filter` applied to their indexed sender, recipient or asset
+    pub(super) async fn linear_streams(&self, filter: Filter) -> Result<Vec<PaymentStream>> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?
+            .as_u64();
+        let filter = filter
+            .address(self.contract.address())
+            .topic0(H256::from(keccak256(
+                "CreateLockupLinearStream(uint256,address,address,address,(uint128,uint128),address,bool,bool,(uint40,uint40,uint40),address)",
+            )))
+            .from_block(head.saturating_sub(self.lookback_blocks))
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch Sablier streams")?;
+
+        let mut streams = Vec::new();
+        for log in logs.iter().rev().take(MAX_LOCKS_PER_QUERY) {
+            // sender, recipient and asset are indexed; the data holds streamId, funder,
+            // (deposit, brokerFee), cancelable, transferable, (start, cliff, end), broker
+            let words: Vec<U256> = log
+                .data
+                .chunks(32)
+                .filter(|word| word.len() == 32)
+                .map(U256::from_big_endian)
+                .collect();
+            let (
+                [_, sender, recipient, asset],
+                [stream_id, _, deposit, _, cancelable, _, start, cliff, end, ..],
+            ) = (&log.topics[..], &words[..])
+            else {
+                continue;
+            };
+            let stream_id = *stream_id;
+            // Canceled (3) and depleted (4) streams hold nothing more for the recipient
+            let status = self
+                .contract
+                .status_of(stream_id)
+                .call()
+                .await
+                .context("Failed to read Sablier stream status")?;
+            if status >= 3 || end <= start {
+                continue;
+            }
+            let withdrawn = self
+                .contract
+                .get_withdrawn_amount(stream_id)
+                .call()
+                .await
+                .context("Failed to read Sablier withdrawn amount")?;
+            streams.push(PaymentStream {
+                protocol: self.name().to_string(),
+                stream_id: Some(stream_id.to_string()),
+                token: Address::from(*asset),
+                sender: Address::from(*sender),
+                recipient: Address::from(*recipient),
+                flow: StreamFlow::Linear {
+                    deposit: *deposit,
+                    withdrawn: U256::from(withdrawn),
+                    start: start.low_u64(),
+                    cliff: cliff.low_u64(),
+                    end: end.low_u64(),
+                    // Still cancelable only while it has not settled (2)
+                    cancelable: !cancelable.is_zero() && status < 2,
+                },
+            });
+        }
+        Ok(streams)
+    }
+
+    async fn streams(&self, filter: Filter) -> Result<Vec<TokenLock>> {
+        Ok(self
+            .linear_streams(filter)
+            .await?
+            .iter()
+            .filter_map(PaymentStream::as_lock)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl LockSource for SablierStreams {
+    fn name(&self) -> &str {
+        "sablier"
+    }
+
+    async fn locks_for_token(&self, token: Address) -> Result<Vec<TokenLock>> {
+        self.streams(Filter::new().topic3(H256::from(token))).await
+    }
+
+    async fn locks_for_owner(&self, owner: Address) -> Result<Vec<TokenLock>> {
+        self.streams(Filter::new().topic2(H256::from(owner))).await
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+// Liquid staking tokens on Ethereum mainnet
+const STETH: &str = "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84";
+const RETH: &str = "0xae78736Cd615f374D3085123A210448E74Fc6393";
+const CBETH: &str = "0xBe9895146f7AF43049ca1c1AE358B0541Ea49704";
+const FRXETH: &str = "0x5E8422345238F34275888049021821E8E08CAa1f";
+// Staked frxETH, the vault frxETH earns its yield in
+const SFRXETH: &str = "0xac3E018457B222d93114458476f3E3416Abbe38F";
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+// About one week of blocks, the window rate growth is measured over
+const LST_LOOKBACK_BLOCKS: u64 = 7 * 7200;
+
+abigen!(
+    ILidoStEth,
+    r#"[
+        function getPooledEthByShares(uint256 sharesAmount) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IRocketTokenReth,
+    r#"[
+        function getExchangeRate() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    ICbEth,
+    r#"[
+        function exchangeRate() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IStakedFrxEth,
+    r#"[
+        function convertToAssets(uint256 shares) external view returns (uint256)
+    ]"#
+);
+
+/// A liquid staking token and the ETH it redeems for
+#[async_trait]
+pub trait LiquidStakingToken: Send + Sync {
+    /// Token symbol such as `rETH`
+    fn symbol(&self) -> &str;
+
+    fn token(&self) -> Address;
+
+    /// ETH one token redeems for; 1 for rebasing tokens
+    async fn exchange_rate(&self) -> Result<Decimal>;
+
+    /// Staking APR in percent, from how fast the token accrues rewards
+    async fn apr(&self) -> Result<Decimal>;
+}
+
+/// Where a token's staking rewards show up
+#[derive(Debug, Clone, Copy)]
+enum Accrual {
+    /// stETH rebases; rewards grow the ETH behind each Lido share
+    LidoShares,
+    /// rETH appreciates against ETH
+    RocketPool,
+    /// cbETH appreciates against ETH
+    Coinbase,
+    /// frxETH holds its peg; rewards go to the sfrxETH vault
+    StakedFrax(Address),
+}
+
+/// One of the mainnet liquid staking tokens, read from its own contracts
+pub struct LstContract {
+    provider: Arc<Provider<Http>>,
+    symbol: &'static str,
+    token: Address,
+    accrual: Accrual,
+}
+
+impl LstContract {
+    /// stETH, rETH, cbETH and frxETH
+    pub fn mainnet(provider: Arc<Provider<Http>>) -> Result<Vec<Self>> {
+        let address = |address: &str, symbol: &str| {
+            address
+                .parse::<Address>()
+                .with_context(|| format!("Invalid {} address", symbol))
+        };
+        let lst = |symbol, token, accrual| -> Result<Self> {
+            Ok(Self {
+                provider: Arc::clone(&provider),
+                symbol,
+                token: address(token, symbol)?,
+                accrual,
+            })
+        };
+        Ok(vec![
+            lst("stETH", STETH, Accrual::LidoShares)?,
+            lst("rETH", RETH, Accrual::RocketPool)?,
+            lst("cbETH", CBETH, Accrual::Coinbase)?,
+            lst(
+                "frxETH",
+                FRXETH,
+                Accrual::StakedFrax(address(SFRXETH, "sfrxETH")?),
+            )?,
+        ])
+    }
+
+    /// Wei one unit of the accruing share is worth at `block`
+    async fn accrual_rate(&self, block: u64) -> Result<U256> {
+        let one = U256::exp10(18);
+        let provider = Arc::clone(&self.provider);
+        let rate = match self.accrual {
+            Accrual::LidoShares => {
+                ILidoStEth::new(self.token, provider)
+                    .get_pooled_eth_by_shares(one)
+                    .block(block)
+                    .call()
+                    .await
+            }
+            Accrual::RocketPool => {
+                IRocketTokenReth::new(self.token, provider)
+                    .get_exchange_rate()
+                    .block(block)
+                    .call()
+                    .await
+            }
+            Accrual::Coinbase => {
+                ICbEth::new(self.token, provider)
+                    .exchange_rate()
+                    .block(block)
+                    .call()
+                    .await
+            }
+            Accrual::StakedFrax(vault) => {
+                IStakedFrxEth::new(vault, provider)
+                    .convert_to_assets(one)
+                    .block(block)
+                    .call()
+                    .await
+            }
+        };
+        rate.with_context(|| format!("Failed to read the {} exchange rate", self.symbol))
+    }
+
+    fn rebases(&self) -> bool {
+        matches!(self.accrual, Accrual::LidoShares | Accrual::StakedFrax(_))
+    }
+}
+
+#[async_trait]
+impl LiquidStakingToken for LstContract {
+    fn symbol(&self) -> &str {
+        self.symbol
+    }
+
+    fn token(&self) -> Address {
+        self.token
+    }
+
+    async fn exchange_rate(&self) -> Result<Decimal> {
+        if self.rebases() {
+            return Ok(Decimal::ONE);
+        }
+        let latest = self.provider.get_block_number().await?.as_u64();
+        let rate = self.accrual_rate(latest).await?;
+        Ok(Decimal::from_i128_with_scale(rate.as_u128() as i128, 18))
+    }
+
+    async fn apr(&self) -> Result<Decimal> {
+        let latest = self.provider.get_block_number().await?.as_u64();
+        let then = latest.saturating_sub(LST_LOOKBACK_BLOCKS);
+        let rate_now = self.accrual_rate(latest).await?;
+        // Historical state needs an archive node
+        let rate_then = self.accrual_rate(then).await.with_context(|| {
+            format!(
+                "Failed to read the past {} rate (archive node required)",
+                self.symbol
+            )
+        })?;
+        if rate_then.is_zero() {
+            anyhow::bail!("{} had no exchange rate a week ago", self.symbol);
+        }
+
+        let growth = rate_now.to_string().parse::<f64>()? / rate_then.to_string().parse::<f64>()?;
+        // 12 second blocks
+        let window_secs = (latest - then) as f64 * 12.0;
+        let apr = (growth - 1.0) * SECONDS_PER_YEAR as f64 / window_secs * 100.0;
+        Decimal::from_f64(apr)
+            .map(|apr| apr.round_dp(4))
+            .with_context(|| format!("{} APR is out of range", self.symbol))
+    }
+}
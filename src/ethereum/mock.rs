@@ -1,28 +1,121 @@
 // Mock implementations for testing
-use crate::ethereum::client::EthereumClientTrait;
+use crate::ethereum::beacon::{BeaconSource, Validator};
+use crate::ethereum::bytecode::SignatureSource;
+use crate::ethereum::client::{
+    ApprovalSimulation, BaseFeeHistory, BlockInfo, Delegation, EthereumClientTrait,
+    PendingTransaction, TransactionOutcome,
+};
+use crate::ethereum::eigenlayer::{RestakingPosition, RestakingSource};
+use crate::ethereum::ens::{EnsName, EnsSource};
+use crate::ethereum::escalation::GasEscalation;
+use crate::ethereum::fx::{FxRate, FxRateSource};
+use crate::ethereum::governance::{GovernanceSource, Proposal, ProposalState, VotingPower};
+use crate::ethereum::history::{BeaconWithdrawal, HistoricalTransaction, TransactionHistorySource};
+use crate::ethereum::locks::{LockSource, TokenLock};
+use crate::ethereum::lsts::LiquidStakingToken;
+use crate::ethereum::nft::{CollectionStats, NftMarketSource};
+use crate::ethereum::options::{OptionQuote, OptionsVenue};
+use crate::ethereum::perps::{PerpMarket, PerpPosition, PerpVenue};
+use crate::ethereum::pool_activity::{PoolActivitySource, PoolState, SwapAmounts};
+use crate::ethereum::pools::{FactoryKind, PoolDiscoveryTrait, PoolInfo, PoolLiquidity};
+use crate::ethereum::price_feeds::{ReferencePrice, ReferencePriceSource};
+use crate::ethereum::simulation::{native_transfer_address, SimulatedCall};
+use crate::ethereum::sponsor::Sponsorship;
+use crate::ethereum::streams::{PaymentStream, StreamSource};
+use crate::ethereum::token_events::{
+    AdminEvent, LiquidityRemoval, TokenEventSource, TokenTransfer,
+};
 use crate::ethereum::uniswap::SwapSimulation;
 use crate::ethereum::uniswap::UniswapRouterTrait;
+use crate::ethereum::uniswap::{PoolReserves, PoolSwap, PricePoint};
+use crate::ethereum::uniswap_v3::{UniswapV3Trait, V3InitializedTick, V3PoolState, V3Quote};
+use crate::ethereum::venues::PriceVenue;
+use crate::ethereum::yields::YieldSource;
 use anyhow::Result;
 use async_trait::async_trait;
 use ethers::prelude::*;
+use ethers::providers::call_raw::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// Mock Ethereum client for testing
 pub struct MockEthereumClient {
     eth_balances: HashMap<Address, Decimal>,
+    eth_balances_at: HashMap<(Address, u64), Decimal>, // (address, block) -> balance
     token_balances: HashMap<(Address, Address), (Decimal, u8)>, // (token, wallet) -> (balance, decimals)
     token_symbols: HashMap<Address, String>,
+    token_decimals: HashMap<Address, u8>,
+    block_number: u64,
+    block_timestamp: u64,
+    safe_block: Option<u64>,
+    finalized_block: Option<u64>,
+    gas_price: U256,
+    /// Base fee of each block, by number
+    base_fee_curve: Option<fn(u64) -> U256>,
+    allowances: HashMap<(Address, Address, Address), U256>, // (token, owner, spender) -> allowance
+    permit_tokens: Vec<Address>,
+    failing_targets: Vec<Address>,
+    sent_transactions: Mutex<Vec<TypedTransaction>>,
+    escalations: Mutex<Vec<Option<GasEscalation>>>,
+    nonces: HashMap<Address, (u64, u64)>,
+    chain_id: u64,
+    sent_raw_transactions: Mutex<Vec<Bytes>>,
+    pending_transactions: HashMap<Address, Vec<PendingTransaction>>,
+    codeless: Vec<Address>,
+    delegations: HashMap<(Address, Address), Delegation>, // (token, account) -> delegation
+    call_results: HashMap<(Address, Bytes), Bytes>,       // (contract, calldata) -> output
+    balance_slots: HashMap<Address, u64>,                 // token -> slot of its balances mapping
+    calls: Mutex<Vec<(TypedTransaction, Option<spoof::State>)>>,
+    storage: HashMap<(Address, H256), H256>,
+    code: HashMap<Address, Bytes>,
     wallet_address: Address,
+    unavailable: bool,
+    sponsorship: Option<Sponsorship>,
+    simulated_logs: HashMap<Address, Vec<Log>>, // target -> logs a simulated call emits
+}
+
+impl Default for MockEthereumClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MockEthereumClient {
     pub fn new() -> Self {
         Self {
             eth_balances: HashMap::new(),
+            eth_balances_at: HashMap::new(),
             token_balances: HashMap::new(),
             token_symbols: HashMap::new(),
+            token_decimals: HashMap::new(),
+            block_number: 0,
+            block_timestamp: 0,
+            safe_block: None,
+            finalized_block: None,
+            gas_price: U256::from(30_000_000_000u64), // 30 gwei
+            base_fee_curve: None,
+            allowances: HashMap::new(),
+            permit_tokens: Vec::new(),
+            failing_targets: Vec::new(),
+            sent_transactions: Mutex::new(Vec::new()),
+            escalations: Mutex::new(Vec::new()),
+            nonces: HashMap::new(),
+            chain_id: 1,
+            sent_raw_transactions: Mutex::new(Vec::new()),
+            pending_transactions: HashMap::new(),
+            codeless: Vec::new(),
+            delegations: HashMap::new(),
+            call_results: HashMap::new(),
+            balance_slots: HashMap::new(),
+            calls: Mutex::new(Vec::new()),
+            storage: HashMap::new(),
+            code: HashMap::new(),
             wallet_address: Address::zero(),
+            unavailable: false,
+            sponsorship: None,
+            simulated_logs: HashMap::new(),
         }
     }
 
@@ -35,6 +128,12 @@ impl MockEthereumClient {
         self
     }
 
+    /// `address` held `balance` as of `block`, when read at that block
+    pub fn with_eth_balance_at(mut self, address: Address, block: u64, balance: Decimal) -> Self {
+        self.eth_balances_at.insert((address, block), balance);
+        self
+    }
+
     pub fn with_token_balance(
         mut self,
         token: Address,
@@ -52,7 +151,180 @@ impl MockEthereumClient {
         self
     }
 
+    pub fn with_token_decimals(mut self, token: Address, decimals: u8) -> Self {
+        self.token_decimals.insert(token, decimals);
+        self
+    }
+
+    /// Make `token` an ERC20Votes token on which `account` has `delegation`
+    pub fn with_delegation(
+        mut self,
+        token: Address,
+        account: Address,
+        delegation: Delegation,
+    ) -> Self {
+        self.delegations.insert((token, account), delegation);
+        self
+    }
+
+    pub fn with_block_number(mut self, block_number: u64) -> Self {
+        self.block_number = block_number;
+        self
+    }
+
+    pub fn with_block_timestamp(mut self, timestamp: u64) -> Self {
+        self.block_timestamp = timestamp;
+        self
+    }
+
+    /// Safe and finalized heads; by default both are the latest block
+    pub fn with_finalized_blocks(mut self, safe: u64, finalized: u64) -> Self {
+        self.safe_block = Some(safe);
+        self.finalized_block = Some(finalized);
+        self
+    }
+
+    pub fn with_gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    /// Charge `curve(n)` as the base fee of block `n`
+    pub fn with_base_fee_curve(mut self, curve: fn(u64) -> U256) -> Self {
+        self.base_fee_curve = Some(curve);
+        self
+    }
+
+    pub fn with_allowance(
+        mut self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        allowance: U256,
+    ) -> Self {
+        self.allowances.insert((token, owner, spender), allowance);
+        self
+    }
+
+    pub fn with_permit_support(mut self, token: Address) -> Self {
+        self.permit_tokens.push(token);
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Raw transactions passed to `send_raw_transaction`, in order
+    pub fn sent_raw_transactions(&self) -> Vec<Bytes> {
+        self.sent_raw_transactions.lock().unwrap().clone()
+    }
+
+    pub fn with_nonces(mut self, address: Address, latest: u64, pending: u64) -> Self {
+        self.nonces.insert(address, (latest, pending));
+        self
+    }
+
+    /// Expose a mempool for `address`; without this the mempool is unavailable
+    pub fn with_pending_transactions(
+        mut self,
+        address: Address,
+        transactions: Vec<PendingTransaction>,
+    ) -> Self {
+        self.pending_transactions.insert(address, transactions);
+        self
+    }
+
+    /// Make gas estimation and sending fail for transactions sent to `target`
+    pub fn with_failing_target(mut self, target: Address) -> Self {
+        self.failing_targets.push(target);
+        self
+    }
+
+    /// Have simulated calls to `target` emit `logs`, after the traced ETH transfer of
+    /// any value they carry
+    pub fn with_simulated_logs(mut self, target: Address, logs: Vec<Log>) -> Self {
+        self.simulated_logs.insert(target, logs);
+        self
+    }
+
+    /// Fail every balance and block read, like an unreachable RPC endpoint
+    pub fn with_unavailable_rpc(mut self) -> Self {
+        self.unavailable = true;
+        self
+    }
+
+    /// Have every sent transaction's gas paid by a sponsor, reported as `sponsorship`
+    pub fn with_sponsorship(mut self, sponsorship: Sponsorship) -> Self {
+        self.sponsorship = Some(sponsorship);
+        self
+    }
+
+    /// Report no contract code at `address`; every other address has code
+    pub fn with_no_code(mut self, address: Address) -> Self {
+        self.codeless.push(address);
+        self
+    }
+
+    /// Answer `eth_call`s of exactly `data` on `contract` with `output`
+    pub fn with_call_result(mut self, contract: Address, data: Bytes, output: Bytes) -> Self {
+        self.call_results.insert((contract, data), output);
+        self
+    }
+
+    /// Serve `balanceOf` on `token` from a Solidity `mapping(address => uint256)` at
+    /// `slot`, so storage overrides of it show up in calls
+    pub fn with_balance_slot(mut self, token: Address, slot: u64) -> Self {
+        self.balance_slots.insert(token, slot);
+        self
+    }
+
+    /// Store `value` at `slot` of `address`; unset slots read zero
+    pub fn with_storage(mut self, address: Address, slot: H256, value: H256) -> Self {
+        self.storage.insert((address, slot), value);
+        self
+    }
+
+    /// Deploy `code` at `address`
+    pub fn with_code(mut self, address: Address, code: Bytes) -> Self {
+        self.code.insert(address, code);
+        self
+    }
+
+    /// Transactions and override sets passed to `call_contract`, in order
+    pub fn calls(&self) -> Vec<(TypedTransaction, Option<spoof::State>)> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Transactions passed to `send_transaction`, in order
+    pub fn sent_transactions(&self) -> Vec<TypedTransaction> {
+        self.sent_transactions.lock().unwrap().clone()
+    }
+
+    /// Escalation policy passed with each sent transaction, in order
+    pub fn escalations(&self) -> Vec<Option<GasEscalation>> {
+        self.escalations.lock().unwrap().clone()
+    }
+
+    fn check_available(&self) -> Result<()> {
+        if self.unavailable {
+            anyhow::bail!("connection refused");
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, tx: &TypedTransaction) -> Result<()> {
+        match tx.to_addr() {
+            Some(to) if self.failing_targets.contains(to) => {
+                anyhow::bail!("execution reverted")
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn get_eth_balance(&self, address: Address) -> Result<Decimal> {
+        self.check_available()?;
         Ok(self
             .eth_balances
             .get(&address)
@@ -65,6 +337,7 @@ impl MockEthereumClient {
         token_address: Address,
         wallet_address: Address,
     ) -> Result<(Decimal, u8)> {
+        self.check_available()?;
         Ok(self
             .token_balances
             .get(&(token_address, wallet_address))
@@ -79,6 +352,100 @@ impl MockEthereumClient {
             .cloned()
             .unwrap_or_else(|| "UNKNOWN".to_string()))
     }
+
+    pub async fn get_token_decimals(&self, token_address: Address) -> Result<u8> {
+        Ok(self
+            .token_decimals
+            .get(&token_address)
+            .copied()
+            .unwrap_or(18))
+    }
+
+    pub async fn get_block_number(&self) -> Result<u64> {
+        self.check_available()?;
+        Ok(self.block_number)
+    }
+
+    pub async fn get_block(&self, tag: BlockNumber) -> Result<BlockInfo> {
+        let number = match tag {
+            BlockNumber::Safe => self.safe_block.unwrap_or(self.block_number),
+            BlockNumber::Finalized => self.finalized_block.unwrap_or(self.block_number),
+            BlockNumber::Number(number) => number.as_u64(),
+            _ => self.block_number,
+        };
+        Ok(BlockInfo {
+            number,
+            timestamp: self.block_timestamp,
+        })
+    }
+
+    pub async fn get_eth_balance_at(
+        &self,
+        address: Address,
+        block: Option<u64>,
+    ) -> Result<Decimal> {
+        match block.and_then(|block| self.eth_balances_at.get(&(address, block))) {
+            Some(balance) => {
+                self.check_available()?;
+                Ok(*balance)
+            }
+            None => self.get_eth_balance(address).await,
+        }
+    }
+
+    pub async fn get_latest_block(&self) -> Result<BlockInfo> {
+        Ok(BlockInfo {
+            number: self.block_number,
+            timestamp: self.block_timestamp,
+        })
+    }
+
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        Ok(self.gas_price)
+    }
+
+    pub async fn get_base_fee_history(
+        &self,
+        newest_block: u64,
+        block_count: u64,
+    ) -> Result<BaseFeeHistory> {
+        let curve = self
+            .base_fee_curve
+            .ok_or_else(|| anyhow::anyhow!("Fee history not available"))?;
+        let oldest_block = (newest_block + 1).saturating_sub(block_count);
+        let blocks = oldest_block..=newest_block;
+        Ok(BaseFeeHistory {
+            oldest_block,
+            base_fees: blocks.clone().map(curve).collect(),
+            next_base_fee: curve(newest_block + 1),
+            gas_used_ratios: blocks.map(|_| 0.5).collect(),
+        })
+    }
+
+    pub async fn simulate_approval(
+        &self,
+        token_address: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<ApprovalSimulation> {
+        let current_allowance = self
+            .allowances
+            .get(&(token_address, owner, spender))
+            .copied()
+            .unwrap_or_default();
+        let approval_required = current_allowance < amount;
+        Ok(ApprovalSimulation {
+            current_allowance,
+            approval_required,
+            gas_estimate: if approval_required {
+                U256::from(46_000u64)
+            } else {
+                U256::zero()
+            },
+            supports_permit: self.permit_tokens.contains(&token_address),
+        })
+    }
 }
 
 #[async_trait]
@@ -99,15 +466,273 @@ impl EthereumClientTrait for MockEthereumClient {
         self.get_token_symbol(token_address).await
     }
 
+    async fn get_token_decimals(&self, token_address: Address) -> Result<u8> {
+        self.get_token_decimals(token_address).await
+    }
+
+    async fn get_block_number(&self) -> Result<u64> {
+        self.get_block_number().await
+    }
+
+    async fn get_latest_block(&self) -> Result<BlockInfo> {
+        self.get_latest_block().await
+    }
+
+    async fn get_block(&self, tag: BlockNumber) -> Result<BlockInfo> {
+        self.get_block(tag).await
+    }
+
+    async fn get_eth_balance_at(&self, address: Address, block: Option<u64>) -> Result<Decimal> {
+        self.get_eth_balance_at(address, block).await
+    }
+
+    async fn get_token_balance_at(
+        &self,
+        token_address: Address,
+        wallet_address: Address,
+        _block: Option<u64>,
+    ) -> Result<(Decimal, u8)> {
+        self.get_token_balance(token_address, wallet_address).await
+    }
+
+    async fn get_gas_price(&self) -> Result<U256> {
+        self.get_gas_price().await
+    }
+
+    async fn get_base_fee_history(
+        &self,
+        newest_block: u64,
+        block_count: u64,
+    ) -> Result<BaseFeeHistory> {
+        self.get_base_fee_history(newest_block, block_count).await
+    }
+
+    async fn simulate_approval(
+        &self,
+        token_address: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<ApprovalSimulation> {
+        self.simulate_approval(token_address, owner, spender, amount)
+            .await
+    }
+
+    async fn estimate_transaction_gas(&self, tx: &TypedTransaction) -> Result<U256> {
+        self.check_target(tx)?;
+        Ok(U256::from(100_000u64))
+    }
+
+    async fn send_transaction(
+        &self,
+        tx: TypedTransaction,
+        escalation: Option<GasEscalation>,
+    ) -> Result<TransactionOutcome> {
+        self.check_target(&tx)?;
+        self.escalations.lock().unwrap().push(escalation);
+        let mut sent = self.sent_transactions.lock().unwrap();
+        sent.push(tx);
+        Ok(TransactionOutcome {
+            tx_hash: H256::from_low_u64_be(sent.len() as u64),
+            success: true,
+            gas_used: U256::from(100_000u64),
+            block_number: Some(self.block_number),
+            effective_gas_price: Some(self.gas_price),
+            replacements: Vec::new(),
+            sponsorship: self.sponsorship.clone(),
+        })
+    }
+
+    async fn gas_sponsored(&self, _tx: &TypedTransaction) -> bool {
+        self.sponsorship.is_some()
+    }
+
+    async fn estimate_fees(&self) -> Result<(U256, U256)> {
+        // Base fee headroom over the configured gas price, 1 gwei tip
+        Ok((self.gas_price * 2, U256::exp10(9)))
+    }
+
+    async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256> {
+        let hash = H256::from(ethers::utils::keccak256(&raw));
+        self.sent_raw_transactions.lock().unwrap().push(raw);
+        Ok(hash)
+    }
+
+    fn get_chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    async fn get_nonces(&self, address: Address) -> Result<(u64, u64)> {
+        Ok(self.nonces.get(&address).copied().unwrap_or_default())
+    }
+
+    async fn get_pending_transactions(
+        &self,
+        address: Address,
+    ) -> Result<Option<Vec<PendingTransaction>>> {
+        Ok(self.pending_transactions.get(&address).cloned())
+    }
+
+    async fn has_code(&self, address: Address) -> Result<bool> {
+        Ok(!self.codeless.contains(&address))
+    }
+
+    async fn get_delegation(&self, token_address: Address, account: Address) -> Result<Delegation> {
+        if let Some(delegation) = self.delegations.get(&(token_address, account)) {
+            return Ok(delegation.clone());
+        }
+        if self
+            .delegations
+            .keys()
+            .any(|(token, _)| *token == token_address)
+        {
+            return Ok(Delegation::default());
+        }
+        anyhow::bail!("Failed to get delegate; is this an ERC20Votes token?")
+    }
+
+    async fn get_code(&self, address: Address, _block: Option<u64>) -> Result<Bytes> {
+        self.check_available()?;
+        Ok(self.code.get(&address).cloned().unwrap_or_default())
+    }
+
+    async fn get_storage(&self, address: Address, slot: H256, _block: Option<u64>) -> Result<H256> {
+        self.check_available()?;
+        Ok(self
+            .storage
+            .get(&(address, slot))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    async fn call_contract(
+        &self,
+        tx: &TypedTransaction,
+        _block: Option<BlockId>,
+        overrides: Option<&spoof::State>,
+    ) -> Result<Bytes> {
+        self.check_available()?;
+        self.calls
+            .lock()
+            .unwrap()
+            .push((tx.clone(), overrides.cloned()));
+        let Some(NameOrAddress::Address(to)) = tx.to().cloned() else {
+            anyhow::bail!("Contract calls need a contract address");
+        };
+        let data = tx.data().cloned().unwrap_or_default();
+        if let Some(output) = self.call_results.get(&(to, data.clone())) {
+            return Ok(output.clone());
+        }
+        // balanceOf(address)
+        if let (Some(slot), [0x70, 0xa0, 0x82, 0x31, args @ ..]) =
+            (self.balance_slots.get(&to), data.as_ref())
+        {
+            let mut key = [0u8; 64];
+            key[..32].copy_from_slice(&args[..32]);
+            U256::from(*slot).to_big_endian(&mut key[32..]);
+            let key = format!("{:?}", H256::from(ethers::utils::keccak256(key)));
+            let overridden = overrides
+                .and_then(|state| serde_json::to_value(state).ok())
+                .and_then(|state| {
+                    state
+                        .get(format!("{:?}", to))?
+                        .get("stateDiff")?
+                        .get(&key)?
+                        .as_str()
+                        .map(str::to_string)
+                });
+            let balance = match overridden {
+                Some(value) => U256::from_str_radix(value.trim_start_matches("0x"), 16)?,
+                None => {
+                    let holder = Address::from_slice(&args[12..32]);
+                    match self.token_balances.get(&(to, holder)) {
+                        Some((balance, decimals)) => U256::from_dec_str(
+                            &(*balance * Decimal::from(10u64.pow(*decimals as u32)))
+                                .trunc()
+                                .to_string(),
+                        )?,
+                        None => U256::zero(),
+                    }
+                }
+            };
+            let mut output = [0u8; 32];
+            balance.to_big_endian(&mut output);
+            return Ok(Bytes::from(output.to_vec()));
+        }
+        anyhow::bail!("execution reverted")
+    }
+
+    async fn simulate_bundle(&self, txs: &[TypedTransaction]) -> Result<Vec<SimulatedCall>> {
+        self.check_available()?;
+        Ok(txs
+            .iter()
+            .map(|tx| {
+                if self.check_target(tx).is_err() {
+                    return SimulatedCall {
+                        success: false,
+                        gas_used: U256::from(30_000),
+                        error: Some("execution reverted".to_string()),
+                        ..Default::default()
+                    };
+                }
+                let mut logs = Vec::new();
+                let value = tx.value().copied().unwrap_or_default();
+                if let (false, Some(to)) = (value.is_zero(), tx.to_addr()) {
+                    let topic = |address: Address| H256::from(address);
+                    let mut data = [0u8; 32];
+                    value.to_big_endian(&mut data);
+                    logs.push(Log {
+                        address: native_transfer_address(),
+                        topics: vec![
+                            H256::from(ethers::utils::keccak256(
+                                "Transfer(address,address,uint256)",
+                            )),
+                            topic(tx.from().copied().unwrap_or(self.wallet_address)),
+                            topic(*to),
+                        ],
+                        data: Bytes::from(data.to_vec()),
+                        ..Default::default()
+                    });
+                }
+                if let Some(to) = tx.to_addr() {
+                    logs.extend(self.simulated_logs.get(to).cloned().unwrap_or_default());
+                }
+                SimulatedCall {
+                    success: true,
+                    gas_used: U256::from(21_000),
+                    logs,
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+
     fn get_wallet_address(&self) -> Address {
         self.wallet_address
     }
+
+    fn rpc_endpoint(&self) -> String {
+        "mock".to_string()
+    }
 }
 
 /// Mock Uniswap router for testing
 pub struct MockUniswapRouter {
     prices: HashMap<(Address, Address), Decimal>, // (from_token, to_token) -> price
     swap_simulations: HashMap<(Address, Address), SwapSimulation>,
+    pool_reserves: HashMap<(Address, Address), PoolReserves>,
+    recent_prices: HashMap<(Address, Address), Vec<PricePoint>>,
+    recent_swaps: HashMap<(Address, Address), Vec<PoolSwap>>,
+    router_address: Address,
+    venue_name: &'static str,
+    /// Price swaps off `pool_reserves` rather than returning fixed simulations
+    constant_product: bool,
+}
+
+impl Default for MockUniswapRouter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MockUniswapRouter {
@@ -115,9 +740,31 @@ impl MockUniswapRouter {
         Self {
             prices: HashMap::new(),
             swap_simulations: HashMap::new(),
+            pool_reserves: HashMap::new(),
+            recent_prices: HashMap::new(),
+            recent_swaps: HashMap::new(),
+            router_address: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"
+                .parse()
+                .unwrap(),
+            venue_name: "uniswap_v2",
+            constant_product: false,
         }
     }
 
+    /// Pose as another V2 venue with its own router
+    pub fn with_venue(mut self, venue_name: &'static str, router_address: Address) -> Self {
+        self.venue_name = venue_name;
+        self.router_address = router_address;
+        self
+    }
+
+    /// Quote swap outputs from the pool reserves with the V2 formula and 0.3% fee,
+    /// keeping the gas figures of the stored simulation
+    pub fn with_constant_product(mut self) -> Self {
+        self.constant_product = true;
+        self
+    }
+
     pub fn with_price(mut self, from_token: Address, to_token: Address, price: Decimal) -> Self {
         self.prices.insert((from_token, to_token), price);
         self
@@ -134,6 +781,36 @@ impl MockUniswapRouter {
         self
     }
 
+    pub fn with_pool_reserves(
+        mut self,
+        token_in: Address,
+        token_out: Address,
+        reserves: PoolReserves,
+    ) -> Self {
+        self.pool_reserves.insert((token_in, token_out), reserves);
+        self
+    }
+
+    pub fn with_recent_prices(
+        mut self,
+        token_in: Address,
+        token_out: Address,
+        prices: Vec<PricePoint>,
+    ) -> Self {
+        self.recent_prices.insert((token_in, token_out), prices);
+        self
+    }
+
+    pub fn with_recent_swaps(
+        mut self,
+        token_in: Address,
+        token_out: Address,
+        swaps: Vec<PoolSwap>,
+    ) -> Self {
+        self.recent_swaps.insert((token_in, token_out), swaps);
+        self
+    }
+
     pub async fn get_price(
         &self,
         from_token: Address,
@@ -150,18 +827,75 @@ impl MockUniswapRouter {
         &self,
         from_token: Address,
         to_token: Address,
-        _amount_in: U256,
+        amount_in: U256,
         _wallet_address: Address,
     ) -> Result<SwapSimulation> {
-        self.swap_simulations
+        let mut simulation = self
+            .swap_simulations
             .get(&(from_token, to_token))
             .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Swap simulation not found"))
+            .ok_or_else(|| anyhow::anyhow!("Swap simulation not found"))?;
+        if self.constant_product {
+            let reserves = self
+                .pool_reserves
+                .get(&(from_token, to_token))
+                .ok_or_else(|| anyhow::anyhow!("Pool not found"))?;
+            let amount_in_with_fee = amount_in * 997;
+            simulation.amount_in = amount_in;
+            simulation.amount_out = amount_in_with_fee * reserves.reserve_out
+                / (reserves.reserve_in * 1000 + amount_in_with_fee);
+        }
+        Ok(simulation)
+    }
+
+    pub async fn get_pool_reserves(
+        &self,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<PoolReserves> {
+        self.pool_reserves
+            .get(&(token_in, token_out))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Pool not found"))
+    }
+
+    pub async fn get_recent_prices(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        _lookback_blocks: u64,
+    ) -> Result<Vec<PricePoint>> {
+        Ok(self
+            .recent_prices
+            .get(&(token_in, token_out))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    pub async fn get_recent_swaps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        _lookback_blocks: u64,
+    ) -> Result<Vec<PoolSwap>> {
+        Ok(self
+            .recent_swaps
+            .get(&(token_in, token_out))
+            .cloned()
+            .unwrap_or_default())
     }
 }
 
 #[async_trait]
 impl UniswapRouterTrait for MockUniswapRouter {
+    fn router_address(&self) -> Address {
+        self.router_address
+    }
+
+    fn venue_name(&self) -> &str {
+        self.venue_name
+    }
+
     async fn simulate_swap(
         &self,
         from_token: Address,
@@ -181,4 +915,1152 @@ impl UniswapRouterTrait for MockUniswapRouter {
     ) -> Result<Decimal> {
         self.get_price(from_token, to_token, amount_in).await
     }
+
+    async fn get_pool_reserves(
+        &self,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<PoolReserves> {
+        self.get_pool_reserves(token_in, token_out).await
+    }
+
+    async fn get_recent_prices(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PricePoint>> {
+        self.get_recent_prices(token_in, token_out, lookback_blocks)
+            .await
+    }
+
+    async fn get_recent_swaps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PoolSwap>> {
+        self.get_recent_swaps(token_in, token_out, lookback_blocks)
+            .await
+    }
+}
+
+/// Mock Uniswap V3 pool source for testing
+#[derive(Default)]
+pub struct MockUniswapV3 {
+    pools: Vec<(Address, Address, V3PoolState)>,
+    quotes: HashMap<(Address, Address), V3Quote>,
+    ticks: HashMap<Address, Vec<V3InitializedTick>>,
+    /// Per pool: the tick held throughout its history, and how many seconds it reaches back
+    observations: HashMap<Address, (i32, u32)>,
+}
+
+impl MockUniswapV3 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pool(mut self, token_a: Address, token_b: Address, pool: V3PoolState) -> Self {
+        self.pools.push((token_a, token_b, pool));
+        self
+    }
+
+    pub fn with_quote(mut self, token_in: Address, token_out: Address, quote: V3Quote) -> Self {
+        self.quotes.insert((token_in, token_out), quote);
+        self
+    }
+
+    pub fn with_ticks(mut self, pool: Address, ticks: Vec<V3InitializedTick>) -> Self {
+        self.ticks.insert(pool, ticks);
+        self
+    }
+
+    /// Oracle history for `pool` that sat at `tick` for the last `history_secs`
+    pub fn with_observations(mut self, pool: Address, tick: i32, history_secs: u32) -> Self {
+        self.observations.insert(pool, (tick, history_secs));
+        self
+    }
+
+    pub async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        _amount_in: U256,
+    ) -> Result<V3Quote> {
+        self.quotes
+            .get(&(token_in, token_out))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("V3 quote not found"))
+    }
+
+    pub async fn get_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<V3PoolState>> {
+        Ok(self
+            .pools
+            .iter()
+            .filter(|(a, b, _)| (*a, *b) == (token_a, token_b) || (*a, *b) == (token_b, token_a))
+            .map(|(_, _, pool)| pool.clone())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl UniswapV3Trait for MockUniswapV3 {
+    async fn get_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<V3PoolState>> {
+        self.get_pools(token_a, token_b).await
+    }
+
+    async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<V3Quote> {
+        self.quote_exact_input(token_in, token_out, amount_in).await
+    }
+
+    async fn get_initialized_ticks(
+        &self,
+        pool: &V3PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<Vec<V3InitializedTick>> {
+        Ok(self
+            .ticks
+            .get(&pool.pool_address)
+            .into_iter()
+            .flatten()
+            .filter(|t| t.tick >= tick_lower && t.tick <= tick_upper)
+            .copied()
+            .collect())
+    }
+
+    async fn observe(&self, pool: Address, seconds_agos: &[u32]) -> Result<Vec<i64>> {
+        let (tick, history_secs) = self
+            .observations
+            .get(&pool)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Pool has no observations"))?;
+        seconds_agos
+            .iter()
+            .map(|ago| {
+                if *ago > history_secs {
+                    anyhow::bail!(
+                        "The pool's oracle history does not reach back {} seconds",
+                        ago
+                    );
+                }
+                Ok(tick as i64 * (history_secs - ago) as i64)
+            })
+            .collect()
+    }
+}
+
+/// Mock price venue quoting at fixed rates for testing
+pub struct MockVenue {
+    name: String,
+    rates: HashMap<(Address, Address), Decimal>,
+    swap_gas_units: u64,
+}
+
+impl MockVenue {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rates: HashMap::new(),
+            swap_gas_units: 150_000,
+        }
+    }
+
+    /// Quote `amount_in * rate` of `token_out` (in base units) for `token_in`
+    pub fn with_rate(mut self, token_in: Address, token_out: Address, rate: Decimal) -> Self {
+        self.rates.insert((token_in, token_out), rate);
+        self
+    }
+
+    pub fn with_swap_gas_units(mut self, gas: u64) -> Self {
+        self.swap_gas_units = gas;
+        self
+    }
+
+    pub async fn quote(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<U256> {
+        let rate = self
+            .rates
+            .get(&(token_in, token_out))
+            .ok_or_else(|| anyhow::anyhow!("{} has no pool for this pair", self.name))?;
+        let amount_in = crate::tools::units::from_base_units(amount_in, 0)?;
+        crate::tools::units::to_base_units(amount_in * rate, 0)
+    }
+}
+
+#[async_trait]
+impl PriceVenue for MockVenue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256> {
+        self.quote(token_in, token_out, amount_in).await
+    }
+
+    fn swap_gas_units(&self) -> u64 {
+        self.swap_gas_units
+    }
+}
+
+/// Mock pool discovery for testing
+#[derive(Default)]
+pub struct MockPoolDiscovery {
+    pools: Vec<(PoolInfo, PoolLiquidity)>,
+}
+
+impl MockPoolDiscovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pool(mut self, pool: PoolInfo, liquidity: PoolLiquidity) -> Self {
+        self.pools.push((pool, liquidity));
+        self
+    }
+
+    pub async fn find_pools(
+        &self,
+        token: Address,
+        other: Option<Address>,
+        from_block: Option<u64>,
+    ) -> Result<Vec<PoolInfo>> {
+        let mut pools: Vec<PoolInfo> = self
+            .pools
+            .iter()
+            .map(|(pool, _)| pool)
+            .filter(|pool| pool.token0 == token || pool.token1 == token)
+            .filter(|pool| other.is_none_or(|o| pool.token0 == o || pool.token1 == o))
+            .filter(|pool| pool.creation_block >= from_block.unwrap_or(0))
+            .cloned()
+            .collect();
+        pools.sort_by_key(|pool| pool.creation_block);
+        Ok(pools)
+    }
+
+    pub async fn get_liquidity(&self, pool: &PoolInfo) -> Result<PoolLiquidity> {
+        self.pools
+            .iter()
+            .find(|(p, _)| p.pool_address == pool.pool_address)
+            .map(|(_, liquidity)| liquidity.clone())
+            .ok_or_else(|| anyhow::anyhow!("Pool not found"))
+    }
+}
+
+#[async_trait]
+impl PoolDiscoveryTrait for MockPoolDiscovery {
+    async fn find_pools(
+        &self,
+        token: Address,
+        other: Option<Address>,
+        from_block: Option<u64>,
+    ) -> Result<Vec<PoolInfo>> {
+        self.find_pools(token, other, from_block).await
+    }
+
+    async fn get_liquidity(&self, pool: &PoolInfo) -> Result<PoolLiquidity> {
+        self.get_liquidity(pool).await
+    }
+}
+
+/// Pools with canned state and swaps, recording the block ranges scanned
+#[derive(Default)]
+pub struct MockPoolActivity {
+    pools: HashMap<Address, PoolState>,
+    swaps: Vec<(Address, SwapAmounts)>,
+    scanned: Mutex<Vec<(Address, u64, u64)>>,
+}
+
+impl MockPoolActivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pool(mut self, pool: Address, state: PoolState) -> Self {
+        self.pools.insert(pool, state);
+        self
+    }
+
+    pub fn with_swap(mut self, pool: Address, swap: SwapAmounts) -> Self {
+        self.swaps.push((pool, swap));
+        self
+    }
+
+    /// Every (pool, from_block, to_block) swaps were requested for
+    pub fn scanned(&self) -> Vec<(Address, u64, u64)> {
+        self.scanned.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl PoolActivitySource for MockPoolActivity {
+    async fn pool_state(&self, pool: Address) -> Result<PoolState> {
+        self.pools
+            .get(&pool)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Pool not found"))
+    }
+
+    async fn swaps(
+        &self,
+        pool: Address,
+        _kind: FactoryKind,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SwapAmounts>> {
+        self.scanned
+            .lock()
+            .unwrap()
+            .push((pool, from_block, to_block));
+        Ok(self
+            .swaps
+            .iter()
+            .filter(|(p, swap)| *p == pool && (from_block..=to_block).contains(&swap.block_number))
+            .map(|(_, swap)| swap.clone())
+            .collect())
+    }
+}
+
+/// Mock FX source with fixed USD rates for testing
+#[derive(Default)]
+pub struct MockFxRates {
+    rates: HashMap<String, Decimal>,
+}
+
+impl MockFxRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One unit of `currency` is worth `usd_per_unit` USD
+    pub fn with_rate(mut self, currency: &str, usd_per_unit: Decimal) -> Self {
+        self.rates.insert(currency.to_uppercase(), usd_per_unit);
+        self
+    }
+}
+
+#[async_trait]
+impl FxRateSource for MockFxRates {
+    fn supported_currencies(&self) -> Vec<String> {
+        let mut currencies: Vec<String> = self.rates.keys().cloned().collect();
+        currencies.sort();
+        currencies
+    }
+
+    async fn usd_rate(&self, currency: &str) -> Result<FxRate> {
+        let currency = currency.to_uppercase();
+        let usd_per_unit = *self
+            .rates
+            .get(&currency)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported fiat currency: {}", currency))?;
+        Ok(FxRate {
+            currency,
+            usd_per_unit,
+            updated_at: 1_700_000_000,
+        })
+    }
+}
+
+/// Mock oracle with fixed USD prices for testing
+#[derive(Default)]
+pub struct MockReferencePrices {
+    prices: HashMap<Address, Decimal>,
+}
+
+impl MockReferencePrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_price(mut self, token: Address, usd_price: Decimal) -> Self {
+        self.prices.insert(token, usd_price);
+        self
+    }
+}
+
+#[async_trait]
+impl ReferencePriceSource for MockReferencePrices {
+    async fn usd_price(&self, token: Address) -> Result<Option<ReferencePrice>> {
+        Ok(self.prices.get(&token).map(|usd_price| ReferencePrice {
+            usd_price: *usd_price,
+            source: "mock".to_string(),
+            updated_at: 1_700_000_000,
+        }))
+    }
+}
+
+/// Mock yield source with fixed APYs for testing
+pub struct MockYieldSource {
+    name: String,
+    apys: HashMap<Address, Decimal>,
+    gas_units: u64,
+}
+
+impl MockYieldSource {
+    /// `gas_units` covers entry and exit together
+    pub fn new(name: &str, gas_units: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            apys: HashMap::new(),
+            gas_units,
+        }
+    }
+
+    pub fn with_apy(mut self, asset: Address, apy_percent: Decimal) -> Self {
+        self.apys.insert(asset, apy_percent);
+        self
+    }
+}
+
+#[async_trait]
+impl YieldSource for MockYieldSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal> {
+        self.apys
+            .get(&asset)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Asset not supported"))
+    }
+
+    fn entry_gas_units(&self) -> u64 {
+        self.gas_units
+    }
+
+    fn exit_gas_units(&self) -> u64 {
+        0
+    }
+}
+
+/// Liquid staking token with a fixed exchange rate and APR
+pub struct MockLst {
+    symbol: String,
+    token: Address,
+    exchange_rate: Decimal,
+    apr: Option<Decimal>,
+}
+
+impl MockLst {
+    pub fn new(symbol: &str, token: Address, exchange_rate: Decimal) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            token,
+            exchange_rate,
+            apr: None,
+        }
+    }
+
+    pub fn with_apr(mut self, apr_percent: Decimal) -> Self {
+        self.apr = Some(apr_percent);
+        self
+    }
+}
+
+#[async_trait]
+impl LiquidStakingToken for MockLst {
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn token(&self) -> Address {
+        self.token
+    }
+
+    async fn exchange_rate(&self) -> Result<Decimal> {
+        Ok(self.exchange_rate)
+    }
+
+    async fn apr(&self) -> Result<Decimal> {
+        self.apr
+            .ok_or_else(|| anyhow::anyhow!("No rate history for {}", self.symbol))
+    }
+}
+
+/// Perpetuals exchange with canned markets and positions
+pub struct MockPerpVenue {
+    name: String,
+    markets: Vec<PerpMarket>,
+    positions: HashMap<Address, Vec<PerpPosition>>,
+    unavailable: bool,
+}
+
+impl MockPerpVenue {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            markets: Vec::new(),
+            positions: HashMap::new(),
+            unavailable: false,
+        }
+    }
+
+    pub fn with_market(mut self, market: PerpMarket) -> Self {
+        self.markets.push(market);
+        self
+    }
+
+    pub fn with_position(mut self, account: Address, position: PerpPosition) -> Self {
+        self.positions.entry(account).or_default().push(position);
+        self
+    }
+
+    /// Fail every read, as when the exchange's API is down
+    pub fn with_unavailable(mut self) -> Self {
+        self.unavailable = true;
+        self
+    }
+
+    fn check_available(&self) -> Result<()> {
+        if self.unavailable {
+            anyhow::bail!("{} is unavailable", self.name);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PerpVenue for MockPerpVenue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn markets(&self) -> Result<Vec<PerpMarket>> {
+        self.check_available()?;
+        Ok(self.markets.clone())
+    }
+
+    async fn positions(&self, account: Address) -> Result<Vec<PerpPosition>> {
+        self.check_available()?;
+        Ok(self.positions.get(&account).cloned().unwrap_or_default())
+    }
+}
+
+/// Options exchange with canned quotes
+pub struct MockOptionsVenue {
+    name: String,
+    quotes: Vec<(String, OptionQuote)>,
+}
+
+impl MockOptionsVenue {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            quotes: Vec::new(),
+        }
+    }
+
+    pub fn with_quote(mut self, underlying: &str, quote: OptionQuote) -> Self {
+        self.quotes.push((underlying.to_ascii_uppercase(), quote));
+        self
+    }
+
+    fn listed<'a>(&'a self, underlying: &str) -> impl Iterator<Item = &'a OptionQuote> {
+        let underlying = underlying.to_ascii_uppercase();
+        self.quotes
+            .iter()
+            .filter(move |(listed, _)| *listed == underlying)
+            .map(|(_, quote)| quote)
+    }
+}
+
+#[async_trait]
+impl OptionsVenue for MockOptionsVenue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn expiries(&self, underlying: &str) -> Result<Vec<u64>> {
+        let mut expiries: Vec<u64> = self.listed(underlying).map(|quote| quote.expiry).collect();
+        expiries.sort_unstable();
+        expiries.dedup();
+        Ok(expiries)
+    }
+
+    async fn option_chain(&self, underlying: &str, expiry: u64) -> Result<Vec<OptionQuote>> {
+        Ok(self
+            .listed(underlying)
+            .filter(|quote| quote.expiry == expiry)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Mock governance source with canned proposals and voting power for testing
+pub struct MockGovernance {
+    name: String,
+    proposals: Vec<(String, Proposal)>,
+    voting_power: Vec<(String, Address, VotingPower)>,
+}
+
+impl MockGovernance {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            proposals: Vec::new(),
+            voting_power: Vec::new(),
+        }
+    }
+
+    /// Add a proposal of `dao`; add them oldest first
+    pub fn with_proposal(mut self, dao: &str, proposal: Proposal) -> Self {
+        self.proposals.push((dao.to_lowercase(), proposal));
+        self
+    }
+
+    pub fn with_voting_power(mut self, dao: &str, voter: Address, power: VotingPower) -> Self {
+        self.voting_power.push((dao.to_lowercase(), voter, power));
+        self
+    }
+}
+
+#[async_trait]
+impl GovernanceSource for MockGovernance {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn proposals(&self, dao: &str, active_only: bool, limit: usize) -> Result<Vec<Proposal>> {
+        Ok(self
+            .proposals
+            .iter()
+            .rev()
+            .filter(|(of, proposal)| {
+                *of == dao.to_lowercase()
+                    && (!active_only || proposal.state == ProposalState::Active)
+            })
+            .map(|(_, proposal)| proposal.clone())
+            .take(limit)
+            .collect())
+    }
+
+    async fn voting_power(
+        &self,
+        dao: &str,
+        voter: Address,
+        _proposal: Option<&str>,
+    ) -> Result<VotingPower> {
+        self.voting_power
+            .iter()
+            .find(|(of, of_voter, _)| *of == dao.to_lowercase() && *of_voter == voter)
+            .map(|(_, _, power)| power.clone())
+            .ok_or_else(|| anyhow::anyhow!("No voting power for {:?} in {}", voter, dao))
+    }
+}
+
+/// Mock ENS with canned names for testing
+#[derive(Default)]
+pub struct MockEns {
+    names: Vec<EnsName>,
+    primary_names: HashMap<Address, String>,
+    /// Wei to renew any name for a year
+    yearly_rent: U256,
+}
+
+impl MockEns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a name; it is owned by its `registrant`, or its `owner` for subnames
+    pub fn with_name(mut self, name: EnsName) -> Self {
+        self.names.push(name);
+        self
+    }
+
+    pub fn with_primary_name(mut self, address: Address, name: &str) -> Self {
+        self.primary_names.insert(address, name.to_string());
+        self
+    }
+
+    pub fn with_yearly_rent(mut self, wei: U256) -> Self {
+        self.yearly_rent = wei;
+        self
+    }
+}
+
+#[async_trait]
+impl EnsSource for MockEns {
+    async fn lookup(&self, name: &str) -> Result<EnsName> {
+        Ok(self
+            .names
+            .iter()
+            .find(|known| known.name == name)
+            .cloned()
+            .unwrap_or_else(|| EnsName {
+                name: name.to_string(),
+                node: crate::ethereum::ens::namehash(name),
+                expires: crate::ethereum::eth_label(name).map(|_| 0),
+                ..EnsName::default()
+            }))
+    }
+
+    async fn names_owned_by(&self, owner: Address) -> Result<Vec<EnsName>> {
+        Ok(self
+            .names
+            .iter()
+            .filter(|name| name.registrant.or(name.owner) == Some(owner))
+            .cloned()
+            .collect())
+    }
+
+    async fn primary_name(&self, address: Address) -> Result<Option<String>> {
+        Ok(self.primary_names.get(&address).cloned())
+    }
+
+    async fn rent_price(&self, _label: &str, duration_secs: u64) -> Result<U256> {
+        Ok(self.yearly_rent * U256::from(duration_secs) / U256::from(365 * 86_400u64))
+    }
+}
+
+/// Mock locker with canned locks for testing
+pub struct MockLockSource {
+    name: String,
+    locks: Vec<TokenLock>,
+    unavailable: bool,
+}
+
+impl MockLockSource {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            locks: Vec::new(),
+            unavailable: false,
+        }
+    }
+
+    pub fn with_lock(mut self, lock: TokenLock) -> Self {
+        self.locks.push(lock);
+        self
+    }
+
+    /// Fail every query, as an unreachable locker would
+    pub fn with_unavailable(mut self) -> Self {
+        self.unavailable = true;
+        self
+    }
+
+    fn matching(&self, keep: impl Fn(&TokenLock) -> bool) -> Result<Vec<TokenLock>> {
+        if self.unavailable {
+            anyhow::bail!("Locker unavailable");
+        }
+        Ok(self
+            .locks
+            .iter()
+            .filter(|lock| keep(lock))
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl LockSource for MockLockSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn locks_for_token(&self, token: Address) -> Result<Vec<TokenLock>> {
+        self.matching(|lock| lock.token == token)
+    }
+
+    async fn locks_for_owner(&self, owner: Address) -> Result<Vec<TokenLock>> {
+        self.matching(|lock| lock.owner == owner)
+    }
+}
+
+/// Mock streaming protocol with canned streams for testing
+pub struct MockStreamSource {
+    name: String,
+    streams: Vec<PaymentStream>,
+    unavailable: bool,
+}
+
+impl MockStreamSource {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            streams: Vec::new(),
+            unavailable: false,
+        }
+    }
+
+    pub fn with_stream(mut self, stream: PaymentStream) -> Self {
+        self.streams.push(stream);
+        self
+    }
+
+    /// Fail every query, as an unreachable protocol would
+    pub fn with_unavailable(mut self) -> Self {
+        self.unavailable = true;
+        self
+    }
+}
+
+#[async_trait]
+impl StreamSource for MockStreamSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn streams_for(&self, wallet: Address) -> Result<Vec<PaymentStream>> {
+        if self.unavailable {
+            anyhow::bail!("Stream source unavailable");
+        }
+        Ok(self
+            .streams
+            .iter()
+            .filter(|stream| stream.sender == wallet || stream.recipient == wallet)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Mock token event source with canned logs for testing
+#[derive(Default)]
+pub struct MockTokenEvents {
+    removals: Vec<LiquidityRemoval>,
+    admin_events: Vec<AdminEvent>,
+    total_supplies: HashMap<Address, U256>,
+    transfers: Vec<(Address, Address, u64)>,
+    token_transfers: Vec<TokenTransfer>,
+}
+
+impl MockTokenEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_removal(mut self, removal: LiquidityRemoval) -> Self {
+        self.removals.push(removal);
+        self
+    }
+
+    pub fn with_admin_event(mut self, event: AdminEvent) -> Self {
+        self.admin_events.push(event);
+        self
+    }
+
+    pub fn with_total_supply(mut self, token: Address, supply: U256) -> Self {
+        self.total_supplies.insert(token, supply);
+        self
+    }
+
+    /// Record a token transfer from `from` to `to` at `block`
+    pub fn with_transfer(mut self, from: Address, to: Address, block: u64) -> Self {
+        self.transfers.push((from, to, block));
+        self
+    }
+
+    /// Record an ERC20 transfer with its token and amount
+    pub fn with_token_transfer(mut self, transfer: TokenTransfer) -> Self {
+        self.token_transfers.push(transfer);
+        self
+    }
+}
+
+#[async_trait]
+impl TokenEventSource for MockTokenEvents {
+    async fn liquidity_removals(
+        &self,
+        pair: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<LiquidityRemoval>> {
+        Ok(self
+            .removals
+            .iter()
+            .filter(|r| r.pair == pair && (from_block..=to_block).contains(&r.block_number))
+            .cloned()
+            .collect())
+    }
+
+    async fn admin_events(
+        &self,
+        token: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<AdminEvent>> {
+        Ok(self
+            .admin_events
+            .iter()
+            .filter(|e| e.token == token && (from_block..=to_block).contains(&e.block_number))
+            .cloned()
+            .collect())
+    }
+
+    async fn total_supply(&self, token: Address) -> Result<U256> {
+        self.total_supplies
+            .get(&token)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Total supply not found"))
+    }
+
+    async fn has_token_transfers(
+        &self,
+        from: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<bool> {
+        Ok(self
+            .transfers
+            .iter()
+            .any(|(f, t, block)| *f == from && *t == to && (from_block..=to_block).contains(block)))
+    }
+
+    async fn incoming_transfers(
+        &self,
+        token: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>> {
+        Ok(self
+            .token_transfers
+            .iter()
+            .filter(|t| {
+                t.token == token && t.to == to && (from_block..=to_block).contains(&t.block_number)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn address_transfers(
+        &self,
+        account: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>> {
+        Ok(self
+            .token_transfers
+            .iter()
+            .filter(|t| {
+                (t.from == account || t.to == account)
+                    && (from_block..=to_block).contains(&t.block_number)
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Default)]
+pub struct MockSignatures {
+    signatures: HashMap<[u8; 4], Vec<String>>,
+    unavailable: bool,
+}
+
+impl MockSignatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name the selector of `signature`, e.g. `transfer(address,uint256)`
+    pub fn with_signature(mut self, signature: &str) -> Self {
+        let selector: [u8; 4] = ethers::utils::keccak256(signature)[..4].try_into().unwrap();
+        self.signatures
+            .entry(selector)
+            .or_default()
+            .push(signature.to_string());
+        self
+    }
+
+    /// Fail every lookup, like an unreachable directory
+    pub fn with_unavailable(mut self) -> Self {
+        self.unavailable = true;
+        self
+    }
+}
+
+#[async_trait]
+impl SignatureSource for MockSignatures {
+    async fn lookup(&self, selectors: &[[u8; 4]]) -> Result<HashMap<[u8; 4], Vec<String>>> {
+        if self.unavailable {
+            anyhow::bail!("Failed to reach the 4byte directory");
+        }
+        Ok(selectors
+            .iter()
+            .filter_map(|selector| {
+                self.signatures
+                    .get(selector)
+                    .map(|signatures| (*selector, signatures.clone()))
+            })
+            .collect())
+    }
+}
+
+/// Mock NFT market with canned collection stats for testing
+#[derive(Default)]
+pub struct MockNftMarket {
+    collections: HashMap<Address, CollectionStats>,
+}
+
+impl MockNftMarket {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_collection(mut self, collection: Address, stats: CollectionStats) -> Self {
+        self.collections.insert(collection, stats);
+        self
+    }
+}
+
+#[async_trait]
+impl NftMarketSource for MockNftMarket {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn collection_stats(&self, collection: Address) -> Result<CollectionStats> {
+        self.collections
+            .get(&collection)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown collection"))
+    }
+}
+
+#[derive(Default)]
+pub struct MockTransactionHistory {
+    transactions: Vec<HistoricalTransaction>,
+    withdrawals: Vec<BeaconWithdrawal>,
+}
+
+impl MockTransactionHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_transaction(mut self, transaction: HistoricalTransaction) -> Self {
+        self.transactions.push(transaction);
+        self
+    }
+
+    pub fn with_withdrawal(mut self, withdrawal: BeaconWithdrawal) -> Self {
+        self.withdrawals.push(withdrawal);
+        self
+    }
+}
+
+#[async_trait]
+impl TransactionHistorySource for MockTransactionHistory {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn transactions(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<HistoricalTransaction>> {
+        let mut found: Vec<HistoricalTransaction> = self
+            .transactions
+            .iter()
+            .filter(|tx| (tx.from == account || tx.to == Some(account)) && tx.timestamp >= since)
+            .cloned()
+            .collect();
+        found.sort_by_key(|tx| std::cmp::Reverse(tx.timestamp));
+        found.truncate(limit);
+        Ok(found)
+    }
+
+    async fn withdrawals(
+        &self,
+        account: Address,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<BeaconWithdrawal>> {
+        let mut found: Vec<BeaconWithdrawal> = self
+            .withdrawals
+            .iter()
+            .filter(|w| w.address == account && w.timestamp >= since)
+            .cloned()
+            .collect();
+        found.sort_by_key(|w| std::cmp::Reverse(w.timestamp));
+        found.truncate(limit);
+        Ok(found)
+    }
+}
+
+/// Beacon node whose validators are set per slot
+#[derive(Default)]
+pub struct MockBeacon {
+    head_slot: u64,
+    validators: HashMap<(u64, u64), Validator>, // (index, slot) -> validator
+}
+
+impl MockBeacon {
+    pub fn new(head_slot: u64) -> Self {
+        Self {
+            head_slot,
+            validators: HashMap::new(),
+        }
+    }
+
+    /// `validator` as it was at `slot`
+    pub fn with_validator(mut self, slot: u64, validator: Validator) -> Self {
+        self.validators.insert((validator.index, slot), validator);
+        self
+    }
+}
+
+#[async_trait]
+impl BeaconSource for MockBeacon {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn head_slot(&self) -> Result<u64> {
+        Ok(self.head_slot)
+    }
+
+    async fn validator(&self, id: &str, slot: Option<u64>) -> Result<Validator> {
+        let slot = slot.unwrap_or(self.head_slot);
+        self.validators
+            .iter()
+            .filter(|((_, at), validator)| {
+                *at <= slot && (validator.index.to_string() == id || validator.pubkey == id)
+            })
+            .max_by_key(|((_, at), _)| *at)
+            .map(|(_, validator)| validator.clone())
+            .ok_or_else(|| anyhow::anyhow!("Unknown validator {}", id))
+    }
+}
+
+/// Restaking protocol with canned positions
+#[derive(Default)]
+pub struct MockRestaking {
+    positions: HashMap<Address, RestakingPosition>,
+}
+
+impl MockRestaking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_position(mut self, staker: Address, position: RestakingPosition) -> Self {
+        self.positions.insert(staker, position);
+        self
+    }
+}
+
+#[async_trait]
+impl RestakingSource for MockRestaking {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn position(&self, staker: Address) -> Result<RestakingPosition> {
+        Ok(self.positions.get(&staker).cloned().unwrap_or_default())
+    }
 }
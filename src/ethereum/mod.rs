@@ -1,9 +1,116 @@
+pub mod beacon;
+pub mod bytecode;
+pub mod ccip;
+pub mod chains;
 pub mod client;
+pub mod eigenlayer;
+pub mod ens;
+pub mod escalation;
+pub mod fx;
+pub mod governance;
+pub mod history;
+pub mod kms;
+pub mod locks;
+pub mod lsts;
 pub mod mock;
+pub mod new_heads;
+pub mod nft;
+pub mod options;
+pub mod perps;
+pub mod plan;
+pub mod pool_activity;
+pub mod pools;
+pub mod price_feeds;
+pub mod simulation;
+pub mod sponsor;
+pub mod storage;
+pub mod streams;
+pub mod token_events;
+pub mod tokens;
 pub mod uniswap;
+pub mod uniswap_v3;
+pub mod venues;
+pub mod walletconnect;
+pub mod yields;
 
-pub use client::{EthereumClient, EthereumClientTrait};
+pub use beacon::{BeaconApi, BeaconSource, Validator, FAR_FUTURE_EPOCH, SECONDS_PER_SLOT};
+pub use bytecode::{
+    disassemble, function_selectors, minimal_proxy_target, FourByteDirectory, Instruction,
+    SignatureSource, DEFAULT_FOUR_BYTE_URL,
+};
+pub use ccip::CcipRead;
+pub use chains::{ChainClients, ChainPricing};
+pub use client::{
+    ApprovalSimulation, BaseFeeHistory, BlockInfo, ChainIdCheck, ChainIdMismatch, Delegation,
+    EthereumClient, EthereumClientTrait, PendingTransaction, TransactionOutcome,
+};
+pub use eigenlayer::{
+    EigenLayerContracts, QueuedWithdrawal, RestakedDeposit, RestakingPosition, RestakingSource,
+    StrategyShares,
+};
+pub use ens::{
+    eth_label, normalize_name, EnsContracts, EnsName, EnsSource, ENS_ETH_REGISTRAR_CONTROLLER,
+    ENS_GRACE_PERIOD_SECS,
+};
+pub use escalation::{FeeReplacement, GasEscalation};
+pub use fx::{ChainlinkFxRates, FxRate, FxRateSource};
+pub use governance::{
+    GovernanceSource, GovernorContracts, Proposal, ProposalState, SnapshotHub, VoteClock,
+    VoteTally, VotingPower, DEFAULT_GOVERNORS, DEFAULT_SNAPSHOT_HUB_URL,
+};
+pub use history::{
+    BeaconWithdrawal, EtherscanHistory, HistoricalTransaction, TransactionHistorySource,
+    DEFAULT_ETHERSCAN_URL,
+};
+pub use kms::{AwsCredentials, KmsConfig, KmsSigner};
+pub use locks::{
+    LockSource, Release, SablierStreams, TeamFinanceLocker, TokenLock, UnicryptLocker,
+    SABLIER_V2_LOCKUP_LINEAR,
+};
+pub use lsts::{LiquidStakingToken, LstContract};
 
 #[cfg(test)]
-pub use mock::{MockEthereumClient, MockUniswapRouter};
-pub use uniswap::{SwapSimulation, UniswapRouterTrait, UniswapV2Router};
+pub use mock::{
+    MockBeacon, MockEns, MockEthereumClient, MockFxRates, MockGovernance, MockLockSource, MockLst, MockNftMarket,
+    MockOptionsVenue, MockPerpVenue, MockPoolActivity, MockPoolDiscovery, MockReferencePrices, MockRestaking, MockSignatures, MockStreamSource, MockTokenEvents, MockTransactionHistory, MockUniswapRouter,
+    MockUniswapV3, MockVenue, MockYieldSource,
+};
+pub use new_heads::{NewHeads, NewHeadsStatus};
+pub use nft::{CollectionStats, NftMarketSource, ReservoirApi, DEFAULT_RESERVOIR_URL};
+pub use options::{
+    AevoApi, LyraApi, OptionKind, OptionQuote, OptionsVenue, OptionsVenueKind, DEFAULT_AEVO_URL,
+    DEFAULT_LYRA_URL,
+};
+pub use plan::{erc20_call_succeeded, NftStandard, Payment, Plan, PlanStep};
+pub use perps::{
+    GmxApi, HyperliquidApi, PerpMarket, PerpPosition, PerpSide, PerpVenue, PerpVenueKind,
+    DEFAULT_GMX_SQUID_URL, DEFAULT_GMX_URL, DEFAULT_HYPERLIQUID_URL,
+};
+pub use pool_activity::{PoolActivity, PoolActivitySource, PoolState, SwapAmounts};
+pub use pools::{FactoryKind, PoolDiscovery, PoolDiscoveryTrait, PoolInfo, PoolLiquidity};
+pub use price_feeds::{ChainlinkPriceFeeds, ReferencePrice, ReferencePriceSource};
+pub use simulation::{
+    asset_transfers, balance_changes, event_signature, native_transfer_address, AssetTransfer,
+    BalanceChange, SimulatedCall,
+};
+pub use sponsor::{GasSponsor, GasSponsorConfig, SponsorKind, Sponsorship, DEFAULT_ENTRY_POINT};
+pub use storage::{array_data_slot, mapping_slot, MappingLayout};
+pub use streams::{PaymentStream, StreamFlow, StreamSource, SuperfluidFlows};
+pub use token_events::{
+    AdminAction, AdminEvent, LiquidityRemoval, TokenEventSource, TokenEvents, TokenTransfer,
+};
+pub use tokens::{AssetIdentity, TokenVariant};
+pub use uniswap::{
+    PoolReserves, PoolSwap, PricePoint, SwapSimulation, UniswapRouterTrait, UniswapV2Router,
+};
+pub use uniswap_v3::{
+    LiquidityBand, UniswapV3Pools, UniswapV3Trait, V3InitializedTick, V3PoolState, V3Quote,
+};
+pub use venues::{CurveVenue, PriceVenue, UniswapV3Venue};
+pub use walletconnect::{
+    Pairing, PairingStatus, WalletConnectConfig, WalletConnectSigner,
+    DEFAULT_WALLETCONNECT_REQUEST_TIMEOUT, WALLETCONNECT_RESOURCE_URI,
+};
+pub use yields::{
+    AaveV3Yield, CompoundV3Yield, Erc4626Vault, SavingsDai, YieldSource, DEFAULT_ERC4626_VAULTS,
+};
@@ -0,0 +1,150 @@
+use crate::secrets::SecretString;
+use anyhow::{Context, Result};
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Wait before reconnecting a dropped WebSocket, doubled after each failed attempt
+pub const WS_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between reconnection attempts
+pub const MAX_WS_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// State of the WebSocket subscription, as reported by health_check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NewHeadsStatus {
+    pub connected: bool,
+    /// Connections made after the first
+    pub reconnects: u64,
+    /// Latest block announced
+    pub head: Option<u64>,
+    /// Why the connection last dropped or failed
+    pub last_error: Option<String>,
+}
+
+/// Follows new blocks over a node's WebSocket endpoint, so watchers run as soon as a
+/// block lands instead of on their next poll.
+///
+/// A dropped connection is reconnected with a growing delay and the `newHeads`
+/// subscription made again. On every (re)connection the current head is announced
+/// before any new block, so subscribers scan the blocks they missed while it was down
+/// with `eth_getLogs` from their own cursor.
+pub struct NewHeads {
+    url: SecretString,
+    heads: watch::Sender<Option<u64>>,
+    status: Mutex<NewHeadsStatus>,
+    reconnect_delay: Duration,
+}
+
+impl NewHeads {
+    pub fn new(url: SecretString) -> Self {
+        Self {
+            url,
+            heads: watch::channel(None).0,
+            status: Mutex::new(NewHeadsStatus::default()),
+            reconnect_delay: WS_RECONNECT_DELAY,
+        }
+    }
+
+    /// Wait `delay` before the first reconnection attempt, doubling from there
+    pub fn with_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// The latest head, changed on every new block and every reconnection
+    pub fn subscribe(&self) -> watch::Receiver<Option<u64>> {
+        self.heads.subscribe()
+    }
+
+    pub fn status(&self) -> NewHeadsStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut NewHeadsStatus)) {
+        change(&mut self.status.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    /// Follow new blocks until `stop` is set, reconnecting whenever the connection drops
+    pub fn spawn(self: Arc<Self>, mut stop: watch::Receiver<bool>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut delay = self.reconnect_delay;
+            let mut connections = 0u64;
+            loop {
+                let outcome = tokio::select! {
+                    outcome = self.follow(&mut connections) => outcome,
+                    // Set, or the server that started the job is gone
+                    _ = stop.changed() => break,
+                };
+                let error = match outcome {
+                    // It connected, so the backoff starts over
+                    Ok(()) => {
+                        delay = self.reconnect_delay;
+                        "connection closed".to_string()
+                    }
+                    Err(e) => format!("{:#}", e),
+                };
+                warn!(
+                    "WebSocket subscription dropped ({}); reconnecting in {:?}",
+                    error, delay
+                );
+                self.update(|status| {
+                    status.connected = false;
+                    status.last_error = Some(error);
+                });
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = stop.changed() => break,
+                }
+                delay = (delay * 2).min(MAX_WS_RECONNECT_DELAY);
+            }
+            self.update(|status| status.connected = false);
+        })
+    }
+
+    /// Connect, announce the current head and then every new block until the
+    /// connection drops. Ok when it connected and the stream later ended.
+    async fn follow(&self, connections: &mut u64) -> Result<()> {
+        // Reconnection is handled here, where the missed blocks can be caught up on
+        let ws = Ws::connect_with_reconnects(self.url.expose_secret(), 0)
+            .await
+            .context("Failed to connect to the WebSocket endpoint")?;
+        let provider = Provider::new(ws);
+        let mut blocks = provider
+            .subscribe_blocks()
+            .await
+            .context("Failed to subscribe to new blocks")?;
+        let head = provider
+            .get_block_number()
+            .await
+            .context("Failed to read the latest block")?
+            .as_u64();
+
+        *connections += 1;
+        if *connections > 1 {
+            info!(head, "WebSocket reconnected; catching up on missed blocks");
+        }
+        self.update(|status| {
+            status.connected = true;
+            status.reconnects = connections.saturating_sub(1);
+        });
+        self.announce(head);
+
+        while let Some(block) = blocks.next().await {
+            if let Some(number) = block.number {
+                self.announce(number.as_u64());
+            }
+        }
+        Ok(())
+    }
+
+    fn announce(&self, head: u64) {
+        self.update(|status| status.head = Some(head));
+        self.heads.send_replace(Some(head));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::secrets::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Reservoir's public API for Ethereum mainnet
+pub const DEFAULT_RESERVOIR_URL: &str = "https://api.reservoir.tools";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Market figures of an NFT collection, priced in `currency`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionStats {
+    pub name: Option<String>,
+    /// Cheapest listing
+    pub floor_price: Option<Decimal>,
+    /// Highest collection-wide bid
+    pub top_bid: Option<Decimal>,
+    pub volume_24h: Option<Decimal>,
+    pub currency: String,
+    pub token_count: Option<u64>,
+}
+
+/// Source of NFT collection market data
+#[async_trait]
+pub trait NftMarketSource: Send + Sync {
+    /// Short name reported with the figures, such as `reservoir`
+    fn name(&self) -> &str;
+
+    async fn collection_stats(&self, collection: Address) -> Result<CollectionStats>;
+}
+
+/// Collection stats from a Reservoir-compatible API
+pub struct ReservoirApi {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<SecretString>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReservoirCollections {
+    collections: Vec<ReservoirCollection>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReservoirCollection {
+    name: Option<String>,
+    token_count: Option<String>,
+    floor_ask: Option<ReservoirOrder>,
+    top_bid: Option<ReservoirOrder>,
+    #[serde(default)]
+    volume: ReservoirVolume,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReservoirOrder {
+    price: Option<ReservoirPrice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReservoirPrice {
+    amount: ReservoirAmount,
+    currency: Option<ReservoirCurrency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReservoirAmount {
+    decimal: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReservoirCurrency {
+    symbol: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ReservoirVolume {
+    #[serde(rename = "1day")]
+    one_day: Option<f64>,
+}
+
+impl ReservoirApi {
+    pub fn new(url: &str, api_key: Option<SecretString>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+fn order_price(order: &Option<ReservoirOrder>) -> Option<&ReservoirPrice> {
+    order.as_ref()?.price.as_ref()
+}
+
+#[async_trait]
+impl NftMarketSource for ReservoirApi {
+    fn name(&self) -> &str {
+        "reservoir"
+    }
+
+    async fn collection_stats(&self, collection: Address) -> Result<CollectionStats> {
+        let mut request = self
+            .http
+            .get(format!("{}/collections/v7", self.url))
+            .query(&[("id", format!("{:?}", collection))]);
+        if let Some(key) = &self.api_key {
+            request = request.header("x-api-key", key.expose_secret());
+        }
+        let mut response: ReservoirCollections = request
+            .send()
+            .await
+            .context("Failed to reach Reservoir")?
+            .error_for_status()
+            .context("Reservoir request failed")?
+            .json()
+            .await
+            .context("Invalid Reservoir response")?;
+        if response.collections.is_empty() {
+            anyhow::bail!("Reservoir knows no collection at {:?}", collection);
+        }
+        let found = response.collections.swap_remove(0);
+
+        let floor = order_price(&found.floor_ask);
+        let currency = floor
+            .or(order_price(&found.top_bid))
+            .and_then(|price| price.currency.as_ref())
+            .map_or_else(|| "ETH".to_string(), |currency| currency.symbol.clone());
+        Ok(CollectionStats {
+            name: found.name,
+            floor_price: floor.and_then(|price| Decimal::from_f64(price.amount.decimal)),
+            top_bid: order_price(&found.top_bid)
+                .and_then(|price| Decimal::from_f64(price.amount.decimal)),
+            volume_24h: found.volume.one_day.and_then(Decimal::from_f64),
+            currency,
+            token_count: found.token_count.and_then(|count| count.parse().ok()),
+        })
+    }
+}
@@ -0,0 +1,363 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Aevo's public REST API
+pub const DEFAULT_AEVO_URL: &str = "https://api.aevo.xyz";
+/// Lyra's (Derive's) public API
+pub const DEFAULT_LYRA_URL: &str = "https://api.lyra.finance";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Lyra serves one ticker per request, so a chain is fetched a few at a time
+const LYRA_TICKER_CONCURRENCY: usize = 8;
+
+/// Which options exchange get_option_chains reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OptionsVenueKind {
+    #[default]
+    Aevo,
+    /// Lyra V2, now Derive
+    Lyra,
+}
+
+impl FromStr for OptionsVenueKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "aevo" => Ok(OptionsVenueKind::Aevo),
+            "lyra" | "derive" => Ok(OptionsVenueKind::Lyra),
+            other => anyhow::bail!("Unknown options venue: {} (expected aevo or lyra)", other),
+        }
+    }
+}
+
+impl OptionsVenueKind {
+    pub fn default_url(self) -> &'static str {
+        match self {
+            OptionsVenueKind::Aevo => DEFAULT_AEVO_URL,
+            OptionsVenueKind::Lyra => DEFAULT_LYRA_URL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+/// One listed option; prices in USD, implied vols as fractions (0.65 for 65%)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionQuote {
+    pub instrument: String,
+    pub kind: OptionKind,
+    pub strike: Decimal,
+    /// Unix seconds
+    pub expiry: u64,
+    pub mark_price: Option<Decimal>,
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+    pub mark_iv: Option<Decimal>,
+    pub bid_iv: Option<Decimal>,
+    pub ask_iv: Option<Decimal>,
+    pub delta: Option<Decimal>,
+    pub index_price: Option<Decimal>,
+}
+
+/// An options exchange whose chains can be read
+#[async_trait]
+pub trait OptionsVenue: Send + Sync {
+    /// Short identifier such as `aevo` or `lyra`
+    fn name(&self) -> &str;
+
+    /// Unexpired expiries listed for `underlying`, as Unix seconds, soonest first
+    async fn expiries(&self, underlying: &str) -> Result<Vec<u64>>;
+
+    /// Every option listed for `underlying` at `expiry`
+    async fn option_chain(&self, underlying: &str, expiry: u64) -> Result<Vec<OptionQuote>>;
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+fn decimal(value: &str) -> Option<Decimal> {
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .ok()
+}
+
+fn option_kind(value: &str) -> Option<OptionKind> {
+    match value.to_ascii_lowercase().as_str() {
+        "call" | "c" => Some(OptionKind::Call),
+        "put" | "p" => Some(OptionKind::Put),
+        _ => None,
+    }
+}
+
+fn active() -> bool {
+    true
+}
+
+/// Options from Aevo's market list, which carries marks and greeks
+pub struct AevoApi {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AevoMarket {
+    instrument_name: String,
+    #[serde(default = "active")]
+    is_active: bool,
+    option_type: Option<String>,
+    strike: Option<String>,
+    /// Unix nanoseconds
+    expiry: Option<String>,
+    mark_price: Option<String>,
+    index_price: Option<String>,
+    greeks: Option<AevoGreeks>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AevoGreeks {
+    delta: Option<String>,
+    iv: Option<String>,
+}
+
+impl AevoApi {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: http_client(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn quotes(&self, underlying: &str) -> Result<Vec<OptionQuote>> {
+        let markets: Vec<AevoMarket> = self
+            .http
+            .get(format!("{}/markets", self.url))
+            .query(&[
+                ("asset", underlying.to_ascii_uppercase()),
+                ("instrument_type", "OPTION".to_string()),
+            ])
+            .send()
+            .await
+            .context("Failed to reach Aevo")?
+            .error_for_status()
+            .context("Aevo request failed")?
+            .json()
+            .await
+            .context("Invalid Aevo response")?;
+        Ok(markets
+            .into_iter()
+            .filter(|market| market.is_active)
+            .filter_map(|market| {
+                let greeks = market.greeks.as_ref();
+                Some(OptionQuote {
+                    kind: option_kind(market.option_type.as_deref()?)?,
+                    strike: decimal(market.strike.as_deref()?)?,
+                    expiry: market.expiry.as_deref()?.parse::<u64>().ok()? / 1_000_000_000,
+                    mark_price: market.mark_price.as_deref().and_then(decimal),
+                    bid: None,
+                    ask: None,
+                    mark_iv: greeks
+                        .and_then(|greeks| greeks.iv.as_deref())
+                        .and_then(decimal),
+                    bid_iv: None,
+                    ask_iv: None,
+                    delta: greeks
+                        .and_then(|greeks| greeks.delta.as_deref())
+                        .and_then(decimal),
+                    index_price: market.index_price.as_deref().and_then(decimal),
+                    instrument: market.instrument_name,
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl OptionsVenue for AevoApi {
+    fn name(&self) -> &str {
+        "aevo"
+    }
+
+    async fn expiries(&self, underlying: &str) -> Result<Vec<u64>> {
+        let mut expiries: Vec<u64> = self
+            .quotes(underlying)
+            .await?
+            .into_iter()
+            .map(|quote| quote.expiry)
+            .collect();
+        expiries.sort_unstable();
+        expiries.dedup();
+        Ok(expiries)
+    }
+
+    async fn option_chain(&self, underlying: &str, expiry: u64) -> Result<Vec<OptionQuote>> {
+        let mut chain = self.quotes(underlying).await?;
+        chain.retain(|quote| quote.expiry == expiry);
+        Ok(chain)
+    }
+}
+
+/// Options from Lyra's instrument list, priced one ticker at a time
+pub struct LyraApi {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyraResponse<T> {
+    result: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyraInstrument {
+    instrument_name: String,
+    #[serde(default = "active")]
+    is_active: bool,
+    option_details: Option<LyraOptionDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyraOptionDetails {
+    /// Unix seconds
+    expiry: u64,
+    strike: String,
+    option_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyraTicker {
+    best_bid_price: Option<String>,
+    best_ask_price: Option<String>,
+    mark_price: Option<String>,
+    index_price: Option<String>,
+    option_pricing: Option<LyraOptionPricing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyraOptionPricing {
+    iv: Option<String>,
+    bid_iv: Option<String>,
+    ask_iv: Option<String>,
+    delta: Option<String>,
+}
+
+impl LyraApi {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: http_client(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn public<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        let response: LyraResponse<T> = self
+            .http
+            .post(format!("{}/public/{}", self.url, method))
+            .json(&params)
+            .send()
+            .await
+            .context("Failed to reach Lyra")?
+            .error_for_status()
+            .context("Lyra request failed")?
+            .json()
+            .await
+            .context("Invalid Lyra response")?;
+        Ok(response.result)
+    }
+
+    /// Listed options as (name, details)
+    async fn instruments(&self, underlying: &str) -> Result<Vec<(String, LyraOptionDetails)>> {
+        let instruments: Vec<LyraInstrument> = self
+            .public(
+                "get_instruments",
+                json!({
+                    "currency": underlying.to_ascii_uppercase(),
+                    "instrument_type": "option",
+                    "expired": false,
+                }),
+            )
+            .await?;
+        Ok(instruments
+            .into_iter()
+            .filter(|instrument| instrument.is_active)
+            .filter_map(|instrument| Some((instrument.instrument_name, instrument.option_details?)))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl OptionsVenue for LyraApi {
+    fn name(&self) -> &str {
+        "lyra"
+    }
+
+    async fn expiries(&self, underlying: &str) -> Result<Vec<u64>> {
+        let mut expiries: Vec<u64> = self
+            .instruments(underlying)
+            .await?
+            .into_iter()
+            .map(|(_, details)| details.expiry)
+            .collect();
+        expiries.sort_unstable();
+        expiries.dedup();
+        Ok(expiries)
+    }
+
+    async fn option_chain(&self, underlying: &str, expiry: u64) -> Result<Vec<OptionQuote>> {
+        let listed: Vec<_> = self
+            .instruments(underlying)
+            .await?
+            .into_iter()
+            .filter(|(_, details)| details.expiry == expiry)
+            .collect();
+        stream::iter(listed)
+            .map(|(instrument, details)| async move {
+                let ticker: LyraTicker = self
+                    .public("get_ticker", json!({ "instrument_name": instrument }))
+                    .await?;
+                let pricing = ticker.option_pricing.as_ref();
+                let field = |pick: fn(&LyraOptionPricing) -> &Option<String>| {
+                    pricing.and_then(|pricing| pick(pricing).as_deref().and_then(decimal))
+                };
+                Ok(OptionQuote {
+                    kind: option_kind(&details.option_type)
+                        .with_context(|| format!("Unknown option type {}", details.option_type))?,
+                    strike: decimal(&details.strike)
+                        .with_context(|| format!("Invalid strike {}", details.strike))?,
+                    expiry: details.expiry,
+                    mark_price: ticker.mark_price.as_deref().and_then(decimal),
+                    bid: ticker.best_bid_price.as_deref().and_then(decimal),
+                    ask: ticker.best_ask_price.as_deref().and_then(decimal),
+                    mark_iv: field(|pricing| &pricing.iv),
+                    bid_iv: field(|pricing| &pricing.bid_iv),
+                    ask_iv: field(|pricing| &pricing.ask_iv),
+                    delta: field(|pricing| &pricing.delta),
+                    index_price: ticker.index_price.as_deref().and_then(decimal),
+                    instrument,
+                })
+            })
+            .buffered(LYRA_TICKER_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+}
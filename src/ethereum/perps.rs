@@ -0,0 +1,541 @@
+use crate::tools::units::from_base_units;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hyperliquid's public info API
+pub const DEFAULT_HYPERLIQUID_URL: &str = "https://api.hyperliquid.xyz";
+/// GMX V2's REST API for Arbitrum
+pub const DEFAULT_GMX_URL: &str = "https://arbitrum-api.gmxinfra.io";
+/// GMX V2's indexer for Arbitrum, which serves open positions
+pub const DEFAULT_GMX_SQUID_URL: &str =
+    "https://gmx.squids.live/gmx-synthetics-arbitrum:prod/api/graphql";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// GMX prices and USD amounts carry 30 decimals
+const GMX_USD_DECIMALS: u32 = 30;
+
+const HOURS_PER_YEAR: u32 = 365 * 24;
+
+/// Which perpetuals exchange the perp tools read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PerpVenueKind {
+    #[default]
+    Hyperliquid,
+    /// GMX V2 on Arbitrum
+    Gmx,
+}
+
+impl FromStr for PerpVenueKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hyperliquid" => Ok(PerpVenueKind::Hyperliquid),
+            "gmx" => Ok(PerpVenueKind::Gmx),
+            other => anyhow::bail!(
+                "Unknown perp venue: {} (expected hyperliquid or gmx)",
+                other
+            ),
+        }
+    }
+}
+
+impl PerpVenueKind {
+    pub fn default_url(self) -> &'static str {
+        match self {
+            PerpVenueKind::Hyperliquid => DEFAULT_HYPERLIQUID_URL,
+            PerpVenueKind::Gmx => DEFAULT_GMX_URL,
+        }
+    }
+
+    /// The adapter for this exchange, reading its API at `url`
+    pub fn connect(self, url: &str) -> Arc<dyn PerpVenue> {
+        match self {
+            PerpVenueKind::Hyperliquid => Arc::new(HyperliquidApi::new(url)),
+            PerpVenueKind::Gmx => Arc::new(GmxApi::new(url)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PerpSide {
+    Long,
+    Short,
+}
+
+/// A perpetual futures market; prices and amounts in USD
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerpMarket {
+    pub symbol: String,
+    pub mark_price: Option<Decimal>,
+    /// Price of the underlying the mark is anchored to
+    pub index_price: Option<Decimal>,
+    /// Fraction of notional longs pay shorts each hour; negative when shorts pay
+    pub funding_rate_hourly: Option<Decimal>,
+    pub open_interest_usd: Option<Decimal>,
+    pub volume_24h_usd: Option<Decimal>,
+    pub max_leverage: Option<u32>,
+}
+
+/// An open perpetual position; prices and amounts in USD
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerpPosition {
+    pub market: String,
+    pub side: PerpSide,
+    /// Size in the market's base asset
+    pub size: Decimal,
+    pub size_usd: Decimal,
+    pub entry_price: Option<Decimal>,
+    pub mark_price: Option<Decimal>,
+    pub unrealized_pnl_usd: Option<Decimal>,
+    /// Collateral backing the position
+    pub margin_usd: Option<Decimal>,
+    pub leverage: Option<Decimal>,
+    pub liquidation_price: Option<Decimal>,
+}
+
+/// A perpetuals exchange whose markets and positions can be read
+#[async_trait]
+pub trait PerpVenue: Send + Sync {
+    /// Short identifier such as `hyperliquid` or `gmx`
+    fn name(&self) -> &str;
+
+    async fn markets(&self) -> Result<Vec<PerpMarket>>;
+
+    async fn positions(&self, account: Address) -> Result<Vec<PerpPosition>>;
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+fn decimal(value: &str) -> Option<Decimal> {
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .ok()
+}
+
+/// A signed fixed-point integer string such as GMX's 30-decimal amounts
+fn fixed(value: &str, decimals: u32) -> Option<Decimal> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let amount = from_base_units(U256::from_dec_str(digits).ok()?, decimals).ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+/// Markets and positions from Hyperliquid's info API
+pub struct HyperliquidApi {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyperliquidAsset {
+    name: String,
+    max_leverage: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyperliquidMeta {
+    universe: Vec<HyperliquidAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyperliquidAssetContext {
+    funding: Option<String>,
+    open_interest: Option<String>,
+    mark_px: Option<String>,
+    oracle_px: Option<String>,
+    day_ntl_vlm: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyperliquidState {
+    #[serde(default)]
+    asset_positions: Vec<HyperliquidAssetPosition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyperliquidAssetPosition {
+    position: HyperliquidPosition,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyperliquidPosition {
+    coin: String,
+    /// Signed size; negative for shorts
+    szi: String,
+    entry_px: Option<String>,
+    position_value: Option<String>,
+    unrealized_pnl: Option<String>,
+    margin_used: Option<String>,
+    liquidation_px: Option<String>,
+    leverage: Option<HyperliquidLeverage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyperliquidLeverage {
+    value: Decimal,
+}
+
+impl HyperliquidApi {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: http_client(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn info<T: serde::de::DeserializeOwned>(&self, request: serde_json::Value) -> Result<T> {
+        self.http
+            .post(format!("{}/info", self.url))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to reach Hyperliquid")?
+            .error_for_status()
+            .context("Hyperliquid request failed")?
+            .json()
+            .await
+            .context("Invalid Hyperliquid response")
+    }
+}
+
+#[async_trait]
+impl PerpVenue for HyperliquidApi {
+    fn name(&self) -> &str {
+        "hyperliquid"
+    }
+
+    async fn markets(&self) -> Result<Vec<PerpMarket>> {
+        let (meta, contexts): (HyperliquidMeta, Vec<HyperliquidAssetContext>) =
+            self.info(json!({ "type": "metaAndAssetCtxs" })).await?;
+        Ok(meta
+            .universe
+            .into_iter()
+            .zip(contexts)
+            .map(|(asset, context)| {
+                let mark_price = context.mark_px.as_deref().and_then(decimal);
+                PerpMarket {
+                    symbol: asset.name,
+                    mark_price,
+                    index_price: context.oracle_px.as_deref().and_then(decimal),
+                    funding_rate_hourly: context.funding.as_deref().and_then(decimal),
+                    // Open interest is counted in the base asset
+                    open_interest_usd: context
+                        .open_interest
+                        .as_deref()
+                        .and_then(decimal)
+                        .zip(mark_price)
+                        .map(|(open_interest, price)| open_interest * price),
+                    volume_24h_usd: context.day_ntl_vlm.as_deref().and_then(decimal),
+                    max_leverage: asset.max_leverage,
+                }
+            })
+            .collect())
+    }
+
+    async fn positions(&self, account: Address) -> Result<Vec<PerpPosition>> {
+        let state: HyperliquidState = self
+            .info(json!({ "type": "clearinghouseState", "user": format!("{:?}", account) }))
+            .await?;
+        let mut positions = Vec::new();
+        for HyperliquidAssetPosition { position } in state.asset_positions {
+            let size = decimal(&position.szi)
+                .with_context(|| format!("Invalid Hyperliquid size {}", position.szi))?;
+            if size.is_zero() {
+                continue;
+            }
+            let size_usd = position
+                .position_value
+                .as_deref()
+                .and_then(decimal)
+                .unwrap_or_default();
+            positions.push(PerpPosition {
+                market: position.coin,
+                side: if size.is_sign_negative() {
+                    PerpSide::Short
+                } else {
+                    PerpSide::Long
+                },
+                size: size.abs(),
+                size_usd,
+                entry_price: position.entry_px.as_deref().and_then(decimal),
+                mark_price: Some(size_usd / size.abs()),
+                unrealized_pnl_usd: position.unrealized_pnl.as_deref().and_then(decimal),
+                margin_usd: position.margin_used.as_deref().and_then(decimal),
+                leverage: position.leverage.map(|leverage| leverage.value),
+                liquidation_price: position.liquidation_px.as_deref().and_then(decimal),
+            });
+        }
+        Ok(positions)
+    }
+}
+
+/// Markets from GMX V2's REST API and positions from its indexer
+pub struct GmxApi {
+    http: reqwest::Client,
+    url: String,
+    squid_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmxTokens {
+    tokens: Vec<GmxToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmxToken {
+    address: Address,
+    decimals: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GmxTicker {
+    token_address: Address,
+    min_price: String,
+    max_price: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmxMarkets {
+    markets: Vec<GmxMarket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GmxMarket {
+    name: String,
+    market_token: Address,
+    index_token: Address,
+    #[serde(default = "listed")]
+    is_listed: bool,
+    open_interest_long: Option<String>,
+    open_interest_short: Option<String>,
+    /// Annualized, in 30 decimals
+    funding_rate_long: Option<String>,
+}
+
+fn listed() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct GmxPositionsData {
+    data: Option<GmxPositions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GmxPositions {
+    positions: Vec<GmxPosition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GmxPosition {
+    market: Address,
+    collateral_token: Address,
+    is_long: bool,
+    size_in_usd: String,
+    size_in_tokens: String,
+    collateral_amount: String,
+}
+
+/// Current prices and decimals of the tokens GMX lists
+struct GmxPrices {
+    decimals: HashMap<Address, u32>,
+    prices: HashMap<Address, Decimal>,
+}
+
+impl GmxPrices {
+    fn price(&self, token: Address) -> Option<Decimal> {
+        self.prices.get(&token).copied()
+    }
+}
+
+impl GmxApi {
+    pub fn new(url: &str) -> Self {
+        Self {
+            http: http_client(),
+            url: url.trim_end_matches('/').to_string(),
+            squid_url: DEFAULT_GMX_SQUID_URL.to_string(),
+        }
+    }
+
+    /// Read positions from another GMX indexer
+    pub fn with_squid_url(mut self, url: &str) -> Self {
+        self.squid_url = url.to_string();
+        self
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.http
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .context("Failed to reach the GMX API")?
+            .error_for_status()
+            .context("GMX request failed")?
+            .json()
+            .await
+            .context("Invalid GMX response")
+    }
+
+    async fn prices(&self) -> Result<GmxPrices> {
+        let tokens: GmxTokens = self.get("/tokens").await?;
+        let tickers: Vec<GmxTicker> = self.get("/prices/tickers").await?;
+        let decimals: HashMap<Address, u32> = tokens
+            .tokens
+            .into_iter()
+            .map(|token| (token.address, token.decimals))
+            .collect();
+        // Prices are scaled so that a base unit times the price is 30-decimal USD
+        let prices = tickers
+            .into_iter()
+            .filter_map(|ticker| {
+                let scale = GMX_USD_DECIMALS.checked_sub(*decimals.get(&ticker.token_address)?)?;
+                let min = fixed(&ticker.min_price, scale)?;
+                let max = fixed(&ticker.max_price, scale)?;
+                Some((ticker.token_address, (min + max) / Decimal::TWO))
+            })
+            .collect();
+        Ok(GmxPrices { decimals, prices })
+    }
+}
+
+#[async_trait]
+impl PerpVenue for GmxApi {
+    fn name(&self) -> &str {
+        "gmx"
+    }
+
+    async fn markets(&self) -> Result<Vec<PerpMarket>> {
+        let prices = self.prices().await?;
+        let markets: GmxMarkets = self.get("/markets/info").await?;
+        let usd = |value: &Option<String>| value.as_deref().and_then(|v| fixed(v, 30));
+        Ok(markets
+            .markets
+            .into_iter()
+            .filter(|market| market.is_listed)
+            .map(|market| {
+                let price = prices.price(market.index_token);
+                PerpMarket {
+                    symbol: market.name,
+                    mark_price: price,
+                    index_price: price,
+                    funding_rate_hourly: usd(&market.funding_rate_long)
+                        .map(|rate| rate / Decimal::from(HOURS_PER_YEAR)),
+                    open_interest_usd: match (
+                        usd(&market.open_interest_long),
+                        usd(&market.open_interest_short),
+                    ) {
+                        (None, None) => None,
+                        (long, short) => Some(long.unwrap_or_default() + short.unwrap_or_default()),
+                    },
+                    volume_24h_usd: None,
+                    max_leverage: None,
+                }
+            })
+            .collect())
+    }
+
+    async fn positions(&self, account: Address) -> Result<Vec<PerpPosition>> {
+        let query = "query($account: String!) { positions(where: { account_eq: $account, sizeInUsd_gt: 0 }) { market collateralToken isLong sizeInUsd sizeInTokens collateralAmount } }";
+        let response: GmxPositionsData = self
+            .http
+            .post(&self.squid_url)
+            .json(&json!({
+                "query": query,
+                "variables": { "account": ethers::utils::to_checksum(&account, None) },
+            }))
+            .send()
+            .await
+            .context("Failed to reach the GMX indexer")?
+            .error_for_status()
+            .context("GMX indexer request failed")?
+            .json()
+            .await
+            .context("Invalid GMX indexer response")?;
+        let found = response
+            .data
+            .context("GMX indexer returned no positions data")?
+            .positions;
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prices = self.prices().await?;
+        let markets: GmxMarkets = self.get("/markets/info").await?;
+        let markets: HashMap<Address, GmxMarket> = markets
+            .markets
+            .into_iter()
+            .map(|market| (market.market_token, market))
+            .collect();
+
+        let mut positions = Vec::new();
+        for position in found {
+            let market = markets
+                .get(&position.market)
+                .with_context(|| format!("Unknown GMX market {:?}", position.market))?;
+            let side = if position.is_long {
+                PerpSide::Long
+            } else {
+                PerpSide::Short
+            };
+            let size_usd = fixed(&position.size_in_usd, GMX_USD_DECIMALS)
+                .context("Invalid GMX position size")?;
+            let size = prices
+                .decimals
+                .get(&market.index_token)
+                .and_then(|decimals| fixed(&position.size_in_tokens, *decimals))
+                .unwrap_or_default();
+            let entry_price = (!size.is_zero()).then(|| size_usd / size);
+            let mark_price = prices.price(market.index_token);
+            let unrealized_pnl_usd = entry_price.zip(mark_price).map(|(entry, mark)| match side {
+                PerpSide::Long => (mark - entry) * size,
+                PerpSide::Short => (entry - mark) * size,
+            });
+            let margin_usd = prices
+                .decimals
+                .get(&position.collateral_token)
+                .and_then(|decimals| fixed(&position.collateral_amount, *decimals))
+                .zip(prices.price(position.collateral_token))
+                .map(|(amount, price)| amount * price);
+            positions.push(PerpPosition {
+                market: market.name.clone(),
+                side,
+                size,
+                size_usd,
+                entry_price,
+                mark_price,
+                unrealized_pnl_usd,
+                margin_usd,
+                leverage: margin_usd
+                    .filter(|margin| !margin.is_zero())
+                    .map(|margin| size_usd / margin),
+                liquidation_price: None,
+            });
+        }
+        Ok(positions)
+    }
+}
@@ -0,0 +1,543 @@
+use crate::ethereum::uniswap::{
+    SwapExactTokensForTokensCall, SwapExactTokensForTokensSupportingFeeOnTransferTokensCall,
+};
+use anyhow::{Context, Result};
+use ethers::abi::AbiEncode;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::{Deserialize, Serialize};
+
+// Wrapped Ether on Ethereum mainnet
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+abigen!(
+    IERC20Actions,
+    r#"[
+        function approve(address spender, uint256 amount) external returns (bool)
+        function transfer(address to, uint256 amount) external returns (bool)
+    ]"#
+);
+
+/// Whether the return data of an ERC20 `transfer`, `transferFrom` or `approve` that
+/// did not revert reports success. USDT, BNB and other early tokens return nothing,
+/// which counts as success; the rest return a bool that must be true.
+pub fn erc20_call_succeeded(output: &[u8]) -> bool {
+    match output.len() {
+        0 => true,
+        len if len >= 32 => output[..32].iter().any(|byte| *byte != 0),
+        _ => false,
+    }
+}
+
+abigen!(
+    IERC721Actions,
+    r#"[
+        function safeTransferFrom(address from, address to, uint256 tokenId) external
+    ]"#
+);
+
+abigen!(
+    IERC1155Actions,
+    r#"[
+        function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data) external
+    ]"#
+);
+
+abigen!(
+    IDisperse,
+    r#"[
+        function disperseEther(address[] recipients, uint256[] values) external payable
+        function disperseToken(address token, address[] recipients, uint256[] values) external
+    ]"#
+);
+
+abigen!(
+    ISablierActions,
+    r#"[
+        struct Durations { uint40 cliff; uint40 total; }
+        struct Broker { address account; uint256 fee; }
+        struct CreateWithDurations { address sender; address recipient; uint128 totalAmount; address asset; bool cancelable; bool transferable; Durations durations; Broker broker; }
+        function createWithDurations(CreateWithDurations params) external returns (uint256 streamId)
+        function cancel(uint256 streamId) external
+    ]"#
+);
+
+abigen!(
+    IGovernorActions,
+    r#"[
+        function castVote(uint256 proposalId, uint8 support) external returns (uint256)
+        function castVoteWithReason(uint256 proposalId, uint8 support, string reason) external returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IVotesActions,
+    r#"[
+        function delegate(address delegatee) external
+    ]"#
+);
+
+abigen!(
+    IEnsControllerActions,
+    r#"[
+        function renew(string name, uint256 duration) external payable
+    ]"#
+);
+
+abigen!(
+    IWETH,
+    r#"[
+        function deposit() external payable
+    ]"#
+);
+
+/// One transaction in a plan. Amounts are integer base units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlanStep {
+    /// Let `spender` move `amount` of `token`
+    Approve {
+        token: Address,
+        spender: Address,
+        #[serde(with = "u256_dec")]
+        amount: U256,
+    },
+    /// Wrap `amount` wei of ETH into WETH
+    Wrap {
+        #[serde(with = "u256_dec")]
+        amount: U256,
+    },
+    /// Exact-input swap along `path` through a V2 router, paid out to the sender
+    Swap {
+        router: Address,
+        path: Vec<Address>,
+        #[serde(with = "u256_dec")]
+        amount_in: U256,
+        #[serde(with = "u256_dec")]
+        amount_out_min: U256,
+        #[serde(default)]
+        fee_on_transfer: bool,
+    },
+    /// Send ETH, or an ERC20 when `token` is set
+    Transfer {
+        #[serde(default)]
+        token: Option<Address>,
+        to: Address,
+        #[serde(with = "u256_dec")]
+        amount: U256,
+    },
+    /// `safeTransferFrom` an NFT of `contract` to `to`: `amount` units of an ERC-1155
+    /// token, or the single ERC-721 token
+    TransferNft {
+        contract: Address,
+        standard: NftStandard,
+        to: Address,
+        #[serde(with = "u256_dec")]
+        token_id: U256,
+        #[serde(with = "u256_dec")]
+        amount: U256,
+    },
+    /// Pay every recipient in one call to a Disperse-style `contract`: ETH, or an
+    /// ERC20 the contract has been approved to move when `token` is set
+    BatchTransfer {
+        contract: Address,
+        #[serde(default)]
+        token: Option<Address>,
+        payments: Vec<Payment>,
+    },
+    /// Stream `amount` of `token` to `to` through a Sablier V2 LockupLinear `contract`,
+    /// released linearly over `duration_secs` from when it is mined, none before `cliff_secs`
+    CreateStream {
+        contract: Address,
+        token: Address,
+        to: Address,
+        #[serde(with = "u256_dec")]
+        amount: U256,
+        #[serde(default)]
+        cliff_secs: u64,
+        duration_secs: u64,
+        cancelable: bool,
+    },
+    /// Cancel a Sablier stream the wallet sends, refunding what has not streamed yet
+    CancelStream {
+        contract: Address,
+        #[serde(with = "u256_dec")]
+        stream_id: U256,
+    },
+    /// Vote on a Governor proposal: `support` is 0 against, 1 for, 2 abstain
+    Vote {
+        governor: Address,
+        #[serde(with = "u256_dec")]
+        proposal_id: U256,
+        support: u8,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// Delegate the wallet's votes on an ERC20Votes `token` to `delegatee`
+    Delegate { token: Address, delegatee: Address },
+    /// Extend the .eth registration of `label` by `duration_secs`, paying up to `value`
+    /// wei to the registrar `controller`, which refunds what the renewal does not cost
+    RenewEns {
+        controller: Address,
+        label: String,
+        duration_secs: u64,
+        #[serde(with = "u256_dec")]
+        value: U256,
+    },
+    /// Replace the pending transaction at `nonce` with an empty self-transfer
+    Cancel {
+        nonce: u64,
+        #[serde(with = "u256_dec")]
+        max_fee_per_gas: U256,
+        #[serde(with = "u256_dec")]
+        max_priority_fee_per_gas: U256,
+    },
+    /// Re-send the pending transaction at `nonce` with higher fees
+    SpeedUp {
+        nonce: u64,
+        #[serde(default)]
+        to: Option<Address>,
+        #[serde(default)]
+        data: Bytes,
+        #[serde(with = "u256_dec")]
+        value: U256,
+        #[serde(with = "u256_dec")]
+        max_fee_per_gas: U256,
+        #[serde(with = "u256_dec")]
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+/// NFT standards a `TransferNft` step can move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+impl PlanStep {
+    pub fn action(&self) -> &'static str {
+        match self {
+            PlanStep::Approve { .. } => "approve",
+            PlanStep::Wrap { .. } => "wrap",
+            PlanStep::Swap { .. } => "swap",
+            PlanStep::Transfer { .. } => "transfer",
+            PlanStep::TransferNft { .. } => "transfer_nft",
+            PlanStep::BatchTransfer { .. } => "batch_transfer",
+            PlanStep::CreateStream { .. } => "create_stream",
+            PlanStep::CancelStream { .. } => "cancel_stream",
+            PlanStep::Vote { .. } => "vote",
+            PlanStep::Delegate { .. } => "delegate",
+            PlanStep::RenewEns { .. } => "renew_ens",
+            PlanStep::Cancel { .. } => "cancel",
+            PlanStep::SpeedUp { .. } => "speed_up",
+        }
+    }
+
+    /// Addresses this step pays
+    pub fn recipients(&self) -> Vec<Address> {
+        match self {
+            PlanStep::Transfer { to, .. }
+            | PlanStep::TransferNft { to, .. }
+            | PlanStep::CreateStream { to, .. } => vec![*to],
+            PlanStep::BatchTransfer { payments, .. } => {
+                payments.iter().map(|payment| payment.to).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Addresses this step pays or lets spend the wallet's tokens
+    pub fn counterparties(&self) -> Vec<Address> {
+        match self {
+            PlanStep::Approve { spender, .. } => vec![*spender],
+            _ => self.recipients(),
+        }
+    }
+
+    /// Build the transaction for this step, sent from `from`
+    pub fn to_transaction(&self, from: Address, deadline: U256) -> Result<TypedTransaction> {
+        let (to, data, value) = match self {
+            // Replacements pin their nonce and fees
+            PlanStep::Cancel {
+                nonce,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                return Ok(Eip1559TransactionRequest::new()
+                    .from(from)
+                    .to(from)
+                    .value(U256::zero())
+                    .nonce(*nonce)
+                    .max_fee_per_gas(*max_fee_per_gas)
+                    .max_priority_fee_per_gas(*max_priority_fee_per_gas)
+                    .into());
+            }
+            PlanStep::SpeedUp {
+                nonce,
+                to,
+                data,
+                value,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let mut tx = Eip1559TransactionRequest::new()
+                    .from(from)
+                    .data(data.clone())
+                    .value(*value)
+                    .nonce(*nonce)
+                    .max_fee_per_gas(*max_fee_per_gas)
+                    .max_priority_fee_per_gas(*max_priority_fee_per_gas);
+                if let Some(to) = to {
+                    tx = tx.to(*to);
+                }
+                return Ok(tx.into());
+            }
+            PlanStep::Approve {
+                token,
+                spender,
+                amount,
+            } => (
+                *token,
+                ApproveCall {
+                    spender: *spender,
+                    amount: *amount,
+                }
+                .encode(),
+                U256::zero(),
+            ),
+            PlanStep::Wrap { amount } => (
+                WETH.parse().context("Invalid WETH address")?,
+                DepositCall.encode(),
+                *amount,
+            ),
+            PlanStep::Swap {
+                router,
+                path,
+                amount_in,
+                amount_out_min,
+                fee_on_transfer,
+            } => {
+                let data = if *fee_on_transfer {
+                    SwapExactTokensForTokensSupportingFeeOnTransferTokensCall {
+                        amount_in: *amount_in,
+                        amount_out_min: *amount_out_min,
+                        path: path.clone(),
+                        to: from,
+                        deadline,
+                    }
+                    .encode()
+                } else {
+                    SwapExactTokensForTokensCall {
+                        amount_in: *amount_in,
+                        amount_out_min: *amount_out_min,
+                        path: path.clone(),
+                        to: from,
+                        deadline,
+                    }
+                    .encode()
+                };
+                (*router, data, U256::zero())
+            }
+            PlanStep::Transfer {
+                token: Some(token),
+                to,
+                amount,
+            } => (
+                *token,
+                TransferCall {
+                    to: *to,
+                    amount: *amount,
+                }
+                .encode(),
+                U256::zero(),
+            ),
+            PlanStep::Transfer {
+                token: None,
+                to,
+                amount,
+            } => (*to, Vec::new(), *amount),
+            PlanStep::TransferNft {
+                contract,
+                standard,
+                to,
+                token_id,
+                amount,
+            } => {
+                let data = match standard {
+                    NftStandard::Erc721 => ierc721_actions::SafeTransferFromCall {
+                        from,
+                        to: *to,
+                        token_id: *token_id,
+                    }
+                    .encode(),
+                    NftStandard::Erc1155 => ierc1155_actions::SafeTransferFromCall {
+                        from,
+                        to: *to,
+                        id: *token_id,
+                        amount: *amount,
+                        data: Bytes::new(),
+                    }
+                    .encode(),
+                };
+                (*contract, data, U256::zero())
+            }
+            PlanStep::BatchTransfer {
+                contract,
+                token,
+                payments,
+            } => {
+                let recipients = payments.iter().map(|payment| payment.to).collect();
+                let values: Vec<U256> = payments.iter().map(|payment| payment.amount).collect();
+                match token {
+                    Some(token) => (
+                        *contract,
+                        DisperseTokenCall {
+                            token: *token,
+                            recipients,
+                            values,
+                        }
+                        .encode(),
+                        U256::zero(),
+                    ),
+                    None => {
+                        let total = values
+                            .iter()
+                            .fold(U256::zero(), |total, value| total.saturating_add(*value));
+                        (
+                            *contract,
+                            DisperseEtherCall { recipients, values }.encode(),
+                            total,
+                        )
+                    }
+                }
+            }
+            PlanStep::CreateStream {
+                contract,
+                token,
+                to,
+                amount,
+                cliff_secs,
+                duration_secs,
+                cancelable,
+            } => (
+                *contract,
+                CreateWithDurationsCall {
+                    params: CreateWithDurations {
+                        sender: from,
+                        recipient: *to,
+                        total_amount: amount.low_u128(),
+                        asset: *token,
+                        cancelable: *cancelable,
+                        transferable: true,
+                        durations: Durations {
+                            cliff: *cliff_secs,
+                            total: *duration_secs,
+                        },
+                        broker: Broker {
+                            account: Address::zero(),
+                            fee: U256::zero(),
+                        },
+                    },
+                }
+                .encode(),
+                U256::zero(),
+            ),
+            PlanStep::CancelStream {
+                contract,
+                stream_id,
+            } => (
+                *contract,
+                CancelCall {
+                    stream_id: *stream_id,
+                }
+                .encode(),
+                U256::zero(),
+            ),
+            PlanStep::Vote {
+                governor,
+                proposal_id,
+                support,
+                reason,
+            } => {
+                let data = match reason {
+                    Some(reason) => CastVoteWithReasonCall {
+                        proposal_id: *proposal_id,
+                        support: *support,
+                        reason: reason.clone(),
+                    }
+                    .encode(),
+                    None => CastVoteCall {
+                        proposal_id: *proposal_id,
+                        support: *support,
+                    }
+                    .encode(),
+                };
+                (*governor, data, U256::zero())
+            }
+            PlanStep::Delegate { token, delegatee } => (
+                *token,
+                DelegateCall {
+                    delegatee: *delegatee,
+                }
+                .encode(),
+                U256::zero(),
+            ),
+            PlanStep::RenewEns {
+                controller,
+                label,
+                duration_secs,
+                value,
+            } => (
+                *controller,
+                RenewCall {
+                    name: label.clone(),
+                    duration: U256::from(*duration_secs),
+                }
+                .encode(),
+                *value,
+            ),
+        };
+
+        Ok(TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .data(data)
+            .value(value)
+            .into())
+    }
+}
+
+/// One recipient of a batch transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Payment {
+    pub to: Address,
+    #[serde(with = "u256_dec")]
+    pub amount: U256,
+}
+
+/// An ordered list of transactions produced by a planning tool and run by `execute_plan`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub steps: Vec<PlanStep>,
+}
+
+/// Base-unit amounts as decimal strings; `0x` hex is accepted on input
+mod u256_dec {
+    use ethers::types::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        let parsed = match raw.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(&raw).ok(),
+        };
+        parsed.ok_or_else(|| serde::de::Error::custom(format!("invalid amount: {}", raw)))
+    }
+}
@@ -0,0 +1,202 @@
+use crate::ethereum::pools::FactoryKind;
+use crate::ethereum::uniswap::IUniswapV2Pair;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::sync::Arc;
+
+// Uniswap V2 pairs charge a flat 0.3%, expressed like a V3 fee tier
+const V2_FEE_TIER: u32 = 3000;
+
+abigen!(
+    IPoolTokens,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+        function fee() external view returns (uint24)
+    ]"#
+);
+
+abigen!(
+    IV3PoolSwaps,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick)
+    ]"#
+);
+
+abigen!(
+    IPoolBalance,
+    r#"[
+        function balanceOf(address) external view returns (uint256)
+    ]"#
+);
+
+/// Tokens, fee and current holdings of a V2 pair or V3 pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolState {
+    pub kind: FactoryKind,
+    pub token0: Address,
+    pub token1: Address,
+    /// Fee in hundredths of a bip
+    pub fee: u32,
+    /// Reserves of a V2 pair, token balances of a V3 pool
+    pub balance0: U256,
+    pub balance1: U256,
+    /// Current V3 price; `None` for V2 pairs, whose reserves give the price
+    pub sqrt_price_x96: Option<U256>,
+}
+
+/// Amounts of each token one swap moved, in raw units
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapAmounts {
+    pub block_number: u64,
+    pub amount0: U256,
+    pub amount1: U256,
+}
+
+/// Pools whose state and swaps can be read straight from the chain
+#[async_trait]
+pub trait PoolActivitySource: Send + Sync {
+    /// Tokens, fee tier and holdings of `pool`, a V2 pair or V3 pool
+    async fn pool_state(&self, pool: Address) -> Result<PoolState>;
+
+    /// Swaps through `pool` in `from_block..=to_block`, oldest first
+    async fn swaps(
+        &self,
+        pool: Address,
+        kind: FactoryKind,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SwapAmounts>>;
+}
+
+pub struct PoolActivity {
+    provider: Arc<Provider<Http>>,
+}
+
+impl PoolActivity {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self { provider }
+    }
+
+    pub async fn pool_state(&self, pool: Address) -> Result<PoolState> {
+        let tokens = IPoolTokens::new(pool, Arc::clone(&self.provider));
+        let token0 = tokens
+            .token_0()
+            .call()
+            .await
+            .context("Failed to get pool token0")?;
+        let token1 = tokens
+            .token_1()
+            .call()
+            .await
+            .context("Failed to get pool token1")?;
+
+        // Only V3 pools have a fee tier of their own
+        match tokens.fee().call().await {
+            Ok(fee) => {
+                let balance = |token| async move {
+                    IPoolBalance::new(token, Arc::clone(&self.provider))
+                        .balance_of(pool)
+                        .call()
+                        .await
+                        .context("Failed to get pool token balance")
+                };
+                let (sqrt_price_x96, ..) = IV3PoolSwaps::new(pool, Arc::clone(&self.provider))
+                    .slot_0()
+                    .call()
+                    .await
+                    .context("Failed to get V3 pool price")?;
+                Ok(PoolState {
+                    kind: FactoryKind::V3,
+                    token0,
+                    token1,
+                    fee,
+                    balance0: balance(token0).await?,
+                    balance1: balance(token1).await?,
+                    sqrt_price_x96: Some(sqrt_price_x96),
+                })
+            }
+            Err(_) => {
+                let (reserve0, reserve1, _) = IUniswapV2Pair::new(pool, Arc::clone(&self.provider))
+                    .get_reserves()
+                    .call()
+                    .await
+                    .context("Failed to get pool reserves")?;
+                Ok(PoolState {
+                    kind: FactoryKind::V2,
+                    token0,
+                    token1,
+                    fee: V2_FEE_TIER,
+                    balance0: U256::from(reserve0),
+                    balance1: U256::from(reserve1),
+                    sqrt_price_x96: None,
+                })
+            }
+        }
+    }
+
+    pub async fn swaps(
+        &self,
+        pool: Address,
+        kind: FactoryKind,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SwapAmounts>> {
+        match kind {
+            FactoryKind::V2 => {
+                let swaps = IUniswapV2Pair::new(pool, Arc::clone(&self.provider))
+                    .swap_filter()
+                    .from_block(from_block)
+                    .to_block(to_block)
+                    .query_with_meta()
+                    .await
+                    .context("Failed to fetch Swap events")?;
+                Ok(swaps
+                    .into_iter()
+                    .map(|(swap, meta)| SwapAmounts {
+                        block_number: meta.block_number.as_u64(),
+                        amount0: swap.amount_0_in + swap.amount_0_out,
+                        amount1: swap.amount_1_in + swap.amount_1_out,
+                    })
+                    .collect())
+            }
+            FactoryKind::V3 => {
+                let swaps = IV3PoolSwaps::new(pool, Arc::clone(&self.provider))
+                    .swap_filter()
+                    .from_block(from_block)
+                    .to_block(to_block)
+                    .query_with_meta()
+                    .await
+                    .context("Failed to fetch V3 Swap events")?;
+                // Signed from the pool's side: positive in, negative out
+                Ok(swaps
+                    .into_iter()
+                    .map(|(swap, meta)| SwapAmounts {
+                        block_number: meta.block_number.as_u64(),
+                        amount0: swap.amount_0.unsigned_abs(),
+                        amount1: swap.amount_1.unsigned_abs(),
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PoolActivitySource for PoolActivity {
+    async fn pool_state(&self, pool: Address) -> Result<PoolState> {
+        self.pool_state(pool).await
+    }
+
+    async fn swaps(
+        &self,
+        pool: Address,
+        kind: FactoryKind,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SwapAmounts>> {
+        self.swaps(pool, kind, from_block, to_block).await
+    }
+}
@@ -0,0 +1,254 @@
+use crate::ethereum::uniswap::IUniswapV2Pair;
+use crate::ethereum::uniswap_v3::IUniswapV3Pool;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::sync::Arc;
+
+// Uniswap V2 pairs charge a flat 0.3%, expressed like a V3 fee tier
+const V2_FEE_TIER: u32 = 3000;
+
+abigen!(
+    IV2FactoryEvents,
+    r#"[
+        event PairCreated(address indexed token0, address indexed token1, address pair, uint256 allPairsLength)
+    ]"#
+);
+
+abigen!(
+    IV3FactoryEvents,
+    r#"[
+        event PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool)
+    ]"#
+);
+
+/// Which pool interface a factory deploys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactoryKind {
+    V2,
+    V3,
+}
+
+/// A DEX factory whose creation events are scanned for pools
+#[derive(Debug, Clone)]
+pub struct DexFactory {
+    pub name: &'static str,
+    pub address: Address,
+    pub kind: FactoryKind,
+    /// Block the factory was deployed at, where log scans start
+    pub deployment_block: u64,
+}
+
+/// Factories supported on Ethereum mainnet
+pub fn mainnet_factories() -> Result<Vec<DexFactory>> {
+    Ok(vec![
+        DexFactory {
+            name: "uniswap_v2",
+            address: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"
+                .parse()
+                .context("Invalid uniswap_v2 factory address")?,
+            kind: FactoryKind::V2,
+            deployment_block: 10_000_835,
+        },
+        DexFactory {
+            name: "sushiswap",
+            address: "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"
+                .parse()
+                .context("Invalid sushiswap factory address")?,
+            kind: FactoryKind::V2,
+            deployment_block: 10_794_229,
+        },
+        DexFactory {
+            name: "uniswap_v3",
+            address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                .parse()
+                .context("Invalid uniswap_v3 factory address")?,
+            kind: FactoryKind::V3,
+            deployment_block: 12_369_621,
+        },
+    ])
+}
+
+/// A pool found through its factory's creation event
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolInfo {
+    pub dex: String,
+    pub kind: FactoryKind,
+    pub pool_address: Address,
+    pub token0: Address,
+    pub token1: Address,
+    /// Fee in hundredths of a bip
+    pub fee: u32,
+    pub creation_block: u64,
+}
+
+/// Current depth of a pool
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolLiquidity {
+    Reserves { reserve0: U256, reserve1: U256 },
+    InRange { liquidity: u128 },
+}
+
+/// Trait for discovering pools across DEX factories
+#[async_trait]
+pub trait PoolDiscoveryTrait: Send + Sync {
+    /// Pools containing `token` (and `other`, when given), oldest first
+    async fn find_pools(
+        &self,
+        token: Address,
+        other: Option<Address>,
+        from_block: Option<u64>,
+    ) -> Result<Vec<PoolInfo>>;
+
+    /// Current reserves or in-range liquidity of a discovered pool
+    async fn get_liquidity(&self, pool: &PoolInfo) -> Result<PoolLiquidity>;
+}
+
+pub struct PoolDiscovery {
+    provider: Arc<Provider<Http>>,
+    factories: Vec<DexFactory>,
+}
+
+impl PoolDiscovery {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self::with_factories(provider, mainnet_factories()?))
+    }
+
+    pub fn with_factories(provider: Arc<Provider<Http>>, factories: Vec<DexFactory>) -> Self {
+        Self {
+            provider,
+            factories,
+        }
+    }
+
+    /// Token topic filters to run: both orderings for one token, the sorted pair for two
+    fn topic_pairs(
+        token: Address,
+        other: Option<Address>,
+    ) -> Vec<(Option<Address>, Option<Address>)> {
+        match other {
+            Some(other) => vec![(Some(token.min(other)), Some(token.max(other)))],
+            None => vec![(Some(token), None), (None, Some(token))],
+        }
+    }
+
+    async fn scan_factory(
+        &self,
+        factory: &DexFactory,
+        token: Address,
+        other: Option<Address>,
+        from_block: u64,
+    ) -> Result<Vec<PoolInfo>> {
+        let mut pools = Vec::new();
+        for (token0, token1) in Self::topic_pairs(token, other) {
+            match factory.kind {
+                FactoryKind::V2 => {
+                    let contract =
+                        IV2FactoryEvents::new(factory.address, Arc::clone(&self.provider));
+                    let mut event = contract.pair_created_filter().from_block(from_block);
+                    if let Some(token0) = token0 {
+                        event = event.topic1(token0);
+                    }
+                    if let Some(token1) = token1 {
+                        event = event.topic2(token1);
+                    }
+                    let logs = event.query_with_meta().await.with_context(|| {
+                        format!("Failed to fetch PairCreated events from {}", factory.name)
+                    })?;
+                    pools.extend(logs.into_iter().map(|(created, meta)| PoolInfo {
+                        dex: factory.name.to_string(),
+                        kind: factory.kind,
+                        pool_address: created.pair,
+                        token0: created.token_0,
+                        token1: created.token_1,
+                        fee: V2_FEE_TIER,
+                        creation_block: meta.block_number.as_u64(),
+                    }));
+                }
+                FactoryKind::V3 => {
+                    let contract =
+                        IV3FactoryEvents::new(factory.address, Arc::clone(&self.provider));
+                    let mut event = contract.pool_created_filter().from_block(from_block);
+                    if let Some(token0) = token0 {
+                        event = event.topic1(token0);
+                    }
+                    if let Some(token1) = token1 {
+                        event = event.topic2(token1);
+                    }
+                    let logs = event.query_with_meta().await.with_context(|| {
+                        format!("Failed to fetch PoolCreated events from {}", factory.name)
+                    })?;
+                    pools.extend(logs.into_iter().map(|(created, meta)| PoolInfo {
+                        dex: factory.name.to_string(),
+                        kind: factory.kind,
+                        pool_address: created.pool,
+                        token0: created.token_0,
+                        token1: created.token_1,
+                        fee: created.fee,
+                        creation_block: meta.block_number.as_u64(),
+                    }));
+                }
+            }
+        }
+        Ok(pools)
+    }
+
+    /// Pools containing `token` (and `other`, when given), oldest first
+    pub async fn find_pools(
+        &self,
+        token: Address,
+        other: Option<Address>,
+        from_block: Option<u64>,
+    ) -> Result<Vec<PoolInfo>> {
+        let mut pools = Vec::new();
+        for factory in &self.factories {
+            let start = from_block.unwrap_or(0).max(factory.deployment_block);
+            pools.extend(self.scan_factory(factory, token, other, start).await?);
+        }
+        pools.sort_by_key(|pool| pool.creation_block);
+        Ok(pools)
+    }
+
+    /// Current reserves or in-range liquidity of a discovered pool
+    pub async fn get_liquidity(&self, pool: &PoolInfo) -> Result<PoolLiquidity> {
+        match pool.kind {
+            FactoryKind::V2 => {
+                let pair = IUniswapV2Pair::new(pool.pool_address, Arc::clone(&self.provider));
+                let (reserve0, reserve1, _) = pair
+                    .get_reserves()
+                    .call()
+                    .await
+                    .context("Failed to get pool reserves")?;
+                Ok(PoolLiquidity::Reserves {
+                    reserve0: U256::from(reserve0),
+                    reserve1: U256::from(reserve1),
+                })
+            }
+            FactoryKind::V3 => {
+                let v3_pool = IUniswapV3Pool::new(pool.pool_address, Arc::clone(&self.provider));
+                let liquidity = v3_pool
+                    .liquidity()
+                    .call()
+                    .await
+                    .context("Failed to get V3 pool liquidity")?;
+                Ok(PoolLiquidity::InRange { liquidity })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PoolDiscoveryTrait for PoolDiscovery {
+    async fn find_pools(
+        &self,
+        token: Address,
+        other: Option<Address>,
+        from_block: Option<u64>,
+    ) -> Result<Vec<PoolInfo>> {
+        self.find_pools(token, other, from_block).await
+    }
+
+    async fn get_liquidity(&self, pool: &PoolInfo) -> Result<PoolLiquidity> {
+        self.get_liquidity(pool).await
+    }
+}
@@ -0,0 +1,142 @@
+use super::fx::IChainlinkAggregator;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Token feeds update at least daily; allow an hour on top of the heartbeat
+const MAX_PRICE_AGE_SECS: u64 = 25 * 60 * 60;
+
+// Chainlink `<token>/USD` aggregators on Ethereum mainnet, by token
+const CHAINLINK_PRICE_FEEDS: &[(&str, &str, &str)] = &[
+    (
+        "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "ETH",
+        "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419",
+    ),
+    (
+        "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599",
+        "BTC",
+        "0xF4030086522a5bEEa4988F8cA5B36dbC97BeE88c",
+    ),
+    (
+        "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "USDC",
+        "0x8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6",
+    ),
+    (
+        "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+        "USDT",
+        "0x3E7d1eAB13ad0104d2750B8863b489D65364e32D",
+    ),
+    (
+        "0x6B175474E89094C44Da98b954EedeAC495271d0F",
+        "DAI",
+        "0xAed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9",
+    ),
+    (
+        "0x514910771AF9Ca656af840dff83E8264EcF986CA",
+        "LINK",
+        "0x2c1d072e956AFFC0D435Cb7AC38EF18d24d9127c",
+    ),
+    (
+        "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984",
+        "UNI",
+        "0x553303d460EE0afB37EdFf9bE42922D8FF63220e",
+    ),
+    (
+        "0x7Fc66500c84A76Ad7e9c93437bFc5Ac33E2DDaE9",
+        "AAVE",
+        "0x547a514d5e3769680Ce22B2361c10Ea13619e8a9",
+    ),
+    (
+        "0x9f8F72aA9304c8B593d555F12eF6589cC3A579A2",
+        "MKR",
+        "0xec1D1B3b0443256cc3860e24a46F108e699484Aa",
+    ),
+    (
+        "0xC011a73ee8576Fb46F5E1c5751cA3B9Fe0af2a6F",
+        "SNX",
+        "0xDC3EA94CD0AC27d9A86C180091e7f78C683d3699",
+    ),
+];
+
+/// A token's USD price from an oracle, to hold DEX prices against
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencePrice {
+    pub usd_price: Decimal,
+    /// Name of the feed, such as `Chainlink ETH/USD`
+    pub source: String,
+    /// Unix seconds of the source's last update
+    pub updated_at: u64,
+}
+
+/// A source of oracle prices independent of DEX pools
+#[async_trait]
+pub trait ReferencePriceSource: Send + Sync {
+    /// USD price of `token`, or `None` when the source has no feed for it
+    async fn usd_price(&self, token: Address) -> Result<Option<ReferencePrice>>;
+}
+
+/// Token prices read from Chainlink `<token>/USD` feeds, on Ethereum mainnet only
+pub struct ChainlinkPriceFeeds {
+    provider: Arc<Provider<Http>>,
+    chain_id: u64,
+}
+
+impl ChainlinkPriceFeeds {
+    pub fn new(provider: Arc<Provider<Http>>, chain_id: u64) -> Self {
+        Self { provider, chain_id }
+    }
+}
+
+#[async_trait]
+impl ReferencePriceSource for ChainlinkPriceFeeds {
+    async fn usd_price(&self, token: Address) -> Result<Option<ReferencePrice>> {
+        if self.chain_id != 1 {
+            return Ok(None);
+        }
+        let Some((_, asset, feed)) = CHAINLINK_PRICE_FEEDS
+            .iter()
+            .find(|(address, _, _)| address.parse::<Address>().ok() == Some(token))
+        else {
+            return Ok(None);
+        };
+        let feed: Address = feed
+            .parse()
+            .with_context(|| format!("Invalid Chainlink {} feed address", asset))?;
+
+        let aggregator = IChainlinkAggregator::new(feed, Arc::clone(&self.provider));
+        let decimals = aggregator
+            .decimals()
+            .call()
+            .await
+            .context("Failed to read price feed decimals")?;
+        let (_, answer, _, updated_at, _) = aggregator
+            .latest_round_data()
+            .call()
+            .await
+            .with_context(|| format!("Failed to read the {}/USD feed", asset))?;
+
+        if answer <= I256::zero() {
+            anyhow::bail!("{}/USD feed returned a non-positive price", asset);
+        }
+        let updated_at = updated_at.as_u64();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if now.saturating_sub(updated_at) > MAX_PRICE_AGE_SECS {
+            anyhow::bail!(
+                "{}/USD feed is stale: last updated {} seconds ago",
+                asset,
+                now - updated_at
+            );
+        }
+
+        Ok(Some(ReferencePrice {
+            usd_price: Decimal::from_i128_with_scale(answer.as_i128(), decimals as u32),
+            source: format!("Chainlink {}/USD", asset),
+            updated_at,
+        }))
+    }
+}
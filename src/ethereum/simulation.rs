@@ -0,0 +1,145 @@
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::collections::BTreeMap;
+
+/// Events named in simulation results, by signature
+const KNOWN_EVENTS: &[&str] = &[
+    "Transfer(address,address,uint256)",
+    "Approval(address,address,uint256)",
+    "ApprovalForAll(address,address,bool)",
+    "TransferSingle(address,address,address,uint256,uint256)",
+    "TransferBatch(address,address,address,uint256[],uint256[])",
+    "Deposit(address,uint256)",
+    "Withdrawal(address,uint256)",
+    "Swap(address,uint256,uint256,uint256,uint256,address)",
+    "Swap(address,address,int256,int256,uint160,uint128,int24)",
+    "Sync(uint112,uint112)",
+    "DelegateChanged(address,address,address)",
+    "VoteCast(address,uint256,uint8,uint256,string)",
+];
+
+/// Outcome of one transaction of a simulated bundle. Each runs on the state the
+/// ones before it left; a reverted one leaves no changes behind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulatedCall {
+    pub success: bool,
+    pub gas_used: U256,
+    pub return_data: Bytes,
+    /// Revert reason or error of a failed call
+    pub error: Option<String>,
+    /// Emitted events, with native ETH transfers as `Transfer` logs of
+    /// `native_transfer_address()`
+    pub logs: Vec<Log>,
+}
+
+/// Address `eth_simulateV1` emits traced ETH transfers from, as ERC20-style logs
+pub fn native_transfer_address() -> Address {
+    Address::repeat_byte(0xee)
+}
+
+/// Signature of the event `topic0` identifies, when it is a well-known one
+pub fn event_signature(topic0: H256) -> Option<&'static str> {
+    KNOWN_EVENTS
+        .iter()
+        .find(|signature| H256::from(keccak256(signature.as_bytes())) == topic0)
+        .copied()
+}
+
+/// Movement of ETH (`token: None`) or an ERC20 found in a log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetTransfer {
+    pub token: Option<Address>,
+    /// The zero address for mints, such as wrapping ETH
+    pub from: Address,
+    /// The zero address for burns, such as unwrapping WETH
+    pub to: Address,
+    pub amount: U256,
+}
+
+/// ETH and ERC20 movements in `logs`: ERC20 `Transfer`s, WETH-style `Deposit`s and
+/// `Withdrawal`s, and traced ETH transfers. NFT transfers are left out.
+pub fn asset_transfers(logs: &[Log]) -> Vec<AssetTransfer> {
+    let transfer = H256::from(keccak256("Transfer(address,address,uint256)"));
+    let deposit = H256::from(keccak256("Deposit(address,uint256)"));
+    let withdrawal = H256::from(keccak256("Withdrawal(address,uint256)"));
+    let address = |topic: &H256| Address::from_slice(&topic.as_bytes()[12..]);
+
+    logs.iter()
+        .filter(|log| log.data.len() == 32)
+        .filter_map(|log| {
+            let amount = U256::from_big_endian(&log.data);
+            let token = (log.address != native_transfer_address()).then_some(log.address);
+            match log.topics.as_slice() {
+                // ERC721 transfers index the token id, leaving no data
+                [topic, from, to] if *topic == transfer => Some(AssetTransfer {
+                    token,
+                    from: address(from),
+                    to: address(to),
+                    amount,
+                }),
+                [topic, to] if *topic == deposit && token.is_some() => Some(AssetTransfer {
+                    token,
+                    from: Address::zero(),
+                    to: address(to),
+                    amount,
+                }),
+                [topic, from] if *topic == withdrawal && token.is_some() => Some(AssetTransfer {
+                    token,
+                    from: address(from),
+                    to: Address::zero(),
+                    amount,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Net change of one account's balance of ETH (`token: None`) or an ERC20
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub account: Address,
+    pub token: Option<Address>,
+    pub received: U256,
+    pub sent: U256,
+}
+
+impl BalanceChange {
+    /// Whether the balance falls, and by how much it moves
+    pub fn net(&self) -> (bool, U256) {
+        if self.sent > self.received {
+            (true, self.sent - self.received)
+        } else {
+            (false, self.received - self.sent)
+        }
+    }
+}
+
+/// Net balance changes `transfers` add up to, per account and asset, leaving out
+/// the zero address and accounts that end where they started
+pub fn balance_changes(transfers: &[AssetTransfer]) -> Vec<BalanceChange> {
+    let mut changes: BTreeMap<(Address, Option<Address>), BalanceChange> = BTreeMap::new();
+    for transfer in transfers {
+        for (account, incoming) in [(transfer.from, false), (transfer.to, true)] {
+            if account.is_zero() {
+                continue;
+            }
+            let change = changes
+                .entry((account, transfer.token))
+                .or_insert(BalanceChange {
+                    account,
+                    token: transfer.token,
+                    ..Default::default()
+                });
+            if incoming {
+                change.received = change.received.saturating_add(transfer.amount);
+            } else {
+                change.sent = change.sent.saturating_add(transfer.amount);
+            }
+        }
+    }
+    changes
+        .into_values()
+        .filter(|change| change.received != change.sent)
+        .collect()
+}
@@ -0,0 +1,514 @@
+use super::client::TransactionOutcome;
+use crate::secrets::SecretString;
+use anyhow::{Context, Result};
+use ethers::abi::{encode, Token};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{id, keccak256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// EntryPoint v0.6, deployed at the same address on every chain
+pub const DEFAULT_ENTRY_POINT: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+
+// Signed forward requests lapse this long after signing
+const FORWARD_REQUEST_TTL_SECS: u64 = 10 * 60;
+
+// How often a bundler is asked whether a user operation landed, and for how long
+const USER_OP_POLL_INTERVAL: Duration = Duration::from_secs(4);
+const USER_OP_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const FORWARD_REQUEST_TYPE: &str = "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint48 deadline,bytes data)";
+const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const FORWARDER_EXECUTE: &str = "execute((address,address,uint256,uint256,uint48,bytes,bytes))";
+
+abigen!(
+    IERC2771Forwarder,
+    r#"[
+        function nonces(address owner) external view returns (uint256)
+        function eip712Domain() external view returns (bytes1 fields, string name, string version, uint256 chainId, address verifyingContract, bytes32 salt, uint256[] extensions)
+    ]"#
+);
+
+abigen!(
+    IERC2771Recipient,
+    r#"[
+        function isTrustedForwarder(address forwarder) external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    IEntryPoint,
+    r#"[
+        function getNonce(address sender, uint192 key) external view returns (uint256 nonce)
+    ]"#
+);
+
+abigen!(
+    ISimpleAccount,
+    r#"[
+        function execute(address dest, uint256 value, bytes func) external
+    ]"#
+);
+
+/// Who pays for gas in place of the wallet
+#[derive(Debug, Clone, PartialEq)]
+pub enum GasSponsorConfig {
+    /// An OpenZeppelin ERC2771Forwarder: the wallet signs each call as a forward
+    /// request and the relayer key sends it, paying for the gas
+    Forwarder {
+        forwarder: Address,
+        relayer_key: SecretString,
+    },
+    /// An ERC-4337 paymaster: calls run as user operations of `account`, a
+    /// SimpleAccount the wallet owns, and the paymaster covers their gas
+    Paymaster {
+        account: Address,
+        entry_point: Address,
+        bundler_url: String,
+        paymaster_url: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SponsorKind {
+    Erc2771Forwarder,
+    Erc4337Paymaster,
+}
+
+/// How a transaction's gas was paid for on the wallet's behalf
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Sponsorship {
+    pub kind: SponsorKind,
+    /// Account whose ETH paid for the gas: the relayer, or the paymaster's deposit
+    pub sponsor: Address,
+    /// Forwarder or EntryPoint the call went through
+    pub via: Address,
+    /// Gas fee the sponsor paid, in wei
+    pub gas_cost: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_op_hash: Option<H256>,
+}
+
+/// ERC-4337 v0.6 user operation, as bundlers take it over JSON-RPC
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperation {
+    sender: Address,
+    nonce: U256,
+    init_code: Bytes,
+    call_data: Bytes,
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+    paymaster_and_data: Bytes,
+    signature: Bytes,
+}
+
+impl UserOperation {
+    /// Hash the account signs: the operation without its signature, bound to the
+    /// EntryPoint and chain
+    fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        let packed = encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+        H256::from(keccak256(encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(U256::from(chain_id)),
+        ])))
+    }
+}
+
+/// Gas fields a paymaster fills in when it agrees to sponsor an operation
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymasterSponsorship {
+    paymaster_and_data: Bytes,
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperationReceipt {
+    success: bool,
+    actual_gas_cost: U256,
+    actual_gas_used: U256,
+    receipt: BundleReceipt,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleReceipt {
+    transaction_hash: H256,
+}
+
+/// EIP-712 digest of an ERC2771Forwarder forward request
+#[allow(clippy::too_many_arguments)]
+fn forward_request_digest(
+    forwarder: Address,
+    chain_id: u64,
+    name: &str,
+    version: &str,
+    from: Address,
+    to: Address,
+    value: U256,
+    gas: U256,
+    nonce: U256,
+    deadline: u64,
+    data: &Bytes,
+) -> H256 {
+    let domain_separator = keccak256(encode(&[
+        Token::FixedBytes(keccak256(EIP712_DOMAIN_TYPE).to_vec()),
+        Token::FixedBytes(keccak256(name).to_vec()),
+        Token::FixedBytes(keccak256(version).to_vec()),
+        Token::Uint(U256::from(chain_id)),
+        Token::Address(forwarder),
+    ]));
+    let struct_hash = keccak256(encode(&[
+        Token::FixedBytes(keccak256(FORWARD_REQUEST_TYPE).to_vec()),
+        Token::Address(from),
+        Token::Address(to),
+        Token::Uint(value),
+        Token::Uint(gas),
+        Token::Uint(nonce),
+        Token::Uint(U256::from(deadline)),
+        Token::FixedBytes(keccak256(data).to_vec()),
+    ]));
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(&[0x19, 0x01]);
+    message.extend_from_slice(&domain_separator);
+    message.extend_from_slice(&struct_hash);
+    H256::from(keccak256(message))
+}
+
+enum SponsorMode {
+    Forwarder {
+        forwarder: Address,
+        relayer: LocalWallet,
+    },
+    Paymaster {
+        account: Address,
+        entry_point: Address,
+        bundler: Arc<Provider<Http>>,
+        paymaster: Arc<Provider<Http>>,
+    },
+}
+
+/// Sends the wallet's transactions with a forwarder's relayer or a paymaster paying
+/// for gas, so the wallet need not hold ETH for it
+pub struct GasSponsor {
+    provider: Arc<Provider<Http>>,
+    chain_id: u64,
+    mode: SponsorMode,
+}
+
+impl GasSponsor {
+    pub fn new(
+        config: &GasSponsorConfig,
+        provider: Arc<Provider<Http>>,
+        chain_id: u64,
+    ) -> Result<Self> {
+        let mode = match config {
+            GasSponsorConfig::Forwarder {
+                forwarder,
+                relayer_key,
+            } => SponsorMode::Forwarder {
+                forwarder: *forwarder,
+                relayer: relayer_key
+                    .expose_secret()
+                    .parse::<LocalWallet>()
+                    .context("Failed to parse relayer private key")?
+                    .with_chain_id(chain_id),
+            },
+            GasSponsorConfig::Paymaster {
+                account,
+                entry_point,
+                bundler_url,
+                paymaster_url,
+            } => SponsorMode::Paymaster {
+                account: *account,
+                entry_point: *entry_point,
+                bundler: Arc::new(
+                    Provider::<Http>::try_from(bundler_url.as_str())
+                        .context("Invalid bundler URL")?,
+                ),
+                paymaster: Arc::new(
+                    Provider::<Http>::try_from(paymaster_url.as_str())
+                        .context("Invalid paymaster URL")?,
+                ),
+            },
+        };
+        Ok(Self {
+            provider,
+            chain_id,
+            mode,
+        })
+    }
+
+    /// The smart account calls run from under a paymaster; `None` when they run
+    /// from the wallet itself
+    pub fn account(&self) -> Option<Address> {
+        match &self.mode {
+            SponsorMode::Forwarder { .. } => None,
+            SponsorMode::Paymaster { account, .. } => Some(*account),
+        }
+    }
+
+    /// Whether `tx` can be sponsored. A forwarder only relays calls without value to
+    /// contracts that trust it; a paymaster is asked when the operation is sent.
+    pub async fn covers(&self, tx: &TypedTransaction) -> bool {
+        match &self.mode {
+            SponsorMode::Forwarder { forwarder, .. } => {
+                let Some(to) = tx.to_addr() else {
+                    return false;
+                };
+                if !tx.value().copied().unwrap_or_default().is_zero() {
+                    return false;
+                }
+                IERC2771Recipient::new(*to, Arc::clone(&self.provider))
+                    .is_trusted_forwarder(*forwarder)
+                    .call()
+                    .await
+                    .unwrap_or(false)
+            }
+            SponsorMode::Paymaster { .. } => true,
+        }
+    }
+
+    /// Have the sponsor pay for `tx`, signed by `wallet`, and wait for it to land
+    pub async fn send(
+        &self,
+        wallet: &LocalWallet,
+        tx: TypedTransaction,
+    ) -> Result<TransactionOutcome> {
+        match &self.mode {
+            SponsorMode::Forwarder { forwarder, relayer } => {
+                self.send_forwarded(wallet, *forwarder, relayer, tx).await
+            }
+            SponsorMode::Paymaster {
+                account,
+                entry_point,
+                bundler,
+                paymaster,
+            } => {
+                self.send_user_operation(wallet, *account, *entry_point, bundler, paymaster, tx)
+                    .await
+            }
+        }
+    }
+
+    async fn send_forwarded(
+        &self,
+        wallet: &LocalWallet,
+        forwarder: Address,
+        relayer: &LocalWallet,
+        mut tx: TypedTransaction,
+    ) -> Result<TransactionOutcome> {
+        let from = wallet.address();
+        let to = *tx
+            .to_addr()
+            .context("Sponsored transactions need a target")?;
+        let value = tx.value().copied().unwrap_or_default();
+        let data = tx.data().cloned().unwrap_or_default();
+        tx.set_from(from);
+        // Calls through the forwarder carry the sender in their calldata, so leave headroom
+        let gas = self
+            .provider
+            .estimate_gas(&tx, None)
+            .await
+            .context("Failed to estimate gas")?
+            * 11
+            / 10;
+
+        let contract = IERC2771Forwarder::new(forwarder, Arc::clone(&self.provider));
+        let nonce = contract
+            .nonces(from)
+            .call()
+            .await
+            .context("Failed to read the forwarder nonce")?;
+        let (_, name, version, _, _, _, _) = contract
+            .eip_712_domain()
+            .call()
+            .await
+            .context("Failed to read the forwarder's EIP-712 domain")?;
+        let deadline =
+            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + FORWARD_REQUEST_TTL_SECS;
+        let digest = forward_request_digest(
+            forwarder,
+            self.chain_id,
+            &name,
+            &version,
+            from,
+            to,
+            value,
+            gas,
+            nonce,
+            deadline,
+            &data,
+        );
+        let signature = wallet
+            .sign_hash(digest)
+            .context("Failed to sign the forward request")?;
+
+        let mut calldata = id(FORWARDER_EXECUTE).to_vec();
+        calldata.extend(encode(&[Token::Tuple(vec![
+            Token::Address(from),
+            Token::Address(to),
+            Token::Uint(value),
+            Token::Uint(gas),
+            Token::Uint(U256::from(deadline)),
+            Token::Bytes(data.to_vec()),
+            Token::Bytes(signature.to_vec()),
+        ])]));
+        let signer = SignerMiddleware::new(Arc::clone(&self.provider), relayer.clone());
+        let request = Eip1559TransactionRequest::new()
+            .to(forwarder)
+            .data(calldata);
+        let pending = signer
+            .send_transaction(request, None)
+            .await
+            .context("The relayer failed to send the forward request")?;
+        let tx_hash = pending.tx_hash();
+        let receipt = pending
+            .await
+            .context("Failed to wait for transaction receipt")?
+            .with_context(|| format!("Transaction {:?} was dropped", tx_hash))?;
+        let gas_used = receipt.gas_used.unwrap_or_default();
+
+        Ok(TransactionOutcome {
+            tx_hash,
+            success: receipt.status == Some(U64::one()),
+            gas_used,
+            block_number: receipt.block_number.map(|block| block.as_u64()),
+            effective_gas_price: receipt.effective_gas_price,
+            replacements: Vec::new(),
+            sponsorship: Some(Sponsorship {
+                kind: SponsorKind::Erc2771Forwarder,
+                sponsor: relayer.address(),
+                via: forwarder,
+                gas_cost: gas_used * receipt.effective_gas_price.unwrap_or_default(),
+                user_op_hash: None,
+            }),
+        })
+    }
+
+    async fn send_user_operation(
+        &self,
+        wallet: &LocalWallet,
+        account: Address,
+        entry_point: Address,
+        bundler: &Provider<Http>,
+        paymaster: &Provider<Http>,
+        tx: TypedTransaction,
+    ) -> Result<TransactionOutcome> {
+        let to = *tx
+            .to_addr()
+            .context("Sponsored transactions need a target")?;
+        let call_data = ISimpleAccount::new(account, Arc::clone(&self.provider))
+            .execute(
+                to,
+                tx.value().copied().unwrap_or_default(),
+                tx.data().cloned().unwrap_or_default(),
+            )
+            .calldata()
+            .context("Failed to encode the account call")?;
+        let nonce = IEntryPoint::new(entry_point, Arc::clone(&self.provider))
+            .get_nonce(account, U256::zero())
+            .call()
+            .await
+            .context("Failed to read the account nonce")?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self
+            .provider
+            .estimate_eip1559_fees(None)
+            .await
+            .context("Failed to estimate fees")?;
+
+        let mut op = UserOperation {
+            sender: account,
+            nonce,
+            call_data,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            // Placeholder of a signature's length, for the paymaster's gas estimate
+            signature: Bytes::from(vec![0xff; 65]),
+            ..Default::default()
+        };
+        let sponsored: PaymasterSponsorship = paymaster
+            .request("pm_sponsorUserOperation", (&op, entry_point))
+            .await
+            .context("The paymaster declined to sponsor the operation")?;
+        op.paymaster_and_data = sponsored.paymaster_and_data;
+        op.call_gas_limit = sponsored.call_gas_limit;
+        op.verification_gas_limit = sponsored.verification_gas_limit;
+        op.pre_verification_gas = sponsored.pre_verification_gas;
+        let paymaster_address = op
+            .paymaster_and_data
+            .get(..20)
+            .map(Address::from_slice)
+            .context("The paymaster returned no paymasterAndData")?;
+
+        // SimpleAccount checks an EIP-191 signature of the operation hash
+        op.signature = wallet
+            .sign_message(op.hash(entry_point, self.chain_id))
+            .await
+            .context("Failed to sign the user operation")?
+            .to_vec()
+            .into();
+        let user_op_hash: H256 = bundler
+            .request("eth_sendUserOperation", (&op, entry_point))
+            .await
+            .context("The bundler rejected the user operation")?;
+
+        let started = Instant::now();
+        let receipt = loop {
+            let receipt: Option<UserOperationReceipt> = bundler
+                .request("eth_getUserOperationReceipt", [user_op_hash])
+                .await
+                .context("Failed to fetch the user operation receipt")?;
+            if let Some(receipt) = receipt {
+                break receipt;
+            }
+            if started.elapsed() > USER_OP_TIMEOUT {
+                anyhow::bail!(
+                    "User operation {:?} was not included within {} seconds",
+                    user_op_hash,
+                    USER_OP_TIMEOUT.as_secs()
+                );
+            }
+            tokio::time::sleep(USER_OP_POLL_INTERVAL).await;
+        };
+
+        Ok(TransactionOutcome {
+            tx_hash: receipt.receipt.transaction_hash,
+            success: receipt.success,
+            gas_used: receipt.actual_gas_used,
+            block_number: None,
+            effective_gas_price: None,
+            replacements: Vec::new(),
+            sponsorship: Some(Sponsorship {
+                kind: SponsorKind::Erc4337Paymaster,
+                sponsor: paymaster_address,
+                via: entry_point,
+                gas_cost: receipt.actual_gas_cost,
+                user_op_hash: Some(user_op_hash),
+            }),
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use ethers::types::H256;
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+pub const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)`
+pub const EIP1967_ADMIN_SLOT: &str =
+    "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+
+/// EIP-1967 `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`
+pub const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+/// EIP-1822 UUPS `keccak256("PROXIABLE")`
+pub const EIP1822_PROXIABLE_SLOT: &str =
+    "0xc5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7";
+
+/// Pre-EIP-1967 OpenZeppelin (zos) proxies: `keccak256("org.zeppelinos.proxy.implementation")`
+pub const ZEPPELINOS_IMPLEMENTATION_SLOT: &str =
+    "0x7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3";
+
+/// How a compiler lays out a mapping entry's slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingLayout {
+    /// `keccak256(key . slot)`
+    #[default]
+    Solidity,
+    /// `keccak256(slot . key)`
+    Vyper,
+}
+
+/// Slot of the entry for `key` in the mapping at `slot`. Value-type keys must be
+/// padded to 32 bytes; string and bytes keys are hashed unpadded, as Solidity does.
+pub fn mapping_slot(slot: H256, key: &[u8], layout: MappingLayout) -> H256 {
+    let packed = match layout {
+        MappingLayout::Solidity => [key, slot.as_bytes()].concat(),
+        MappingLayout::Vyper => [slot.as_bytes(), key].concat(),
+    };
+    H256::from(keccak256(packed))
+}
+
+/// First slot of the elements of the dynamic array whose length is at `slot`
+pub fn array_data_slot(slot: H256) -> H256 {
+    H256::from(keccak256(slot.as_bytes()))
+}
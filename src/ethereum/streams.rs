@@ -0,0 +1,236 @@
+use crate::ethereum::locks::{Release, SablierStreams, TokenLock};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::sync::Arc;
+
+// Superfluid's constant flow agreement on Ethereum mainnet
+const SUPERFLUID_CFA_V1: &str = "0x2844c1BBdA121E9E43105630b9C8310e5c72744b";
+
+/// Blocks of Superfluid flow updates searched by default, about a year
+pub const DEFAULT_FLOW_LOOKBACK_BLOCKS: u64 = 2_600_000;
+
+// Bounds the flows one query reads back from the agreement
+const MAX_FLOWS_PER_QUERY: usize = 100;
+
+abigen!(
+    ISuperfluidCfa,
+    r#"[
+        function getFlow(address token, address sender, address receiver) external view returns (uint256 timestamp, int96 flowRate, uint256 deposit, uint256 owedDeposit)
+    ]"#
+);
+
+/// How a payment stream pays its recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFlow {
+    /// A fixed `deposit` released linearly from `start` to `end`, none of it before `cliff`
+    Linear {
+        deposit: U256,
+        withdrawn: U256,
+        start: u64,
+        cliff: u64,
+        end: u64,
+        /// Whether the sender can still cancel and take back the unstreamed part
+        cancelable: bool,
+    },
+    /// An open-ended flow of `rate` base units per second, at that rate since `since`
+    Constant { rate: U256, since: u64 },
+}
+
+/// Tokens streaming from `sender` to `recipient`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentStream {
+    pub protocol: String,
+    /// Superfluid flows have no id; the token, sender and recipient identify them
+    pub stream_id: Option<String>,
+    pub token: Address,
+    pub sender: Address,
+    pub recipient: Address,
+    pub flow: StreamFlow,
+}
+
+impl PaymentStream {
+    /// Tokens streamed to the recipient by time `at`: withdrawn or not for linear
+    /// streams, since the flow last changed for constant ones
+    pub fn streamed_at(&self, at: u64) -> U256 {
+        match self.flow {
+            StreamFlow::Linear { .. } => self
+                .as_lock()
+                .map(|lock| lock.unlocked_at(at))
+                .unwrap_or_default(),
+            StreamFlow::Constant { rate, since } => rate * U256::from(at.saturating_sub(since)),
+        }
+    }
+
+    /// A linear stream seen as tokens vesting to its recipient
+    pub fn as_lock(&self) -> Option<TokenLock> {
+        let StreamFlow::Linear {
+            deposit,
+            withdrawn,
+            start,
+            cliff,
+            end,
+            ..
+        } = self.flow
+        else {
+            return None;
+        };
+        Some(TokenLock {
+            locker: self.protocol.clone(),
+            lock_id: self.stream_id.clone().unwrap_or_default(),
+            token: self.token,
+            owner: self.recipient,
+            deposited: deposit,
+            withdrawn,
+            release: Release::Linear { start, cliff, end },
+        })
+    }
+}
+
+/// A streaming payments protocol whose active streams can be listed by wallet
+#[async_trait]
+pub trait StreamSource: Send + Sync {
+    /// Short identifier such as `sablier` or `superfluid`
+    fn name(&self) -> &str;
+
+    /// Active streams `wallet` sends or receives
+    async fn streams_for(&self, wallet: Address) -> Result<Vec<PaymentStream>>;
+}
+
+#[async_trait]
+impl StreamSource for SablierStreams {
+    fn name(&self) -> &str {
+        "sablier"
+    }
+
+    async fn streams_for(&self, wallet: Address) -> Result<Vec<PaymentStream>> {
+        let mut streams = self
+            .linear_streams(Filter::new().topic1(H256::from(wallet)))
+            .await?;
+        for stream in self
+            .linear_streams(Filter::new().topic2(H256::from(wallet)))
+            .await?
+        {
+            // A stream to oneself matches both queries
+            if !streams.contains(&stream) {
+                streams.push(stream);
+            }
+        }
+        Ok(streams)
+    }
+}
+
+/// Superfluid constant flows, found through the agreement's update events and read
+/// back for their current rate
+pub struct SuperfluidFlows {
+    provider: Arc<Provider<Http>>,
+    contract: ISuperfluidCfa<Provider<Http>>,
+    lookback_blocks: u64,
+}
+
+impl SuperfluidFlows {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            contract: ISuperfluidCfa::new(
+                SUPERFLUID_CFA_V1
+                    .parse::<Address>()
+                    .context("Invalid Superfluid CFA address")?,
+                Arc::clone(&provider),
+            ),
+            provider,
+            lookback_blocks: DEFAULT_FLOW_LOOKBACK_BLOCKS,
+        })
+    }
+
+    /// Search flow updates over the last `blocks` blocks
+    pub fn with_lookback_blocks(mut self, blocks: u64) -> Self {
+        self.lookback_blocks = blocks;
+        self
+    }
+
+    /// (token, sender, receiver) of flows updated with `filter` applied to their
+    /// indexed sender or receiver, latest first
+    async fn flow_keys(&self, filter: Filter) -> Result<Vec<(Address, Address, Address)>> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?
+            .as_u64();
+        let filter = filter
+            .address(self.contract.address())
+            .topic0(H256::from(keccak256(
+                "FlowUpdated(address,address,address,int96,int256,int256,bytes)",
+            )))
+            .from_block(head.saturating_sub(self.lookback_blocks))
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch Superfluid flow updates")?;
+        Ok(logs
+            .iter()
+            .rev()
+            .filter_map(|log| match log.topics[..] {
+                [_, token, sender, receiver] => Some((
+                    Address::from(token),
+                    Address::from(sender),
+                    Address::from(receiver),
+                )),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl StreamSource for SuperfluidFlows {
+    fn name(&self) -> &str {
+        "superfluid"
+    }
+
+    async fn streams_for(&self, wallet: Address) -> Result<Vec<PaymentStream>> {
+        let mut keys = self
+            .flow_keys(Filter::new().topic2(H256::from(wallet)))
+            .await?;
+        keys.extend(
+            self.flow_keys(Filter::new().topic3(H256::from(wallet)))
+                .await?,
+        );
+        let mut seen = Vec::new();
+        let mut streams = Vec::new();
+        for (token, sender, receiver) in keys {
+            if seen.contains(&(token, sender, receiver)) {
+                continue;
+            }
+            if seen.len() == MAX_FLOWS_PER_QUERY {
+                break;
+            }
+            seen.push((token, sender, receiver));
+            // Only the current rate counts; earlier updates may have changed or closed it
+            let (since, rate, _, _) = self
+                .contract
+                .get_flow(token, sender, receiver)
+                .call()
+                .await
+                .context("Failed to read Superfluid flow")?;
+            if rate <= 0 {
+                continue;
+            }
+            streams.push(PaymentStream {
+                protocol: self.name().to_string(),
+                stream_id: None,
+                token,
+                sender,
+                recipient: receiver,
+                flow: StreamFlow::Constant {
+                    rate: U256::from(rate as u128),
+                    since: since.low_u64(),
+                },
+            });
+        }
+        Ok(streams)
+    }
+}
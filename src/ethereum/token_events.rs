@@ -0,0 +1,412 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::sync::Arc;
+
+// Blacklist events emitted by common stablecoins and tax tokens, and whether each adds
+const BLACKLIST_EVENTS: [(&str, Option<bool>); 5] = [
+    ("Blacklisted(address)", Some(true)),
+    ("UnBlacklisted(address)", Some(false)),
+    ("AddedBlackList(address)", Some(true)),
+    ("RemovedBlackList(address)", Some(false)),
+    // The flag is the event's second argument
+    ("BlacklistUpdated(address,bool)", None),
+];
+
+abigen!(
+    IERC20Supply,
+    r#"[
+        function totalSupply() external view returns (uint256)
+    ]"#
+);
+
+fn topic(signature: &str) -> H256 {
+    H256::from(keccak256(signature.as_bytes()))
+}
+
+/// Liquidity withdrawn from a V2 pair by a `Burn`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidityRemoval {
+    pub pair: Address,
+    pub block_number: u64,
+    pub tx_hash: H256,
+    pub amount0: U256,
+    pub amount1: U256,
+}
+
+/// An ERC20 `Transfer` into or out of a watched address
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTransfer {
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub amount: U256,
+    pub block_number: u64,
+    pub tx_hash: H256,
+}
+
+/// A privileged action on a token contract
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminAction {
+    /// New supply minted to `to`
+    Mint {
+        to: Address,
+        amount: U256,
+    },
+    OwnershipTransferred {
+        from: Address,
+        to: Address,
+    },
+    /// Ownership transferred to the zero address
+    OwnershipRenounced {
+        previous_owner: Address,
+    },
+    BlacklistUpdated {
+        account: Address,
+        blacklisted: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminEvent {
+    pub token: Address,
+    pub block_number: u64,
+    pub tx_hash: H256,
+    pub action: AdminAction,
+}
+
+/// Log queries behind rug-pull heuristics
+#[async_trait]
+pub trait TokenEventSource: Send + Sync {
+    /// `Burn` events of a V2 pair in `[from_block, to_block]`
+    async fn liquidity_removals(
+        &self,
+        pair: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<LiquidityRemoval>>;
+
+    /// Mints, ownership changes and blacklist updates of `token` in `[from_block, to_block]`
+    async fn admin_events(
+        &self,
+        token: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<AdminEvent>>;
+
+    async fn total_supply(&self, token: Address) -> Result<U256>;
+
+    /// Whether any ERC20 `Transfer` from `from` to `to` was logged in `[from_block, to_block]`
+    async fn has_token_transfers(
+        &self,
+        from: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<bool>;
+
+    /// `Transfer` events of `token` paying `to` in `[from_block, to_block]`, oldest first
+    async fn incoming_transfers(
+        &self,
+        token: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>>;
+
+    /// `Transfer` events of any token paying or paid by `account` in
+    /// `[from_block, to_block]`, oldest first
+    async fn address_transfers(
+        &self,
+        account: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>>;
+}
+
+pub struct TokenEvents {
+    provider: Arc<Provider<Http>>,
+}
+
+impl TokenEvents {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self { provider }
+    }
+
+    /// Indexed arguments followed by 32-byte data words
+    fn words(log: &Log) -> Vec<H256> {
+        log.topics
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(
+                log.data
+                    .chunks(32)
+                    .filter(|w| w.len() == 32)
+                    .map(H256::from_slice),
+            )
+            .collect()
+    }
+
+    fn address(word: &H256) -> Address {
+        Address::from(*word)
+    }
+
+    fn meta(log: &Log) -> (u64, H256) {
+        (
+            log.block_number.map_or(0, |number| number.as_u64()),
+            log.transaction_hash.unwrap_or_default(),
+        )
+    }
+}
+
+#[async_trait]
+impl TokenEventSource for TokenEvents {
+    async fn liquidity_removals(
+        &self,
+        pair: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<LiquidityRemoval>> {
+        let filter = Filter::new()
+            .address(pair)
+            .topic0(topic("Burn(address,uint256,uint256,address)"))
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch pair Burn events")?;
+
+        Ok(logs
+            .iter()
+            .filter_map(|log| {
+                let words = Self::words(log);
+                // sender and to are indexed; amount0 and amount1 are data
+                let [_, _, amount0, amount1] = words[..] else {
+                    return None;
+                };
+                let (block_number, tx_hash) = Self::meta(log);
+                Some(LiquidityRemoval {
+                    pair,
+                    block_number,
+                    tx_hash,
+                    amount0: U256::from_big_endian(amount0.as_bytes()),
+                    amount1: U256::from_big_endian(amount1.as_bytes()),
+                })
+            })
+            .collect())
+    }
+
+    async fn admin_events(
+        &self,
+        token: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<AdminEvent>> {
+        let mints = Filter::new()
+            .address(token)
+            .topic0(topic("Transfer(address,address,uint256)"))
+            .topic1(H256::zero())
+            .from_block(from_block)
+            .to_block(to_block);
+        let ownership = topic("OwnershipTransferred(address,address)");
+        let mut admin_topics = vec![ownership];
+        admin_topics.extend(BLACKLIST_EVENTS.iter().map(|(sig, _)| topic(sig)));
+        let admin = Filter::new()
+            .address(token)
+            .topic0(admin_topics)
+            .from_block(from_block)
+            .to_block(to_block);
+
+        let mut logs = self
+            .provider
+            .get_logs(&mints)
+            .await
+            .context("Failed to fetch mint events")?;
+        logs.extend(
+            self.provider
+                .get_logs(&admin)
+                .await
+                .context("Failed to fetch admin events")?,
+        );
+
+        let mut events = Vec::new();
+        for log in &logs {
+            let Some(topic0) = log.topics.first() else {
+                continue;
+            };
+            let words = Self::words(log);
+            let action = if log.topics.get(1) == Some(&H256::zero()) && words.len() == 3 {
+                AdminAction::Mint {
+                    to: Self::address(&words[1]),
+                    amount: U256::from_big_endian(words[2].as_bytes()),
+                }
+            } else if *topic0 == ownership && words.len() >= 2 {
+                let (from, to) = (Self::address(&words[0]), Self::address(&words[1]));
+                if to.is_zero() {
+                    AdminAction::OwnershipRenounced {
+                        previous_owner: from,
+                    }
+                } else {
+                    AdminAction::OwnershipTransferred { from, to }
+                }
+            } else if let Some((_, adds)) = BLACKLIST_EVENTS
+                .iter()
+                .find(|(sig, _)| topic(sig) == *topic0)
+            {
+                let Some(account) = words.first() else {
+                    continue;
+                };
+                let blacklisted = match adds {
+                    Some(adds) => *adds,
+                    None => words.get(1).is_some_and(|flag| !flag.is_zero()),
+                };
+                AdminAction::BlacklistUpdated {
+                    account: Self::address(account),
+                    blacklisted,
+                }
+            } else {
+                continue;
+            };
+            let (block_number, tx_hash) = Self::meta(log);
+            events.push(AdminEvent {
+                token,
+                block_number,
+                tx_hash,
+                action,
+            });
+        }
+        events.sort_by_key(|event| event.block_number);
+        Ok(events)
+    }
+
+    async fn total_supply(&self, token: Address) -> Result<U256> {
+        IERC20Supply::new(token, Arc::clone(&self.provider))
+            .total_supply()
+            .call()
+            .await
+            .context("Failed to get total supply")
+    }
+
+    async fn has_token_transfers(
+        &self,
+        from: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<bool> {
+        // Any token contract: both parties are indexed topics
+        let filter = Filter::new()
+            .topic0(topic("Transfer(address,address,uint256)"))
+            .topic1(H256::from(from))
+            .topic2(H256::from(to))
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch transfer history")?;
+        Ok(!logs.is_empty())
+    }
+
+    async fn incoming_transfers(
+        &self,
+        token: Address,
+        to: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>> {
+        let filter = Filter::new()
+            .address(token)
+            .topic0(topic("Transfer(address,address,uint256)"))
+            .topic2(H256::from(to))
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch incoming transfers")?;
+
+        let mut transfers: Vec<TokenTransfer> = logs
+            .iter()
+            .filter_map(|log| {
+                let words = Self::words(log);
+                // from and to are indexed; the amount is data
+                let [from, _, amount] = words[..] else {
+                    return None;
+                };
+                let (block_number, tx_hash) = Self::meta(log);
+                Some(TokenTransfer {
+                    token,
+                    from: Self::address(&from),
+                    to,
+                    amount: U256::from_big_endian(amount.as_bytes()),
+                    block_number,
+                    tx_hash,
+                })
+            })
+            .collect();
+        transfers.sort_by_key(|transfer| transfer.block_number);
+        Ok(transfers)
+    }
+
+    async fn address_transfers(
+        &self,
+        account: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TokenTransfer>> {
+        let transfers = Filter::new()
+            .topic0(topic("Transfer(address,address,uint256)"))
+            .from_block(from_block)
+            .to_block(to_block);
+        let mut logs = self
+            .provider
+            .get_logs(&transfers.clone().topic1(H256::from(account)))
+            .await
+            .context("Failed to fetch outgoing transfers")?;
+        logs.extend(
+            self.provider
+                .get_logs(&transfers.topic2(H256::from(account)))
+                .await
+                .context("Failed to fetch incoming transfers")?
+                .into_iter()
+                // Transfers to itself were found by the first query
+                .filter(|log| log.topics.get(1) != Some(&H256::from(account))),
+        );
+
+        let mut found: Vec<(u64, TokenTransfer)> = logs
+            .iter()
+            .filter_map(|log| {
+                let words = Self::words(log);
+                // ERC721 transfers index the token id, leaving no data
+                let [from, to, amount] = words[..] else {
+                    return None;
+                };
+                if log.data.len() != 32 {
+                    return None;
+                }
+                let (block_number, tx_hash) = Self::meta(log);
+                let index = log.log_index.map_or(0, |index| index.as_u64());
+                Some((
+                    index,
+                    TokenTransfer {
+                        token: log.address,
+                        from: Self::address(&from),
+                        to: Self::address(&to),
+                        amount: U256::from_big_endian(amount.as_bytes()),
+                        block_number,
+                        tx_hash,
+                    },
+                ))
+            })
+            .collect();
+        found.sort_by_key(|(index, transfer)| (transfer.block_number, *index));
+        Ok(found.into_iter().map(|(_, transfer)| transfer).collect())
+    }
+}
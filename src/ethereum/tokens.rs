@@ -0,0 +1,280 @@
+use crate::ethereum::chains::native_symbol;
+use ethers::prelude::*;
+use serde::Serialize;
+
+/// One deployment of a logical asset on one chain: the issuer's own contract, the
+/// wrapped native token, or a bridged copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenVariant {
+    pub chain_id: u64,
+    pub address: &'static str,
+    pub symbol: &'static str,
+    /// Logical asset every variant stands for, e.g. `USDC`
+    pub asset: &'static str,
+    /// How a non-canonical variant reached the chain; `None` for the canonical one
+    pub bridge: Option<&'static str>,
+}
+
+const fn canonical(
+    chain_id: u64,
+    address: &'static str,
+    symbol: &'static str,
+    asset: &'static str,
+) -> TokenVariant {
+    TokenVariant {
+        chain_id,
+        address,
+        symbol,
+        asset,
+        bridge: None,
+    }
+}
+
+const fn bridged(
+    chain_id: u64,
+    address: &'static str,
+    symbol: &'static str,
+    asset: &'static str,
+    bridge: &'static str,
+) -> TokenVariant {
+    TokenVariant {
+        chain_id,
+        address,
+        symbol,
+        asset,
+        bridge: Some(bridge),
+    }
+}
+
+// Wrapped natives count as their native asset, so WETH and ETH aggregate together
+const TOKEN_VARIANTS: &[TokenVariant] = &[
+    canonical(
+        1,
+        "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "USDC",
+        "USDC",
+    ),
+    canonical(
+        1,
+        "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+        "USDT",
+        "USDT",
+    ),
+    canonical(
+        1,
+        "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "WETH",
+        "ETH",
+    ),
+    canonical(
+        10,
+        "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85",
+        "USDC",
+        "USDC",
+    ),
+    bridged(
+        10,
+        "0x7F5c764cBc14f9669B88837ca1490cCa17c31607",
+        "USDC.e",
+        "USDC",
+        "Optimism standard bridge",
+    ),
+    canonical(
+        10,
+        "0x4200000000000000000000000000000000000006",
+        "WETH",
+        "ETH",
+    ),
+    bridged(
+        56,
+        "0x8AC76a51cc950d9822D68b83fE1Ad97B32Cd580d",
+        "USDC",
+        "USDC",
+        "Binance-Peg",
+    ),
+    bridged(
+        56,
+        "0x55d398326f99059fF775485246999027B3197955",
+        "USDT",
+        "USDT",
+        "Binance-Peg",
+    ),
+    bridged(
+        56,
+        "0x2170Ed0880ac9A755fd29B2688956BD959F933F8",
+        "ETH",
+        "ETH",
+        "Binance-Peg",
+    ),
+    canonical(
+        56,
+        "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c",
+        "WBNB",
+        "BNB",
+    ),
+    canonical(
+        137,
+        "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359",
+        "USDC",
+        "USDC",
+    ),
+    bridged(
+        137,
+        "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174",
+        "USDC.e",
+        "USDC",
+        "Polygon PoS bridge",
+    ),
+    bridged(
+        137,
+        "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619",
+        "WETH",
+        "ETH",
+        "Polygon PoS bridge",
+    ),
+    canonical(
+        137,
+        "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270",
+        "WPOL",
+        "POL",
+    ),
+    canonical(
+        8453,
+        "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+        "USDC",
+        "USDC",
+    ),
+    bridged(
+        8453,
+        "0xd9aAEc86B65D86f6A7B5B1b0c42FFA531710b6CA",
+        "USDbC",
+        "USDC",
+        "Base standard bridge",
+    ),
+    canonical(
+        8453,
+        "0x4200000000000000000000000000000000000006",
+        "WETH",
+        "ETH",
+    ),
+    canonical(
+        42161,
+        "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+        "USDC",
+        "USDC",
+    ),
+    bridged(
+        42161,
+        "0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8",
+        "USDC.e",
+        "USDC",
+        "Arbitrum bridge",
+    ),
+    canonical(
+        42161,
+        "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        "WETH",
+        "ETH",
+    ),
+];
+
+impl TokenVariant {
+    /// The token's address; `None` only if the table entry is malformed
+    pub fn address(&self) -> Option<Address> {
+        self.address.parse().ok()
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.bridge.is_none()
+    }
+
+    /// What callers are told about this token
+    pub fn identity(&self) -> AssetIdentity {
+        AssetIdentity {
+            asset: self.asset.to_string(),
+            canonical: self.is_canonical(),
+            bridge: self.bridge.map(str::to_string),
+            canonical_address: if self.is_canonical() {
+                None
+            } else {
+                canonical_variant(self.chain_id, self.asset).and_then(TokenVariant::address)
+            },
+        }
+    }
+}
+
+/// The logical asset a token stands for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetIdentity {
+    pub asset: String,
+    pub canonical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bridge: Option<String>,
+    /// The canonical token for the same asset on this chain, when a bridged one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_address: Option<Address>,
+}
+
+/// The known variant `token` is on `chain_id`, if any
+pub fn token_variant(chain_id: u64, token: Address) -> Option<&'static TokenVariant> {
+    TOKEN_VARIANTS
+        .iter()
+        .find(|variant| variant.chain_id == chain_id && variant.address() == Some(token))
+}
+
+/// The canonical deployment of `asset` on `chain_id`, if it has one
+pub fn canonical_variant(chain_id: u64, asset: &str) -> Option<&'static TokenVariant> {
+    TOKEN_VARIANTS.iter().find(|variant| {
+        variant.chain_id == chain_id && variant.asset == asset && variant.is_canonical()
+    })
+}
+
+/// Asset of `token` on `chain_id`, or of the native token when `token` is `None`.
+/// Unknown tokens have no asset: matching on symbol alone would merge lookalikes.
+pub fn asset_of(chain_id: u64, token: Option<Address>) -> Option<AssetIdentity> {
+    match token {
+        None => Some(AssetIdentity {
+            asset: native_symbol(chain_id).to_string(),
+            canonical: true,
+            bridge: None,
+            canonical_address: None,
+        }),
+        Some(token) => token_variant(chain_id, token).map(TokenVariant::identity),
+    }
+}
+
+/// Warning for swapping through `token` when it is a bridged copy of an asset
+pub fn bridged_token_warning(chain_id: u64, token: Address) -> Option<String> {
+    let variant = token_variant(chain_id, token)?;
+    let bridge = variant.bridge?;
+    let mut warning = format!(
+        "{:?} is {}, a bridged {} ({}), not the canonical token",
+        token, variant.symbol, variant.asset, bridge
+    );
+    if let Some(canonical) =
+        canonical_variant(chain_id, variant.asset).and_then(TokenVariant::address)
+    {
+        warning.push_str(&format!(
+            "; canonical {} on this chain is {:?}",
+            variant.asset, canonical
+        ));
+    }
+    Some(warning)
+}
+
+// Tokens whose approve reverts when changing one non-zero allowance to another
+const ALLOWANCE_RESET_TOKENS: &[(u64, &str)] = &[
+    // USDT
+    (1, "0xdAC17F958D2ee523a2206206994597C13D831ec7"),
+    // Kyber Network Crystal (legacy)
+    (1, "0xdd974D5C2e2928deA5F71b9825b8b646686BD200"),
+    // BNT
+    (1, "0x1F573D6Fb3F13d689FF844B4cE37794d79a7FF1C"),
+];
+
+/// Whether `token` must have its allowance zeroed before it can be changed
+pub fn requires_allowance_reset(chain_id: u64, token: Address) -> bool {
+    ALLOWANCE_RESET_TOKENS.iter().any(|(chain, address)| {
+        *chain == chain_id && address.parse::<Address>().ok() == Some(token)
+    })
+}
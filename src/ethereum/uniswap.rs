@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -8,18 +9,132 @@ use std::sync::Arc;
 // Uniswap V2 Router address on Ethereum mainnet
 const UNISWAP_V2_ROUTER: &str = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
 
-#[derive(Debug, Clone)]
+// Uniswap V2 Factory address on Ethereum mainnet
+const UNISWAP_V2_FACTORY: &str = "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f";
+
+// SushiSwap (V2 fork) Router and Factory addresses on Ethereum mainnet
+const SUSHISWAP_ROUTER: &str = "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F";
+const SUSHISWAP_FACTORY: &str = "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac";
+
+// Number of eth_call probes used to bracket the realized output of a fee-on-transfer swap
+const FEE_PROBE_ITERATIONS: usize = 24;
+
+abigen!(
+    IUniswapV2Router02,
+    r#"[
+        function getAmountsOut(uint amountIn, address[] memory path) external view returns (uint[] memory amounts)
+        function swapExactTokensForTokens(uint amountIn, uint amountOutMin, address[] calldata path, address to, uint deadline) external returns (uint[] memory amounts)
+        function swapExactTokensForTokensSupportingFeeOnTransferTokens(uint amountIn, uint amountOutMin, address[] calldata path, address to, uint deadline) external
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address pair)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Pair,
+    r#"[
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+        function token0() external view returns (address)
+        event Sync(uint112 reserve0, uint112 reserve1)
+        event Swap(address indexed sender, uint amount0In, uint amount1In, uint amount0Out, uint amount1Out, address indexed to)
+    ]"#
+);
+
+/// Reserves of a V2 pair, oriented to a swap direction
+#[derive(Debug, Clone, Default)]
+pub struct PoolReserves {
+    pub pair_address: Address,
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+}
+
+/// Pool spot price (raw `token_out` units per raw `token_in` unit) after a reserve update
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricePoint {
+    pub block_number: u64,
+    pub price: Decimal,
+}
+
+/// One V2 swap, oriented to a token pair
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolSwap {
+    pub block_number: u64,
+    /// Raw `token_in` amount traded, in either direction
+    pub base_amount: U256,
+    /// Raw `token_out` amount traded, in either direction
+    pub quote_amount: U256,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct SwapSimulation {
     pub amount_in: U256,
     pub amount_out: U256,
     pub gas_estimate: U256,
     pub gas_price: U256,
     pub gas_cost: U256,
+    /// Set when the simulation detected a transfer tax on the path
+    pub fee_on_transfer: bool,
+    /// Detected transfer fee in basis points, relative to the router quote
+    pub transfer_fee_bps: Option<u32>,
+    /// Set when the swap reverted even without a minimum output, so a transfer tax
+    /// could not be looked for
+    pub fee_detection_warning: Option<String>,
+    /// Set when gas estimation reverted, with the decoded reason; `gas_estimate` is
+    /// then a default rather than a measurement
+    pub gas_estimate_error: Option<String>,
+}
+
+impl SwapSimulation {
+    /// Router method that should be used to execute this swap
+    pub fn router_method(&self) -> &'static str {
+        if self.fee_on_transfer {
+            "swapExactTokensForTokensSupportingFeeOnTransferTokens"
+        } else {
+            "swapExactTokensForTokens"
+        }
+    }
+}
+
+// Gas assumed for swaps whose estimate fails
+const FALLBACK_SWAP_GAS: u64 = 200_000;
+const FALLBACK_FEE_ON_TRANSFER_SWAP_GAS: u64 = 250_000;
+
+/// Why a simulated call failed: its `Error(string)` reason, the raw data of a custom
+/// error, or the node's message when it did not revert
+pub fn revert_reason<M: Middleware>(error: &ContractError<M>) -> String {
+    if let Some(reason) = error.decode_revert::<String>() {
+        return reason;
+    }
+    match error.as_revert() {
+        Some(data) if !data.is_empty() => format!("custom error {}", data),
+        Some(_) => "reverted without a reason".to_string(),
+        None => error.to_string(),
+    }
+}
+
+/// Fee in basis points between the router quote and the amount actually received
+pub fn transfer_fee_bps(quoted_out: U256, realized_out: U256) -> u32 {
+    if quoted_out.is_zero() || realized_out >= quoted_out {
+        return 0;
+    }
+    let fee = (quoted_out - realized_out) * U256::from(10_000u32) / quoted_out;
+    fee.as_u32()
 }
 
 /// Trait for Uniswap router operations
 #[async_trait]
 pub trait UniswapRouterTrait: Send + Sync {
+    /// Router contract that swaps are sent to, and that token approvals must name
+    fn router_address(&self) -> Address;
+
+    /// Short identifier of the exchange, such as `uniswap_v2` or `sushiswap`
+    fn venue_name(&self) -> &str;
+
     /// Simulate a token swap and return expected output amount
     async fn simulate_swap(
         &self,
@@ -36,22 +151,94 @@ pub trait UniswapRouterTrait: Send + Sync {
         to_token: Address,
         amount_in: U256,
     ) -> Result<Decimal>;
+
+    /// Get the current reserves of the direct pool between two tokens
+    async fn get_pool_reserves(
+        &self,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<PoolReserves>;
+
+    /// Get spot prices recorded by the pool's Sync events over the last `lookback_blocks`,
+    /// oldest first
+    async fn get_recent_prices(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PricePoint>>;
+
+    /// Get the pool's Swap events over the last `lookback_blocks`, oldest first
+    async fn get_recent_swaps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PoolSwap>>;
 }
 
 pub struct UniswapV2Router {
     provider: Arc<Provider<Http>>,
     router_address: Address,
+    factory_address: Address,
+    venue_name: &'static str,
 }
 
 impl UniswapV2Router {
-    pub fn new(provider: Arc<Provider<Http>>) -> Self {
-        let router_address = UNISWAP_V2_ROUTER.parse().unwrap();
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        let router_address = UNISWAP_V2_ROUTER
+            .parse()
+            .context("Invalid Uniswap V2 router address")?;
+        let factory_address = UNISWAP_V2_FACTORY
+            .parse()
+            .context("Invalid Uniswap V2 factory address")?;
+        Ok(Self::with_addresses(
+            provider,
+            "uniswap_v2",
+            router_address,
+            factory_address,
+        ))
+    }
+
+    /// SushiSwap, a Uniswap V2 fork with its own router and factory
+    pub fn sushiswap(provider: Arc<Provider<Http>>) -> Result<Self> {
+        let router_address = SUSHISWAP_ROUTER
+            .parse()
+            .context("Invalid SushiSwap router address")?;
+        let factory_address = SUSHISWAP_FACTORY
+            .parse()
+            .context("Invalid SushiSwap factory address")?;
+        Ok(Self::with_addresses(
+            provider,
+            "sushiswap",
+            router_address,
+            factory_address,
+        ))
+    }
+
+    /// Router for a V2 fork deployed at different addresses
+    pub fn with_addresses(
+        provider: Arc<Provider<Http>>,
+        venue_name: &'static str,
+        router_address: Address,
+        factory_address: Address,
+    ) -> Self {
         Self {
             provider,
             router_address,
+            factory_address,
+            venue_name,
         }
     }
 
+    pub fn venue_name(&self) -> &'static str {
+        self.venue_name
+    }
+
+    pub fn router_address(&self) -> Address {
+        self.router_address
+    }
+
     /// Simulate a token swap and return expected output amount
     pub async fn simulate_swap(
         &self,
@@ -60,14 +247,6 @@ impl UniswapV2Router {
         amount_in: U256,
         wallet_address: Address,
     ) -> Result<SwapSimulation> {
-        abigen!(
-            IUniswapV2Router02,
-            r#"[
-                function getAmountsOut(uint amountIn, address[] memory path) external view returns (uint[] memory amounts)
-                function swapExactTokensForTokens(uint amountIn, uint amountOutMin, address[] calldata path, address to, uint deadline) external returns (uint[] memory amounts)
-            ]"#
-        );
-
         let router = IUniswapV2Router02::new(self.router_address, Arc::clone(&self.provider));
 
         // Get amounts out for the swap path
@@ -88,13 +267,63 @@ impl UniswapV2Router {
         let swap_call = router.swap_exact_tokens_for_tokens(
             amount_in,
             amount_out_min,
-            path,
+            path.clone(),
             wallet_address,
             deadline,
         );
 
-        // Estimate gas
-        let gas_estimate = swap_call.estimate_gas().await.unwrap_or(U256::from(200000)); // Default gas estimate
+        // Detect transfer taxes: a fee-on-transfer token makes the standard method revert
+        // when the quoted output is enforced, while the supporting method still succeeds
+        let strict_call = router
+            .swap_exact_tokens_for_tokens(
+                amount_in,
+                amount_out,
+                path.clone(),
+                wallet_address,
+                deadline,
+            )
+            .from(wallet_address);
+        let mut realized_out = None;
+        let mut fee_detection_warning = None;
+        if !amount_out.is_zero() && strict_call.call().await.is_err() {
+            realized_out = self
+                .probe_fee_on_transfer_output(&router, amount_in, amount_out, &path, wallet_address)
+                .await;
+            if realized_out.is_none() {
+                fee_detection_warning = Some(format!(
+                    "Transfer-tax detection on {} was skipped because the swap reverts without a minimum output, usually for lack of balance or allowance; a fee-on-transfer token would deliver less than estimated",
+                    self.venue_name
+                ));
+            }
+        }
+
+        // Estimate gas. A failed estimate usually means the swap would revert, so the
+        // reason is kept next to the default the estimate falls back to.
+        let (estimate, fallback_gas) = if realized_out.is_some() {
+            (
+                router
+                    .swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
+                        amount_in,
+                        amount_out_min,
+                        path,
+                        wallet_address,
+                        deadline,
+                    )
+                    .from(wallet_address)
+                    .estimate_gas()
+                    .await,
+                FALLBACK_FEE_ON_TRANSFER_SWAP_GAS,
+            )
+        } else {
+            (
+                swap_call.from(wallet_address).estimate_gas().await,
+                FALLBACK_SWAP_GAS,
+            )
+        };
+        let (gas_estimate, gas_estimate_error) = match estimate {
+            Ok(gas) => (gas, None),
+            Err(e) => (U256::from(fallback_gas), Some(revert_reason(&e))),
+        };
 
         // Get current gas price
         let gas_price = self
@@ -107,13 +336,65 @@ impl UniswapV2Router {
 
         Ok(SwapSimulation {
             amount_in,
-            amount_out,
+            amount_out: realized_out.unwrap_or(amount_out),
             gas_estimate,
             gas_price,
             gas_cost,
+            fee_on_transfer: realized_out.is_some(),
+            transfer_fee_bps: realized_out.map(|out| transfer_fee_bps(amount_out, out)),
+            fee_detection_warning,
+            gas_estimate_error,
         })
     }
 
+    /// Find the output actually received through the fee-on-transfer router method.
+    ///
+    /// The supporting method enforces `amountOutMin` against the recipient's balance delta,
+    /// so a binary search over `amountOutMin` with eth_call brackets the realized output.
+    /// Returns `None` when even an unconstrained call reverts (e.g. missing balance or
+    /// allowance), in which case the token cannot be classified.
+    async fn probe_fee_on_transfer_output(
+        &self,
+        router: &IUniswapV2Router02<Provider<Http>>,
+        amount_in: U256,
+        quoted_out: U256,
+        path: &[Address],
+        wallet_address: Address,
+    ) -> Option<U256> {
+        let deadline = U256::from(u64::MAX);
+        let succeeds = |min_out: U256| {
+            let call = router
+                .swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
+                    amount_in,
+                    min_out,
+                    path.to_vec(),
+                    wallet_address,
+                    deadline,
+                )
+                .from(wallet_address);
+            async move { call.call().await.is_ok() }
+        };
+
+        if !succeeds(U256::zero()).await {
+            return None;
+        }
+
+        let (mut low, mut high) = (U256::zero(), quoted_out);
+        for _ in 0..FEE_PROBE_ITERATIONS {
+            if high - low <= U256::one() {
+                break;
+            }
+            let mid = low + (high - low) / 2;
+            if succeeds(mid).await {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Some(low)
+    }
+
     /// Get the best price for a token pair
     pub async fn get_price(
         &self,
@@ -121,13 +402,6 @@ impl UniswapV2Router {
         to_token: Address,
         amount_in: U256,
     ) -> Result<Decimal> {
-        abigen!(
-            IUniswapV2Router02,
-            r#"[
-                function getAmountsOut(uint amountIn, address[] memory path) external view returns (uint[] memory amounts)
-            ]"#
-        );
-
         let router = IUniswapV2Router02::new(self.router_address, Arc::clone(&self.provider));
 
         let path = vec![from_token, to_token];
@@ -151,10 +425,189 @@ impl UniswapV2Router {
 
         Ok(price)
     }
+
+    /// Raw output of `getAmountsOut` along a direct path
+    pub async fn get_amount_out(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<U256> {
+        let router = IUniswapV2Router02::new(self.router_address, Arc::clone(&self.provider));
+        let amounts = router
+            .get_amounts_out(amount_in, vec![token_in, token_out])
+            .call()
+            .await
+            .with_context(|| format!("Failed to get amounts out from {}", self.venue_name))?;
+        Ok(amounts.get(1).copied().unwrap_or(U256::zero()))
+    }
+
+    async fn get_pair(&self, token_a: Address, token_b: Address) -> Result<Address> {
+        let factory = IUniswapV2Factory::new(self.factory_address, Arc::clone(&self.provider));
+        let pair = factory
+            .get_pair(token_a, token_b)
+            .call()
+            .await
+            .context("Failed to query Uniswap V2 factory")?;
+
+        if pair.is_zero() {
+            anyhow::bail!("No Uniswap V2 pool exists for {:?}/{:?}", token_a, token_b);
+        }
+        Ok(pair)
+    }
+
+    /// Get the current reserves of the direct pool between two tokens
+    pub async fn get_pool_reserves(
+        &self,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<PoolReserves> {
+        let pair_address = self.get_pair(token_in, token_out).await?;
+        let pair = IUniswapV2Pair::new(pair_address, Arc::clone(&self.provider));
+
+        let (reserve0, reserve1, _) = pair
+            .get_reserves()
+            .call()
+            .await
+            .context("Failed to get pool reserves")?;
+        let token0 = pair
+            .token_0()
+            .call()
+            .await
+            .context("Failed to get token0")?;
+
+        let (reserve_in, reserve_out) = if token0 == token_in {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+
+        Ok(PoolReserves {
+            pair_address,
+            reserve_in: U256::from(reserve_in),
+            reserve_out: U256::from(reserve_out),
+        })
+    }
+
+    /// Resolve the pair for a token pair along with the log range ending at the latest block
+    async fn pair_log_range(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<(IUniswapV2Pair<Provider<Http>>, bool, u64, u64)> {
+        let pair_address = self.get_pair(token_in, token_out).await?;
+        let pair = IUniswapV2Pair::new(pair_address, Arc::clone(&self.provider));
+        let token0 = pair
+            .token_0()
+            .call()
+            .await
+            .context("Failed to get token0")?;
+
+        let latest = self
+            .provider
+            .get_block_number()
+            .await
+            .context("Failed to get block number")?
+            .as_u64();
+
+        Ok((
+            pair,
+            token0 == token_in,
+            latest.saturating_sub(lookback_blocks),
+            latest,
+        ))
+    }
+
+    /// Get spot prices recorded by the pool's Sync events, oldest first
+    pub async fn get_recent_prices(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PricePoint>> {
+        let (pair, in_is_token0, from_block, to_block) = self
+            .pair_log_range(token_in, token_out, lookback_blocks)
+            .await?;
+
+        let syncs = pair
+            .sync_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query_with_meta()
+            .await
+            .context("Failed to fetch Sync events")?;
+
+        let prices = syncs
+            .into_iter()
+            .filter_map(|(sync, meta)| {
+                let (reserve_in, reserve_out) = if in_is_token0 {
+                    (sync.reserve_0, sync.reserve_1)
+                } else {
+                    (sync.reserve_1, sync.reserve_0)
+                };
+                if reserve_in == 0 {
+                    return None;
+                }
+                Some(PricePoint {
+                    block_number: meta.block_number.as_u64(),
+                    price: Decimal::from_u128(reserve_out)? / Decimal::from_u128(reserve_in)?,
+                })
+            })
+            .collect();
+
+        Ok(prices)
+    }
+
+    /// Get the pool's Swap events, oldest first
+    pub async fn get_recent_swaps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PoolSwap>> {
+        let (pair, in_is_token0, from_block, to_block) = self
+            .pair_log_range(token_in, token_out, lookback_blocks)
+            .await?;
+
+        let swaps = pair
+            .swap_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query_with_meta()
+            .await
+            .context("Failed to fetch Swap events")?;
+
+        Ok(swaps
+            .into_iter()
+            .map(|(swap, meta)| {
+                let amount0 = swap.amount_0_in + swap.amount_0_out;
+                let amount1 = swap.amount_1_in + swap.amount_1_out;
+                let (base_amount, quote_amount) = if in_is_token0 {
+                    (amount0, amount1)
+                } else {
+                    (amount1, amount0)
+                };
+                PoolSwap {
+                    block_number: meta.block_number.as_u64(),
+                    base_amount,
+                    quote_amount,
+                }
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
 impl UniswapRouterTrait for UniswapV2Router {
+    fn router_address(&self) -> Address {
+        self.router_address
+    }
+
+    fn venue_name(&self) -> &str {
+        self.venue_name
+    }
+
     async fn simulate_swap(
         &self,
         from_token: Address,
@@ -174,4 +627,32 @@ impl UniswapRouterTrait for UniswapV2Router {
     ) -> Result<Decimal> {
         self.get_price(from_token, to_token, amount_in).await
     }
+
+    async fn get_pool_reserves(
+        &self,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<PoolReserves> {
+        self.get_pool_reserves(token_in, token_out).await
+    }
+
+    async fn get_recent_prices(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PricePoint>> {
+        self.get_recent_prices(token_in, token_out, lookback_blocks)
+            .await
+    }
+
+    async fn get_recent_swaps(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        lookback_blocks: u64,
+    ) -> Result<Vec<PoolSwap>> {
+        self.get_recent_swaps(token_in, token_out, lookback_blocks)
+            .await
+    }
 }
@@ -0,0 +1,407 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+// Uniswap V3 Factory address on Ethereum mainnet
+const UNISWAP_V3_FACTORY: &str = "0x1F98431c8aD98523631AE4a59f267346ea31F984";
+
+// Uniswap V3 Quoter address on Ethereum mainnet
+const UNISWAP_V3_QUOTER: &str = "0xb27308f9F90D607463bb33eA1BeBb41C27CE5AB6";
+
+/// Fee tiers (in hundredths of a bip) deployed by the V3 factory
+pub const V3_FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+
+// Each tick bitmap read costs one call; bounds the range a liquidity scan may cover
+const MAX_BITMAP_WORDS: i32 = 64;
+
+/// Tick spacing the V3 factory assigns to a fee tier
+pub fn tick_spacing(fee: u32) -> Option<i32> {
+    match fee {
+        100 => Some(1),
+        500 => Some(10),
+        3000 => Some(60),
+        10000 => Some(200),
+        _ => None,
+    }
+}
+
+abigen!(
+    IUniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function liquidity() external view returns (uint128)
+        function token0() external view returns (address)
+        function tickBitmap(int16 wordPosition) external view returns (uint256)
+        function ticks(int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+        function observe(uint32[] secondsAgos) external view returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s)
+    ]"#
+);
+
+abigen!(
+    IQuoter,
+    r#"[
+        function quoteExactInputSingle(address tokenIn, address tokenOut, uint24 fee, uint256 amountIn, uint160 sqrtPriceLimitX96) external returns (uint256 amountOut)
+    ]"#
+);
+
+/// Best single-pool V3 quote for an exact input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V3Quote {
+    pub amount_out: U256,
+    pub fee: u32,
+}
+
+/// Current state of one V3 pool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V3PoolState {
+    pub pool_address: Address,
+    pub token0: Address,
+    pub fee: u32,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+impl V3PoolState {
+    /// Square root of the raw token1-per-token0 price
+    pub fn sqrt_price(&self) -> f64 {
+        u256_to_f64(self.sqrt_price_x96) / 2f64.powi(96)
+    }
+
+    /// Spot price in raw `token_out` units per raw `token_in` unit
+    pub fn spot_price(&self, token_in: Address) -> Option<Decimal> {
+        let sqrt_price = self.sqrt_price();
+        let price_token1_per_token0 = sqrt_price * sqrt_price;
+        let price = if token_in == self.token0 {
+            price_token1_per_token0
+        } else if price_token1_per_token0 > 0.0 {
+            1.0 / price_token1_per_token0
+        } else {
+            return None;
+        };
+        Decimal::from_f64(price)
+    }
+}
+
+/// An initialized tick and the liquidity added when the price crosses it upwards
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct V3InitializedTick {
+    pub tick: i32,
+    pub liquidity_net: i128,
+}
+
+/// A tick range over which a pool's active liquidity is constant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidityBand {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+}
+
+/// Split `[tick_lower, tick_upper)` into constant-liquidity bands around the pool's
+/// current tick, walking `ticks` outwards from the in-range liquidity
+pub fn liquidity_bands(
+    pool: &V3PoolState,
+    ticks: &[V3InitializedTick],
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Vec<LiquidityBand> {
+    let mut ticks: Vec<V3InitializedTick> = ticks
+        .iter()
+        .filter(|t| t.tick > tick_lower && t.tick < tick_upper)
+        .copied()
+        .collect();
+    ticks.sort_by_key(|t| t.tick);
+    let split = ticks.partition_point(|t| t.tick <= pool.tick);
+
+    let mut bands = Vec::new();
+
+    // Below the price: crossing a tick downwards removes its net liquidity
+    let mut liquidity = pool.liquidity as i128;
+    let mut upper = ticks.get(split).map_or(tick_upper, |t| t.tick);
+    for t in ticks[..split].iter().rev() {
+        bands.push((t.tick, upper, liquidity));
+        liquidity -= t.liquidity_net;
+        upper = t.tick;
+    }
+    bands.push((tick_lower, upper, liquidity));
+    bands.reverse();
+
+    // Above the price: crossing a tick upwards adds it
+    let mut liquidity = pool.liquidity as i128;
+    for (i, t) in ticks[split..].iter().enumerate() {
+        liquidity += t.liquidity_net;
+        let upper = ticks
+            .get(split + i + 1)
+            .map_or(tick_upper, |next| next.tick);
+        bands.push((t.tick, upper, liquidity));
+    }
+
+    bands
+        .into_iter()
+        .filter(|(lower, upper, _)| lower < upper)
+        .map(|(tick_lower, tick_upper, liquidity)| LiquidityBand {
+            tick_lower,
+            tick_upper,
+            liquidity: liquidity.max(0) as u128,
+        })
+        .collect()
+}
+
+/// Raw token1-per-token0 price at `tick`
+pub fn price_at_tick(tick: i32) -> f64 {
+    1.0001f64.powi(tick)
+}
+
+/// Arithmetic mean tick between two tick cumulatives `window_secs` apart, rounded
+/// towards negative infinity as the V3 oracle library does
+pub fn mean_tick(cumulative_then: i64, cumulative_now: i64, window_secs: u32) -> i32 {
+    let delta = cumulative_now - cumulative_then;
+    let window = window_secs.max(1) as i64;
+    let mut tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        tick -= 1;
+    }
+    tick as i32
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    value
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 2f64.powi(64) + *limb as f64)
+}
+
+/// Trait for Uniswap V3 pool queries
+#[async_trait]
+pub trait UniswapV3Trait: Send + Sync {
+    /// Get every deployed pool for a token pair, across fee tiers
+    async fn get_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<V3PoolState>>;
+
+    /// Quote an exact-input swap through the best-paying fee tier
+    async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<V3Quote>;
+
+    /// Initialized ticks of `pool` within `[tick_lower, tick_upper]`
+    async fn get_initialized_ticks(
+        &self,
+        pool: &V3PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<Vec<V3InitializedTick>>;
+
+    /// Tick cumulatives `pool` recorded each of `seconds_agos` before the latest block
+    async fn observe(&self, pool: Address, seconds_agos: &[u32]) -> Result<Vec<i64>>;
+}
+
+/// Pick the pool with the most in-range liquidity
+pub fn deepest_pool(pools: &[V3PoolState]) -> Option<&V3PoolState> {
+    pools.iter().max_by_key(|pool| pool.liquidity)
+}
+
+pub struct UniswapV3Pools {
+    provider: Arc<Provider<Http>>,
+    factory_address: Address,
+    quoter_address: Address,
+}
+
+impl UniswapV3Pools {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        let factory_address = UNISWAP_V3_FACTORY
+            .parse()
+            .context("Invalid Uniswap V3 factory address")?;
+        let quoter_address = UNISWAP_V3_QUOTER
+            .parse()
+            .context("Invalid Uniswap V3 quoter address")?;
+        Ok(Self {
+            provider,
+            factory_address,
+            quoter_address,
+        })
+    }
+
+    /// Get every deployed pool for a token pair, across fee tiers
+    pub async fn get_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<V3PoolState>> {
+        let factory = IUniswapV3Factory::new(self.factory_address, Arc::clone(&self.provider));
+
+        let mut pools = Vec::new();
+        for fee in V3_FEE_TIERS {
+            let pool_address = factory
+                .get_pool(token_a, token_b, fee)
+                .call()
+                .await
+                .context("Failed to query Uniswap V3 factory")?;
+            if pool_address.is_zero() {
+                continue;
+            }
+
+            let pool = IUniswapV3Pool::new(pool_address, Arc::clone(&self.provider));
+            let (sqrt_price_x96, tick, _, _, _, _, _) = pool
+                .slot_0()
+                .call()
+                .await
+                .context("Failed to get V3 pool slot0")?;
+            let liquidity = pool
+                .liquidity()
+                .call()
+                .await
+                .context("Failed to get V3 pool liquidity")?;
+            let token0 = pool
+                .token_0()
+                .call()
+                .await
+                .context("Failed to get token0")?;
+
+            pools.push(V3PoolState {
+                pool_address,
+                token0,
+                fee,
+                sqrt_price_x96,
+                tick,
+                liquidity,
+            });
+        }
+
+        Ok(pools)
+    }
+
+    /// Quote an exact-input swap through the best-paying fee tier
+    pub async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<V3Quote> {
+        let quoter = IQuoter::new(self.quoter_address, Arc::clone(&self.provider));
+
+        let mut best: Option<V3Quote> = None;
+        for pool in self.get_pools(token_in, token_out).await? {
+            // The quoter reverts for pools that cannot fill the amount
+            let Ok(amount_out) = quoter
+                .quote_exact_input_single(token_in, token_out, pool.fee, amount_in, U256::zero())
+                .call()
+                .await
+            else {
+                continue;
+            };
+            if best.as_ref().is_none_or(|b| amount_out > b.amount_out) {
+                best = Some(V3Quote {
+                    amount_out,
+                    fee: pool.fee,
+                });
+            }
+        }
+
+        best.ok_or_else(|| anyhow::anyhow!("No Uniswap V3 pool can quote this swap"))
+    }
+
+    /// Initialized ticks of `pool` within `[tick_lower, tick_upper]`, found through the
+    /// pool's tick bitmap
+    pub async fn get_initialized_ticks(
+        &self,
+        pool: &V3PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<Vec<V3InitializedTick>> {
+        let spacing = tick_spacing(pool.fee).context("Unknown V3 fee tier")?;
+        let contract = IUniswapV3Pool::new(pool.pool_address, Arc::clone(&self.provider));
+
+        // Bit `b` of word `w` marks compressed tick `w * 256 + b`
+        let first_word = tick_lower.div_euclid(spacing) >> 8;
+        let last_word = tick_upper.div_euclid(spacing) >> 8;
+        if last_word - first_word >= MAX_BITMAP_WORDS {
+            anyhow::bail!("Tick range is too wide to scan; narrow it");
+        }
+
+        let mut ticks = Vec::new();
+        for word in first_word..=last_word {
+            let bitmap = contract
+                .tick_bitmap(word as i16)
+                .call()
+                .await
+                .context("Failed to read V3 tick bitmap")?;
+            if bitmap.is_zero() {
+                continue;
+            }
+            for bit in 0..256 {
+                if !bitmap.bit(bit) {
+                    continue;
+                }
+                let tick = ((word << 8) + bit as i32) * spacing;
+                if tick < tick_lower || tick > tick_upper {
+                    continue;
+                }
+                let (_, liquidity_net, _, _, _, _, _, _) = contract
+                    .ticks(tick)
+                    .call()
+                    .await
+                    .context("Failed to read V3 tick")?;
+                ticks.push(V3InitializedTick {
+                    tick,
+                    liquidity_net,
+                });
+            }
+        }
+
+        Ok(ticks)
+    }
+
+    /// Tick cumulatives `pool` recorded each of `seconds_agos` before the latest block
+    pub async fn observe(&self, pool: Address, seconds_agos: &[u32]) -> Result<Vec<i64>> {
+        let contract = IUniswapV3Pool::new(pool, Arc::clone(&self.provider));
+        match contract.observe(seconds_agos.to_vec()).call().await {
+            Ok((tick_cumulatives, _)) => Ok(tick_cumulatives),
+            // The pool reverts with OLD when asked for a time before its oldest observation
+            Err(e) if e.decode_revert::<String>().as_deref() == Some("OLD") => anyhow::bail!(
+                "The pool's oracle history does not reach back {} seconds; use a shorter window",
+                seconds_agos.iter().max().copied().unwrap_or_default()
+            ),
+            Err(e) => Err(e).context("Failed to read V3 pool observations"),
+        }
+    }
+}
+
+#[async_trait]
+impl UniswapV3Trait for UniswapV3Pools {
+    async fn get_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<V3PoolState>> {
+        self.get_pools(token_a, token_b).await
+    }
+
+    async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<V3Quote> {
+        self.quote_exact_input(token_in, token_out, amount_in).await
+    }
+
+    async fn get_initialized_ticks(
+        &self,
+        pool: &V3PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<Vec<V3InitializedTick>> {
+        self.get_initialized_ticks(pool, tick_lower, tick_upper)
+            .await
+    }
+
+    async fn observe(&self, pool: Address, seconds_agos: &[u32]) -> Result<Vec<i64>> {
+        self.observe(pool, seconds_agos).await
+    }
+}
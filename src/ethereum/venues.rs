@@ -0,0 +1,122 @@
+use crate::ethereum::uniswap::UniswapV2Router;
+use crate::ethereum::uniswap_v3::UniswapV3Trait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::sync::Arc;
+
+// Curve Registry Exchange address on Ethereum mainnet
+const CURVE_REGISTRY_EXCHANGE: &str = "0x99a58482BD75cbab83b27EC03CA68fF489b5788f";
+
+// Typical gas used by a single swap on each venue
+const V2_SWAP_GAS: u64 = 150_000;
+const V3_SWAP_GAS: u64 = 180_000;
+const CURVE_SWAP_GAS: u64 = 250_000;
+
+abigen!(
+    ICurveRegistryExchange,
+    r#"[
+        function get_best_rate(address _from, address _to, uint256 _amount) external view returns (address, uint256)
+    ]"#
+);
+
+/// A venue that can quote an exact-input swap between two tokens
+#[async_trait]
+pub trait PriceVenue: Send + Sync {
+    /// Short identifier such as `uniswap_v2` or `curve`
+    fn name(&self) -> &str;
+
+    /// Amount of `token_out` received for `amount_in` of `token_in`, in base units
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256>;
+
+    /// Estimated gas for one swap on this venue
+    fn swap_gas_units(&self) -> u64;
+}
+
+#[async_trait]
+impl PriceVenue for UniswapV2Router {
+    fn name(&self) -> &str {
+        self.venue_name()
+    }
+
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256> {
+        self.get_amount_out(token_in, token_out, amount_in).await
+    }
+
+    fn swap_gas_units(&self) -> u64 {
+        V2_SWAP_GAS
+    }
+}
+
+/// Uniswap V3 quoted through the best fee tier
+pub struct UniswapV3Venue<V: UniswapV3Trait> {
+    pools: Arc<V>,
+}
+
+impl<V: UniswapV3Trait> UniswapV3Venue<V> {
+    pub fn new(pools: Arc<V>) -> Self {
+        Self { pools }
+    }
+}
+
+#[async_trait]
+impl<V: UniswapV3Trait + 'static> PriceVenue for UniswapV3Venue<V> {
+    fn name(&self) -> &str {
+        "uniswap_v3"
+    }
+
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256> {
+        let quote = self
+            .pools
+            .quote_exact_input(token_in, token_out, amount_in)
+            .await?;
+        Ok(quote.amount_out)
+    }
+
+    fn swap_gas_units(&self) -> u64 {
+        V3_SWAP_GAS
+    }
+}
+
+/// Curve pools routed through the on-chain registry exchange
+pub struct CurveVenue {
+    provider: Arc<Provider<Http>>,
+    exchange_address: Address,
+}
+
+impl CurveVenue {
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        let exchange_address = CURVE_REGISTRY_EXCHANGE
+            .parse()
+            .context("Invalid Curve exchange address")?;
+        Ok(Self {
+            provider,
+            exchange_address,
+        })
+    }
+}
+
+#[async_trait]
+impl PriceVenue for CurveVenue {
+    fn name(&self) -> &str {
+        "curve"
+    }
+
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256> {
+        let exchange =
+            ICurveRegistryExchange::new(self.exchange_address, Arc::clone(&self.provider));
+        let (pool, amount_out) = exchange
+            .get_best_rate(token_in, token_out, amount_in)
+            .call()
+            .await
+            .context("Failed to query Curve registry exchange")?;
+        if pool.is_zero() {
+            anyhow::bail!("No Curve pool found for this pair");
+        }
+        Ok(amount_out)
+    }
+
+    fn swap_gas_units(&self) -> u64 {
+        CURVE_SWAP_GAS
+    }
+}
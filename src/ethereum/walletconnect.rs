@@ -0,0 +1,270 @@
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a signing request may wait for the user to approve it on their phone
+pub const DEFAULT_WALLETCONNECT_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub const WALLETCONNECT_RESOURCE_URI: &str = "walletconnect://pairing";
+
+const PAIRING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Where the bridge runs and which account the paired wallet must sign with
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletConnectConfig {
+    pub bridge_url: String,
+    /// WalletConnect Cloud project the bridge relays under
+    pub project_id: String,
+    /// Transactions are sent from this account; sessions for any other are refused
+    pub address: Address,
+    pub request_timeout: Duration,
+}
+
+/// A pairing offer for a wallet to scan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub topic: String,
+    /// `wc:` URI; render it as a QR code or open it as a deep link
+    pub uri: String,
+    /// Unix time the offer lapses if no wallet takes it
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl Pairing {
+    fn expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Where the pairing stands
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairingStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pairing: Option<Pairing>,
+    /// A wallet approved the session
+    pub approved: bool,
+    /// Account the approved session signs with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<Address>,
+    pub expected_account: Address,
+    /// Approved, and for the expected account: transactions can be sent
+    pub ready: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionResponse {
+    #[serde(default)]
+    approved: bool,
+    #[serde(default)]
+    accounts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestResponse {
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RequestError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestError {
+    #[serde(default)]
+    code: i64,
+    message: String,
+}
+
+/// Account of a CAIP-10 id such as `eip155:1:0xabc…` on `chain_id`
+fn caip10_account(id: &str, chain_id: u64) -> Option<Address> {
+    let mut parts = id.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("eip155"), Some(chain), Some(address)) if chain == chain_id.to_string() => {
+            address.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Sends transactions for the user's mobile wallet to approve, over WalletConnect v2.
+///
+/// The relay protocol itself runs in a bridge built on a WalletConnect sign client,
+/// driven over HTTP: `POST /pairings` offers a pairing, `GET /pairings/{topic}` reports
+/// the session's approval and CAIP-10 accounts, and `POST /pairings/{topic}/requests`
+/// forwards a JSON-RPC request and answers once the wallet does.
+pub struct WalletConnectSigner {
+    http: reqwest::Client,
+    url: String,
+    project_id: String,
+    chain_id: u64,
+    address: Address,
+    request_timeout: Duration,
+    pairing: RwLock<Option<Pairing>>,
+}
+
+impl WalletConnectSigner {
+    pub fn new(config: &WalletConnectConfig, chain_id: u64) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: config.bridge_url.trim_end_matches('/').to_string(),
+            project_id: config.project_id.clone(),
+            chain_id,
+            address: config.address,
+            request_timeout: config.request_timeout,
+            pairing: RwLock::new(None),
+        }
+    }
+
+    /// The account transactions are sent from
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// The current pairing offer, if one was made
+    pub fn pairing(&self) -> Option<Pairing> {
+        self.pairing.read().ok().and_then(|pairing| pairing.clone())
+    }
+
+    /// Offer a new pairing, replacing the current one
+    pub async fn pair(&self) -> Result<Pairing> {
+        let pairing: Pairing = self
+            .http
+            .post(format!("{}/pairings", self.url))
+            .timeout(PAIRING_TIMEOUT)
+            .json(&json!({
+                "chain_id": self.chain_id,
+                "project_id": self.project_id,
+                "metadata": {
+                    "name": "Ethereum Trading MCP Server",
+                    "description": "Approve transactions sent by your AI agent",
+                },
+            }))
+            .send()
+            .await
+            .context("Failed to reach the WalletConnect bridge")?
+            .error_for_status()
+            .context("WalletConnect bridge refused the pairing")?
+            .json()
+            .await
+            .context("Invalid WalletConnect pairing response")?;
+        if let Ok(mut current) = self.pairing.write() {
+            *current = Some(pairing.clone());
+        }
+        Ok(pairing)
+    }
+
+    /// The current pairing, or a new one when there is none or it lapsed unused
+    pub async fn pairing_or_pair(&self) -> Result<Pairing> {
+        match self.pairing() {
+            Some(pairing) if !pairing.expired() => Ok(pairing),
+            Some(pairing) if self.status_of(&pairing).await?.approved => Ok(pairing),
+            _ => self.pair().await,
+        }
+    }
+
+    pub async fn status(&self) -> Result<PairingStatus> {
+        match self.pairing() {
+            Some(pairing) => self.status_of(&pairing).await,
+            None => Ok(PairingStatus {
+                pairing: None,
+                approved: false,
+                account: None,
+                expected_account: self.address,
+                ready: false,
+            }),
+        }
+    }
+
+    async fn status_of(&self, pairing: &Pairing) -> Result<PairingStatus> {
+        let session: SessionResponse = self
+            .http
+            .get(format!("{}/pairings/{}", self.url, pairing.topic))
+            .timeout(PAIRING_TIMEOUT)
+            .send()
+            .await
+            .context("Failed to reach the WalletConnect bridge")?
+            .error_for_status()
+            .context("WalletConnect bridge lost the pairing")?
+            .json()
+            .await
+            .context("Invalid WalletConnect session response")?;
+        let accounts: Vec<Address> = session
+            .accounts
+            .iter()
+            .filter_map(|id| caip10_account(id, self.chain_id))
+            .collect();
+        let account = accounts
+            .iter()
+            .find(|account| **account == self.address)
+            .or(accounts.first())
+            .copied();
+        Ok(PairingStatus {
+            pairing: Some(pairing.clone()),
+            approved: session.approved,
+            account,
+            expected_account: self.address,
+            ready: session.approved && account == Some(self.address),
+        })
+    }
+
+    /// Ask the wallet to sign and broadcast the filled `tx`, returning its hash once
+    /// the user approves
+    pub async fn send_transaction(&self, tx: &TypedTransaction) -> Result<H256> {
+        let status = self.status().await?;
+        let Some(pairing) = status.pairing.filter(|_| status.approved) else {
+            anyhow::bail!(
+                "No wallet is paired over WalletConnect; scan the pairing URI from connect_wallet first"
+            );
+        };
+        if status.account != Some(self.address) {
+            anyhow::bail!(
+                "The paired wallet signs for {:?}, not WALLET_ADDRESS {:?}",
+                status.account.unwrap_or_default(),
+                self.address
+            );
+        }
+
+        let mut params = serde_json::to_value(tx).context("Failed to encode transaction")?;
+        if let Some(params) = params.as_object_mut() {
+            // Wallets pick the envelope from the fee fields
+            params.remove("type");
+            params.insert("from".to_string(), json!(self.address));
+        }
+        let response: RequestResponse = self
+            .http
+            .post(format!("{}/pairings/{}/requests", self.url, pairing.topic))
+            .timeout(self.request_timeout)
+            .json(&json!({
+                "chain_id": self.chain_id,
+                "method": "eth_sendTransaction",
+                "params": [params],
+            }))
+            .send()
+            .await
+            .context("No answer from the wallet over WalletConnect")?
+            .error_for_status()
+            .context("WalletConnect bridge failed the request")?
+            .json()
+            .await
+            .context("Invalid WalletConnect request response")?;
+        if let Some(error) = response.error {
+            anyhow::bail!(
+                "The wallet declined the transaction: {} ({})",
+                error.message,
+                error.code
+            );
+        }
+        response
+            .result
+            .and_then(|result| result.as_str()?.parse().ok())
+            .context("The wallet returned no transaction hash")
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+// MakerDAO Pot (DAI Savings Rate) and Savings DAI on Ethereum mainnet
+const MAKER_POT: &str = "0x197E90f9FAD81970bA7976f33CbD77088E5D7cf7";
+const DAI: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+
+// Aave V3 pool data provider on Ethereum mainnet
+const AAVE_V3_DATA_PROVIDER: &str = "0x7B4EB56E7CD4b454BA8ff71E4518426369a138a3";
+
+// Compound V3 (Comet) USDC market on Ethereum mainnet
+const COMPOUND_V3_USDC: &str = "0xc3d688B66703497DAA19211EEdff47f25384cdc3";
+
+/// Well-known ERC-4626 stablecoin vaults on Ethereum mainnet
+pub const DEFAULT_ERC4626_VAULTS: &[(&str, &str)] =
+    &[("susde", "0x9D39A5DE30e57443BfF2A8307A4256c8797A3497")];
+
+// Gas of the ERC20 approval preceding every deposit
+const APPROVE_GAS: u64 = 46_000;
+
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+// About one week of blocks, the window share-price growth is measured over
+const VAULT_LOOKBACK_BLOCKS: u64 = 7 * 7200;
+
+abigen!(
+    IMakerPot,
+    r#"[
+        function dsr() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    IAaveDataProvider,
+    r#"[
+        function getReserveData(address asset) external view returns (uint256 unbacked, uint256 accruedToTreasuryScaled, uint256 totalAToken, uint256 totalStableDebt, uint256 totalVariableDebt, uint256 liquidityRate, uint256 variableBorrowRate, uint256 stableBorrowRate, uint256 averageStableBorrowRate, uint256 liquidityIndex, uint256 variableBorrowIndex, uint40 lastUpdateTimestamp)
+    ]"#
+);
+
+abigen!(
+    IComet,
+    r#"[
+        function baseToken() external view returns (address)
+        function getUtilization() external view returns (uint256)
+        function getSupplyRate(uint256 utilization) external view returns (uint64)
+    ]"#
+);
+
+abigen!(
+    IERC4626,
+    r#"[
+        function asset() external view returns (address)
+        function decimals() external view returns (uint8)
+        function convertToAssets(uint256 shares) external view returns (uint256)
+    ]"#
+);
+
+/// A protocol that pays interest on deposits of a token
+#[async_trait]
+pub trait YieldSource: Send + Sync {
+    /// Short identifier such as `aave_v3` or `sdai`
+    fn name(&self) -> &str;
+
+    /// Current supply APY for `asset` in percent; errors when the asset is not accepted
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal>;
+
+    /// Typical gas to deposit, including the approval
+    fn entry_gas_units(&self) -> u64;
+
+    /// Typical gas to withdraw
+    fn exit_gas_units(&self) -> u64;
+}
+
+/// APY in percent of a rate compounded every second, e.g. 1e-9 per second
+pub fn apy_from_per_second_rate(rate: f64) -> Result<Decimal> {
+    let apy = ((1.0 + rate).powf(SECONDS_PER_YEAR as f64) - 1.0) * 100.0;
+    Decimal::from_f64(apy)
+        .map(|apy| apy.round_dp(4))
+        .with_context(|| format!("APY of a {} per second rate is out of range", rate))
+}
+
+fn ray_to_f64(value: U256) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0) / 1e27
+}
+
+/// DAI deposited into the Maker DSR, as Savings DAI does
+pub struct SavingsDai {
+    provider: Arc<Provider<Http>>,
+}
+
+impl SavingsDai {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl YieldSource for SavingsDai {
+    fn name(&self) -> &str {
+        "sdai"
+    }
+
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal> {
+        if asset != DAI.parse::<Address>().context("Invalid DAI address")? {
+            anyhow::bail!("The DAI Savings Rate only accepts DAI");
+        }
+        let pot = IMakerPot::new(
+            MAKER_POT
+                .parse::<Address>()
+                .context("Invalid Maker pot address")?,
+            Arc::clone(&self.provider),
+        );
+        let dsr = pot.dsr().call().await.context("Failed to read the DSR")?;
+        apy_from_per_second_rate(ray_to_f64(dsr) - 1.0)
+    }
+
+    fn entry_gas_units(&self) -> u64 {
+        APPROVE_GAS + 120_000
+    }
+
+    fn exit_gas_units(&self) -> u64 {
+        110_000
+    }
+}
+
+/// Supplying to Aave V3
+pub struct AaveV3Yield {
+    provider: Arc<Provider<Http>>,
+}
+
+impl AaveV3Yield {
+    pub fn new(provider: Arc<Provider<Http>>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl YieldSource for AaveV3Yield {
+    fn name(&self) -> &str {
+        "aave_v3"
+    }
+
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal> {
+        let provider = IAaveDataProvider::new(
+            AAVE_V3_DATA_PROVIDER
+                .parse::<Address>()
+                .context("Invalid Aave data provider address")?,
+            Arc::clone(&self.provider),
+        );
+        let reserve = provider
+            .get_reserve_data(asset)
+            .call()
+            .await
+            .context("Failed to read Aave reserve data")?;
+        // An unlisted asset has an empty reserve
+        if reserve.9.is_zero() {
+            anyhow::bail!("Asset is not listed on Aave V3");
+        }
+        // liquidityRate is an APR in ray, accrued every second
+        let apr = ray_to_f64(reserve.5);
+        apy_from_per_second_rate(apr / SECONDS_PER_YEAR as f64)
+    }
+
+    fn entry_gas_units(&self) -> u64 {
+        APPROVE_GAS + 210_000
+    }
+
+    fn exit_gas_units(&self) -> u64 {
+        230_000
+    }
+}
+
+/// Supplying the base asset of a Compound V3 market
+pub struct CompoundV3Yield {
+    provider: Arc<Provider<Http>>,
+    comet: Address,
+}
+
+impl CompoundV3Yield {
+    /// The USDC market
+    pub fn new(provider: Arc<Provider<Http>>) -> Result<Self> {
+        Ok(Self {
+            provider,
+            comet: COMPOUND_V3_USDC
+                .parse()
+                .context("Invalid Compound V3 market address")?,
+        })
+    }
+}
+
+#[async_trait]
+impl YieldSource for CompoundV3Yield {
+    fn name(&self) -> &str {
+        "compound_v3"
+    }
+
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal> {
+        let comet = IComet::new(self.comet, Arc::clone(&self.provider));
+        let base_token = comet
+            .base_token()
+            .call()
+            .await
+            .context("Failed to read the Compound market")?;
+        if base_token != asset {
+            anyhow::bail!("Compound V3 market does not accept this asset");
+        }
+        let utilization = comet.get_utilization().call().await?;
+        let rate = comet.get_supply_rate(utilization).call().await?;
+        apy_from_per_second_rate(rate as f64 / 1e18)
+    }
+
+    fn entry_gas_units(&self) -> u64 {
+        APPROVE_GAS + 150_000
+    }
+
+    fn exit_gas_units(&self) -> u64 {
+        160_000
+    }
+}
+
+/// An ERC-4626 vault, its APY measured from share-price growth over the last week
+pub struct Erc4626Vault {
+    provider: Arc<Provider<Http>>,
+    name: String,
+    vault: Address,
+}
+
+impl Erc4626Vault {
+    pub fn new(provider: Arc<Provider<Http>>, name: impl Into<String>, vault: Address) -> Self {
+        Self {
+            provider,
+            name: name.into(),
+            vault,
+        }
+    }
+}
+
+#[async_trait]
+impl YieldSource for Erc4626Vault {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn supply_apy(&self, asset: Address) -> Result<Decimal> {
+        let vault = IERC4626::new(self.vault, Arc::clone(&self.provider));
+        let vault_asset = vault
+            .asset()
+            .call()
+            .await
+            .context("Failed to read the vault asset")?;
+        if vault_asset != asset {
+            anyhow::bail!("Vault does not accept this asset");
+        }
+        let one_share = U256::exp10(vault.decimals().call().await? as usize);
+        let latest = self.provider.get_block_number().await?.as_u64();
+        let then = latest.saturating_sub(VAULT_LOOKBACK_BLOCKS);
+
+        let assets_now = vault
+            .convert_to_assets(one_share)
+            .block(latest)
+            .call()
+            .await?;
+        // Historical state needs an archive node
+        let assets_then = vault
+            .convert_to_assets(one_share)
+            .block(then)
+            .call()
+            .await
+            .context("Failed to read the vault's past share price (archive node required)")?;
+        if assets_then.is_zero() {
+            anyhow::bail!("Vault had no share price a week ago");
+        }
+
+        let growth =
+            assets_now.to_string().parse::<f64>()? / assets_then.to_string().parse::<f64>()?;
+        // 12 second blocks
+        let window_secs = (latest - then) as f64 * 12.0;
+        let apy = (growth.powf(SECONDS_PER_YEAR as f64 / window_secs) - 1.0) * 100.0;
+        Decimal::from_f64(apy)
+            .map(|apy| apy.round_dp(4))
+            .context("Vault APY is out of range")
+    }
+
+    fn entry_gas_units(&self) -> u64 {
+        APPROVE_GAS + 110_000
+    }
+
+    fn exit_gas_units(&self) -> u64 {
+        100_000
+    }
+}
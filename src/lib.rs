@@ -1,7 +1,14 @@
 pub mod config;
 pub mod ethereum;
+pub mod logging;
 pub mod mcp;
+pub mod runtime;
+pub mod secrets;
+pub mod supervisor;
+pub mod telemetry;
+pub mod time;
 pub mod tools;
+pub mod vault;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,151 @@
+use serde_json::{json, Map, Value};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::span::Record;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Fields log lines carry under the same name wherever they are logged. A line
+/// logged inside a span, such as a tool call's, inherits the span's.
+pub const STABLE_LOG_FIELDS: &[&str] =
+    &["tool", "chain", "duration_ms", "rpc_endpoint", "error_code"];
+
+/// How log lines on stderr are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => anyhow::bail!("Unknown log format: {} (expected text or json)", other),
+        }
+    }
+}
+
+/// Collects fields as JSON values; `message` is the event's formatted message
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, json!(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, json!(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, json!(format!("{:?}", value)));
+    }
+}
+
+/// Formats span fields as a JSON object, so `JsonFormat` can lift stable ones into lines
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(&self, current: &mut FormattedFields<Self>, fields: &Record<'_>) -> fmt::Result {
+        let mut visitor = JsonVisitor(
+            serde_json::from_str(&current.fields)
+                .ok()
+                .and_then(|fields: Value| fields.as_object().cloned())
+                .unwrap_or_default(),
+        );
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// Writes each event as one JSON object: `timestamp`, `level`, `target`, `message`,
+/// the event's fields, and the stable fields of the spans it was logged in
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        let metadata = event.metadata();
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        let mut line = visitor.0;
+        line.insert("timestamp".to_string(), json!(timestamp));
+        line.insert("level".to_string(), json!(metadata.level().as_str()));
+        line.insert("target".to_string(), json!(metadata.target()));
+
+        // Innermost spans first, so the closest value of a field wins
+        for span in ctx.event_scope().into_iter().flatten() {
+            let extensions = span.extensions();
+            let Some(fields) = extensions
+                .get::<FormattedFields<JsonFields>>()
+                .and_then(|fields| serde_json::from_str::<Map<String, Value>>(&fields.fields).ok())
+            else {
+                continue;
+            };
+            for (key, value) in fields {
+                if STABLE_LOG_FIELDS.contains(&key.as_str()) && !line.contains_key(&key) {
+                    line.insert(key, value);
+                }
+            }
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
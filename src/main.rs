@@ -1,41 +1,99 @@
-mod config;
-mod ethereum;
-mod mcp;
-mod tools;
-
-#[cfg(test)]
-mod tests;
-
 use anyhow::Result;
-use rmcp::{ServiceExt, transport::stdio};
+use ethereum_trading_mcp_server::config::{Config, Transport};
+use ethereum_trading_mcp_server::logging::{JsonFields, JsonFormat, LogFormat};
+use ethereum_trading_mcp_server::mcp;
+use ethereum_trading_mcp_server::runtime::{load_env_file, run_http_service};
+use ethereum_trading_mcp_server::secrets::{redact, RedactingMakeWriter};
+use ethereum_trading_mcp_server::telemetry::{exported, OtlpExporter};
+use ethereum_trading_mcp_server::vault;
+use rmcp::{transport::stdio, ServiceExt};
 use tracing::info;
-use tracing_subscriber;
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::prelude::*;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    // Configuration says where traces go, so it is read before logging starts
+    load_env_file();
+    let config = match vault::load_secrets().await {
+        Ok(()) => Config::from_env(),
+        Err(e) => Err(e.context("Failed to read secrets from Vault")),
+    };
+    let (exporter, otlp_layer) = match config.as_ref().ok().and_then(|c| c.otlp.as_ref()) {
+        Some(otlp) => {
+            let (exporter, layer) = OtlpExporter::start(otlp);
+            (Some(exporter), Some(layer.with_filter(filter_fn(exported))))
+        }
+        None => (None, None),
+    };
+
     // Initialize logging - MUST write to stderr, not stdout!
     // stdout is reserved for JSON-RPC protocol messages
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .with_writer(std::io::stderr)
+    let log_format = config.as_ref().map(|c| c.log_format).unwrap_or_default();
+    let (text_layer, json_layer) = match log_format {
+        LogFormat::Text => (
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(RedactingMakeWriter::new(std::io::stderr))
+                    .with_filter(EnvFilter::new("info")),
+            ),
+            None,
+        ),
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .fmt_fields(JsonFields)
+                    .event_format(JsonFormat)
+                    .with_writer(RedactingMakeWriter::new(std::io::stderr))
+                    .with_filter(EnvFilter::new("info")),
+            ),
+        ),
+    };
+    tracing_subscriber::registry()
+        .with(text_layer)
+        .with(json_layer)
+        .with(otlp_layer)
         .init();
 
-    info!("Starting Ethereum Trading MCP Server");
+    let result = match config {
+        Ok(config) => run(config).await,
+        Err(e) => Err(e),
+    };
+    if let Some(exporter) = exporter {
+        exporter.flush().await;
+    }
 
-    // Load configuration
-    dotenv::dotenv().ok();
-    let config = config::Config::from_env()?;
+    // Error chains can carry config values, so scrub them like log lines
+    if let Err(e) = result {
+        eprintln!("Error: {}", redact(&format!("{:?}", e)));
+        std::process::exit(1);
+    }
+}
+
+async fn run(config: Config) -> Result<()> {
+    info!("Starting Ethereum Trading MCP Server");
 
-    // Create MCP server
-    let server = mcp::McpServer::new(config).await?;
+    match config.transport {
+        Transport::Http { bind } => run_http_service(config, bind).await,
+        Transport::Stdio => {
+            let server = mcp::McpServer::new(config).await?;
 
-    // Serve over stdio using tokio stdin/stdout
-    info!("Server ready, listening on stdio");
-    let service = server.serve(stdio()).await.inspect_err(|e| {
-        tracing::error!("serving error: {:?}", e);
-    })?;
+            // Serve over stdio using tokio stdin/stdout
+            info!("Server ready, listening on stdio");
+            let service = server
+                .open_session()
+                .serve(stdio())
+                .await
+                .inspect_err(|e| {
+                    tracing::error!("serving error: {:?}", e);
+                })?;
 
-    service.waiting().await?;
+            let result = service.waiting().await;
+            server.stop_background_tasks().await;
+            result?;
 
-    Ok(())
+            Ok(())
+        }
+    }
 }
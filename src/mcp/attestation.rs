@@ -0,0 +1,97 @@
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use ethers::utils::{hash_message, keccak256};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Signature over a tool result, returned in the result's `_meta.attestation`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub signer: Address,
+    pub chain_id: u64,
+    /// Latest block when the result was signed
+    pub block_number: u64,
+    /// keccak256 of the exact result text
+    pub payload_hash: H256,
+    /// EIP-191 signature over `message()`
+    pub signature: String,
+}
+
+impl Attestation {
+    /// The personal message that was signed
+    pub fn message(&self) -> String {
+        attestation_message(self.chain_id, self.block_number, self.payload_hash)
+    }
+
+    /// Recover the signer for `payload`, failing if the payload or fields were altered
+    pub fn verify(&self, payload: &str) -> Result<Address> {
+        let payload_hash = H256::from(keccak256(payload.as_bytes()));
+        if payload_hash != self.payload_hash {
+            anyhow::bail!("Payload does not match the attested hash");
+        }
+        let signature: Signature = self.signature.parse().context("Invalid signature")?;
+        let signer = signature
+            .recover(self.message())
+            .context("Failed to recover signer")?;
+        if signer != self.signer {
+            anyhow::bail!("Signature was made by {:?}, not {:?}", signer, self.signer);
+        }
+        Ok(signer)
+    }
+}
+
+fn attestation_message(chain_id: u64, block_number: u64, payload_hash: H256) -> String {
+    format!(
+        "Ethereum Trading MCP attestation\nchain: {}\nblock: {}\npayload: {:?}",
+        chain_id, block_number, payload_hash
+    )
+}
+
+/// Signs tool results so downstream systems can check they came from this server unmodified
+pub struct Attestor {
+    wallet: LocalWallet,
+    client: Arc<dyn EthereumClientTrait>,
+}
+
+impl Attestor {
+    pub fn new(key: &str, chain_id: u64, client: Arc<dyn EthereumClientTrait>) -> Result<Self> {
+        // The parse error can echo the key, so it is dropped
+        let wallet = key
+            .parse::<LocalWallet>()
+            .map_err(|_| anyhow::anyhow!("Invalid attestation key"))?
+            .with_chain_id(chain_id);
+        Ok(Self { wallet, client })
+    }
+
+    pub fn signer(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Sign `payload` at the chain's current block
+    pub async fn attest(&self, payload: &str) -> Result<Attestation> {
+        let block_number = self
+            .client
+            .get_block_number()
+            .await
+            .context("Failed to fetch block number for attestation")?;
+        self.sign(payload, block_number)
+    }
+
+    pub fn sign(&self, payload: &str, block_number: u64) -> Result<Attestation> {
+        let chain_id = self.wallet.chain_id();
+        let payload_hash = H256::from(keccak256(payload.as_bytes()));
+        let message = attestation_message(chain_id, block_number, payload_hash);
+        let signature = self
+            .wallet
+            .sign_hash(hash_message(message))
+            .context("Failed to sign attestation")?;
+        Ok(Attestation {
+            signer: self.signer(),
+            chain_id,
+            block_number,
+            payload_hash,
+            signature: format!("0x{}", signature),
+        })
+    }
+}
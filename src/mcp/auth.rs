@@ -0,0 +1,80 @@
+use crate::config::ApiToken;
+use crate::tools::PermissionTier;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// Maps bearer tokens to permission tiers
+#[derive(Debug, Clone, Default)]
+pub struct Authenticator {
+    tokens: Vec<ApiToken>,
+}
+
+impl Authenticator {
+    pub fn new(tokens: Vec<ApiToken>) -> Self {
+        Self { tokens }
+    }
+
+    /// With no tokens configured every caller is trusted
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    pub fn tier_for_token(&self, token: &str) -> Option<PermissionTier> {
+        self.tokens
+            .iter()
+            .find(|api_token| constant_time_eq(api_token.token.expose_secret(), token))
+            .map(|api_token| api_token.tier)
+    }
+
+    /// Tier granted by an `Authorization: Bearer` header, or `None` if it is missing or unknown
+    pub fn tier_for_headers(&self, headers: &HeaderMap) -> Option<PermissionTier> {
+        if !self.is_enabled() {
+            return Some(PermissionTier::Admin);
+        }
+        let token = headers
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?;
+        self.tier_for_token(token.trim())
+    }
+
+    /// Which configured token `headers` carry, as `token-<n>` for its position in
+    /// MCP_AUTH_TOKENS, so callers can tell clients apart without keeping the token
+    pub fn token_label(&self, headers: &HeaderMap) -> Option<String> {
+        let token = headers
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?
+            .trim();
+        self.tokens
+            .iter()
+            .position(|api_token| constant_time_eq(api_token.token.expose_secret(), token))
+            .map(|index| format!("token-{}", index + 1))
+    }
+}
+
+// Avoid leaking how much of a token matched through response timing
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Reject HTTP requests without a recognized bearer token
+pub async fn require_bearer(
+    State(auth): State<Arc<Authenticator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if auth.tier_for_headers(request.headers()).is_none() {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response();
+    }
+    next.run(request).await
+}
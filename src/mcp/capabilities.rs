@@ -0,0 +1,127 @@
+use crate::config::Config;
+use crate::mcp::usage::ToolCallStats;
+use crate::tools::pool_share::OverLimitAction;
+use crate::tools::route_objective::RouteObjective;
+use crate::tools::units::from_base_units;
+use crate::tools::{PermissionTier, ToolCategory};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What this deployment is configured to do, independent of who is asking
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Deployment {
+    pub chain_id: u64,
+    /// Every chain the cross-chain tools read, including the primary one
+    pub chains: Vec<u64>,
+    /// Venues compared by the pricing and arbitrage tools
+    pub price_venues: Vec<String>,
+    /// Deposit yields compared by compare_yields
+    pub yield_sources: Vec<String>,
+    /// Whether live transactions may be sent at all
+    pub execution_enabled: bool,
+    pub limits: DeploymentLimits,
+}
+
+/// Configured bounds on calls and writes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeploymentLimits {
+    /// Serialized results above this size are trimmed; `None` when uncapped
+    pub max_response_bytes: Option<usize>,
+    pub quote_ttl_secs: u64,
+    pub max_quote_move_bps: u32,
+    /// Slippage percent swap_tokens applies when neither the call nor an override sets it
+    pub default_slippage_percent: f64,
+    /// Share of a pool's reserve one swap may sell; `None` when unbounded
+    pub max_pool_share_percent: Option<Decimal>,
+    /// Whether swaps over the pool share limit are refused or need confirmation
+    pub pool_share_action: Option<OverLimitAction>,
+    /// What swap routes are ranked by unless a call chooses
+    pub route_objective: RouteObjective,
+    /// ETH live writes must leave in the wallet for gas
+    pub gas_reserve_eth: Decimal,
+    pub gas_reserve_override_allowed: bool,
+    pub confirm_unknown_recipients: bool,
+    pub confirm_new_recipients: bool,
+    pub batch_max_recipients: usize,
+    /// `None` when batch_transfer leaves per-recipient ETH amounts uncapped
+    pub batch_max_eth_per_recipient: Option<Decimal>,
+}
+
+impl Deployment {
+    pub fn from_config(
+        config: &Config,
+        price_venues: Vec<String>,
+        yield_sources: Vec<String>,
+    ) -> Self {
+        Self {
+            chain_id: config.chain_id,
+            chains: std::iter::once(config.chain_id)
+                .chain(config.chain_rpc_urls.iter().map(|(chain_id, _)| *chain_id))
+                .collect(),
+            price_venues,
+            yield_sources,
+            execution_enabled: config.execution_enabled,
+            limits: DeploymentLimits {
+                max_response_bytes: config.max_response_bytes,
+                quote_ttl_secs: config.quote_ttl.as_secs(),
+                max_quote_move_bps: config.max_quote_move_bps,
+                default_slippage_percent: config.slippage.default_percent,
+                max_pool_share_percent: config
+                    .pool_share_limit
+                    .map(|limit| limit.max_percent.normalize()),
+                pool_share_action: config.pool_share_limit.map(|limit| limit.action),
+                route_objective: config.route_objective,
+                gas_reserve_eth: from_base_units(config.gas_reserve.min_balance, 18)
+                    .unwrap_or_default()
+                    .normalize(),
+                gas_reserve_override_allowed: config.gas_reserve.allow_override,
+                confirm_unknown_recipients: config.confirm_unknown_recipients,
+                confirm_new_recipients: config.confirm_new_recipients,
+                batch_max_recipients: config.batch_limits.max_recipients,
+                batch_max_eth_per_recipient: config.batch_limits.max_eth_per_recipient,
+            },
+        }
+    }
+}
+
+/// Whether the caller can get transactions sent
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteAccess {
+    pub execution_enabled: bool,
+    pub caller_tier: PermissionTier,
+    /// Live sends need both execution enabled and at least the trade tier
+    pub caller_can_send: bool,
+    /// Live sends also need a one-time code from the user's authenticator app
+    pub one_time_code_required: bool,
+}
+
+/// Concurrency budget of a tool category; live load is in the admin metrics resource
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryBudget {
+    pub category: ToolCategory,
+    pub max_concurrency: usize,
+    pub queue_depth: usize,
+}
+
+/// A tool the caller may use, with its usage since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCapability {
+    pub name: String,
+    pub category: ToolCategory,
+    pub required_tier: PermissionTier,
+    #[serde(flatten)]
+    pub usage: ToolCallStats,
+}
+
+/// Body of the capabilities resource, as seen by one caller
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// `None` for servers built around an explicit tool set
+    pub deployment: Option<Deployment>,
+    pub writes: WriteAccess,
+    pub concurrency: Vec<CategoryBudget>,
+    pub tools: Vec<ToolCapability>,
+    /// Alternate tool names, alias to tool
+    pub aliases: BTreeMap<String, String>,
+}
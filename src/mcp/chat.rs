@@ -0,0 +1,184 @@
+use crate::secrets::SecretString;
+use crate::tools::alerts::{AlertSeverity, AlertSink};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::warn;
+
+pub const DEFAULT_TELEGRAM_API_URL: &str = "https://api.telegram.org";
+pub const DEFAULT_DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+// Telegram takes up to 4096 characters a message, Discord 2000
+const TELEGRAM_MAX_CHARS: usize = 4096;
+const DISCORD_MAX_CHARS: usize = 2000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A bot's token and the chat or channel it posts to
+#[derive(Debug, Clone)]
+pub struct ChatBot {
+    pub token: SecretString,
+    pub chat_id: String,
+}
+
+/// Chat service a bot posts through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatService {
+    Telegram,
+    Discord,
+}
+
+impl ChatService {
+    fn max_chars(self) -> usize {
+        match self {
+            ChatService::Telegram => TELEGRAM_MAX_CHARS,
+            ChatService::Discord => DISCORD_MAX_CHARS,
+        }
+    }
+}
+
+/// Posts alerts as chat messages through a Telegram or Discord bot, so they reach
+/// people with no MCP client connected.
+///
+/// Messages are sent in the background, once; a failed post is logged and dropped.
+#[derive(Clone)]
+pub struct ChatSink {
+    http: reqwest::Client,
+    service: ChatService,
+    api_url: String,
+    bot: ChatBot,
+    /// Topics delivered; empty delivers every topic
+    topics: Vec<String>,
+}
+
+impl ChatSink {
+    pub fn new(service: ChatService, api_url: &str, bot: ChatBot) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            service,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            bot,
+            topics: Vec::new(),
+        }
+    }
+
+    pub fn telegram(bot: ChatBot) -> Self {
+        Self::new(ChatService::Telegram, DEFAULT_TELEGRAM_API_URL, bot)
+    }
+
+    pub fn discord(bot: ChatBot) -> Self {
+        Self::new(ChatService::Discord, DEFAULT_DISCORD_API_URL, bot)
+    }
+
+    /// Only deliver alerts of `topics`
+    pub fn with_topics(mut self, topics: Vec<String>) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    fn subscribed(&self, topic: &str) -> bool {
+        self.topics.is_empty() || self.topics.iter().any(|t| t == topic)
+    }
+
+    async fn post(&self, text: &str) {
+        let request = match self.service {
+            // The token is part of Telegram's URL, so errors are logged without it
+            ChatService::Telegram => self
+                .http
+                .post(format!(
+                    "{}/bot{}/sendMessage",
+                    self.api_url,
+                    self.bot.token.expose_secret()
+                ))
+                .json(&json!({
+                    "chat_id": self.bot.chat_id,
+                    "text": text,
+                    "disable_web_page_preview": true,
+                })),
+            ChatService::Discord => self
+                .http
+                .post(format!(
+                    "{}/channels/{}/messages",
+                    self.api_url, self.bot.chat_id
+                ))
+                .header(
+                    reqwest::header::AUTHORIZATION,
+                    format!("Bot {}", self.bot.token.expose_secret()),
+                )
+                .json(&json!({
+                    "content": text,
+                    "allowed_mentions": { "parse": [] },
+                })),
+        };
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => warn!(
+                service = ?self.service,
+                status = %response.status(),
+                "Chat service refused notification"
+            ),
+            Err(e) => warn!(
+                service = ?self.service,
+                "Failed to post notification: {}",
+                e.without_url()
+            ),
+        }
+    }
+}
+
+/// Plain-text message for `alert`: a headline naming the topic and event, then one
+/// line per field, cut to `max_chars` characters
+pub fn format_alert(
+    topic: &str,
+    severity: AlertSeverity,
+    alert: &Value,
+    max_chars: usize,
+) -> String {
+    let marker = match severity {
+        AlertSeverity::Info => "ℹ️",
+        AlertSeverity::Warning => "⚠️",
+        AlertSeverity::Critical => "🚨",
+    };
+    let mut text = match alert.get("event").and_then(Value::as_str) {
+        Some(event) => format!("{} [{}] {}", marker, topic, event),
+        None => format!("{} [{}]", marker, topic),
+    };
+    match alert {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                if key == "event" || value.is_null() {
+                    continue;
+                }
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                text.push_str(&format!("\n{}: {}", key, value));
+            }
+        }
+        Value::Null => {}
+        value => text.push_str(&format!("\n{}", value)),
+    }
+    if text.chars().count() > max_chars {
+        text = text.chars().take(max_chars.saturating_sub(1)).collect();
+        text.push('…');
+    }
+    text
+}
+
+#[async_trait]
+impl AlertSink for ChatSink {
+    /// Queue the alert as a chat message, returning 1 when it was queued
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+        if !self.subscribed(topic) {
+            return 0;
+        }
+        let text = format_alert(topic, severity, alert, self.service.max_chars());
+        let sink = self.clone();
+        tokio::spawn(async move { sink.post(&text).await });
+        1
+    }
+}
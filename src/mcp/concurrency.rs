@@ -0,0 +1,154 @@
+use crate::config::{ConcurrencyLimit, Config};
+use crate::tools::ToolCategory;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Returned when a category is running at capacity and its wait queue is full
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Saturated {
+    pub category: ToolCategory,
+    pub limit: ConcurrencyLimit,
+}
+
+impl std::fmt::Display for Saturated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tool category '{}' is saturated (limit {} running, {} queued); retry later",
+            self.category.as_str(),
+            self.limit.max_concurrency,
+            self.limit.queue_depth
+        )
+    }
+}
+
+impl std::error::Error for Saturated {}
+
+/// Point-in-time counters for one tool category
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryStats {
+    pub category: ToolCategory,
+    pub max_concurrency: usize,
+    pub queue_depth: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    pub completed: u64,
+    pub rejected: u64,
+    /// Fraction of execution slots in use, 0.0 to 1.0
+    pub saturation: f64,
+}
+
+struct CategoryLimiter {
+    limit: ConcurrencyLimit,
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    completed: Arc<AtomicU64>,
+    rejected: AtomicU64,
+}
+
+impl CategoryLimiter {
+    fn new(limit: ConcurrencyLimit) -> Self {
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit.max_concurrency)),
+            queued: AtomicUsize::new(0),
+            completed: Arc::new(AtomicU64::new(0)),
+            rejected: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Execution slot held for the duration of a tool call
+pub struct ToolPermit {
+    _permit: OwnedSemaphorePermit,
+    completed: Arc<AtomicU64>,
+}
+
+impl Drop for ToolPermit {
+    fn drop(&mut self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Per-category semaphores bounding how many tool calls hit the RPC at once
+pub struct ConcurrencyLimiter {
+    categories: HashMap<ToolCategory, CategoryLimiter>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(limits: impl Fn(ToolCategory) -> ConcurrencyLimit) -> Self {
+        let categories = ToolCategory::ALL
+            .into_iter()
+            .map(|category| (category, CategoryLimiter::new(limits(category))))
+            .collect();
+        Self { categories }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(|category| config.tool_limit(category))
+    }
+
+    /// Wait for an execution slot, or fail fast if the category's queue is full
+    pub async fn acquire(&self, category: ToolCategory) -> Result<ToolPermit, Saturated> {
+        let limiter = &self.categories[&category];
+
+        if let Ok(permit) = Arc::clone(&limiter.semaphore).try_acquire_owned() {
+            return Ok(ToolPermit {
+                _permit: permit,
+                completed: Arc::clone(&limiter.completed),
+            });
+        }
+
+        let reserved = limiter
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < limiter.limit.queue_depth).then_some(queued + 1)
+            });
+        if reserved.is_err() {
+            limiter.rejected.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                category = category.as_str(),
+                "tool category saturated, rejecting call"
+            );
+            return Err(Saturated {
+                category,
+                limit: limiter.limit,
+            });
+        }
+
+        let permit = Arc::clone(&limiter.semaphore)
+            .acquire_owned()
+            .await
+            .expect("tool semaphore is never closed");
+        limiter.queued.fetch_sub(1, Ordering::AcqRel);
+
+        Ok(ToolPermit {
+            _permit: permit,
+            completed: Arc::clone(&limiter.completed),
+        })
+    }
+
+    pub fn stats(&self) -> Vec<CategoryStats> {
+        ToolCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let limiter = &self.categories[&category];
+                let in_flight =
+                    limiter.limit.max_concurrency - limiter.semaphore.available_permits();
+                CategoryStats {
+                    category,
+                    max_concurrency: limiter.limit.max_concurrency,
+                    queue_depth: limiter.limit.queue_depth,
+                    in_flight,
+                    queued: limiter.queued.load(Ordering::Acquire),
+                    completed: limiter.completed.load(Ordering::Relaxed),
+                    rejected: limiter.rejected.load(Ordering::Relaxed),
+                    saturation: in_flight as f64 / limiter.limit.max_concurrency as f64,
+                }
+            })
+            .collect()
+    }
+}
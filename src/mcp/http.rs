@@ -0,0 +1,166 @@
+use crate::mcp::auth::require_bearer;
+use crate::mcp::McpServer;
+use anyhow::{Context, Result};
+use axum::extract::connect_info::Connected;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::serve::{IncomingStream, Listener};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+
+/// Open HTTP connections allowed by default
+pub const DEFAULT_HTTP_MAX_CONNECTIONS: usize = 256;
+
+/// TCP listener that stops accepting while `max_connections` are open. Further
+/// clients wait in the kernel's accept backlog until a connection closes.
+pub struct LimitedListener {
+    inner: TcpListener,
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl LimitedListener {
+    /// `None` leaves connections uncapped
+    pub fn new(inner: TcpListener, max_connections: Option<usize>) -> Self {
+        Self {
+            inner,
+            slots: max_connections.map(|max| Arc::new(Semaphore::new(max))),
+        }
+    }
+
+    /// Connections that can still be accepted, or `None` when uncapped
+    pub fn available(&self) -> Option<usize> {
+        self.slots.as_ref().map(|slots| slots.available_permits())
+    }
+}
+
+impl Listener for LimitedListener {
+    type Io = LimitedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let permit = match &self.slots {
+            Some(slots) => Some(
+                Arc::clone(slots)
+                    .acquire_owned()
+                    .await
+                    .expect("connection semaphore is never closed"),
+            ),
+            None => None,
+        };
+        let (stream, addr) = Listener::accept(&mut self.inner).await;
+        (
+            LimitedStream {
+                stream,
+                _permit: permit,
+            },
+            addr,
+        )
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// An accepted connection, holding its slot until it is dropped
+pub struct LimitedStream {
+    stream: TcpStream,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl AsyncRead for LimitedStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for LimitedStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// Address of the client behind an HTTP request, available as `ConnectInfo<PeerAddr>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, LimitedListener>> for PeerAddr {
+    fn connect_info(stream: IncomingStream<'_, LimitedListener>) -> Self {
+        PeerAddr(*stream.remote_addr())
+    }
+}
+
+/// The `/mcp` endpoint behind bearer authentication
+pub fn router(server: McpServer) -> axum::Router {
+    reloadable_router(watch::channel(server).1)
+}
+
+/// The `/mcp` endpoint of whichever server `servers` holds. Sessions opened after it
+/// changes are served, and requests authenticated, by the new server; open sessions
+/// keep the one they started with.
+pub fn reloadable_router(servers: watch::Receiver<McpServer>) -> axum::Router {
+    let auth = servers.clone();
+    // Every MCP session gets its own handler, and its state goes with it
+    let service = StreamableHttpService::new(
+        move || Ok(servers.borrow().open_session()),
+        LocalSessionManager::default().into(),
+        StreamableHttpServerConfig::default(),
+    );
+    axum::Router::new()
+        .nest_service("/mcp", service)
+        .layer(axum::middleware::from_fn_with_state(
+            auth,
+            |State(servers): State<watch::Receiver<McpServer>>, request: Request, next: Next| {
+                let auth = servers.borrow().authenticator();
+                require_bearer(State(auth), request, next)
+            },
+        ))
+}
+
+/// Serve MCP over streamable HTTP on `listener` until `shutdown` resolves
+pub async fn serve_listener(
+    server: McpServer,
+    listener: LimitedListener,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    serve_reloadable(watch::channel(server).1, listener, shutdown).await
+}
+
+/// Serve MCP over streamable HTTP on `listener` until `shutdown` resolves, with
+/// new sessions going to the latest server in `servers`
+pub async fn serve_reloadable(
+    servers: watch::Receiver<McpServer>,
+    listener: LimitedListener,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    // Peer addresses identify clients for rate limiting when tokens are off
+    let app = reloadable_router(servers).into_make_service_with_connect_info::<PeerAddr>();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .context("HTTP server failed")
+}
@@ -0,0 +1,519 @@
+use rmcp::ErrorData as McpError;
+use serde::Serialize;
+use serde_json::json;
+
+/// Language of tool descriptions and server error messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+
+    /// Tool description in this locale; `None` means the tool's own English text applies
+    pub fn tool_description(&self, tool: &str) -> Option<&'static str> {
+        match self {
+            Locale::En => None,
+            Locale::Zh => ZH_TOOL_DESCRIPTIONS
+                .iter()
+                .find(|(name, _)| *name == tool)
+                .map(|(_, description)| *description),
+        }
+    }
+
+    /// Question asked before a call that policy `rule` holds for confirmation
+    pub fn policy_prompt(&self, tool: &str, rule: &str, reason: Option<&str>) -> String {
+        let template = match (self, reason) {
+            (Locale::En, Some(_)) => "Policy rule {} asks you to confirm this {} call: {}",
+            (Locale::En, None) => "Policy rule {} asks you to confirm this {} call",
+            (Locale::Zh, Some(_)) => "策略规则 {} 要求你确认此次 {} 调用：{}",
+            (Locale::Zh, None) => "策略规则 {} 要求你确认此次 {} 调用",
+        };
+        fill(template, &[rule, tool, reason.unwrap_or_default()])
+    }
+
+    /// Description listed for an alias of `tool`
+    pub fn alias_description(&self, tool: &str, description: &str) -> String {
+        let template = match self {
+            Locale::En => "Alias of {}. {}",
+            Locale::Zh => "{} 的别名。{}",
+        };
+        fill(template, &[tool, description])
+    }
+
+    pub fn text(&self, text: Text) -> &'static str {
+        match (self, text) {
+            (Locale::En, Text::Instructions) => "Ethereum Trading MCP Server - Provides tools for querying balances, getting token prices, and simulating swaps on Ethereum",
+            (Locale::Zh, Text::Instructions) => "以太坊交易 MCP 服务器 - 提供以太坊余额查询、代币价格获取和兑换模拟等工具",
+            (Locale::En, Text::MetricsDescription) => "In-flight, queued and rejected tool calls per concurrency category",
+            (Locale::Zh, Text::MetricsDescription) => "各并发类别中正在执行、排队和被拒绝的工具调用数",
+            (Locale::En, Text::CapabilitiesDescription) => "What this deployment allows: chain, venues, write access, limits, and the tools available to you with their call counts",
+            (Locale::Zh, Text::CapabilitiesDescription) => "此部署允许的操作：链、交易场所、写入权限、限制，以及你可用的工具及其调用次数",
+            (Locale::En, Text::PaymentRequestDescription) => "Payment request from create_payment_request: EIP-681 URI, QR payload, amount and expiry",
+            (Locale::Zh, Text::PaymentRequestDescription) => "create_payment_request 创建的收款请求：EIP-681 URI、二维码载荷、金额和过期时间",
+            (Locale::En, Text::WalletConnectDescription) => "WalletConnect pairing: the wc: URI to scan with your wallet, and whether a session for the server's account is approved",
+            (Locale::Zh, Text::WalletConnectDescription) => "WalletConnect 配对：用钱包扫描的 wc: URI，以及服务器账户的会话是否已获批准",
+            (Locale::En, Text::WatchEventsDescription) => "Recent transfers and balance changes of the addresses watched with watch_address, oldest first",
+            (Locale::Zh, Text::WatchEventsDescription) => "通过 watch_address 监控的地址最近的转账和余额变化，按时间从早到晚排列",
+            (Locale::En, Text::ConfirmField) => "Go ahead with the call",
+            (Locale::Zh, Text::ConfirmField) => "继续执行此调用",
+            (Locale::En, Text::OneTimeCodePrompt) => "This call sends a transaction. Enter the current code from your authenticator app to approve it",
+            (Locale::Zh, Text::OneTimeCodePrompt) => "此调用会发送交易。请输入身份验证器应用中的当前验证码以批准",
+            (Locale::En, Text::OneTimeCodeField) => "6-digit code from your authenticator app",
+            (Locale::Zh, Text::OneTimeCodeField) => "身份验证器应用中的 6 位验证码",
+        }
+    }
+
+    /// Build the error for `code`, filling the message's `{}` placeholders with `args`
+    /// in order. The code itself is attached as `data.code` in every locale.
+    pub fn error(&self, code: ErrorCode, args: &[&str]) -> McpError {
+        let message = fill(self.template(code), args);
+        // Calls refused for load also carry HTTP's Too Many Requests status, so clients
+        // can back off from them as from any 429
+        let data = Some(match code {
+            ErrorCode::Saturated | ErrorCode::RateLimited => {
+                json!({ "code": code.as_str(), "status": 429 })
+            }
+            _ => json!({ "code": code.as_str() }),
+        });
+        match code {
+            ErrorCode::UnknownTool | ErrorCode::InvalidParams => {
+                McpError::invalid_params(message, data)
+            }
+            ErrorCode::Unauthorized
+            | ErrorCode::ToolForbidden
+            | ErrorCode::ResourceForbidden
+            | ErrorCode::ConfirmationUnsupported
+            | ErrorCode::NotConfirmed
+            | ErrorCode::SecondFactorRequired
+            | ErrorCode::SecondFactorInvalid
+            | ErrorCode::PolicyDenied => McpError::invalid_request(message, data),
+            ErrorCode::ResourceNotFound => McpError::resource_not_found(message, data),
+            ErrorCode::Saturated
+            | ErrorCode::RateLimited
+            | ErrorCode::ToolFailed
+            | ErrorCode::ToolPanicked
+            | ErrorCode::SerializationFailed
+            | ErrorCode::AttestationFailed
+            | ErrorCode::ConfirmationFailed => McpError::internal_error(message, data),
+        }
+    }
+
+    fn template(&self, code: ErrorCode) -> &'static str {
+        match (self, code) {
+            (Locale::En, ErrorCode::UnknownTool) => "Unknown tool: {}",
+            (Locale::Zh, ErrorCode::UnknownTool) => "未知工具：{}",
+            (Locale::En, ErrorCode::InvalidParams) => "Invalid parameters for {}: {}",
+            (Locale::Zh, ErrorCode::InvalidParams) => "{} 的参数无效：{}",
+            (Locale::En, ErrorCode::Unauthorized) => "Missing or invalid bearer token",
+            (Locale::Zh, ErrorCode::Unauthorized) => "缺少或无效的 Bearer 令牌",
+            (Locale::En, ErrorCode::ToolForbidden) => "Tool {} requires the '{}' permission tier",
+            (Locale::Zh, ErrorCode::ToolForbidden) => "工具 {} 需要 '{}' 权限级别",
+            (Locale::En, ErrorCode::ResourceForbidden) => "{} requires the '{}' permission tier",
+            (Locale::Zh, ErrorCode::ResourceForbidden) => "资源 {} 需要 '{}' 权限级别",
+            (Locale::En, ErrorCode::ResourceNotFound) => "Unknown resource: {}",
+            (Locale::Zh, ErrorCode::ResourceNotFound) => "未知资源：{}",
+            (Locale::En, ErrorCode::Saturated) => {
+                "Tool category '{}' is saturated (limit {} running, {} queued); retry later"
+            }
+            (Locale::Zh, ErrorCode::Saturated) => {
+                "工具类别 '{}' 已满（上限 {} 个执行中，{} 个排队），请稍后重试"
+            }
+            (Locale::En, ErrorCode::RateLimited) => {
+                "Rate limit of {} tool calls per minute exceeded; retry in {}s"
+            }
+            (Locale::Zh, ErrorCode::RateLimited) => {
+                "已超过每分钟 {} 次工具调用的速率限制，请在 {} 秒后重试"
+            }
+            (Locale::En, ErrorCode::ToolFailed) => "Failed to execute {}: {}",
+            (Locale::Zh, ErrorCode::ToolFailed) => "执行 {} 失败：{}",
+            (Locale::En, ErrorCode::ToolPanicked) => "Tool {} hit an internal error: {}",
+            (Locale::Zh, ErrorCode::ToolPanicked) => "工具 {} 发生内部错误：{}",
+            (Locale::En, ErrorCode::SerializationFailed) => "Failed to serialize the response: {}",
+            (Locale::Zh, ErrorCode::SerializationFailed) => "响应序列化失败：{}",
+            (Locale::En, ErrorCode::AttestationFailed) => "Failed to sign the result: {}",
+            (Locale::Zh, ErrorCode::AttestationFailed) => "结果签名失败：{}",
+            (Locale::En, ErrorCode::ConfirmationUnsupported) => {
+                "{} This client cannot ask the user for confirmation, so the call was refused"
+            }
+            (Locale::Zh, ErrorCode::ConfirmationUnsupported) => {
+                "{} 此客户端无法请求用户确认，调用已被拒绝"
+            }
+            (Locale::En, ErrorCode::ConfirmationFailed) => "Confirmation failed: {}",
+            (Locale::Zh, ErrorCode::ConfirmationFailed) => "确认失败：{}",
+            (Locale::En, ErrorCode::NotConfirmed) => "The user did not confirm the call",
+            (Locale::Zh, ErrorCode::NotConfirmed) => "用户未确认此调用",
+            (Locale::En, ErrorCode::SecondFactorRequired) => {
+                "{} sends a transaction and needs the current code from the user's authenticator app; pass it as one_time_code"
+            }
+            (Locale::Zh, ErrorCode::SecondFactorRequired) => {
+                "{} 会发送交易，需要用户身份验证器应用中的当前验证码；请通过 one_time_code 传入"
+            }
+            (Locale::En, ErrorCode::SecondFactorInvalid) => {
+                "The one-time code is wrong, expired or already used"
+            }
+            (Locale::Zh, ErrorCode::SecondFactorInvalid) => "一次性验证码错误、已过期或已被使用",
+            (Locale::En, ErrorCode::PolicyDenied) => "{} was denied by policy rule {}{}",
+            (Locale::Zh, ErrorCode::PolicyDenied) => "{} 被策略规则 {} 拒绝{}",
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = anyhow::Error;
+
+    /// Accepts a bare language or a tag such as `zh-CN` or `en_US`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let language = s.trim().split(['-', '_']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "zh" => Ok(Locale::Zh),
+            _ => anyhow::bail!("Unsupported locale: {} (expected en or zh)", s),
+        }
+    }
+}
+
+/// Locale-independent identifier of a server error, sent as the error's `data.code`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    UnknownTool,
+    InvalidParams,
+    Unauthorized,
+    ToolForbidden,
+    ResourceForbidden,
+    ResourceNotFound,
+    Saturated,
+    RateLimited,
+    ToolFailed,
+    ToolPanicked,
+    SerializationFailed,
+    AttestationFailed,
+    ConfirmationUnsupported,
+    ConfirmationFailed,
+    NotConfirmed,
+    SecondFactorRequired,
+    SecondFactorInvalid,
+    PolicyDenied,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnknownTool => "unknown_tool",
+            ErrorCode::InvalidParams => "invalid_params",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::ToolForbidden => "tool_forbidden",
+            ErrorCode::ResourceForbidden => "resource_forbidden",
+            ErrorCode::ResourceNotFound => "resource_not_found",
+            ErrorCode::Saturated => "saturated",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::ToolFailed => "tool_failed",
+            ErrorCode::ToolPanicked => "tool_panicked",
+            ErrorCode::SerializationFailed => "serialization_failed",
+            ErrorCode::AttestationFailed => "attestation_failed",
+            ErrorCode::ConfirmationUnsupported => "confirmation_unsupported",
+            ErrorCode::ConfirmationFailed => "confirmation_failed",
+            ErrorCode::NotConfirmed => "not_confirmed",
+            ErrorCode::SecondFactorRequired => "second_factor_required",
+            ErrorCode::SecondFactorInvalid => "second_factor_invalid",
+            ErrorCode::PolicyDenied => "policy_denied",
+        }
+    }
+}
+
+/// Fixed server strings outside tool descriptions and errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    Instructions,
+    MetricsDescription,
+    CapabilitiesDescription,
+    PaymentRequestDescription,
+    WalletConnectDescription,
+    WatchEventsDescription,
+    ConfirmField,
+    OneTimeCodePrompt,
+    OneTimeCodeField,
+}
+
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut parts = template.split("{}");
+    let mut message = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        message.push_str(args.get(i).copied().unwrap_or_default());
+        message.push_str(part);
+    }
+    message
+}
+
+const ZH_TOOL_DESCRIPTIONS: &[(&str, &str)] = &[
+    (
+        "get_balance",
+        "查询指定钱包地址的 ETH 或代币余额。会检测并报告代币标准：ERC20 和 ERC777 以整币计的余额，ERC721 为持有的 NFT 数量，ERC1155 为所给 token_id 的余额。",
+    ),
+    (
+        "get_balance_all_chains",
+        "同时查询钱包在所有已配置链上的原生代币余额，并可选查询 ERC20 余额。各链并行查询；RPC 失败或超时的链会单独报告错误，其余链仍返回余额。",
+    ),
+    (
+        "get_portfolio",
+        "以美元估值钱包在所有已配置链上的资产：原生代币余额及所列 ERC20，每项都在其所在链的 Uniswap V2 部署上对当地美元稳定币定价。返回总值及按链的明细；无法读取或定价的链或资产会单独报告，不计入总值。同一资产的各种形式（如 ETH 与 WETH、USDC 与 USDC.e）会在 assets 中跨链合并。",
+    ),
+    (
+        "get_token_price",
+        "通过 Uniswap V2 获取代币当前的 USD 或 ETH 价格。没有直接 USDC（或 WETH）池子的代币会经由 WETH 或其他配置的中间代币定价，并返回所用路径。有 Chainlink 喂价的代币会与其交叉核对，偏离超过阈值的价格会被标记为异常（可能遭操纵或流动性不足）并附带警告。代币可按地址或符号指定（如 WETH、USDC、DAI、USDT、UNI、LINK、WBTC、AAVE、MKR、SNX）。设置 fiat_currency（如 EUR、GBP、JPY）可同时获得按 Chainlink 汇率换算的价格。已知代币会标明其代表的资产，以及是否为跨链桥映射的非规范版本。",
+    ),
+    (
+        "swap_tokens",
+        "在 Uniswap V2 上模拟代币兑换，返回预计输出和 gas 费用，不会执行交易。自动识别转账收费代币并改用相应的路由方法。路由合约授权不足时会包含 approve 步骤（按 approval_strategy 授权精确数量、数量加缓冲或无限额度），并合计整组交易的 gas；USDT 等代币会先将授权额度清零。涉及跨链桥映射代币（如 USDC.e）的兑换会附带警告并给出规范代币地址。设置 split: true 时，大额订单会按池子深度分散到该交易对的 Uniswap V2 和 SushiSwap 池子，仅在总输出高于单一池子时采用。include_alternatives 会列出排名靠前的候选路径及其 gas 和扣除 gas 后的输出。路径默认按扣除 gas 后的输出排名。gas 估算回滚时会返回解码后的回滚原因并标记 will_likely_revert: true，而不是静默使用默认值。",
+    ),
+    (
+        "compare_quotes",
+        "比较两份兑换报价，解释价格为何变化（例如规划与执行之间）。每一侧可以是 swap_tokens 返回的 quote_id（过期报价在 10 分钟内仍可读取），也可以是一组立即报价的 swap_tokens 参数。返回两份报价的对照，预计输出与最低输出、成交价与池子价格、价格影响、gas 估算、gas 价格和 gas 费用的变化，以及按影响排序的原因。",
+    ),
+    (
+        "get_twap_price",
+        "根据 Uniswap V3 池子自身的预言机观测值，获取其在一段时间窗口内的时间加权平均价格（TWAP）。与现货价格不同，它无法在单个区块内被操纵，因此在判断价格是否遭操纵时应优先以它为参考。返回 TWAP、当前现货价格以及现货相对 TWAP 的偏离。",
+    ),
+    (
+        "suggest_tx_timing",
+        "为非紧急的交易建议立即发送还是等待。读取近期 base fee 走势，以及前几天同一时段之后数小时内 base fee 的变化，给出 send_now、wait_for_spike（短暂飙升）或 wait（附 wait_hours）的建议，并按给定 gas 用量给出预计 base fee 和可节省的费用。规律来自历史，并非保证。",
+    ),
+    (
+        "recommend_slippage",
+        "根据交易对近期的价格波动率以及交易规模相对池子流动性的比例，为 Uniswap V2 兑换推荐滑点容忍度。",
+    ),
+    (
+        "get_nft_floor_price",
+        "从 NFT 市场 API 获取 NFT 系列的地板价、最高系列出价和 24 小时交易量。传入 owner 时还会统计其在该系列中持有的 ERC721 代币数量并按地板价估值，用于投资组合估值。",
+    ),
+    (
+        "get_option_chains",
+        "从所配置的期权交易所（Aevo 或 Lyra）读取标的资产的期权链：每个到期日在指数价格附近按行权价列出看涨和看跌期权的标记价格、买卖报价（如有）、隐含波动率和 Delta，以及平值隐含波动率和 25 Delta 看跌-看涨偏斜。可为对冲规模和定价提供参考。只读。",
+    ),
+    (
+        "get_perp_markets",
+        "列出所配置永续合约交易所（Hyperliquid 或 GMX）上的永续合约市场：标记价格和指数价格、每小时资金费率及其年化值、未平仓量、24 小时交易量和最大杠杆，按未平仓量从大到小排序。只读。",
+    ),
+    (
+        "get_perp_positions",
+        "列出钱包（或其他账户）在所配置永续合约交易所（Hyperliquid 或 GMX）上的未平仓永续合约头寸：方向、规模、开仓价和标记价格、未实现盈亏、保证金、杠杆和强平价格，并汇总总敞口、净敞口和权益。只读。",
+    ),
+    (
+        "get_funding_rates",
+        "比较主要资产在所配置永续合约交易所（Hyperliquid、GMX）上的资金费率：每小时及年化资金费率、交易所间的费率差，以及相对 Chainlink 现货价（或交易所指数价）的期现基差。传入提醒阈值时，资金费率年化值或基差在两次调用之间越过阈值会触发提醒，提醒随结果返回并推送给已连接的客户端。可重复调用进行轮询。",
+    ),
+    (
+        "get_market_stats",
+        "获取交易对的市场统计：可配置窗口内的已实现波动率，基于 Uniswap V2 Swap 事件的 24 小时成交量和平均交易规模，以及 Uniswap V2/V3 价差。",
+    ),
+    (
+        "get_dex_stats",
+        "所配置 Uniswap V2/V3 池子的 24 小时成交量、交易笔数、手续费和 TVL，直接由链上 Swap 事件和储备量计算（不依赖外部 API），并按 Chainlink 价格估值。数据在后台定期刷新，因此可即时返回；age_secs 表示数据的新旧程度。只读。",
+    ),
+    (
+        "scan_arbitrage",
+        "比较代币在 Uniswap V2、Uniswap V3、SushiSwap 和 Curve 上的卖出价格，报告扣除预计 gas 后价差仍超过阈值的交易场所组合。仅做只读分析，不会发送任何交易。",
+    ),
+    (
+        "check_token_locks",
+        "检查锁仓和归属合约（Unicrypt V2 流动性锁、Team Finance 代币锁和 Sablier V2 线性流）中某代币的锁仓或某钱包的锁定仓位。报告每个锁的存入、已提取、仍锁定和可领取数量及其解锁时间或归属区间，并按代币给出锁定总量、占总供应量的比例以及即将到来的解锁计划。Unicrypt V2 锁定的是 Uniswap V2 LP 代币，查看代币的流动性锁请传入交易对地址。",
+    ),
+    (
+        "execute_plan",
+        "逐步执行计划（按顺序的 approve/wrap/swap/transfer/transfer_nft/batch_transfer/create_stream/cancel_stream/vote/delegate/renew_ens/cancel/speed_up 步骤，由 swap_tokens、diagnose_wallet 等规划工具返回）。默认试运行：只估算每一步的 gas 而不发送。实际执行在第一个失败步骤处停止，并报告应从哪一步恢复。由已配置的转发合约或 paymaster 支付 gas 的步骤标记为 gas_sponsored，已发送的步骤会报告赞助详情。向钱包从未付款过的收款方转账会被标记，向已知诈骗地址的转账或授权会被拒绝。",
+    ),
+    (
+        "connect_wallet",
+        "通过 WalletConnect v2 配对用户的手机钱包。返回用于在钱包中扫描的配对 URI（以及相同文本的二维码载荷），以及服务器账户的会话是否已获批准。配对后，服务器发送的每笔交易都在手机上批准或拒绝，服务器不持有任何私钥。再次调用可查看会话状态，传入 new_pairing 可重新配对。",
+    ),
+    (
+        "transfer_nft",
+        "用 safeTransferFrom 发送 ERC721 或 ERC1155 NFT。通过 ERC-165 识别标准，先检查钱包的所有权（或 ERC1155 余额），并在构建交易前模拟转账，从而发现无法接收 NFT 的收款方。默认试运行；实际执行遵循 execute_plan 的 gas 预留、收款方和诈骗地址检查。",
+    ),
+    (
+        "batch_transfer",
+        "通过 Disperse 合约在一笔交易中向多个收款方发送 ETH 或 ERC20，代币授权不足时先执行 approve。构建前逐项校验：收款方不得重复或为零地址，金额须为正且不超过代币精度，遵守服务器的收款方数量与单个收款方 ETH 上限，且总额不超过钱包余额。默认试运行；实际发送沿用 execute_plan 的 gas 储备与收款方检查。",
+    ),
+    (
+        "get_restaking_positions",
+        "报告钱包（或其他质押者）在 EigenLayer 的再质押情况：各策略中的存款及其份额可赎回的代币数量、因罚没损失的份额、EigenPod 中原生再质押的 ETH、委托的运营者，以及排队中的提款和每笔可完成的区块。",
+    ),
+    (
+        "list_streams",
+        "列出钱包接收或发送的活跃支付流：Sablier V2 线性流（存入、已流出、可提取和剩余数量及归属区间）以及 Superfluid 恒定流（每日流速）。默认查询服务器钱包的双向支付流；可用 cancel_stream 取消发出的 Sablier 流。",
+    ),
+    (
+        "create_stream",
+        "通过 Sablier V2 向收款方流式支付 ERC20：金额锁入 LockupLinear 流，自上链起在持续时间内线性释放，可选的悬崖期之前不可提取。合约授权不足时先执行 approve。可取消的流可用 cancel_stream 停止并退还尚未流出的部分。默认试运行；实际执行沿用 execute_plan 的 gas 储备与收款方检查。",
+    ),
+    (
+        "cancel_stream",
+        "按 list_streams 报告的 stream_id 取消钱包发出的可取消 Sablier V2 流。已流出的部分仍归收款方提取，其余退还给钱包。默认试运行；若钱包不是发送方或该流不可取消，估算会失败。",
+    ),
+    (
+        "get_governance_proposals",
+        "列出 DAO 的最新提案（从新到旧）：来自链上 Governor（OpenZeppelin Governor 或 Compound GovernorBravo，可传地址或 uniswap、compound、ens 等已知名称）或 Snapshot 空间（如 'aave.eth'）。每个提案包含状态、投票时间窗口、各选项得票以及描述开头。可用 cast_vote 对 Governor 提案投票。",
+    ),
+    (
+        "get_voting_power",
+        "显示钱包在 DAO 中的投票权（当前或某提案快照时）：链上 Governor 给出委托票数、代币余额、当前委托对象以及是否已对该提案投票；Snapshot 空间给出按空间策略计算的投票权。默认查询服务器钱包，并提示因从未委托而没有投票权的代币。",
+    ),
+    (
+        "cast_vote",
+        "用服务器钱包对链上 Governor 提案投赞成、反对或弃权票，可附理由。若钱包已投票或在提案快照时没有投票权则拒绝。Snapshot 投票为链下签名，不受支持。默认试运行；实际投票需服务器启用执行。",
+    ),
+    (
+        "get_delegation",
+        "显示钱包的 ERC20Votes 治理代币（UNI、ENS、COMP 等）的委托情况：未委托（没有投票权）、委托给自己或委托给其他地址，并给出余额和委托给它的票数。默认查询服务器钱包；可用 delegate_votes 修改委托。",
+    ),
+    (
+        "delegate_votes",
+        "把服务器钱包在 ERC20Votes 治理代币上的投票权委托给某个地址、联系人或 'self'（自己直接投票）。委托转移的是票数而非代币，也涵盖之后收到的代币；只对快照晚于委托的提案有效。若已委托给该对象则拒绝。默认试运行；实际执行需服务器启用执行。",
+    ),
+    (
+        "get_ens_name",
+        "查询 ENS 名称：所有者、注册人、解析器和解析到的地址（链下解析的名称，如通配子名称，会通过 CCIP-Read 获取）；对 .eth 名称还给出注册到期时间及其后 90 天的宽限期。状态为 active、grace_period（已过期但仍可由任何人续费）或 available。30 天内到期的名称会被标记。",
+    ),
+    (
+        "list_ens_names",
+        "列出钱包持有的 ENS 名称（.eth 注册和封装的名称），每个名称附状态和到期时间，按到期先后排序，并给出钱包的主名称。无法从注册事件恢复标签的名称显示为 [labelhash].eth。默认查询服务器钱包。",
+    ),
+    (
+        "renew_ens_name",
+        "用服务器钱包向 ENS 注册控制器付费，延长 .eth 名称的注册期。任何名称都可续费（包括处于宽限期的名称），不论其所有者；子名称没有可续的注册。发送的 ETH 比当前租金多 5% 的缓冲，未用部分由控制器退还。默认试运行；实际执行需服务器启用执行。",
+    ),
+    (
+        "execute_swap",
+        "按 quote_id 执行先前由 swap_tokens 模拟的兑换。报价在短时间后过期。执行前会重新报价：若输出变化超出服务器设定的范围则拒绝，否则按新报价更新最低输出。卖出量占池子比例超过服务器上限的兑换会被拒绝或需要用户确认。实际执行的兑换会记录到 get_execution_report 中。默认试运行。",
+    ),
+    (
+        "diagnose_wallet",
+        "诊断钱包的待处理交易：最新与待处理 nonce、在内存池中等待的交易（节点支持时）、gas 价格过低或因 nonce 空缺而阻塞的交易，以及附带 execute_plan 计划的加速/取消建议。",
+    ),
+    (
+        "get_pool_liquidity_profile",
+        "展示 Uniswap V3 池子在当前价格附近各 tick 的流动性分布：恒定流动性区间及其持有的代币数量，以及使价格变动 0.5-10% 需要兑换的数量。可用于估算大额交易的滑点。",
+    ),
+    (
+        "monitor_new_pairs",
+        "报告自上次调用以来在 Uniswap V2、SushiSwap 和 Uniswap V3 上新建的、将新代币与受监控基础代币（默认 WETH、USDC、USDT、DAI）配对的池子。每个新代币都会自动筛查（合约代码、可读的 ERC20 元数据、池子流动性），并给出 ok、caution 或 danger 风险评级。重复调用即可轮询，每次从上次停止处继续。",
+    ),
+    (
+        "monitor_token_risks",
+        "监控钱包所持代币自上次调用以来的跑路信号：其 Uniswap V2/SushiSwap 交易对的大额流动性撤出、新增铸币、所有权转移或放弃，以及黑名单更新。告警会在结果中返回，并以 MCP 日志通知推送给已连接的客户端。重复调用即可轮询，每次从上次停止处继续。",
+    ),
+    (
+        "compare_yields",
+        "比较代币（通常是稳定币）在 DAI 储蓄利率（sDAI）、Aave V3、Compound V3 和 ERC-4626 金库中的当前链上存款收益，按持有期内扣除预计存入和取出 gas 后的收益排序。",
+    ),
+    (
+        "compare_lsts",
+        "比较流动性质押代币（stETH、rETH、cbETH、frxETH）：每个代币可赎回的 ETH、根据最近一周链上汇率增长计算的质押 APR，以及其 DEX 市场情况：最佳 WETH 兑换率、相对赎回汇率的溢价或折价，以及卖出指定数量时的价格影响。按 APR 排序。",
+    ),
+    (
+        "build_transaction",
+        "为计划（如来自 swap_tokens）构建未签名的 EIP-1559 交易，供离线或物理隔离设备签名。每一步都会填入 nonce、gas 和费用，并给出序列化的未签名交易、签名哈希以及大写十六进制二维码载荷（过长时分帧）。签名结果可通过 import_signed_transaction 广播。",
+    ),
+    (
+        "detect_interfaces",
+        "探测合约实现了哪些接口：ERC-165、ERC-20、ERC-721、ERC-1155、ERC-777、ERC-4626 和 ERC-2612 permit，是否为 Ownable（以及所有者），以及是否为代理合约（EIP-1967、信标、EIP-1822、旧版 OpenZeppelin）或 EIP-1167 克隆。适合在调用未知合约前进行初步分析。",
+    ),
+    (
+        "preview_transfer",
+        "用 eth_call 和状态覆盖模拟一笔 ERC20 转账，在发送前查看扣除转账税、销毁或钩子后收款方实际能收到多少。发送方余额不足时同样可用。不发送任何交易。",
+    ),
+    (
+        "simulate_bundle",
+        "在最新区块之上按顺序模拟一组交易或一个计划的各个步骤，不发送任何交易。返回每笔交易是否成功、消耗的 gas 和触发的事件，以及所有相关账户的 ETH 和 ERC20 净余额变化，是在执行前向用户清楚展示一组交易究竟会做什么的最佳方式。",
+    ),
+    (
+        "watch_address",
+        "监控任意地址（不限于服务器自己的钱包）的 ERC20 转入、转出和 ETH 余额变化。服务器在后台轮询被监控的地址，将每个事件作为 MCP 日志通知推送给已连接的客户端，并在 watch://events 资源中保留最近事件的记录。操作：add（可附带标签）、remove、list，以及 events，用于读取某个事件 id 之后的记录。",
+    ),
+    (
+        "analyze_gas_usage",
+        "报告钱包在一段时间内的 gas 花费去向：按合约和方法汇总其发送的交易，列出次数、gas 用量、手续费和失败次数，花费最多的排在前面。并给出带粗略估算的节省建议：合并重复的付款、一次授权代替反复授权、将高成本兑换改走更便宜的交易场所、发送前先模拟以避免回滚，以及将活动迁移到 L2。",
+    ),
+    (
+        "get_validator_info",
+        "通过配置的信标节点，按索引或公钥查询信标链验证者：状态、余额、有效余额、是否被罚没、激活和退出 epoch，以及提款地址和它是否为服务器钱包。APR 由最近 apr_days 天的余额变化加上期间划转到提款地址的提款估算。",
+    ),
+    (
+        "list_withdrawals",
+        "列出最近若干天内划入服务器钱包（或其他地址）的信标链提款，按时间从新到旧排列，并给出每个验证者的提款总额和合计。部分提款会划走超过 32 ETH 的奖励；全额提款会返还已退出验证者的余额。",
+    ),
+    (
+        "get_execution_report",
+        "报告 execute_swap 发送的兑换实际执行得如何：实际收到的数量与报价以及发送前重新报价的对比（以基点计的实际滑点）；若有其他账户在同一区块内紧挨着该兑换前后交易同一池子，则标记为疑似 MEV（三明治攻击）；以及实际消耗和支付的 gas 与估算的对比。返回最近的执行记录、汇总统计和可用于调整后续交易的观察结论。",
+    ),
+    (
+        "health_check",
+        "检查服务器是否正常运行：节点是否响应及其最新区块，以及每个后台任务（诈骗地址列表刷新、地址监控、定时付款、WebSocket 区块订阅）的状态，包括崩溃后被重启的次数和最近一次停止的原因。配置了 WebSocket 端点时，还会报告其是否已连接以及重连次数。状态为 ok；有任务正在重启或已被放弃时为 degraded；无法连接节点时为 unavailable。",
+    ),
+    (
+        "generate_tax_report",
+        "按日历年（UTC）生成服务器所执行兑换的税务批次报告，格式参照美国国税局 8949 表：每次处置按所用批次各占一行，列出取得日期、出售日期、收入、成本基础、盈亏，以及持有是否超过一年。批次可按 FIFO、LIFO 或 HIFO 匹配（默认使用服务器设置的方法）。交易以其稳定币一方计价，否则按记录时的预言机价格折算为美元；美元稳定币视同现金。账本开始前买入的数量没有成本基础，标记为 UNKNOWN。默认返回 CSV。不构成税务建议。",
+    ),
+    (
+        "call_contract",
+        "用 eth_call 读取任意合约：传入函数签名（如 'balanceOf(address) returns (uint256)'）和参数以得到解码后的返回值，或传入原始 calldata。状态覆盖可在不分叉的情况下回答假设性问题：覆盖任意账户的余额、nonce、代码或存储，或按数量设定代币余额，由工具自动查找其存储槽。会跟随链下查询（EIP-3668 CCIP-Read）。代理合约（EIP-1967、信标、UUPS 和克隆）会解析到其实现合约，同时报告代理和实现地址，并检查实现中是否存在所调用的函数；原始 calldata 会按选择器命名。不发送任何交易。",
+    ),
+    (
+        "read_storage",
+        "读取任意合约（无论是否已验证）的原始存储字。可直接给出槽位，或给出基础槽位加映射键（Solidity 或 Vyper 布局）、数组下标和字段偏移，由工具算出最终槽位；count 可读取连续槽位。每个字会以十六进制、整数以及（适用时）地址形式显示。每次读取都会检查合约的代理槽位（EIP-1967 实现、管理员和信标、EIP-1822、旧版 OpenZeppelin），以揭示代理背后的实现合约。",
+    ),
+    (
+        "inspect_bytecode",
+        "检查合约已部署的字节码（无论是否已验证）：提取其分发器检查的函数选择器并通过 4byte 目录命名，标记 SELFDESTRUCT、DELEGATECALL 和 CALLCODE，并列出让所有者能控制代币持有人的函数（增发、暂停、黑名单、修改费用、升级）。会检测代理和最小克隆合约，并一并检查其实现合约。可选返回反汇编结果。",
+    ),
+    (
+        "import_signed_transaction",
+        "解码在离线设备上签名的交易（十六进制或其二维码分帧），并可选择广播。报告恢复出的签名者、nonce 和收款方；拒绝为其他链签名的交易。",
+    ),
+    (
+        "create_payment_request",
+        "为一定数量的 ETH 或 ERC20 创建收款请求，收款方默认为服务器钱包。返回 EIP-681 支付 URI、供钱包扫描的同一文本二维码载荷，以及保存该请求的 payment-request:// MCP 资源。将其 request_id 传给 check_payment 即可查看是否已付款。",
+    ),
+    (
+        "check_payment",
+        "检查 create_payment_request 创建的请求是否已付款。代币付款通过请求创建以来代币转给收款方的 Transfer 日志匹配；ETH 付款不产生日志，通过收款方余额的增长检测。设置 wait_secs 可持续等待付款到账。",
+    ),
+    (
+        "schedule_payment",
+        "安排 ETH 或 ERC20 的定期转账，如每周工资或每月订阅。服务器在每次到期时发送，直到达到计划的 max_runs、max_total 或 end_at 限制，并审计记录每次执行。返回计划及其接下来的执行时间。需要服务器启用实际执行；可用 list_scheduled_payments、pause_scheduled_payment 和 resume_scheduled_payment 管理计划。",
+    ),
+    (
+        "list_scheduled_payments",
+        "列出定期付款计划：状态、限制、已付总额和接下来的执行时间，以及最近执行的审计记录（已发送或失败，附交易哈希和错误）。",
+    ),
+    (
+        "pause_scheduled_payment",
+        "暂停定期付款计划，在通过 resume_scheduled_payment 恢复之前不再发送。",
+    ),
+    (
+        "resume_scheduled_payment",
+        "恢复已暂停的定期付款计划。暂停期间到期的执行会被跳过，不会补付；结果显示接下来的执行时间。",
+    ),
+    (
+        "add_contact",
+        "在地址簿中保存一个具名地址。凡需要钱包或收款地址的地方都可以使用联系人名称，无需重复输入地址。",
+    ),
+    (
+        "list_contacts",
+        "列出地址簿：每个联系人的名称、地址和备注。",
+    ),
+    (
+        "find_pools",
+        "在 Uniswap V2、SushiSwap 和 Uniswap V3 中查找某个代币或代币对的现有池子。返回每个池子的地址、费率档位、创建区块以及当前储备（V2）或区间内流动性（V3）。",
+    ),
+];
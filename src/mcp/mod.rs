@@ -1,3 +1,34 @@
+pub mod attestation;
+pub mod auth;
+pub mod capabilities;
+pub mod chat;
+pub mod concurrency;
+pub mod http;
+pub mod messages;
+pub mod notifier;
+pub mod policy;
+pub mod rate_limit;
+pub mod response_limit;
+pub mod second_factor;
+pub mod sessions;
+pub mod usage;
+pub mod validation;
+pub mod webhooks;
 mod server;
 
+#[cfg(test)]
+mod tests;
+
+pub use attestation::{Attestation, Attestor};
+pub use auth::Authenticator;
+pub use capabilities::{Capabilities, Deployment};
+pub use chat::{ChatBot, ChatService, ChatSink};
+pub use concurrency::ConcurrencyLimiter;
+pub use messages::{ErrorCode, Locale};
+pub use notifier::Notifier;
+pub use policy::{Policy, PolicyDecision, PolicyEngine, PolicyOutcome};
+pub use rate_limit::{RateLimit, RateLimiter};
+pub use second_factor::Totp;
 pub use server::McpServer;
+pub use sessions::{Session, SessionManager};
+pub use webhooks::Webhooks;
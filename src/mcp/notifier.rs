@@ -0,0 +1,65 @@
+use crate::tools::{AlertSeverity, AlertSink};
+use async_trait::async_trait;
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::service::Peer;
+use rmcp::RoleServer;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Pushes alerts to every connected client as MCP logging notifications
+#[derive(Clone, Default)]
+pub struct Notifier {
+    peers: Arc<Mutex<Vec<Peer<RoleServer>>>>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start delivering to a client once it has initialized
+    pub fn register(&self, peer: Peer<RoleServer>) {
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers.retain(|peer| !peer.is_transport_closed());
+        peers.push(peer);
+    }
+
+    pub fn connected(&self) -> usize {
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers
+            .iter()
+            .filter(|peer| !peer.is_transport_closed())
+            .count()
+    }
+}
+
+#[async_trait]
+impl AlertSink for Notifier {
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+        let peers: Vec<Peer<RoleServer>> = {
+            let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+            peers.retain(|peer| !peer.is_transport_closed());
+            peers.clone()
+        };
+        let level = match severity {
+            AlertSeverity::Info => LoggingLevel::Info,
+            AlertSeverity::Warning => LoggingLevel::Warning,
+            AlertSeverity::Critical => LoggingLevel::Critical,
+        };
+
+        let mut delivered = 0;
+        for peer in peers {
+            let notification = LoggingMessageNotificationParam {
+                level,
+                logger: Some(topic.to_string()),
+                data: alert.clone(),
+            };
+            match peer.notify_logging_message(notification).await {
+                Ok(()) => delivered += 1,
+                Err(e) => warn!("Failed to deliver {} notification: {}", topic, e),
+            }
+        }
+        delivered
+    }
+}
@@ -0,0 +1,348 @@
+use crate::ethereum::{ChainPricing, EthereumClientTrait};
+use crate::tools::address_book::{resolve_address, AddressBook};
+use crate::tools::get_portfolio::price_in_usd;
+use crate::tools::{Notional, PermissionTier, Tool};
+use anyhow::{Context, Result};
+use ethers::types::Address;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parameters naming the tokens a call touches, at any depth (plan steps included)
+const TOKEN_KEYS: &[&str] = &[
+    "token",
+    "from_token",
+    "to_token",
+    "token_in",
+    "token_out",
+    "collection",
+];
+/// Parameters naming who a call pays or hands power to
+const RECIPIENT_KEYS: &[&str] = &["to", "recipient", "delegatee"];
+
+/// What happens to a call a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyOutcome {
+    #[default]
+    Allow,
+    Deny,
+    /// Run only once the user approves it through elicitation
+    Confirm,
+}
+
+impl PolicyOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyOutcome::Allow => "allow",
+            PolicyOutcome::Deny => "deny",
+            PolicyOutcome::Confirm => "confirm",
+        }
+    }
+}
+
+/// Minutes past midnight UTC a rule applies between, as `"HH:MM-HH:MM"`. The end is
+/// exclusive and may be earlier than the start for windows across midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HourWindow {
+    start: u32,
+    end: u32,
+}
+
+impl HourWindow {
+    fn contains(&self, minute_of_day: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start || minute_of_day < self.end
+        }
+    }
+}
+
+impl std::str::FromStr for HourWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let minutes = |time: &str| -> Result<u32> {
+            let (hours, minutes) = time.trim().split_once(':').unwrap_or((time.trim(), "0"));
+            let (hours, minutes): (u32, u32) = (hours.parse()?, minutes.parse()?);
+            if hours > 24 || minutes > 59 || hours * 60 + minutes > 24 * 60 {
+                anyhow::bail!("out of range");
+            }
+            Ok(hours * 60 + minutes)
+        };
+        let (start, end) = s
+            .split_once('-')
+            .with_context(|| format!("Invalid hours '{}', expected HH:MM-HH:MM", s))?;
+        Ok(Self {
+            start: minutes(start).with_context(|| format!("Invalid start time in '{}'", s))?,
+            end: minutes(end).with_context(|| format!("Invalid end time in '{}'", s))?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for HourWindow {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// One declarative rule. Every condition given must hold for the rule to match;
+/// conditions left out match any call.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    /// Logged with every decision the rule makes
+    pub id: String,
+    /// Caller tiers the rule applies to
+    #[serde(default)]
+    pub roles: Vec<PermissionTier>,
+    /// Tool names; a trailing `*` matches a prefix, as in `execute_*`
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Token addresses; matches calls touching any of them
+    #[serde(default)]
+    pub tokens: Vec<Address>,
+    /// Addresses or contact names; matches calls paying any of them
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// Matches calls worth at least this many USD
+    #[serde(default)]
+    pub min_notional_usd: Option<Decimal>,
+    /// Matches calls worth less than this many USD
+    #[serde(default)]
+    pub max_notional_usd: Option<Decimal>,
+    /// UTC time of day, as `"22:00-06:00"`
+    #[serde(default)]
+    pub hours: Option<HourWindow>,
+    pub outcome: PolicyOutcome,
+    /// Shown to the caller when the rule denies or asks for confirmation
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl PolicyRule {
+    fn bounds_notional(&self) -> bool {
+        self.min_notional_usd.is_some() || self.max_notional_usd.is_some()
+    }
+
+    fn matches_tool(&self, name: &str) -> bool {
+        self.tools.is_empty()
+            || self
+                .tools
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == pattern,
+                })
+    }
+
+    fn matches_notional(&self, usd: Decimal) -> bool {
+        self.min_notional_usd.is_none_or(|min| usd >= min)
+            && self.max_notional_usd.is_none_or(|max| usd < max)
+    }
+}
+
+/// Rules in the order they are tried; the first match decides
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Outcome for calls no rule matches
+    #[serde(default)]
+    pub default: PolicyOutcome,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let policy: Policy = serde_yaml::from_str(yaml).context("Invalid policy")?;
+        let mut ids = std::collections::HashSet::new();
+        for rule in &policy.rules {
+            if !ids.insert(rule.id.as_str()) {
+                anyhow::bail!("Policy rule id '{}' is used twice", rule.id);
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy {}", path.display()))?;
+        Self::from_yaml(&yaml).with_context(|| format!("Invalid policy {}", path.display()))
+    }
+}
+
+/// How a call was judged, and by which rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyDecision {
+    pub outcome: PolicyOutcome,
+    /// `None` when no rule matched and the default applied
+    pub rule_id: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// What a call touches, as far as rules can see before it runs
+#[derive(Debug, Default)]
+struct CallFacts {
+    tokens: Vec<Address>,
+    recipients: Vec<Address>,
+}
+
+impl CallFacts {
+    fn collect(&mut self, book: Option<&AddressBook>, value: &Value) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    if let (Value::Array(path), "path") = (field, key.as_str()) {
+                        let hops = path.iter().filter_map(Value::as_str);
+                        self.tokens
+                            .extend(hops.filter_map(|hop| hop.trim().parse::<Address>().ok()));
+                    }
+                    if let Value::String(text) = field {
+                        if TOKEN_KEYS.contains(&key.as_str()) {
+                            self.tokens.extend(text.trim().parse::<Address>().ok());
+                        } else if RECIPIENT_KEYS.contains(&key.as_str()) {
+                            self.recipients.extend(resolve_address(book, text).ok());
+                        }
+                    }
+                    self.collect(book, field);
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.collect(book, item)),
+            _ => {}
+        }
+    }
+}
+
+/// Evaluates every tool call against a `Policy`.
+///
+/// A rule bounding notional value matches a call whose value cannot be worked out
+/// (no pricing, no liquidity, or a tool that cannot tell its amount up front) only
+/// when that call sends a transaction, so unpriced sends meet the stricter rule.
+pub struct PolicyEngine {
+    policy: Policy,
+    book: Option<Arc<AddressBook>>,
+    /// Reads token decimals and prices notional values
+    pricing: Option<(Arc<dyn EthereumClientTrait>, ChainPricing)>,
+}
+
+impl PolicyEngine {
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            book: None,
+            pricing: None,
+        }
+    }
+
+    /// Resolve contact names among rule and call recipients
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Price notional values through `pricing`, on `client`'s chain
+    pub fn with_pricing(
+        mut self,
+        client: Arc<dyn EthereumClientTrait>,
+        pricing: ChainPricing,
+    ) -> Self {
+        self.pricing = Some((client, pricing));
+        self
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.policy.rules.len()
+    }
+
+    pub async fn evaluate(
+        &self,
+        tier: PermissionTier,
+        tool: &dyn Tool,
+        params: &Value,
+    ) -> PolicyDecision {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.evaluate_at(tier, tool, params, now).await
+    }
+
+    /// Judge a call made at `unix_secs`
+    pub async fn evaluate_at(
+        &self,
+        tier: PermissionTier,
+        tool: &dyn Tool,
+        params: &Value,
+        unix_secs: u64,
+    ) -> PolicyDecision {
+        let book = self.book.as_deref();
+        let mut facts = CallFacts::default();
+        facts.collect(book, params);
+        let minute_of_day = ((unix_secs % 86_400) / 60) as u32;
+        // Priced once, and only when a rule asks
+        let mut notional_usd: Option<Option<Decimal>> = None;
+
+        for rule in &self.policy.rules {
+            let matched = (rule.roles.is_empty() || rule.roles.contains(&tier))
+                && rule.matches_tool(tool.name())
+                && rule.hours.is_none_or(|hours| hours.contains(minute_of_day))
+                && (rule.tokens.is_empty()
+                    || rule.tokens.iter().any(|token| facts.tokens.contains(token)))
+                && (rule.recipients.is_empty()
+                    || rule.recipients.iter().any(|recipient| {
+                        resolve_address(book, recipient)
+                            .is_ok_and(|address| facts.recipients.contains(&address))
+                    }));
+            if !matched {
+                continue;
+            }
+            if rule.bounds_notional() {
+                if notional_usd.is_none() {
+                    notional_usd = Some(match tool.notional(params) {
+                        Some(notional) => self.usd_value(notional).await,
+                        None => None,
+                    });
+                }
+                let in_bounds = match notional_usd.flatten() {
+                    Some(usd) => rule.matches_notional(usd),
+                    None => tool.moves_funds(params),
+                };
+                if !in_bounds {
+                    continue;
+                }
+            }
+            return PolicyDecision {
+                outcome: rule.outcome,
+                rule_id: Some(rule.id.clone()),
+                reason: rule.reason.clone(),
+            };
+        }
+        PolicyDecision {
+            outcome: self.policy.default,
+            rule_id: None,
+            reason: None,
+        }
+    }
+
+    async fn usd_value(&self, notional: Notional) -> Option<Decimal> {
+        let (client, pricing) = self.pricing.as_ref()?;
+        let (token, decimals) = match notional.token {
+            Some(token) => (token, client.get_token_decimals(token).await.ok()?),
+            None => (pricing.wrapped_native, 18),
+        };
+        let native_usd = price_in_usd(pricing, pricing.wrapped_native, 18, None)
+            .await
+            .ok();
+        let price = price_in_usd(pricing, token, decimals, native_usd)
+            .await
+            .ok()?;
+        Some(notional.amount * price)
+    }
+}
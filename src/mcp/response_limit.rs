@@ -0,0 +1,97 @@
+use serde_json::{json, Map, Value};
+
+/// Default cap on a serialized tool result, in bytes
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+fn serialized_len<T: serde::Serialize>(value: &T) -> usize {
+    serde_json::to_string_pretty(value)
+        .map(|s| s.len())
+        .unwrap_or(usize::MAX)
+}
+
+/// Shrink a tool result so its serialized form fits in `max_bytes`.
+///
+/// Top-level arrays (or array fields of a top-level object) are trimmed, largest first,
+/// and the result is annotated with `truncated: true` plus per-field counts so the agent
+/// knows to page. Results that cannot be trimmed are replaced by a size report.
+pub fn enforce_response_limit(value: Value, max_bytes: usize) -> Value {
+    let size = serialized_len(&value);
+    if size <= max_bytes {
+        return value;
+    }
+
+    let trimmed = match value {
+        Value::Array(items) => {
+            let total = items.len();
+            let mut wrapper = Map::new();
+            wrapper.insert("items".to_string(), Value::Array(items));
+            trim_arrays(wrapper, max_bytes).map(|mut obj| {
+                obj.insert("total".to_string(), json!(total));
+                Value::Object(obj)
+            })
+        }
+        Value::Object(obj) => trim_arrays(obj, max_bytes).map(Value::Object),
+        _ => None,
+    };
+
+    trimmed.unwrap_or_else(|| {
+        json!({
+            "truncated": true,
+            "error": "Result exceeded the maximum response size and could not be trimmed; narrow the query or page through it",
+            "size_bytes": size,
+            "max_bytes": max_bytes,
+        })
+    })
+}
+
+fn trim_arrays(mut obj: Map<String, Value>, max_bytes: usize) -> Option<Map<String, Value>> {
+    let mut array_fields: Vec<(String, usize)> = obj
+        .iter()
+        .filter_map(|(key, v)| v.as_array().map(|a| (key.clone(), a.len())))
+        .collect();
+    array_fields.sort_by_key(|(_, len)| std::cmp::Reverse(*len));
+
+    obj.insert("truncated".to_string(), Value::Bool(true));
+    let mut report = Map::new();
+
+    for (field, total) in array_fields {
+        let full = obj.get_mut(&field)?.as_array_mut()?.split_off(0);
+
+        // Binary search the longest prefix that keeps the response in budget
+        let (mut low, mut high) = (0usize, total);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if keep_prefix(&mut obj, &mut report, &field, &full, mid) <= max_bytes {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        if keep_prefix(&mut obj, &mut report, &field, &full, low) <= max_bytes {
+            return Some(obj);
+        }
+    }
+
+    None
+}
+
+/// Keep the first `n` items of `field`, record the counts, and return the new size
+fn keep_prefix(
+    obj: &mut Map<String, Value>,
+    report: &mut Map<String, Value>,
+    field: &str,
+    full: &[Value],
+    n: usize,
+) -> usize {
+    obj.insert(field.to_string(), Value::Array(full[..n].to_vec()));
+    report.insert(
+        field.to_string(),
+        json!({ "returned": n, "total": full.len() }),
+    );
+    obj.insert(
+        "truncated_fields".to_string(),
+        Value::Object(report.clone()),
+    );
+    serialized_len(obj)
+}
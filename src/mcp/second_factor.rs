@@ -0,0 +1,146 @@
+use anyhow::Result;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha1::Sha1;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parameter a call that moves funds carries its one-time code in
+pub const ONE_TIME_CODE_PARAM: &str = "one_time_code";
+
+const TOTP_STEP_SECS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// Steps either side of now a code is accepted for, to absorb clock drift
+const TOTP_SKEW: u64 = 1;
+
+/// RFC 6238 one-time codes (HMAC-SHA1, 6 digits, 30 second steps), as authenticator
+/// apps generate them. A compromised prompt can ask for a transfer but cannot produce
+/// the code from the user's phone.
+pub struct Totp {
+    secret: Vec<u8>,
+    /// Step of the last accepted code; each code approves a single call
+    last_step: Mutex<u64>,
+}
+
+impl Totp {
+    /// `secret` is the base32 key the authenticator app was enrolled with
+    pub fn new(secret: &str) -> Result<Self> {
+        let normalized: String = secret
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        // The decode error can echo the secret, so it is dropped
+        let secret = BASE32_NOPAD
+            .decode(normalized.as_bytes())
+            .map_err(|_| anyhow::anyhow!("TOTP secret is not valid base32"))?;
+        if secret.len() < 10 {
+            anyhow::bail!("TOTP secret must be at least 80 bits (16 base32 characters)");
+        }
+        Ok(Self {
+            secret,
+            last_step: Mutex::new(0),
+        })
+    }
+
+    /// The code an authenticator app shows at `unix_secs`
+    pub fn code_at(&self, unix_secs: u64) -> String {
+        self.code_for_step(unix_secs / TOTP_STEP_SECS)
+    }
+
+    fn code_for_step(&self, step: u64) -> String {
+        let mut mac =
+            Hmac::<Sha1>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(&step.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+        // RFC 4226 dynamic truncation
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(TOTP_DIGITS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+
+    /// Check `code` against the current time
+    pub fn verify(&self, code: &str) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.verify_at(code, now)
+    }
+
+    /// Whether `code` is valid at `unix_secs` and newer than the last accepted one,
+    /// so a code seen once cannot be replayed
+    pub fn verify_at(&self, code: &str, unix_secs: u64) -> bool {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        if code.len() != TOTP_DIGITS as usize {
+            return false;
+        }
+        let now = unix_secs / TOTP_STEP_SECS;
+        let Some(step) = (now.saturating_sub(TOTP_SKEW)..=now + TOTP_SKEW)
+            .find(|step| constant_time_eq(&self.code_for_step(*step), &code))
+        else {
+            return false;
+        };
+        let Ok(mut last_step) = self.last_step.lock() else {
+            return false;
+        };
+        if step <= *last_step {
+            return false;
+        }
+        *last_step = step;
+        true
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Remove the one-time code from a call's parameters, so tools never see it
+pub fn take_one_time_code(params: &mut Value) -> Option<String> {
+    params
+        .as_object_mut()
+        .and_then(|params| params.remove(ONE_TIME_CODE_PARAM))
+        .and_then(|code| match code {
+            Value::String(code) => Some(code),
+            // Sent as a number, a code loses its leading zeros
+            Value::Number(code) => Some(format!(
+                "{:0>width$}",
+                code.to_string(),
+                width = TOTP_DIGITS as usize
+            )),
+            _ => None,
+        })
+}
+
+/// Add the one-time code parameter to a write tool's input schema
+pub fn with_one_time_code_property(mut schema: Value) -> Value {
+    if let Some(properties) = schema
+        .as_object_mut()
+        .map(|schema| schema.entry("properties").or_insert_with(|| json!({})))
+        .and_then(Value::as_object_mut)
+    {
+        properties.insert(
+            ONE_TIME_CODE_PARAM.to_string(),
+            json!({
+                "type": "string",
+                "description": "Current code from the user's authenticator app; calls that send transactions are refused without it. Ask the user for it rather than guessing; when omitted the client is asked to prompt the user"
+            }),
+        );
+    }
+    schema
+}
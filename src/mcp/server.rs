@@ -1,90 +1,1541 @@
 use crate::config::Config;
-use crate::ethereum::{EthereumClient, UniswapV2Router};
-use crate::tools::{GetBalanceTool, GetTokenPriceTool, SwapTokensTool, Tool as ToolTrait};
+use crate::ethereum::{
+    AaveV3Yield, AevoApi, BeaconApi, ChainClients, ChainIdMismatch, ChainPricing, ChainlinkFxRates,
+    ChainlinkPriceFeeds, CompoundV3Yield, CurveVenue, EigenLayerContracts, EnsContracts, EnsSource,
+    Erc4626Vault, EthereumClient, EthereumClientTrait, EtherscanHistory, FourByteDirectory,
+    GasSponsor, GovernanceSource, GovernorContracts, LiquidStakingToken, LockSource, LstContract,
+    LyraApi, NewHeads, OptionsVenue, OptionsVenueKind, PerpVenue, PoolActivity, PoolDiscovery,
+    PriceVenue, ReservoirApi, SablierStreams, SavingsDai, SnapshotHub, StreamSource,
+    SuperfluidFlows, TeamFinanceLocker, TokenEvents, UnicryptLocker, UniswapV2Router,
+    UniswapV3Pools, UniswapV3Venue, WalletConnectSigner, YieldSource, DEFAULT_ERC4626_VAULTS,
+    DEFAULT_GOVERNORS, SABLIER_V2_LOCKUP_LINEAR, WALLETCONNECT_RESOURCE_URI,
+};
+use crate::mcp::attestation::Attestor;
+use crate::mcp::auth::Authenticator;
+use crate::mcp::capabilities::{
+    Capabilities, CategoryBudget, Deployment, ToolCapability, WriteAccess,
+};
+use crate::mcp::chat::ChatSink;
+use crate::mcp::concurrency::ConcurrencyLimiter;
+use crate::mcp::http::PeerAddr;
+use crate::mcp::messages::{ErrorCode, Locale, Text};
+use crate::mcp::notifier::Notifier;
+use crate::mcp::policy::{PolicyDecision, PolicyEngine, PolicyOutcome};
+use crate::mcp::rate_limit::{RateLimit, RateLimiter};
+use crate::mcp::response_limit::enforce_response_limit;
+use crate::mcp::second_factor::{take_one_time_code, with_one_time_code_property, Totp};
+use crate::mcp::sessions::{Session, SessionManager};
+use crate::mcp::usage::ToolUsage;
+use crate::mcp::validation::validate;
+use crate::mcp::webhooks::{Webhooks, WEBHOOK_RETRY_DELAY};
+use crate::secrets::redact;
+use crate::supervisor::Supervisor;
+use crate::tools::execution_reports::DEFAULT_EXECUTION_REPORTS;
+use crate::tools::explain::{take_explain_flag, with_explain_property};
+use crate::tools::payment_requests::PAYMENT_REQUEST_RESOURCE_PREFIX;
+use crate::tools::sessions::{in_session, LOCAL_SESSION};
+use crate::tools::watch_list::WATCH_EVENTS_RESOURCE_URI;
+use crate::tools::{
+    AddContactTool, AddressBook, AlertSeverity, AlertSink, AnalyzeGasUsageTool, BatchTransferTool,
+    BuildTransactionTool, CallContractTool, CancelStreamTool, CastVoteTool, CheckPaymentTool,
+    CheckTokenLocksTool, CompareLstsTool, CompareQuotesTool, CompareYieldsTool, ConnectWalletTool,
+    Contact, CreatePaymentRequestTool, CreateStreamTool, DelegateVotesTool, DetectInterfacesTool,
+    DexStats, DiagnoseWalletTool, EventBus, EventLog, ExecutePlanTool, ExecuteSwapTool,
+    ExecutionReports, FindPoolsTool, GenerateTaxReportTool, GetBalanceAllChainsTool,
+    GetBalanceTool, GetDelegationTool, GetDexStatsTool, GetEnsNameTool, GetExecutionReportTool,
+    GetFundingRatesTool, GetGovernanceProposalsTool, GetMarketStatsTool, GetNftFloorPriceTool,
+    GetOptionChainsTool, GetPerpMarketsTool, GetPerpPositionsTool, GetPoolLiquidityProfileTool,
+    GetPortfolioTool, GetRestakingPositionsTool, GetTokenPriceTool, GetTwapPriceTool,
+    GetValidatorInfoTool, GetVotingPowerTool, HealthCheckTool, ImportSignedTransactionTool,
+    InspectBytecodeTool, ListContactsTool, ListEnsNamesTool, ListScheduledPaymentsTool,
+    ListStreamsTool, ListWithdrawalsTool, MonitorNewPairsTool, MonitorTokenRisksTool,
+    PauseScheduledPaymentTool, PaymentRequests, PaymentScheduler, PaymentSchedules, PermissionTier,
+    PreviewTransferTool, QuoteStore, ReadStorageTool, RecipientHistory, RecommendSlippageTool,
+    RenewEnsNameTool, ResumeScheduledPaymentTool, ScamList, ScanArbitrageTool, SchedulePaymentTool,
+    SimulateBundleTool, SuggestTxTimingTool, SwapTokensTool, Tool as ToolTrait, TradeLedger,
+    TransferNftTool, WatchAddressTool, WatchList,
+};
 use anyhow::{Context, Result};
+use axum::extract::ConnectInfo;
+use futures::FutureExt;
 use rmcp::model::*;
+use rmcp::service::{NotificationContext, Peer, RequestContext};
 use rmcp::{ErrorData as McpError, RoleServer, ServerHandler};
-use rmcp::service::{RequestContext, NotificationContext};
 use serde_json::json;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::{error, info, info_span, warn, Instrument};
+
+const METRICS_RESOURCE_URI: &str = "metrics://tool-concurrency";
+const CAPABILITIES_RESOURCE_URI: &str = "capabilities://deployment";
+/// Alert topic of calls refused by the policy
+const POLICY_TOPIC: &str = "policy";
 
 #[derive(Clone)]
 pub struct McpServer {
-    get_balance_tool: Arc<GetBalanceTool<EthereumClient>>,
-    get_token_price_tool: Arc<GetTokenPriceTool<EthereumClient, UniswapV2Router>>,
-    swap_tokens_tool: Arc<SwapTokensTool<EthereumClient, UniswapV2Router>>,
+    tools: Vec<Arc<dyn ToolTrait>>,
+    limiter: Arc<ConcurrencyLimiter>,
+    max_response_bytes: Option<usize>,
+    auth: Arc<Authenticator>,
+    attestor: Option<Arc<Attestor>>,
+    notifier: Notifier,
+    /// Where policy violations are reported, besides the refused call
+    alerts: Option<Arc<dyn AlertSink>>,
+    locale: Locale,
+    /// Alternate tool names, alias to tool name
+    aliases: HashMap<String, String>,
+    list_aliases: bool,
+    usage: Arc<ToolUsage>,
+    deployment: Option<Deployment>,
+    /// Published as `payment-request://` resources
+    payment_requests: Option<Arc<PaymentRequests>>,
+    /// Listed addresses in tool inputs and outputs are flagged in `scam_warnings`
+    scam_list: Option<Arc<ScamList>>,
+    /// Published as the `watch://events` resource
+    watch_list: Option<Arc<WatchList>>,
+    /// Tool calls allowed per HTTP client
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Clears session-scoped tool state as clients go away
+    sessions: Arc<SessionManager>,
+    /// Session of the client this handler serves; unset on the server template
+    session: Option<Arc<Session>>,
+    /// Published as the `walletconnect://pairing` resource when a paired wallet signs
+    walletconnect: Option<Arc<WalletConnectSigner>>,
+    /// Calls that send transactions must carry a current code from it
+    second_factor: Option<Arc<Totp>>,
+    /// Allows, denies or holds for confirmation every tool call
+    policy: Option<Arc<PolicyEngine>>,
+    /// Host of the primary RPC endpoint, logged with each tool call
+    rpc_endpoint: Option<String>,
+    /// Periodic jobs such as the payment scheduler, shared by every session
+    background: Arc<Supervisor>,
+}
+
+/// Client for `rpc_url` that signs with the local key, through `walletconnect`, or
+/// with the configured KMS key
+async fn connect(
+    config: &Config,
+    walletconnect: Option<&Arc<WalletConnectSigner>>,
+    rpc_url: &str,
+    chain_id: u64,
+) -> Result<EthereumClient> {
+    match (walletconnect, &config.kms) {
+        (Some(signer), _) => EthereumClient::walletconnect(rpc_url, signer.clone(), chain_id).await,
+        (None, Some(kms)) => EthereumClient::kms(rpc_url, kms, chain_id).await,
+        (None, None) => {
+            let key = config
+                .private_key
+                .as_ref()
+                .context("PRIVATE_KEY not set in environment")?;
+            EthereumClient::new(rpc_url, key.expose_secret(), chain_id).await
+        }
+    }
 }
 
 impl McpServer {
     pub async fn new(config: Config) -> Result<Self> {
+        let server = Self::build(config).await?;
+        server.start_background_tasks();
+        Ok(server)
+    }
+
+    /// Set up a server from `config` without starting its background tasks, so a
+    /// server it replaces can stop its own first
+    pub async fn build(config: Config) -> Result<Self> {
         info!("Initializing Ethereum Trading MCP Server");
+        let mut background = Supervisor::new();
+
+        // A paired mobile wallet approves each transaction instead of a local key
+        let walletconnect = config.walletconnect.as_ref().map(|walletconnect| {
+            Arc::new(WalletConnectSigner::new(walletconnect, config.chain_id))
+        });
 
         // Initialize Ethereum client
-        let client = Arc::new(
-            EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
+        let mut client = connect(
+            &config,
+            walletconnect.as_ref(),
+            &config.eth_rpc_url,
+            config.chain_id,
+        )
+        .await
+        .context("Failed to create Ethereum client")?
+        .with_chain_id_check(config.chain_id_check);
+        if let Some(sponsor) = &config.gas_sponsor {
+            let sponsor = GasSponsor::new(sponsor, client.get_provider(), config.chain_id)
+                .context("Failed to set up the gas sponsor")?;
+            client = client.with_gas_sponsor(sponsor);
+        }
+        if let Some(rpc_url) = &config.simulation_rpc_url {
+            client = client.with_simulation_rpc(rpc_url)?;
+        }
+        let client = Arc::new(client);
+
+        // Signing for the wrong chain is worse than not starting
+        if let Err(e) = client.verify_chain_id().await {
+            if e.is::<ChainIdMismatch>() {
+                return Err(e);
+            }
+            tracing::warn!(
+                chain = config.chain_id,
+                rpc_endpoint = client.rpc_endpoint(),
+                "Could not verify chain id at startup: {:#}",
+                e
+            );
+        }
+
+        // Other chains are read-only: their clients hold the same signer but never send
+        let mut chains = ChainClients::new().with_client(client.clone());
+        if let Some(pricing) = ChainPricing::uniswap_v2(config.chain_id, client.get_provider())? {
+            chains = chains.with_pricing(config.chain_id, pricing);
+        }
+        for (chain_id, rpc_url) in &config.chain_rpc_urls {
+            let chain_client = connect(&config, walletconnect.as_ref(), rpc_url, *chain_id)
                 .await
-                .context("Failed to create Ethereum client")?,
-        );
+                .with_context(|| format!("Failed to create client for chain {}", chain_id))?
+                .with_chain_id_check(config.chain_id_check);
+            if let Err(e) = chain_client.verify_chain_id().await {
+                if e.is::<ChainIdMismatch>() {
+                    return Err(e);
+                }
+                tracing::warn!(
+                    chain = chain_id,
+                    rpc_endpoint = chain_client.rpc_endpoint(),
+                    "Could not verify chain {} at startup: {:#}",
+                    chain_id,
+                    e
+                );
+            }
+            if let Some(pricing) = ChainPricing::uniswap_v2(*chain_id, chain_client.get_provider())?
+            {
+                chains = chains.with_pricing(*chain_id, pricing);
+            }
+            chains = chains.with_client(Arc::new(chain_client));
+        }
+        let chains = Arc::new(chains);
 
         // Initialize Uniswap router
-        let uniswap = Arc::new(UniswapV2Router::new(client.get_provider()));
+        let uniswap = Arc::new(UniswapV2Router::new(client.get_provider())?);
+        let sushiswap = Arc::new(UniswapV2Router::sushiswap(client.get_provider())?);
+        let uniswap_v3 = Arc::new(UniswapV3Pools::new(client.get_provider())?);
+
+        // Venues compared by the arbitrage scanner
+        let venues: Vec<Arc<dyn PriceVenue>> = vec![
+            uniswap.clone(),
+            Arc::new(UniswapV3Venue::new(uniswap_v3.clone())),
+            sushiswap.clone(),
+            Arc::new(CurveVenue::new(client.get_provider())?),
+        ];
+
+        // Liquid staking tokens compared by compare_lsts
+        let lsts: Vec<Arc<dyn LiquidStakingToken>> = LstContract::mainnet(client.get_provider())?
+            .into_iter()
+            .map(|lst| Arc::new(lst) as Arc<dyn LiquidStakingToken>)
+            .collect();
+
+        // Deposit yields compared by compare_yields
+        let mut yield_sources: Vec<Arc<dyn YieldSource>> = vec![
+            Arc::new(SavingsDai::new(client.get_provider())),
+            Arc::new(AaveV3Yield::new(client.get_provider())),
+            Arc::new(CompoundV3Yield::new(client.get_provider())?),
+        ];
+        let vaults = DEFAULT_ERC4626_VAULTS
+            .iter()
+            .map(|(name, vault)| {
+                let vault = vault
+                    .parse()
+                    .with_context(|| format!("Invalid {} vault address", name))?;
+                Ok((name.to_string(), vault))
+            })
+            .chain(config.yield_vaults.iter().cloned().map(Ok))
+            .collect::<Result<Vec<_>>>()?;
+        for (name, vault) in vaults {
+            yield_sources.push(Arc::new(Erc4626Vault::new(
+                client.get_provider(),
+                name,
+                vault,
+            )));
+        }
+
+        // Lockers and vesting contracts inspected by check_token_locks
+        let lock_sources: Vec<Arc<dyn LockSource>> = vec![
+            Arc::new(UnicryptLocker::new(client.get_provider())?),
+            Arc::new(TeamFinanceLocker::new(client.get_provider())?),
+            Arc::new(SablierStreams::new(client.get_provider())?),
+        ];
+
+        // Streaming payments for list_streams, and the Sablier contract create_stream and
+        // cancel_stream go through
+        let stream_sources: Vec<Arc<dyn StreamSource>> = vec![
+            Arc::new(SablierStreams::new(client.get_provider())?),
+            Arc::new(SuperfluidFlows::new(client.get_provider())?),
+        ];
+        let sablier: ethers::types::Address = SABLIER_V2_LOCKUP_LINEAR
+            .parse()
+            .context("Invalid Sablier lockup address")?;
+
+        let ens: Arc<dyn EnsSource> = Arc::new(EnsContracts::new(client.get_provider())?);
+
+        // Names function selectors found in bytecode
+        let signatures = Arc::new(FourByteDirectory::new(&config.four_byte_url));
+
+        // The wallet's past transactions, for gas usage reports
+        let history = Arc::new(EtherscanHistory::new(
+            &config.etherscan_url,
+            config.etherscan_api_key.clone(),
+            config.chain_id,
+        ));
+
+        // NFT collection floor prices
+        let nft_market = Arc::new(ReservoirApi::new(
+            &config.reservoir_url,
+            config.reservoir_api_key.clone(),
+        ));
+
+        // Perpetuals exchange read by the perp tools and get_portfolio
+        let perps = config.perp_venue.connect(&config.perp_api_url);
+        // Exchanges compared by get_funding_rates, sharing the one above when listed
+        let funding_venues: Vec<Arc<dyn PerpVenue>> = config
+            .funding_rate_venues
+            .iter()
+            .map(|&kind| {
+                if kind == config.perp_venue {
+                    perps.clone()
+                } else {
+                    kind.connect(kind.default_url())
+                }
+            })
+            .collect();
+
+        // Options exchange read by get_option_chains
+        let options: Arc<dyn OptionsVenue> = match config.options_venue {
+            OptionsVenueKind::Aevo => Arc::new(AevoApi::new(&config.options_api_url)),
+            OptionsVenueKind::Lyra => Arc::new(LyraApi::new(&config.options_api_url)),
+        };
+
+        // DAOs read by the governance tools; configured governors extend the known names
+        let governor_source: Arc<dyn GovernanceSource> =
+            Arc::new(GovernorContracts::new(client.get_provider()));
+        let snapshot: Arc<dyn GovernanceSource> =
+            Arc::new(SnapshotHub::new(&config.snapshot_hub_url));
+        let governors: Vec<(String, ethers::types::Address)> = DEFAULT_GOVERNORS
+            .iter()
+            .map(|(name, governor)| {
+                let governor = governor
+                    .parse()
+                    .with_context(|| format!("Invalid {} governor address", name))?;
+                Ok((name.to_string(), governor))
+            })
+            .chain(config.governors.iter().cloned().map(Ok))
+            .collect::<Result<_>>()?;
+
+        let deployment = Deployment::from_config(
+            &config,
+            venues
+                .iter()
+                .map(|venue| venue.name().to_string())
+                .collect(),
+            yield_sources
+                .iter()
+                .map(|source| source.name().to_string())
+                .collect(),
+        );
+
+        let discovery = Arc::new(PoolDiscovery::new(client.get_provider())?);
+
+        // Transactions, alerts and policy violations, published to connected clients as
+        // logging notifications, the log, webhooks and chat bots
+        let events = EventBus::new(config.event_bus_capacity);
+        let notifier = Notifier::new();
+        events.subscribe_sink("notifications", Arc::new(notifier.clone()));
+        events.subscribe_sink("log", Arc::new(EventLog));
+        if !config.webhook_urls.is_empty() {
+            let urls = config
+                .webhook_urls
+                .iter()
+                .map(|url| url.expose_secret().to_string())
+                .collect();
+            let webhooks = Webhooks::new(urls, config.webhook_secret.clone())
+                .with_topics(config.webhook_topics.clone())
+                .with_retries(config.webhook_max_retries, WEBHOOK_RETRY_DELAY);
+            info!("Posting alerts to {} webhooks", config.webhook_urls.len());
+            events.subscribe_sink("webhooks", Arc::new(webhooks));
+        }
+        if let Some(bot) = &config.telegram {
+            info!("Posting alerts to Telegram chat {}", bot.chat_id);
+            events.subscribe_sink(
+                "telegram",
+                Arc::new(ChatSink::telegram(bot.clone()).with_topics(config.chat_topics.clone())),
+            );
+        }
+        if let Some(bot) = &config.discord {
+            info!("Posting alerts to Discord channel {}", bot.chat_id);
+            events.subscribe_sink(
+                "discord",
+                Arc::new(ChatSink::discord(bot.clone()).with_topics(config.chat_topics.clone())),
+            );
+        }
+        let alerts: Arc<dyn AlertSink> = Arc::new(events);
+
+        let contacts = config
+            .contacts
+            .iter()
+            .map(|(name, address)| Contact {
+                name: name.clone(),
+                address: *address,
+                note: None,
+            })
+            .collect();
+        let book = match &config.address_book_path {
+            Some(path) => AddressBook::open(path.clone(), contacts)?,
+            None => AddressBook::new(contacts)?,
+        };
+        let book =
+            Arc::new(book.with_confirm_unknown_recipients(config.confirm_unknown_recipients));
+
+        // Known scam addresses, flagged in every tool call and refused as payees
+        let scams = Arc::new(
+            ScamList::new(config.scam_list_feeds.clone())
+                .with_override_allowed(config.allow_scam_list_override),
+        );
+        if !config.scam_list_feeds.is_empty() {
+            let (scams, refresh) = (scams.clone(), config.scam_list_refresh);
+            background.add("scam_list", config.task_restart, move |stop| {
+                scams.clone().spawn(refresh, stop)
+            });
+        }
+
+        let token_events = Arc::new(TokenEvents::new(client.get_provider()));
+        let recipients = Arc::new(
+            RecipientHistory::new(token_events.clone(), config.recipient_history_blocks)
+                .with_required_confirmation(config.confirm_new_recipients),
+        );
+
+        // New blocks over WebSocket, reconnected and resubscribed when the connection drops
+        let new_heads = config.eth_ws_url.as_ref().map(|url| {
+            let heads = Arc::new(NewHeads::new(url.clone()));
+            let job = heads.clone();
+            background.add("new_heads", config.task_restart, move |stop| {
+                job.clone().spawn(stop)
+            });
+            heads
+        });
+
+        // Addresses polled for transfers and balance changes, pushed as notifications
+        let mut watches = WatchList::new(token_events.clone(), config.watch_feed_size)
+            .with_alert_sink(alerts.clone());
+        if let Some(heads) = &new_heads {
+            watches = watches.with_new_heads(heads.clone());
+        }
+        let watches = Arc::new(watches);
+        for (label, address) in &config.watch_addresses {
+            watches.add(*address, Some(label.clone()), None)?;
+        }
+        {
+            let (watches, client, poll) = (watches.clone(), client.clone(), config.watch_poll);
+            background.add("watch_list", config.task_restart, move |stop| {
+                watches.clone().spawn(client.clone(), poll, stop)
+            });
+        }
+
+        // Volume and TVL of configured pools, read on-chain in the background
+        let dex_stats = Arc::new(
+            DexStats::new(
+                client.clone(),
+                Arc::new(PoolActivity::new(client.get_provider())),
+                config.dex_stats_pools.clone(),
+            )
+            .with_reference_prices(Arc::new(ChainlinkPriceFeeds::new(
+                client.get_provider(),
+                config.chain_id,
+            ))),
+        );
+        if !config.dex_stats_pools.is_empty() {
+            let (stats, refresh) = (dex_stats.clone(), config.dex_stats_refresh);
+            background.add("dex_stats", config.task_restart, move |stop| {
+                stats.clone().spawn(refresh, stop)
+            });
+        }
+
+        // How live swaps filled against their quotes
+        let execution_reports = Arc::new(ExecutionReports::new(
+            token_events.clone(),
+            DEFAULT_EXECUTION_REPORTS,
+        ));
+        // Executed swaps, valued in dollars, for tax lot reports
+        let trade_ledger = match &config.trade_ledger_path {
+            Some(path) => TradeLedger::open(path.clone(), config.chain_id)?,
+            None => TradeLedger::new(config.chain_id),
+        };
+        let trade_ledger = Arc::new(trade_ledger.with_reference_prices(Arc::new(
+            ChainlinkPriceFeeds::new(client.get_provider(), config.chain_id),
+        )));
+
+        let schedules = match &config.schedules_path {
+            Some(path) => PaymentSchedules::open(path.clone())?,
+            None => PaymentSchedules::new(),
+        };
+        let schedules = Arc::new(match &config.schedule_audit_log {
+            Some(path) => schedules.with_audit_log(path.clone())?,
+            None => schedules,
+        });
+        if config.execution_enabled {
+            let scheduler = PaymentScheduler::new(client.clone(), schedules.clone(), true)
+                .with_gas_escalation(config.gas_escalation)
+                .with_recipient_history(recipients.clone())
+                .with_scam_list(scams.clone())
+                .with_gas_reserve(config.gas_reserve)
+                .with_alert_sink(alerts.clone());
+            let (scheduler, tick) = (Arc::new(scheduler), config.schedule_tick);
+            background.add("payment_scheduler", config.task_restart, move |stop| {
+                scheduler.clone().spawn(tick, stop)
+            });
+        }
+        let background = Arc::new(background);
+
+        let mut health_check = HealthCheckTool::new(client.clone(), background.clone());
+        if let Some(heads) = &new_heads {
+            health_check = health_check.with_new_heads(heads.clone());
+        }
+        let health_check = Arc::new(health_check);
+
+        // Requests from create_payment_request, checked by check_payment
+        let payment_requests = Arc::new(PaymentRequests::new());
+
+        // Quotes from swap_tokens, redeemable through execute_swap by the same session
+        let quotes = Arc::new(QuoteStore::new(config.quote_ttl));
+        let sessions = Arc::new(SessionManager::new());
+        sessions.register(quotes.clone());
+
+        let swap_tokens = || {
+            SwapTokensTool::new(client.clone(), uniswap.clone())
+                .with_slippage_defaults(config.slippage.clone())
+                .with_pool_share_limit(config.pool_share_limit)
+                .with_split_routers(vec![sushiswap.clone()])
+                .with_route_objective(config.route_objective)
+                .with_approval_defaults(config.approvals)
+        };
 
         // Create tool instances
-        let get_balance_tool = Arc::new(GetBalanceTool::new(client.clone()));
-        let get_token_price_tool = Arc::new(GetTokenPriceTool::new(client.clone(), uniswap.clone()));
-        let swap_tokens_tool = Arc::new(SwapTokensTool::new(client.clone(), uniswap.clone()));
-
-        Ok(Self {
-            get_balance_tool,
-            get_token_price_tool,
-            swap_tokens_tool,
+        let mut get_token_price = GetTokenPriceTool::new(client.clone(), uniswap.clone())?
+            .with_fx_rates(Arc::new(ChainlinkFxRates::new(client.get_provider())))
+            .with_intermediaries(config.price_intermediaries.clone());
+        if let Some(max_deviation) = config.max_price_deviation {
+            get_token_price = get_token_price.with_reference_prices(
+                Arc::new(ChainlinkPriceFeeds::new(
+                    client.get_provider(),
+                    config.chain_id,
+                )),
+                max_deviation,
+            );
+        }
+        let mut tools: Vec<Arc<dyn ToolTrait>> = vec![
+            Arc::new(GetBalanceTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(
+                GetBalanceAllChainsTool::new(chains.clone())
+                    .with_max_parallel(config.chain_query_concurrency)
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                GetPortfolioTool::new(chains.clone())
+                    .with_max_parallel(config.chain_query_concurrency)
+                    .with_address_book(book.clone())
+                    .with_perps(perps.clone()),
+            ),
+            Arc::new(get_token_price),
+            Arc::new(swap_tokens().with_quote_store(quotes.clone())),
+            Arc::new(CompareQuotesTool::new(swap_tokens(), quotes.clone())),
+            Arc::new(RecommendSlippageTool::new(client.clone(), uniswap.clone())),
+            Arc::new(GetTwapPriceTool::new(client.clone(), uniswap_v3.clone())),
+            Arc::new(SuggestTxTimingTool::new(client.clone())),
+            Arc::new(
+                GetFundingRatesTool::new(funding_venues)
+                    .with_reference_prices(Arc::new(ChainlinkPriceFeeds::new(
+                        client.get_provider(),
+                        config.chain_id,
+                    )))
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(GetMarketStatsTool::new(
+                client.clone(),
+                uniswap.clone(),
+                uniswap_v3.clone(),
+            )),
+            Arc::new(
+                GetNftFloorPriceTool::new(client.clone(), nft_market)
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(GetOptionChainsTool::new(options)),
+            Arc::new(GetPerpMarketsTool::new(perps.clone())),
+            Arc::new(
+                GetPerpPositionsTool::new(client.clone(), perps).with_address_book(book.clone()),
+            ),
+            Arc::new(GetPoolLiquidityProfileTool::new(
+                client.clone(),
+                uniswap_v3.clone(),
+            )),
+            Arc::new(CompareLstsTool::new(client.clone(), lsts, venues.clone())),
+            Arc::new(ScanArbitrageTool::new(client.clone(), venues)),
+            Arc::new(CompareYieldsTool::new(
+                client.clone(),
+                yield_sources,
+                uniswap.clone(),
+            )),
+            Arc::new(
+                CheckTokenLocksTool::new(client.clone(), lock_sources, token_events.clone())
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                ExecutePlanTool::new(client.clone(), config.execution_enabled)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_address_book(book.clone())
+                    .with_recipient_history(recipients.clone())
+                    .with_scam_list(scams.clone())
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                BatchTransferTool::new(
+                    client.clone(),
+                    config.disperse_contract,
+                    config.execution_enabled,
+                )
+                .with_limits(config.batch_limits)
+                .with_gas_escalation(config.gas_escalation)
+                .with_address_book(book.clone())
+                .with_recipient_history(recipients.clone())
+                .with_scam_list(scams.clone())
+                .with_gas_reserve(config.gas_reserve)
+                .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                TransferNftTool::new(client.clone(), config.execution_enabled)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_address_book(book.clone())
+                    .with_recipient_history(recipients.clone())
+                    .with_scam_list(scams.clone())
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                GetRestakingPositionsTool::new(
+                    client.clone(),
+                    Arc::new(EigenLayerContracts::new(client.get_provider())?),
+                )
+                .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                ListStreamsTool::new(client.clone(), stream_sources)
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                CreateStreamTool::new(client.clone(), sablier, config.execution_enabled)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_address_book(book.clone())
+                    .with_recipient_history(recipients)
+                    .with_scam_list(scams.clone())
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                CancelStreamTool::new(client.clone(), sablier, config.execution_enabled)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                GetGovernanceProposalsTool::new(governor_source.clone(), snapshot.clone())
+                    .with_governors(governors.clone()),
+            ),
+            Arc::new(
+                GetVotingPowerTool::new(client.clone(), governor_source.clone(), snapshot)
+                    .with_governors(governors.clone())
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(GetDelegationTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(
+                DelegateVotesTool::new(client.clone(), config.execution_enabled)
+                    .with_address_book(book.clone())
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                CastVoteTool::new(client.clone(), governor_source, config.execution_enabled)
+                    .with_governors(governors)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                GetEnsNameTool::new(client.clone(), ens.clone()).with_address_book(book.clone()),
+            ),
+            Arc::new(
+                ListEnsNamesTool::new(client.clone(), ens.clone()).with_address_book(book.clone()),
+            ),
+            Arc::new(
+                RenewEnsNameTool::new(client.clone(), ens, config.execution_enabled)
+                    .with_gas_escalation(config.gas_escalation)
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                ExecuteSwapTool::new(
+                    client.clone(),
+                    uniswap.clone(),
+                    quotes,
+                    config.execution_enabled,
+                    config.max_quote_move_bps,
+                )
+                .with_gas_escalation(config.gas_escalation)
+                .with_gas_reserve(config.gas_reserve)
+                .with_alert_sink(alerts.clone())
+                .with_pool_share_limit(config.pool_share_limit)
+                .with_split_routers(vec![sushiswap])
+                .with_execution_reports(execution_reports.clone())
+                .with_trade_ledger(trade_ledger.clone()),
+            ),
+            Arc::new(GetExecutionReportTool::new(execution_reports)),
+            Arc::new(GenerateTaxReportTool::new(
+                trade_ledger,
+                config.tax_accounting_method,
+            )),
+            Arc::new(DiagnoseWalletTool::new(client.clone()).with_address_book(book.clone())),
+            health_check,
+            Arc::new(BuildTransactionTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(
+                CallContractTool::new(client.clone())
+                    .with_address_book(book.clone())
+                    .with_signature_source(signatures.clone()),
+            ),
+            Arc::new(PreviewTransferTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(SimulateBundleTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(DetectInterfacesTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(ReadStorageTool::new(client.clone()).with_address_book(book.clone())),
+            Arc::new(
+                InspectBytecodeTool::new(client.clone(), signatures.clone())
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                CreatePaymentRequestTool::new(client.clone(), payment_requests.clone())
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(CheckPaymentTool::new(
+                client.clone(),
+                token_events.clone(),
+                payment_requests.clone(),
+            )),
+            Arc::new(
+                SchedulePaymentTool::new(
+                    client.clone(),
+                    schedules.clone(),
+                    config.execution_enabled,
+                )
+                .with_address_book(book.clone())
+                .with_scam_list(scams.clone()),
+            ),
+            Arc::new(ListScheduledPaymentsTool::new(schedules.clone())),
+            Arc::new(PauseScheduledPaymentTool::new(schedules.clone())),
+            Arc::new(ResumeScheduledPaymentTool::new(schedules)),
+            Arc::new(AddContactTool::new(book.clone())),
+            Arc::new(ListContactsTool::new(book.clone())),
+            Arc::new(
+                ImportSignedTransactionTool::new(client.clone(), config.execution_enabled)
+                    .with_scam_list(scams.clone())
+                    .with_gas_reserve(config.gas_reserve)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(FindPoolsTool::new(discovery.clone())),
+            Arc::new(MonitorNewPairsTool::new(
+                client.clone(),
+                discovery.clone(),
+                config.monitor_base_tokens.clone(),
+            )?),
+            Arc::new(
+                MonitorTokenRisksTool::new(client.clone(), discovery, token_events)
+                    .with_alert_sink(alerts.clone()),
+            ),
+            Arc::new(
+                WatchAddressTool::new(client.clone(), watches.clone())
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                AnalyzeGasUsageTool::new(client.clone(), history.clone())
+                    .with_signatures(signatures)
+                    .with_address_book(book.clone()),
+            ),
+            Arc::new(
+                ListWithdrawalsTool::new(client.clone(), history.clone())
+                    .with_address_book(book.clone()),
+            ),
+        ];
+        if !config.dex_stats_pools.is_empty() {
+            tools.push(Arc::new(GetDexStatsTool::new(dex_stats)));
+        }
+        if let Some(url) = &config.beacon_api_url {
+            tools.push(Arc::new(GetValidatorInfoTool::new(
+                client.clone(),
+                Arc::new(BeaconApi::new(url.clone())),
+                history,
+            )));
+        }
+        if let Some(signer) = &walletconnect {
+            tools.push(Arc::new(ConnectWalletTool::new(signer.clone())));
+            match signer.pair().await {
+                Ok(pairing) => info!(
+                    "Scan to approve transactions from your wallet over WalletConnect: {}",
+                    pairing.uri
+                ),
+                Err(e) => tracing::warn!(
+                    "Could not offer a WalletConnect pairing yet; use connect_wallet: {:#}",
+                    e
+                ),
+            }
+        }
+
+        let mut server = Self::with_tools(tools, ConcurrencyLimiter::from_config(&config))
+            .with_max_response_bytes(config.max_response_bytes)
+            .with_authenticator(Authenticator::new(config.auth_tokens.clone()))
+            .with_notifier(notifier)
+            .with_alert_sink(alerts)
+            .with_locale(config.locale)
+            .with_deployment(deployment)
+            .with_rpc_endpoint(client.rpc_endpoint())
+            .with_background_tasks(background)
+            .with_payment_requests(payment_requests)
+            .with_scam_list(scams)
+            .with_watch_list(watches)
+            .with_sessions(sessions)
+            .with_aliases(config.tool_aliases.clone(), config.list_tool_aliases)?;
+        if let Some(limit) = config.rate_limit {
+            server = server.with_rate_limit(limit);
+        }
+        if let Some(signer) = walletconnect {
+            server = server.with_walletconnect(signer);
+        }
+        if let Some(policy) = &config.policy {
+            let mut engine = PolicyEngine::new(policy.clone()).with_address_book(book);
+            if let Some(pricing) = chains.pricing(config.chain_id) {
+                engine = engine.with_pricing(client.clone(), pricing.clone());
+            }
+            info!(
+                "Checking tool calls against {} policy rules",
+                engine.rule_count()
+            );
+            server = server.with_policy(engine);
+        }
+        if let Some(secret) = &config.totp_secret {
+            server = server.with_second_factor(Totp::new(secret.expose_secret())?);
+            info!("Calls that send transactions need a one-time code");
+        }
+        if let Some(key) = &config.attestation_key {
+            let attestor = Attestor::new(key.expose_secret(), config.chain_id, client.clone())?;
+            info!("Signing tool results as {:?}", attestor.signer());
+            server = server.with_attestor(attestor);
+        }
+
+        Ok(server)
+    }
+
+    /// Build a server around an explicit tool set
+    pub fn with_tools(tools: Vec<Arc<dyn ToolTrait>>, limiter: ConcurrencyLimiter) -> Self {
+        let usage = Arc::new(ToolUsage::new(tools.iter().map(|tool| tool.name())));
+        Self {
+            tools,
+            limiter: Arc::new(limiter),
+            max_response_bytes: None,
+            auth: Arc::new(Authenticator::default()),
+            attestor: None,
+            notifier: Notifier::new(),
+            alerts: None,
+            locale: Locale::default(),
+            aliases: HashMap::new(),
+            list_aliases: false,
+            usage,
+            deployment: None,
+            payment_requests: None,
+            scam_list: None,
+            watch_list: None,
+            rate_limiter: None,
+            sessions: Arc::new(SessionManager::new()),
+            session: None,
+            walletconnect: None,
+            second_factor: None,
+            policy: None,
+            rpc_endpoint: None,
+            background: Arc::new(Supervisor::new()),
+        }
+    }
+
+    /// Trim tool results whose serialized size exceeds `max_bytes`
+    pub fn with_max_response_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    /// Resolve HTTP callers' bearer tokens to permission tiers
+    pub fn with_authenticator(mut self, auth: Authenticator) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    /// Flag addresses on `scams` found in tool inputs and outputs
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.scam_list = Some(scams);
+        self
+    }
+
+    /// Publish the events seen on `watches` as a resource
+    pub fn with_watch_list(mut self, watches: Arc<WatchList>) -> Self {
+        self.watch_list = Some(watches);
+        self
+    }
+
+    /// Limit how many tools each HTTP client calls; stdio calls are not limited
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limit)));
+        self
+    }
+
+    /// Track client sessions with `sessions`, whose registered state is cleared when
+    /// a session ends
+    pub fn with_sessions(mut self, sessions: Arc<SessionManager>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    pub fn sessions(&self) -> &Arc<SessionManager> {
+        &self.sessions
+    }
+
+    /// Handler for a newly connected client, with a session of its own that ends once
+    /// the transport drops the handler
+    pub fn open_session(&self) -> Self {
+        Self {
+            session: Some(Arc::new(self.sessions.open())),
+            ..self.clone()
+        }
+    }
+
+    /// Id of the session this handler serves
+    pub fn session_id(&self) -> &str {
+        self.session
+            .as_ref()
+            .map_or(LOCAL_SESSION, |session| session.id())
+    }
+
+    /// Sign every tool result, attaching the attestation as `_meta.attestation`
+    pub fn with_attestor(mut self, attestor: Attestor) -> Self {
+        self.attestor = Some(Arc::new(attestor));
+        self
+    }
+
+    /// Register initialized clients with `notifier` so tools' alerts reach them
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// Report refused calls to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Serve tool descriptions and error messages in `locale`
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Description of `tool` in the server's locale
+    pub fn tool_description<'a>(&self, tool: &'a dyn ToolTrait) -> &'a str {
+        self.locale
+            .tool_description(tool.name())
+            .unwrap_or_else(|| tool.description())
+    }
+
+    /// Accept each `(alias, tool)` pair's alias as another name for the tool, and list
+    /// the aliases as tools of their own when `listed` is set. Aliases must name an
+    /// existing tool and must not shadow one.
+    pub fn with_aliases(mut self, aliases: Vec<(String, String)>, listed: bool) -> Result<Self> {
+        for (alias, tool) in aliases {
+            if self.tools.iter().any(|t| t.name() == alias) {
+                anyhow::bail!("Tool alias {} shadows the tool of the same name", alias);
+            }
+            if !self.tools.iter().any(|t| t.name() == tool) {
+                anyhow::bail!("Tool alias {} points to unknown tool {}", alias, tool);
+            }
+            self.aliases.insert(alias, tool);
+        }
+        self.list_aliases = listed;
+        Ok(self)
+    }
+
+    /// Report `deployment` in the capabilities resource
+    pub fn with_deployment(mut self, deployment: Deployment) -> Self {
+        self.deployment = Some(deployment);
+        self
+    }
+
+    fn with_background_tasks(mut self, supervisor: Arc<Supervisor>) -> Self {
+        self.background = supervisor;
+        self
+    }
+
+    /// Start the server's periodic jobs, unless they are running already
+    pub fn start_background_tasks(&self) {
+        self.background.start();
+    }
+
+    /// Stop the server's periodic jobs, waiting for any run in progress to finish.
+    /// Its sessions keep serving tool calls.
+    pub async fn stop_background_tasks(&self) {
+        self.background.stop().await;
+    }
+
+    /// Log tool calls as served through `rpc_endpoint`
+    pub fn with_rpc_endpoint(mut self, rpc_endpoint: String) -> Self {
+        self.rpc_endpoint = Some(rpc_endpoint);
+        self
+    }
+
+    /// Publish the WalletConnect pairing of `signer` as a resource for trade-tier callers
+    pub fn with_walletconnect(mut self, signer: Arc<WalletConnectSigner>) -> Self {
+        self.walletconnect = Some(signer);
+        self
+    }
+
+    /// Require a code from `totp` on every call that sends a transaction
+    pub fn with_second_factor(mut self, totp: Totp) -> Self {
+        self.second_factor = Some(Arc::new(totp));
+        self
+    }
+
+    /// Check every call against `policy` before it runs
+    pub fn with_policy(mut self, policy: PolicyEngine) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Publish each open request in `requests` as a resource
+    pub fn with_payment_requests(mut self, requests: Arc<PaymentRequests>) -> Self {
+        self.payment_requests = Some(requests);
+        self
+    }
+
+    /// What a client of `tier` can do on this deployment, with each tool's usage so far
+    pub fn capabilities(&self, tier: PermissionTier) -> Capabilities {
+        let execution_enabled = self
+            .deployment
+            .as_ref()
+            .is_some_and(|deployment| deployment.execution_enabled);
+        let visible = self.tools_for_tier(tier);
+        Capabilities {
+            deployment: self.deployment.clone(),
+            writes: WriteAccess {
+                execution_enabled,
+                caller_tier: tier,
+                caller_can_send: execution_enabled && tier >= PermissionTier::Trade,
+                one_time_code_required: self.second_factor.is_some(),
+            },
+            concurrency: self
+                .limiter
+                .stats()
+                .into_iter()
+                .map(|stats| CategoryBudget {
+                    category: stats.category,
+                    max_concurrency: stats.max_concurrency,
+                    queue_depth: stats.queue_depth,
+                })
+                .collect(),
+            tools: visible
+                .iter()
+                .map(|tool| ToolCapability {
+                    name: tool.name().to_string(),
+                    category: tool.category(),
+                    required_tier: tool.required_tier(),
+                    usage: self.usage.stats(tool.name()),
+                })
+                .collect(),
+            aliases: self
+                .aliases
+                .iter()
+                .filter(|(_, target)| visible.iter().any(|tool| tool.name() == *target))
+                .map(|(alias, target)| (alias.clone(), target.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    pub fn limiter(&self) -> &ConcurrencyLimiter {
+        &self.limiter
+    }
+
+    pub fn authenticator(&self) -> Arc<Authenticator> {
+        Arc::clone(&self.auth)
+    }
+
+    /// Tier of the client behind a request. Stdio requests carry no HTTP parts and come
+    /// from the local operator, so they get full access.
+    fn caller_tier(&self, extensions: &Extensions) -> Result<PermissionTier, McpError> {
+        match extensions.get::<axum::http::request::Parts>() {
+            None => Ok(PermissionTier::Admin),
+            Some(parts) => self
+                .auth
+                .tier_for_headers(&parts.headers)
+                .ok_or_else(|| self.locale.error(ErrorCode::Unauthorized, &[])),
+        }
+    }
+
+    /// Who made an HTTP request, for rate limiting: the bearer token when tokens are
+    /// configured, otherwise the peer's IP address
+    fn client_id(&self, parts: &axum::http::request::Parts) -> String {
+        self.auth
+            .token_label(&parts.headers)
+            .or_else(|| {
+                parts
+                    .extensions
+                    .get::<ConnectInfo<PeerAddr>>()
+                    .map(|ConnectInfo(PeerAddr(addr))| addr.ip().to_string())
+            })
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Take a call from `client`'s rate limit, failing with `rate_limited` and
+    /// `data.retry_after_secs` once it is used up
+    pub fn check_rate_limit(&self, client: &str) -> Result<(), McpError> {
+        let Some(limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        limiter.check(client).map_err(|wait| {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::warn!(client, "rate limit exceeded, rejecting call");
+            let mut error = self.locale.error(
+                ErrorCode::RateLimited,
+                &[
+                    &limiter.limit().calls_per_minute.to_string(),
+                    &retry_after.to_string(),
+                ],
+            );
+            if let Some(data) = error.data.as_mut() {
+                data["retry_after_secs"] = json!(retry_after);
+            }
+            error
         })
     }
 
-    async fn handle_get_balance(&self, params_value: serde_json::Value) -> Result<CallToolResult, String> {
-        let result = self.get_balance_tool
-            .execute(params_value)
-            .await
-            .map_err(|e| format!("Failed to get balance: {}", e))?;
+    /// Tools a client of `tier` may see and call
+    pub fn tools_for_tier(&self, tier: PermissionTier) -> Vec<&Arc<dyn ToolTrait>> {
+        self.tools
+            .iter()
+            .filter(|tool| tier >= tool.required_tier())
+            .collect()
+    }
+
+    /// Tool listing for a client of `tier`, followed by the aliases of those tools
+    /// when aliases are listed
+    pub fn listed_tools(&self, tier: PermissionTier) -> Vec<Tool> {
+        let entry = |name: &str, description: String, tool: &Arc<dyn ToolTrait>| {
+            let mut schema = with_explain_property(tool.input_schema());
+            if self.second_factor.is_some() && tool.required_tier() >= PermissionTier::Trade {
+                schema = with_one_time_code_property(schema);
+            }
+            Tool {
+                name: name.to_string().into(),
+                description: Some(description.into()),
+                input_schema: Arc::new(schema.as_object().cloned().unwrap_or_default()),
+                output_schema: None,
+                annotations: None,
+                title: None,
+                icons: None,
+            }
+        };
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| format!("Failed to serialize result: {}", e))?;
+        let visible = self.tools_for_tier(tier);
+        let mut listed: Vec<Tool> = visible
+            .iter()
+            .map(|tool| {
+                entry(
+                    tool.name(),
+                    self.tool_description(tool.as_ref()).to_string(),
+                    tool,
+                )
+            })
+            .collect();
+        if self.list_aliases {
+            let mut aliases: Vec<_> = self.aliases.iter().collect();
+            aliases.sort();
+            for (alias, target) in aliases {
+                if let Some(tool) = visible.iter().find(|tool| tool.name() == target) {
+                    let description = self
+                        .locale
+                        .alias_description(target, self.tool_description(tool.as_ref()));
+                    listed.push(entry(alias, description, tool));
+                }
+            }
+        }
+        listed
+    }
 
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+    /// Tool called `name`, directly or through an alias
+    fn find_tool(&self, name: &str) -> Option<&Arc<dyn ToolTrait>> {
+        let name = self.aliases.get(name).map(String::as_str).unwrap_or(name);
+        self.tools.iter().find(|tool| tool.name() == name)
     }
 
-    async fn handle_get_token_price(&self, params_value: serde_json::Value) -> Result<CallToolResult, String> {
-        let result = self.get_token_price_tool
-            .execute(params_value)
-            .await
-            .map_err(|e| format!("Failed to get token price: {}", e))?;
+    /// Log a policy decision on a call to `tool`, then refuse the call or have the user
+    /// confirm it as the decision says
+    async fn enforce(
+        &self,
+        peer: &Peer<RoleServer>,
+        tool: &str,
+        decision: PolicyDecision,
+    ) -> Result<(), McpError> {
+        let rule = decision.rule_id.as_deref().unwrap_or("default");
+        info!(
+            tool,
+            rule,
+            outcome = decision.outcome.as_str(),
+            "policy decision"
+        );
+        match decision.outcome {
+            PolicyOutcome::Allow => Ok(()),
+            PolicyOutcome::Deny => {
+                if let Some(alerts) = &self.alerts {
+                    let alert = json!({
+                        "event": "policy_violation",
+                        "tool": tool,
+                        "rule": rule,
+                        "reason": decision.reason,
+                    });
+                    alerts
+                        .send(POLICY_TOPIC, AlertSeverity::Warning, &alert)
+                        .await;
+                }
+                let reason = decision
+                    .reason
+                    .map(|reason| format!(": {}", reason))
+                    .unwrap_or_default();
+                Err(self
+                    .locale
+                    .error(ErrorCode::PolicyDenied, &[tool, rule, &reason]))
+            }
+            PolicyOutcome::Confirm => {
+                let prompt = self
+                    .locale
+                    .policy_prompt(tool, rule, decision.reason.as_deref());
+                confirm(peer, self.locale, prompt).await
+            }
+        }
+    }
+
+    /// Check a tool call against the caller's tier, rate limit, policy and second
+    /// factor, then run it
+    async fn handle_call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let tier = self.caller_tier(&context.extensions)?;
+        if let Some(parts) = context.extensions.get::<axum::http::request::Parts>() {
+            self.check_rate_limit(&self.client_id(parts))?;
+        }
+        let mut args_value = json!(request.arguments.unwrap_or_default());
+        let one_time_code = take_one_time_code(&mut args_value);
+
+        // Prompts can depend on session state, such as the quote being executed
+        in_session(self.session_id(), async {
+            if let Some(tool) = self.find_tool(request.name.as_ref()) {
+                if tier >= tool.required_tier() {
+                    if let Some(policy) = &self.policy {
+                        let decision = policy.evaluate(tier, tool.as_ref(), &args_value).await;
+                        self.enforce(&context.peer, tool.name(), decision).await?;
+                    }
+                    if let Some(prompt) = tool.confirmation_prompt(&args_value) {
+                        confirm(&context.peer, self.locale, prompt).await?;
+                    }
+                    if let Some(totp) = &self.second_factor {
+                        if tool.moves_funds(&args_value) {
+                            let code = match one_time_code {
+                                Some(code) => code,
+                                None => {
+                                    ask_one_time_code(&context.peer, self.locale, tool.name())
+                                        .await?
+                                }
+                            };
+                            if !totp.verify(&code) {
+                                return Err(self.locale.error(ErrorCode::SecondFactorInvalid, &[]));
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.dispatch_as(tier, request.name.as_ref(), args_value)
+                .await
+        })
+        .await
+    }
+
+    /// Run a tool on behalf of a client, refusing tools above its tier
+    pub async fn dispatch_as(
+        &self,
+        tier: PermissionTier,
+        name: &str,
+        params_value: serde_json::Value,
+    ) -> Result<CallToolResult, McpError> {
+        if let Some(tool) = self.find_tool(name) {
+            if tier < tool.required_tier() {
+                return Err(self.locale.error(
+                    ErrorCode::ToolForbidden,
+                    &[name, tool.required_tier().as_str()],
+                ));
+            }
+        }
+        self.dispatch(name, params_value).await
+    }
+
+    /// Run a tool inside its category's concurrency budget
+    pub async fn dispatch(
+        &self,
+        name: &str,
+        params_value: serde_json::Value,
+    ) -> Result<CallToolResult, McpError> {
+        let tool = self
+            .find_tool(name)
+            .ok_or_else(|| self.locale.error(ErrorCode::UnknownTool, &[name]))?;
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| format!("Failed to serialize result: {}", e))?;
+        let started = Instant::now();
+        let result = self.run(tool.as_ref(), name, params_value).await;
+        self.usage.record(tool.name(), result.is_ok());
 
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let chain = self
+            .deployment
+            .as_ref()
+            .map(|deployment| deployment.chain_id);
+        let rpc_endpoint = self.rpc_endpoint.as_deref();
+        match &result {
+            Ok(_) => info!(
+                tool = tool.name(),
+                chain, rpc_endpoint, duration_ms, "tool call succeeded"
+            ),
+            Err(e) => warn!(
+                tool = tool.name(),
+                chain,
+                rpc_endpoint,
+                duration_ms,
+                error_code = e.data.as_ref().and_then(|data| data["code"].as_str()),
+                "tool call failed: {}",
+                e.message
+            ),
+        }
+        result
     }
 
-    async fn handle_swap_tokens(&self, params_value: serde_json::Value) -> Result<CallToolResult, String> {
-        let result = self.swap_tokens_tool
-            .execute(params_value)
+    async fn run(
+        &self,
+        tool: &dyn ToolTrait,
+        name: &str,
+        mut params_value: serde_json::Value,
+    ) -> Result<CallToolResult, McpError> {
+        // Checked against the listed schema so a bad call names the field at fault
+        // rather than surfacing whatever serde reports
+        let violations = validate(&with_explain_property(tool.input_schema()), &params_value);
+        if !violations.is_empty() {
+            let details: Vec<String> = violations.iter().map(ToString::to_string).collect();
+            let mut error = self
+                .locale
+                .error(ErrorCode::InvalidParams, &[name, &details.join("; ")]);
+            error.data = Some(json!({
+                "code": ErrorCode::InvalidParams.as_str(),
+                "violations": violations,
+            }));
+            return Err(error);
+        }
+
+        let _permit = self
+            .limiter
+            .acquire(tool.category())
+            .instrument(info_span!(
+                "tool.queue",
+                category = tool.category().as_str()
+            ))
             .await
-            .map_err(|e| format!("Failed to simulate swap: {}", e))?;
+            .map_err(|e| {
+                self.locale.error(
+                    ErrorCode::Saturated,
+                    &[
+                        e.category.as_str(),
+                        &e.limit.max_concurrency.to_string(),
+                        &e.limit.queue_depth.to_string(),
+                    ],
+                )
+            })?;
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| format!("Failed to serialize result: {}", e))?;
+        let explain = take_explain_flag(&mut params_value);
+        let span = info_span!(
+            "tool.execute",
+            tool = name,
+            category = tool.category().as_str(),
+            error = Empty
+        );
+        // A panicking tool fails its call rather than the client's session
+        let outcome = AssertUnwindSafe(tool.execute(params_value.clone()))
+            .catch_unwind()
+            .instrument(span.clone())
+            .await
+            .map_err(|panic| {
+                let detail = redact(&panic_message(panic.as_ref()));
+                span.record("error", detail.as_str());
+                error!(tool = name, "Tool {} panicked: {}", name, detail);
+                self.locale.error(ErrorCode::ToolPanicked, &[name, &detail])
+            })?;
+        let mut result = outcome.map_err(|e| {
+            span.record("error", redact(&e.to_string()).as_str());
+            let error = self
+                .locale
+                .error(ErrorCode::ToolFailed, &[name, &e.to_string()]);
+            McpError {
+                message: redact(&error.message).into(),
+                ..error
+            }
+        })?;
+        if explain {
+            let explanation = tool.explain(&params_value, &result);
+            if let Some(fields) = result.as_object_mut() {
+                fields.insert("explanation".to_string(), json!(explanation));
+            }
+        }
+
+        if let Some(scams) = &self.scam_list {
+            let warnings = scams.warnings(&params_value, &result);
+            if let (false, Some(fields)) = (warnings.is_empty(), result.as_object_mut()) {
+                fields.insert("scam_warnings".to_string(), json!(warnings));
+            }
+        }
+
+        if let Some(max_bytes) = self.max_response_bytes {
+            result = enforce_response_limit(result, max_bytes);
+        }
+
+        let json_str = serde_json::to_string_pretty(&result).map_err(|e| {
+            self.locale
+                .error(ErrorCode::SerializationFailed, &[&e.to_string()])
+        })?;
+
+        let attestation = match &self.attestor {
+            Some(attestor) => Some(attestor.attest(&json_str).await.map_err(|e| {
+                self.locale
+                    .error(ErrorCode::AttestationFailed, &[&e.to_string()])
+            })?),
+            None => None,
+        };
+
+        let mut result = CallToolResult::success(vec![Content::text(json_str)]);
+        if let Some(attestation) = attestation {
+            let mut meta = Meta::new();
+            meta.insert("attestation".to_string(), json!(attestation));
+            result.meta = Some(meta);
+        }
+        Ok(result)
+    }
+}
+
+/// The message a panic was raised with
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Have the user approve a tool call through MCP elicitation. Clients without
+/// elicitation support cannot approve, so the call is refused.
+async fn confirm(peer: &Peer<RoleServer>, locale: Locale, prompt: String) -> Result<(), McpError> {
+    if !peer.supports_elicitation() {
+        return Err(locale.error(ErrorCode::ConfirmationUnsupported, &[&prompt]));
+    }
+    let requested_schema = ElicitationSchema::builder()
+        .required_bool_property("confirm", |schema| {
+            schema.description(locale.text(Text::ConfirmField))
+        })
+        .build_unchecked();
+    let response = peer
+        .create_elicitation(CreateElicitationRequestParam {
+            message: prompt,
+            requested_schema,
+        })
+        .await
+        .map_err(|e| locale.error(ErrorCode::ConfirmationFailed, &[&e.to_string()]))?;
+
+    let confirmed = response.action == ElicitationAction::Accept
+        && response
+            .content
+            .as_ref()
+            .and_then(|content| content.get("confirm"))
+            .and_then(serde_json::Value::as_bool)
+            == Some(true);
+    if !confirmed {
+        return Err(locale.error(ErrorCode::NotConfirmed, &[]));
+    }
+    Ok(())
+}
 
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+/// Ask the user for their one-time code through MCP elicitation, for a call made
+/// without one
+async fn ask_one_time_code(
+    peer: &Peer<RoleServer>,
+    locale: Locale,
+    tool: &str,
+) -> Result<String, McpError> {
+    if !peer.supports_elicitation() {
+        return Err(locale.error(ErrorCode::SecondFactorRequired, &[tool]));
     }
+    let requested_schema = ElicitationSchema::builder()
+        .required_string_property("code", |schema| {
+            schema.description(locale.text(Text::OneTimeCodeField))
+        })
+        .build_unchecked();
+    let response = peer
+        .create_elicitation(CreateElicitationRequestParam {
+            message: locale.text(Text::OneTimeCodePrompt).to_string(),
+            requested_schema,
+        })
+        .await
+        .map_err(|e| locale.error(ErrorCode::ConfirmationFailed, &[&e.to_string()]))?;
+
+    response
+        .content
+        .filter(|_| response.action == ElicitationAction::Accept)
+        .as_ref()
+        .and_then(|content| content.get("code"))
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| locale.error(ErrorCode::SecondFactorRequired, &[tool]))
 }
 
 impl ServerHandler for McpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_logging()
+                .build(),
             server_info: Implementation::from_build_env(),
-            instructions: Some("Ethereum Trading MCP Server - Provides tools for querying balances, getting token prices, and simulating swaps on Ethereum".to_string()),
+            instructions: Some(self.locale.text(Text::Instructions).to_string()),
             ..Default::default()
         }
     }
@@ -92,70 +1543,36 @@ impl ServerHandler for McpServer {
     async fn initialize(
         &self,
         _request: InitializeRequestParam,
-        context: RequestContext<RoleServer>,
+        _context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, McpError> {
         info!("Client sent initialize request");
         Ok(self.get_info())
     }
 
-    async fn on_initialized(
-        &self,
-        _context: NotificationContext<RoleServer>,
-    ) {
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
         info!("Client sent initialized notification - server is ready for requests");
+        self.notifier.register(context.peer);
+    }
+
+    // Alerts are few and always delivered, so the requested level is not applied
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        info!("Client requested log level {:?}", request.level);
+        Ok(())
     }
 
     async fn list_tools(
         &self,
         _request: Option<PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, McpError> {
         info!("list_tools called");
+        let tier = self.caller_tier(&context.extensions)?;
         Ok(ListToolsResult {
-            tools: vec![
-                Tool {
-                    name: self.get_balance_tool.name().to_string().into(),
-                    description: Some(self.get_balance_tool.description().to_string().into()),
-                    input_schema: Arc::new(
-                        self.get_balance_tool.input_schema()
-                            .as_object()
-                            .unwrap()
-                            .clone()
-                    ),
-                    output_schema: None,
-                    annotations: None,
-                    title: None,
-                    icons: None,
-                },
-                Tool {
-                    name: self.get_token_price_tool.name().to_string().into(),
-                    description: Some(self.get_token_price_tool.description().to_string().into()),
-                    input_schema: Arc::new(
-                        self.get_token_price_tool.input_schema()
-                            .as_object()
-                            .unwrap()
-                            .clone()
-                    ),
-                    output_schema: None,
-                    annotations: None,
-                    title: None,
-                    icons: None,
-                },
-                Tool {
-                    name: self.swap_tokens_tool.name().to_string().into(),
-                    description: Some(self.swap_tokens_tool.description().to_string().into()),
-                    input_schema: Arc::new(
-                        self.swap_tokens_tool.input_schema()
-                            .as_object()
-                            .unwrap()
-                            .clone()
-                    ),
-                    output_schema: None,
-                    annotations: None,
-                    title: None,
-                    icons: None,
-                },
-            ],
+            tools: self.listed_tools(tier),
             next_cursor: None,
         })
     }
@@ -163,27 +1580,159 @@ impl ServerHandler for McpServer {
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
         info!("call_tool called: {}", request.name);
-        let args_value = json!(request.arguments.unwrap_or_default());
-        
-        match request.name.as_ref() {
-            "get_balance" => {
-                self.handle_get_balance(args_value).await
-                    .map_err(|e| McpError::internal_error(e, None))
+        let span = info_span!(
+            "mcp.call_tool",
+            otel.kind = "server",
+            tool = request.name.as_ref(),
+            traceparent = Empty,
+            error = Empty
+        );
+        // Over HTTP a caller's W3C trace context makes the call part of its trace
+        if let Some(traceparent) = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.headers.get("traceparent"))
+            .and_then(|value| value.to_str().ok())
+        {
+            span.record("traceparent", traceparent);
+        }
+        let result = self
+            .handle_call_tool(request, context)
+            .instrument(span.clone())
+            .await;
+        if let Err(e) = &result {
+            span.record("error", e.message.as_ref());
+        }
+        result
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut capabilities = RawResource::new(CAPABILITIES_RESOURCE_URI, "capabilities");
+        capabilities.description =
+            Some(self.locale.text(Text::CapabilitiesDescription).to_string());
+        capabilities.mime_type = Some("application/json".to_string());
+        let mut resources = vec![capabilities.no_annotation()];
+
+        let tier = self.caller_tier(&context.extensions)?;
+        if tier >= PermissionTier::Trade && self.walletconnect.is_some() {
+            let mut pairing = RawResource::new(WALLETCONNECT_RESOURCE_URI, "walletconnect-pairing");
+            pairing.description =
+                Some(self.locale.text(Text::WalletConnectDescription).to_string());
+            pairing.mime_type = Some("application/json".to_string());
+            resources.push(pairing.no_annotation());
+        }
+
+        if tier >= PermissionTier::Admin {
+            let mut metrics = RawResource::new(METRICS_RESOURCE_URI, "tool-concurrency");
+            metrics.description = Some(self.locale.text(Text::MetricsDescription).to_string());
+            metrics.mime_type = Some("application/json".to_string());
+            resources.push(metrics.no_annotation());
+        }
+
+        if self.watch_list.is_some() {
+            let mut events = RawResource::new(WATCH_EVENTS_RESOURCE_URI, "watch-events");
+            events.description = Some(self.locale.text(Text::WatchEventsDescription).to_string());
+            events.mime_type = Some("application/json".to_string());
+            resources.push(events.no_annotation());
+        }
+
+        if let Some(requests) = &self.payment_requests {
+            for request in requests.open() {
+                let mut resource = RawResource::new(&request.resource_uri, &request.request_id);
+                resource.description = Some(
+                    self.locale
+                        .text(Text::PaymentRequestDescription)
+                        .to_string(),
+                );
+                resource.mime_type = Some("application/json".to_string());
+                resources.push(resource.no_annotation());
             }
-            "get_token_price" => {
-                self.handle_get_token_price(args_value).await
-                    .map_err(|e| McpError::internal_error(e, None))
+        }
+
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let tier = self.caller_tier(&context.extensions)?;
+        let body = match request.uri.as_str() {
+            CAPABILITIES_RESOURCE_URI => serde_json::to_string_pretty(&self.capabilities(tier)),
+            METRICS_RESOURCE_URI if tier < PermissionTier::Admin => {
+                return Err(self.locale.error(
+                    ErrorCode::ResourceForbidden,
+                    &[METRICS_RESOURCE_URI, PermissionTier::Admin.as_str()],
+                ))
+            }
+            METRICS_RESOURCE_URI => serde_json::to_string_pretty(&self.limiter.stats()),
+            WALLETCONNECT_RESOURCE_URI => {
+                let Some(signer) = &self.walletconnect else {
+                    return Err(self
+                        .locale
+                        .error(ErrorCode::ResourceNotFound, &[&request.uri]));
+                };
+                if tier < PermissionTier::Trade {
+                    return Err(self.locale.error(
+                        ErrorCode::ResourceForbidden,
+                        &[WALLETCONNECT_RESOURCE_URI, PermissionTier::Trade.as_str()],
+                    ));
+                }
+                match signer.status().await {
+                    Ok(status) => serde_json::to_string_pretty(&status),
+                    Err(e) => {
+                        return Err(self.locale.error(
+                            ErrorCode::ToolFailed,
+                            &[WALLETCONNECT_RESOURCE_URI, &format!("{:#}", e)],
+                        ))
+                    }
+                }
+            }
+            WATCH_EVENTS_RESOURCE_URI => {
+                let Some(watches) = &self.watch_list else {
+                    return Err(self
+                        .locale
+                        .error(ErrorCode::ResourceNotFound, &[&request.uri]));
+                };
+                serde_json::to_string_pretty(&json!({
+                    "watched": watches.watched(),
+                    "events": watches.events_since(0, None),
+                }))
+            }
+            uri if uri.starts_with(PAYMENT_REQUEST_RESOURCE_PREFIX) => {
+                match self
+                    .payment_requests
+                    .as_ref()
+                    .and_then(|requests| requests.by_resource_uri(uri))
+                {
+                    Some(payment_request) => serde_json::to_string_pretty(&payment_request),
+                    None => {
+                        return Err(self
+                            .locale
+                            .error(ErrorCode::ResourceNotFound, &[&request.uri]))
+                    }
+                }
             }
-            "swap_tokens" => {
-                self.handle_swap_tokens(args_value).await
-                    .map_err(|e| McpError::internal_error(e, None))
+            _ => {
+                return Err(self
+                    .locale
+                    .error(ErrorCode::ResourceNotFound, &[&request.uri]))
             }
-            _ => Err(McpError::invalid_params(format!("Unknown tool: {}", request.name), None)),
         }
+        .map_err(|e| {
+            self.locale
+                .error(ErrorCode::SerializationFailed, &[&e.to_string()])
+        })?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(body, request.uri)],
+        })
     }
 }
-
-
@@ -0,0 +1,67 @@
+use crate::tools::sessions::SessionState;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Hands out ids to client sessions and clears their tool state when they end
+#[derive(Default)]
+pub struct SessionManager {
+    next_id: AtomicU64,
+    open: Mutex<HashSet<String>>,
+    states: Mutex<Vec<Arc<dyn SessionState>>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear `state`'s entry for each session that ends
+    pub fn register(&self, state: Arc<dyn SessionState>) {
+        self.states.lock().unwrap().push(state);
+    }
+
+    /// Start a session, which ends when the returned handle is dropped
+    pub fn open(self: &Arc<Self>) -> Session {
+        let id = format!("s{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        self.open.lock().unwrap().insert(id.clone());
+        info!(session = %id, "session opened");
+        Session {
+            id,
+            manager: Arc::clone(self),
+        }
+    }
+
+    /// Sessions that have not ended yet
+    pub fn open_sessions(&self) -> usize {
+        self.open.lock().unwrap().len()
+    }
+
+    fn close(&self, id: &str) {
+        self.open.lock().unwrap().remove(id);
+        let states = self.states.lock().unwrap().clone();
+        for state in states {
+            state.end_session(id);
+        }
+        info!(session = %id, "session closed");
+    }
+}
+
+/// A client's session; its tool state is cleared once the handle is dropped
+pub struct Session {
+    id: String,
+    manager: Arc<SessionManager>,
+}
+
+impl Session {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.manager.close(&self.id);
+    }
+}
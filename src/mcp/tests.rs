@@ -0,0 +1,944 @@
+use crate::config::{ApiToken, ConcurrencyLimit};
+use crate::ethereum::MockEthereumClient;
+use crate::mcp::http::LimitedListener;
+use crate::mcp::{
+    Attestation, Attestor, Authenticator, ConcurrencyLimiter, Deployment, ErrorCode, Locale,
+    McpServer, Policy, PolicyEngine, PolicyOutcome, RateLimit, RateLimiter, SessionManager, Totp,
+};
+use crate::tools::explain::with_explain_property;
+use crate::tools::{
+    BatchTransferTool, ExecutePlanTool, GetBalanceTool, PermissionTier, Tool, ToolCategory,
+};
+use axum::http::{header, HeaderMap};
+use axum::serve::Listener;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+fn single_slot_limiter(queue_depth: usize) -> ConcurrencyLimiter {
+    ConcurrencyLimiter::new(|_| ConcurrencyLimit {
+        max_concurrency: 1,
+        queue_depth,
+    })
+}
+
+fn stats_for(limiter: &ConcurrencyLimiter, category: ToolCategory) -> (usize, usize, u64, u64) {
+    let stats = limiter
+        .stats()
+        .into_iter()
+        .find(|s| s.category == category)
+        .unwrap();
+    (
+        stats.in_flight,
+        stats.queued,
+        stats.completed,
+        stats.rejected,
+    )
+}
+
+#[tokio::test]
+async fn test_limiter_rejects_when_queue_full() {
+    let limiter = single_slot_limiter(0);
+
+    let permit = limiter.acquire(ToolCategory::Heavy).await.unwrap();
+    let rejected = limiter.acquire(ToolCategory::Heavy).await;
+
+    assert!(rejected.is_err());
+    assert!(rejected.err().unwrap().to_string().contains("heavy"));
+    assert_eq!(stats_for(&limiter, ToolCategory::Heavy), (1, 0, 0, 1));
+
+    // Other categories have their own budget
+    assert!(limiter.acquire(ToolCategory::Read).await.is_ok());
+
+    drop(permit);
+    assert_eq!(stats_for(&limiter, ToolCategory::Heavy), (0, 0, 1, 1));
+}
+
+#[tokio::test]
+async fn test_limiter_queues_until_slot_frees() {
+    let limiter = Arc::new(single_slot_limiter(1));
+
+    let permit = limiter.acquire(ToolCategory::Pricing).await.unwrap();
+
+    let waiter = {
+        let limiter = Arc::clone(&limiter);
+        tokio::spawn(async move { limiter.acquire(ToolCategory::Pricing).await.is_ok() })
+    };
+
+    // Let the waiter enqueue
+    while stats_for(&limiter, ToolCategory::Pricing).1 == 0 {
+        tokio::task::yield_now().await;
+    }
+    assert!(limiter.acquire(ToolCategory::Pricing).await.is_err());
+
+    drop(permit);
+    assert!(waiter.await.unwrap());
+    assert_eq!(stats_for(&limiter, ToolCategory::Pricing), (0, 0, 2, 1));
+}
+
+#[tokio::test]
+async fn test_dispatch_runs_tool() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mock_client = MockEthereumClient::new().with_eth_balance(wallet_addr, Decimal::new(2, 0));
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(Arc::new(mock_client)))];
+    let server = McpServer::with_tools(tools, single_slot_limiter(0));
+
+    let result = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045" }),
+        )
+        .await
+        .unwrap();
+    let text = result.content[0].as_text().unwrap().text.clone();
+    assert!(text.contains("\"balance\": \"2\""));
+
+    let (in_flight, _, completed, _) = stats_for(server.limiter(), ToolCategory::Read);
+    assert_eq!((in_flight, completed), (0, 1));
+}
+
+#[tokio::test]
+async fn test_dispatch_flags_scam_addresses() {
+    use crate::tools::ScamList;
+
+    let scammer: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mock_client = MockEthereumClient::new().with_eth_balance(scammer, Decimal::new(2, 0));
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(Arc::new(mock_client)))];
+    let server = McpServer::with_tools(tools, single_slot_limiter(0)).with_scam_list(Arc::new(
+        ScamList::new(Vec::new()).with_addresses("scamsniffer", [scammer]),
+    ));
+
+    let result = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": format!("{:?}", scammer) }),
+        )
+        .await
+        .unwrap();
+    let text = result.content[0].as_text().unwrap().text.clone();
+    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(value["balance"], "2");
+    assert_eq!(value["scam_warnings"][0]["address"], json!(scammer));
+    assert_eq!(value["scam_warnings"][0]["sources"], json!(["scamsniffer"]));
+    assert_eq!(value["scam_warnings"][0]["found_in"], "input");
+
+    let result = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": format!("{:?}", Address::repeat_byte(1)) }),
+        )
+        .await
+        .unwrap();
+    assert!(!result.content[0]
+        .as_text()
+        .unwrap()
+        .text
+        .contains("scam_warnings"));
+}
+
+#[tokio::test]
+async fn test_dispatch_unknown_tool() {
+    let server = McpServer::with_tools(vec![], single_slot_limiter(0));
+
+    let result = server.dispatch("does_not_exist", json!({})).await;
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().message.contains("Unknown tool"));
+}
+
+#[tokio::test]
+async fn test_localized_messages_keep_error_codes() {
+    let english = McpServer::with_tools(vec![], single_slot_limiter(0));
+    let chinese = McpServer::with_tools(vec![], single_slot_limiter(0)).with_locale(Locale::Zh);
+
+    let en_err = english
+        .dispatch("does_not_exist", json!({}))
+        .await
+        .unwrap_err();
+    let zh_err = chinese
+        .dispatch("does_not_exist", json!({}))
+        .await
+        .unwrap_err();
+    assert_eq!(en_err.message, "Unknown tool: does_not_exist");
+    assert_eq!(zh_err.message, "未知工具：does_not_exist");
+    assert_eq!(en_err.code, zh_err.code);
+    assert_eq!(en_err.data, Some(json!({ "code": "unknown_tool" })));
+    assert_eq!(zh_err.data, en_err.data);
+
+    let tool = GetBalanceTool::new(Arc::new(MockEthereumClient::new()));
+    assert_eq!(english.tool_description(&tool), tool.description());
+    assert!(chinese.tool_description(&tool).contains("余额"));
+
+    assert_eq!("zh-CN".parse::<Locale>().unwrap(), Locale::Zh);
+    assert_eq!("en_US".parse::<Locale>().unwrap(), Locale::En);
+    assert!("fr".parse::<Locale>().is_err());
+}
+
+#[tokio::test]
+async fn test_tool_aliases() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let client = MockEthereumClient::new().with_eth_balance(wallet_addr, Decimal::new(2, 0));
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(Arc::new(client)))];
+    let alias = vec![("eth_getBalance".to_string(), "get_balance".to_string())];
+
+    let server = McpServer::with_tools(tools.clone(), single_slot_limiter(0))
+        .with_aliases(alias.clone(), false)
+        .unwrap();
+    let result = server
+        .dispatch(
+            "eth_getBalance",
+            json!({ "address": format!("{:?}", wallet_addr) }),
+        )
+        .await
+        .unwrap();
+    let text = result.content[0].as_text().unwrap().text.clone();
+    assert!(text.contains("\"balance\": \"2\""));
+
+    let names = |server: &McpServer| -> Vec<String> {
+        server
+            .listed_tools(PermissionTier::Admin)
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    };
+    assert_eq!(names(&server), ["get_balance"]);
+
+    let listed = McpServer::with_tools(tools.clone(), single_slot_limiter(0))
+        .with_aliases(alias, true)
+        .unwrap();
+    assert_eq!(names(&listed), ["get_balance", "eth_getBalance"]);
+    let entry = &listed.listed_tools(PermissionTier::Admin)[1];
+    assert!(entry
+        .description
+        .as_deref()
+        .unwrap()
+        .starts_with("Alias of get_balance."));
+
+    // Aliases cannot point nowhere or hide a real tool
+    let dangling = vec![("check_price".to_string(), "get_token_price".to_string())];
+    assert!(McpServer::with_tools(tools.clone(), single_slot_limiter(0))
+        .with_aliases(dangling, false)
+        .is_err());
+    let shadowing = vec![("get_balance".to_string(), "get_balance".to_string())];
+    assert!(McpServer::with_tools(tools, single_slot_limiter(0))
+        .with_aliases(shadowing, false)
+        .is_err());
+}
+
+fn tiered_server() -> McpServer {
+    let client = Arc::new(MockEthereumClient::new());
+    let tools: Vec<Arc<dyn Tool>> = vec![
+        Arc::new(GetBalanceTool::new(Arc::clone(&client))),
+        Arc::new(ExecutePlanTool::new(client, false)),
+    ];
+    McpServer::with_tools(tools, single_slot_limiter(0))
+}
+
+#[test]
+fn test_tools_filtered_by_tier() {
+    let server = tiered_server();
+
+    let names = |tier| {
+        server
+            .tools_for_tier(tier)
+            .iter()
+            .map(|tool| tool.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(PermissionTier::ReadOnly), vec!["get_balance"]);
+    assert_eq!(
+        names(PermissionTier::Trade),
+        vec!["get_balance", "execute_plan"]
+    );
+    assert_eq!(names(PermissionTier::Admin).len(), 2);
+}
+
+#[tokio::test]
+async fn test_capabilities_report_access_and_usage() {
+    let deployment = Deployment {
+        chain_id: 1,
+        price_venues: vec!["uniswap_v2".to_string()],
+        execution_enabled: true,
+        ..Default::default()
+    };
+    let server = tiered_server()
+        .with_aliases(
+            vec![
+                ("eth_getBalance".to_string(), "get_balance".to_string()),
+                ("run_plan".to_string(), "execute_plan".to_string()),
+            ],
+            false,
+        )
+        .unwrap()
+        .with_deployment(deployment.clone());
+
+    let address = format!("{:?}", Address::repeat_byte(0x11));
+    server
+        .dispatch("get_balance", json!({ "address": address }))
+        .await
+        .unwrap();
+    server
+        .dispatch("eth_getBalance", json!({ "address": address }))
+        .await
+        .unwrap();
+    assert!(server
+        .dispatch("get_balance", json!({ "address": "not-an-address" }))
+        .await
+        .is_err());
+
+    let read_only = server.capabilities(PermissionTier::ReadOnly);
+    assert_eq!(read_only.deployment, Some(deployment));
+    assert!(read_only.writes.execution_enabled);
+    assert!(!read_only.writes.caller_can_send);
+    assert_eq!(read_only.tools.len(), 1);
+    assert_eq!(read_only.tools[0].name, "get_balance");
+    assert_eq!(
+        (
+            read_only.tools[0].usage.calls,
+            read_only.tools[0].usage.errors
+        ),
+        (3, 1)
+    );
+    assert_eq!(read_only.aliases.len(), 1);
+    assert_eq!(read_only.concurrency.len(), ToolCategory::ALL.len());
+
+    let trade = server.capabilities(PermissionTier::Trade);
+    assert!(trade.writes.caller_can_send);
+    assert_eq!(trade.tools[1].name, "execute_plan");
+    assert_eq!(trade.tools[1].usage.calls, 0);
+
+    let body = serde_json::to_value(&trade).unwrap();
+    assert_eq!(body["writes"]["caller_tier"], "trade");
+    assert_eq!(body["tools"][0]["calls"], 3);
+    assert_eq!(body["aliases"]["run_plan"], "execute_plan");
+}
+
+#[tokio::test]
+async fn test_dispatch_as_rejects_tool_above_tier() {
+    let server = tiered_server();
+    let params = json!({ "plan": { "steps": [{ "action": "wrap", "amount": "1" }] } });
+
+    let rejected = server
+        .dispatch_as(PermissionTier::ReadOnly, "execute_plan", params.clone())
+        .await;
+    assert!(rejected.unwrap_err().message.contains("'trade'"));
+
+    assert!(server
+        .dispatch_as(PermissionTier::Trade, "execute_plan", params)
+        .await
+        .is_ok());
+}
+
+#[test]
+fn test_authenticator_resolves_bearer_tokens() {
+    let auth = Authenticator::new(vec![
+        ApiToken {
+            token: "reader-token-123".into(),
+            tier: PermissionTier::ReadOnly,
+        },
+        ApiToken {
+            token: "trader-token-456".into(),
+            tier: PermissionTier::Trade,
+        },
+    ]);
+    let headers = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        headers
+    };
+
+    assert_eq!(
+        auth.tier_for_headers(&headers("Bearer trader-token-456")),
+        Some(PermissionTier::Trade)
+    );
+    assert_eq!(
+        auth.tier_for_headers(&headers("Bearer reader-token-123")),
+        Some(PermissionTier::ReadOnly)
+    );
+    assert_eq!(auth.tier_for_headers(&headers("Bearer wrong-token")), None);
+    assert_eq!(auth.tier_for_headers(&headers("trader-token-456")), None);
+    assert_eq!(auth.tier_for_headers(&HeaderMap::new()), None);
+
+    // Clients are told apart by which token they hold, never by the token itself
+    assert_eq!(
+        auth.token_label(&headers("Bearer trader-token-456")),
+        Some("token-2".to_string())
+    );
+    assert_eq!(auth.token_label(&headers("Bearer wrong-token")), None);
+
+    // Without configured tokens every caller is trusted
+    assert_eq!(
+        Authenticator::default().tier_for_headers(&HeaderMap::new()),
+        Some(PermissionTier::Admin)
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limiter_allows_burst_then_refills() {
+    // 100 calls a second, so the test only waits a few milliseconds for a refill
+    let limiter = RateLimiter::new(RateLimit {
+        calls_per_minute: 6_000,
+        burst: 3,
+    });
+
+    for _ in 0..3 {
+        assert!(limiter.check("token-1").is_ok());
+    }
+    let wait = limiter.check("token-1").unwrap_err();
+    assert!(wait <= Duration::from_millis(10));
+    // Each client has its own allowance
+    assert!(limiter.check("token-2").is_ok());
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(limiter.check("token-1").is_ok());
+}
+
+#[tokio::test]
+async fn test_rate_limited_calls_carry_retry_after() {
+    let client = Arc::new(MockEthereumClient::new());
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(client))];
+    let server = McpServer::with_tools(tools, single_slot_limiter(0)).with_rate_limit(RateLimit {
+        calls_per_minute: 2,
+        burst: 1,
+    });
+
+    assert!(server.check_rate_limit("127.0.0.1").is_ok());
+    let error = server.check_rate_limit("127.0.0.1").unwrap_err();
+    assert!(error.message.contains("2 tool calls per minute"));
+    let data = error.data.unwrap();
+    assert_eq!(data["code"], "rate_limited");
+    assert_eq!(data["status"], 429);
+    assert_eq!(data["retry_after_secs"], 30);
+
+    // A saturated category is reported with the same status
+    let saturated = Locale::En.error(ErrorCode::Saturated, &["heavy", "1", "0"]);
+    // The counts are the configured limits, not live occupancy
+    assert!(saturated.message.contains("limit 1 running, 0 queued"));
+    assert_eq!(saturated.data.unwrap()["status"], 429);
+}
+
+#[tokio::test]
+async fn test_limited_listener_holds_connections_past_the_cap() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut listener = LimitedListener::new(listener, Some(1));
+
+    let _first_client = TcpStream::connect(addr).await.unwrap();
+    let (first, _) = listener.accept().await;
+    assert_eq!(listener.available(), Some(0));
+
+    // The second client waits in the backlog until the first connection closes
+    let _second_client = TcpStream::connect(addr).await.unwrap();
+    let waiting = tokio::time::timeout(Duration::from_millis(50), listener.accept()).await;
+    assert!(waiting.is_err());
+
+    drop(first);
+    let accepted = tokio::time::timeout(Duration::from_secs(5), listener.accept()).await;
+    assert!(accepted.is_ok());
+    assert_eq!(listener.available(), Some(0));
+}
+
+const ATTESTATION_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+#[test]
+fn test_attestation_verifies_and_detects_tampering() {
+    let client = Arc::new(MockEthereumClient::new());
+    let attestor = Attestor::new(ATTESTATION_KEY, 1, client).unwrap();
+    let payload = r#"{"price_usd": "3000"}"#;
+
+    let attestation = attestor.sign(payload, 19_000_000).unwrap();
+    assert_eq!(attestation.block_number, 19_000_000);
+    assert_eq!(attestation.verify(payload).unwrap(), attestor.signer());
+
+    assert!(attestation.verify(r#"{"price_usd": "3001"}"#).is_err());
+
+    // Moving the block context invalidates the signature
+    let mut moved = attestation.clone();
+    moved.block_number += 1;
+    assert!(moved.verify(payload).is_err());
+}
+
+// RFC 6238 test key "12345678901234567890"
+const TOTP_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+#[test]
+fn test_totp_codes_match_rfc_6238_and_are_single_use() {
+    let totp = Totp::new(TOTP_SECRET).unwrap();
+    assert_eq!(totp.code_at(59), "287082");
+    assert_eq!(totp.code_at(1_111_111_109), "081804");
+    assert!(Totp::new("not base32!").is_err());
+    assert!(Totp::new("GEZDGNBV").is_err());
+
+    let now = 1_111_111_109;
+    assert!(!totp.verify_at("000000", now));
+    // A code from the previous step is still taken, to absorb clock drift
+    assert!(totp.verify_at(&totp.code_at(now - 30), now));
+    assert!(!totp.verify_at(&totp.code_at(now - 30), now));
+    assert!(totp.verify_at(&totp.code_at(now), now));
+    assert!(!totp.verify_at(&totp.code_at(now), now + 1));
+    assert!(!totp.verify_at(&totp.code_at(now + 300), now + 30));
+}
+
+#[test]
+fn test_second_factor_gates_live_write_calls() {
+    let client = Arc::new(MockEthereumClient::new());
+    let execute = ExecutePlanTool::new(Arc::clone(&client), true);
+    assert!(!execute.moves_funds(&json!({ "plan": { "steps": [] } })));
+    assert!(execute.moves_funds(&json!({ "plan": { "steps": [] }, "dry_run": false })));
+    assert!(!GetBalanceTool::new(client).moves_funds(&json!({ "dry_run": false })));
+
+    let server = tiered_server().with_second_factor(Totp::new(TOTP_SECRET).unwrap());
+    let schema = |name: &str| {
+        server
+            .listed_tools(PermissionTier::Trade)
+            .into_iter()
+            .find(|tool| tool.name == name)
+            .unwrap()
+            .input_schema["properties"]
+            .clone()
+    };
+    assert!(schema("execute_plan").get("one_time_code").is_some());
+    assert!(schema("get_balance").get("one_time_code").is_none());
+    assert!(
+        server
+            .capabilities(PermissionTier::Trade)
+            .writes
+            .one_time_code_required
+    );
+}
+
+const POLICY: &str = r#"
+default: allow
+rules:
+  - id: readers-cannot-plan
+    roles: [read_only]
+    tools: [execute_plan]
+    outcome: deny
+  - id: no-night-sends
+    tools: ["execute_*"]
+    hours: "22:00-06:00"
+    outcome: deny
+    reason: Sends are paused overnight
+  - id: blocked-recipient
+    recipients: ["0x00000000000000000000000000000000000000ba"]
+    outcome: deny
+  - id: large-batches
+    tools: [batch_transfer]
+    min_notional_usd: 10000
+    outcome: confirm
+"#;
+
+#[tokio::test]
+async fn test_policy_rules_match_in_order() {
+    use crate::ethereum::{ChainPricing, MockUniswapRouter};
+
+    assert!(Policy::from_yaml("rules:\n  - id: a\n    outcome: deny\n    colour: red").is_err());
+    assert!(Policy::from_yaml(
+        "rules:\n  - id: a\n    outcome: deny\n  - id: a\n    outcome: allow"
+    )
+    .is_err());
+    assert!(Policy::from_yaml("rules:\n  - id: a\n    hours: 22-25\n    outcome: deny").is_err());
+
+    let (weth, usdc) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+    let client = Arc::new(MockEthereumClient::new());
+    let pricing = ChainPricing {
+        venue: "uniswap_v2".to_string(),
+        router: Arc::new(MockUniswapRouter::new().with_price(weth, usdc, Decimal::new(3, 9))),
+        wrapped_native: weth,
+        usd_token: usdc,
+        usd_decimals: 6,
+    };
+    let policy = Policy::from_yaml(POLICY).unwrap();
+    let priced = PolicyEngine::new(policy.clone()).with_pricing(client.clone(), pricing);
+    let unpriced = PolicyEngine::new(policy);
+
+    let execute = ExecutePlanTool::new(client.clone(), true);
+    let batch = BatchTransferTool::new(client.clone(), Address::repeat_byte(0xd1), true);
+    let balance = GetBalanceTool::new(client);
+    let noon = 12 * 3600;
+    let plan = |to: &str| json!({ "plan": { "steps": [{ "action": "transfer", "to": to, "amount": "1" }] } });
+    let safe = "0x00000000000000000000000000000000000000aa";
+    let blocked = "0x00000000000000000000000000000000000000BA";
+
+    let rule = |decision: crate::mcp::PolicyDecision| (decision.outcome, decision.rule_id);
+    let denied = |id: &str| (PolicyOutcome::Deny, Some(id.to_string()));
+    assert_eq!(
+        rule(
+            priced
+                .evaluate_at(PermissionTier::ReadOnly, &execute, &plan(safe), noon)
+                .await
+        ),
+        denied("readers-cannot-plan")
+    );
+    assert_eq!(
+        rule(
+            priced
+                .evaluate_at(PermissionTier::Trade, &execute, &plan(safe), 23 * 3600)
+                .await
+        ),
+        denied("no-night-sends")
+    );
+    assert_eq!(
+        rule(
+            priced
+                .evaluate_at(PermissionTier::Trade, &execute, &plan(blocked), noon)
+                .await
+        ),
+        denied("blocked-recipient")
+    );
+    assert_eq!(
+        rule(
+            priced
+                .evaluate_at(PermissionTier::Trade, &execute, &plan(safe), noon)
+                .await
+        ),
+        (PolicyOutcome::Allow, None)
+    );
+
+    // 5 ETH at $3000 is over the confirmation threshold; 1 ETH is not
+    let payments = |amounts: &[&str]| {
+        json!({
+            "payments": amounts
+                .iter()
+                .map(|amount| json!({ "to": safe, "amount": amount }))
+                .collect::<Vec<_>>(),
+            "dry_run": false
+        })
+    };
+    let large = payments(&["3", "2"]);
+    let decision = priced
+        .evaluate_at(PermissionTier::Trade, &batch, &large, noon)
+        .await;
+    assert_eq!(decision.outcome, PolicyOutcome::Confirm);
+    assert_eq!(decision.rule_id.as_deref(), Some("large-batches"));
+    let small = payments(&["1"]);
+    assert_eq!(
+        priced
+            .evaluate_at(PermissionTier::Trade, &batch, &small, noon)
+            .await
+            .outcome,
+        PolicyOutcome::Allow
+    );
+
+    // Without a price, a live send meets the threshold rule but a dry run does not
+    assert_eq!(
+        unpriced
+            .evaluate_at(PermissionTier::Trade, &batch, &small, noon)
+            .await
+            .outcome,
+        PolicyOutcome::Confirm
+    );
+    let mut dry_run = small.clone();
+    dry_run["dry_run"] = json!(true);
+    assert_eq!(
+        unpriced
+            .evaluate_at(PermissionTier::Trade, &batch, &dry_run, noon)
+            .await
+            .outcome,
+        PolicyOutcome::Allow
+    );
+    assert_eq!(
+        unpriced
+            .evaluate_at(
+                PermissionTier::ReadOnly,
+                &balance,
+                &json!({ "address": blocked }),
+                noon
+            )
+            .await
+            .outcome,
+        PolicyOutcome::Allow
+    );
+}
+
+#[tokio::test]
+async fn test_dispatch_explain_mode() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mock_client = MockEthereumClient::new().with_eth_balance(wallet_addr, Decimal::new(2, 0));
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(Arc::new(mock_client)))];
+    let server = McpServer::with_tools(tools, single_slot_limiter(0));
+
+    let plain = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": format!("{:?}", wallet_addr) }),
+        )
+        .await
+        .unwrap();
+    let text = plain.content[0].as_text().unwrap().text.clone();
+    assert!(!text.contains("explanation"));
+
+    // The flag is consumed by the server, so tools with strict params still accept it
+    let explained = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": format!("{:?}", wallet_addr), "explain": true }),
+        )
+        .await
+        .unwrap();
+    let text = explained.content[0].as_text().unwrap().text.clone();
+    let result: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(result["balance"], "2");
+    let narrative = result["explanation"]["narrative"].as_str().unwrap();
+    assert!(narrative.contains("eth_getBalance"));
+    assert!(narrative.contains("10^18"));
+    assert_eq!(
+        result["explanation"]["data"]["formula"],
+        "balance = raw_balance / 10^decimals"
+    );
+    assert!(result["explanation"]["data"]["inputs"]
+        .get("explain")
+        .is_none());
+
+    let schema = with_explain_property(json!({ "type": "object" }));
+    assert_eq!(schema["properties"]["explain"]["type"], "boolean");
+}
+
+#[tokio::test]
+async fn test_dispatch_attaches_attestation() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_eth_balance(wallet_addr, Decimal::new(2, 0))
+            .with_block_number(123),
+    );
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(client.clone()))];
+    let attestor = Attestor::new(ATTESTATION_KEY, 1, client).unwrap();
+    let signer = attestor.signer();
+    let server = McpServer::with_tools(tools, single_slot_limiter(0)).with_attestor(attestor);
+
+    let result = server
+        .dispatch(
+            "get_balance",
+            json!({ "address": format!("{:?}", wallet_addr) }),
+        )
+        .await
+        .unwrap();
+
+    let text = result.content[0].as_text().unwrap().text.clone();
+    let meta = result.meta.unwrap();
+    let attestation: Attestation =
+        serde_json::from_value(meta.get("attestation").unwrap().clone()).unwrap();
+    assert_eq!(attestation.block_number, 123);
+    assert_eq!(attestation.verify(&text).unwrap(), signer);
+}
+
+#[test]
+fn test_response_limit_trims_largest_array() {
+    use crate::mcp::response_limit::enforce_response_limit;
+
+    let events: Vec<_> = (0..1000).map(|i| json!({ "index": i })).collect();
+    let value = json!({ "address": "0xabc", "events": events, "tags": ["a", "b"] });
+
+    let trimmed = enforce_response_limit(value.clone(), 2048);
+
+    assert!(serde_json::to_string_pretty(&trimmed).unwrap().len() <= 2048);
+    assert_eq!(trimmed["truncated"], true);
+    assert_eq!(trimmed["address"], "0xabc");
+    assert_eq!(trimmed["truncated_fields"]["events"]["total"], 1000);
+    let returned = trimmed["truncated_fields"]["events"]["returned"]
+        .as_u64()
+        .unwrap();
+    assert!(returned > 0 && returned < 1000);
+    assert_eq!(trimmed["events"].as_array().unwrap().len() as u64, returned);
+
+    // Small results pass through untouched
+    assert_eq!(enforce_response_limit(value.clone(), usize::MAX), value);
+}
+
+#[test]
+fn test_response_limit_reports_untrimmable_result() {
+    use crate::mcp::response_limit::enforce_response_limit;
+
+    let value = json!({ "blob": "x".repeat(4096) });
+    let trimmed = enforce_response_limit(value, 1024);
+
+    assert_eq!(trimmed["truncated"], true);
+    assert_eq!(trimmed["max_bytes"], 1024);
+    assert!(trimmed.get("blob").is_none());
+}
+
+#[test]
+fn test_schema_validation_pinpoints_fields() {
+    use crate::mcp::validation::validate;
+
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "recipients": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "to": { "type": "string" },
+                        "amount": { "type": "string" }
+                    },
+                    "required": ["to", "amount"]
+                }
+            },
+            "speed": { "type": "string", "enum": ["slow", "fast"] },
+            "slippage_bps": { "type": "integer", "minimum": 0, "maximum": 10000 },
+            "memo": { "type": "string" }
+        },
+        "required": ["recipients"]
+    });
+
+    let messages = |params| -> Vec<String> {
+        validate(&schema, &params)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    };
+    assert!(messages(json!({ "recipients": [], "memo": null })).is_empty());
+    assert_eq!(messages(json!({})), vec!["$.recipients: is required"]);
+    assert_eq!(
+        messages(json!({
+            "recipients": [{ "to": "0xabc", "amount": "1" }, { "to": "0xdef", "amount": 2 }],
+            "speed": "turbo",
+            "slippage_bps": 20000
+        })),
+        vec![
+            "$.recipients[1].amount: expected string, got integer",
+            "$.slippage_bps: must be at most 10000, got 20000",
+            r#"$.speed: must be one of "slow", "fast", got "turbo""#,
+        ]
+    );
+    assert_eq!(
+        messages(json!({ "recipients": [{ "to": "0xabc" }], "slippage_bps": 0.5 })),
+        vec![
+            "$.recipients[0].amount: is required",
+            "$.slippage_bps: expected integer, got number",
+        ]
+    );
+
+    let either = json!({
+        "type": "object",
+        "properties": {
+            "token_address": { "type": "string" },
+            "token_symbol": { "type": "string" }
+        },
+        "oneOf": [{ "required": ["token_address"] }, { "required": ["token_symbol"] }]
+    });
+    assert!(validate(&either, &json!({ "token_symbol": "WETH" })).is_empty());
+    assert_eq!(
+        validate(&either, &json!({ "token_address": "0x1", "token_symbol": "WETH" }))[0].message,
+        "expected exactly one of token_address, token_symbol, but matches token_address and token_symbol"
+    );
+    assert_eq!(
+        validate(&either, &json!({}))[0].message,
+        "expected exactly one of token_address, token_symbol, got object"
+    );
+}
+
+#[tokio::test]
+async fn test_dispatch_rejects_params_breaking_the_schema() {
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(Arc::new(
+        MockEthereumClient::new(),
+    )))];
+    let server = McpServer::with_tools(tools.clone(), single_slot_limiter(0));
+
+    let err = server
+        .dispatch("get_balance", json!({ "address": 42, "explain": "yes" }))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.message,
+        "Invalid parameters for get_balance: $.address: expected string, got integer; $.explain: expected boolean, got string"
+    );
+    let data = err.data.unwrap();
+    assert_eq!(data["code"], "invalid_params");
+    assert_eq!(data["violations"][0]["path"], "$.address");
+
+    let zh = McpServer::with_tools(tools, single_slot_limiter(0)).with_locale(Locale::Zh);
+    let err = zh.dispatch("get_balance", json!({})).await.unwrap_err();
+    assert_eq!(
+        err.message,
+        "get_balance 的参数无效：$.address: is required"
+    );
+}
+
+#[tokio::test]
+async fn test_dispatch_contains_tool_panics() {
+    struct PanickingTool;
+
+    #[async_trait::async_trait]
+    impl Tool for PanickingTool {
+        fn name(&self) -> &str {
+            "panics"
+        }
+
+        fn description(&self) -> &str {
+            "Panics on any call"
+        }
+
+        fn input_schema(&self) -> serde_json::Value {
+            json!({ "type": "object", "properties": {} })
+        }
+
+        async fn execute(&self, _params: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+            let amounts: Vec<u64> = Vec::new();
+            Ok(json!(amounts[3]))
+        }
+    }
+
+    let server = McpServer::with_tools(vec![Arc::new(PanickingTool)], single_slot_limiter(0));
+
+    let err = server.dispatch("panics", json!({})).await.unwrap_err();
+    assert!(err
+        .message
+        .starts_with("Tool panics hit an internal error: index out of bounds"));
+    assert_eq!(err.data.unwrap()["code"], "tool_panicked");
+
+    // The panic released the tool's permit, so the server keeps answering
+    let err = server.dispatch("panics", json!({})).await.unwrap_err();
+    assert_eq!(err.data.unwrap()["code"], "tool_panicked");
+}
+
+#[tokio::test]
+async fn test_sessions_clear_state_when_they_end() {
+    use crate::tools::sessions::{current_session, in_session, SessionMap, LOCAL_SESSION};
+
+    let seen = Arc::new(SessionMap::<Vec<u32>>::new());
+    let sessions = Arc::new(SessionManager::new());
+    sessions.register(seen.clone());
+    let client = Arc::new(MockEthereumClient::new());
+    let tools: Vec<Arc<dyn Tool>> = vec![Arc::new(GetBalanceTool::new(client))];
+    let server = McpServer::with_tools(tools, single_slot_limiter(0)).with_sessions(sessions);
+    assert_eq!(server.session_id(), LOCAL_SESSION);
+
+    let first = server.open_session();
+    let second = server.open_session();
+    assert_ne!(first.session_id(), second.session_id());
+    assert_eq!(server.sessions().open_sessions(), 2);
+
+    in_session(first.session_id(), async { seen.with(|v| v.push(1)) }).await;
+    in_session(second.session_id(), async { seen.with(|v| v.push(2)) }).await;
+    let mine = in_session(first.session_id(), async {
+        assert_eq!(current_session(), first.session_id());
+        seen.with(|v| v.clone())
+    })
+    .await;
+    assert_eq!(mine, vec![1]);
+    assert_eq!(seen.sessions(), 2);
+
+    // Clones of a handler share its session, which ends with the last of them
+    let clone = first.clone();
+    drop(first);
+    assert_eq!(server.sessions().open_sessions(), 2);
+    drop(clone);
+    assert_eq!(server.sessions().open_sessions(), 1);
+    assert_eq!(seen.sessions(), 1);
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Call counters for one tool since the server started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct ToolCallStats {
+    pub calls: u64,
+    /// Calls that returned an error, including ones rejected as saturated
+    pub errors: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    calls: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Per-tool call counters, keyed by tool name. Calls made through an alias count
+/// toward the tool it names.
+#[derive(Default)]
+pub struct ToolUsage {
+    tools: HashMap<String, Counters>,
+}
+
+impl ToolUsage {
+    pub fn new<'a>(tools: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            tools: tools
+                .into_iter()
+                .map(|name| (name.to_string(), Counters::default()))
+                .collect(),
+        }
+    }
+
+    pub fn record(&self, tool: &str, succeeded: bool) {
+        if let Some(counters) = self.tools.get(tool) {
+            counters.calls.fetch_add(1, Ordering::Relaxed);
+            if !succeeded {
+                counters.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn stats(&self, tool: &str) -> ToolCallStats {
+        self.tools
+            .get(tool)
+            .map(|counters| ToolCallStats {
+                calls: counters.calls.load(Ordering::Relaxed),
+                errors: counters.errors.load(Ordering::Relaxed),
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,207 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A way a tool call's parameters break the tool's declared input schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaViolation {
+    /// Where the problem is, e.g. `$.recipients[1].amount`; `$` is the parameters object
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Check `params` against a tool's input schema, returning every violation found.
+///
+/// Covers the keywords tool schemas use: `type`, `enum`, `minimum`, `maximum`,
+/// `required`, `properties`, `additionalProperties`, `items` and `oneOf`. Optional
+/// properties set to `null` count as omitted, as they do when deserialized.
+pub fn validate(schema: &Value, params: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    check(schema, params, "$", &mut violations);
+    violations
+}
+
+fn check(schema: &Value, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let mut violation = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            violation(format!(
+                "expected {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            // Nothing else about the value means much once its type is wrong
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            violation(format!(
+                "must be one of {}, got {}",
+                allowed.join(", "),
+                value
+            ));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema
+            .get("minimum")
+            .filter(|bound| bound.as_f64().is_some_and(|bound| number < bound))
+        {
+            violation(format!("must be at least {}, got {}", minimum, value));
+        }
+        if let Some(maximum) = schema
+            .get("maximum")
+            .filter(|bound| bound.as_f64().is_some_and(|bound| number > bound))
+        {
+            violation(format!("must be at most {}, got {}", maximum, value));
+        }
+    }
+
+    if let Some(branches) = schema.get("oneOf").and_then(Value::as_array) {
+        let matched: Vec<&Value> = branches
+            .iter()
+            .filter(|branch| validate(branch, value).is_empty())
+            .collect();
+        if matched.len() != 1 {
+            let options: Vec<String> = branches.iter().map(describe_branch).collect();
+            let matched: Vec<String> = matched.into_iter().map(describe_branch).collect();
+            violation(if matched.is_empty() {
+                format!(
+                    "expected exactly one of {}, got {}",
+                    options.join(", "),
+                    type_name(value)
+                )
+            } else {
+                format!(
+                    "expected exactly one of {}, but matches {}",
+                    options.join(", "),
+                    matched.join(" and ")
+                )
+            });
+        }
+    }
+
+    match value {
+        Value::Object(fields) => check_object(schema, fields, path, violations),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(
+                        item_schema,
+                        item,
+                        &format!("{}[{}]", path, index),
+                        violations,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    path: &str,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    for name in &required {
+        if !fields.contains_key(*name) {
+            violations.push(SchemaViolation {
+                path: field_path(path, name),
+                message: "is required".to_string(),
+            });
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, field) in fields {
+        let field_schema = properties.and_then(|properties| properties.get(name));
+        if field.is_null() && !required.contains(&name.as_str()) {
+            continue;
+        }
+        match (field_schema, schema.get("additionalProperties")) {
+            (Some(field_schema), _) => {
+                check(field_schema, field, &field_path(path, name), violations)
+            }
+            (None, Some(Value::Bool(false))) => violations.push(SchemaViolation {
+                path: field_path(path, name),
+                message: "is not a known parameter".to_string(),
+            }),
+            (None, Some(extra @ Value::Object(_))) => {
+                check(extra, field, &field_path(path, name), violations)
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    format!("{}.{}", path, name)
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// How a `oneOf` branch is named in messages: its required fields or its type
+fn describe_branch(branch: &Value) -> String {
+    if let Some(required) = branch.get("required").and_then(Value::as_array) {
+        let names: Vec<&str> = required.iter().filter_map(Value::as_str).collect();
+        return names.join(" + ");
+    }
+    match branch.get("type") {
+        Some(Value::String(name)) => name.clone(),
+        _ => "an alternative".to_string(),
+    }
+}
@@ -0,0 +1,151 @@
+use crate::secrets::SecretString;
+use crate::tools::alerts::{AlertSeverity, AlertSink};
+use async_trait::async_trait;
+use data_encoding::HEXLOWER;
+use ethers::core::k256::sha2::Sha256;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Attempts after the first failed delivery, by default
+pub const DEFAULT_WEBHOOK_RETRIES: u32 = 3;
+/// Wait before the first retry, doubled for each one after
+pub const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` under the webhook secret
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Unix seconds the payload was signed at, for receivers to refuse replays
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POSTs alerts as JSON to configured URLs, signed when a secret is set.
+///
+/// Deliveries run in the background, so a slow or failing endpoint never holds up the
+/// tool or job that raised the alert. Failed deliveries are retried with doubling
+/// delays; client errors other than 429 are not retried.
+#[derive(Clone)]
+pub struct Webhooks {
+    http: reqwest::Client,
+    urls: Vec<String>,
+    /// Topics delivered; empty delivers every topic
+    topics: Vec<String>,
+    secret: Option<SecretString>,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl Webhooks {
+    pub fn new(urls: Vec<String>, secret: Option<SecretString>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            urls,
+            topics: Vec::new(),
+            secret,
+            max_retries: DEFAULT_WEBHOOK_RETRIES,
+            retry_delay: WEBHOOK_RETRY_DELAY,
+        }
+    }
+
+    /// Only deliver alerts of `topics`
+    pub fn with_topics(mut self, topics: Vec<String>) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Retry a failed delivery `max_retries` times, waiting `first_delay` and then
+    /// twice as long each time
+    pub fn with_retries(mut self, max_retries: u32, first_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = first_delay;
+        self
+    }
+
+    fn subscribed(&self, topic: &str) -> bool {
+        self.topics.is_empty() || self.topics.iter().any(|t| t == topic)
+    }
+
+    async fn deliver(&self, url: &str, body: &str, timestamp: u64) {
+        // Chat webhook URLs carry their credentials, so only the host is logged
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let mut delay = self.retry_delay;
+        for attempt in 0..=self.max_retries {
+            let mut request = self
+                .http
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .body(body.to_string());
+            if let Some(secret) = &self.secret {
+                request = request.header(
+                    SIGNATURE_HEADER,
+                    format!("sha256={}", sign(secret.expose_secret(), timestamp, body)),
+                );
+            }
+            let retry = match request.send().await {
+                Ok(response) if response.status().is_success() => return,
+                Ok(response) => {
+                    let status = response.status();
+                    warn!(host, %status, attempt, "Webhook endpoint refused delivery");
+                    status.is_server_error() || status.as_u16() == 429
+                }
+                Err(e) => {
+                    warn!(
+                        host,
+                        attempt,
+                        "Failed to deliver webhook: {}",
+                        e.without_url()
+                    );
+                    true
+                }
+            };
+            if !retry || attempt == self.max_retries {
+                break;
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        warn!(host, "Gave up delivering webhook");
+    }
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>` under `secret`
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    HEXLOWER.encode(&mac.finalize().into_bytes())
+}
+
+#[async_trait]
+impl AlertSink for Webhooks {
+    /// Queue the alert for every endpoint, returning how many it was queued for
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+        if !self.subscribed(topic) {
+            return 0;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let body = json!({
+            "topic": topic,
+            "severity": severity,
+            "timestamp": timestamp,
+            "data": alert,
+        })
+        .to_string();
+        for url in &self.urls {
+            let (webhooks, url, body) = (self.clone(), url.clone(), body.clone());
+            tokio::spawn(async move { webhooks.deliver(&url, &body, timestamp).await });
+        }
+        self.urls.len()
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::{store_env_file, Config};
+use crate::mcp::http::{serve_reloadable, LimitedListener};
+use crate::mcp::McpServer;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Load the `.env` file in the working directory or the nearest parent, if there is one
+pub fn load_env_file() {
+    let Ok(cwd) = env::current_dir() else {
+        return;
+    };
+    if let Some(path) = cwd
+        .ancestors()
+        .map(|dir| dir.join(".env"))
+        .find(|path| path.is_file())
+    {
+        if let Err(e) = load_env_file_from(&path) {
+            // Logging may not be up yet
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+/// Read the env file at `path` for configuration to fall back to, replacing what an
+/// earlier load read. The process environment is left untouched, so variables set
+/// outside the file keep taking precedence.
+pub fn load_env_file_from(path: &Path) -> Result<()> {
+    // Deprecated, but the one way to read the file without applying it
+    #[allow(deprecated)]
+    let vars = dotenv::from_path_iter(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    store_env_file(vars);
+    Ok(())
+}
+
+/// Holds the process id in a file while the service runs, and removes it on drop
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write this process's id to `path`. Refuses while the id already there is a
+    /// running process; a stale file from one that died is replaced.
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Ok(existing) = fs::read_to_string(path) {
+            if let Ok(pid) = existing.trim().parse::<u32>() {
+                if pid != std::process::id() && process_running(pid) {
+                    anyhow::bail!(
+                        "Already running as process {} (PID file {})",
+                        pid,
+                        path.display()
+                    );
+                }
+            }
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write PID file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Where there is no /proc, an existing PID file is always taken as stale
+fn process_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Send `state`, such as `READY=1`, to the service manager when started by systemd
+/// with `Type=notify`. Does nothing elsewhere.
+pub fn notify_systemd(state: &str) {
+    let Some(socket) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(e) = send_notification(Path::new(&socket), state) {
+        warn!("Could not notify systemd of {}: {}", state, e);
+    }
+}
+
+#[cfg(unix)]
+fn send_notification(socket: &Path, state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+    // A leading `@` names a socket in the abstract namespace
+    #[cfg(target_os = "linux")]
+    if let Some(name) = socket.to_str().and_then(|s| s.strip_prefix('@')) {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        return datagram.send_to_addr(state.as_bytes(), &addr).map(drop);
+    }
+    datagram.send_to(state.as_bytes(), socket).map(drop)
+}
+
+#[cfg(not(unix))]
+fn send_notification(_socket: &Path, _state: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM as sent by service managers
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+}
+
+/// Put back the `running` values of settings that take effect on restart, so `config`
+/// describes what is in effect. Returns whether `config` had changed any of them.
+pub(crate) fn keep_restart_settings(config: &mut Config, running: &Config) -> bool {
+    let changed = config.transport != running.transport
+        || config.http_max_connections != running.http_max_connections
+        || config.pid_file != running.pid_file
+        || config.log_format != running.log_format
+        || config.otlp != running.otlp;
+    config.transport = running.transport;
+    config.http_max_connections = running.http_max_connections;
+    config.pid_file = running.pid_file.clone();
+    config.log_format = running.log_format;
+    config.otlp = running.otlp.clone();
+    changed
+}
+
+/// Set up a server from the environment as it is now, after re-reading the `.env`
+/// file and the Vault secret. Settings the running service cannot change are kept
+/// from `running`.
+async fn reload(running: &Config) -> Result<(Config, McpServer)> {
+    load_env_file();
+    crate::vault::load_secrets()
+        .await
+        .context("Failed to read secrets from Vault")?;
+    let mut config = Config::from_env().context("Invalid configuration")?;
+    if keep_restart_settings(&mut config, running) {
+        warn!(
+            "Transport, connection, PID file, logging and tracing settings take effect on restart"
+        );
+    }
+    let server = McpServer::build(config.clone()).await?;
+    Ok((config, server))
+}
+
+/// Swap in a freshly configured server on every SIGHUP, keeping the running one
+/// when the new configuration fails
+#[cfg(unix)]
+async fn reload_on_hangup(mut config: Config, servers: watch::Sender<McpServer>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            warn!(
+                "Could not listen for SIGHUP; configuration will not reload: {}",
+                e
+            );
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        info!("Reloading configuration");
+        notify_systemd("RELOADING=1");
+        match reload(&config).await {
+            Ok((reloaded, server)) => {
+                let previous = servers.borrow().clone();
+                previous.stop_background_tasks().await;
+                server.start_background_tasks();
+                servers.send_replace(server);
+                config = reloaded;
+                info!("Configuration reloaded; new sessions use it");
+            }
+            Err(e) => error!("Reload failed, keeping the running configuration: {:#}", e),
+        }
+        notify_systemd("READY=1");
+    }
+}
+
+#[cfg(not(unix))]
+async fn reload_on_hangup(_config: Config, _servers: watch::Sender<McpServer>) {}
+
+/// Run as a long-lived HTTP service on `bind`: hold the PID file, tell systemd once
+/// the listener is up, reload configuration on SIGHUP, and stop on SIGTERM or Ctrl-C
+pub async fn run_http_service(config: Config, bind: SocketAddr) -> Result<()> {
+    let _pid_file = config
+        .pid_file
+        .as_deref()
+        .map(PidFile::create)
+        .transpose()?;
+
+    let server = McpServer::new(config.clone()).await?;
+    if !server.authenticator().is_enabled() {
+        warn!("MCP_AUTH_TOKENS is not set; loopback HTTP clients get the admin tier");
+    }
+    let (reloads, servers) = watch::channel(server);
+
+    let listener = TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("Failed to bind {}", bind))?;
+    info!("Server ready, listening on http://{}/mcp", bind);
+
+    let reloader = tokio::spawn(reload_on_hangup(config.clone(), reloads));
+    notify_systemd(&format!("READY=1\nMAINPID={}", std::process::id()));
+
+    let live = servers.clone();
+    let result = serve_reloadable(
+        servers,
+        LimitedListener::new(listener, config.http_max_connections),
+        async {
+            shutdown_signal().await;
+            info!("Shutting down");
+            notify_systemd("STOPPING=1");
+        },
+    )
+    .await;
+    reloader.abort();
+    // Let a scheduled payment or watch poll in progress finish
+    let server = live.borrow().clone();
+    server.stop_background_tasks().await;
+    result
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+use std::sync::RwLock;
+use tracing_subscriber::fmt::MakeWriter;
+
+const REDACTED: &str = "[REDACTED]";
+
+// Values shorter than this are too likely to collide with ordinary output
+const MIN_SECRET_LEN: usize = 8;
+
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// A string whose value is kept out of `Debug` and `Display` output
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The underlying value; only pass it to the code that needs it
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl std::fmt::Display for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Scrub `secret` from everything passed through `redact` from now on.
+///
+/// Hex values are matched case-insensitively, with or without a `0x` prefix.
+pub fn register_secret(secret: &SecretString) {
+    let value = secret.expose_secret().trim();
+    let value = value
+        .strip_prefix("0x")
+        .unwrap_or(value)
+        .to_ascii_lowercase();
+    if value.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|e| e.into_inner());
+    if !secrets.contains(&value) {
+        secrets.push(value);
+    }
+}
+
+/// Replace every registered secret in `text` with a placeholder
+pub fn redact(text: &str) -> String {
+    let secrets = SECRETS.read().unwrap_or_else(|e| e.into_inner());
+    let mut output = text.to_string();
+    for secret in secrets.iter() {
+        let mut cursor = 0;
+        // ASCII lowercasing keeps byte offsets aligned with the original
+        while let Some(offset) = output.to_ascii_lowercase()[cursor..].find(secret.as_str()) {
+            let found = cursor + offset;
+            let start = if output[..found].to_ascii_lowercase().ends_with("0x") {
+                found - 2
+            } else {
+                found
+            };
+            output.replace_range(start..found + secret.len(), REDACTED);
+            cursor = start + REDACTED.len();
+        }
+    }
+    output
+}
+
+/// Log writer that scrubs registered secrets before output
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+pub struct RedactingWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    // The fmt layer writes each event in one call, so secrets are never split
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,260 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Wait before restarting a task that stopped on its own, doubled after each restart
+pub const DEFAULT_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest wait between restarts, by default
+pub const DEFAULT_MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Spawns a job that runs until the flag it is given is set
+pub type Job = Box<dyn Fn(watch::Receiver<bool>) -> JoinHandle<()> + Send + Sync>;
+
+/// When a task that stopped on its own, by panicking or returning before it was
+/// told to, is started again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Wait before the first restart
+    pub backoff: Duration,
+    /// Longest wait; a task that ran at least this long restarts after `backoff` again
+    pub max_backoff: Duration,
+    /// Restarts in a row before the task is given up on; None restarts forever
+    pub max_restarts: Option<u32>,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            backoff: DEFAULT_RESTART_BACKOFF,
+            max_backoff: DEFAULT_MAX_RESTART_BACKOFF,
+            max_restarts: None,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Never restart: a task that stops stays failed
+    pub fn never() -> Self {
+        Self {
+            max_restarts: Some(0),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// Stopped on its own and waiting out the backoff
+    Restarting,
+    /// Stopped on its own more often than the restart policy allows
+    Failed,
+    /// Stopped because the server told it to
+    Stopped,
+}
+
+/// How a supervised task is doing, as reported by health_check
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    /// Restarts since the server started its tasks
+    pub restarts: u32,
+    /// Why it last stopped on its own
+    pub last_error: Option<String>,
+    /// Unix seconds it was last started at
+    pub started_at: Option<u64>,
+}
+
+/// A job the supervisor runs, and what to do when it stops on its own
+struct SupervisedJob {
+    name: String,
+    job: Arc<Job>,
+    policy: RestartPolicy,
+}
+
+/// Started tasks, and the flag that stops them
+struct RunningTasks {
+    stop: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+/// Runs a server's background jobs, such as the payment scheduler and address
+/// watcher, restarting any that panic or return early with a growing backoff.
+///
+/// Jobs run while the server is the live one. They are stopped before a reloaded
+/// server starts its own, so a scheduled payment is never sent by both, and when
+/// the process shuts down.
+#[derive(Default)]
+pub struct Supervisor {
+    jobs: Vec<SupervisedJob>,
+    health: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+    running: Mutex<Option<RunningTasks>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Supervise `job` under `name`, restarted as `policy` says
+    pub fn add<F>(&mut self, name: &str, policy: RestartPolicy, job: F)
+    where
+        F: Fn(watch::Receiver<bool>) -> JoinHandle<()> + Send + Sync + 'static,
+    {
+        self.jobs.push(SupervisedJob {
+            name: name.to_string(),
+            job: Arc::new(Box::new(job)),
+            policy,
+        });
+    }
+
+    /// Start every job, unless they are running already
+    pub fn start(&self) {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        if running.is_some() {
+            return;
+        }
+        let (stop, stopped) = watch::channel(false);
+        let handles = self
+            .jobs
+            .iter()
+            .map(|job| {
+                tokio::spawn(supervise(
+                    job.name.clone(),
+                    job.job.clone(),
+                    job.policy,
+                    self.health.clone(),
+                    stopped.clone(),
+                ))
+            })
+            .collect();
+        *running = Some(RunningTasks { stop, handles });
+    }
+
+    /// Stop every job, waiting for any run in progress to finish
+    pub async fn stop(&self) {
+        let running = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(running) = running {
+            let _ = running.stop.send(true);
+            futures::future::join_all(running.handles).await;
+        }
+    }
+
+    /// Health of every task, by name; tasks never started are left out
+    pub fn health(&self) -> Vec<TaskHealth> {
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health.values().cloned().collect()
+    }
+
+    /// Whether any task has been given up on or is waiting to restart
+    pub fn degraded(&self) -> bool {
+        self.health()
+            .iter()
+            .any(|task| matches!(task.state, TaskState::Failed | TaskState::Restarting))
+    }
+}
+
+/// Run `job` until `stop` is set, restarting it whenever it stops on its own
+async fn supervise(
+    name: String,
+    job: Arc<Job>,
+    policy: RestartPolicy,
+    health: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let update = |change: &dyn Fn(&mut TaskHealth)| {
+        let mut health = health.lock().unwrap_or_else(|e| e.into_inner());
+        let task = health.entry(name.clone()).or_insert_with(|| TaskHealth {
+            name: name.clone(),
+            state: TaskState::Running,
+            restarts: 0,
+            last_error: None,
+            started_at: None,
+        });
+        change(task);
+    };
+    let mut backoff = policy.backoff;
+    let mut failures = 0u32;
+    loop {
+        update(&|task| {
+            task.state = TaskState::Running;
+            task.started_at = Some(unix_now());
+        });
+        let started = Instant::now();
+        let mut handle = job(stop.clone());
+        let outcome = tokio::select! {
+            outcome = &mut handle => outcome,
+            _ = stop.changed() => {
+                // Let a run in progress finish
+                let _ = handle.await;
+                update(&|task| task.state = TaskState::Stopped);
+                return;
+            }
+        };
+        if *stop.borrow() {
+            update(&|task| task.state = TaskState::Stopped);
+            return;
+        }
+
+        let reason = match outcome {
+            Ok(()) => "exited before it was stopped".to_string(),
+            Err(e) if e.is_panic() => match e.into_panic().downcast::<String>() {
+                Ok(message) => format!("panicked: {}", message),
+                Err(payload) => match payload.downcast::<&str>() {
+                    Ok(message) => format!("panicked: {}", message),
+                    Err(_) => "panicked".to_string(),
+                },
+            },
+            Err(e) => e.to_string(),
+        };
+        // A task that ran a while before stopping starts its backoff over
+        if started.elapsed() >= policy.max_backoff {
+            backoff = policy.backoff;
+            failures = 0;
+        }
+        failures += 1;
+        if policy.max_restarts.is_some_and(|max| failures > max) {
+            error!(task = name, "Background task {}; giving up", reason);
+            update(&|task| {
+                task.state = TaskState::Failed;
+                task.last_error = Some(reason.clone());
+            });
+            return;
+        }
+        warn!(
+            task = name,
+            "Background task {}; restarting in {:?}", reason, backoff
+        );
+        update(&|task| {
+            task.state = TaskState::Restarting;
+            task.restarts += 1;
+            task.last_error = Some(reason.clone());
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = stop.changed() => {
+                update(&|task| task.state = TaskState::Stopped);
+                return;
+            }
+        }
+        backoff = (backoff * 2).min(policy.max_backoff);
+        info!(task = name, "Restarting background task");
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,355 @@
+use crate::secrets::SecretString;
+use ethers::core::rand::random;
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Metadata, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+pub const DEFAULT_OTEL_SERVICE_NAME: &str = "ethereum-trading-mcp-server";
+
+// Spans go out in batches of up to this many, and at least this often
+const EXPORT_BATCH_SIZE: usize = 512;
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+// Finished spans held while the collector is slow; later ones are dropped
+const EXPORT_QUEUE_CAPACITY: usize = 8192;
+
+// ethers opens a trace-level `rpc` span around every JSON-RPC request
+const ETHERS_RPC_TARGET: &str = "ethers_providers::rpc::provider";
+
+/// Where traces are exported to over OTLP/HTTP
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Collector base URL; spans are posted to `{endpoint}/v1/traces`
+    pub endpoint: String,
+    pub service_name: String,
+    /// Extra request headers, such as a vendor's API key
+    pub headers: Vec<(String, SecretString)>,
+}
+
+/// Whether spans from `metadata` are exported: this crate's own, and ethers' RPC requests
+pub fn exported(metadata: &Metadata<'_>) -> bool {
+    metadata.is_span()
+        && (metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+            || metadata.target() == ETHERS_RPC_TARGET)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpanKind {
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+}
+
+/// A span as exported, filled in while it is open
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    name: String,
+    kind: SpanKind,
+    start_unix_nanos: u128,
+    end_unix_nanos: u128,
+    attributes: Vec<(String, Value)>,
+    /// Set by an `error` field; marks the span failed
+    error: Option<String>,
+}
+
+impl SpanRecord {
+    fn set(&mut self, key: &str, value: Value) {
+        match (key, &value) {
+            ("otel.kind", Value::String(kind)) => {
+                self.kind = match kind.as_str() {
+                    "server" => SpanKind::Server,
+                    "client" => SpanKind::Client,
+                    _ => SpanKind::Internal,
+                }
+            }
+            ("error", Value::String(message)) => self.error = Some(message.clone()),
+            // A caller's W3C trace context: the span joins the caller's trace
+            ("traceparent", Value::String(header)) => {
+                if let Some((trace_id, parent_span_id)) = parse_traceparent(header) {
+                    self.trace_id = trace_id;
+                    self.parent_span_id = Some(parent_span_id);
+                }
+            }
+            // Request bodies can be large and carry signed transactions
+            ("params", _) => {}
+            ("method", Value::String(method)) if self.kind == SpanKind::Client => {
+                self.name = method.clone();
+                self.attributes
+                    .push(("rpc.system".to_string(), json!("jsonrpc")));
+                self.attributes.push(("rpc.method".to_string(), value));
+            }
+            _ => self.attributes.push((key.to_string(), value)),
+        }
+    }
+
+    fn to_otlp(&self) -> Value {
+        let mut span = json!({
+            "traceId": format!("{:032x}", self.trace_id),
+            "spanId": format!("{:016x}", self.span_id),
+            "name": self.name,
+            "kind": self.kind as u8,
+            "startTimeUnixNano": self.start_unix_nanos.to_string(),
+            "endTimeUnixNano": self.end_unix_nanos.to_string(),
+            "attributes": self
+                .attributes
+                .iter()
+                .map(|(key, value)| attribute(key, value))
+                .collect::<Vec<_>>(),
+            "status": match &self.error {
+                Some(message) => json!({ "code": 2, "message": message }),
+                None => json!({ "code": 0 }),
+            },
+        });
+        if let Some(parent) = self.parent_span_id {
+            span["parentSpanId"] = json!(format!("{:016x}", parent));
+        }
+        span
+    }
+}
+
+impl Visit for SpanRecord {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field.name(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field.name(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field.name(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field.name(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field.name(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field.name(), json!(format!("{:?}", value)));
+    }
+}
+
+/// An OTLP `KeyValue`; integers go out as strings, as OTLP/JSON encodes int64
+fn attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(value) => json!({ "boolValue": value }),
+        Value::Number(number) if number.is_f64() => json!({ "doubleValue": number }),
+        Value::Number(number) => json!({ "intValue": number.to_string() }),
+        Value::String(value) => json!({ "stringValue": value }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+/// Trace id and parent span id of a `traceparent` header, `00-<trace>-<span>-<flags>`
+fn parse_traceparent(header: &str) -> Option<(u128, u64)> {
+    let mut parts = header.trim().split('-');
+    let (Some("00"), Some(trace_id), Some(span_id), Some(_)) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    if trace_id.len() != 32 || span_id.len() != 16 {
+        return None;
+    }
+    let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+    let span_id = u64::from_str_radix(span_id, 16).ok()?;
+    (trace_id != 0 && span_id != 0).then_some((trace_id, span_id))
+}
+
+/// An OTLP/JSON `ExportTraceServiceRequest` carrying `spans`
+fn otlp_request(service_name: &str, spans: &[SpanRecord]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &json!(service_name))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans.iter().map(SpanRecord::to_otlp).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default()
+}
+
+enum Export {
+    Span(SpanRecord),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Records spans and hands them to the exporter as they close. Children inherit
+/// their parent's trace; root spans start a new one.
+pub struct OtlpLayer {
+    queue: mpsc::Sender<Export>,
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let (trace_id, parent_span_id) = span
+            .parent()
+            .and_then(|parent| {
+                parent
+                    .extensions()
+                    .get::<SpanRecord>()
+                    .map(|record| (record.trace_id, Some(record.span_id)))
+            })
+            .unwrap_or_else(|| (random(), None));
+        let metadata = attrs.metadata();
+        let mut record = SpanRecord {
+            trace_id,
+            span_id: random(),
+            parent_span_id,
+            name: metadata.name().to_string(),
+            kind: if metadata.target() == ETHERS_RPC_TARGET {
+                SpanKind::Client
+            } else {
+                SpanKind::Internal
+            },
+            start_unix_nanos: unix_nanos(),
+            end_unix_nanos: 0,
+            attributes: Vec::new(),
+            error: None,
+        };
+        attrs.record(&mut record);
+        span.extensions_mut().insert(record);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(record) = span.extensions_mut().get_mut::<SpanRecord>() {
+                values.record(record);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(mut record) = span.extensions_mut().remove::<SpanRecord>() else {
+            return;
+        };
+        record.end_unix_nanos = unix_nanos();
+        // A full queue means the collector is behind; tracing must not block the caller
+        let _ = self.queue.try_send(Export::Span(record));
+    }
+}
+
+/// Background task posting finished spans to an OTLP/HTTP collector
+pub struct OtlpExporter {
+    queue: mpsc::Sender<Export>,
+}
+
+impl OtlpExporter {
+    /// Start exporting to `config.endpoint`, returning the layer that feeds it.
+    /// Must be called within a Tokio runtime.
+    pub fn start(config: &OtlpConfig) -> (Self, OtlpLayer) {
+        let (queue, spans) = mpsc::channel(EXPORT_QUEUE_CAPACITY);
+        tokio::spawn(export(config.clone(), spans));
+        (
+            Self {
+                queue: queue.clone(),
+            },
+            OtlpLayer { queue },
+        )
+    }
+
+    /// Send every span closed so far, such as before the process exits
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.queue.send(Export::Flush(done)).await.is_ok() {
+            let _ = flushed.await;
+        }
+    }
+}
+
+async fn export(config: OtlpConfig, mut spans: mpsc::Receiver<Export>) {
+    let url = format!("{}/v1/traces", config.endpoint.trim_end_matches('/'));
+    let http = reqwest::Client::new();
+    let mut batch = Vec::new();
+    let mut failing = false;
+    let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+
+    loop {
+        let flushed = tokio::select! {
+            export = spans.recv() => match export {
+                Some(Export::Span(span)) => {
+                    batch.push(span);
+                    if batch.len() < EXPORT_BATCH_SIZE {
+                        continue;
+                    }
+                    None
+                }
+                Some(Export::Flush(done)) => Some(done),
+                None => return post(&http, &url, &config, &batch, &mut failing).await,
+            },
+            _ = interval.tick() => None,
+        };
+        post(&http, &url, &config, &batch, &mut failing).await;
+        batch.clear();
+        if let Some(done) = flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Post `batch` to the collector, warning when exports start or stop failing
+async fn post(
+    http: &reqwest::Client,
+    url: &str,
+    config: &OtlpConfig,
+    batch: &[SpanRecord],
+    failing: &mut bool,
+) {
+    if batch.is_empty() {
+        return;
+    }
+    let mut request = http
+        .post(url)
+        .timeout(EXPORT_TIMEOUT)
+        .json(&otlp_request(&config.service_name, batch));
+    for (name, value) in &config.headers {
+        request = request.header(name, value.expose_secret());
+    }
+    let result = match request.send().await {
+        Ok(response) => response.error_for_status().map(drop),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) if *failing => {
+            tracing::info!("Trace export to {} recovered", url);
+            *failing = false;
+        }
+        Err(e) if !*failing => {
+            tracing::warn!("Failed to export traces to {}: {}", url, e);
+            *failing = true;
+        }
+        _ => {}
+    }
+}
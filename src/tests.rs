@@ -1,94 +1,3378 @@
-#[cfg(test)]
-mod tests {
-    use serial_test::serial;
+use serial_test::serial;
 
-    // ============ Config Tests ============
+// ============ Config Tests ============
 
-    #[test]
-    #[serial]
-    fn test_config_from_env() {
-        use crate::config::Config;
-        std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
-        std::env::set_var(
-            "PRIVATE_KEY",
-            "0000000000000000000000000000000000000000000000000000000000000001",
+#[test]
+#[serial]
+fn test_config_from_env() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.eth_rpc_url, "https://eth.llamarpc.com");
+    assert_eq!(config.chain_id, 1);
+}
+
+#[test]
+#[serial]
+fn test_config_default_chain_id() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::remove_var("CHAIN_ID");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.chain_id, 1); // Default chain ID
+}
+
+#[test]
+#[serial]
+fn test_config_missing_rpc_url() {
+    use crate::config::Config;
+    std::env::remove_var("ETH_RPC_URL");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+
+    let result = Config::from_env();
+    assert!(result.is_err());
+}
+
+#[test]
+#[serial]
+fn test_config_missing_private_key() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::remove_var("PRIVATE_KEY");
+
+    let result = Config::from_env();
+    assert!(result.is_err());
+}
+
+#[test]
+#[serial]
+fn test_config_invalid_chain_id() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "invalid");
+
+    let result = Config::from_env();
+    assert!(result.is_err());
+}
+
+#[test]
+#[serial]
+fn test_config_different_networks() {
+    use crate::config::Config;
+
+    // Test Sepolia
+    std::env::set_var("ETH_RPC_URL", "https://sepolia.infura.io");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "11155111");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.chain_id, 11155111);
+}
+
+#[test]
+#[serial]
+fn test_config_tool_concurrency_limits() {
+    use crate::config::{ConcurrencyLimit, Config};
+    use crate::tools::ToolCategory;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::set_var("TOOL_CONCURRENCY_HEAVY", "1");
+    std::env::set_var("TOOL_QUEUE_DEPTH_HEAVY", "3");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.tool_limit(ToolCategory::Heavy),
+        ConcurrencyLimit {
+            max_concurrency: 1,
+            queue_depth: 3
+        }
+    );
+    assert_eq!(
+        config.tool_limit(ToolCategory::Read),
+        ConcurrencyLimit::default_for(ToolCategory::Read)
+    );
+
+    std::env::set_var("TOOL_CONCURRENCY_HEAVY", "0");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("TOOL_CONCURRENCY_HEAVY");
+    std::env::remove_var("TOOL_QUEUE_DEPTH_HEAVY");
+}
+
+#[test]
+#[serial]
+fn test_config_execution_disabled_by_default() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("ENABLE_EXECUTION");
+
+    assert!(!Config::from_env().unwrap().execution_enabled);
+
+    std::env::set_var("ENABLE_EXECUTION", "true");
+    assert!(Config::from_env().unwrap().execution_enabled);
+
+    std::env::set_var("ENABLE_EXECUTION", "yes");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("ENABLE_EXECUTION");
+}
+
+#[test]
+#[serial]
+fn test_config_http_transport_and_auth_tokens() {
+    use crate::config::{Config, Transport};
+    use crate::tools::PermissionTier;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("MCP_TRANSPORT");
+    std::env::remove_var("MCP_HTTP_BIND");
+    std::env::remove_var("MCP_AUTH_TOKENS");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.transport, Transport::Stdio);
+    assert!(config.auth_tokens.is_empty());
+
+    std::env::set_var("MCP_TRANSPORT", "http");
+    std::env::set_var("MCP_HTTP_BIND", "0.0.0.0:9000");
+    std::env::set_var(
+        "MCP_AUTH_TOKENS",
+        "reader-secret-token:read_only, trader-secret-token:trade",
+    );
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.transport,
+        Transport::Http {
+            bind: "0.0.0.0:9000".parse().unwrap()
+        }
+    );
+    let tiers: Vec<_> = config.auth_tokens.iter().map(|t| t.tier).collect();
+    assert_eq!(tiers, vec![PermissionTier::ReadOnly, PermissionTier::Trade]);
+    assert_eq!(
+        config.auth_tokens[1].token.expose_secret(),
+        "trader-secret-token"
+    );
+
+    // Parse errors must not echo the token
+    std::env::set_var("MCP_AUTH_TOKENS", "leaky-secret-token:superuser");
+    let err = format!("{:#}", Config::from_env().unwrap_err());
+    assert!(!err.contains("leaky-secret-token"));
+
+    // Empty and short tokens are refused by position
+    for tokens in [
+        "reader-secret-token:read_only, :admin",
+        "reader-secret-token:read_only, short:trade",
+    ] {
+        std::env::set_var("MCP_AUTH_TOKENS", tokens);
+        let err = format!("{:#}", Config::from_env().unwrap_err());
+        assert!(err.contains("entry 2: token must be at least 16 characters"));
+        assert!(!err.contains("short"));
+    }
+
+    // Beyond localhost, HTTP needs tokens
+    std::env::remove_var("MCP_AUTH_TOKENS");
+    let err = Config::from_env().unwrap_err().to_string();
+    assert!(err.contains("MCP_AUTH_TOKENS must be set"));
+    std::env::set_var("MCP_HTTP_BIND", "127.0.0.1:9000");
+    assert!(Config::from_env().unwrap().auth_tokens.is_empty());
+
+    std::env::remove_var("MCP_TRANSPORT");
+    std::env::remove_var("MCP_HTTP_BIND");
+    std::env::remove_var("MCP_AUTH_TOKENS");
+}
+
+#[test]
+#[serial]
+fn test_config_http_backpressure() {
+    use crate::config::Config;
+    use crate::mcp::RateLimit;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("MCP_HTTP_MAX_CONNECTIONS");
+    std::env::remove_var("MCP_RATE_LIMIT_PER_MINUTE");
+    std::env::remove_var("MCP_RATE_LIMIT_BURST");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.http_max_connections, Some(256));
+    assert_eq!(config.rate_limit, None);
+
+    std::env::set_var("MCP_HTTP_MAX_CONNECTIONS", "0");
+    std::env::set_var("MCP_RATE_LIMIT_PER_MINUTE", "120");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.http_max_connections, None);
+    assert_eq!(
+        config.rate_limit,
+        Some(RateLimit {
+            calls_per_minute: 120,
+            burst: 120
+        })
+    );
+
+    std::env::set_var("MCP_RATE_LIMIT_BURST", "10");
+    assert_eq!(Config::from_env().unwrap().rate_limit.unwrap().burst, 10);
+
+    std::env::set_var("MCP_RATE_LIMIT_BURST", "0");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("MCP_HTTP_MAX_CONNECTIONS");
+    std::env::remove_var("MCP_RATE_LIMIT_PER_MINUTE");
+    std::env::remove_var("MCP_RATE_LIMIT_BURST");
+}
+
+#[test]
+#[serial]
+fn test_config_attestation_key() {
+    use crate::config::Config;
+    let key = "0000000000000000000000000000000000000000000000000000000000000001";
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var("PRIVATE_KEY", key);
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("ATTEST_RESPONSES");
+    std::env::remove_var("ATTESTATION_KEY");
+
+    assert!(Config::from_env().unwrap().attestation_key.is_none());
+
+    std::env::set_var("ATTEST_RESPONSES", "true");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.attestation_key.unwrap().expose_secret(), key);
+
+    // A dedicated key wins over the server key
+    let dedicated = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    std::env::set_var("ATTESTATION_KEY", dedicated);
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.attestation_key.unwrap().expose_secret(), dedicated);
+
+    std::env::remove_var("ATTEST_RESPONSES");
+    std::env::remove_var("ATTESTATION_KEY");
+}
+
+#[test]
+#[serial]
+fn test_config_gas_escalation() {
+    use crate::config::Config;
+    use ethers::types::U256;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("GAS_ESCALATION_MAX_FEE_GWEI");
+    std::env::remove_var("GAS_ESCALATION_BUMP_PERCENT");
+
+    assert!(Config::from_env().unwrap().gas_escalation.is_none());
+
+    std::env::set_var("GAS_ESCALATION_MAX_FEE_GWEI", "80.5");
+    let policy = Config::from_env().unwrap().gas_escalation.unwrap();
+    assert_eq!(policy.after_blocks, 3);
+    assert_eq!(policy.bump_percent, 15);
+    assert_eq!(policy.max_fee_per_gas, U256::from(80_500_000_000u64));
+
+    // Nodes reject replacements bumped by less than 10%
+    std::env::set_var("GAS_ESCALATION_BUMP_PERCENT", "5");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("GAS_ESCALATION_MAX_FEE_GWEI");
+    std::env::remove_var("GAS_ESCALATION_BUMP_PERCENT");
+}
+
+#[test]
+fn test_gas_escalation_bump_stops_at_ceiling() {
+    use crate::ethereum::GasEscalation;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest, U256};
+
+    let policy = GasEscalation {
+        after_blocks: 2,
+        bump_percent: 20,
+        max_fee_per_gas: U256::from(130u64),
+    };
+
+    let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+        .max_fee_per_gas(100u64)
+        .max_priority_fee_per_gas(10u64)
+        .into();
+    assert!(policy.bump(&mut tx));
+    assert_eq!(GasEscalation::current_fee(&tx), U256::from(120u64));
+    assert!(policy.bump(&mut tx));
+    assert_eq!(GasEscalation::current_fee(&tx), U256::from(130u64));
+    assert!(!policy.bump(&mut tx));
+    match &tx {
+        TypedTransaction::Eip1559(inner) => {
+            assert_eq!(inner.max_priority_fee_per_gas, Some(U256::from(15u64)))
+        }
+        _ => unreachable!(),
+    }
+
+    let mut legacy: TypedTransaction = TransactionRequest::new().gas_price(100u64).into();
+    assert!(policy.bump(&mut legacy));
+    assert_eq!(legacy.gas_price(), Some(U256::from(120u64)));
+}
+
+// ============ Secret Redaction Tests ============
+
+#[test]
+#[serial]
+fn test_config_debug_redacts_private_key() {
+    use crate::config::Config;
+    let key = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var("PRIVATE_KEY", key);
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.private_key.as_ref().unwrap().expose_secret(), key);
+    let debug = format!("{:?}", config);
+    assert!(!debug.contains(key));
+    assert!(debug.contains("[REDACTED]"));
+}
+
+#[test]
+fn test_redact_registered_secret_in_error_chain() {
+    use crate::secrets::{redact, register_secret, SecretString};
+    let key = "8da4ef21b864d2cc526dbdb2a120bd2874c36c9d0a1fb7f8c63d7f7a8b41de8f";
+    register_secret(&SecretString::new(key));
+
+    let err = anyhow::anyhow!("signer rejected 0x{}", key.to_uppercase())
+        .context(format!("Failed to load key {}", key));
+    let chain = redact(&format!("{:?}", err));
+    assert!(!chain.to_lowercase().contains(key));
+    assert!(chain.contains("Failed to load key [REDACTED]"));
+    assert!(chain.contains("signer rejected [REDACTED]"));
+
+    // Unregistered values pass through untouched
+    assert_eq!(redact("block 0x1234"), "block 0x1234");
+}
+
+#[test]
+fn test_redacting_writer_scrubs_log_lines() {
+    use crate::secrets::{register_secret, RedactingMakeWriter, SecretString};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::MakeWriter;
+
+    let key = "c87509a1c067bbde78beb793e6fa76530b6382a4c0241e5e4a9ec0a0f44dc0d3";
+    register_secret(&SecretString::new(key));
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let sink = buffer.clone();
+    let make_writer = RedactingMakeWriter::new(move || sink.clone());
+    writeln!(make_writer.make_writer(), "loaded key {}", key).unwrap();
+
+    let logged = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(logged, "loaded key [REDACTED]\n");
+}
+
+#[test]
+fn test_json_log_lines_carry_span_fields() {
+    use crate::logging::{JsonFields, JsonFormat};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::prelude::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let sink = buffer.clone();
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer(move || sink.clone()),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!(
+            "tool.execute",
+            tool = "swap_tokens",
+            category = "simulation"
+        );
+        let _entered = span.enter();
+        tracing::warn!(
+            chain = 1u64,
+            duration_ms = 42u64,
+            error_code = "tool_failed",
+            "tool call failed: {}",
+            "no route"
         );
-        std::env::set_var("CHAIN_ID", "1");
+    });
+
+    let logged = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(logged.lines().count(), 1);
+    let line: serde_json::Value = serde_json::from_str(logged.trim_end()).unwrap();
+    assert_eq!(line["level"], "WARN");
+    assert_eq!(line["message"], "tool call failed: no route");
+    assert_eq!(line["tool"], "swap_tokens");
+    assert_eq!(line["chain"], 1);
+    assert_eq!(line["duration_ms"], 42);
+    assert_eq!(line["error_code"], "tool_failed");
+    assert!(line["timestamp"].is_string());
+    // Only the stable fields are lifted from spans
+    assert!(line.get("category").is_none());
+}
+
+#[test]
+fn test_pid_file_refuses_a_running_process() {
+    use crate::runtime::PidFile;
+
+    let path = std::env::temp_dir().join(format!("mcp-{}.pid", ethers::types::H64::random()));
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap().trim(),
+        std::process::id().to_string()
+    );
+    drop(pid_file);
+    assert!(!path.exists());
+
+    // pid 1 is always running
+    std::fs::write(&path, "1\n").unwrap();
+    let err = PidFile::create(&path).unwrap_err();
+    assert!(err.to_string().contains("Already running as process 1"));
+
+    // A file left by a process that died is replaced
+    std::fs::write(&path, format!("{}\n", u32::MAX)).unwrap();
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(pid_file.path(), path.as_path());
+    drop(pid_file);
+    assert!(!path.exists());
+}
+
+#[test]
+#[serial]
+fn test_systemd_notification() {
+    use crate::runtime::notify_systemd;
+    use std::os::unix::net::UnixDatagram;
+
+    let path = std::env::temp_dir().join(format!("mcp-notify-{}", ethers::types::H64::random()));
+    let socket = UnixDatagram::bind(&path).unwrap();
+    socket.set_nonblocking(true).unwrap();
+
+    // Not started by systemd: nothing is sent
+    std::env::remove_var("NOTIFY_SOCKET");
+    notify_systemd("READY=1");
+    let mut buf = [0u8; 64];
+    assert!(socket.recv(&mut buf).is_err());
+
+    std::env::set_var("NOTIFY_SOCKET", &path);
+    notify_systemd("READY=1");
+    let len = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"READY=1");
+
+    std::env::remove_var("NOTIFY_SOCKET");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_env_file_reload_keeps_real_environment() {
+    use crate::config::env_var;
+    use crate::runtime::load_env_file_from;
+
+    let path = std::env::temp_dir().join(format!("mcp-{}.env", ethers::types::H64::random()));
+    std::env::set_var("RELOAD_TEST_REAL", "from-environment");
+    std::fs::write(
+        &path,
+        "RELOAD_TEST_REAL=from-file\nRELOAD_TEST_RATE=10\nRELOAD_TEST_DROPPED=1\n",
+    )
+    .unwrap();
+    load_env_file_from(&path).unwrap();
+    assert_eq!(env_var("RELOAD_TEST_REAL").unwrap(), "from-environment");
+    assert_eq!(env_var("RELOAD_TEST_RATE").unwrap(), "10");
+    assert_eq!(env_var("RELOAD_TEST_DROPPED").unwrap(), "1");
+    // The process environment is never written
+    assert!(std::env::var("RELOAD_TEST_RATE").is_err());
+
+    // Edits to the file apply on reload; variables it stops setting go away
+    std::fs::write(&path, "RELOAD_TEST_REAL=from-file\nRELOAD_TEST_RATE=20\n").unwrap();
+    load_env_file_from(&path).unwrap();
+    assert_eq!(env_var("RELOAD_TEST_REAL").unwrap(), "from-environment");
+    assert_eq!(env_var("RELOAD_TEST_RATE").unwrap(), "20");
+    assert!(env_var("RELOAD_TEST_DROPPED").is_err());
+
+    std::fs::write(&path, "").unwrap();
+    load_env_file_from(&path).unwrap();
+    std::env::remove_var("RELOAD_TEST_REAL");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_reload_keeps_restart_settings() {
+    use crate::config::{Config, Transport};
+    use crate::runtime::keep_restart_settings;
+
+    let running = Config {
+        transport: Transport::Http {
+            bind: "127.0.0.1:8080".parse().unwrap(),
+        },
+        http_max_connections: Some(256),
+        ..Config::default()
+    };
+    let mut reloaded = Config {
+        http_max_connections: Some(16),
+        chain_id: 5,
+        ..running.clone()
+    };
+    assert!(keep_restart_settings(&mut reloaded, &running));
+    assert_eq!(reloaded.http_max_connections, Some(256));
+    assert_eq!(reloaded.chain_id, 5);
+
+    // Once applied, the same edit is still reported on the next reload
+    let mut again = Config {
+        http_max_connections: Some(16),
+        ..reloaded.clone()
+    };
+    assert!(keep_restart_settings(&mut again, &reloaded));
+    let mut unchanged = reloaded.clone();
+    assert!(!keep_restart_settings(&mut unchanged, &reloaded));
+}
+
+#[tokio::test]
+async fn test_invalid_private_key_error_does_not_leak() {
+    use crate::ethereum::EthereumClient;
+    let key = "zz0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f3623";
+
+    let err = EthereumClient::new("http://localhost:8545", key, 1)
+        .await
+        .err()
+        .unwrap();
+    assert!(!format!("{:?}", err).contains(key));
+}
+
+#[tokio::test]
+async fn test_webhooks_sign_and_retry_deliveries() {
+    use crate::mcp::webhooks::{sign, Webhooks, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+    use crate::secrets::SecretString;
+    use crate::tools::{AlertSeverity, AlertSink};
+    use mockito::Matcher;
+    use serde_json::json;
+    use std::time::Duration;
+
+    let mut server = mockito::Server::new_async().await;
+    let delivered = server
+        .mock("POST", "/ok")
+        .match_header(
+            SIGNATURE_HEADER,
+            Matcher::Regex("^sha256=[0-9a-f]{64}$".into()),
+        )
+        .match_header(TIMESTAMP_HEADER, Matcher::Regex("^[0-9]+$".into()))
+        .match_body(Matcher::PartialJson(json!({
+            "topic": "transactions",
+            "severity": "warning",
+            "data": { "event": "transaction_failed" }
+        })))
+        .expect(1)
+        .create_async()
+        .await;
+    // Retried after the first failure, then given up on
+    let failing = server
+        .mock("POST", "/down")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+    let refused = server
+        .mock("POST", "/gone")
+        .with_status(410)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let webhooks = Webhooks::new(
+        vec![
+            format!("{}/ok", server.url()),
+            format!("{}/down", server.url()),
+            format!("{}/gone", server.url()),
+        ],
+        Some(SecretString::new("whsec-test")),
+    )
+    .with_topics(vec!["transactions".to_string()])
+    .with_retries(2, Duration::from_millis(1));
+
+    let alert = json!({ "event": "transaction_failed" });
+    assert_eq!(
+        webhooks
+            .send("token_risk", AlertSeverity::Critical, &alert)
+            .await,
+        0
+    );
+    assert_eq!(
+        webhooks
+            .send("transactions", AlertSeverity::Warning, &alert)
+            .await,
+        3
+    );
 
-        let config = Config::from_env().unwrap();
-        assert_eq!(config.eth_rpc_url, "https://eth.llamarpc.com");
-        assert_eq!(config.chain_id, 1);
+    for _ in 0..200 {
+        if delivered.matched_async().await
+            && failing.matched_async().await
+            && refused.matched_async().await
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
     }
+    // Give a stray fourth attempt time to show up
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    delivered.assert_async().await;
+    failing.assert_async().await;
+    refused.assert_async().await;
 
-    #[test]
-    #[serial]
-    fn test_config_default_chain_id() {
-        use crate::config::Config;
-        std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
-        std::env::set_var(
-            "PRIVATE_KEY",
-            "0000000000000000000000000000000000000000000000000000000000000001",
+    // HMAC-SHA256 of `<timestamp>.<body>`
+    assert_eq!(
+        sign("key", 1_700_000_000, "{}"),
+        "9d713ed406bb7076d4123f0dc2c39d2df5c654ed4b0cd56b52c8b4c940bd63ae"
+    );
+}
+
+#[tokio::test]
+async fn test_chat_sinks_post_alerts() {
+    use crate::mcp::chat::{ChatBot, ChatService, ChatSink};
+    use crate::secrets::SecretString;
+    use crate::tools::{AlertSeverity, AlertSink};
+    use mockito::Matcher;
+    use serde_json::json;
+    use std::time::Duration;
+
+    let mut server = mockito::Server::new_async().await;
+    let telegram = server
+        .mock("POST", "/bottg-token/sendMessage")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::PartialJson(json!({ "chat_id": "42" })),
+            Matcher::Regex("\\[transactions\\] transaction_confirmed.*tx_hash: 0xabc".into()),
+        ]))
+        .expect(1)
+        .create_async()
+        .await;
+    let discord = server
+        .mock("POST", "/channels/777/messages")
+        .match_header("authorization", "Bot dc-token")
+        .match_body(Matcher::Regex("transaction_confirmed".into()))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let bot = |token: &str, chat_id: &str| ChatBot {
+        token: SecretString::new(token),
+        chat_id: chat_id.to_string(),
+    };
+    let sinks = [
+        ChatSink::new(ChatService::Telegram, &server.url(), bot("tg-token", "42")),
+        ChatSink::new(ChatService::Discord, &server.url(), bot("dc-token", "777"))
+            .with_topics(vec!["transactions".to_string()]),
+    ];
+
+    let alert = json!({ "event": "transaction_confirmed", "tx_hash": "0xabc", "error": null });
+    for sink in &sinks {
+        assert_eq!(
+            sink.send("transactions", AlertSeverity::Info, &alert).await,
+            1
         );
-        std::env::remove_var("CHAIN_ID");
+    }
+    assert_eq!(
+        sinks[1]
+            .send("policy", AlertSeverity::Warning, &alert)
+            .await,
+        0
+    );
 
-        let config = Config::from_env().unwrap();
-        assert_eq!(config.chain_id, 1); // Default chain ID
+    for _ in 0..200 {
+        if telegram.matched_async().await && discord.matched_async().await {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
     }
+    telegram.assert_async().await;
+    discord.assert_async().await;
+}
 
-    #[test]
-    #[serial]
-    fn test_config_missing_rpc_url() {
-        use crate::config::Config;
-        std::env::remove_var("ETH_RPC_URL");
-        std::env::set_var(
-            "PRIVATE_KEY",
-            "0000000000000000000000000000000000000000000000000000000000000001",
+#[test]
+fn test_chat_alert_format() {
+    use crate::mcp::chat::format_alert;
+    use crate::tools::AlertSeverity;
+    use serde_json::json;
+
+    let alert = json!({ "event": "policy_violation", "tool": "execute_swap", "reason": null });
+    assert_eq!(
+        format_alert("policy", AlertSeverity::Warning, &alert, 100),
+        "⚠️ [policy] policy_violation\ntool: execute_swap"
+    );
+    let long = json!({ "data": "x".repeat(500) });
+    let text = format_alert("token_risk", AlertSeverity::Critical, &long, 50);
+    assert_eq!(text.chars().count(), 50);
+    assert!(text.ends_with('…'));
+}
+
+// ============ Chain ID Verification Tests ============
+
+async fn chain_id_server(chain_id: &str) -> mockito::ServerGuard {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/")
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#,
+            chain_id
+        ))
+        .create_async()
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_walletconnect_signer_pairs_and_sends() {
+    use crate::ethereum::{WalletConnectConfig, WalletConnectSigner};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, TransactionRequest};
+
+    let wallet: Address = "0x00000000000000000000000000000000000000a1"
+        .parse()
+        .unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/pairings")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"chain_id":1,"project_id":"demo-project"}"#.to_string(),
+        ))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"topic":"t1","uri":"wc:t1@2?relay-protocol=irn&symKey=00","expires_at":4102444800}"#)
+        .create_async()
+        .await;
+    let session = server
+        .mock("GET", "/pairings/t1")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"approved":true,"accounts":["eip155:1:0x00000000000000000000000000000000000000b2"]}"#)
+        .create_async()
+        .await;
+
+    let signer = WalletConnectSigner::new(
+        &WalletConnectConfig {
+            bridge_url: format!("{}/", server.url()),
+            project_id: "demo-project".to_string(),
+            address: wallet,
+            request_timeout: std::time::Duration::from_secs(5),
+        },
+        1,
+    );
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(Address::repeat_byte(0x22))
+        .value(1u64)
+        .into();
+    assert!(signer.send_transaction(&tx).await.is_err());
+
+    let pairing = signer.pairing_or_pair().await.unwrap();
+    assert!(pairing.uri.starts_with("wc:"));
+    assert_eq!(signer.pairing_or_pair().await.unwrap(), pairing);
+
+    // A session for some other account must not send
+    let status = signer.status().await.unwrap();
+    assert!(status.approved && !status.ready);
+    let err = signer.send_transaction(&tx).await.unwrap_err().to_string();
+    assert!(err.contains("not WALLET_ADDRESS"), "{}", err);
+
+    session.remove_async().await;
+    server
+        .mock("GET", "/pairings/t1")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"approved":true,"accounts":["eip155:1:0x00000000000000000000000000000000000000a1"]}"#)
+        .create_async()
+        .await;
+    let hash = format!("0x{}", "ab".repeat(32));
+    server
+        .mock("POST", "/pairings/t1/requests")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"method":"eth_sendTransaction","params":[{"from":"0x00000000000000000000000000000000000000a1"}]}"#
+                .to_string(),
+        ))
+        .with_header("content-type", "application/json")
+        .with_body(format!(r#"{{"result":"{}"}}"#, hash))
+        .create_async()
+        .await;
+    assert!(signer.status().await.unwrap().ready);
+    assert_eq!(
+        format!("{:?}", signer.send_transaction(&tx).await.unwrap()),
+        hash
+    );
+}
+
+/// DER `SEQUENCE { r INTEGER, s INTEGER }`, as cloud KMS returns signatures
+fn der_signature(r: &[u8], s: &[u8]) -> Vec<u8> {
+    let integer = |value: &[u8]| {
+        let value = &value[value.iter().take_while(|&&b| b == 0).count()..];
+        let mut out = vec![0x02];
+        if value[0] & 0x80 != 0 {
+            out.push(value.len() as u8 + 1);
+            out.push(0);
+        } else {
+            out.push(value.len() as u8);
+        }
+        out.extend_from_slice(value);
+        out
+    };
+    let body = [integer(r), integer(s)].concat();
+    [vec![0x30, body.len() as u8], body].concat()
+}
+
+#[test]
+fn test_kms_der_signature_to_rsv() {
+    use crate::ethereum::kms::rsv_from_der;
+    use ethers::core::k256::ecdsa::SigningKey;
+    use ethers::core::k256::elliptic_curve::ops::Neg;
+    use ethers::types::H256;
+    use ethers::utils::keccak256;
+
+    let key = SigningKey::from_slice(&[0x11; 32]).unwrap();
+    let address = ethers::utils::secret_key_to_address(&key);
+    for message in ["first", "second", "third", "fourth"] {
+        let digest = H256::from(keccak256(message));
+        let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_bytes()).unwrap();
+        let rsv = rsv_from_der(
+            &der_signature(&signature.r().to_bytes(), &signature.s().to_bytes()),
+            digest,
+            key.verifying_key(),
+        )
+        .unwrap();
+        assert_eq!(rsv.v, u8::from(recovery_id) as u64);
+        assert_eq!(rsv.recover(digest).unwrap(), address);
+
+        // KMS may return the high-s twin, which Ethereum rejects
+        let high_s = signature.s().neg();
+        let twin = rsv_from_der(
+            &der_signature(&signature.r().to_bytes(), &high_s.to_bytes()),
+            digest,
+            key.verifying_key(),
+        )
+        .unwrap();
+        assert_eq!(twin, rsv);
+    }
+
+    let digest = H256::from(keccak256("first"));
+    let other = SigningKey::from_slice(&[0x22; 32]).unwrap();
+    let (signature, _) = other.sign_prehash_recoverable(digest.as_bytes()).unwrap();
+    let der = der_signature(&signature.r().to_bytes(), &signature.s().to_bytes());
+    assert!(rsv_from_der(&der, digest, key.verifying_key()).is_err());
+    assert!(rsv_from_der(&der[..der.len() - 1], digest, other.verifying_key()).is_err());
+}
+
+#[tokio::test]
+async fn test_aws_kms_signer_signs_eip155_transactions() {
+    use crate::ethereum::kms::public_key_from_spki;
+    use crate::ethereum::{AwsCredentials, KmsConfig, KmsSigner};
+    use crate::secrets::SecretString;
+    use data_encoding::BASE64;
+    use ethers::core::k256::ecdsa::SigningKey;
+    use ethers::signers::Signer;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, TransactionRequest};
+
+    let key = SigningKey::from_slice(&[0x11; 32]).unwrap();
+    let address = ethers::utils::secret_key_to_address(&key);
+    // The fixed secp256k1 SubjectPublicKeyInfo header, then the point
+    let mut spki = hex::decode("3056301006072a8648ce3d020106052b8104000a034200").unwrap();
+    spki.extend_from_slice(key.verifying_key().to_encoded_point(false).as_bytes());
+    assert_eq!(&public_key_from_spki(&spki).unwrap(), key.verifying_key());
+    assert!(public_key_from_spki(&spki[..40]).is_err());
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/")
+        .match_header("x-amz-target", "TrentService.GetPublicKey")
+        .match_header(
+            "authorization",
+            mockito::Matcher::Regex(
+                "^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/[0-9]{8}/us-east-1/kms/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-target, Signature=[0-9a-f]{64}$"
+                    .to_string(),
+            ),
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"KeyId":"alias/trading"}"#.to_string(),
+        ))
+        .with_body(format!(r#"{{"PublicKey":"{}"}}"#, BASE64.encode(&spki)))
+        .create_async()
+        .await;
+    let signing_key = key.clone();
+    server
+        .mock("POST", "/")
+        .match_header("x-amz-target", "TrentService.Sign")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"MessageType":"DIGEST","SigningAlgorithm":"ECDSA_SHA_256"}"#.to_string(),
+        ))
+        .with_body_from_request(move |request| {
+            let body: serde_json::Value = serde_json::from_slice(request.body().unwrap()).unwrap();
+            let digest = BASE64
+                .decode(body["Message"].as_str().unwrap().as_bytes())
+                .unwrap();
+            let (signature, _) = signing_key.sign_prehash_recoverable(&digest).unwrap();
+            let der = der_signature(&signature.r().to_bytes(), &signature.s().to_bytes());
+            format!(r#"{{"Signature":"{}"}}"#, BASE64.encode(&der)).into()
+        })
+        .create_async()
+        .await;
+
+    let config = KmsConfig::Aws {
+        key_id: "alias/trading".to_string(),
+        region: "us-east-1".to_string(),
+        credentials: AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: SecretString::new("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+            session_token: None,
+        },
+        endpoint: Some(server.url()),
+    };
+    let signer = KmsSigner::connect(&config, 137).await.unwrap();
+    assert_eq!(signer.address(), address);
+
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(Address::repeat_byte(0x22))
+        .value(1u64)
+        .nonce(0u64)
+        .gas(21_000u64)
+        .gas_price(1u64)
+        .into();
+    let signature = signer.sign_transaction(&tx).await.unwrap();
+    assert!(signature.v == 137 * 2 + 35 || signature.v == 137 * 2 + 36);
+    let mut signed = tx.clone();
+    signed.set_chain_id(137u64);
+    assert_eq!(signature.recover(signed.sighash()).unwrap(), address);
+
+    let signature = signer.sign_message("hello").await.unwrap();
+    assert!(signature.v == 27 || signature.v == 28);
+    signature.verify("hello", address).unwrap();
+}
+
+#[tokio::test]
+async fn test_snapshot_hub_proposals_and_voting_power() {
+    use crate::ethereum::{GovernanceSource, ProposalState, SnapshotHub};
+    use rust_decimal::Decimal;
+
+    let author = "0x00000000000000000000000000000000000000a1";
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("proposals".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"data":{{"proposals":[{{"id":"0xabc","title":"Raise the fee switch","body":"Details","author":"{}","state":"active","start":1700000000,"end":1700600000,"choices":["For","Against"],"scores":[1250.5,300]}}]}}}}"#,
+            author
+        ))
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("vp\\(".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"data":{"vp":{"vp":42.25},"votes":[{"id":"0xvote"}]}}"#)
+        .create_async()
+        .await;
+
+    let hub = SnapshotHub::new(&format!("{}/", server.url()));
+    let proposals = hub.proposals("aave.eth", true, 5).await.unwrap();
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].id, "0xabc");
+    assert_eq!(proposals[0].state, ProposalState::Active);
+    assert_eq!(proposals[0].proposer, author.parse().unwrap());
+    assert_eq!(proposals[0].tallies[0].choice, "For");
+    assert_eq!(proposals[0].tallies[0].votes, Decimal::new(12505, 1));
+
+    let power = hub
+        .voting_power("aave.eth", author.parse().unwrap(), Some("0xabc"))
+        .await
+        .unwrap();
+    assert_eq!(power.votes, Decimal::new(4225, 2));
+    assert_eq!(power.has_voted, Some(true));
+}
+
+#[tokio::test]
+async fn test_etherscan_history_pages_back_to_the_period_start() {
+    use crate::ethereum::{EtherscanHistory, TransactionHistorySource};
+    use crate::secrets::SecretString;
+    use ethers::types::{Address, U256};
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/api")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("chainid".into(), "1".into()),
+            mockito::Matcher::UrlEncoded("action".into(), "txlist".into()),
+            mockito::Matcher::UrlEncoded("sort".into(), "desc".into()),
+            mockito::Matcher::UrlEncoded("apikey".into(), "key".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"status":"1","message":"OK","result":[
+                {{"hash":"0x{h1}","blockNumber":"200","timeStamp":"1700000200","from":"{w}","to":"0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48","value":"0","gasUsed":"46000","gasPrice":"20000000000","input":"0xa9059cbb0000","functionName":"transfer(address _to, uint256 _value)","isError":"0"}},
+                {{"hash":"0x{h2}","blockNumber":"150","timeStamp":"1700000150","from":"{w}","to":"","value":"0","gasUsed":"500000","gasPrice":"10000000000","input":"0x6080","functionName":"","isError":"1"}},
+                {{"hash":"0x{h3}","blockNumber":"100","timeStamp":"1600000000","from":"{w}","to":"{w}","value":"1","gasUsed":"21000","gasPrice":"1","input":"0x","functionName":"","isError":"0"}}
+            ]}}"#,
+            h1 = "11".repeat(32),
+            h2 = "22".repeat(32),
+            h3 = "33".repeat(32),
+            w = format!("{:?}", wallet)
+        ))
+        .create_async()
+        .await;
+
+    let history = EtherscanHistory::new(
+        &format!("{}/api", server.url()),
+        Some(SecretString::new("key")),
+        1,
+    );
+    let transactions = history
+        .transactions(wallet, 1_700_000_000, 100)
+        .await
+        .unwrap();
+    // The third is older than the period
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0].block_number, 200);
+    assert_eq!(transactions[0].selector, Some([0xa9, 0x05, 0x9c, 0xbb]));
+    assert_eq!(
+        transactions[0].function_name.as_deref(),
+        Some("transfer(address _to, uint256 _value)")
+    );
+    assert_eq!(transactions[0].fee(), U256::from(920_000_000_000_000u64));
+    assert!(transactions[0].success);
+    assert_eq!(transactions[1].to, None);
+    assert!(!transactions[1].success);
+
+    server
+        .mock("GET", "/empty")
+        .match_query(mockito::Matcher::Any)
+        .with_body(r#"{"status":"0","message":"No transactions found","result":[]}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/limited")
+        .match_query(mockito::Matcher::Any)
+        .with_body(r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#)
+        .create_async()
+        .await;
+    let empty = EtherscanHistory::new(&format!("{}/empty", server.url()), None, 1);
+    assert!(empty.transactions(wallet, 0, 100).await.unwrap().is_empty());
+    let limited = EtherscanHistory::new(&format!("{}/limited", server.url()), None, 1);
+    let error = limited.transactions(wallet, 0, 100).await.unwrap_err();
+    assert!(error.to_string().contains("Invalid API Key"));
+}
+
+#[tokio::test]
+async fn test_etherscan_history_reads_beacon_withdrawals() {
+    use crate::ethereum::{EtherscanHistory, TransactionHistorySource};
+    use ethers::types::Address;
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/api")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "action".into(),
+            "txsBeaconWithdrawal".into(),
+        ))
+        .with_body(format!(
+            r#"{{"status":"1","message":"OK","result":[
+                {{"withdrawalIndex":"9001","validatorIndex":"42","address":"{w}","amount":"18000000","blockNumber":"200","timestamp":"1700000200"}},
+                {{"withdrawalIndex":"8001","validatorIndex":"42","address":"{w}","amount":"17000000","blockNumber":"100","timestamp":"1600000000"}}
+            ]}}"#,
+            w = format!("{:?}", wallet)
+        ))
+        .create_async()
+        .await;
+
+    let history = EtherscanHistory::new(&format!("{}/api", server.url()), None, 1);
+    let withdrawals = history
+        .withdrawals(wallet, 1_700_000_000, 100)
+        .await
+        .unwrap();
+    assert_eq!(withdrawals.len(), 1);
+    assert_eq!(withdrawals[0].index, 9001);
+    assert_eq!(withdrawals[0].validator_index, 42);
+    assert_eq!(withdrawals[0].amount_gwei, 18_000_000);
+    assert_eq!(withdrawals[0].address, wallet);
+}
+
+#[tokio::test]
+async fn test_beacon_api_reads_validators_at_a_slot() {
+    use crate::ethereum::{BeaconApi, BeaconSource};
+    use crate::secrets::SecretString;
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/eth/v1/beacon/headers/head")
+        .with_body(r#"{"data":{"header":{"message":{"slot":"8000000"}}}}"#)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/eth/v1/beacon/states/7949600/validators/42")
+        .with_body(
+            r#"{"data":{"index":"42","balance":"32012345678","status":"active_ongoing",
+                "validator":{"pubkey":"0xa1d1","withdrawal_credentials":"0x010000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa96045",
+                "effective_balance":"32000000000","slashed":false,"activation_epoch":"100",
+                "exit_epoch":"18446744073709551615"}}}"#,
+        )
+        .create_async()
+        .await;
+
+    let beacon = BeaconApi::new(SecretString::new(format!("{}/", server.url())));
+    assert!(beacon.name().starts_with("http://"));
+    assert_eq!(beacon.head_slot().await.unwrap(), 8_000_000);
+    let validator = beacon.validator("42", Some(7_949_600)).await.unwrap();
+    assert_eq!(validator.balance_gwei, 32_012_345_678);
+    assert_eq!(validator.status, "active_ongoing");
+    assert_eq!(validator.exit_epoch, u64::MAX);
+    assert_eq!(
+        validator.withdrawal_address(),
+        Some(
+            "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+                .parse()
+                .unwrap()
+        )
+    );
+    assert!(beacon.validator("43", None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_hyperliquid_api_reads_markets_and_positions() {
+    use crate::ethereum::{HyperliquidApi, PerpSide, PerpVenue};
+    use ethers::types::Address;
+    use rust_decimal::Decimal;
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/info")
+        .match_body(mockito::Matcher::PartialJson(
+            serde_json::json!({ "type": "metaAndAssetCtxs" }),
+        ))
+        .with_body(
+            r#"[{"universe":[{"name":"BTC","szDecimals":5,"maxLeverage":40},{"name":"ETH","szDecimals":4,"maxLeverage":25}]},
+                [{"funding":"0.0000125","openInterest":"100.5","markPx":"60000.0","oraclePx":"60010.0","dayNtlVlm":"1000000000.0"},
+                 {"funding":"-0.00001","openInterest":"2000","markPx":"3000.5","oraclePx":"3001.0","dayNtlVlm":"500000000.0"}]]"#,
+        )
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/info")
+        .match_body(mockito::Matcher::PartialJson(
+            serde_json::json!({ "type": "clearinghouseState" }),
+        ))
+        .with_body(
+            r#"{"assetPositions":[{"type":"oneWay","position":{"coin":"ETH","szi":"-2.5","entryPx":"3100.0",
+                "positionValue":"7500.0","unrealizedPnl":"250.0","marginUsed":"1500.0","liquidationPx":"3600.0",
+                "leverage":{"type":"cross","value":5}}}],"marginSummary":{"accountValue":"1750.0"}}"#,
+        )
+        .create_async()
+        .await;
+
+    let venue = HyperliquidApi::new(&format!("{}/", server.url()));
+    let markets = venue.markets().await.unwrap();
+    assert_eq!(markets.len(), 2);
+    assert_eq!(markets[0].symbol, "BTC");
+    assert_eq!(markets[0].max_leverage, Some(40));
+    assert_eq!(markets[0].open_interest_usd, Some(Decimal::from(6_030_000)));
+    assert_eq!(markets[1].funding_rate_hourly, Some(Decimal::new(-1, 5)));
+
+    let positions = venue.positions(Address::repeat_byte(0x11)).await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].side, PerpSide::Short);
+    assert_eq!(positions[0].size, Decimal::new(25, 1));
+    assert_eq!(positions[0].mark_price, Some(Decimal::from(3000)));
+    assert_eq!(positions[0].leverage, Some(Decimal::from(5)));
+    assert_eq!(positions[0].liquidation_price, Some(Decimal::from(3600)));
+}
+
+#[tokio::test]
+async fn test_aevo_api_reads_option_chains() {
+    use crate::ethereum::{AevoApi, OptionKind, OptionsVenue};
+    use rust_decimal::Decimal;
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/markets")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("asset".into(), "ETH".into()),
+            mockito::Matcher::UrlEncoded("instrument_type".into(), "OPTION".into()),
+        ]))
+        .with_body(
+            r#"[{"instrument_name":"ETH-27DEC30-3000-C","is_active":true,"option_type":"call","strike":"3000",
+                 "expiry":"1924588800000000000","mark_price":"420.5","index_price":"3050.25",
+                 "greeks":{"delta":"0.55","iv":"0.62"}},
+                {"instrument_name":"ETH-27DEC30-2500-P","is_active":true,"option_type":"put","strike":"2500",
+                 "expiry":"1924588800000000000","mark_price":"150","index_price":"3050.25",
+                 "greeks":{"delta":"-0.25","iv":"0.7"}},
+                {"instrument_name":"ETH-28DEC29-3000-C","is_active":false,"option_type":"call","strike":"3000",
+                 "expiry":"1893110400000000000"}]"#,
+        )
+        .create_async()
+        .await;
+
+    let venue = AevoApi::new(&server.url());
+    assert_eq!(venue.expiries("eth").await.unwrap(), vec![1_924_588_800]);
+    let chain = venue.option_chain("ETH", 1_924_588_800).await.unwrap();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0].kind, OptionKind::Call);
+    assert_eq!(chain[0].strike, Decimal::from(3000));
+    assert_eq!(chain[0].mark_iv, Some(Decimal::new(62, 2)));
+    assert_eq!(chain[1].kind, OptionKind::Put);
+    assert_eq!(chain[1].delta, Some(Decimal::new(-25, 2)));
+    assert_eq!(chain[1].index_price, Some(Decimal::new(305025, 2)));
+}
+
+#[tokio::test]
+async fn test_scam_list_feeds_refresh_and_flag_addresses() {
+    use crate::tools::{ScamFeed, ScamList};
+    use ethers::types::Address;
+    use serde_json::json;
+
+    let drainer = "0x00000000000000000000000000000000000000d1";
+    let phisher = "0x00000000000000000000000000000000000000d2";
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/address.json")
+        .with_body(format!(
+            r#"["{}", "{}"]"#,
+            drainer,
+            phisher.to_uppercase().replace("0X", "0x")
+        ))
+        .create_async()
+        .await;
+    // A transaction hash is 64 hex digits and lists nothing
+    let reports = server
+        .mock("GET", "/reports.csv")
+        .with_body(format!(
+            "address,category\n{},phishing\n0x{},hash\n",
+            phisher,
+            "ab".repeat(32)
+        ))
+        .create_async()
+        .await;
+
+    let sniffer = ScamFeed::from_url(&format!("{}/address.json", server.url()));
+    assert_eq!(sniffer.name, "127.0.0.1");
+    let feeds = vec![
+        ScamFeed {
+            name: "scamsniffer".to_string(),
+            url: sniffer.url,
+        },
+        ScamFeed {
+            name: "chainabuse".to_string(),
+            url: format!("{}/reports.csv", server.url()),
+        },
+    ];
+    let scams = ScamList::new(feeds.clone());
+    assert!(scams.is_empty());
+    scams.refresh().await;
+    assert_eq!(scams.len(), 2);
+    let phisher: Address = phisher.parse().unwrap();
+    assert_eq!(scams.sources(phisher), ["chainabuse", "scamsniffer"]);
+    assert_eq!(scams.sources(drainer.parse().unwrap()), ["scamsniffer"]);
+    assert!(scams.sources(Address::repeat_byte(0x11)).is_empty());
+
+    // A feed that fails keeps its last listing
+    reports.remove_async().await;
+    server
+        .mock("GET", "/reports.csv")
+        .with_status(503)
+        .create_async()
+        .await;
+    assert!(scams.refresh_feed(&feeds[1]).await.is_err());
+    assert_eq!(scams.sources(phisher), ["chainabuse", "scamsniffer"]);
+
+    let warnings = scams.warnings(
+        &json!({ "plan": { "steps": [{ "to": drainer }] } }),
+        &json!({ format!("{:?}", phisher): { "note": format!("paid {}", drainer) } }),
+    );
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(warnings[0].found_in, "input");
+    assert_eq!(warnings[0].address, drainer.parse().unwrap());
+    assert_eq!(warnings[1].found_in, "output");
+
+    assert!(scams
+        .ensure_unlisted(&[Address::repeat_byte(0x11)], false)
+        .is_ok());
+    let err = scams.ensure_unlisted(&[phisher], false).unwrap_err();
+    assert!(err.to_string().contains("chainabuse, scamsniffer"));
+    assert!(scams.ensure_unlisted(&[phisher], true).is_err());
+    let permissive = ScamList::new(Vec::new())
+        .with_override_allowed(true)
+        .with_addresses("local", [phisher]);
+    assert!(permissive.ensure_unlisted(&[phisher], true).is_ok());
+}
+
+#[tokio::test]
+async fn test_ccip_read_follows_offchain_lookup() {
+    use crate::ethereum::CcipRead;
+    use ethers::abi::Token;
+    use ethers::providers::{Http, Provider};
+    use ethers::types::{Address, Bytes};
+    use ethers::utils::{hex, id};
+    use std::sync::Arc;
+
+    let mut gateway = mockito::Server::new_async().await;
+    gateway
+        .mock(
+            "GET",
+            mockito::Matcher::Regex("^/lookup/0x.*/0xdeadbeef\\.json$".to_string()),
+        )
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"data":"0xcafe"}"#)
+        .create_async()
+        .await;
+
+    let contract = Address::repeat_byte(0xaa);
+    let mut revert = id("OffchainLookup(address,string[],bytes,bytes4,bytes)").to_vec();
+    revert.extend(ethers::abi::encode(&[
+        Token::Address(contract),
+        Token::Array(vec![Token::String(format!(
+            "{}/lookup/{{sender}}/{{data}}.json",
+            gateway.url()
+        ))]),
+        Token::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+        Token::FixedBytes(vec![0xab, 0xcd, 0xef, 0x01]),
+        Token::Bytes(vec![0x01, 0x02]),
+    ]));
+    let mut rpc = mockito::Server::new_async().await;
+    rpc.mock("POST", "/")
+        .match_body(mockito::Matcher::Regex("0x11223344".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","id":1,"error":{{"code":3,"message":"execution reverted","data":"0x{}"}}}}"#,
+            hex::encode(&revert)
+        ))
+        .create_async()
+        .await;
+    // The callback carries the gateway's answer and the extra data back to the contract
+    rpc.mock("POST", "/")
+        .match_body(mockito::Matcher::Regex("0xabcdef01.*cafe".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":"0x{}"}}"#,
+            "00".repeat(31) + "2a"
+        ))
+        .create_async()
+        .await;
+
+    let provider = Arc::new(Provider::<Http>::try_from(rpc.url()).unwrap());
+    let output = CcipRead::new(provider)
+        .call(contract, Bytes::from(vec![0x11, 0x22, 0x33, 0x44]))
+        .await
+        .unwrap();
+    assert_eq!(output.len(), 32);
+    assert_eq!(output[31], 0x2a);
+}
+
+#[tokio::test]
+async fn test_pool_activity_reads_v3_swaps_as_absolute_amounts() {
+    use crate::ethereum::{FactoryKind, PoolActivity};
+    use ethers::abi::Token;
+    use ethers::providers::{Http, Provider};
+    use ethers::types::{Address, I256, U256};
+    use ethers::utils::hex;
+    use std::sync::Arc;
+
+    let pool = Address::repeat_byte(0x88);
+    let topic =
+        ethers::utils::keccak256("Swap(address,address,int256,int256,uint160,uint128,int24)");
+    // The pool paid out 1000 USDC and took in 0.5 WETH
+    let data = ethers::abi::encode(&[
+        Token::Int(I256::from(-1_000_000_000i64).into_raw()),
+        Token::Int(I256::from(500_000_000_000_000_000i64).into_raw()),
+        Token::Uint(U256::one() << 96),
+        Token::Uint(U256::from(1u64) << 64),
+        Token::Int(U256::zero()),
+    ]);
+    let mut rpc = mockito::Server::new_async().await;
+    rpc.mock("POST", "/")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"method":"eth_getLogs"}"#.to_string(),
+        ))
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":[{{"address":"{:?}","topics":["0x{}","0x{}","0x{}"],"data":"0x{}","blockNumber":"0x3e8","blockHash":"0x{}","transactionHash":"0x{}","transactionIndex":"0x0","logIndex":"0x0","removed":false}}]}}"#,
+            pool,
+            hex::encode(topic),
+            "00".repeat(32),
+            "00".repeat(32),
+            hex::encode(data),
+            "11".repeat(32),
+            "22".repeat(32)
+        ))
+        .create_async()
+        .await;
+
+    let provider = Arc::new(Provider::<Http>::try_from(rpc.url()).unwrap());
+    let swaps = PoolActivity::new(provider)
+        .swaps(pool, FactoryKind::V3, 900, 1_000)
+        .await
+        .unwrap();
+    assert_eq!(swaps.len(), 1);
+    assert_eq!(swaps[0].block_number, 1_000);
+    assert_eq!(swaps[0].amount0, U256::from(1_000_000_000u64));
+    assert_eq!(swaps[0].amount1, U256::from(500_000_000_000_000_000u64));
+}
+
+#[tokio::test]
+async fn test_simulate_bundle_uses_eth_simulate_v1() {
+    use crate::ethereum::{asset_transfers, EthereumClient};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, TransactionRequest, U256};
+    let key = "0000000000000000000000000000000000000000000000000000000000000001";
+    let main = mockito::Server::new_async().await;
+    let mut fork = mockito::Server::new_async().await;
+    let transfer = format!(
+        r#"{{"address":"0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee","topics":["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef","0x0000000000000000000000007e5f4552091a69125d5dfcb7b8c2659029395bdf","0x{}"],"data":"0x{:064x}"}}"#,
+        "00".repeat(12) + &"22".repeat(20),
+        1000
+    );
+    fork.mock("POST", "/")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"method":"eth_simulateV1","params":[{"traceTransfers":true,"validation":false,"blockStateCalls":[{"calls":[{"to":"0x2222222222222222222222222222222222222222","value":"0x3e8"},{"input":"0x1234"}]}]},"latest"]}"#.to_string(),
+        ))
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":[{{"number":"0x1","calls":[{{"status":"0x1","gasUsed":"0x5208","returnData":"0x","logs":[{}]}},{{"status":"0x0","gasUsed":"0x7530","returnData":"0x","logs":[],"error":{{"code":3,"message":"execution reverted"}}}}]}}]}}"#,
+            transfer
+        ))
+        .create_async()
+        .await;
+
+    let client = EthereumClient::new(&main.url(), key, 1)
+        .await
+        .unwrap()
+        .with_simulation_rpc(&fork.url())
+        .unwrap();
+    let txs: Vec<TypedTransaction> = vec![
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x22))
+            .value(1000u64)
+            .into(),
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x33))
+            .data(vec![0x12, 0x34])
+            .into(),
+    ];
+    let calls = client.simulate_bundle(&txs).await.unwrap();
+    assert!(calls[0].success);
+    assert_eq!(calls[0].gas_used, U256::from(21_000));
+    let transfers = asset_transfers(&calls[0].logs);
+    assert_eq!(transfers[0].token, None);
+    assert_eq!(transfers[0].to, Address::repeat_byte(0x22));
+    assert_eq!(transfers[0].amount, U256::from(1000));
+    assert!(!calls[1].success);
+    assert_eq!(calls[1].error.as_deref(), Some("execution reverted"));
+}
+
+#[tokio::test]
+async fn test_verify_chain_id_modes() {
+    use crate::ethereum::{ChainIdCheck, ChainIdMismatch, EthereumClient};
+    let key = "0000000000000000000000000000000000000000000000000000000000000001";
+    let server = chain_id_server("0x89").await; // Polygon
+
+    let url = server.url();
+    let client = |chain_id| EthereumClient::new(&url, key, chain_id);
+
+    assert!(client(137).await.unwrap().verify_chain_id().await.is_ok());
+
+    let err = client(1)
+        .await
+        .unwrap()
+        .verify_chain_id()
+        .await
+        .unwrap_err();
+    let mismatch = err.downcast_ref::<ChainIdMismatch>().unwrap();
+    assert_eq!(mismatch.expected, 1);
+    assert_eq!(mismatch.actual, 137.into());
+
+    for mode in [ChainIdCheck::Warn, ChainIdCheck::Off] {
+        let lenient = client(1).await.unwrap().with_chain_id_check(mode);
+        assert!(lenient.verify_chain_id().await.is_ok());
+    }
+}
+
+#[test]
+#[serial]
+fn test_config_chain_id_check() {
+    use crate::config::Config;
+    use crate::ethereum::ChainIdCheck;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("CHAIN_ID_CHECK");
+
+    assert_eq!(
+        Config::from_env().unwrap().chain_id_check,
+        ChainIdCheck::Strict
+    );
+
+    std::env::set_var("CHAIN_ID_CHECK", "warn");
+    assert_eq!(
+        Config::from_env().unwrap().chain_id_check,
+        ChainIdCheck::Warn
+    );
+
+    std::env::set_var("CHAIN_ID_CHECK", "sometimes");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("CHAIN_ID_CHECK");
+}
+
+#[test]
+#[serial]
+fn test_config_yield_vaults() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::remove_var("YIELD_VAULTS");
+
+    assert!(Config::from_env().unwrap().yield_vaults.is_empty());
+
+    std::env::set_var(
+        "YIELD_VAULTS",
+        "scrvusd:0x0655977FEb2f289A4aB78af67BAB0d17aAb84367, ",
+    );
+    let vaults = Config::from_env().unwrap().yield_vaults;
+    assert_eq!(vaults.len(), 1);
+    assert_eq!(vaults[0].0, "scrvusd");
+
+    std::env::set_var("YIELD_VAULTS", "no-address");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("YIELD_VAULTS");
+}
+
+#[test]
+#[serial]
+fn test_config_chain_rpc_urls() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::set_var(
+        "CHAIN_RPC_URLS",
+        "42161=https://arb1.arbitrum.io/rpc, 8453=https://mainnet.base.org?key=abc",
+    );
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.chain_rpc_urls.len(), 2);
+    assert_eq!(config.chain_rpc_urls[0].0, 42161);
+    assert_eq!(
+        config.chain_rpc_urls[1].1,
+        "https://mainnet.base.org?key=abc"
+    );
+    assert_eq!(config.chain_query_concurrency, 4);
+
+    // The primary chain already has ETH_RPC_URL
+    std::env::set_var("CHAIN_RPC_URLS", "1=https://eth.example.org");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("CHAIN_RPC_URLS");
+}
+
+#[test]
+#[serial]
+fn test_config_batch_transfer() {
+    use crate::config::Config;
+    use crate::tools::batch_transfer::DEFAULT_DISPERSE_CONTRACT;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.disperse_contract,
+        DEFAULT_DISPERSE_CONTRACT.parse().unwrap()
+    );
+    assert_eq!(config.batch_limits.max_recipients, 200);
+    assert_eq!(config.batch_limits.max_eth_per_recipient, None);
+
+    std::env::set_var("BATCH_MAX_RECIPIENTS", "50");
+    std::env::set_var("BATCH_MAX_ETH_PER_RECIPIENT", "0.5");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.batch_limits.max_recipients, 50);
+    assert_eq!(
+        config.batch_limits.max_eth_per_recipient,
+        Some(rust_decimal::Decimal::new(5, 1))
+    );
+
+    std::env::set_var("BATCH_MAX_RECIPIENTS", "0");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("BATCH_MAX_RECIPIENTS");
+    std::env::remove_var("BATCH_MAX_ETH_PER_RECIPIENT");
+}
+
+#[test]
+#[serial]
+fn test_config_slippage_defaults() {
+    use crate::config::Config;
+    use crate::tools::SlippageSource;
+    use ethers::types::Address;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.slippage.default_percent, 0.5);
+    assert!(config.slippage.pairs.is_empty());
+
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let usdt: Address = "0xdAC17F958D2ee523a2206206994597C13D831ec7"
+        .parse()
+        .unwrap();
+    let pepe: Address = "0x6982508145454Ce325dDbE47a25d4ec3d2311933"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    std::env::set_var("SLIPPAGE_DEFAULT_PERCENT", "0.3");
+    std::env::set_var("SLIPPAGE_PAIRS", format!("{:?}/{:?}:0.1", usdc, usdt));
+    std::env::set_var("SLIPPAGE_TOKENS", format!("{:?}:1, {:?}:0.2", pepe, usdt));
+    let slippage = Config::from_env().unwrap().slippage;
+
+    // Pairs match either way round and beat token overrides
+    assert_eq!(
+        slippage.resolve(None, usdt, usdc),
+        (0.1, SlippageSource::Pair)
+    );
+    // The looser of two token overrides applies
+    assert_eq!(
+        slippage.resolve(None, usdt, pepe),
+        (1.0, SlippageSource::Token)
+    );
+    assert_eq!(
+        slippage.resolve(None, weth, usdc),
+        (0.3, SlippageSource::Default)
+    );
+    assert_eq!(
+        slippage.resolve(Some(2.0), usdc, usdt),
+        (2.0, SlippageSource::Requested)
+    );
+
+    std::env::set_var("SLIPPAGE_PAIRS", format!("{:?}:0.1", usdc));
+    assert!(Config::from_env().is_err());
+    std::env::set_var("SLIPPAGE_PAIRS", format!("{:?}/{:?}:150", usdc, usdt));
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("SLIPPAGE_DEFAULT_PERCENT");
+    std::env::remove_var("SLIPPAGE_PAIRS");
+    std::env::remove_var("SLIPPAGE_TOKENS");
+}
+
+#[test]
+#[serial]
+fn test_config_pool_share_limit() {
+    use crate::config::Config;
+    use crate::tools::{OverLimitAction, PoolShareLimit};
+    use rust_decimal::Decimal;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.pool_share_limit,
+        Some(PoolShareLimit {
+            max_percent: Decimal::from(10),
+            action: OverLimitAction::Reject,
+        })
+    );
+
+    std::env::set_var("MAX_POOL_SHARE_PERCENT", "2.5");
+    std::env::set_var("POOL_SHARE_ACTION", "confirm");
+    let limit = Config::from_env().unwrap().pool_share_limit.unwrap();
+    assert_eq!(limit.max_percent, Decimal::new(25, 1));
+    assert_eq!(limit.action, OverLimitAction::Confirm);
+
+    std::env::set_var("MAX_POOL_SHARE_PERCENT", "0");
+    assert_eq!(Config::from_env().unwrap().pool_share_limit, None);
+
+    std::env::set_var("MAX_POOL_SHARE_PERCENT", "5");
+    std::env::set_var("POOL_SHARE_ACTION", "warn");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("MAX_POOL_SHARE_PERCENT");
+    std::env::remove_var("POOL_SHARE_ACTION");
+}
+
+#[test]
+#[serial]
+fn test_config_price_cross_check() {
+    use crate::config::Config;
+    use rust_decimal::Decimal;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.max_price_deviation, Some(Decimal::from(5)));
+    assert!(config.price_intermediaries.is_empty());
+
+    std::env::set_var("MAX_PRICE_DEVIATION_PERCENT", "0");
+    std::env::set_var(
+        "PRICE_INTERMEDIARIES",
+        "0x6B175474E89094C44Da98b954EedeAC495271d0F, 0xdAC17F958D2ee523a2206206994597C13D831ec7",
+    );
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.max_price_deviation, None);
+    assert_eq!(config.price_intermediaries.len(), 2);
+
+    std::env::set_var("MAX_PRICE_DEVIATION_PERCENT", "-1");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("MAX_PRICE_DEVIATION_PERCENT");
+    std::env::remove_var("PRICE_INTERMEDIARIES");
+}
+
+#[tokio::test]
+async fn test_otlp_exporter_posts_nested_spans() {
+    use crate::telemetry::{OtlpConfig, OtlpExporter};
+    use tracing_subscriber::prelude::*;
+
+    let mut server = mockito::Server::new_async().await;
+    // The caller's trace id carries over from its traceparent header
+    let collector = server
+        .mock("POST", "/v1/traces")
+        .match_header("x-api-key", "collector-key")
+        .match_body(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::Regex(r#""traceId":"4bf92f3577b34da6a3ce929d0e0e4736""#.to_string()),
+            mockito::Matcher::Regex(r#""parentSpanId":"00f067aa0ba902b7""#.to_string()),
+            mockito::Matcher::Regex(r#""name":"tool.execute""#.to_string()),
+            mockito::Matcher::Regex(r#""stringValue":"get_balance""#.to_string()),
+            mockito::Matcher::Regex(r#""message":"RPC unavailable""#.to_string()),
+            mockito::Matcher::Regex(r#""stringValue":"test-server""#.to_string()),
+        ]))
+        .create_async()
+        .await;
+
+    let (exporter, layer) = OtlpExporter::start(&OtlpConfig {
+        endpoint: format!("{}/", server.url()),
+        service_name: "test-server".to_string(),
+        headers: vec![("x-api-key".to_string(), "collector-key".into())],
+    });
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        let call = tracing::info_span!(
+            "mcp.call_tool",
+            otel.kind = "server",
+            traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
         );
+        let _call = call.enter();
+        let execute = tracing::info_span!(
+            "tool.execute",
+            tool = "get_balance",
+            error = tracing::field::Empty
+        );
+        execute.record("error", "RPC unavailable");
+    });
+    exporter.flush().await;
+
+    collector.assert_async().await;
+}
+
+#[test]
+#[serial]
+fn test_config_otlp_export() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    assert!(Config::from_env().unwrap().otlp.is_none());
+
+    std::env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318");
+    std::env::set_var(
+        "OTEL_EXPORTER_OTLP_HEADERS",
+        "x-honeycomb-team=abcdef123456, x-dataset=mcp",
+    );
+    let otlp = Config::from_env().unwrap().otlp.unwrap();
+    assert_eq!(otlp.endpoint, "http://collector:4318");
+    assert_eq!(otlp.service_name, "ethereum-trading-mcp-server");
+    assert_eq!(otlp.headers.len(), 2);
+    assert_eq!(otlp.headers[0].0, "x-honeycomb-team");
+    assert_eq!(otlp.headers[0].1.expose_secret(), "abcdef123456");
+    assert!(!format!("{:?}", otlp).contains("abcdef123456"));
+
+    std::env::set_var("OTEL_SERVICE_NAME", "trading-prod");
+    assert_eq!(
+        Config::from_env().unwrap().otlp.unwrap().service_name,
+        "trading-prod"
+    );
 
-        let result = Config::from_env();
-        assert!(result.is_err());
+    std::env::set_var("OTEL_EXPORTER_OTLP_HEADERS", "no-separator");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT");
+    std::env::remove_var("OTEL_EXPORTER_OTLP_HEADERS");
+    std::env::remove_var("OTEL_SERVICE_NAME");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_config_reads_secrets_from_vault() {
+    use crate::config::Config;
+    use crate::vault::{store_secrets, VaultClient, VaultConfig};
+    use std::time::Duration;
+
+    let key = "00000000000000000000000000000000000000000000000000000000000000aa";
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/v1/auth/approle/login")
+        .match_header("x-vault-namespace", "trading")
+        .match_body(mockito::Matcher::Json(
+            serde_json::json!({"role_id": "role-1", "secret_id": "secret-id-1"}),
+        ))
+        .with_body(
+            r#"{"auth":{"client_token":"hvs.first-token","lease_duration":3600,"renewable":true}}"#,
+        )
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/v1/secret/data/trading")
+        .match_header("x-vault-token", "hvs.first-token")
+        .with_body(format!(
+            r#"{{"data":{{"data":{{"eth_rpc_url":"https://mainnet.example/v3/vault-key","PRIVATE_KEY":"{}","nested":{{}}}},"metadata":{{"version":3}}}}}}"#,
+            key
+        ))
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/v1/auth/token/renew-self")
+        .match_header("x-vault-token", "hvs.first-token")
+        .with_body(
+            r#"{"auth":{"client_token":"hvs.first-token","lease_duration":600,"renewable":true}}"#,
+        )
+        .create_async()
+        .await;
+
+    std::env::set_var("VAULT_ADDR", format!("{}/", server.url()));
+    std::env::set_var("VAULT_ROLE_ID", "role-1");
+    std::env::set_var("VAULT_SECRET_ID", "secret-id-1");
+    std::env::set_var("VAULT_NAMESPACE", "trading");
+    assert!(VaultConfig::from_env().is_err());
+    std::env::set_var("VAULT_SECRET_PATH", "/secret/data/trading");
+    let vault = VaultConfig::from_env().unwrap().unwrap();
+    assert_eq!(vault.secret_path, "secret/data/trading");
+
+    let client = VaultClient::login(&vault).await.unwrap();
+    assert_eq!(client.next_renewal().await, Some(Duration::from_secs(2400)));
+    let secrets = client.read_secrets().await.unwrap();
+    assert_eq!(secrets.len(), 2);
+    assert_eq!(
+        client.renew().await.unwrap(),
+        Some(Duration::from_secs(400))
+    );
+
+    std::env::remove_var("ETH_RPC_URL");
+    std::env::remove_var("PRIVATE_KEY");
+    std::env::set_var("CHAIN_ID", "1");
+    assert!(Config::from_env().is_err());
+    store_secrets(secrets);
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.eth_rpc_url, "https://mainnet.example/v3/vault-key");
+    assert_eq!(config.private_key.unwrap().expose_secret(), key);
+    // The provider key in the URL is scrubbed from logs and errors
+    let logged = crate::secrets::redact("request to https://mainnet.example/v3/vault-key failed");
+    assert!(!logged.contains("vault-key"));
+
+    // Variables set in the environment take precedence
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    assert_eq!(
+        Config::from_env().unwrap().eth_rpc_url,
+        "https://eth.llamarpc.com"
+    );
+
+    store_secrets(Default::default());
+    for key in [
+        "VAULT_ADDR",
+        "VAULT_ROLE_ID",
+        "VAULT_SECRET_ID",
+        "VAULT_NAMESPACE",
+        "VAULT_SECRET_PATH",
+    ] {
+        std::env::remove_var(key);
     }
+}
 
-    #[test]
-    #[serial]
-    fn test_config_missing_private_key() {
-        use crate::config::Config;
-        std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
-        std::env::remove_var("PRIVATE_KEY");
+#[test]
+#[serial]
+fn test_config_reads_secret_files() {
+    use crate::config::Config;
+    let dir = std::env::temp_dir().join(format!("secrets-{}", ethers::types::H64::random()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = "0000000000000000000000000000000000000000000000000000000000000002";
+    std::fs::write(dir.join("private_key"), format!("{}\n", key)).unwrap();
+    std::fs::write(dir.join("rpc_url"), "https://rpc.example/v2/secret-token\n").unwrap();
+
+    std::env::remove_var("PRIVATE_KEY");
+    std::env::remove_var("ETH_RPC_URL");
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::set_var("PRIVATE_KEY_FILE", dir.join("private_key"));
+    std::env::set_var("ETH_RPC_URL_FILE", dir.join("rpc_url"));
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.private_key.unwrap().expose_secret(), key);
+    assert_eq!(config.eth_rpc_url, "https://rpc.example/v2/secret-token");
+    assert!(
+        !crate::secrets::redact("https://rpc.example/v2/secret-token").contains("secret-token")
+    );
+
+    // Per-chain and simulation endpoints carry keys the same way
+    std::env::set_var(
+        "CHAIN_RPC_URLS",
+        "42161=https://arb.example/v2/arb-chain-key",
+    );
+    std::env::set_var("SIMULATION_RPC_URL", "https://fork.example/sim-fork-key");
+    Config::from_env().unwrap();
+    let logged = crate::secrets::redact(
+        "https://arb.example/v2/arb-chain-key and https://fork.example/sim-fork-key",
+    );
+    assert!(!logged.contains("arb-chain-key"));
+    assert!(!logged.contains("sim-fork-key"));
+    std::env::remove_var("CHAIN_RPC_URLS");
+    std::env::remove_var("SIMULATION_RPC_URL");
+
+    // Both forms at once is ambiguous
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    let err = Config::from_env().unwrap_err().to_string();
+    assert!(err.contains("Both PRIVATE_KEY and PRIVATE_KEY_FILE are set"));
+    std::env::remove_var("PRIVATE_KEY");
+
+    std::env::set_var("PRIVATE_KEY_FILE", dir.join("missing"));
+    let err = format!("{:#}", Config::from_env().unwrap_err());
+    assert!(err.contains("Failed to read PRIVATE_KEY"));
+
+    std::env::remove_var("PRIVATE_KEY_FILE");
+    std::env::remove_var("ETH_RPC_URL_FILE");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_config_pid_file() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    assert!(Config::from_env().unwrap().pid_file.is_none());
+
+    std::env::set_var("PID_FILE", "/run/ethereum-mcp/server.pid");
+    assert_eq!(
+        Config::from_env().unwrap().pid_file,
+        Some(std::path::PathBuf::from("/run/ethereum-mcp/server.pid"))
+    );
+
+    std::env::remove_var("PID_FILE");
+}
+
+#[test]
+#[serial]
+fn test_config_log_format() {
+    use crate::config::Config;
+    use crate::logging::LogFormat;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    assert_eq!(Config::from_env().unwrap().log_format, LogFormat::Text);
+
+    std::env::set_var("LOG_FORMAT", "JSON");
+    assert_eq!(Config::from_env().unwrap().log_format, LogFormat::Json);
+
+    std::env::set_var("LOG_FORMAT", "yaml");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("LOG_FORMAT");
+}
 
-        let result = Config::from_env();
-        assert!(result.is_err());
+#[test]
+#[serial]
+fn test_config_gas_sponsor() {
+    use crate::config::Config;
+    use crate::ethereum::GasSponsorConfig;
+    use ethers::types::Address;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    assert!(Config::from_env().unwrap().gas_sponsor.is_none());
+
+    std::env::set_var("GAS_SPONSOR", "forwarder");
+    let err = Config::from_env().unwrap_err().to_string();
+    assert!(err.contains("RELAYER_PRIVATE_KEY is required with GAS_SPONSOR=forwarder"));
+    std::env::set_var(
+        "RELAYER_PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+    );
+    std::env::set_var(
+        "TRUSTED_FORWARDER",
+        "0x1111111111111111111111111111111111111111",
+    );
+    match Config::from_env().unwrap().gas_sponsor {
+        Some(GasSponsorConfig::Forwarder { forwarder, .. }) => {
+            assert_eq!(forwarder, Address::repeat_byte(0x11))
+        }
+        other => panic!("expected a forwarder, got {:?}", other),
     }
 
-    #[test]
-    #[serial]
-    fn test_config_invalid_chain_id() {
-        use crate::config::Config;
-        std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
-        std::env::set_var(
-            "PRIVATE_KEY",
-            "0000000000000000000000000000000000000000000000000000000000000001",
+    std::env::set_var("GAS_SPONSOR", "paymaster");
+    std::env::set_var("PAYMASTER_URL", "https://paymaster.example/rpc");
+    std::env::set_var(
+        "SMART_ACCOUNT",
+        "0x2222222222222222222222222222222222222222",
+    );
+    match Config::from_env().unwrap().gas_sponsor {
+        Some(GasSponsorConfig::Paymaster {
+            account,
+            entry_point,
+            bundler_url,
+            ..
+        }) => {
+            assert_eq!(account, Address::repeat_byte(0x22));
+            assert_eq!(
+                entry_point,
+                "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789"
+                    .parse::<Address>()
+                    .unwrap()
+            );
+            assert_eq!(bundler_url, "https://paymaster.example/rpc");
+        }
+        other => panic!("expected a paymaster, got {:?}", other),
+    }
+
+    std::env::set_var("GAS_SPONSOR", "relayer");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("GAS_SPONSOR");
+    std::env::remove_var("RELAYER_PRIVATE_KEY");
+    std::env::remove_var("TRUSTED_FORWARDER");
+    std::env::remove_var("PAYMASTER_URL");
+    std::env::remove_var("SMART_ACCOUNT");
+}
+
+#[test]
+#[serial]
+fn test_config_route_objective() {
+    use crate::config::Config;
+    use crate::tools::RouteObjective;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    assert_eq!(
+        Config::from_env().unwrap().route_objective,
+        RouteObjective::NetOfGas
+    );
+
+    std::env::set_var("ROUTE_OBJECTIVE", "output");
+    assert_eq!(
+        Config::from_env().unwrap().route_objective,
+        RouteObjective::Output
+    );
+
+    std::env::set_var("ROUTE_OBJECTIVE", "cheapest");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("ROUTE_OBJECTIVE");
+}
+
+#[test]
+#[serial]
+fn test_config_totp_secret() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    assert!(Config::from_env().unwrap().totp_secret.is_none());
+
+    std::env::set_var("TOTP_SECRET", "gezd gnbv gy3t qojq gezd gnbv gy3t qojq");
+    let config = Config::from_env().unwrap();
+    assert!(config.totp_secret.is_some());
+    assert!(!format!("{:?}", config).contains("gezd"));
+
+    std::env::set_var("TOTP_SECRET", "123456");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("TOTP_SECRET");
+}
+
+#[test]
+#[serial]
+fn test_config_kms_signer() {
+    use crate::config::Config;
+    use crate::ethereum::KmsConfig;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::remove_var("PRIVATE_KEY");
+    std::env::set_var("CHAIN_ID", "1");
+
+    // The key stays in KMS, so no local key is needed
+    std::env::set_var("SIGNER", "aws_kms");
+    std::env::set_var("KMS_KEY_ID", "alias/trading");
+    std::env::set_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE");
+    std::env::set_var(
+        "AWS_SECRET_ACCESS_KEY",
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    );
+    std::env::remove_var("AWS_REGION");
+    std::env::remove_var("AWS_DEFAULT_REGION");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("AWS_DEFAULT_REGION", "eu-west-1");
+    let config = Config::from_env().unwrap();
+    assert!(config.private_key.is_none());
+    match config.kms.unwrap() {
+        KmsConfig::Aws {
+            key_id,
+            region,
+            credentials,
+            endpoint,
+        } => {
+            assert_eq!(key_id, "alias/trading");
+            assert_eq!(region, "eu-west-1");
+            assert_eq!(credentials.access_key_id, "AKIDEXAMPLE");
+            assert!(credentials.session_token.is_none());
+            assert!(endpoint.is_none());
+        }
+        other => panic!("unexpected KMS config: {:?}", other),
+    }
+
+    // Sponsored calls are signed with the local key
+    std::env::set_var("GAS_SPONSOR", "paymaster");
+    assert!(Config::from_env().is_err());
+    std::env::remove_var("GAS_SPONSOR");
+
+    std::env::set_var("SIGNER", "gcp_kms");
+    assert!(Config::from_env().is_err());
+    let key_name = "projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1";
+    std::env::set_var("KMS_KEY_NAME", key_name);
+    std::env::set_var("KMS_ENDPOINT", "http://127.0.0.1:9000/");
+    match Config::from_env().unwrap().kms.unwrap() {
+        KmsConfig::Gcp {
+            key_name: name,
+            access_token,
+            endpoint,
+        } => {
+            assert_eq!(name, key_name);
+            assert!(access_token.is_none());
+            assert_eq!(endpoint.as_deref(), Some("http://127.0.0.1:9000"));
+        }
+        other => panic!("unexpected KMS config: {:?}", other),
+    }
+
+    for key in [
+        "SIGNER",
+        "KMS_KEY_ID",
+        "KMS_KEY_NAME",
+        "KMS_ENDPOINT",
+        "AWS_ACCESS_KEY_ID",
+        "AWS_SECRET_ACCESS_KEY",
+        "AWS_DEFAULT_REGION",
+    ] {
+        std::env::remove_var(key);
+    }
+}
+
+#[test]
+#[serial]
+fn test_config_walletconnect_signer() {
+    use crate::config::Config;
+    let wallet = "0x00000000000000000000000000000000000000a1";
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::remove_var("PRIVATE_KEY");
+    std::env::set_var("CHAIN_ID", "1");
+    assert!(Config::from_env().is_err());
+
+    // A paired wallet signs, so no local key is needed
+    std::env::set_var("SIGNER", "walletconnect");
+    std::env::set_var("WALLETCONNECT_BRIDGE_URL", "http://127.0.0.1:8787");
+    std::env::set_var("WALLETCONNECT_PROJECT_ID", "demo-project");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("WALLET_ADDRESS", wallet);
+    let config = Config::from_env().unwrap();
+    assert!(config.private_key.is_none());
+    let walletconnect = config.walletconnect.unwrap();
+    assert_eq!(walletconnect.address, wallet.parse().unwrap());
+    assert_eq!(walletconnect.request_timeout.as_secs(), 300);
+
+    // Attesting with the trading key needs one
+    std::env::set_var("ATTEST_RESPONSES", "true");
+    assert!(Config::from_env().is_err());
+    std::env::remove_var("ATTEST_RESPONSES");
+
+    std::env::set_var("SIGNER", "ledger");
+    assert!(Config::from_env().is_err());
+
+    for key in [
+        "SIGNER",
+        "WALLETCONNECT_BRIDGE_URL",
+        "WALLETCONNECT_PROJECT_ID",
+        "WALLET_ADDRESS",
+    ] {
+        std::env::remove_var(key);
+    }
+}
+
+#[test]
+#[serial]
+fn test_config_payment_schedules() {
+    use crate::config::Config;
+    use std::time::Duration;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.schedules_path, None);
+    assert_eq!(config.schedule_audit_log, None);
+    assert_eq!(config.schedule_tick, Duration::from_secs(30));
+
+    std::env::set_var("SCHEDULES_PATH", "/var/lib/mcp/schedules.json");
+    std::env::set_var("SCHEDULE_AUDIT_LOG", "/var/log/mcp/payments.jsonl");
+    std::env::set_var("SCHEDULE_TICK_SECS", "10");
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.schedules_path,
+        Some("/var/lib/mcp/schedules.json".into())
+    );
+    assert_eq!(
+        config.schedule_audit_log,
+        Some("/var/log/mcp/payments.jsonl".into())
+    );
+    assert_eq!(config.schedule_tick, Duration::from_secs(10));
+
+    std::env::set_var("SCHEDULE_TICK_SECS", "0");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("SCHEDULES_PATH");
+    std::env::remove_var("SCHEDULE_AUDIT_LOG");
+    std::env::remove_var("SCHEDULE_TICK_SECS");
+}
+
+#[test]
+#[serial]
+fn test_config_governance() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert!(config.governors.is_empty());
+    assert_eq!(config.snapshot_hub_url, "https://hub.snapshot.org");
+    assert_eq!(config.four_byte_url, "https://www.4byte.directory");
+    assert_eq!(config.scam_list_feeds.len(), 1);
+    assert_eq!(config.scam_list_feeds[0].name, "scamsniffer");
+    assert!(!config.allow_scam_list_override);
+
+    std::env::set_var(
+        "GOVERNORS",
+        "arbitrum:0xf07DeD9dC292157749B6Fd268E37DF6EA38395B9",
+    );
+    std::env::set_var("SNAPSHOT_HUB_URL", "https://testnet.hub.snapshot.org");
+    std::env::set_var("FOUR_BYTE_URL", "http://localhost:8000/");
+    std::env::set_var(
+        "SCAM_LIST_URLS",
+        "chainabuse=https://example.com/reports.csv, https://lists.example.org/scams.json",
+    );
+    std::env::set_var("SCAM_LIST_REFRESH_SECS", "600");
+    std::env::set_var("ALLOW_SCAM_LIST_OVERRIDE", "true");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.governors[0].0, "arbitrum");
+    assert_eq!(
+        config.governors[0].1,
+        "0xf07DeD9dC292157749B6Fd268E37DF6EA38395B9"
+            .parse::<ethers::types::Address>()
+            .unwrap()
+    );
+    assert_eq!(config.snapshot_hub_url, "https://testnet.hub.snapshot.org");
+    assert_eq!(config.four_byte_url, "http://localhost:8000/");
+    let names: Vec<&str> = config
+        .scam_list_feeds
+        .iter()
+        .map(|feed| feed.name.as_str())
+        .collect();
+    assert_eq!(names, ["chainabuse", "lists.example.org"]);
+    assert_eq!(
+        config.scam_list_feeds[0].url,
+        "https://example.com/reports.csv"
+    );
+    assert_eq!(config.scam_list_refresh.as_secs(), 600);
+    assert!(config.allow_scam_list_override);
+
+    std::env::set_var("SCAM_LIST_URLS", "none");
+    assert!(Config::from_env().unwrap().scam_list_feeds.is_empty());
+    std::env::set_var("SCAM_LIST_URLS", "a=https://x.example, a=https://y.example");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("SCAM_LIST_URLS", "ftp.example.com/list");
+    assert!(Config::from_env().is_err());
+    std::env::remove_var("SCAM_LIST_URLS");
+
+    std::env::set_var("GOVERNORS", "arbitrum");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("GOVERNORS");
+    std::env::remove_var("SNAPSHOT_HUB_URL");
+    std::env::remove_var("FOUR_BYTE_URL");
+    std::env::remove_var("SCAM_LIST_REFRESH_SECS");
+    std::env::remove_var("ALLOW_SCAM_LIST_OVERRIDE");
+}
+
+#[test]
+#[serial]
+fn test_config_watch_addresses() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert!(config.watch_addresses.is_empty());
+    assert_eq!(config.watch_poll.as_secs(), 15);
+    assert_eq!(config.watch_feed_size, 1000);
+
+    std::env::set_var(
+        "WATCH_ADDRESSES",
+        "treasury:0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+    );
+    std::env::set_var("WATCH_POLL_SECS", "60");
+    std::env::set_var("WATCH_FEED_SIZE", "50");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.watch_addresses[0].0, "treasury");
+    assert_eq!(
+        config.watch_addresses[0].1,
+        "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+            .parse::<ethers::types::Address>()
+            .unwrap()
+    );
+    assert_eq!(config.watch_poll.as_secs(), 60);
+    assert_eq!(config.watch_feed_size, 50);
+
+    std::env::set_var("WATCH_FEED_SIZE", "0");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("WATCH_POLL_SECS", "0");
+    std::env::remove_var("WATCH_FEED_SIZE");
+    assert!(Config::from_env().is_err());
+    std::env::set_var(
+        "WATCH_ADDRESSES",
+        "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+    );
+    std::env::remove_var("WATCH_POLL_SECS");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("WATCH_ADDRESSES");
+}
+
+#[test]
+#[serial]
+fn test_config_trade_ledger() {
+    use crate::config::Config;
+    use crate::tools::AccountingMethod;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.trade_ledger_path, None);
+    assert_eq!(config.tax_accounting_method, AccountingMethod::Fifo);
+
+    std::env::set_var("TRADE_LEDGER_PATH", "./trades.json");
+    std::env::set_var("TAX_ACCOUNTING_METHOD", "HIFO");
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.trade_ledger_path,
+        Some(std::path::PathBuf::from("./trades.json"))
+    );
+    assert_eq!(config.tax_accounting_method, AccountingMethod::Hifo);
+
+    std::env::set_var("TAX_ACCOUNTING_METHOD", "average");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("TRADE_LEDGER_PATH");
+    std::env::remove_var("TAX_ACCOUNTING_METHOD");
+}
+
+#[test]
+#[serial]
+fn test_config_webhooks() {
+    use crate::config::Config;
+    use crate::mcp::webhooks::DEFAULT_WEBHOOK_RETRIES;
+    use crate::tools::event_bus::DEFAULT_EVENT_BUS_CAPACITY;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert!(config.webhook_urls.is_empty());
+    assert_eq!(config.webhook_max_retries, DEFAULT_WEBHOOK_RETRIES);
+    assert_eq!(config.event_bus_capacity, DEFAULT_EVENT_BUS_CAPACITY);
+
+    std::env::set_var("EVENT_BUS_CAPACITY", "0");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("EVENT_BUS_CAPACITY", "64");
+    assert_eq!(Config::from_env().unwrap().event_bus_capacity, 64);
+    std::env::remove_var("EVENT_BUS_CAPACITY");
+
+    std::env::set_var(
+        "WEBHOOK_URLS",
+        "https://hooks.example.com/T000/B000/xyz, http://localhost:9000/events",
+    );
+    std::env::set_var("WEBHOOK_TOPICS", "transactions, policy");
+    std::env::set_var("WEBHOOK_SECRET", "whsec-test");
+    std::env::set_var("WEBHOOK_MAX_RETRIES", "5");
+    let config = Config::from_env().unwrap();
+    let urls: Vec<&str> = config
+        .webhook_urls
+        .iter()
+        .map(|url| url.expose_secret())
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "https://hooks.example.com/T000/B000/xyz",
+            "http://localhost:9000/events"
+        ]
+    );
+    assert_eq!(config.webhook_topics, ["transactions", "policy"]);
+    assert_eq!(
+        config.webhook_secret.as_ref().map(|s| s.expose_secret()),
+        Some("whsec-test")
+    );
+    assert_eq!(config.webhook_max_retries, 5);
+    // The URLs carry credentials
+    let logged = crate::secrets::redact("posting to https://hooks.example.com/T000/B000/xyz");
+    assert!(!logged.contains("B000/xyz"));
+
+    std::env::set_var("WEBHOOK_URLS", "ftp://example.com/hook");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("WEBHOOK_URLS");
+    std::env::remove_var("WEBHOOK_TOPICS");
+    std::env::remove_var("WEBHOOK_SECRET");
+    std::env::remove_var("WEBHOOK_MAX_RETRIES");
+}
+
+#[test]
+#[serial]
+fn test_config_chat_bots() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert!(config.telegram.is_none());
+    assert!(config.discord.is_none());
+
+    std::env::set_var("TELEGRAM_BOT_TOKEN", "123456:telegram-token");
+    std::env::set_var("TELEGRAM_CHAT_ID", "-1001234567890");
+    std::env::set_var("CHAT_TOPICS", "transactions");
+    let config = Config::from_env().unwrap();
+    let telegram = config.telegram.unwrap();
+    assert_eq!(telegram.token.expose_secret(), "123456:telegram-token");
+    assert_eq!(telegram.chat_id, "-1001234567890");
+    assert_eq!(config.chat_topics, ["transactions"]);
+    assert!(!crate::secrets::redact("bot123456:telegram-token").contains("telegram-token"));
+
+    // A token without a chat to post to is a mistake
+    std::env::set_var("DISCORD_BOT_TOKEN", "discord-token");
+    assert!(Config::from_env().is_err());
+    std::env::set_var("DISCORD_CHANNEL_ID", "123456789012345678");
+    assert!(Config::from_env().unwrap().discord.is_some());
+
+    for key in [
+        "TELEGRAM_BOT_TOKEN",
+        "TELEGRAM_CHAT_ID",
+        "DISCORD_BOT_TOKEN",
+        "DISCORD_CHANNEL_ID",
+        "CHAT_TOPICS",
+    ] {
+        std::env::remove_var(key);
+    }
+}
+
+#[test]
+#[serial]
+fn test_config_tool_aliases() {
+    use crate::config::Config;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::set_var(
+        "TOOL_ALIASES",
+        "eth_getBalance:get_balance, check_price : get_token_price",
+    );
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        config.tool_aliases,
+        [
+            ("eth_getBalance".to_string(), "get_balance".to_string()),
+            ("check_price".to_string(), "get_token_price".to_string()),
+        ]
+    );
+    assert!(!config.list_tool_aliases);
+
+    std::env::set_var("TOOL_ALIASES", "check_price");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("TOOL_ALIASES");
+}
+
+#[test]
+#[serial]
+fn test_config_contacts_and_saved_address_book() {
+    use crate::config::Config;
+    use crate::tools::{AddressBook, Contact};
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+    std::env::set_var(
+        "CONTACTS",
+        "vitalik:0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+    );
+    let path = std::env::temp_dir().join(format!("address-book-{}.json", std::process::id()));
+    std::env::set_var("ADDRESS_BOOK_PATH", &path);
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.contacts[0].0, "vitalik");
+    assert_eq!(config.address_book_path.as_ref(), Some(&path));
+    assert!(!config.confirm_unknown_recipients);
+
+    let configured = |config: &Config| {
+        config
+            .contacts
+            .iter()
+            .map(|(name, address)| Contact {
+                name: name.clone(),
+                address: *address,
+                note: None,
+            })
+            .collect()
+    };
+    let book = AddressBook::open(path.clone(), configured(&config)).unwrap();
+    book.add(
+        Contact {
+            name: "exchange".to_string(),
+            address: ethers::types::Address::repeat_byte(0x11),
+            note: Some("deposit address".to_string()),
+        },
+        false,
+    )
+    .unwrap();
+
+    // Added contacts survive a restart alongside the configured ones
+    let reopened = AddressBook::open(path.clone(), configured(&config)).unwrap();
+    let names: Vec<String> = reopened.list().into_iter().map(|c| c.name).collect();
+    assert_eq!(names, ["exchange", "vitalik"]);
+
+    std::fs::remove_file(&path).unwrap();
+    std::env::remove_var("CONTACTS");
+    std::env::remove_var("ADDRESS_BOOK_PATH");
+}
+
+#[test]
+fn test_apy_from_per_second_rate() {
+    use crate::ethereum::yields::apy_from_per_second_rate;
+    use rust_decimal::Decimal;
+
+    assert_eq!(apy_from_per_second_rate(0.0).unwrap(), Decimal::ZERO);
+    // 5% APR accrued every second compounds to about 5.127%
+    let apy = apy_from_per_second_rate(0.05 / (365.0 * 24.0 * 3600.0)).unwrap();
+    assert!((apy - Decimal::new(5127, 3)).abs() < Decimal::new(1, 3));
+
+    // A garbage rate from a misbehaving contract is an error, not a 0% APY
+    let err = apy_from_per_second_rate(0.01).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+    assert!(apy_from_per_second_rate(f64::NAN).is_err());
+}
+
+#[test]
+fn test_ens_namehash_and_normalization() {
+    use crate::ethereum::ens::{eth_label, namehash, normalize_name};
+    use ethers::types::H256;
+
+    assert_eq!(namehash(""), H256::zero());
+    assert_eq!(
+        format!("{:?}", namehash("eth")),
+        "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+    );
+    assert_eq!(
+        format!("{:?}", namehash("foo.eth")),
+        "0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+    );
+
+    assert_eq!(normalize_name(" Vitalik.ETH. ").unwrap(), "vitalik.eth");
+    assert_eq!(normalize_name("vitalik").unwrap(), "vitalik.eth");
+    assert!(normalize_name("a..eth").is_err());
+    assert!(normalize_name("my name.eth").is_err());
+    assert_eq!(eth_label("vitalik.eth"), Some("vitalik"));
+    assert_eq!(eth_label("pay.vitalik.eth"), None);
+}
+
+#[test]
+fn test_bytecode_disassembly_and_selectors() {
+    use crate::ethereum::bytecode::*;
+    use ethers::utils::hex;
+
+    let code = hex::decode("6080604052630102").unwrap();
+    let instructions = disassemble(&code);
+    assert_eq!(instructions.len(), 4);
+    assert_eq!(instructions[1].text(), "PUSH1 0x40");
+    assert_eq!(instructions[2].text(), "MSTORE");
+    // A PUSH cut off by the end of the code keeps what is there
+    assert_eq!(instructions[3].pc, 5);
+    assert_eq!(instructions[3].immediate, vec![0x01, 0x02]);
+    assert_eq!(disassemble(&[0x0c])[0].text(), "UNKNOWN(0x0c)");
+
+    let with_metadata = hex::decode("6001a2f4ff000004").unwrap();
+    assert_eq!(strip_metadata(&with_metadata), &with_metadata[..2]);
+    let without = hex::decode("6001600200").unwrap();
+    assert_eq!(strip_metadata(&without), &without[..]);
+
+    // Legacy (DUP1 PUSH4 EQ) and via-IR (PUSH4 DUP2 EQ) dispatchers; GT pivots are skipped
+    let dispatcher = hex::decode("8063a9059cbb14636352211e1163095ea7b38114").unwrap();
+    assert_eq!(
+        function_selectors(&dispatcher),
+        vec![[0xa9, 0x05, 0x9c, 0xbb], [0x09, 0x5e, 0xa7, 0xb3]]
+    );
+    assert_eq!(minimal_proxy_target(&dispatcher), None);
+    // Code cut off inside a PUSH4 has no selector to read
+    let truncated = hex::decode("8063a9059c").unwrap();
+    assert!(function_selectors(&truncated).is_empty());
+}
+
+#[test]
+fn test_storage_slot_constants_and_mapping_slots() {
+    use crate::ethereum::storage::*;
+    use ethers::types::{BigEndianHash, H256, U256};
+    use ethers::utils::keccak256;
+
+    let eip1967 = |label: &str| H256::from_uint(&(U256::from(keccak256(label)) - U256::one()));
+    let slot = |constant: &str| constant.parse::<H256>().unwrap();
+    assert_eq!(
+        slot(EIP1967_IMPLEMENTATION_SLOT),
+        eip1967("eip1967.proxy.implementation")
+    );
+    assert_eq!(slot(EIP1967_ADMIN_SLOT), eip1967("eip1967.proxy.admin"));
+    assert_eq!(slot(EIP1967_BEACON_SLOT), eip1967("eip1967.proxy.beacon"));
+    assert_eq!(
+        slot(EIP1822_PROXIABLE_SLOT),
+        H256::from(keccak256("PROXIABLE"))
+    );
+    assert_eq!(
+        slot(ZEPPELINOS_IMPLEMENTATION_SLOT),
+        H256::from(keccak256("org.zeppelinos.proxy.implementation"))
+    );
+
+    // Known: balances at slot 0 of a Solidity token, for holder 0x...01
+    let key = H256::from_low_u64_be(1);
+    assert_eq!(
+        format!(
+            "{:?}",
+            mapping_slot(H256::zero(), key.as_bytes(), MappingLayout::Solidity)
+        ),
+        "0xada5013122d395ba3c54772283fb069b10426056ef8ca54750cb9bb552a59e7d"
+    );
+    assert_eq!(
+        mapping_slot(H256::zero(), key.as_bytes(), MappingLayout::Vyper),
+        H256::from(keccak256(
+            [H256::zero().as_bytes(), key.as_bytes()].concat()
+        ))
+    );
+    assert_eq!(
+        format!("{:?}", array_data_slot(H256::zero())),
+        "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+    );
+}
+
+#[test]
+fn test_built_in_contracts_construct_from_their_address_constants() {
+    use crate::ethereum::pools::mainnet_factories;
+    use crate::ethereum::*;
+    use ethers::providers::{Http, Provider};
+    use ethers::types::{Address, U256};
+    use std::sync::Arc;
+
+    let provider = Arc::new(Provider::<Http>::try_from("http://localhost:8545").unwrap());
+    UniswapV2Router::new(provider.clone()).unwrap();
+    UniswapV2Router::sushiswap(provider.clone()).unwrap();
+    UniswapV3Pools::new(provider.clone()).unwrap();
+    CurveVenue::new(provider.clone()).unwrap();
+    CompoundV3Yield::new(provider.clone()).unwrap();
+    UnicryptLocker::new(provider.clone()).unwrap();
+    TeamFinanceLocker::new(provider.clone()).unwrap();
+    SablierStreams::new(provider.clone()).unwrap();
+    SuperfluidFlows::new(provider.clone()).unwrap();
+    EnsContracts::new(provider.clone()).unwrap();
+    PoolDiscovery::new(provider.clone()).unwrap();
+    assert_eq!(mainnet_factories().unwrap().len(), 3);
+
+    for chain_id in [1, 10, 56, 137, 8453, 42161] {
+        let pricing = ChainPricing::uniswap_v2(chain_id, provider.clone()).unwrap();
+        assert!(pricing.is_some(), "chain {}", chain_id);
+    }
+    assert!(ChainPricing::uniswap_v2(31337, provider).unwrap().is_none());
+
+    let wrap = PlanStep::Wrap {
+        amount: U256::exp10(18),
+    };
+    let tx = wrap.to_transaction(Address::zero(), U256::zero()).unwrap();
+    assert_eq!(tx.value(), Some(&U256::exp10(18)));
+}
+
+#[tokio::test]
+async fn test_snapshot_hub_rejects_out_of_range_numbers() {
+    use crate::ethereum::{GovernanceSource, SnapshotHub};
+    use ethers::types::Address;
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("proposals".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"data":{"proposals":[{"id":"0xabc","title":"Spam","body":"","author":"0x00000000000000000000000000000000000000a1","state":"active","start":1700000000,"end":1700600000,"choices":["For","Against"],"scores":[1e40]}]}}"#,
+        )
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("vp\\(".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"data":{"vp":{"vp":1e40}}}"#)
+        .create_async()
+        .await;
+
+    let hub = SnapshotHub::new(&format!("{}/", server.url()));
+    let err = hub.proposals("spam.eth", true, 5).await.unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+    let err = hub
+        .voting_power("spam.eth", Address::zero(), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+}
+
+// ============ Fee-on-Transfer Probe Tests ============
+
+/// A V2 router node quoting `quoted` out, where the supporting swap method delivers
+/// `delivered` and every call reverts when the wallet is not `funded`. Returns the
+/// server and a count of supporting-method calls.
+async fn fee_on_transfer_server(
+    quoted: u64,
+    delivered: u64,
+    funded: bool,
+) -> (
+    mockito::ServerGuard,
+    std::sync::Arc<std::sync::atomic::AtomicUsize>,
+) {
+    use ethers::abi::{encode, Token};
+    use ethers::types::U256;
+    use ethers::utils::id;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let amounts_out = id("getAmountsOut(uint256,address[])");
+    let strict = id("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)");
+    let supporting = id(
+        "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+    );
+    let probes = Arc::new(AtomicUsize::new(0));
+    let counted = probes.clone();
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/")
+        .with_header("content-type", "application/json")
+        .with_body_from_request(move |request| {
+            let request: Value = serde_json::from_slice(request.body().unwrap()).unwrap();
+            let tx = &request["params"][0];
+            let data = tx["data"].as_str().or(tx["input"].as_str()).unwrap_or("0x");
+            let data = hex::decode(data.trim_start_matches("0x")).unwrap_or_default();
+            let selector = data.get(..4).unwrap_or_default();
+            // Both swap methods take amountIn, then amountOutMin
+            let min_out = data
+                .get(36..68)
+                .map(U256::from_big_endian)
+                .unwrap_or_default();
+            let amounts = |out: u64| {
+                let amounts = vec![Token::Uint(U256::exp10(18)), Token::Uint(out.into())];
+                format!("0x{}", hex::encode(encode(&[Token::Array(amounts)])))
+            };
+            let result = match request["method"].as_str().unwrap() {
+                "eth_gasPrice" => Some("0x3b9aca00".to_string()),
+                "eth_estimateGas" => funded.then(|| "0x30d40".to_string()),
+                "eth_call" if selector == amounts_out => Some(amounts(quoted)),
+                "eth_call" if selector == strict => {
+                    (funded && U256::from(delivered) >= min_out).then(|| amounts(quoted))
+                }
+                "eth_call" if selector == supporting => {
+                    counted.fetch_add(1, Ordering::SeqCst);
+                    (funded && U256::from(delivered) >= min_out).then(|| "0x".to_string())
+                }
+                _ => None,
+            };
+            let response = match result {
+                Some(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": { "code": 3, "message": "execution reverted: TRANSFER_FAILED" }
+                }),
+            };
+            response.to_string().into_bytes()
+        })
+        .create_async()
+        .await;
+    (server, probes)
+}
+
+#[tokio::test]
+async fn test_simulate_swap_probes_fee_on_transfer_output() {
+    use crate::ethereum::uniswap::UniswapV2Router;
+    use ethers::providers::{Http, Provider};
+    use ethers::types::{Address, U256};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    let (token_in, token_out) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    let wallet = Address::repeat_byte(3);
+    let simulate = |server: &mockito::ServerGuard| {
+        let provider = Provider::<Http>::try_from(server.url()).unwrap();
+        let router = UniswapV2Router::with_addresses(
+            Arc::new(provider),
+            "uniswap_v2",
+            Address::repeat_byte(4),
+            Address::repeat_byte(5),
         );
-        std::env::set_var("CHAIN_ID", "invalid");
+        async move {
+            router
+                .simulate_swap(token_in, token_out, U256::exp10(18), wallet)
+                .await
+                .unwrap()
+        }
+    };
+
+    // A 5% tax is bracketed to the unit by the binary search
+    let (server, probes) = fee_on_transfer_server(1_000_000, 950_000, true).await;
+    let simulation = simulate(&server).await;
+    assert!(simulation.fee_on_transfer);
+    assert_eq!(simulation.amount_out, U256::from(950_000));
+    assert_eq!(simulation.transfer_fee_bps, Some(500));
+    assert!(simulation.fee_detection_warning.is_none());
+    assert!(probes.load(Ordering::SeqCst) <= 25);
+
+    // Without a tax the strict method passes and nothing is probed
+    let (server, probes) = fee_on_transfer_server(1_000_000, 1_000_000, true).await;
+    let simulation = simulate(&server).await;
+    assert!(!simulation.fee_on_transfer);
+    assert_eq!(simulation.amount_out, U256::from(1_000_000));
+    assert_eq!(simulation.transfer_fee_bps, None);
+    assert_eq!(probes.load(Ordering::SeqCst), 0);
+
+    // A token that delivers nothing reverts at every positive amount
+    let (server, _) = fee_on_transfer_server(1_000_000, 0, true).await;
+    let simulation = simulate(&server).await;
+    assert!(simulation.fee_on_transfer);
+    assert_eq!(simulation.amount_out, U256::zero());
+    assert_eq!(simulation.transfer_fee_bps, Some(10_000));
+
+    // Without balance or allowance even the unconstrained call reverts, so detection
+    // is skipped with a warning instead of reporting a fee
+    let (server, probes) = fee_on_transfer_server(1_000_000, 950_000, false).await;
+    let simulation = simulate(&server).await;
+    assert!(!simulation.fee_on_transfer);
+    assert_eq!(simulation.amount_out, U256::from(1_000_000));
+    assert_eq!(probes.load(Ordering::SeqCst), 1);
+    let warning = simulation.fee_detection_warning.unwrap();
+    assert!(
+        warning.contains("detection on uniswap_v2 was skipped"),
+        "{}",
+        warning
+    );
+    assert!(simulation.gas_estimate_error.is_some());
+}
+
+// ============ Background Task Supervisor Tests ============
+
+#[tokio::test]
+async fn test_supervisor_restarts_crashed_tasks() {
+    use crate::supervisor::{RestartPolicy, Supervisor, TaskState};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let policy = RestartPolicy {
+        backoff: Duration::from_millis(5),
+        max_backoff: Duration::from_secs(60),
+        max_restarts: None,
+    };
+    let starts = Arc::new(AtomicU32::new(0));
+    let mut supervisor = Supervisor::new();
+    {
+        let starts = starts.clone();
+        supervisor.add("flaky", policy, move |mut stop| {
+            let run = starts.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                // Crashes twice, then runs until stopped
+                if run < 2 {
+                    panic!("crash {}", run);
+                }
+                let _ = stop.changed().await;
+            })
+        });
+    }
+    supervisor.add(
+        "exits",
+        RestartPolicy {
+            max_restarts: Some(1),
+            ..policy
+        },
+        |_stop| tokio::spawn(async {}),
+    );
+    assert!(supervisor.health().is_empty());
+
+    supervisor.start();
+    for _ in 0..200 {
+        let health = supervisor.health();
+        if health.len() == 2
+            && health[0].state == TaskState::Failed
+            && health[1].state == TaskState::Running
+            && health[1].restarts == 2
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    let health = supervisor.health();
+    assert_eq!(health[0].name, "exits");
+    assert_eq!(health[0].state, TaskState::Failed);
+    assert_eq!(health[0].restarts, 1);
+    assert_eq!(
+        health[0].last_error.as_deref(),
+        Some("exited before it was stopped")
+    );
+    assert_eq!(health[1].name, "flaky");
+    assert_eq!(health[1].state, TaskState::Running);
+    assert_eq!(health[1].restarts, 2);
+    assert_eq!(health[1].last_error.as_deref(), Some("panicked: crash 1"));
+    assert!(supervisor.degraded());
+
+    // Stopping waits for the running task and is not counted as a crash
+    tokio::time::timeout(Duration::from_secs(1), supervisor.stop())
+        .await
+        .unwrap();
+    let health = supervisor.health();
+    assert_eq!(health[1].state, TaskState::Stopped);
+    assert_eq!(health[1].restarts, 2);
+    assert_eq!(starts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+#[serial]
+fn test_config_task_restart() {
+    use crate::config::Config;
+    use crate::supervisor::{RestartPolicy, DEFAULT_MAX_RESTART_BACKOFF};
+    use std::time::Duration;
+    std::env::set_var("ETH_RPC_URL", "https://eth.llamarpc.com");
+    std::env::set_var(
+        "PRIVATE_KEY",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    std::env::set_var("CHAIN_ID", "1");
+
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.task_restart, RestartPolicy::default());
+    assert_eq!(config.task_restart.max_backoff, DEFAULT_MAX_RESTART_BACKOFF);
+
+    std::env::set_var("TASK_RESTART_MAX_BACKOFF_SECS", "300");
+    std::env::set_var("TASK_MAX_RESTARTS", "5");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.task_restart.max_backoff, Duration::from_secs(300));
+    assert_eq!(config.task_restart.max_restarts, Some(5));
+
+    std::env::set_var("TASK_MAX_RESTARTS", "many");
+    assert!(Config::from_env().is_err());
+
+    std::env::remove_var("TASK_RESTART_MAX_BACKOFF_SECS");
+    std::env::remove_var("TASK_MAX_RESTARTS");
+}
+
+// ============ WebSocket Reconnection Tests ============
+
+/// Serves one WebSocket connection as a node would: answers eth_subscribe and
+/// eth_blockNumber with `head`, pushes block `head + 1`, then drops the connection
+/// when `drop_after` is set or stays open otherwise
+async fn serve_ws_node(stream: tokio::net::TcpStream, head: u64, drop_after: bool) {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+    let mut answered = 0;
+    while let Some(Ok(Message::Text(text))) = ws.next().await {
+        let request: Value = serde_json::from_str(&text).unwrap();
+        let result = match request["method"].as_str() {
+            Some("eth_subscribe") => json!("0xabc"),
+            Some("eth_blockNumber") => json!(format!("{:#x}", head)),
+            _ => continue,
+        };
+        let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+        ws.send(Message::Text(response.to_string())).await.unwrap();
+        answered += 1;
+        if answered == 2 {
+            break;
+        }
+    }
+    let block = json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": { "subscription": "0xabc", "result": { "number": format!("{:#x}", head + 1) } }
+    });
+    ws.send(Message::Text(block.to_string())).await.unwrap();
+    if drop_after {
+        return;
+    }
+    while ws.next().await.is_some() {}
+}
+
+#[tokio::test]
+async fn test_new_heads_reconnects_and_reannounces_head() {
+    use crate::ethereum::NewHeads;
+    use crate::secrets::SecretString;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        // The first connection drops after block 101; blocks up to 109 are missed
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_ws_node(stream, 100, true).await;
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_ws_node(stream, 110, false).await;
+    });
+
+    let heads = Arc::new(
+        NewHeads::new(SecretString::new(url)).with_reconnect_delay(Duration::from_millis(5)),
+    );
+    let mut announced = heads.subscribe();
+    let (stop, stopped) = tokio::sync::watch::channel(false);
+    let running = heads.clone().spawn(stopped);
+
+    while *announced.borrow_and_update() != Some(111) {
+        tokio::time::timeout(Duration::from_secs(5), announced.changed())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    // Reconnected and subscribed again after the drop
+    let status = heads.status();
+    assert!(status.connected);
+    assert_eq!(status.reconnects, 1);
+    assert_eq!(status.head, Some(111));
+    assert_eq!(status.last_error.as_deref(), Some("connection closed"));
+
+    stop.send(true).unwrap();
+    tokio::time::timeout(Duration::from_secs(1), running)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!heads.status().connected);
+}
+
+// ============ Unit Conversion Property Tests ============
+
+mod units_properties {
+    use crate::tools::units::{from_base_units, minimum_amount_out, to_base_units};
+    use ethers::types::{U256, U512};
+    use proptest::prelude::*;
+    use rust_decimal::Decimal;
+
+    const MAX_MANTISSA: u128 = (1 << 96) - 1;
+
+    fn pow10(exponent: u32) -> U256 {
+        U256::exp10(exponent as usize)
+    }
+
+    /// Base-unit amounts from dust to the whole U256 range
+    fn base_units() -> impl Strategy<Value = U256> {
+        prop_oneof![
+            Just(U256::zero()),
+            Just(U256::one()),
+            Just(U256::MAX),
+            any::<u64>().prop_map(U256::from),
+            (0..=MAX_MANTISSA).prop_map(U256::from),
+            any::<[u8; 32]>().prop_map(|bytes| U256::from_big_endian(&bytes)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn base_units_round_trip_exactly_within_decimal_precision(
+            amount in 0..=MAX_MANTISSA,
+            decimals in 0u32..=28,
+        ) {
+            let amount = U256::from(amount);
+            let human = from_base_units(amount, decimals).unwrap();
+            prop_assert_eq!(to_base_units(human, decimals).unwrap(), amount);
+        }
+
+        #[test]
+        fn base_units_round_trip_within_rounding_for_any_amount(
+            amount in base_units(),
+            decimals in 0u32..=36,
+        ) {
+            match from_base_units(amount, decimals) {
+                Ok(human) => {
+                    let back = to_base_units(human, decimals).unwrap();
+                    let error = if back > amount { back - amount } else { amount - back };
+                    // Off by at most half the last digit kept: below 1e-28 of a token,
+                    // or one part in 1e27 when the amount needs all of Decimal's digits
+                    let within_scale = error.full_mul(U256::from(2))
+                        <= U512::from(pow10(decimals.saturating_sub(28)));
+                    let within_precision = error.full_mul(pow10(27)) <= U512::from(amount);
+                    prop_assert!(within_scale || within_precision, "{} -> {} -> {}", amount, human, back);
+                }
+                Err(_) => prop_assert!(amount / pow10(decimals) >= U256::from(MAX_MANTISSA)),
+            }
+        }
+
+        #[test]
+        fn human_amounts_round_trip_through_base_units(
+            mantissa in any::<u64>(),
+            decimals in 0u32..=36,
+            scale in 0u32..=28,
+        ) {
+            let amount = Decimal::new(mantissa as i64 & i64::MAX, scale.min(decimals));
+            let base = to_base_units(amount, decimals).unwrap();
+            prop_assert_eq!(from_base_units(base, decimals).unwrap(), amount.normalize());
+        }
+
+        #[test]
+        fn finer_amounts_round_to_the_nearest_base_unit(
+            whole in any::<u32>(),
+            extra_digits in 1u32..=6,
+            fraction in any::<u32>(),
+            decimals in 0u32..=12,
+        ) {
+            let scale = decimals + extra_digits;
+            let fraction = fraction as u64 % 10u64.pow(extra_digits);
+            let amount = Decimal::from_i128_with_scale(
+                whole as i128 * 10i128.pow(scale) + fraction as i128,
+                scale,
+            );
+            let base = to_base_units(amount, decimals).unwrap();
+            let floor = U256::from(whole) * pow10(decimals);
+            prop_assert!(base == floor || base == floor + 1);
+        }
 
-        let result = Config::from_env();
-        assert!(result.is_err());
+        #[test]
+        fn minimum_out_never_exceeds_the_quote(
+            amount_out in base_units(),
+            slippage in 0.0f64..=100.0,
+        ) {
+            prop_assert!(minimum_amount_out(amount_out, slippage).unwrap() <= amount_out);
+        }
+
+        #[test]
+        fn minimum_out_falls_as_slippage_rises(
+            amount_out in base_units(),
+            lower in 0.0f64..=100.0,
+            higher in 0.0f64..=100.0,
+        ) {
+            let (lower, higher) = if lower <= higher { (lower, higher) } else { (higher, lower) };
+            prop_assert!(
+                minimum_amount_out(amount_out, lower).unwrap()
+                    >= minimum_amount_out(amount_out, higher).unwrap()
+            );
+        }
+
+        #[test]
+        fn minimum_out_matches_decimal_math(
+            amount_out in any::<u64>(),
+            slippage_bps in 0u32..=10_000,
+        ) {
+            let slippage = slippage_bps as f64 / 100.0;
+            let expected = (Decimal::from(amount_out)
+                * (Decimal::ONE - Decimal::new(slippage_bps as i64, 4)))
+            .floor();
+            prop_assert_eq!(
+                minimum_amount_out(U256::from(amount_out), slippage).unwrap(),
+                to_base_units(expected, 0).unwrap()
+            );
+        }
     }
 
     #[test]
-    #[serial]
-    fn test_config_different_networks() {
-        use crate::config::Config;
-
-        // Test Sepolia
-        std::env::set_var("ETH_RPC_URL", "https://sepolia.infura.io");
-        std::env::set_var(
-            "PRIVATE_KEY",
-            "0000000000000000000000000000000000000000000000000000000000000001",
+    fn test_dust_and_max_supply_edges() {
+        // One base unit is exact up to Decimal's 28 places; past them dust rounds half up
+        for decimals in 0..=28 {
+            assert_eq!(
+                from_base_units(U256::one(), decimals).unwrap(),
+                Decimal::new(1, decimals)
+            );
+        }
+        for decimals in 29..=36 {
+            assert!(from_base_units(U256::one(), decimals).unwrap().is_zero());
+        }
+        assert_eq!(
+            from_base_units(U256::from(5 * 10u64.pow(7)), 36).unwrap(),
+            Decimal::new(1, 28)
+        );
+
+        // A 36-decimal token with a trillion supply overflowed the old 10^decimals scaling
+        let supply = U256::from(10u64.pow(12)) * pow10(36);
+        assert_eq!(
+            from_base_units(supply, 36).unwrap(),
+            Decimal::from(10u64.pow(12))
+        );
+        assert_eq!(
+            to_base_units(Decimal::from(10u64.pow(12)), 36).unwrap(),
+            supply
+        );
+
+        // Unlimited approvals are beyond Decimal at any decimals
+        for decimals in [0, 18, 36] {
+            assert!(from_base_units(U256::MAX, decimals).is_err());
+        }
+        assert_eq!(
+            from_base_units(U256::from(MAX_MANTISSA), 0).unwrap(),
+            Decimal::MAX
         );
-        std::env::set_var("CHAIN_ID", "11155111");
+        assert!(to_base_units(Decimal::MAX, 49).is_err());
+        assert!(to_base_units(Decimal::new(-1, 0), 18).is_err());
 
-        let config = Config::from_env().unwrap();
-        assert_eq!(config.chain_id, 11155111);
+        assert_eq!(minimum_amount_out(U256::MAX, 0.0).unwrap(), U256::MAX);
+        assert!(minimum_amount_out(U256::MAX, 100.0).unwrap().is_zero());
+        for invalid in [-0.1, 100.1, f64::NAN] {
+            assert!(minimum_amount_out(U256::one(), invalid).is_err());
+        }
     }
 }
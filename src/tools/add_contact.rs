@@ -0,0 +1,88 @@
+use super::address_book::{AddressBook, Contact};
+use super::{PermissionTier, Tool};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct AddContactTool {
+    book: Arc<AddressBook>,
+}
+
+impl AddContactTool {
+    pub fn new(book: Arc<AddressBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AddContactParams {
+    name: String,
+    address: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    replace: bool,
+}
+
+#[async_trait]
+impl Tool for AddContactTool {
+    fn name(&self) -> &str {
+        "add_contact"
+    }
+
+    fn description(&self) -> &str {
+        "Save a named address in the address book. Contact names are accepted wherever a wallet or recipient address is expected, so recipients do not have to be retyped."
+    }
+
+    // Contacts vouch for recipients, so adding one is reserved for trading clients
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Contact name, matched ignoring case (e.g. 'cold wallet')"
+                },
+                "address": {
+                    "type": "string",
+                    "description": "Address the name stands for"
+                },
+                "note": {
+                    "type": "string",
+                    "description": "Optional free-text note"
+                },
+                "replace": {
+                    "type": "boolean",
+                    "description": "Re-point an existing contact to a new address (default: false)"
+                }
+            },
+            "required": ["name", "address"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: AddContactParams =
+            serde_json::from_value(params).context("Invalid parameters for add_contact")?;
+        let address: Address = params.address.parse().context("Invalid address")?;
+
+        let contact = Contact {
+            name: params.name,
+            address,
+            note: params.note,
+        };
+        self.book.add(contact.clone(), params.replace)?;
+
+        Ok(json!({
+            "name": contact.name.trim(),
+            "address": format!("{:?}", address),
+            "contacts": self.book.list().len(),
+        }))
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+// Longer names are more likely pasted data than a label
+const MAX_NAME_LEN: usize = 64;
+
+/// A named recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Named addresses shared by the tools that take wallet or recipient addresses.
+///
+/// Contacts come from configuration and `add_contact`; with a backing file, added
+/// contacts are saved there and survive restarts.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    path: Option<PathBuf>,
+    contacts: RwLock<Vec<Contact>>,
+    confirm_unknown_recipients: bool,
+}
+
+impl AddressBook {
+    pub fn new(contacts: Vec<Contact>) -> Result<Self> {
+        let book = Self::default();
+        for contact in contacts {
+            book.insert(contact, false)?;
+        }
+        Ok(book)
+    }
+
+    /// Load the contacts saved at `path`, if it exists, on top of `contacts`
+    pub fn open(path: PathBuf, contacts: Vec<Contact>) -> Result<Self> {
+        let mut book = Self::new(contacts)?;
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read address book {}", path.display()))?;
+            let saved: Vec<Contact> = serde_json::from_str(&raw)
+                .with_context(|| format!("Invalid address book {}", path.display()))?;
+            for contact in saved {
+                book.insert(contact, true)?;
+            }
+        }
+        book.path = Some(path);
+        Ok(book)
+    }
+
+    /// Ask the user before live transfers to addresses that are not contacts
+    pub fn with_confirm_unknown_recipients(mut self, confirm: bool) -> Self {
+        self.confirm_unknown_recipients = confirm;
+        self
+    }
+
+    pub fn confirms_unknown_recipients(&self) -> bool {
+        self.confirm_unknown_recipients
+    }
+
+    /// Add `contact`, saving the book when it has a backing file. An existing name is
+    /// only re-pointed when `replace` is set.
+    pub fn add(&self, contact: Contact, replace: bool) -> Result<()> {
+        self.insert(contact, replace)?;
+        self.save()
+    }
+
+    fn insert(&self, contact: Contact, replace: bool) -> Result<()> {
+        let name = contact.name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            anyhow::bail!(
+                "Contact names must be 1 to {} characters long",
+                MAX_NAME_LEN
+            );
+        }
+        // A name that reads as an address would shadow or impersonate one
+        if name.to_ascii_lowercase().starts_with("0x") {
+            anyhow::bail!("Contact names cannot start with 0x");
+        }
+        let contact = Contact {
+            name: name.to_string(),
+            ..contact
+        };
+
+        let mut contacts = self.contacts.write().unwrap_or_else(|e| e.into_inner());
+        match contacts
+            .iter_mut()
+            .find(|existing| existing.name.eq_ignore_ascii_case(name))
+        {
+            Some(existing) if existing.address == contact.address || replace => *existing = contact,
+            Some(existing) => anyhow::bail!(
+                "Contact {} already points to {:?}; pass replace to change it",
+                existing.name,
+                existing.address
+            ),
+            None => contacts.push(contact),
+        }
+        contacts.sort_by_key(|contact| contact.name.to_ascii_lowercase());
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.list())?;
+        // Write beside the book and rename so a crash never leaves it truncated
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, json)
+            .with_context(|| format!("Failed to write address book {}", temp.display()))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("Failed to save address book {}", path.display()))
+    }
+
+    /// All contacts, sorted by name
+    pub fn list(&self) -> Vec<Contact> {
+        self.contacts
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Contact with `name`, ignoring case
+    pub fn lookup(&self, name: &str) -> Option<Contact> {
+        let name = name.trim();
+        self.contacts
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|contact| contact.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    pub fn name_of(&self, address: Address) -> Option<String> {
+        self.contacts
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|contact| contact.address == address)
+            .map(|contact| contact.name.clone())
+    }
+}
+
+/// Parse `input` as an address or, with a `book`, as a contact name
+pub fn resolve_address(book: Option<&AddressBook>, input: &str) -> Result<Address> {
+    let input = input.trim();
+    if let Ok(address) = input.parse() {
+        return Ok(address);
+    }
+    match book.and_then(|book| book.lookup(input)) {
+        Some(contact) => Ok(contact.address),
+        None if book.is_some() && !input.starts_with("0x") => {
+            anyhow::bail!("Unknown contact: {}", input)
+        }
+        None => anyhow::bail!("Invalid address: {}", input),
+    }
+}
+
+/// Replace contact names in a plan's `to` and `spender` fields, and in the `to` of
+/// batch transfer payments, with their addresses
+pub fn resolve_plan_addresses(book: Option<&AddressBook>, plan: &mut Value) -> Result<()> {
+    let Some(steps) = plan.get_mut("steps").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for step in steps {
+        for field in ["to", "spender"] {
+            if let Some(Value::String(input)) = step.get_mut(field) {
+                let address = resolve_address(book, input)
+                    .with_context(|| format!("Invalid plan step {}", field))?;
+                *input = format!("{:?}", address);
+            }
+        }
+        if let Some(payments) = step.get_mut("payments").and_then(Value::as_array_mut) {
+            for payment in payments {
+                if let Some(Value::String(input)) = payment.get_mut("to") {
+                    let address =
+                        resolve_address(book, input).context("Invalid batch transfer recipient")?;
+                    *input = format!("{:?}", address);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+
+/// How urgently an alert needs the agent's attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Where tools push alerts outside of their results, such as MCP notifications
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    /// Deliver `alert` under `topic`, returning how many recipients received it
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize;
+}
@@ -0,0 +1,454 @@
+use super::address_book::AddressBook;
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{
+    EthereumClientTrait, HistoricalTransaction, SignatureSource, TransactionHistorySource,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::hex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_DAYS: u64 = 30;
+const MAX_DAYS: u64 = 365;
+const DEFAULT_TOP: usize = 10;
+const MAX_TOP: usize = 50;
+
+// Transactions read per analysis; older ones in the period are left out
+const MAX_HISTORY_TRANSACTIONS: usize = 5_000;
+
+// Base cost every transaction pays, saved for each one folded into a batch
+const BASE_TX_GAS: u64 = 21_000;
+
+// Repeats of one kind of payment or approval worth a suggestion
+const MIN_REPEATS: usize = 3;
+
+// Swaps averaging more gas than this are worth routing elsewhere; a plain
+// Uniswap V2 swap is the reference cost
+const COSTLY_SWAP_GAS: u64 = 180_000;
+const REFERENCE_SWAP_GAS: u64 = 120_000;
+
+// Share of mainnet fees the same activity typically costs on a rollup
+const L2_FEE_PERCENT: u64 = 5;
+
+// Mainnet fees over a period above this, in ETH thousandths, warrant suggesting an L2
+const L2_SUGGESTION_MIN_FEE_MILLI_ETH: u64 = 50;
+
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+// Swap entry points of common routers: Uniswap V2 and its forks, V3's SwapRouter
+// and SwapRouter02, their multicalls, and the Universal Router
+const SWAP_SELECTORS: &[[u8; 4]] = &[
+    [0x38, 0xed, 0x17, 0x39],
+    [0x88, 0x03, 0xdb, 0xee],
+    [0x7f, 0xf3, 0x6a, 0xb5],
+    [0xfb, 0x3b, 0xdb, 0x41],
+    [0x18, 0xcb, 0xaf, 0xe5],
+    [0x4a, 0x25, 0xd9, 0x4a],
+    [0x5c, 0x11, 0xd7, 0x95],
+    [0xb6, 0xf9, 0xde, 0x95],
+    [0x79, 0x1a, 0xc9, 0x47],
+    [0x41, 0x4b, 0xf3, 0x89],
+    [0xc0, 0x4b, 0x8d, 0x59],
+    [0x04, 0xe4, 0x5a, 0xaf],
+    [0xb8, 0x58, 0x18, 0x3f],
+    [0xac, 0x96, 0x50, 0xd8],
+    [0x5a, 0xe4, 0x01, 0xdc],
+    [0x35, 0x93, 0x56, 0x4c],
+];
+
+pub struct AnalyzeGasUsageTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    history: Arc<dyn TransactionHistorySource>,
+    signatures: Option<Arc<dyn SignatureSource>>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> AnalyzeGasUsageTool<C> {
+    pub fn new(client: Arc<C>, history: Arc<dyn TransactionHistorySource>) -> Self {
+        Self {
+            client,
+            history,
+            signatures: None,
+            book: None,
+        }
+    }
+
+    /// Name methods the history source leaves unnamed through `signatures`
+    pub fn with_signatures(mut self, signatures: Arc<dyn SignatureSource>) -> Self {
+        self.signatures = Some(signatures);
+        self
+    }
+
+    /// Report contacts by name
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeGasUsageParams {
+    #[serde(default)]
+    days: Option<u64>,
+    #[serde(default)]
+    top: Option<usize>,
+}
+
+/// Totals of one method called on one contract
+#[derive(Debug, Default)]
+struct Usage {
+    count: usize,
+    failed: usize,
+    gas_used: U256,
+    fee: U256,
+    /// Fee of the reverted calls
+    wasted_fee: U256,
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MethodUsage {
+    /// Absent for contract creations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contract: Option<Address>,
+    /// Contact name of the contract or recipient
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contact: Option<String>,
+    /// Function signature, its selector when unknown, or `transfer (ETH)`
+    method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selector: Option<String>,
+    count: usize,
+    failed: usize,
+    gas_used: String,
+    average_gas_used: String,
+    fee_eth: String,
+    /// Share of the period's fees
+    fee_percent: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Suggestion {
+    /// batching, approvals, venues, failed_transactions or l2_migration
+    kind: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_gas_saved: Option<String>,
+    estimated_fee_saved_eth: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AnalyzeGasUsageResult {
+    wallet: Address,
+    days: u64,
+    source: String,
+    /// Transactions the wallet sent in the period
+    transaction_count: usize,
+    failed_count: usize,
+    total_gas_used: String,
+    total_fee_eth: String,
+    average_gas_price_gwei: String,
+    /// Costliest contract and method pairs first
+    by_contract_method: Vec<MethodUsage>,
+    /// Rough savings at the period's average gas price, largest first
+    suggestions: Vec<Suggestion>,
+    /// The period held more transactions than were read; the oldest are left out
+    truncated: bool,
+}
+
+fn eth(wei: U256) -> String {
+    from_base_units(wei, 18)
+        .map(|eth| eth.normalize().to_string())
+        .unwrap_or_else(|_| wei.to_string())
+}
+
+fn percent(part: U256, whole: U256) -> String {
+    if whole.is_zero() {
+        return "0".to_string();
+    }
+    let basis_points = part.saturating_mul(U256::from(10_000u64)) / whole;
+    format!("{:.2}", basis_points.low_u64() as f64 / 100.0)
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for AnalyzeGasUsageTool<C> {
+    fn name(&self) -> &str {
+        "analyze_gas_usage"
+    }
+
+    fn description(&self) -> &str {
+        "Report where the wallet's gas went over a period: transactions it sent grouped by contract and method, with counts, gas used, fees and failures, costliest first. Suggests savings with rough estimates: batching repeated payments, approving once instead of repeatedly, routing costly swaps through cheaper venues, simulating before sending to avoid reverts, and moving activity to an L2."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "days": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_DAYS,
+                    "description": "How many days back to analyze (default: 30)"
+                },
+                "top": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_TOP,
+                    "description": "Contract and method pairs to list (default: 10)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: AnalyzeGasUsageParams =
+            serde_json::from_value(params).context("Invalid parameters for analyze_gas_usage")?;
+        let days = params.days.unwrap_or(DEFAULT_DAYS);
+        if !(1..=MAX_DAYS).contains(&days) {
+            anyhow::bail!("days must be between 1 and {}", MAX_DAYS);
+        }
+        let top = params.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP);
+        let wallet = self.client.get_wallet_address();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let since = now.saturating_sub(days * 86_400);
+
+        let history = self
+            .history
+            .transactions(wallet, since, MAX_HISTORY_TRANSACTIONS)
+            .await?;
+        let truncated = history.len() >= MAX_HISTORY_TRANSACTIONS;
+        // Gas is paid by the sender only
+        let sent: Vec<&HistoricalTransaction> =
+            history.iter().filter(|tx| tx.from == wallet).collect();
+
+        let mut usage: BTreeMap<(Option<Address>, Option<[u8; 4]>), Usage> = BTreeMap::new();
+        let (mut total_gas, mut total_fee) = (U256::zero(), U256::zero());
+        for tx in &sent {
+            let entry = usage.entry((tx.to, tx.selector)).or_default();
+            entry.count += 1;
+            entry.gas_used = entry.gas_used.saturating_add(tx.gas_used);
+            entry.fee = entry.fee.saturating_add(tx.fee());
+            if !tx.success {
+                entry.failed += 1;
+                entry.wasted_fee = entry.wasted_fee.saturating_add(tx.fee());
+            }
+            if entry.name.is_none() {
+                entry.name = tx.function_name.clone();
+            }
+            total_gas = total_gas.saturating_add(tx.gas_used);
+            total_fee = total_fee.saturating_add(tx.fee());
+        }
+        let average_price = if total_gas.is_zero() {
+            U256::zero()
+        } else {
+            total_fee / total_gas
+        };
+
+        // Name what the history source could not
+        let mut unnamed: Vec<[u8; 4]> = usage
+            .iter()
+            .filter(|(_, usage)| usage.name.is_none())
+            .filter_map(|((_, selector), _)| *selector)
+            .collect();
+        unnamed.sort();
+        unnamed.dedup();
+        let names: HashMap<[u8; 4], Vec<String>> = match &self.signatures {
+            Some(signatures) if !unnamed.is_empty() => {
+                signatures.lookup(&unnamed).await.unwrap_or_default()
+            }
+            _ => HashMap::new(),
+        };
+        let method = |selector: Option<[u8; 4]>, usage: &Usage| match (selector, &usage.name) {
+            (None, _) => "transfer (ETH)".to_string(),
+            (_, Some(name)) => name.clone(),
+            (Some(selector), None) => names
+                .get(&selector)
+                .and_then(|names| names.first().cloned())
+                .unwrap_or_else(|| format!("0x{}", hex::encode(selector))),
+        };
+
+        let suggestions = suggest(
+            &usage,
+            &sent,
+            total_fee,
+            average_price,
+            self.client.get_chain_id(),
+        );
+
+        let mut ranked: Vec<_> = usage.iter().collect();
+        ranked.sort_by(|(_, a), (_, b)| b.fee.cmp(&a.fee).then(b.count.cmp(&a.count)));
+        let by_contract_method = ranked
+            .into_iter()
+            .take(top)
+            .map(|((contract, selector), usage)| MethodUsage {
+                contract: *contract,
+                contact: contract.and_then(|contract| {
+                    self.book.as_ref().and_then(|book| book.name_of(contract))
+                }),
+                method: method(*selector, usage),
+                selector: selector.map(|selector| format!("0x{}", hex::encode(selector))),
+                count: usage.count,
+                failed: usage.failed,
+                gas_used: usage.gas_used.to_string(),
+                average_gas_used: (usage.gas_used / U256::from(usage.count)).to_string(),
+                fee_eth: eth(usage.fee),
+                fee_percent: percent(usage.fee, total_fee),
+            })
+            .collect();
+
+        let result = AnalyzeGasUsageResult {
+            wallet,
+            days,
+            source: self.history.name().to_string(),
+            transaction_count: sent.len(),
+            failed_count: sent.iter().filter(|tx| !tx.success).count(),
+            total_gas_used: total_gas.to_string(),
+            total_fee_eth: eth(total_fee),
+            average_gas_price_gwei: from_base_units(average_price, 9)
+                .map(|gwei| gwei.round_dp(3).normalize().to_string())
+                .unwrap_or_default(),
+            by_contract_method,
+            suggestions,
+            truncated,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+/// Savings worth pointing out in `usage`, largest first
+fn suggest(
+    usage: &BTreeMap<(Option<Address>, Option<[u8; 4]>), Usage>,
+    sent: &[&HistoricalTransaction],
+    total_fee: U256,
+    average_price: U256,
+    chain_id: u64,
+) -> Vec<Suggestion> {
+    let mut suggestions: Vec<(Suggestion, U256)> = Vec::new();
+    let mut push = |kind, message, gas: Option<U256>, fee: U256| {
+        if !fee.is_zero() {
+            suggestions.push((
+                Suggestion {
+                    kind,
+                    message,
+                    estimated_gas_saved: gas.map(|gas| gas.to_string()),
+                    estimated_fee_saved_eth: eth(fee),
+                },
+                fee,
+            ));
+        }
+    };
+
+    // Plain ETH payments and ERC20 transfers, each of which could share one transaction
+    let eth_payments = sent.iter().filter(|tx| tx.selector.is_none()).count();
+    let token_payments: usize = usage
+        .iter()
+        .filter(|((_, selector), _)| *selector == Some(TRANSFER_SELECTOR))
+        .map(|(_, usage)| usage.count)
+        .sum();
+    for (payments, asset) in [(eth_payments, "ETH"), (token_payments, "ERC20")] {
+        if payments >= MIN_REPEATS {
+            let gas = U256::from(BASE_TX_GAS) * U256::from(payments - 1);
+            push(
+                "batching",
+                format!(
+                    "{} separate {} payments were sent; paying recipients together with batch_transfer saves the {} base gas of all but one",
+                    payments, asset, BASE_TX_GAS
+                ),
+                Some(gas),
+                gas.saturating_mul(average_price),
+            );
+        }
+    }
+
+    // The same token approved over and over
+    for ((token, _), approvals) in usage
+        .iter()
+        .filter(|((_, selector), _)| *selector == Some(APPROVE_SELECTOR))
+    {
+        if approvals.count >= MIN_REPEATS {
+            let average = approvals.gas_used / U256::from(approvals.count);
+            let gas = average * U256::from(approvals.count - 1);
+            push(
+                "approvals",
+                format!(
+                    "{:?} was approved {} times; an approval_strategy of exact_plus_buffer or unlimited, or permit where the token supports it, avoids most of them",
+                    token.unwrap_or_default(),
+                    approvals.count
+                ),
+                Some(gas),
+                gas.saturating_mul(average_price),
+            );
+        }
+    }
+
+    // Swaps on routers whose calls cost well above a plain swap
+    for ((router, _), swaps) in usage
+        .iter()
+        .filter(|((_, selector), _)| selector.is_some_and(|s| SWAP_SELECTORS.contains(&s)))
+    {
+        let average = swaps.gas_used / U256::from(swaps.count);
+        if average > U256::from(COSTLY_SWAP_GAS) {
+            let gas = (average - U256::from(REFERENCE_SWAP_GAS)) * U256::from(swaps.count);
+            push(
+                "venues",
+                format!(
+                    "{} swaps through {:?} averaged {} gas; compare_quotes and swap_tokens rank venues net of gas, and a direct pool often costs about {}",
+                    swaps.count,
+                    router.unwrap_or_default(),
+                    average,
+                    REFERENCE_SWAP_GAS
+                ),
+                Some(gas),
+                gas.saturating_mul(average_price),
+            );
+        }
+    }
+
+    let (failed, wasted) = usage
+        .values()
+        .fold((0, U256::zero()), |(count, fee), usage| {
+            (count + usage.failed, fee.saturating_add(usage.wasted_fee))
+        });
+    if failed > 0 {
+        push(
+            "failed_transactions",
+            format!(
+                "{} transactions reverted and still paid gas; simulate_bundle or a dry run of execute_plan shows a revert before anything is sent",
+                failed
+            ),
+            None,
+            wasted,
+        );
+    }
+
+    let l2_threshold = U256::from(L2_SUGGESTION_MIN_FEE_MILLI_ETH) * U256::exp10(15);
+    if chain_id == 1 && total_fee >= l2_threshold {
+        push(
+            "l2_migration",
+            format!(
+                "Mainnet fees came to {} ETH; the same activity on a rollup such as Arbitrum, Base or Optimism typically costs around {}% of that",
+                eth(total_fee),
+                L2_FEE_PERCENT
+            ),
+            None,
+            total_fee * U256::from(100 - L2_FEE_PERCENT) / U256::from(100),
+        );
+    }
+
+    suggestions.sort_by(|(_, a), (_, b)| b.cmp(a));
+    suggestions
+        .into_iter()
+        .map(|(suggestion, _)| suggestion)
+        .collect()
+}
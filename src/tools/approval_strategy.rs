@@ -0,0 +1,115 @@
+use crate::ethereum::tokens::requires_allowance_reset;
+use crate::ethereum::{erc20_call_succeeded, EthereumClientTrait, PlanStep};
+use ethers::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Headroom `exact_plus_buffer` approves above the amount spent, in percent
+pub const DEFAULT_APPROVAL_BUFFER_PERCENT: u32 = 10;
+
+/// How much a spender is approved for when an allowance falls short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStrategy {
+    /// Exactly the amount spent, leaving nothing behind for the spender
+    #[default]
+    Exact,
+    /// The amount plus a buffer, so a re-quote spending slightly more needs no new approval
+    ExactPlusBuffer,
+    /// The maximum allowance, so later spends skip the approve step
+    Unlimited,
+}
+
+impl std::str::FromStr for ApprovalStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "exact" => Ok(ApprovalStrategy::Exact),
+            "exact_plus_buffer" => Ok(ApprovalStrategy::ExactPlusBuffer),
+            "unlimited" => Ok(ApprovalStrategy::Unlimited),
+            other => anyhow::bail!(
+                "Unknown approval strategy: {} (expected exact, exact_plus_buffer or unlimited)",
+                other
+            ),
+        }
+    }
+}
+
+/// Approval strategy calls fall back to when they choose none
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApprovalDefaults {
+    pub strategy: ApprovalStrategy,
+    /// Percent `exact_plus_buffer` adds
+    pub buffer_percent: Decimal,
+}
+
+impl Default for ApprovalDefaults {
+    fn default() -> Self {
+        Self {
+            strategy: ApprovalStrategy::default(),
+            buffer_percent: Decimal::from(DEFAULT_APPROVAL_BUFFER_PERCENT),
+        }
+    }
+}
+
+impl ApprovalDefaults {
+    /// Allowance to grant for spending `amount` under `strategy`, or the default one
+    pub fn allowance(&self, strategy: Option<ApprovalStrategy>, amount: U256) -> U256 {
+        match strategy.unwrap_or(self.strategy) {
+            ApprovalStrategy::Exact => amount,
+            ApprovalStrategy::ExactPlusBuffer => {
+                // Basis points keep the buffer in integer math
+                let bps = (self.buffer_percent * Decimal::ONE_HUNDRED)
+                    .to_u64()
+                    .unwrap_or_default();
+                amount.saturating_add(amount.saturating_mul(U256::from(bps)) / U256::from(10_000))
+            }
+            ApprovalStrategy::Unlimited => U256::MAX,
+        }
+    }
+}
+
+/// Approve steps that raise `owner`'s allowance for `spender` from `current` to
+/// `allowance`. Tokens such as USDT revert when a non-zero allowance is changed to
+/// another non-zero one, so for known ones, and any whose approve would revert, the
+/// allowance is zeroed first.
+pub async fn approval_steps<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+    owner: Address,
+    spender: Address,
+    current: U256,
+    allowance: U256,
+) -> Vec<PlanStep> {
+    let approve = PlanStep::Approve {
+        token,
+        spender,
+        amount: allowance,
+    };
+    let reset = !current.is_zero()
+        && (requires_allowance_reset(client.get_chain_id(), token) || {
+            // Simulated from the owner, a revert or a false return means the token
+            // refuses the change
+            match approve.to_transaction(owner, U256::zero()) {
+                Ok(tx) => !client
+                    .call_contract(&tx, None, None)
+                    .await
+                    .is_ok_and(|output| erc20_call_succeeded(&output)),
+                Err(_) => false,
+            }
+        });
+    if reset {
+        vec![
+            PlanStep::Approve {
+                token,
+                spender,
+                amount: U256::zero(),
+            },
+            approve,
+        ]
+    } else {
+        vec![approve]
+    }
+}
@@ -0,0 +1,385 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::recipients::RecipientHistory;
+use super::scam_list::ScamList;
+use super::units::to_base_units;
+use super::{ExecutePlanTool, Notional, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Payment, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Disperse.app, deployed at the same address on mainnet and most L2s
+pub const DEFAULT_DISPERSE_CONTRACT: &str = "0xD152f549545093347A162Dce210e7293f1452150";
+
+/// Recipients one batch may pay by default
+pub const DEFAULT_MAX_BATCH_RECIPIENTS: usize = 200;
+
+/// Bounds every batch must stay within
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchLimits {
+    pub max_recipients: usize,
+    /// Largest ETH amount any one recipient may receive; `None` leaves it uncapped
+    pub max_eth_per_recipient: Option<Decimal>,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_recipients: DEFAULT_MAX_BATCH_RECIPIENTS,
+            max_eth_per_recipient: None,
+        }
+    }
+}
+
+pub struct BatchTransferTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    contract: Address,
+    limits: BatchLimits,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> BatchTransferTool<C> {
+    /// Pay through the Disperse-compatible `contract`; without `execution_enabled`
+    /// only dry runs are allowed
+    pub fn new(client: Arc<C>, contract: Address, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            contract,
+            limits: BatchLimits::default(),
+            book: None,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: BatchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Accept contact names from `book` as recipients
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.executor = self.executor.with_address_book(Arc::clone(&book));
+        self.book = Some(book);
+        self
+    }
+
+    /// Flag recipients the wallet has never paid, and record new payments
+    pub fn with_recipient_history(mut self, recipients: Arc<RecipientHistory>) -> Self {
+        self.executor = self.executor.with_recipient_history(recipients);
+        self
+    }
+
+    /// Refuse recipients on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.executor = self.executor.with_scam_list(scams);
+        self
+    }
+
+    /// Default fee escalation for live batches; calls can override or disable it
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live batches must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchTransferParams {
+    /// ERC20 to send; ETH when omitted
+    #[serde(default)]
+    token: Option<String>,
+    payments: Vec<PaymentParams>,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    confirm_new_recipients: bool,
+    #[serde(default)]
+    override_gas_reserve: bool,
+    #[serde(default)]
+    override_scam_list: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentParams {
+    to: String,
+    amount: String,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct BatchTransferResult {
+    contract: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    symbol: String,
+    recipients: usize,
+    total_amount: String,
+    balance: String,
+    /// The approve (when the contract's allowance is short) and batch steps, as input for execute_plan
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for BatchTransferTool<C> {
+    fn name(&self) -> &str {
+        "batch_transfer"
+    }
+
+    fn description(&self) -> &str {
+        "Send ETH or an ERC20 to many recipients in a single transaction through a Disperse contract, approving it first when its token allowance is short. Every amount is checked before anything is built: no duplicate or zero recipients, no zero amounts or extra decimals, the server's recipient count and per-recipient ETH caps, and a total within the wallet's balance. Dry run by default; live sends follow execute_plan's gas reserve and recipient checks."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    // The whole batch
+    fn notional(&self, params: &Value) -> Option<Notional> {
+        let token = match params.get("token").and_then(Value::as_str) {
+            Some(token) => Some(token.trim().parse().ok()?),
+            None => None,
+        };
+        let mut amount = Decimal::ZERO;
+        for payment in params.get("payments")?.as_array()? {
+            amount += Notional::from_params(payment, "token", "amount")?.amount;
+        }
+        Some(Notional { token, amount })
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "ERC20 token address to send; omit to send ETH"
+                },
+                "payments": {
+                    "type": "array",
+                    "description": format!(
+                        "Recipients and amounts, at most {} per batch",
+                        self.limits.max_recipients
+                    ),
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "to": { "type": "string", "description": "Recipient address or contact name" },
+                            "amount": { "type": "string", "description": "Amount in whole tokens, e.g. '1.5'" }
+                        },
+                        "required": ["to", "amount"]
+                    }
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transactions without sending (default: true). Live execution must be enabled on the server"
+                },
+                "confirm_new_recipients": {
+                    "type": "boolean",
+                    "description": "Pay recipients the wallet has never paid, as for execute_plan (default: false)"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                },
+                "override_scam_list": {
+                    "type": "boolean",
+                    "description": "Pay an address on the scam list anyway, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["payments"]
+        })
+    }
+
+    // The same go-ahead execute_plan asks for before paying addresses outside the book
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        self.executor.confirmation_prompt(&json!({
+            "dry_run": params.get("dry_run").cloned().unwrap_or(Value::Bool(true)),
+            "plan": {
+                "steps": [{ "action": "batch_transfer", "payments": params.get("payments")? }]
+            }
+        }))
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: BatchTransferParams =
+            serde_json::from_value(params).context("Invalid parameters for batch_transfer")?;
+
+        if params.payments.is_empty() {
+            anyhow::bail!("payments is empty");
+        }
+        if params.payments.len() > self.limits.max_recipients {
+            anyhow::bail!(
+                "{} recipients exceed the limit of {} per batch; split the payments",
+                params.payments.len(),
+                self.limits.max_recipients
+            );
+        }
+
+        let wallet = self.client.get_wallet_address();
+        let token: Option<Address> = params
+            .token
+            .as_deref()
+            .map(|token| token.parse().context("Invalid token address"))
+            .transpose()?;
+        let (balance, decimals, symbol) = match token {
+            Some(token) => {
+                let (balance, decimals) = self
+                    .client
+                    .get_token_balance(token, wallet)
+                    .await
+                    .context("Failed to read token balance")?;
+                let symbol = self
+                    .client
+                    .get_token_symbol(token)
+                    .await
+                    .unwrap_or_else(|_| "UNKNOWN".to_string());
+                (balance, decimals, symbol)
+            }
+            None => (
+                self.client.get_eth_balance(wallet).await?,
+                18,
+                "ETH".to_string(),
+            ),
+        };
+
+        let mut payments: Vec<Payment> = Vec::with_capacity(params.payments.len());
+        let mut total = Decimal::ZERO;
+        for (index, payment) in params.payments.iter().enumerate() {
+            let to = resolve_address(self.book.as_deref(), &payment.to)
+                .with_context(|| format!("Invalid recipient in payment {}", index))?;
+            if to.is_zero() || to == self.contract || Some(to) == token {
+                anyhow::bail!(
+                    "Payment {} is to {:?}, which cannot receive a batch transfer",
+                    index,
+                    to
+                );
+            }
+            if payments.iter().any(|earlier| earlier.to == to) {
+                anyhow::bail!(
+                    "{:?} appears more than once; combine its payments into one",
+                    to
+                );
+            }
+
+            let amount = Decimal::from_str(payment.amount.trim())
+                .with_context(|| format!("Invalid amount in payment {}", index))?;
+            if amount <= Decimal::ZERO {
+                anyhow::bail!("Payment {} to {:?} must be a positive amount", index, to);
+            }
+            if amount.normalize().scale() > decimals as u32 {
+                anyhow::bail!(
+                    "Payment {} amount {} has more than the {} decimals {} supports",
+                    index,
+                    amount,
+                    decimals,
+                    symbol
+                );
+            }
+            if let (None, Some(cap)) = (token, self.limits.max_eth_per_recipient) {
+                if amount > cap {
+                    anyhow::bail!(
+                        "Payment {} of {} ETH to {:?} exceeds the {} ETH per-recipient limit",
+                        index,
+                        amount.normalize(),
+                        to,
+                        cap.normalize()
+                    );
+                }
+            }
+
+            total += amount;
+            payments.push(Payment {
+                to,
+                amount: to_base_units(amount, decimals as u32)?,
+            });
+        }
+        if total > balance {
+            anyhow::bail!(
+                "The batch sends {} {} but the wallet holds {}",
+                total.normalize(),
+                symbol,
+                balance.normalize()
+            );
+        }
+
+        let mut plan = Plan::default();
+        if let Some(token) = token {
+            let amount = to_base_units(total, decimals as u32)?;
+            let approval = self
+                .client
+                .simulate_approval(token, wallet, self.contract, amount)
+                .await?;
+            if approval.approval_required {
+                plan.steps.push(PlanStep::Approve {
+                    token,
+                    spender: self.contract,
+                    amount,
+                });
+            }
+        }
+        plan.steps.push(PlanStep::BatchTransfer {
+            contract: self.contract,
+            token,
+            payments,
+        });
+
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "confirm_new_recipients": params.confirm_new_recipients,
+                "override_gas_reserve": params.override_gas_reserve,
+                "override_scam_list": params.override_scam_list,
+            }))
+            .await?;
+
+        let result = BatchTransferResult {
+            contract: format!("{:?}", self.contract),
+            token: token.map(|token| format!("{:?}", token)),
+            symbol,
+            recipients: params.payments.len(),
+            total_amount: total.normalize().to_string(),
+            balance: balance.normalize().to_string(),
+            plan,
+            execution,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
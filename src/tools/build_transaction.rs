@@ -0,0 +1,279 @@
+use super::address_book::{resolve_address, resolve_plan_addresses, AddressBook};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Swaps must be mined within this many seconds of building; offline signing is slow
+const SWAP_DEADLINE_SECS: u64 = 60 * 60;
+
+/// Hex characters per animated-QR frame, well inside a version 25 alphanumeric code
+pub const QR_FRAME_CHARS: usize = 1000;
+
+/// Frame tag of unsigned transactions; signed ones coming back use `STX`
+const UNSIGNED_QR_TAG: &str = "UTX";
+
+/// Gas limit used when a step cannot be estimated, typically because it depends on
+/// an earlier step in the same plan
+fn fallback_gas(step: &PlanStep) -> u64 {
+    match step {
+        PlanStep::Approve { .. } => 60_000,
+        PlanStep::Wrap { .. } => 50_000,
+        PlanStep::Swap { .. } => 300_000,
+        PlanStep::Transfer { token: Some(_), .. } => 65_000,
+        PlanStep::Transfer { token: None, .. } | PlanStep::Cancel { .. } => 21_000,
+        // Receiver contracts run their onERC721Received/onERC1155Received hook
+        PlanStep::TransferNft { .. } => 120_000,
+        // Fresh recipients cost the most: a new balance slot or account each
+        PlanStep::BatchTransfer {
+            token: Some(_),
+            payments,
+            ..
+        } => 50_000 + 35_000 * payments.len() as u64,
+        PlanStep::BatchTransfer { payments, .. } => 30_000 + 35_000 * payments.len() as u64,
+        // Creating a stream also mints its NFT to the recipient
+        PlanStep::CreateStream { .. } => 300_000,
+        PlanStep::CancelStream { .. } => 120_000,
+        PlanStep::Vote { .. } => 150_000,
+        // Moves vote checkpoints of both the old and the new delegate
+        PlanStep::Delegate { .. } => 120_000,
+        PlanStep::RenewEns { .. } => 120_000,
+        PlanStep::SpeedUp { .. } => 300_000,
+    }
+}
+
+/// Uppercase hex fits QR alphanumeric mode; long payloads are also split into
+/// `TAG/index/total/chunk` frames for animated codes
+pub fn qr_encode(bytes: &[u8], tag: &str) -> (String, Vec<String>) {
+    let payload = hex::encode_upper(bytes);
+    if payload.len() <= QR_FRAME_CHARS {
+        return (payload, Vec::new());
+    }
+    // Hex is ASCII, so byte chunks are whole characters
+    let chunks: Vec<_> = payload
+        .as_bytes()
+        .chunks(QR_FRAME_CHARS)
+        .map(String::from_utf8_lossy)
+        .collect();
+    let frames = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| format!("{}/{}/{}/{}", tag, i + 1, chunks.len(), chunk))
+        .collect();
+    (payload, frames)
+}
+
+/// Decode hex in any case, with or without `0x`, or a complete set of QR frames in any order
+pub fn qr_decode(parts: &[String]) -> Result<Vec<u8>> {
+    let hex_payload = match parts {
+        [single] if !single.contains('/') => single.trim().to_string(),
+        frames => {
+            let mut indexed = Vec::with_capacity(frames.len());
+            for frame in frames {
+                let fields: Vec<&str> = frame.trim().splitn(4, '/').collect();
+                let [_, index, total, chunk] = fields[..] else {
+                    anyhow::bail!("Malformed QR frame: expected TAG/index/total/data");
+                };
+                let index: usize = index.parse().context("Invalid QR frame index")?;
+                let total: usize = total.parse().context("Invalid QR frame total")?;
+                if total != frames.len() {
+                    anyhow::bail!("Expected {} QR frames, got {}", total, frames.len());
+                }
+                indexed.push((index, chunk.to_string()));
+            }
+            indexed.sort_by_key(|(index, _)| *index);
+            if indexed
+                .iter()
+                .enumerate()
+                .any(|(i, (index, _))| *index != i + 1)
+            {
+                anyhow::bail!("QR frames are missing or duplicated");
+            }
+            indexed.into_iter().map(|(_, chunk)| chunk).collect()
+        }
+    };
+    let hex_payload = hex_payload
+        .strip_prefix("0x")
+        .or_else(|| hex_payload.strip_prefix("0X"))
+        .unwrap_or(&hex_payload);
+    hex::decode(hex_payload).context("Transaction is not valid hex")
+}
+
+pub struct BuildTransactionTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> BuildTransactionTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names from `book` for the signer and plan recipients
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildTransactionParams {
+    plan: Plan,
+    #[serde(default)]
+    from: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct UnsignedTransaction {
+    index: usize,
+    action: String,
+    nonce: u64,
+    gas_limit: String,
+    /// False when the fallback gas limit for the action was used
+    gas_estimated: bool,
+    max_fee_per_gas: String,
+    max_priority_fee_per_gas: String,
+    /// EIP-2718 typed transaction (type 2), unsigned
+    unsigned_transaction: String,
+    /// Hash the signer signs
+    signing_hash: String,
+    qr_payload: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    qr_frames: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BuildTransactionResult {
+    from: String,
+    chain_id: u64,
+    transactions: Vec<UnsignedTransaction>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for BuildTransactionTool<C> {
+    fn name(&self) -> &str {
+        "build_transaction"
+    }
+
+    fn description(&self) -> &str {
+        "Build unsigned EIP-1559 transactions for a plan (e.g. from swap_tokens) for signing on an offline or air-gapped device. Each step gets its nonce, gas and fees filled in, the serialized unsigned transaction, its signing hash, and an uppercase-hex QR payload (split into frames when long). Broadcast the signed result with import_signed_transaction."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "plan": {
+                    "type": "object",
+                    "description": "Plan object with a `steps` array, as accepted by execute_plan. Recipients and spenders may be contact names",
+                    "properties": {
+                        "steps": { "type": "array", "items": { "type": "object" } }
+                    },
+                    "required": ["steps"]
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Address or contact name of the offline signer (default: the server's wallet)"
+                }
+            },
+            "required": ["plan"]
+        })
+    }
+
+    async fn execute(&self, mut params: Value) -> Result<Value> {
+        if let Some(plan) = params.get_mut("plan") {
+            resolve_plan_addresses(self.book.as_deref(), plan)?;
+        }
+        let params: BuildTransactionParams =
+            serde_json::from_value(params).context("Invalid parameters for build_transaction")?;
+        if params.plan.steps.is_empty() {
+            anyhow::bail!("Plan has no steps");
+        }
+
+        let from: Address = match &params.from {
+            Some(addr) => {
+                resolve_address(self.book.as_deref(), addr).context("Invalid from address")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        let chain_id = self.client.get_chain_id();
+        let (_, mut nonce) = self.client.get_nonces(from).await?;
+        let (max_fee, priority_fee) = self.client.estimate_fees().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let deadline = U256::from(now + SWAP_DEADLINE_SECS);
+
+        let mut transactions = Vec::with_capacity(params.plan.steps.len());
+        for (index, step) in params.plan.steps.iter().enumerate() {
+            let request = step.to_transaction(from, deadline)?;
+            let (gas_limit, gas_estimated) =
+                match self.client.estimate_transaction_gas(&request).await {
+                    Ok(gas) => (gas, true),
+                    Err(_) => (U256::from(fallback_gas(step)), false),
+                };
+
+            // Replacement steps carry their own nonce and fees
+            let mut tx = match request {
+                TypedTransaction::Eip1559(inner) => inner,
+                other => Eip1559TransactionRequest::new()
+                    .from(from)
+                    .to(other
+                        .to()
+                        .cloned()
+                        .with_context(|| format!("Plan step {} has no recipient", index))?)
+                    .data(other.data().cloned().unwrap_or_default())
+                    .value(other.value().copied().unwrap_or_default()),
+            };
+            if tx.nonce.is_none() {
+                tx.nonce = Some(nonce.into());
+                nonce += 1;
+            }
+            tx.max_fee_per_gas.get_or_insert(max_fee);
+            tx.max_priority_fee_per_gas.get_or_insert(priority_fee);
+            tx.gas = Some(gas_limit);
+            tx.chain_id = Some(chain_id.into());
+            let tx = TypedTransaction::Eip1559(tx);
+
+            let unsigned = tx.rlp();
+            let (qr_payload, qr_frames) = qr_encode(&unsigned, UNSIGNED_QR_TAG);
+            transactions.push(UnsignedTransaction {
+                index,
+                action: step.action().to_string(),
+                nonce: tx.nonce().copied().unwrap_or_default().as_u64(),
+                gas_limit: gas_limit.to_string(),
+                gas_estimated,
+                max_fee_per_gas: tx
+                    .as_eip1559_ref()
+                    .and_then(|t| t.max_fee_per_gas)
+                    .unwrap_or_default()
+                    .to_string(),
+                max_priority_fee_per_gas: tx
+                    .as_eip1559_ref()
+                    .and_then(|t| t.max_priority_fee_per_gas)
+                    .unwrap_or_default()
+                    .to_string(),
+                unsigned_transaction: format!("{}", unsigned),
+                signing_hash: format!("{:?}", tx.sighash()),
+                qr_payload,
+                qr_frames,
+            });
+        }
+
+        let result = BuildTransactionResult {
+            from: format!("{:?}", from),
+            chain_id,
+            transactions,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
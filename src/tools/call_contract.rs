@@ -0,0 +1,499 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::freshness::{finality_schema_properties, Finality};
+use super::read_storage::{detect_proxy, ProxyInfo};
+use super::units::to_base_units;
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{
+    erc20_call_succeeded, function_selectors, minimal_proxy_target, EthereumClientTrait,
+    SignatureSource,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{Function, HumanReadableParser, ParamType, Token};
+use ethers::prelude::*;
+use ethers::providers::call_raw::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Storage slots probed for a token's balances mapping; declared state rarely goes deeper
+const MAX_BALANCE_SLOT: u64 = 20;
+
+// Written while probing so an existing balance cannot be mistaken for the override
+const PROBE_BALANCE: u64 = 0x1337_1337_1337;
+
+pub struct CallContractTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+    signatures: Option<Arc<dyn SignatureSource>>,
+}
+
+impl<C: EthereumClientTrait> CallContractTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            book: None,
+            signatures: None,
+        }
+    }
+
+    /// Accept contact names as the contract, sender and address arguments
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Name the function raw calldata calls
+    pub fn with_signature_source(mut self, signatures: Arc<dyn SignatureSource>) -> Self {
+        self.signatures = Some(signatures);
+        self
+    }
+
+    /// What runs a call to `to`: its proxy or clone target, and whether `selector` is
+    /// among that code's functions. `None` when no dispatcher could be read.
+    async fn resolve_target(
+        &self,
+        to: Address,
+        selector: Option<[u8; 4]>,
+        block: Option<u64>,
+    ) -> Result<(Option<ProxyInfo>, Option<Address>, Option<bool>)> {
+        let code = self.client.get_code(to, block).await?;
+        if code.is_empty() {
+            return Ok((None, None, None));
+        }
+        let clone_of = minimal_proxy_target(&code);
+        let proxy = match clone_of {
+            Some(_) => None,
+            None => detect_proxy(self.client.as_ref(), to, block).await?,
+        };
+        let code = match clone_of.or(proxy.as_ref().map(|proxy| proxy.implementation)) {
+            Some(implementation) => self.client.get_code(implementation, block).await?,
+            None => code,
+        };
+        let selectors = function_selectors(&code);
+        let found = selector
+            .filter(|_| !selectors.is_empty())
+            .map(|selector| selectors.contains(&selector));
+        Ok((proxy, clone_of, found))
+    }
+}
+
+/// Find the storage slot of `holder`'s balance in `token` by overriding candidate
+/// slots (Solidity and Vyper mapping layouts) until `balanceOf` reads the override
+pub(crate) async fn balance_slot<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+    holder: Address,
+) -> Result<H256> {
+    let probe = H256::from_low_u64_be(PROBE_BALANCE);
+    let call: TypedTransaction = TransactionRequest::new()
+        .to(token)
+        .data(
+            [
+                &keccak256("balanceOf(address)")[..4],
+                &ethers::abi::encode(&[Token::Address(holder)])[..],
+            ]
+            .concat(),
+        )
+        .into();
+    for slot in 0..=MAX_BALANCE_SLOT {
+        let holder_word = H256::from(holder);
+        let slot_word = H256::from_low_u64_be(slot);
+        for (first, second) in [(holder_word, slot_word), (slot_word, holder_word)] {
+            let key = H256::from(keccak256([first.as_bytes(), second.as_bytes()].concat()));
+            let mut state = spoof::state();
+            state.account(token).store(key, probe);
+            match client.call_contract(&call, None, Some(&state)).await {
+                Ok(output) if output.as_ref() == probe.as_bytes() => return Ok(key),
+                Ok(_) => {}
+                Err(e) => return Err(e).context("balanceOf failed; is this an ERC20 token?"),
+            }
+        }
+    }
+    anyhow::bail!(
+        "Could not find where {:?} stores balances; pass a state override of its storage instead",
+        token
+    )
+}
+
+/// Set `holder`'s balance of `token` for the call
+#[derive(Debug, Deserialize)]
+struct TokenBalanceOverride {
+    token: String,
+    #[serde(default)]
+    holder: Option<String>,
+    /// Whole tokens
+    amount: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallContractParams {
+    to: String,
+    #[serde(default)]
+    function: Option<String>,
+    #[serde(default)]
+    args: Vec<Value>,
+    #[serde(default)]
+    data: Option<Bytes>,
+    #[serde(default)]
+    from: Option<String>,
+    /// Wei sent with the call
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    block: Option<u64>,
+    #[serde(default)]
+    finality: Finality,
+    #[serde(default)]
+    state_overrides: Option<spoof::State>,
+    #[serde(default)]
+    token_balances: Vec<TokenBalanceOverride>,
+    #[serde(default = "default_follow_proxy")]
+    follow_proxy: bool,
+}
+
+fn default_follow_proxy() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct AppliedBalance {
+    token: Address,
+    holder: Address,
+    amount: String,
+    slot: H256,
+}
+
+#[derive(Debug, Serialize)]
+struct CallContractResult {
+    to: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function: Option<String>,
+    /// Set when `to` is a proxy: its standard and the implementation the call runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<ProxyInfo>,
+    /// Implementation behind an EIP-1167 minimal proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clone_of: Option<Address>,
+    /// Whether the implementation's dispatcher handles the called selector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selector_found: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block: Option<u64>,
+    output: Bytes,
+    /// Return values, when `function` declares them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decoded: Option<Vec<Value>>,
+    overridden: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    token_balances: Vec<AppliedBalance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+/// Text form of a JSON argument for the lenient tokenizer; addresses may be contact names
+fn arg_text(book: Option<&AddressBook>, kind: &ParamType, value: &Value) -> Result<String> {
+    Ok(match (kind, value) {
+        (ParamType::Address, Value::String(address)) => {
+            format!("{:?}", resolve_address(book, address)?)
+        }
+        (ParamType::Array(inner) | ParamType::FixedArray(inner, _), Value::Array(items)) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| arg_text(book, inner, item))
+                .collect::<Result<_>>()?;
+            format!("[{}]", items.join(","))
+        }
+        (ParamType::Tuple(kinds), Value::Array(items)) => {
+            if kinds.len() != items.len() {
+                anyhow::bail!("Tuple needs {} fields, got {}", kinds.len(), items.len());
+            }
+            let items: Vec<String> = kinds
+                .iter()
+                .zip(items)
+                .map(|(kind, item)| arg_text(book, kind, item))
+                .collect::<Result<_>>()?;
+            format!("({})", items.join(","))
+        }
+        (_, Value::String(text)) => text.clone(),
+        (_, other) => other.to_string(),
+    })
+}
+
+fn token_json(token: Token) -> Value {
+    match token {
+        Token::Address(address) => json!(address),
+        Token::Uint(value) => json!(value.to_string()),
+        Token::Int(value) => json!(I256::from_raw(value).to_string()),
+        Token::Bool(value) => json!(value),
+        Token::String(value) => json!(value),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => json!(Bytes::from(bytes)),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => {
+            Value::Array(items.into_iter().map(token_json).collect())
+        }
+    }
+}
+
+/// An ERC20 `transfer`, `transferFrom` or `approve` declared to return a bool
+fn is_erc20_action(function: &Function) -> bool {
+    matches!(
+        function.name.as_str(),
+        "transfer" | "transferFrom" | "approve"
+    ) && matches!(function.outputs.as_slice(), [output] if output.kind == ParamType::Bool)
+}
+
+fn parse_function(signature: &str) -> Result<Function> {
+    let signature = signature.trim();
+    let signature = if signature.starts_with("function ") {
+        signature.to_string()
+    } else {
+        format!("function {}", signature)
+    };
+    HumanReadableParser::parse_function(&signature)
+        .with_context(|| format!("Invalid function signature: {}", signature))
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CallContractTool<C> {
+    fn name(&self) -> &str {
+        "call_contract"
+    }
+
+    fn description(&self) -> &str {
+        "Read from any contract with eth_call: pass a function signature such as 'balanceOf(address) returns (uint256)' with its args to get decoded return values, or raw calldata. State overrides answer what-if questions without a fork: override any account's balance, nonce, code or storage, or set a token balance by amount and let the tool find its storage slot. Off-chain lookups (EIP-3668 CCIP-Read) are followed. Proxies (EIP-1967, beacon, UUPS and clones) are resolved to their implementation, which is reported with the proxy and checked for the called function; raw calldata is named from its selector. Nothing is sent."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "to": {
+                    "type": "string",
+                    "description": "Contract address or contact name"
+                },
+                "function": {
+                    "type": "string",
+                    "description": "Function signature with return types, e.g. 'getAmountsOut(uint256,address[]) returns (uint256[])'"
+                },
+                "args": {
+                    "type": "array",
+                    "description": "Arguments for function, in order: numbers as strings or numbers, arrays and tuples as JSON arrays; addresses may be contact names"
+                },
+                "data": {
+                    "type": "string",
+                    "description": "Raw 0x calldata, instead of function and args"
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Caller address or contact name (default: the server's wallet)"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Wei sent with the call"
+                },
+                "block": {
+                    "type": "integer",
+                    "description": "Block number to call at (default: latest)"
+                },
+                "state_overrides": {
+                    "type": "object",
+                    "description": "Geth state override set: address => {balance, nonce, code, state, stateDiff}, quantities and slots as 0x hex"
+                },
+                "follow_proxy": {
+                    "type": "boolean",
+                    "description": "Resolve the implementation behind an EIP-1967, beacon, UUPS or clone proxy to check and name the called function (default: true)"
+                },
+                "token_balances": {
+                    "type": "array",
+                    "description": "ERC20 balances to assume, found by probing the token's storage",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "token": { "type": "string" },
+                            "holder": {
+                                "type": "string",
+                                "description": "Address or contact name (default: from)"
+                            },
+                            "amount": {
+                                "type": "string",
+                                "description": "Whole tokens"
+                            }
+                        },
+                        "required": ["token", "amount"]
+                    }
+                }
+            },
+            "required": ["to"]
+        });
+        merge_schema_properties(&mut schema["properties"], finality_schema_properties());
+        schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CallContractParams =
+            serde_json::from_value(params).context("Invalid parameters for call_contract")?;
+        let block = params
+            .finality
+            .resolve(self.client.as_ref(), params.block)
+            .await?;
+        let book = self.book.as_deref();
+        let to = resolve_address(book, &params.to).context("Invalid contract address")?;
+        let from = match params.from.as_deref() {
+            Some(from) => resolve_address(book, from).context("Invalid from address")?,
+            None => self.client.get_wallet_address(),
+        };
+
+        let function = params.function.as_deref().map(parse_function).transpose()?;
+        let data = match (&function, params.data) {
+            (Some(_), Some(_)) => anyhow::bail!("Pass either function or data, not both"),
+            (Some(function), None) => {
+                if params.args.len() != function.inputs.len() {
+                    anyhow::bail!(
+                        "{} takes {} arguments, got {}",
+                        function.name,
+                        function.inputs.len(),
+                        params.args.len()
+                    );
+                }
+                let tokens = function
+                    .inputs
+                    .iter()
+                    .zip(&params.args)
+                    .map(|(input, arg)| {
+                        let text = arg_text(book, &input.kind, arg)?;
+                        LenientTokenizer::tokenize(&input.kind, &text)
+                            .map_err(|e| anyhow::anyhow!("Invalid argument {}: {}", input.name, e))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Bytes::from(function.encode_input(&tokens)?)
+            }
+            (None, Some(data)) => data,
+            (None, None) => anyhow::bail!("Pass a function signature or raw data"),
+        };
+        let value = params
+            .value
+            .as_deref()
+            .map(|value| U256::from_dec_str(value.trim()).context("Invalid value"))
+            .transpose()?;
+
+        let selector = data
+            .get(..4)
+            .and_then(|selector| <[u8; 4]>::try_from(selector).ok());
+        let mut tx = TransactionRequest::new().from(from).to(to).data(data);
+        if let Some(value) = value {
+            tx = tx.value(value);
+        }
+        let tx: TypedTransaction = tx.into();
+
+        let mut state = params.state_overrides.unwrap_or_default();
+        let mut token_balances = Vec::new();
+        for balance in &params.token_balances {
+            let token: Address = balance
+                .token
+                .trim()
+                .parse()
+                .context("Invalid token address")?;
+            let holder = match balance.holder.as_deref() {
+                Some(holder) => resolve_address(book, holder).context("Invalid holder")?,
+                None => from,
+            };
+            let decimals = self
+                .client
+                .get_token_decimals(token)
+                .await
+                .context("Failed to read token decimals")?;
+            let amount = to_base_units(balance.amount, decimals as u32)?;
+            let slot = balance_slot(self.client.as_ref(), token, holder).await?;
+            let mut word = [0u8; 32];
+            amount.to_big_endian(&mut word);
+            state.account(token).store(slot, H256::from(word));
+            token_balances.push(AppliedBalance {
+                token,
+                holder,
+                amount: balance.amount.normalize().to_string(),
+                slot,
+            });
+        }
+        let overridden = state != spoof::State::default();
+
+        let output = self
+            .client
+            .call_contract(
+                &tx,
+                block.map(|block| BlockId::from(U64::from(block))),
+                overridden.then_some(&state),
+            )
+            .await?;
+        let decoded = match &function {
+            // USDT-style tokens return nothing where the standard promises a bool
+            Some(function) if output.is_empty() && is_erc20_action(function) => {
+                Some(vec![token_json(Token::Bool(erc20_call_succeeded(&output)))])
+            }
+            Some(function) if !function.outputs.is_empty() => Some(
+                function
+                    .decode_output(&output)
+                    .context("Failed to decode the return values")?
+                    .into_iter()
+                    .map(token_json)
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        let (proxy, clone_of, selector_found) = if params.follow_proxy {
+            self.resolve_target(to, selector, block).await?
+        } else {
+            (None, None, None)
+        };
+        let mut warnings = Vec::new();
+        if let (Some(false), Some(selector)) = (selector_found, selector) {
+            warnings.push(format!(
+                "0x{} is not among the functions of {:?}; the call reached its fallback, if any",
+                hex::encode(selector),
+                clone_of
+                    .or(proxy.as_ref().map(|proxy| proxy.implementation))
+                    .unwrap_or(to)
+            ));
+        }
+        // Raw calldata is named after the first signature submitted for its selector
+        let named = match (&function, selector, &self.signatures) {
+            (None, Some(selector), Some(signatures)) => signatures
+                .lookup(&[selector])
+                .await
+                .ok()
+                .and_then(|mut names| names.remove(&selector))
+                .and_then(|names| names.into_iter().next()),
+            _ => None,
+        };
+
+        let result = CallContractResult {
+            to,
+            proxy,
+            clone_of,
+            selector_found,
+            warnings,
+            function: named.or(function.map(|function| {
+                let inputs: Vec<String> = function
+                    .inputs
+                    .iter()
+                    .map(|input| input.kind.to_string())
+                    .collect();
+                format!("{}({})", function.name, inputs.join(","))
+            })),
+            block,
+            output,
+            decoded,
+            overridden,
+            token_balances,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,140 @@
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct CancelStreamTool<C: EthereumClientTrait> {
+    executor: ExecutePlanTool<C>,
+    contract: Address,
+}
+
+impl<C: EthereumClientTrait> CancelStreamTool<C> {
+    /// Cancel streams of the Sablier V2 LockupLinear `contract`; without
+    /// `execution_enabled` only dry runs are allowed
+    pub fn new(client: Arc<C>, contract: Address, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(client, execution_enabled),
+            contract,
+        }
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live runs must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CancelStreamParams {
+    stream_id: String,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    override_gas_reserve: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct CancelStreamResult {
+    contract: String,
+    stream_id: String,
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CancelStreamTool<C> {
+    fn name(&self) -> &str {
+        "cancel_stream"
+    }
+
+    fn description(&self) -> &str {
+        "Cancel a cancelable Sablier V2 stream the wallet sends, by the stream_id list_streams reports. What has already streamed stays withdrawable by the recipient and the rest is refunded to the wallet. Dry run by default; the estimate fails when the wallet is not the sender or the stream cannot be canceled."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "stream_id": {
+                    "type": "string",
+                    "description": "Sablier stream id, e.g. '1234'"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transaction without sending (default: true). Live execution must be enabled on the server"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["stream_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CancelStreamParams =
+            serde_json::from_value(params).context("Invalid parameters for cancel_stream")?;
+        let stream_id = U256::from_dec_str(params.stream_id.trim()).context("Invalid stream_id")?;
+
+        let mut plan = Plan::default();
+        plan.steps.push(PlanStep::CancelStream {
+            contract: self.contract,
+            stream_id,
+        });
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "override_gas_reserve": params.override_gas_reserve,
+            }))
+            .await?;
+
+        let result = CancelStreamResult {
+            contract: format!("{:?}", self.contract),
+            stream_id: stream_id.to_string(),
+            plan,
+            execution,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,223 @@
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::get_governance_proposals::Dao;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, GovernanceSource, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct CastVoteTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    governor: Arc<dyn GovernanceSource>,
+    governors: Vec<(String, Address)>,
+}
+
+impl<C: EthereumClientTrait> CastVoteTool<C> {
+    /// Vote through governors read by `governor`; without `execution_enabled` only
+    /// dry runs are allowed
+    pub fn new(
+        client: Arc<C>,
+        governor: Arc<dyn GovernanceSource>,
+        execution_enabled: bool,
+    ) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            governor,
+            governors: Vec::new(),
+        }
+    }
+
+    /// Governors that can be passed by name
+    pub fn with_governors(mut self, governors: Vec<(String, Address)>) -> Self {
+        self.governors = governors;
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live votes must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Support {
+    Against,
+    For,
+    Abstain,
+}
+
+#[derive(Debug, Deserialize)]
+struct CastVoteParams {
+    #[serde(default)]
+    governor: Option<String>,
+    #[serde(default)]
+    space: Option<String>,
+    proposal_id: String,
+    support: Support,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    override_gas_reserve: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct CastVoteResult {
+    governor: Address,
+    proposal_id: String,
+    support: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    votes: Option<String>,
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CastVoteTool<C> {
+    fn name(&self) -> &str {
+        "cast_vote"
+    }
+
+    fn description(&self) -> &str {
+        "Vote for, against or abstain on an on-chain Governor proposal with the server's wallet, optionally with a reason. Refuses when the wallet already voted or had no voting power at the proposal's snapshot. Snapshot votes are signed off-chain and are not supported. Dry run by default; live votes need execution enabled on the server."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "governor": {
+                    "type": "string",
+                    "description": "Governor contract address or known name, as for get_governance_proposals"
+                },
+                "proposal_id": {
+                    "type": "string",
+                    "description": "Proposal id as listed by get_governance_proposals"
+                },
+                "support": {
+                    "type": "string",
+                    "enum": ["for", "against", "abstain"]
+                },
+                "reason": {
+                    "type": "string",
+                    "description": "Rationale recorded on-chain with the vote"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the vote without sending (default: true). Live execution must be enabled on the server"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["governor", "proposal_id", "support"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CastVoteParams =
+            serde_json::from_value(params).context("Invalid parameters for cast_vote")?;
+        let dao = Dao::resolve(
+            &self.governors,
+            params.governor.as_deref(),
+            params.space.as_deref(),
+        )?;
+        let Dao::Governor(governor) = dao else {
+            anyhow::bail!(
+                "Snapshot votes are signed off-chain and cannot be cast here; vote on snapshot.org"
+            );
+        };
+        let proposal_id =
+            U256::from_dec_str(params.proposal_id.trim()).context("Invalid proposal_id")?;
+
+        // Governors that cannot report these are left to the gas estimate to reject
+        let wallet = self.client.get_wallet_address();
+        let power = self
+            .governor
+            .voting_power(&dao.id(), wallet, Some(&proposal_id.to_string()))
+            .await
+            .ok();
+        if let Some(power) = &power {
+            if power.has_voted == Some(true) {
+                anyhow::bail!("The wallet already voted on proposal {}", proposal_id);
+            }
+            if power.votes <= Decimal::ZERO {
+                anyhow::bail!(
+                    "The wallet had no voting power at proposal {}'s snapshot; tokens must be delegated before it",
+                    proposal_id
+                );
+            }
+        }
+
+        let mut plan = Plan::default();
+        plan.steps.push(PlanStep::Vote {
+            governor,
+            proposal_id,
+            support: params.support as u8,
+            reason: params
+                .reason
+                .map(|reason| reason.trim().to_string())
+                .filter(|reason| !reason.is_empty()),
+        });
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "override_gas_reserve": params.override_gas_reserve,
+            }))
+            .await?;
+
+        let result = CastVoteResult {
+            governor,
+            proposal_id: proposal_id.to_string(),
+            support: params.support as u8,
+            votes: power.map(|power| power.votes.normalize().to_string()),
+            plan,
+            execution,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,218 @@
+use super::payment_requests::{unix_now, PaymentRequest, PaymentRequests};
+use super::units::from_base_units;
+use super::Tool;
+use crate::ethereum::{EthereumClientTrait, TokenEventSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest a call may wait for the payment to arrive
+pub const MAX_PAYMENT_WAIT: Duration = Duration::from_secs(60);
+
+// Time between checks while waiting
+const PAYMENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct CheckPaymentTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    events: Arc<dyn TokenEventSource>,
+    requests: Arc<PaymentRequests>,
+}
+
+impl<C: EthereumClientTrait> CheckPaymentTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        events: Arc<dyn TokenEventSource>,
+        requests: Arc<PaymentRequests>,
+    ) -> Self {
+        Self {
+            client,
+            events,
+            requests,
+        }
+    }
+
+    /// Payments received toward `request` up to the latest block
+    async fn check(&self, request: &PaymentRequest) -> Result<PaymentCheck> {
+        let head = self.client.get_block_number().await?;
+        let mut check = PaymentCheck {
+            checked_block: head,
+            received: Decimal::ZERO,
+            transfers: Vec::new(),
+            paid_in: None,
+            confirmations: None,
+        };
+        match request.token {
+            Some(token) => {
+                let transfers = self
+                    .events
+                    .incoming_transfers(token, request.recipient, request.created_block, head)
+                    .await?;
+                let mut received = U256::zero();
+                for transfer in transfers {
+                    received = received.saturating_add(transfer.amount);
+                    if check.paid_in.is_none() && received >= request.amount_base_units {
+                        check.paid_in = Some(transfer.tx_hash);
+                        check.confirmations = Some(head.saturating_sub(transfer.block_number) + 1);
+                    }
+                    check.transfers.push(IncomingTransfer {
+                        from: transfer.from,
+                        amount: from_base_units(transfer.amount, request.decimals as u32)?
+                            .normalize(),
+                        block_number: transfer.block_number,
+                        tx_hash: transfer.tx_hash,
+                    });
+                }
+                check.received = from_base_units(received, request.decimals as u32)?;
+            }
+            None => {
+                let balance = self.client.get_eth_balance(request.recipient).await?;
+                check.received = (balance - request.baseline_balance).max(Decimal::ZERO);
+            }
+        }
+        check.received = check.received.normalize();
+        Ok(check)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckPaymentParams {
+    request_id: String,
+    /// Seconds to keep checking until the request is paid
+    #[serde(default)]
+    wait_secs: u64,
+}
+
+struct PaymentCheck {
+    checked_block: u64,
+    received: Decimal,
+    transfers: Vec<IncomingTransfer>,
+    paid_in: Option<H256>,
+    confirmations: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PaymentStatus {
+    Pending,
+    PartiallyPaid,
+    Paid,
+    /// Expired before being paid in full
+    Expired,
+}
+
+#[derive(Debug, Serialize)]
+struct IncomingTransfer {
+    from: Address,
+    amount: Decimal,
+    block_number: u64,
+    tx_hash: H256,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckPaymentResult {
+    request_id: String,
+    status: PaymentStatus,
+    recipient: Address,
+    symbol: String,
+    amount: Decimal,
+    received: Decimal,
+    remaining: Decimal,
+    /// `transfer_logs` for tokens; `balance_increase` for ETH, which leaves no logs
+    detection: &'static str,
+    /// Transfer that completed the payment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payment_tx: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    transfers: Vec<IncomingTransfer>,
+    checked_block: u64,
+    expires_at: u64,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CheckPaymentTool<C> {
+    fn name(&self) -> &str {
+        "check_payment"
+    }
+
+    fn description(&self) -> &str {
+        "Check whether a request from create_payment_request has been paid. Token payments are matched against the token's Transfer logs to the recipient since the request was created; ETH payments, which leave no logs, are detected as growth of the recipient's balance. Set wait_secs to keep watching until the payment arrives."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "request_id": {
+                    "type": "string",
+                    "description": "request_id returned by create_payment_request"
+                },
+                "wait_secs": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": MAX_PAYMENT_WAIT.as_secs(),
+                    "description": "Keep checking for up to this many seconds until the request is paid (default: 0, check once)"
+                }
+            },
+            "required": ["request_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CheckPaymentParams =
+            serde_json::from_value(params).context("Invalid parameters for check_payment")?;
+        if params.wait_secs > MAX_PAYMENT_WAIT.as_secs() {
+            anyhow::bail!("wait_secs must be at most {}", MAX_PAYMENT_WAIT.as_secs());
+        }
+
+        let request = self.requests.get(&params.request_id)?;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(params.wait_secs);
+        let check = loop {
+            let check = self.check(&request).await?;
+            if check.received >= request.amount || tokio::time::Instant::now() >= deadline {
+                break check;
+            }
+            tokio::time::sleep(PAYMENT_POLL_INTERVAL.min(deadline - tokio::time::Instant::now()))
+                .await;
+        };
+
+        let status = if check.received >= request.amount {
+            PaymentStatus::Paid
+        } else if request.is_expired(unix_now()) {
+            PaymentStatus::Expired
+        } else if check.received > Decimal::ZERO {
+            PaymentStatus::PartiallyPaid
+        } else {
+            PaymentStatus::Pending
+        };
+        let result = CheckPaymentResult {
+            request_id: request.request_id,
+            status,
+            recipient: request.recipient,
+            symbol: request.symbol,
+            amount: request.amount,
+            received: check.received,
+            remaining: (request.amount - check.received)
+                .max(Decimal::ZERO)
+                .normalize(),
+            detection: if request.token.is_some() {
+                "transfer_logs"
+            } else {
+                "balance_increase"
+            },
+            payment_tx: check.paid_in,
+            confirmations: check.confirmations,
+            transfers: check.transfers,
+            checked_block: check.checked_block,
+            expires_at: request.expires_at,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
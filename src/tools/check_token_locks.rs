@@ -0,0 +1,305 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::monitor_token_risks::percent_of;
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, LockSource, Release, TokenEventSource, TokenLock};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct CheckTokenLocksTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    sources: Vec<Arc<dyn LockSource>>,
+    /// Total supply, to size locks against
+    events: Arc<dyn TokenEventSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> CheckTokenLocksTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        sources: Vec<Arc<dyn LockSource>>,
+        events: Arc<dyn TokenEventSource>,
+    ) -> Self {
+        Self {
+            client,
+            sources,
+            events,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the wallet
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckTokenLocksParams {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    wallet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LockReport {
+    locker: String,
+    lock_id: String,
+    token: Address,
+    symbol: String,
+    owner: Address,
+    deposited: String,
+    withdrawn: String,
+    /// Still locked now
+    locked: String,
+    /// Unlocked but not yet withdrawn
+    claimable: String,
+    release: Release,
+    fully_unlocked_at: u64,
+}
+
+/// Tokens released between `from` and `to`; all at once when they are equal
+#[derive(Debug, Serialize)]
+struct UnlockWindow {
+    from: u64,
+    to: u64,
+    amount: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenSummary {
+    token: Address,
+    symbol: String,
+    locks: usize,
+    locked: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked_percent_of_supply: Option<String>,
+    /// Next time more tokens unlock, or now while a stream is vesting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_unlock_at: Option<u64>,
+    unlock_schedule: Vec<UnlockWindow>,
+}
+
+#[derive(Debug, Serialize)]
+struct LockerError {
+    locker: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckTokenLocksResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wallet: Option<Address>,
+    /// Chain time the locks were evaluated at
+    timestamp: u64,
+    tokens: Vec<TokenSummary>,
+    /// Every open lock, soonest to fully unlock first
+    locks: Vec<LockReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<LockerError>,
+}
+
+/// Releases of `lock` still ahead of `now`
+fn unlock_windows(lock: &TokenLock, now: u64) -> Vec<(u64, u64, U256)> {
+    match lock.release {
+        Release::Cliff { unlock_at } if unlock_at > now => {
+            vec![(unlock_at, unlock_at, lock.deposited)]
+        }
+        Release::Cliff { .. } => Vec::new(),
+        Release::Linear { cliff, end, .. } => {
+            let mut windows = Vec::new();
+            let from = now.max(cliff);
+            if cliff > now && !lock.unlocked_at(cliff).is_zero() {
+                windows.push((cliff, cliff, lock.unlocked_at(cliff)));
+            }
+            if end > from {
+                windows.push((from, end, lock.locked_at(from)));
+            }
+            windows
+        }
+    }
+}
+
+fn amount(value: U256, decimals: u8) -> Result<String> {
+    Ok(from_base_units(value, decimals as u32)?
+        .normalize()
+        .to_string())
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CheckTokenLocksTool<C> {
+    fn name(&self) -> &str {
+        "check_token_locks"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect locker and vesting contracts (Unicrypt V2 liquidity locks, Team Finance token locks and Sablier V2 linear streams) for a token's locks or a wallet's locked positions. Reports each lock's deposited, withdrawn, still-locked and claimable amounts with its unlock time or vesting range, and per token the total locked, its share of supply and the upcoming unlock schedule. Unicrypt V2 locks hold Uniswap V2 LP tokens, so pass the pair address to see a token's liquidity locks."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Token (or Uniswap V2 pair) address whose locks to list"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Address or contact name whose locked positions to list; with token, only that token's locks it owns"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CheckTokenLocksParams =
+            serde_json::from_value(params).context("Invalid parameters for check_token_locks")?;
+        let token: Option<Address> = params
+            .token
+            .as_deref()
+            .map(|token| token.trim().parse().context("Invalid token address"))
+            .transpose()?;
+        let wallet = params
+            .wallet
+            .as_deref()
+            .map(|wallet| resolve_address(self.book.as_deref(), wallet).context("Invalid wallet"))
+            .transpose()?;
+        if token.is_none() && wallet.is_none() {
+            anyhow::bail!("Pass a token, a wallet, or both");
+        }
+
+        let now = self.client.get_latest_block().await?.timestamp;
+        let mut locks = Vec::new();
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            let found = match (token, wallet) {
+                (Some(token), wallet) => source.locks_for_token(token).await.map(|locks| {
+                    locks
+                        .into_iter()
+                        .filter(|lock| wallet.is_none_or(|wallet| lock.owner == wallet))
+                        .collect()
+                }),
+                (None, Some(wallet)) => source.locks_for_owner(wallet).await,
+                (None, None) => unreachable!(),
+            };
+            match found {
+                Ok(found) => locks.extend(found),
+                Err(e) => errors.push(LockerError {
+                    locker: source.name().to_string(),
+                    error: format!("{:#}", e),
+                }),
+            }
+        }
+        locks.retain(|lock| !lock.deposited.saturating_sub(lock.withdrawn).is_zero());
+        locks.sort_by_key(|lock| lock.fully_unlocked_at());
+
+        let mut metadata: HashMap<Address, (String, u8)> = HashMap::new();
+        for lock in &locks {
+            if metadata.contains_key(&lock.token) {
+                continue;
+            }
+            let symbol = self
+                .client
+                .get_token_symbol(lock.token)
+                .await
+                .unwrap_or_else(|_| "UNKNOWN".to_string());
+            let decimals = self
+                .client
+                .get_token_decimals(lock.token)
+                .await
+                .context("Failed to read token decimals")?;
+            metadata.insert(lock.token, (symbol, decimals));
+        }
+
+        let mut reports = Vec::with_capacity(locks.len());
+        let mut tokens: Vec<TokenSummary> = Vec::new();
+        let mut windows: HashMap<Address, Vec<(u64, u64, U256)>> = HashMap::new();
+        let mut locked_totals: HashMap<Address, U256> = HashMap::new();
+        for lock in &locks {
+            let (symbol, decimals) = &metadata[&lock.token];
+            let locked = lock.locked_at(now);
+            let claimable = lock.unlocked_at(now).saturating_sub(lock.withdrawn);
+            reports.push(LockReport {
+                locker: lock.locker.clone(),
+                lock_id: lock.lock_id.clone(),
+                token: lock.token,
+                symbol: symbol.clone(),
+                owner: lock.owner,
+                deposited: amount(lock.deposited, *decimals)?,
+                withdrawn: amount(lock.withdrawn, *decimals)?,
+                locked: amount(locked, *decimals)?,
+                claimable: amount(claimable, *decimals)?,
+                release: lock.release,
+                fully_unlocked_at: lock.fully_unlocked_at(),
+            });
+            *locked_totals.entry(lock.token).or_default() += locked;
+            windows
+                .entry(lock.token)
+                .or_default()
+                .extend(unlock_windows(lock, now));
+        }
+
+        let mut seen: Vec<Address> = Vec::new();
+        for lock in &locks {
+            if seen.contains(&lock.token) {
+                continue;
+            }
+            seen.push(lock.token);
+            let (symbol, decimals) = &metadata[&lock.token];
+            let locked = locked_totals[&lock.token];
+            let mut schedule = windows.remove(&lock.token).unwrap_or_default();
+            schedule.sort_by_key(|(from, to, _)| (*from, *to));
+            let locked_percent_of_supply = match self.events.total_supply(lock.token).await {
+                Ok(supply) if !supply.is_zero() => {
+                    Some(percent_of(locked, supply).normalize().to_string())
+                }
+                _ => None,
+            };
+            tokens.push(TokenSummary {
+                token: lock.token,
+                symbol: symbol.clone(),
+                locks: locks
+                    .iter()
+                    .filter(|other| other.token == lock.token)
+                    .count(),
+                locked: amount(locked, *decimals)?,
+                locked_percent_of_supply,
+                next_unlock_at: schedule.first().map(|(from, _, _)| *from),
+                unlock_schedule: schedule
+                    .into_iter()
+                    .map(|(from, to, unlocking)| {
+                        Ok(UnlockWindow {
+                            from,
+                            to,
+                            amount: amount(unlocking, *decimals)?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            });
+        }
+
+        let result = CheckTokenLocksResult {
+            token,
+            wallet,
+            timestamp: now,
+            tokens,
+            locks: reports,
+            errors,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
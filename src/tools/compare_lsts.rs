@@ -0,0 +1,243 @@
+use super::scan_arbitrage::WETH;
+use super::units::{from_base_units, to_base_units};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, LiquidStakingToken, PriceVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_AMOUNT: u64 = 100;
+
+// Every supported LST has 18 decimals, like ETH
+const LST_DECIMALS: u32 = 18;
+
+pub struct CompareLstsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    lsts: Vec<Arc<dyn LiquidStakingToken>>,
+    /// Venues the tokens are sold into WETH on
+    venues: Vec<Arc<dyn PriceVenue>>,
+}
+
+impl<C: EthereumClientTrait> CompareLstsTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        lsts: Vec<Arc<dyn LiquidStakingToken>>,
+        venues: Vec<Arc<dyn PriceVenue>>,
+    ) -> Self {
+        Self {
+            client,
+            lsts,
+            venues,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareLstsParams {
+    #[serde(default)]
+    amount: Option<Decimal>,
+    /// Symbols to compare, all when unset
+    #[serde(default)]
+    tokens: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct MarketReport {
+    /// Venue paying the most WETH for `amount`
+    venue: String,
+    /// WETH per token selling one token
+    rate: String,
+    /// Market rate against the redemption rate; negative is a discount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peg_deviation_bps: Option<String>,
+    /// WETH received selling `amount`
+    amount_out: String,
+    /// How much worse selling `amount` is than selling one token
+    price_impact_percent: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LstReport {
+    symbol: String,
+    token: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exchange_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apr_percent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    market: Option<MarketReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CompareLstsResult {
+    amount: String,
+    /// Highest APR first, followed by those without one
+    lsts: Vec<LstReport>,
+    block_number: u64,
+}
+
+impl<C: EthereumClientTrait> CompareLstsTool<C> {
+    /// Best WETH out for `amount_in` of `token` across the venues
+    async fn best_quote(&self, token: Address, amount_in: U256) -> Result<(String, U256)> {
+        let weth: Address = WETH.parse().context("Invalid WETH address")?;
+        let mut best: Option<(String, U256)> = None;
+        for venue in &self.venues {
+            if let Ok(out) = venue.quote(token, weth, amount_in).await {
+                if best.as_ref().is_none_or(|(_, best)| out > *best) {
+                    best = Some((venue.name().to_string(), out));
+                }
+            }
+        }
+        best.context("No venue quotes this token against WETH")
+    }
+
+    async fn market(
+        &self,
+        token: Address,
+        amount: Decimal,
+        exchange_rate: Option<Decimal>,
+    ) -> Result<MarketReport> {
+        let (_, one_out) = self
+            .best_quote(token, to_base_units(Decimal::ONE, LST_DECIMALS)?)
+            .await?;
+        let rate = from_base_units(one_out, 18)?;
+        let (venue, amount_out) = self
+            .best_quote(token, to_base_units(amount, LST_DECIMALS)?)
+            .await?;
+        let amount_out = from_base_units(amount_out, 18)?;
+        let impact = if rate.is_zero() {
+            Decimal::ZERO
+        } else {
+            (Decimal::ONE - amount_out / amount / rate) * Decimal::from(100)
+        };
+        Ok(MarketReport {
+            venue,
+            rate: rate.round_dp(6).normalize().to_string(),
+            peg_deviation_bps: exchange_rate
+                .filter(|exchange_rate| !exchange_rate.is_zero())
+                .map(|exchange_rate| {
+                    ((rate / exchange_rate - Decimal::ONE) * Decimal::from(10_000))
+                        .round_dp(2)
+                        .normalize()
+                        .to_string()
+                }),
+            amount_out: amount_out.round_dp(6).normalize().to_string(),
+            price_impact_percent: impact.round_dp(4).normalize().to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CompareLstsTool<C> {
+    fn name(&self) -> &str {
+        "compare_lsts"
+    }
+
+    fn description(&self) -> &str {
+        "Compare liquid staking tokens (stETH, rETH, cbETH, frxETH): the ETH each redeems for, staking APR from the last week of on-chain rate growth, and the DEX market for it: the best WETH rate, its premium or discount to the redemption rate, and the price impact of selling an amount. Ranked by APR."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let symbols: Vec<&str> = self.lsts.iter().map(|lst| lst.symbol()).collect();
+        json!({
+            "type": "object",
+            "properties": {
+                "amount": {
+                    "type": "string",
+                    "description": "Tokens to price selling into WETH, sizing the liquidity check (default: 100)"
+                },
+                "tokens": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": symbols },
+                    "description": "Only these tokens (default: all)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CompareLstsParams =
+            serde_json::from_value(params).context("Invalid parameters for compare_lsts")?;
+        let amount = params.amount.unwrap_or(Decimal::from(DEFAULT_AMOUNT));
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("Amount must be positive");
+        }
+        if let Some(tokens) = &params.tokens {
+            for symbol in tokens {
+                if !self
+                    .lsts
+                    .iter()
+                    .any(|lst| lst.symbol().eq_ignore_ascii_case(symbol))
+                {
+                    anyhow::bail!("Unknown liquid staking token {}", symbol);
+                }
+            }
+        }
+        let block_number = self.client.get_block_number().await?;
+
+        let mut ranked = Vec::new();
+        let mut unranked = Vec::new();
+        for lst in &self.lsts {
+            let wanted = params.tokens.as_ref().is_none_or(|tokens| {
+                tokens
+                    .iter()
+                    .any(|symbol| lst.symbol().eq_ignore_ascii_case(symbol))
+            });
+            if !wanted {
+                continue;
+            }
+
+            let mut errors = Vec::new();
+            let exchange_rate = lst
+                .exchange_rate()
+                .await
+                .map_err(|e| errors.push(format!("exchange rate: {}", e)))
+                .ok();
+            let apr = lst
+                .apr()
+                .await
+                .map_err(|e| errors.push(format!("apr: {}", e)))
+                .ok();
+            let market = self
+                .market(lst.token(), amount, exchange_rate)
+                .await
+                .map_err(|e| errors.push(format!("market: {}", e)))
+                .ok();
+
+            let report = LstReport {
+                symbol: lst.symbol().to_string(),
+                token: lst.token(),
+                exchange_rate: exchange_rate.map(|rate| rate.round_dp(6).normalize().to_string()),
+                apr_percent: apr.map(|apr| apr.normalize().to_string()),
+                market,
+                errors,
+            };
+            match apr {
+                Some(apr) => ranked.push((apr, report)),
+                None => unranked.push(report),
+            }
+        }
+        ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let result = CompareLstsResult {
+            amount: amount.to_string(),
+            lsts: ranked
+                .into_iter()
+                .map(|(_, report)| report)
+                .chain(unranked)
+                .collect(),
+            block_number,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,383 @@
+use super::quotes::{QuoteStore, StoredQuote};
+use super::units::from_base_units;
+use super::{SwapTokensTool, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PlanStep, UniswapRouterTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Moves smaller than this, in percent, are reported as unchanged
+const NOISE_PERCENT: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+pub struct CompareQuotesTool<C: EthereumClientTrait, U: UniswapRouterTrait> {
+    quotes: Arc<QuoteStore>,
+    /// Quotes parameter sets into `quotes`
+    swap: SwapTokensTool<C, U>,
+}
+
+impl<C: EthereumClientTrait, U: UniswapRouterTrait> CompareQuotesTool<C, U> {
+    pub fn new(swap: SwapTokensTool<C, U>, quotes: Arc<QuoteStore>) -> Self {
+        Self {
+            swap: swap.with_quote_store(Arc::clone(&quotes)),
+            quotes,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareQuotesParams {
+    before: Value,
+    after: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct QuoteSummary {
+    quote_id: String,
+    from_token: Address,
+    to_token: Address,
+    amount_in: String,
+    expected_amount_out: String,
+    minimum_amount_out: String,
+    /// `to_token` received per `from_token` sold
+    execution_price: String,
+    /// Pool price before the trade
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spot_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_impact_percent: Option<String>,
+    approvals: usize,
+    gas_estimate: String,
+    gas_price_gwei: String,
+    gas_cost_eth: String,
+    block_number: u64,
+    quoted_at: u64,
+}
+
+/// One figure in both quotes
+#[derive(Debug, Serialize)]
+struct Change {
+    before: String,
+    after: String,
+    change: String,
+    /// Relative to `before`; absent when `before` is zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_percent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Changes {
+    expected_amount_out: Change,
+    minimum_amount_out: Change,
+    execution_price: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spot_price: Option<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_impact_percent: Option<Change>,
+    gas_estimate: Change,
+    gas_price_gwei: Change,
+    gas_cost_eth: Change,
+}
+
+#[derive(Debug, Serialize)]
+struct CompareQuotesResult {
+    before: QuoteSummary,
+    after: QuoteSummary,
+    /// Same tokens and amount; otherwise only prices compare meaningfully
+    same_trade: bool,
+    blocks_elapsed: i64,
+    seconds_elapsed: i64,
+    changes: Changes,
+    /// What moved: prices first, then gas
+    explanation: Vec<String>,
+}
+
+/// A quote's figures in display units (both tokens taken as 18 decimals)
+struct Figures {
+    amount_in: Decimal,
+    amount_out: Decimal,
+    minimum_out: Decimal,
+    execution_price: Decimal,
+    gas_price_gwei: Decimal,
+    gas_cost_eth: Decimal,
+    approvals: usize,
+}
+
+impl Figures {
+    fn of(quote: &StoredQuote) -> Result<Self> {
+        let amount_in = from_base_units(quote.amount_in, 18)?;
+        let amount_out = from_base_units(quote.amount_out, 18)?;
+        let minimum_out = quote
+            .plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PlanStep::Swap { amount_out_min, .. } => Some(*amount_out_min),
+                _ => None,
+            })
+            .try_fold(Decimal::ZERO, |total, min| {
+                Ok::<_, anyhow::Error>(total + from_base_units(min, 18)?)
+            })?;
+        Ok(Self {
+            execution_price: amount_out.checked_div(amount_in).unwrap_or_default(),
+            amount_in,
+            amount_out,
+            minimum_out,
+            gas_price_gwei: from_base_units(quote.gas_price, 9)?,
+            gas_cost_eth: from_base_units(quote.gas_estimate * quote.gas_price, 18)?,
+            approvals: quote
+                .plan
+                .steps
+                .iter()
+                .filter(|step| matches!(step, PlanStep::Approve { .. }))
+                .count(),
+        })
+    }
+}
+
+fn percent_change(before: Decimal, after: Decimal) -> Option<Decimal> {
+    let ratio = (after - before).checked_div(before)?;
+    Some((ratio * Decimal::ONE_HUNDRED).round_dp(2))
+}
+
+fn change(before: Decimal, after: Decimal) -> Change {
+    Change {
+        before: before.normalize().to_string(),
+        after: after.normalize().to_string(),
+        change: (after - before).normalize().to_string(),
+        change_percent: percent_change(before, after).map(|percent| percent.to_string()),
+    }
+}
+
+fn moved(before: Decimal, after: Decimal) -> bool {
+    percent_change(before, after).map_or(before != after, |percent| percent.abs() >= NOISE_PERCENT)
+}
+
+fn signed(percent: Decimal) -> String {
+    if percent.is_sign_negative() {
+        format!("{}%", percent.normalize())
+    } else {
+        format!("+{}%", percent.normalize())
+    }
+}
+
+/// Why the second quote differs from the first
+fn explain(
+    before: &StoredQuote,
+    after: &StoredQuote,
+    old: &Figures,
+    new: &Figures,
+    same_trade: bool,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if !same_trade {
+        reasons.push(format!(
+            "The quotes are for different trades ({} vs {} in); compare execution_price rather than output",
+            old.amount_in.normalize(),
+            new.amount_in.normalize()
+        ));
+    }
+    if moved(old.execution_price, new.execution_price) {
+        reasons.push(format!(
+            "Execution price moved {} ({} to {} per token in)",
+            signed(percent_change(old.execution_price, new.execution_price).unwrap_or_default()),
+            old.execution_price.round_dp(8).normalize(),
+            new.execution_price.round_dp(8).normalize()
+        ));
+    }
+    if let (Some(old_spot), Some(new_spot)) = (before.spot_price, after.spot_price) {
+        if moved(old_spot, new_spot) {
+            reasons.push(format!(
+                "The pool price itself moved {} between blocks {} and {}: its reserves changed through other trades or liquidity moves",
+                signed(percent_change(old_spot, new_spot).unwrap_or_default()),
+                before.block_number,
+                after.block_number
+            ));
+        }
+    }
+    if let (Some(old_impact), Some(new_impact)) =
+        (before.price_impact_percent, after.price_impact_percent)
+    {
+        if (new_impact - old_impact).abs() >= NOISE_PERCENT {
+            reasons.push(format!(
+                "Price impact {} from {}% to {}%, as the trade takes {} share of the pool",
+                if new_impact > old_impact {
+                    "rose"
+                } else {
+                    "fell"
+                },
+                old_impact.normalize(),
+                new_impact.normalize(),
+                if new_impact > old_impact {
+                    "a larger"
+                } else {
+                    "a smaller"
+                }
+            ));
+        }
+    }
+    if moved(old.gas_price_gwei, new.gas_price_gwei) {
+        reasons.push(format!(
+            "Gas price moved {} ({} to {} gwei)",
+            signed(percent_change(old.gas_price_gwei, new.gas_price_gwei).unwrap_or_default()),
+            old.gas_price_gwei.round_dp(4).normalize(),
+            new.gas_price_gwei.round_dp(4).normalize()
+        ));
+    }
+    if old.approvals != new.approvals {
+        reasons.push(format!(
+            "The bundle needs {} approval step(s) instead of {}, changing the gas estimate from {} to {}",
+            new.approvals, old.approvals, before.gas_estimate, after.gas_estimate
+        ));
+    } else if before.gas_estimate != after.gas_estimate {
+        reasons.push(format!(
+            "Gas estimate changed from {} to {}",
+            before.gas_estimate, after.gas_estimate
+        ));
+    }
+    if moved(old.gas_cost_eth, new.gas_cost_eth) {
+        reasons.push(format!(
+            "Gas cost moved {} ({} to {} ETH)",
+            signed(percent_change(old.gas_cost_eth, new.gas_cost_eth).unwrap_or_default()),
+            old.gas_cost_eth.normalize(),
+            new.gas_cost_eth.normalize()
+        ));
+    }
+    if reasons.is_empty() {
+        reasons.push("Nothing material changed between the quotes".to_string());
+    }
+    reasons
+}
+
+impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> CompareQuotesTool<C, U> {
+    /// The stored quote a side names, quoting a parameter set through swap_tokens first
+    async fn resolve(&self, side: &str, value: Value) -> Result<(String, StoredQuote)> {
+        let id = match value {
+            Value::String(id) => id,
+            params @ Value::Object(_) => {
+                let quote = self
+                    .swap
+                    .execute(params)
+                    .await
+                    .with_context(|| format!("Failed to quote {}", side))?;
+                quote["quote_id"]
+                    .as_str()
+                    .context("swap_tokens returned no quote_id")?
+                    .to_string()
+            }
+            _ => anyhow::bail!("{} must be a quote_id or swap_tokens parameters", side),
+        };
+        let quote = self.quotes.recall(&id)?;
+        Ok((id, quote))
+    }
+}
+
+fn summary(id: String, quote: &StoredQuote, figures: &Figures) -> QuoteSummary {
+    QuoteSummary {
+        quote_id: id,
+        from_token: quote.from_token,
+        to_token: quote.to_token,
+        amount_in: figures.amount_in.normalize().to_string(),
+        expected_amount_out: figures.amount_out.normalize().to_string(),
+        minimum_amount_out: figures.minimum_out.normalize().to_string(),
+        execution_price: figures.execution_price.normalize().to_string(),
+        spot_price: quote.spot_price.map(|price| price.normalize().to_string()),
+        price_impact_percent: quote
+            .price_impact_percent
+            .map(|impact| impact.normalize().to_string()),
+        approvals: figures.approvals,
+        gas_estimate: quote.gas_estimate.to_string(),
+        gas_price_gwei: figures.gas_price_gwei.normalize().to_string(),
+        gas_cost_eth: figures.gas_cost_eth.normalize().to_string(),
+        block_number: quote.block_number,
+        quoted_at: quote.quoted_at,
+    }
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
+    for CompareQuotesTool<C, U>
+{
+    fn name(&self) -> &str {
+        "compare_quotes"
+    }
+
+    fn description(&self) -> &str {
+        "Diff two swap quotes to explain why the price changed, e.g. between planning and execution. Each side is a quote_id from swap_tokens (expired quotes stay readable for 10 minutes) or a set of swap_tokens parameters to quote now. Returns both quotes side by side, the change in expected and minimum output, execution and pool price, price impact, gas estimate, gas price and gas cost, and the causes ranked by effect."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn input_schema(&self) -> Value {
+        let side = |description: &str| {
+            json!({
+                "description": description,
+                "oneOf": [
+                    { "type": "string", "description": "quote_id from swap_tokens" },
+                    {
+                        "type": "object",
+                        "description": "swap_tokens parameters, quoted now",
+                        "required": ["from_token", "to_token", "amount"]
+                    }
+                ]
+            })
+        };
+        json!({
+            "type": "object",
+            "properties": {
+                "before": side("Earlier quote: a quote_id, or swap_tokens parameters"),
+                "after": side("Later quote: a quote_id, or swap_tokens parameters, such as the planned trade re-quoted at execution")
+            },
+            "required": ["before", "after"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CompareQuotesParams =
+            serde_json::from_value(params).context("Invalid parameters for compare_quotes")?;
+        let (before_id, before) = self.resolve("before", params.before).await?;
+        let (after_id, after) = self.resolve("after", params.after).await?;
+
+        let old = Figures::of(&before)?;
+        let new = Figures::of(&after)?;
+        let same_trade = before.from_token == after.from_token
+            && before.to_token == after.to_token
+            && before.amount_in == after.amount_in;
+        let changes = Changes {
+            expected_amount_out: change(old.amount_out, new.amount_out),
+            minimum_amount_out: change(old.minimum_out, new.minimum_out),
+            execution_price: change(old.execution_price, new.execution_price),
+            spot_price: before
+                .spot_price
+                .zip(after.spot_price)
+                .map(|(old, new)| change(old, new)),
+            price_impact_percent: before
+                .price_impact_percent
+                .zip(after.price_impact_percent)
+                .map(|(old, new)| change(old, new)),
+            gas_estimate: change(
+                Decimal::from(before.gas_estimate.low_u64()),
+                Decimal::from(after.gas_estimate.low_u64()),
+            ),
+            gas_price_gwei: change(old.gas_price_gwei, new.gas_price_gwei),
+            gas_cost_eth: change(old.gas_cost_eth, new.gas_cost_eth),
+        };
+        let explanation = explain(&before, &after, &old, &new, same_trade);
+
+        let result = CompareQuotesResult {
+            blocks_elapsed: after.block_number as i64 - before.block_number as i64,
+            seconds_elapsed: after.quoted_at as i64 - before.quoted_at as i64,
+            before: summary(before_id, &before, &old),
+            after: summary(after_id, &after, &new),
+            same_trade,
+            changes,
+            explanation,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,211 @@
+use super::explain::display;
+use super::scan_arbitrage::WETH;
+use super::units::{from_base_units, to_base_units};
+use super::{Explanation, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PriceVenue, YieldSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_AMOUNT: u64 = 10_000;
+const DEFAULT_HOLDING_DAYS: u32 = 365;
+
+pub struct CompareYieldsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    sources: Vec<Arc<dyn YieldSource>>,
+    /// Prices ETH in the deposited token to cost gas
+    gas_venue: Arc<dyn PriceVenue>,
+}
+
+impl<C: EthereumClientTrait> CompareYieldsTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        sources: Vec<Arc<dyn YieldSource>>,
+        gas_venue: Arc<dyn PriceVenue>,
+    ) -> Self {
+        Self {
+            client,
+            sources,
+            gas_venue,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareYieldsParams {
+    token: String,
+    #[serde(default)]
+    amount: Option<Decimal>,
+    #[serde(default)]
+    holding_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct YieldReport {
+    protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apy_percent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_cost: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_earnings: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_earnings: Option<String>,
+    /// APY after entry and exit gas over the holding period
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_apy_percent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CompareYieldsResult {
+    token: String,
+    amount: String,
+    holding_days: u32,
+    gas_price_gwei: String,
+    /// Sources with a yield, best net earnings first, followed by those without
+    yields: Vec<YieldReport>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CompareYieldsTool<C> {
+    fn name(&self) -> &str {
+        "compare_yields"
+    }
+
+    fn description(&self) -> &str {
+        "Compare current on-chain deposit yields for a token (typically a stablecoin) across the DAI Savings Rate (sDAI), Aave V3, Compound V3 and ERC-4626 vaults, ranked by earnings net of estimated entry and exit gas over a holding period."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Address of the token to deposit, e.g. DAI or USDC"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount to deposit, in human units (default: 10000)"
+                },
+                "holding_days": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "How long the deposit is held, spreading gas over the period (default: 365)"
+                }
+            },
+            "required": ["token"]
+        })
+    }
+
+    fn explain(&self, params: &Value, result: &Value) -> Explanation {
+        Explanation::new(
+            format!(
+                "Read each protocol's current supply APY for {} and projected earnings on {} over {} days. Entry plus exit gas at {} gwei was priced in the token through the Uniswap V2 WETH pair and subtracted, and protocols were ranked by what is left.",
+                display(&result["token"]), display(&result["amount"]), display(&result["holding_days"]), display(&result["gas_price_gwei"])
+            ),
+            json!({
+                "inputs": params,
+                "gas_price_gwei": result["gas_price_gwei"],
+                "formulas": {
+                    "expected_earnings": "amount * apy_percent / 100 * holding_days / 365",
+                    "gas_cost": "(entry_gas + exit_gas) * gas_price / 10^18 * token_per_eth",
+                    "net_apy_percent": "(expected_earnings - gas_cost) / amount / (holding_days / 365) * 100",
+                },
+            }),
+        )
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CompareYieldsParams =
+            serde_json::from_value(params).context("Invalid parameters for compare_yields")?;
+
+        let token: Address = params.token.parse().context("Invalid token address")?;
+        let amount = params.amount.unwrap_or(Decimal::from(DEFAULT_AMOUNT));
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("Amount must be positive");
+        }
+        let holding_days = params.holding_days.unwrap_or(DEFAULT_HOLDING_DAYS);
+        if holding_days == 0 {
+            anyhow::bail!("holding_days must be at least 1");
+        }
+
+        // Gas is paid in ETH, so price one ETH in the token
+        let decimals = self.client.get_token_decimals(token).await? as u32;
+        let gas_price = self.client.get_gas_price().await?;
+        let eth_in_token = self
+            .gas_venue
+            .quote(
+                WETH.parse().context("Invalid WETH address")?,
+                token,
+                to_base_units(Decimal::ONE, 18)?,
+            )
+            .await
+            .and_then(|out| from_base_units(out, decimals))
+            .context("Could not price gas in the token")?;
+
+        let period = Decimal::from(holding_days) / Decimal::from(365);
+        let mut ranked = Vec::new();
+        let mut failed = Vec::new();
+        for source in &self.sources {
+            let apy = match source.supply_apy(token).await {
+                Ok(apy) => apy,
+                Err(e) => {
+                    failed.push(YieldReport {
+                        protocol: source.name().to_string(),
+                        apy_percent: None,
+                        gas_cost: None,
+                        expected_earnings: None,
+                        net_earnings: None,
+                        net_apy_percent: None,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let gas_units = U256::from(source.entry_gas_units() + source.exit_gas_units());
+            let gas_cost = from_base_units(gas_units.saturating_mul(gas_price), 18)? * eth_in_token;
+            let earnings = amount * apy / Decimal::from(100) * period;
+            let net = earnings - gas_cost;
+            let net_apy = net / amount / period * Decimal::from(100);
+            ranked.push((
+                net,
+                YieldReport {
+                    protocol: source.name().to_string(),
+                    apy_percent: Some(apy.normalize().to_string()),
+                    gas_cost: Some(gas_cost.round_dp(6).normalize().to_string()),
+                    expected_earnings: Some(earnings.round_dp(6).normalize().to_string()),
+                    net_earnings: Some(net.round_dp(6).normalize().to_string()),
+                    net_apy_percent: Some(net_apy.round_dp(4).normalize().to_string()),
+                    error: None,
+                },
+            ));
+        }
+        ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let result = CompareYieldsResult {
+            token: params.token,
+            amount: amount.to_string(),
+            holding_days,
+            gas_price_gwei: from_base_units(gas_price, 9)?.normalize().to_string(),
+            yields: ranked
+                .into_iter()
+                .map(|(_, report)| report)
+                .chain(failed)
+                .collect(),
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,80 @@
+use super::{PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{PairingStatus, WalletConnectSigner, WALLETCONNECT_RESOURCE_URI};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct ConnectWalletTool {
+    signer: Arc<WalletConnectSigner>,
+}
+
+impl ConnectWalletTool {
+    pub fn new(signer: Arc<WalletConnectSigner>) -> Self {
+        Self { signer }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectWalletParams {
+    /// Drop the current pairing and offer a fresh one
+    #[serde(default)]
+    new_pairing: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ConnectWalletResult {
+    #[serde(flatten)]
+    status: PairingStatus,
+    /// Text to render as a QR code for the wallet to scan; the same `wc:` URI
+    qr_payload: String,
+    resource_uri: &'static str,
+}
+
+#[async_trait]
+impl Tool for ConnectWalletTool {
+    fn name(&self) -> &str {
+        "connect_wallet"
+    }
+
+    fn description(&self) -> &str {
+        "Pair the user's mobile wallet over WalletConnect v2. Returns the pairing URI (and the same text as a QR payload) to scan in the wallet, and whether a session for the server's account is approved. Once paired, every transaction the server sends is approved or rejected on the phone; no key is held by the server. Call again to check the session, or with new_pairing to start over."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "new_pairing": {
+                    "type": "boolean",
+                    "description": "Offer a fresh pairing instead of reusing the current one (default: false)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ConnectWalletParams =
+            serde_json::from_value(params).context("Invalid parameters for connect_wallet")?;
+        let pairing = if params.new_pairing {
+            self.signer.pair().await?
+        } else {
+            self.signer.pairing_or_pair().await?
+        };
+        let result = ConnectWalletResult {
+            status: self.signer.status().await?,
+            qr_payload: pairing.uri,
+            resource_uri: WALLETCONNECT_RESOURCE_URI,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
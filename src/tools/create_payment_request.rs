@@ -0,0 +1,183 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::payment_requests::{
+    eip681_uri, unix_now, PaymentRequest, PaymentRequests, DEFAULT_PAYMENT_REQUEST_TTL,
+    MAX_PAYMENT_REQUEST_TTL,
+};
+use super::units::to_base_units;
+use super::Tool;
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub struct CreatePaymentRequestTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    requests: Arc<PaymentRequests>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> CreatePaymentRequestTool<C> {
+    pub fn new(client: Arc<C>, requests: Arc<PaymentRequests>) -> Self {
+        Self {
+            client,
+            requests,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the recipient
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatePaymentRequestParams {
+    amount: String,
+    /// ERC20 to be paid in; ETH when omitted
+    #[serde(default)]
+    token: Option<String>,
+    /// Who gets paid; the server's wallet when omitted
+    #[serde(default)]
+    recipient: Option<String>,
+    #[serde(default)]
+    memo: Option<String>,
+    #[serde(default)]
+    expires_in_secs: Option<u64>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CreatePaymentRequestTool<C> {
+    fn name(&self) -> &str {
+        "create_payment_request"
+    }
+
+    fn description(&self) -> &str {
+        "Create a payment request for an amount of ETH or an ERC20 to a recipient (the server's wallet by default). Returns an EIP-681 payment URI, the same text as a QR payload for wallets to scan, and a payment-request:// MCP resource holding the request. Pass its request_id to check_payment to see whether it has been paid."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "amount": {
+                    "type": "string",
+                    "description": "Amount to request in whole tokens, e.g. '25.5'"
+                },
+                "token": {
+                    "type": "string",
+                    "description": "ERC20 token address to be paid in; omit for ETH"
+                },
+                "recipient": {
+                    "type": "string",
+                    "description": "Address or contact name to be paid (default: the server's wallet)"
+                },
+                "memo": {
+                    "type": "string",
+                    "description": "Note kept with the request, e.g. an invoice number; not part of the URI"
+                },
+                "expires_in_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": format!(
+                        "How long the request stays open (default: {}, at most {})",
+                        DEFAULT_PAYMENT_REQUEST_TTL.as_secs(),
+                        MAX_PAYMENT_REQUEST_TTL.as_secs()
+                    )
+                }
+            },
+            "required": ["amount"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CreatePaymentRequestParams = serde_json::from_value(params)
+            .context("Invalid parameters for create_payment_request")?;
+
+        let recipient = match &params.recipient {
+            Some(recipient) => {
+                resolve_address(self.book.as_deref(), recipient).context("Invalid recipient")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        if recipient.is_zero() {
+            anyhow::bail!("The zero address cannot be paid");
+        }
+        let token: Option<Address> = params
+            .token
+            .as_deref()
+            .map(|token| token.parse().context("Invalid token address"))
+            .transpose()?;
+        let ttl = params
+            .expires_in_secs
+            .unwrap_or(DEFAULT_PAYMENT_REQUEST_TTL.as_secs());
+        if ttl == 0 || ttl > MAX_PAYMENT_REQUEST_TTL.as_secs() {
+            anyhow::bail!(
+                "expires_in_secs must be between 1 and {}",
+                MAX_PAYMENT_REQUEST_TTL.as_secs()
+            );
+        }
+
+        let (symbol, decimals) = match token {
+            Some(token) => (
+                self.client
+                    .get_token_symbol(token)
+                    .await
+                    .unwrap_or_else(|_| "UNKNOWN".to_string()),
+                self.client
+                    .get_token_decimals(token)
+                    .await
+                    .context("Failed to read token decimals")?,
+            ),
+            None => ("ETH".to_string(), 18),
+        };
+        let amount = Decimal::from_str(params.amount.trim()).context("Invalid amount")?;
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("amount must be positive");
+        }
+        if amount.normalize().scale() > decimals as u32 {
+            anyhow::bail!(
+                "amount has more than the {} decimals {} supports",
+                decimals,
+                symbol
+            );
+        }
+        let amount_base_units = to_base_units(amount, decimals as u32)?;
+
+        // ETH payments leave no logs, so they are detected against this balance
+        let baseline_balance = match token {
+            Some(_) => Decimal::ZERO,
+            None => self.client.get_eth_balance(recipient).await?,
+        };
+        let chain_id = self.client.get_chain_id();
+        let uri = eip681_uri(chain_id, recipient, token, amount_base_units);
+        let now = unix_now();
+
+        let request = self.requests.insert(PaymentRequest {
+            request_id: String::new(),
+            qr_payload: uri.clone(),
+            uri,
+            resource_uri: String::new(),
+            chain_id,
+            recipient,
+            token,
+            symbol,
+            decimals,
+            amount: amount.normalize(),
+            amount_base_units,
+            memo: params.memo,
+            created_block: self.client.get_block_number().await?,
+            baseline_balance,
+            created_at: now,
+            expires_at: now + ttl,
+        });
+
+        Ok(serde_json::to_value(request)?)
+    }
+}
@@ -0,0 +1,325 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::recipients::RecipientHistory;
+use super::scam_list::ScamList;
+use super::units::{from_base_units, to_base_units};
+use super::{ExecutePlanTool, Notional, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Sablier stores durations as uint40
+const MAX_STREAM_DURATION_SECS: u64 = (1 << 40) - 1;
+
+pub struct CreateStreamTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    contract: Address,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> CreateStreamTool<C> {
+    /// Stream through the Sablier V2 LockupLinear `contract`; without `execution_enabled`
+    /// only dry runs are allowed
+    pub fn new(client: Arc<C>, contract: Address, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            contract,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the recipient
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.executor = self.executor.with_address_book(Arc::clone(&book));
+        self.book = Some(book);
+        self
+    }
+
+    /// Flag recipients the wallet has never paid, and record new ones
+    pub fn with_recipient_history(mut self, recipients: Arc<RecipientHistory>) -> Self {
+        self.executor = self.executor.with_recipient_history(recipients);
+        self
+    }
+
+    /// Refuse recipients on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.executor = self.executor.with_scam_list(scams);
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live runs must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateStreamParams {
+    to: String,
+    token: String,
+    amount: String,
+    duration_secs: u64,
+    #[serde(default)]
+    cliff_secs: u64,
+    #[serde(default = "default_cancelable")]
+    cancelable: bool,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    confirm_new_recipients: bool,
+    #[serde(default)]
+    override_gas_reserve: bool,
+    #[serde(default)]
+    override_scam_list: bool,
+}
+
+fn default_cancelable() -> bool {
+    true
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct CreateStreamResult {
+    contract: String,
+    to: String,
+    token: String,
+    symbol: String,
+    amount: String,
+    per_day: String,
+    duration_secs: u64,
+    cliff_secs: u64,
+    cancelable: bool,
+    balance: String,
+    /// The approve (when the contract's allowance is short) and stream steps, as input for execute_plan
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for CreateStreamTool<C> {
+    fn name(&self) -> &str {
+        "create_stream"
+    }
+
+    fn description(&self) -> &str {
+        "Stream an ERC20 to a recipient through Sablier V2: the amount is locked in a LockupLinear stream and released linearly over the duration from when it is mined, with nothing withdrawable before the optional cliff. Approves the contract first when its allowance is short. Cancelable streams can be stopped with cancel_stream, refunding what has not streamed. Dry run by default; live runs follow execute_plan's gas reserve and recipient checks."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn notional(&self, params: &Value) -> Option<Notional> {
+        Notional::from_params(params, "token", "amount")
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "to": {
+                    "type": "string",
+                    "description": "Recipient address or contact name"
+                },
+                "token": {
+                    "type": "string",
+                    "description": "ERC20 token address to stream"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Total to stream in whole tokens, e.g. '12000'"
+                },
+                "duration_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Seconds the stream runs for, e.g. 31536000 for a year"
+                },
+                "cliff_secs": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Seconds before anything can be withdrawn, after which the streamed share unlocks at once (default: 0)"
+                },
+                "cancelable": {
+                    "type": "boolean",
+                    "description": "Let the wallet cancel the stream later and recover the unstreamed part (default: true)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transactions without sending (default: true). Live execution must be enabled on the server"
+                },
+                "confirm_new_recipients": {
+                    "type": "boolean",
+                    "description": "Stream to a recipient the wallet has never paid, as for execute_plan (default: false)"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                },
+                "override_scam_list": {
+                    "type": "boolean",
+                    "description": "Stream to an address on the scam list anyway, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["to", "token", "amount", "duration_secs"]
+        })
+    }
+
+    // The same go-ahead execute_plan asks for before paying addresses outside the book
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        self.executor.confirmation_prompt(&json!({
+            "dry_run": params.get("dry_run").cloned().unwrap_or(Value::Bool(true)),
+            "plan": {
+                "steps": [{ "action": "create_stream", "to": params.get("to")? }]
+            }
+        }))
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: CreateStreamParams =
+            serde_json::from_value(params).context("Invalid parameters for create_stream")?;
+
+        let wallet = self.client.get_wallet_address();
+        let token: Address = params
+            .token
+            .trim()
+            .parse()
+            .context("Invalid token address")?;
+        let to = resolve_address(self.book.as_deref(), &params.to).context("Invalid recipient")?;
+        if to.is_zero() || to == self.contract || to == token {
+            anyhow::bail!("{:?} cannot receive a stream", to);
+        }
+        if params.duration_secs == 0 || params.duration_secs > MAX_STREAM_DURATION_SECS {
+            anyhow::bail!(
+                "duration_secs must be between 1 and {}",
+                MAX_STREAM_DURATION_SECS
+            );
+        }
+        if params.cliff_secs >= params.duration_secs {
+            anyhow::bail!("cliff_secs must be shorter than duration_secs");
+        }
+
+        let (balance, decimals) = self
+            .client
+            .get_token_balance(token, wallet)
+            .await
+            .context("Failed to read token balance")?;
+        let symbol = self
+            .client
+            .get_token_symbol(token)
+            .await
+            .unwrap_or_else(|_| "UNKNOWN".to_string());
+        let amount = Decimal::from_str(params.amount.trim()).context("Invalid amount")?;
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("amount must be positive");
+        }
+        if amount.normalize().scale() > decimals as u32 {
+            anyhow::bail!(
+                "amount has more than the {} decimals {} supports",
+                decimals,
+                symbol
+            );
+        }
+        if amount > balance {
+            anyhow::bail!(
+                "The stream needs {} {} but the wallet holds {}",
+                amount.normalize(),
+                symbol,
+                balance.normalize()
+            );
+        }
+        let base_amount = to_base_units(amount, decimals as u32)?;
+        if base_amount > U256::from(u128::MAX) {
+            anyhow::bail!("amount is too large for a Sablier stream");
+        }
+        let per_day = from_base_units(
+            base_amount * U256::from(24 * 60 * 60) / U256::from(params.duration_secs),
+            decimals as u32,
+        )?;
+
+        let mut plan = Plan::default();
+        let approval = self
+            .client
+            .simulate_approval(token, wallet, self.contract, base_amount)
+            .await?;
+        if approval.approval_required {
+            plan.steps.push(PlanStep::Approve {
+                token,
+                spender: self.contract,
+                amount: base_amount,
+            });
+        }
+        plan.steps.push(PlanStep::CreateStream {
+            contract: self.contract,
+            token,
+            to,
+            amount: base_amount,
+            cliff_secs: params.cliff_secs,
+            duration_secs: params.duration_secs,
+            cancelable: params.cancelable,
+        });
+
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "confirm_new_recipients": params.confirm_new_recipients,
+                "override_gas_reserve": params.override_gas_reserve,
+                "override_scam_list": params.override_scam_list,
+            }))
+            .await?;
+
+        let result = CreateStreamResult {
+            contract: format!("{:?}", self.contract),
+            to: format!("{:?}", to),
+            token: format!("{:?}", token),
+            symbol,
+            amount: amount.normalize().to_string(),
+            per_day: per_day.normalize().to_string(),
+            duration_secs: params.duration_secs,
+            cliff_secs: params.cliff_secs,
+            cancelable: params.cancelable,
+            balance: balance.normalize().to_string(),
+            plan,
+            execution,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
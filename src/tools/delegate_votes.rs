@@ -0,0 +1,192 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::get_delegation::DelegationStatus;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct DelegateVotesTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> DelegateVotesTool<C> {
+    /// Without `execution_enabled` only dry runs are allowed
+    pub fn new(client: Arc<C>, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the delegatee
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live delegations must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DelegateVotesParams {
+    token: String,
+    delegatee: String,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    override_gas_reserve: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct DelegateVotesResult {
+    token: Address,
+    symbol: String,
+    balance: String,
+    /// The delegation being replaced
+    current_status: DelegationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_delegate: Option<Address>,
+    delegatee: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegatee_name: Option<String>,
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for DelegateVotesTool<C> {
+    fn name(&self) -> &str {
+        "delegate_votes"
+    }
+
+    fn description(&self) -> &str {
+        "Delegate the server wallet's voting power on an ERC20Votes governance token (UNI, ENS, COMP, ...) to an address or contact, or to 'self' to vote directly. Delegation moves votes, not tokens, and covers tokens received later too; it only counts for proposals whose snapshot comes after it. Dry run by default; live runs need execution enabled on the server."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Governance token address"
+                },
+                "delegatee": {
+                    "type": "string",
+                    "description": "Address or contact name to delegate to, or 'self'"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transaction without sending (default: true). Live execution must be enabled on the server"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["token", "delegatee"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: DelegateVotesParams =
+            serde_json::from_value(params).context("Invalid parameters for delegate_votes")?;
+        let wallet = self.client.get_wallet_address();
+        let token: Address = params
+            .token
+            .trim()
+            .parse()
+            .context("Invalid token address")?;
+        let delegatee = if params.delegatee.trim().eq_ignore_ascii_case("self") {
+            wallet
+        } else {
+            resolve_address(self.book.as_deref(), &params.delegatee).context("Invalid delegatee")?
+        };
+        if delegatee.is_zero() {
+            anyhow::bail!("Votes cannot be delegated to the zero address");
+        }
+
+        let current = self.client.get_delegation(token, wallet).await?;
+        if current.delegate == Some(delegatee) {
+            anyhow::bail!("The wallet already delegates to {:?}", delegatee);
+        }
+        let (balance, _) = self
+            .client
+            .get_token_balance(token, wallet)
+            .await
+            .context("Failed to read token balance")?;
+        let symbol = self
+            .client
+            .get_token_symbol(token)
+            .await
+            .unwrap_or_else(|_| "UNKNOWN".to_string());
+
+        let mut plan = Plan::default();
+        plan.steps.push(PlanStep::Delegate { token, delegatee });
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "override_gas_reserve": params.override_gas_reserve,
+            }))
+            .await?;
+
+        let result = DelegateVotesResult {
+            token,
+            symbol,
+            balance: balance.normalize().to_string(),
+            current_status: DelegationStatus::of(wallet, current.delegate),
+            current_delegate: current.delegate,
+            delegatee,
+            delegatee_name: self.book.as_ref().and_then(|book| book.name_of(delegatee)),
+            plan,
+            execution,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,211 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::interfaces::{
+    call, is_erc777, supports_interface, ERC1155_INTERFACE, ERC165_INTERFACE, ERC721_INTERFACE,
+};
+use super::read_storage::{detect_proxy, ProxyInfo};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{minimal_proxy_target, EthereumClientTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::Token;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct DetectInterfacesTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> DetectInterfacesTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names as the contract
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Whether every one of `calls` returns at least a word
+    async fn answers(&self, contract: Address, calls: &[(&str, &[Token])]) -> bool {
+        for (signature, args) in calls {
+            match call(self.client.as_ref(), contract, signature, args).await {
+                Ok(output) if output.len() >= 32 => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// How an interface was detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Evidence {
+    /// `supportsInterface` said so
+    Erc165,
+    /// The interface's view functions answered
+    Calls,
+    /// Registered in the ERC-1820 registry
+    Erc1820,
+}
+
+#[derive(Debug, Serialize)]
+struct InterfaceCheck {
+    interface: &'static str,
+    supported: bool,
+    via: Evidence,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectInterfacesParams {
+    address: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DetectInterfacesResult {
+    address: Address,
+    interfaces: Vec<InterfaceCheck>,
+    /// Names of the supported interfaces, for a quick read
+    supported: Vec<&'static str>,
+    /// `owner()`, when the contract is Ownable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<ProxyInfo>,
+    /// Target of an EIP-1167 minimal proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clone_of: Option<Address>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for DetectInterfacesTool<C> {
+    fn name(&self) -> &str {
+        "detect_interfaces"
+    }
+
+    fn description(&self) -> &str {
+        "Probe a contract for the interfaces it implements: ERC-165, ERC-20, ERC-721, ERC-1155, ERC-777, ERC-4626 and ERC-2612 permit, whether it is Ownable (and by whom), and whether it is a proxy (EIP-1967, beacon, EIP-1822, legacy OpenZeppelin) or an EIP-1167 clone. Useful to triage an unknown contract before calling it."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Contract address or contact name"
+                }
+            },
+            "required": ["address"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: DetectInterfacesParams =
+            serde_json::from_value(params).context("Invalid parameters for detect_interfaces")?;
+        let address =
+            resolve_address(self.book.as_deref(), &params.address).context("Invalid address")?;
+        let code = self.client.get_code(address, None).await?;
+        if code.is_empty() {
+            anyhow::bail!("No contract code at {:?}", address);
+        }
+        let client = self.client.as_ref();
+        let zero = Token::Address(Address::zero());
+
+        let interfaces = vec![
+            InterfaceCheck {
+                interface: "erc165",
+                supported: supports_interface(client, address, ERC165_INTERFACE).await,
+                via: Evidence::Erc165,
+            },
+            InterfaceCheck {
+                interface: "erc20",
+                supported: self
+                    .answers(
+                        address,
+                        &[
+                            ("totalSupply()", &[]),
+                            ("allowance(address,address)", &[zero.clone(), zero.clone()]),
+                        ],
+                    )
+                    .await,
+                via: Evidence::Calls,
+            },
+            InterfaceCheck {
+                interface: "erc721",
+                supported: supports_interface(client, address, ERC721_INTERFACE).await,
+                via: Evidence::Erc165,
+            },
+            InterfaceCheck {
+                interface: "erc1155",
+                supported: supports_interface(client, address, ERC1155_INTERFACE).await,
+                via: Evidence::Erc165,
+            },
+            InterfaceCheck {
+                interface: "erc777",
+                supported: is_erc777(client, address).await,
+                via: Evidence::Erc1820,
+            },
+            InterfaceCheck {
+                interface: "erc4626",
+                supported: self
+                    .answers(
+                        address,
+                        &[
+                            ("asset()", &[]),
+                            ("convertToAssets(uint256)", &[Token::Uint(U256::one())]),
+                        ],
+                    )
+                    .await,
+                via: Evidence::Calls,
+            },
+            InterfaceCheck {
+                interface: "erc2612",
+                supported: self
+                    .answers(
+                        address,
+                        &[("nonces(address)", &[zero]), ("DOMAIN_SEPARATOR()", &[])],
+                    )
+                    .await,
+                via: Evidence::Calls,
+            },
+        ];
+
+        let owner = call(client, address, "owner()", &[])
+            .await
+            .ok()
+            .filter(|output| output.len() >= 32 && output[..12].iter().all(|byte| *byte == 0))
+            .map(|output| Address::from_slice(&output[12..32]));
+        let clone_of = minimal_proxy_target(&code);
+        let proxy = match clone_of {
+            Some(_) => None,
+            None => detect_proxy(client, address, None).await?,
+        };
+
+        let mut supported: Vec<&'static str> = interfaces
+            .iter()
+            .filter(|check| check.supported)
+            .map(|check| check.interface)
+            .collect();
+        if owner.is_some() {
+            supported.push("ownable");
+        }
+        let result = DetectInterfacesResult {
+            address,
+            interfaces,
+            supported,
+            owner,
+            proxy,
+            clone_of,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,333 @@
+use super::get_market_stats::BLOCKS_PER_HOUR;
+use super::units::from_base_units;
+use crate::ethereum::{
+    EthereumClientTrait, FactoryKind, PoolActivitySource, PoolState, ReferencePriceSource,
+    SwapAmounts, V3PoolState,
+};
+use anyhow::Result;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// How often pools are read again by default
+pub const DEFAULT_DEX_STATS_REFRESH: Duration = Duration::from_secs(5 * 60);
+
+/// Pools tracked at once, each costing a log scan per refresh
+pub const MAX_DEX_STATS_POOLS: usize = 50;
+
+/// Blocks volume is summed over
+pub const DEX_STATS_WINDOW_BLOCKS: u64 = 24 * BLOCKS_PER_HOUR;
+
+/// Busy, long-lived mainnet pools tracked when none are configured
+pub const DEFAULT_DEX_STATS_POOLS: &[(&str, &str)] = &[
+    (
+        "usdc-weth-v3-005",
+        "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640",
+    ),
+    (
+        "weth-usdt-v3-03",
+        "0x4e68Ccd3E89f51C3074ca5072bbAC773960dFa36",
+    ),
+    (
+        "wbtc-weth-v3-03",
+        "0xCBCdF9626bC03E24f779434178A73a0B4bad62eD",
+    ),
+    ("usdc-weth-v2", "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+];
+
+/// Figures for one pool as of its last refresh
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub name: String,
+    pub pool: Address,
+    /// `v2` or `v3`
+    pub version: &'static str,
+    /// `TOKEN0/TOKEN1`
+    pub pair: String,
+    pub fee_percent: Decimal,
+    /// Pooled amounts of token0 and token1
+    pub balance0: Decimal,
+    pub balance1: Decimal,
+    /// Both sides at USD prices; `None` when a side could not be priced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tvl_usd: Option<Decimal>,
+    /// token0 priced in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    pub swaps_24h: usize,
+    pub volume0_24h: Decimal,
+    pub volume1_24h: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_24h_usd: Option<Decimal>,
+    /// Volume times the fee tier, earned by liquidity providers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees_24h_usd: Option<Decimal>,
+    /// Block the figures were read at
+    pub block_number: u64,
+    /// Unix seconds
+    pub refreshed_at: u64,
+}
+
+/// A pool's last read state and the swaps still inside the window
+#[derive(Debug, Clone)]
+struct TrackedPool {
+    state: PoolState,
+    symbols: (String, String),
+    decimals: (u32, u32),
+    swaps: VecDeque<SwapAmounts>,
+    scanned_to: u64,
+    stats: PoolStats,
+}
+
+/// Volume and TVL of configured pools, read from their reserves and Swap events
+/// and refreshed in the background so lookups never wait on the chain. Each
+/// refresh only scans blocks since the last one; a pool that fails to refresh
+/// keeps its last figures.
+pub struct DexStats {
+    client: Arc<dyn EthereumClientTrait>,
+    source: Arc<dyn PoolActivitySource>,
+    reference_prices: Option<Arc<dyn ReferencePriceSource>>,
+    pools: Vec<(String, Address)>,
+    tracked: Mutex<HashMap<Address, TrackedPool>>,
+    /// Why each pool's last refresh failed
+    errors: Mutex<HashMap<Address, String>>,
+}
+
+impl DexStats {
+    pub fn new(
+        client: Arc<dyn EthereumClientTrait>,
+        source: Arc<dyn PoolActivitySource>,
+        pools: Vec<(String, Address)>,
+    ) -> Self {
+        Self {
+            client,
+            source,
+            reference_prices: None,
+            pools,
+            tracked: Mutex::new(HashMap::new()),
+            errors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Value pools in USD with these prices; without them only token amounts are reported
+    pub fn with_reference_prices(mut self, prices: Arc<dyn ReferencePriceSource>) -> Self {
+        self.reference_prices = Some(prices);
+        self
+    }
+
+    /// Configured pools as (name, address)
+    pub fn pools(&self) -> &[(String, Address)] {
+        &self.pools
+    }
+
+    /// Figures from `pool`'s last successful refresh
+    pub fn stats(&self, pool: Address) -> Option<PoolStats> {
+        self.tracked
+            .lock()
+            .unwrap()
+            .get(&pool)
+            .map(|tracked| tracked.stats.clone())
+    }
+
+    /// Why `pool`'s last refresh failed, if it did
+    pub fn error(&self, pool: Address) -> Option<String> {
+        self.errors.lock().unwrap().get(&pool).cloned()
+    }
+
+    async fn usd_price(&self, token: Address) -> Option<Decimal> {
+        let prices = self.reference_prices.as_ref()?;
+        match prices.usd_price(token).await {
+            Ok(price) => price.map(|price| price.usd_price),
+            Err(e) => {
+                warn!("No USD price for {:?}: {:#}", token, e);
+                None
+            }
+        }
+    }
+
+    async fn refresh_pool(
+        &self,
+        name: &str,
+        pool: Address,
+        latest: u64,
+        previous: Option<TrackedPool>,
+    ) -> Result<TrackedPool> {
+        let state = self.source.pool_state(pool).await?;
+        let window_start = latest.saturating_sub(DEX_STATS_WINDOW_BLOCKS) + 1;
+
+        // Carry over swaps already scanned while they are still in the window
+        let (symbols, decimals, mut swaps, from_block) = match previous {
+            Some(previous) if previous.state.token0 == state.token0 => {
+                let from_block = (previous.scanned_to + 1).max(window_start);
+                (
+                    previous.symbols,
+                    previous.decimals,
+                    previous.swaps,
+                    from_block,
+                )
+            }
+            _ => (
+                (
+                    self.client.get_token_symbol(state.token0).await?,
+                    self.client.get_token_symbol(state.token1).await?,
+                ),
+                (
+                    self.client.get_token_decimals(state.token0).await? as u32,
+                    self.client.get_token_decimals(state.token1).await? as u32,
+                ),
+                VecDeque::new(),
+                window_start,
+            ),
+        };
+        if from_block <= latest {
+            swaps.extend(
+                self.source
+                    .swaps(pool, state.kind, from_block, latest)
+                    .await?,
+            );
+        }
+        while swaps
+            .front()
+            .is_some_and(|swap| swap.block_number < window_start)
+        {
+            swaps.pop_front();
+        }
+
+        let balance0 = from_base_units(state.balance0, decimals.0)?;
+        let balance1 = from_base_units(state.balance1, decimals.1)?;
+        let price = match state.sqrt_price_x96 {
+            Some(sqrt_price_x96) => {
+                let sqrt_price = V3PoolState {
+                    sqrt_price_x96,
+                    ..Default::default()
+                }
+                .sqrt_price();
+                Decimal::from_f64(
+                    sqrt_price * sqrt_price * 10f64.powi(decimals.0 as i32 - decimals.1 as i32),
+                )
+            }
+            None => (!balance0.is_zero()).then(|| balance1 / balance0),
+        };
+        // A side with no feed is priced through the other side and the pool's price
+        let (usd0, usd1) = match (
+            self.usd_price(state.token0).await,
+            self.usd_price(state.token1).await,
+        ) {
+            (Some(usd0), None) => (Some(usd0), price.filter(|p| !p.is_zero()).map(|p| usd0 / p)),
+            (None, Some(usd1)) => (price.map(|p| usd1 * p), Some(usd1)),
+            prices => prices,
+        };
+
+        let volume0 = from_base_units(
+            swaps
+                .iter()
+                .fold(U256::zero(), |sum, swap| sum + swap.amount0),
+            decimals.0,
+        )?;
+        let volume1 = from_base_units(
+            swaps
+                .iter()
+                .fold(U256::zero(), |sum, swap| sum + swap.amount1),
+            decimals.1,
+        )?;
+        let volume_usd = usd0
+            .map(|usd| volume0 * usd)
+            .or_else(|| usd1.map(|usd| volume1 * usd));
+        let fee_percent = Decimal::new(state.fee as i64, 4);
+
+        let stats = PoolStats {
+            name: name.to_string(),
+            pool,
+            version: match state.kind {
+                FactoryKind::V2 => "v2",
+                FactoryKind::V3 => "v3",
+            },
+            pair: format!("{}/{}", symbols.0, symbols.1),
+            fee_percent: fee_percent.normalize(),
+            balance0,
+            balance1,
+            tvl_usd: usd0
+                .zip(usd1)
+                .map(|(usd0, usd1)| (balance0 * usd0 + balance1 * usd1).round_dp(2).normalize()),
+            price: price.map(|price| price.round_dp(8).normalize()),
+            swaps_24h: swaps.len(),
+            volume0_24h: volume0,
+            volume1_24h: volume1,
+            volume_24h_usd: volume_usd.map(|usd| usd.round_dp(2).normalize()),
+            fees_24h_usd: volume_usd.map(|usd| {
+                (usd * fee_percent / Decimal::ONE_HUNDRED)
+                    .round_dp(2)
+                    .normalize()
+            }),
+            block_number: latest,
+            refreshed_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        Ok(TrackedPool {
+            state,
+            symbols,
+            decimals,
+            swaps,
+            scanned_to: latest,
+            stats,
+        })
+    }
+
+    /// Read every pool, keeping the last figures of any that fail
+    pub async fn refresh(&self) {
+        let latest = match self.client.get_block_number().await {
+            Ok(latest) => latest,
+            Err(e) => {
+                warn!("DEX stats not refreshed: {:#}", e);
+                return;
+            }
+        };
+        for (name, pool) in &self.pools {
+            let previous = self.tracked.lock().unwrap().get(pool).cloned();
+            match self.refresh_pool(name, *pool, latest, previous).await {
+                Ok(tracked) => {
+                    self.errors.lock().unwrap().remove(pool);
+                    self.tracked.lock().unwrap().insert(*pool, tracked);
+                }
+                Err(e) => {
+                    warn!("DEX stats for {} not refreshed: {:#}", name, e);
+                    self.errors
+                        .lock()
+                        .unwrap()
+                        .insert(*pool, format!("{:#}", e));
+                }
+            }
+        }
+        let failed = self.errors.lock().unwrap().len();
+        info!(
+            "DEX stats refreshed at block {}: {} of {} pools current",
+            latest,
+            self.pools.len() - failed,
+            self.pools.len()
+        );
+    }
+
+    /// Refresh the pools now and then every `every` until `stop` is set
+    pub fn spawn(
+        self: Arc<Self>,
+        every: Duration,
+        mut stop: watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => self.refresh().await,
+                    // Set, or the server that started the job is gone
+                    _ = stop.changed() => break,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,280 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::units::from_base_units;
+use super::Tool;
+use crate::ethereum::{EthereumClientTrait, PendingTransaction, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+// Nodes want replacements to outbid the original by at least 10%
+const REPLACEMENT_BUMP_PERCENT: u64 = 15;
+
+// Headroom over the current gas price so a replacement is not stuck as well
+const GAS_PRICE_HEADROOM_PERCENT: u64 = 25;
+
+// 1 gwei
+const MIN_PRIORITY_FEE: u64 = 1_000_000_000;
+
+// Unknown pending nonces reported when the node has no txpool API
+const MAX_UNKNOWN_NONCES: u64 = 32;
+
+pub struct DiagnoseWalletTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> DiagnoseWalletTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names from `book` in place of the wallet address
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DiagnoseWalletParams {
+    #[serde(default)]
+    address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum WalletStatus {
+    /// Nothing waiting in the mempool
+    Healthy,
+    /// Transactions waiting with competitive fees
+    Pending,
+    /// Underpriced transactions or a nonce gap
+    Stuck,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TransactionState {
+    Pending,
+    /// Bids below the current gas price
+    Underpriced,
+    /// Waiting behind a missing nonce
+    Queued,
+}
+
+#[derive(Debug, Serialize)]
+struct PendingReport {
+    hash: String,
+    nonce: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    max_fee_gwei: String,
+    state: TransactionState,
+}
+
+#[derive(Debug, Serialize)]
+struct Recommendation {
+    nonce: u64,
+    /// speed_up, cancel or fill_gap
+    action: String,
+    reason: String,
+    /// Input for execute_plan; only for the server's own wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<Plan>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnoseWalletResult {
+    address: String,
+    status: WalletStatus,
+    latest_nonce: u64,
+    pending_nonce: u64,
+    /// Sent transactions not yet mined, by nonce
+    pending_count: u64,
+    mempool_available: bool,
+    gas_price_gwei: String,
+    transactions: Vec<PendingReport>,
+    recommendations: Vec<Recommendation>,
+}
+
+fn bump(fee: U256, percent: u64) -> U256 {
+    (fee * (100 + percent) + 99) / 100
+}
+
+/// Fees that outbid `original` and clear the current gas price
+fn replacement_fees(original: Option<&PendingTransaction>, gas_price: U256) -> (U256, U256) {
+    let floor = bump(gas_price, GAS_PRICE_HEADROOM_PERCENT);
+    let (max_fee, priority_fee) = match original {
+        Some(tx) => (
+            bump(tx.max_fee_per_gas, REPLACEMENT_BUMP_PERCENT).max(floor),
+            bump(
+                tx.max_priority_fee_per_gas.unwrap_or_default(),
+                REPLACEMENT_BUMP_PERCENT,
+            ),
+        ),
+        None => (floor, U256::zero()),
+    };
+    let priority_fee = priority_fee.max(U256::from(MIN_PRIORITY_FEE)).min(max_fee);
+    (max_fee, priority_fee)
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for DiagnoseWalletTool<C> {
+    fn name(&self) -> &str {
+        "diagnose_wallet"
+    }
+
+    fn description(&self) -> &str {
+        "Diagnose a wallet's pending transactions: latest vs pending nonce, transactions waiting in the mempool (where the node exposes it), underpriced or gap-blocked ones, and speed-up/cancel recommendations with plans for execute_plan."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Wallet address or contact name to diagnose (default: the server's wallet). Remediation plans are only returned for the server's wallet"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: DiagnoseWalletParams =
+            serde_json::from_value(params).context("Invalid parameters for diagnose_wallet")?;
+
+        let wallet = self.client.get_wallet_address();
+        let address: Address = match &params.address {
+            Some(addr) => {
+                resolve_address(self.book.as_deref(), addr).context("Invalid wallet address")?
+            }
+            None => wallet,
+        };
+        let own_wallet = address == wallet;
+
+        let (latest_nonce, pending_nonce) = self.client.get_nonces(address).await?;
+        let gas_price = self.client.get_gas_price().await?;
+        let mempool = self.client.get_pending_transactions(address).await?;
+
+        let mut transactions = Vec::new();
+        let mut recommendations = Vec::new();
+        let mut stuck = false;
+
+        let plan_for = |step: PlanStep| own_wallet.then(|| Plan { steps: vec![step] });
+        let cancel_step = |nonce: u64, original: Option<&PendingTransaction>| {
+            let (max_fee_per_gas, max_priority_fee_per_gas) = replacement_fees(original, gas_price);
+            PlanStep::Cancel {
+                nonce,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            }
+        };
+
+        match &mempool {
+            Some(pool) => {
+                let pool: Vec<_> = pool.iter().filter(|tx| tx.nonce >= latest_nonce).collect();
+                for tx in &pool {
+                    let state = if tx.queued {
+                        TransactionState::Queued
+                    } else if tx.max_fee_per_gas < gas_price {
+                        TransactionState::Underpriced
+                    } else {
+                        TransactionState::Pending
+                    };
+
+                    if let TransactionState::Underpriced = state {
+                        stuck = true;
+                        let (max_fee_per_gas, max_priority_fee_per_gas) =
+                            replacement_fees(Some(tx), gas_price);
+                        recommendations.push(Recommendation {
+                            nonce: tx.nonce,
+                            action: "speed_up".to_string(),
+                            reason: format!(
+                                "Bids {} gwei, below the current {} gwei; re-send with higher fees, or cancel it instead",
+                                from_base_units(tx.max_fee_per_gas, 9)?.normalize(),
+                                from_base_units(gas_price, 9)?.normalize()
+                            ),
+                            plan: plan_for(PlanStep::SpeedUp {
+                                nonce: tx.nonce,
+                                to: tx.to,
+                                data: tx.input.clone(),
+                                value: tx.value,
+                                max_fee_per_gas,
+                                max_priority_fee_per_gas,
+                            }),
+                        });
+                    }
+
+                    transactions.push(PendingReport {
+                        hash: format!("{:?}", tx.hash),
+                        nonce: tx.nonce,
+                        to: tx.to.map(|to| format!("{:?}", to)),
+                        max_fee_gwei: from_base_units(tx.max_fee_per_gas, 9)?
+                            .normalize()
+                            .to_string(),
+                        state,
+                    });
+                }
+
+                // Queued transactions wait for every lower nonce to be used
+                if let Some(highest_queued) =
+                    pool.iter().filter(|tx| tx.queued).map(|tx| tx.nonce).max()
+                {
+                    let known: BTreeSet<u64> = pool.iter().map(|tx| tx.nonce).collect();
+                    for nonce in (latest_nonce..highest_queued).filter(|n| !known.contains(n)) {
+                        stuck = true;
+                        recommendations.push(Recommendation {
+                            nonce,
+                            action: "fill_gap".to_string(),
+                            reason: format!(
+                                "Nonce {} was never sent, so queued transactions behind it cannot be mined",
+                                nonce
+                            ),
+                            plan: plan_for(cancel_step(nonce, None)),
+                        });
+                    }
+                }
+            }
+            None => {
+                let unknown_end = pending_nonce.min(latest_nonce + MAX_UNKNOWN_NONCES);
+                for nonce in latest_nonce..unknown_end {
+                    recommendations.push(Recommendation {
+                        nonce,
+                        action: "cancel".to_string(),
+                        reason: "Pending but the RPC does not expose its mempool; cancel if it stays unmined. The cancel only lands if it outbids the original by 10%".to_string(),
+                        plan: plan_for(cancel_step(nonce, None)),
+                    });
+                }
+            }
+        }
+        recommendations.sort_by_key(|r| r.nonce);
+
+        let pending_count = pending_nonce.saturating_sub(latest_nonce);
+        let status = if stuck {
+            WalletStatus::Stuck
+        } else if pending_count > 0 || !transactions.is_empty() {
+            WalletStatus::Pending
+        } else {
+            WalletStatus::Healthy
+        };
+
+        let result = DiagnoseWalletResult {
+            address: format!("{:?}", address),
+            status,
+            latest_nonce,
+            pending_nonce,
+            pending_count,
+            mempool_available: mempool.is_some(),
+            gas_price_gwei: from_base_units(gas_price, 9)?.normalize().to_string(),
+            transactions,
+            recommendations,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
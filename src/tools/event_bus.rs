@@ -0,0 +1,108 @@
+use super::alerts::{AlertSeverity, AlertSink};
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Events held for subscribers that fall behind, by default
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 1_024;
+
+/// Something that happened, as published to every subscriber
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub topic: String,
+    pub severity: AlertSeverity,
+    pub data: Value,
+}
+
+/// Carries transactions, alerts and policy violations from the tools and jobs that
+/// raise them to every subscriber, such as MCP notifications, webhooks and the log.
+///
+/// Publishers never wait on subscribers: each subscriber runs as its own task, and
+/// one that falls more than the bus's capacity behind skips the events it missed.
+/// Subscriber tasks end once the bus and every clone of it are dropped.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<Event>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUS_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event, returning how many subscribers will receive it
+    pub fn publish(&self, topic: &str, severity: AlertSeverity, data: Value) -> usize {
+        let event = Event {
+            topic: topic.to_string(),
+            severity,
+            data,
+        };
+        // Fails only when nobody is subscribed
+        self.sender.send(Arc::new(event)).unwrap_or(0)
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Event>> {
+        self.sender.subscribe()
+    }
+
+    /// Deliver every event published from now on to `sink`, in order, from a task
+    /// of its own. `name` identifies the subscriber in logs.
+    pub fn subscribe_sink(&self, name: &str, sink: Arc<dyn AlertSink>) -> JoinHandle<()> {
+        let mut events = self.subscribe();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        sink.send(&event.topic, event.severity, &event.data).await;
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(subscriber = name, missed, "Event subscriber fell behind");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[async_trait]
+impl AlertSink for EventBus {
+    /// Publish the alert, returning how many subscribers will receive it
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+        self.publish(topic, severity, alert.clone())
+    }
+}
+
+/// Writes every event to the log, as an audit trail of what the server did
+pub struct EventLog;
+
+#[async_trait]
+impl AlertSink for EventLog {
+    async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+        let event = alert.get("event").and_then(Value::as_str).unwrap_or(topic);
+        match severity {
+            AlertSeverity::Info => info!(topic, data = %alert, "event {}", event),
+            AlertSeverity::Warning | AlertSeverity::Critical => {
+                warn!(topic, ?severity, data = %alert, "event {}", event)
+            }
+        }
+        1
+    }
+}
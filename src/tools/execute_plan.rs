@@ -0,0 +1,597 @@
+use super::address_book::{resolve_address, resolve_plan_addresses, AddressBook};
+use super::alerts::{AlertSeverity, AlertSink};
+use super::gas_reserve::{max_spend, GasReserve};
+use super::recipients::RecipientHistory;
+use super::scam_list::ScamList;
+use super::units::to_base_units;
+use super::{PermissionTier, Tool, ToolCategory};
+use crate::ethereum::escalation::{DEFAULT_BUMP_PERCENT, DEFAULT_ESCALATION_AFTER_BLOCKS};
+use crate::ethereum::{
+    EthereumClientTrait, FeeReplacement, GasEscalation, Plan, PlanStep, Sponsorship,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Swaps must be mined within this many seconds of the plan starting
+const SWAP_DEADLINE_SECS: u64 = 20 * 60;
+
+/// Alert topic of transactions sent by live runs, confirmed or failed
+pub const TRANSACTION_TOPIC: &str = "transactions";
+
+pub struct ExecutePlanTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    execution_enabled: bool,
+    gas_escalation: Option<GasEscalation>,
+    book: Option<Arc<AddressBook>>,
+    recipients: Option<Arc<RecipientHistory>>,
+    scams: Option<Arc<ScamList>>,
+    gas_reserve: GasReserve,
+    alerts: Option<Arc<dyn AlertSink>>,
+}
+
+impl<C: EthereumClientTrait> ExecutePlanTool<C> {
+    /// `execution_enabled` gates sending; without it only dry runs are allowed
+    pub fn new(client: Arc<C>, execution_enabled: bool) -> Self {
+        Self {
+            client,
+            execution_enabled,
+            gas_escalation: None,
+            book: None,
+            recipients: None,
+            scams: None,
+            gas_reserve: GasReserve::default(),
+            alerts: None,
+        }
+    }
+
+    /// ETH that live runs must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.gas_reserve = gas_reserve;
+        self
+    }
+
+    /// Push every transaction a live run sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Accept contact names from `book` for plan recipients and spenders
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Flag transfers to recipients the wallet has never paid, and record new payments
+    pub fn with_recipient_history(mut self, recipients: Arc<RecipientHistory>) -> Self {
+        self.recipients = Some(recipients);
+        self
+    }
+
+    /// Refuse steps that pay or approve addresses on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.scams = Some(scams);
+        self
+    }
+
+    /// Transfer recipients from `start_step` on that the wallet has never paid. A failed
+    /// history lookup counts as never paid.
+    async fn first_time_recipients(
+        &self,
+        plan: &Plan,
+        start_step: usize,
+        wallet: Address,
+    ) -> Result<Vec<Address>> {
+        let Some(history) = &self.recipients else {
+            return Ok(Vec::new());
+        };
+        let recipients: Vec<Address> = plan.steps[start_step..]
+            .iter()
+            .flat_map(PlanStep::recipients)
+            .collect();
+        if recipients.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let head = self.client.get_block_number().await?;
+        let mut first_time = Vec::new();
+        for to in recipients {
+            if !first_time.contains(&to)
+                && !history.is_known(wallet, to, head).await.unwrap_or(false)
+            {
+                first_time.push(to);
+            }
+        }
+        Ok(first_time)
+    }
+
+    /// Tell the alert sink how a sent step ended
+    async fn alert(&self, report: &StepReport) {
+        let Some(alerts) = &self.alerts else {
+            return;
+        };
+        let (event, severity) = match report.status {
+            StepStatus::Confirmed => ("transaction_confirmed", AlertSeverity::Info),
+            _ => ("transaction_failed", AlertSeverity::Warning),
+        };
+        let alert = json!({
+            "event": event,
+            "step": report.index,
+            "action": report.action,
+            "tx_hash": report.tx_hash,
+            "block_number": report.block_number,
+            "gas_used": report.gas_used,
+            "error": report.error,
+        });
+        alerts.send(TRANSACTION_TOPIC, severity, &alert).await;
+    }
+
+    /// Default fee escalation for live runs; calls can override or disable it
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.gas_escalation = gas_escalation;
+        self
+    }
+
+    /// Merge a call's `gas_escalation` over the server default. A call may lower the
+    /// server's fee ceiling but not raise it.
+    fn resolve_escalation(
+        &self,
+        params: Option<GasEscalationParams>,
+    ) -> Result<Option<GasEscalation>> {
+        let Some(params) = params else {
+            return Ok(self.gas_escalation);
+        };
+        if params.enabled == Some(false) {
+            return Ok(None);
+        }
+
+        let max_fee_per_gas = match (params.max_fee_gwei, self.gas_escalation) {
+            (Some(gwei), server) => {
+                let fee = to_base_units(gwei, 9).context("Invalid gas_escalation.max_fee_gwei")?;
+                server.map_or(fee, |server| fee.min(server.max_fee_per_gas))
+            }
+            (None, Some(server)) => server.max_fee_per_gas,
+            (None, None) => anyhow::bail!(
+                "gas_escalation.max_fee_gwei is required: the server has no escalation ceiling"
+            ),
+        };
+        let policy = GasEscalation {
+            after_blocks: params
+                .after_blocks
+                .or(self.gas_escalation.map(|server| server.after_blocks))
+                .unwrap_or(DEFAULT_ESCALATION_AFTER_BLOCKS),
+            bump_percent: params
+                .bump_percent
+                .or(self.gas_escalation.map(|server| server.bump_percent))
+                .unwrap_or(DEFAULT_BUMP_PERCENT),
+            max_fee_per_gas,
+        };
+        policy.validate()?;
+        Ok(Some(policy))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecutePlanParams {
+    plan: Plan,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Index of the first step to run, used to resume after a partial failure
+    #[serde(default)]
+    start_step: usize,
+    #[serde(default)]
+    gas_escalation: Option<GasEscalationParams>,
+    /// Acknowledges first-time recipients when the server requires it
+    #[serde(default)]
+    confirm_new_recipients: bool,
+    /// Skip the gas reserve check, when the server's policy allows it
+    #[serde(default)]
+    override_gas_reserve: bool,
+    /// Pay addresses on the scam list, when the server's policy allows it
+    #[serde(default)]
+    override_scam_list: bool,
+}
+
+/// Per-call override of the server's fee escalation; unset fields use the server's
+#[derive(Debug, Default, Deserialize)]
+struct GasEscalationParams {
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    after_blocks: Option<u64>,
+    #[serde(default)]
+    bump_percent: Option<u64>,
+    #[serde(default)]
+    max_fee_gwei: Option<Decimal>,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StepStatus {
+    /// Before `start_step`, completed by an earlier run
+    Skipped,
+    Simulated,
+    Confirmed,
+    Failed,
+    NotRun,
+}
+
+#[derive(Debug, Serialize)]
+struct StepReport {
+    index: usize,
+    action: String,
+    status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_estimate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_used: Option<String>,
+    /// Block a sent step was mined in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    /// Wei per gas a sent step paid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effective_gas_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    replacements: Vec<FeeReplacement>,
+    /// A forwarder or paymaster pays this step's gas, so the wallet needs no ETH for it
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    gas_sponsored: bool,
+    /// Who paid the gas of a sent step, and through which contract
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sponsorship: Option<Sponsorship>,
+    /// The wallet has never paid this transfer's recipient
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    first_time_recipient: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ExecutePlanResult {
+    dry_run: bool,
+    steps: Vec<StepReport>,
+    completed_steps: usize,
+    /// Step to pass as `start_step` to pick up where this run stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resume_from: Option<usize>,
+    total_gas_estimate: String,
+    /// Transfer recipients the wallet has never paid; double-check them before sending
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    first_time_recipients: Vec<String>,
+    /// Set by a dry run whose steps together would eat into the gas reserve
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_reserve_warning: Option<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ExecutePlanTool<C> {
+    fn name(&self) -> &str {
+        "execute_plan"
+    }
+
+    fn description(&self) -> &str {
+        "Run a plan (ordered approve/wrap/swap/transfer/transfer_nft/batch_transfer/create_stream/cancel_stream/vote/delegate/renew_ens/cancel/speed_up steps, as returned by planning tools such as swap_tokens and diagnose_wallet) step by step. Dry run by default: each step is gas-estimated without sending. Live runs stop at the first failure and report which step to resume from. Steps whose gas a configured forwarder or paymaster pays are marked gas_sponsored, and sent ones report the sponsorship. Transfers to recipients the wallet has never paid are flagged, and transfers or approvals to known scam addresses are refused."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "plan": {
+                    "type": "object",
+                    "description": "Plan object with a `steps` array; each step has an `action` of approve, wrap, swap, transfer, transfer_nft, batch_transfer, create_stream, cancel_stream, vote, delegate, renew_ens, cancel or speed_up. Amounts are integer base units as strings; recipients and spenders may be contact names",
+                    "properties": {
+                        "steps": { "type": "array", "items": { "type": "object" } }
+                    },
+                    "required": ["steps"]
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate every step without sending (default: true). Live execution must be enabled on the server"
+                },
+                "start_step": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Index of the first step to run, e.g. resume_from of a previous run (default: 0)"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Let live steps spend into the ETH reserve kept for gas; only honored when the server's policy allows it (default: false)"
+                },
+                "confirm_new_recipients": {
+                    "type": "boolean",
+                    "description": "Send transfers to recipients the wallet has never paid (flagged in first_time_recipients by a dry run) when the server requires confirmation (default: false)"
+                },
+                "override_scam_list": {
+                    "type": "boolean",
+                    "description": "Pay or approve addresses on the scam list anyway; only honored when the server's policy allows it (default: false)"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Re-send unmined transactions with higher fees. Unset fields use the server's policy",
+                    "properties": {
+                        "enabled": { "type": "boolean", "description": "Set false to disable escalation for this call" },
+                        "after_blocks": { "type": "integer", "minimum": 1, "description": "Blocks to wait before each fee bump" },
+                        "bump_percent": { "type": "integer", "minimum": 10, "description": "Fee increase per bump in percent" },
+                        "max_fee_gwei": { "type": "string", "description": "Fee ceiling in gwei; cannot exceed the server's ceiling" }
+                    }
+                }
+            },
+            "required": ["plan"]
+        })
+    }
+
+    // Live transfers to addresses outside the address book need the user's go-ahead
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        let book = self
+            .book
+            .as_deref()
+            .filter(|book| book.confirms_unknown_recipients())?;
+        if params
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+        {
+            return None;
+        }
+        let unknown: Vec<String> = params
+            .pointer("/plan/steps")?
+            .as_array()?
+            .iter()
+            .flat_map(|step| match step.get("action").and_then(Value::as_str) {
+                Some("transfer") | Some("transfer_nft") | Some("create_stream") => {
+                    step.get("to").into_iter().collect()
+                }
+                Some("batch_transfer") => step
+                    .get("payments")
+                    .and_then(Value::as_array)
+                    .map(|payments| payments.iter().filter_map(|p| p.get("to")).collect())
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            })
+            .filter_map(Value::as_str)
+            .filter_map(|to| resolve_address(Some(book), to).ok())
+            .filter(|to| book.name_of(*to).is_none())
+            .map(|to| format!("{:?}", to))
+            .collect();
+        if unknown.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "This plan sends funds to {} which {} not in the address book. Send anyway?",
+            unknown.join(", "),
+            if unknown.len() == 1 { "is" } else { "are" }
+        ))
+    }
+
+    async fn execute(&self, mut params: Value) -> Result<Value> {
+        if let Some(plan) = params.get_mut("plan") {
+            resolve_plan_addresses(self.book.as_deref(), plan)?;
+        }
+        let params: ExecutePlanParams =
+            serde_json::from_value(params).context("Invalid parameters for execute_plan")?;
+
+        if params.plan.steps.is_empty() {
+            anyhow::bail!("Plan has no steps");
+        }
+        if params.start_step >= params.plan.steps.len() {
+            anyhow::bail!(
+                "start_step {} is past the end of the plan ({} steps)",
+                params.start_step,
+                params.plan.steps.len()
+            );
+        }
+        if !params.dry_run && !self.execution_enabled {
+            anyhow::bail!(
+                "Live execution is disabled on this server; set ENABLE_EXECUTION=true or use dry_run"
+            );
+        }
+
+        if let Some(scams) = &self.scams {
+            let counterparties: Vec<Address> = params.plan.steps[params.start_step..]
+                .iter()
+                .flat_map(PlanStep::counterparties)
+                .collect();
+            scams.ensure_unlisted(&counterparties, params.override_scam_list)?;
+        }
+
+        let escalation = self.resolve_escalation(params.gas_escalation)?;
+        self.gas_reserve
+            .check_override(params.override_gas_reserve)?;
+        let reserve_checked =
+            !self.gas_reserve.min_balance.is_zero() && !params.override_gas_reserve;
+        // Fee per gas a step may end up paying, escalation included
+        let fee_ceiling = if reserve_checked {
+            let (max_fee_per_gas, _) = self.client.estimate_fees().await?;
+            escalation.map_or(max_fee_per_gas, |escalation| {
+                escalation.max_fee_per_gas.max(max_fee_per_gas)
+            })
+        } else {
+            U256::zero()
+        };
+        let mut planned_spend = U256::zero();
+
+        let from = self.client.get_wallet_address();
+        let first_time = self
+            .first_time_recipients(&params.plan, params.start_step, from)
+            .await?;
+        let confirmation_required = self
+            .recipients
+            .as_ref()
+            .is_some_and(|history| history.requires_confirmation());
+        if !params.dry_run
+            && confirmation_required
+            && !first_time.is_empty()
+            && !params.confirm_new_recipients
+        {
+            anyhow::bail!(
+                "The wallet has never paid {}; check the address and pass confirm_new_recipients: true to send",
+                first_time
+                    .iter()
+                    .map(|to| format!("{:?}", to))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let deadline = U256::from(now + SWAP_DEADLINE_SECS);
+
+        let mut reports = Vec::with_capacity(params.plan.steps.len());
+        let mut total_gas = U256::zero();
+        let mut completed_steps = params.start_step;
+        let mut resume_from = None;
+
+        for (index, step) in params.plan.steps.iter().enumerate() {
+            let mut report = StepReport {
+                index,
+                action: step.action().to_string(),
+                status: StepStatus::NotRun,
+                gas_estimate: None,
+                tx_hash: None,
+                gas_used: None,
+                block_number: None,
+                effective_gas_price: None,
+                error: None,
+                replacements: Vec::new(),
+                gas_sponsored: false,
+                sponsorship: None,
+                first_time_recipient: step.recipients().iter().any(|to| first_time.contains(to)),
+            };
+            if index < params.start_step {
+                report.status = StepStatus::Skipped;
+                reports.push(report);
+                continue;
+            }
+            if resume_from.is_some() {
+                reports.push(report);
+                continue;
+            }
+
+            let tx = step.to_transaction(from, deadline)?;
+            let gas = match self.client.estimate_transaction_gas(&tx).await {
+                Ok(gas) => {
+                    total_gas += gas;
+                    report.gas_estimate = Some(gas.to_string());
+                    gas
+                }
+                Err(e) => {
+                    report.status = StepStatus::Failed;
+                    report.error = Some(e.to_string());
+                    // A dry run keeps going: later steps may only fail because earlier
+                    // ones were not applied
+                    if !params.dry_run {
+                        resume_from = Some(index);
+                    }
+                    reports.push(report);
+                    continue;
+                }
+            };
+            report.gas_sponsored = self.client.gas_sponsored(&tx).await;
+            let spend = max_spend(
+                tx.value().copied().unwrap_or_default(),
+                if report.gas_sponsored {
+                    U256::zero()
+                } else {
+                    gas
+                },
+                tx.gas_price().unwrap_or(fee_ceiling),
+            );
+
+            if params.dry_run {
+                planned_spend = planned_spend.saturating_add(spend);
+                report.status = StepStatus::Simulated;
+                reports.push(report);
+                continue;
+            }
+
+            if reserve_checked {
+                if let Err(e) = self
+                    .gas_reserve
+                    .check(self.client.as_ref(), from, spend)
+                    .await
+                {
+                    report.status = StepStatus::Failed;
+                    report.error = Some(e.to_string());
+                    resume_from = Some(index);
+                    reports.push(report);
+                    continue;
+                }
+            }
+
+            match self.client.send_transaction(tx, escalation).await {
+                Ok(outcome) => {
+                    report.replacements = outcome.replacements;
+                    report.sponsorship = outcome.sponsorship;
+                    report.tx_hash = Some(format!("{:?}", outcome.tx_hash));
+                    report.gas_used = Some(outcome.gas_used.to_string());
+                    report.block_number = outcome.block_number;
+                    report.effective_gas_price =
+                        outcome.effective_gas_price.map(|price| price.to_string());
+                    if outcome.success {
+                        report.status = StepStatus::Confirmed;
+                        completed_steps = index + 1;
+                        if let Some(history) = &self.recipients {
+                            for to in step.recipients() {
+                                history.record(to);
+                            }
+                        }
+                    } else {
+                        report.status = StepStatus::Failed;
+                        report.error = Some("Transaction reverted".to_string());
+                        resume_from = Some(index);
+                    }
+                }
+                Err(e) => {
+                    report.status = StepStatus::Failed;
+                    report.error = Some(e.to_string());
+                    resume_from = Some(index);
+                }
+            }
+            self.alert(&report).await;
+            reports.push(report);
+        }
+
+        let gas_reserve_warning = if params.dry_run && reserve_checked {
+            self.gas_reserve
+                .check(self.client.as_ref(), from, planned_spend)
+                .await
+                .err()
+                .map(|e| e.to_string())
+        } else {
+            None
+        };
+
+        let result = ExecutePlanResult {
+            dry_run: params.dry_run,
+            steps: reports,
+            completed_steps,
+            resume_from,
+            total_gas_estimate: total_gas.to_string(),
+            first_time_recipients: first_time.iter().map(|to| format!("{:?}", to)).collect(),
+            gas_reserve_warning,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,411 @@
+use super::alerts::AlertSink;
+use super::execution_reports::{ExecutedSwap, ExecutionReport, ExecutionReports, SentTransaction};
+use super::gas_reserve::GasReserve;
+use super::payment_requests::unix_now;
+use super::pool_share::{pool_share_percent, OverLimitAction, PoolShareLimit};
+use super::quotes::QuoteStore;
+use super::trade_ledger::TradeLedger;
+use super::units::minimum_amount_out;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Plan, PlanStep, UniswapRouterTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+pub struct ExecuteSwapTool<C: EthereumClientTrait, U: UniswapRouterTrait> {
+    client: Arc<C>,
+    uniswap: Arc<U>,
+    quotes: Arc<QuoteStore>,
+    executor: ExecutePlanTool<C>,
+    max_move_bps: u32,
+    pool_share_limit: Option<PoolShareLimit>,
+    /// Other V2 venues split quotes may route through
+    split_routers: Vec<Arc<U>>,
+    reports: Option<Arc<ExecutionReports>>,
+    ledger: Option<Arc<TradeLedger>>,
+}
+
+impl<C: EthereumClientTrait, U: UniswapRouterTrait> ExecuteSwapTool<C, U> {
+    /// `max_move_bps` bounds how far the output may drift from the quote before refusing
+    pub fn new(
+        client: Arc<C>,
+        uniswap: Arc<U>,
+        quotes: Arc<QuoteStore>,
+        execution_enabled: bool,
+        max_move_bps: u32,
+    ) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            uniswap,
+            quotes,
+            max_move_bps,
+            pool_share_limit: None,
+            split_routers: Vec::new(),
+            reports: None,
+            ledger: None,
+        }
+    }
+
+    /// Default fee escalation for live swaps; calls can override or disable it
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live swaps must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+
+    /// Refuse, or have the user confirm, swaps selling more than `limit` of their pool
+    pub fn with_pool_share_limit(mut self, limit: Option<PoolShareLimit>) -> Self {
+        self.pool_share_limit = limit;
+        self
+    }
+
+    /// Routers split quotes from swap_tokens may send legs through
+    pub fn with_split_routers(mut self, routers: Vec<Arc<U>>) -> Self {
+        self.split_routers = routers;
+        self
+    }
+
+    /// Report how each live swap executed against its quote to `reports`
+    pub fn with_execution_reports(mut self, reports: Arc<ExecutionReports>) -> Self {
+        self.reports = Some(reports);
+        self
+    }
+
+    /// Enter each live swap in `ledger`, the basis of tax lot reports
+    pub fn with_trade_ledger(mut self, ledger: Arc<TradeLedger>) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    fn router(&self, address: Address) -> Result<&U> {
+        std::iter::once(&self.uniswap)
+            .chain(&self.split_routers)
+            .find(|router| router.router_address() == address)
+            .map(AsRef::as_ref)
+            .with_context(|| format!("Quote swaps through unknown router {:?}", address))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecuteSwapParams {
+    quote_id: String,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    override_gas_reserve: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct ExecuteSwapResult {
+    quote_id: String,
+    quoted_amount_out: String,
+    current_amount_out: String,
+    /// Change in output since the quote, negative when the price got worse
+    price_move_bps: String,
+    /// The minimum output was recomputed from the current quote
+    requoted: bool,
+    /// Percent of the pool's from_token reserve the swap sells, when a limit is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool_share_percent: Option<String>,
+    execution: Value,
+    /// How a live swap executed against its quote, once every leg was mined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    execution_report: Option<ExecutionReport>,
+}
+
+/// The parts of an execute_plan step report the execution report is built from
+#[derive(Debug, Deserialize)]
+struct SentStep {
+    index: usize,
+    status: String,
+    #[serde(default)]
+    tx_hash: Option<H256>,
+    #[serde(default)]
+    gas_used: Option<String>,
+    #[serde(default)]
+    block_number: Option<u64>,
+    #[serde(default)]
+    effective_gas_price: Option<String>,
+}
+
+/// The transactions of a live run, or None unless every swap leg was mined successfully
+fn sent_transactions(plan: &Plan, execution: &Value) -> Option<Vec<SentTransaction>> {
+    let steps: Vec<SentStep> = serde_json::from_value(execution.get("steps")?.clone()).ok()?;
+    let mut sent = Vec::new();
+    for step in steps {
+        let swap = matches!(plan.steps.get(step.index), Some(PlanStep::Swap { .. }));
+        if step.status != "confirmed" {
+            if swap {
+                return None;
+            }
+            continue;
+        }
+        sent.push(SentTransaction {
+            tx_hash: step.tx_hash?,
+            block_number: step.block_number?,
+            gas_used: U256::from_dec_str(step.gas_used.as_deref()?).ok()?,
+            effective_gas_price: step
+                .effective_gas_price
+                .and_then(|price| U256::from_dec_str(&price).ok()),
+            swap,
+        });
+    }
+    Some(sent)
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
+    for ExecuteSwapTool<C, U>
+{
+    fn name(&self) -> &str {
+        "execute_swap"
+    }
+
+    fn description(&self) -> &str {
+        "Execute a swap previously simulated by swap_tokens, identified by its quote_id. Quotes expire after a short TTL. The swap is re-quoted first: if the output moved beyond the server's bound it is refused, otherwise the minimum output is refreshed from the new quote. Swaps selling more of their pool than the server allows are refused or need the user's confirmation. Live swaps are reported in get_execution_report. Dry run by default."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "quote_id": {
+                    "type": "string",
+                    "description": "quote_id returned by swap_tokens"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transactions without sending (default: true). Live execution must be enabled on the server"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["quote_id"]
+        })
+    }
+
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        let limit = self
+            .pool_share_limit
+            .filter(|limit| limit.action == OverLimitAction::Confirm)?;
+        if params
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+        {
+            return None;
+        }
+        let quote = self.quotes.get(params.get("quote_id")?.as_str()?).ok()?;
+        let share = quote
+            .pool_share_percent
+            .filter(|share| limit.is_exceeded_by(*share))?;
+        Some(format!(
+            "{}. Send it anyway?",
+            limit.describe(share, quote.from_token)
+        ))
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ExecuteSwapParams =
+            serde_json::from_value(params).context("Invalid parameters for execute_swap")?;
+
+        let quote = self.quotes.get(&params.quote_id)?;
+        // Split quotes have a swap per venue, each re-quoted through its own router
+        let wallet = self.client.get_wallet_address();
+        let mut legs = Vec::new();
+        for (index, step) in quote.plan.steps.iter().enumerate() {
+            if let PlanStep::Swap {
+                router, amount_in, ..
+            } = step
+            {
+                let router = self.router(*router)?;
+                let current = router
+                    .simulate_swap(quote.from_token, quote.to_token, *amount_in, wallet)
+                    .await
+                    .context("Failed to re-quote swap")?;
+                legs.push((index, router, *amount_in, current.amount_out));
+            }
+        }
+        let current_amount_out = legs.iter().fold(U256::zero(), |total, (_, _, _, out)| {
+            total.saturating_add(*out)
+        });
+
+        let quoted_out = Decimal::from_str(&quote.amount_out.to_string())?;
+        let current_out = Decimal::from_str(&current_amount_out.to_string())?;
+        if quoted_out.is_zero() {
+            anyhow::bail!("Quote {} has no output", params.quote_id);
+        }
+        let move_bps = (current_out - quoted_out) / quoted_out * Decimal::from(10_000);
+        if move_bps.abs() > Decimal::from(self.max_move_bps) {
+            anyhow::bail!(
+                "Price moved {} bps since quote {}, beyond the {} bps bound; call swap_tokens again",
+                move_bps.round_dp(2).normalize(),
+                params.quote_id,
+                self.max_move_bps
+            );
+        }
+
+        // Checked against the pools as they are now, which may have thinned since the quote
+        let pool_share = match &self.pool_share_limit {
+            Some(limit) => {
+                let mut share = Decimal::ZERO;
+                for (_, router, amount_in, _) in &legs {
+                    let reserves = router
+                        .get_pool_reserves(quote.from_token, quote.to_token)
+                        .await
+                        .context("Failed to read pool reserves for the trade size limit")?;
+                    share = share.max(pool_share_percent(*amount_in, reserves.reserve_in));
+                }
+                // Confirmation is asked from the quote, so a pool that crossed the limit
+                // after quoting was never confirmed
+                let confirmed = limit.action == OverLimitAction::Confirm
+                    && (params.dry_run
+                        || quote
+                            .pool_share_percent
+                            .is_some_and(|quoted| limit.is_exceeded_by(quoted)));
+                if limit.is_exceeded_by(share) && !confirmed {
+                    anyhow::bail!(
+                        "{}; trade a smaller amount{}",
+                        limit.describe(share, quote.from_token),
+                        match limit.action {
+                            OverLimitAction::Reject => "",
+                            OverLimitAction::Confirm => " or call swap_tokens again to confirm it",
+                        }
+                    );
+                }
+                Some(share)
+            }
+            None => None,
+        };
+
+        let requoted = current_amount_out != quote.amount_out;
+        let mut plan = quote.plan.clone();
+        if requoted {
+            for (index, _, _, current_out) in &legs {
+                if let PlanStep::Swap { amount_out_min, .. } = &mut plan.steps[*index] {
+                    *amount_out_min = minimum_amount_out(*current_out, quote.slippage_tolerance)?;
+                }
+            }
+        }
+
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "override_gas_reserve": params.override_gas_reserve,
+            }))
+            .await?;
+
+        // Swaps whose every leg was mined are reported and entered in the ledger
+        let sent = if params.dry_run {
+            None
+        } else {
+            sent_transactions(&plan, &execution)
+        };
+        let execution_report = match (&self.reports, &sent) {
+            (Some(reports), Some(transactions)) => {
+                let amount_out_min =
+                    plan.steps
+                        .iter()
+                        .fold(U256::zero(), |total, step| match step {
+                            PlanStep::Swap { amount_out_min, .. } => {
+                                total.saturating_add(*amount_out_min)
+                            }
+                            _ => total,
+                        });
+                Some(
+                    reports
+                        .record(ExecutedSwap {
+                            quote_id: params.quote_id.clone(),
+                            quote: quote.clone(),
+                            wallet,
+                            sent_amount_out: current_amount_out,
+                            amount_out_min,
+                            transactions: transactions.clone(),
+                        })
+                        .await,
+                )
+            }
+            _ => None,
+        };
+        if let (Some(ledger), Some(transactions)) = (&self.ledger, &sent) {
+            let amount_out = execution_report
+                .as_ref()
+                .and_then(|report| report.realized_amount_out.as_deref())
+                .and_then(|amount| U256::from_dec_str(amount).ok())
+                .unwrap_or(current_amount_out);
+            let tx_hash = transactions
+                .iter()
+                .rev()
+                .find(|tx| tx.swap)
+                .map(|tx| tx.tx_hash);
+            // The swap went through; a ledger that cannot be written must not hide that
+            if let Err(e) = ledger
+                .record(
+                    self.client.as_ref(),
+                    (quote.from_token, quote.amount_in),
+                    (quote.to_token, amount_out),
+                    tx_hash,
+                    unix_now(),
+                )
+                .await
+            {
+                warn!(tx_hash = ?tx_hash, "Failed to record swap in the trade ledger: {:#}", e);
+            }
+        }
+
+        let result = ExecuteSwapResult {
+            quote_id: params.quote_id,
+            quoted_amount_out: quote.amount_out.to_string(),
+            current_amount_out: current_amount_out.to_string(),
+            price_move_bps: move_bps.round_dp(2).normalize().to_string(),
+            requoted,
+            pool_share_percent: pool_share.map(|share| share.normalize().to_string()),
+            execution,
+            execution_report,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,303 @@
+use super::payment_requests::unix_now;
+use super::quotes::StoredQuote;
+use super::units::from_base_units;
+use crate::ethereum::{TokenEventSource, TokenTransfer};
+use anyhow::Result;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Execution reports kept for get_execution_report
+pub const DEFAULT_EXECUTION_REPORTS: usize = 500;
+
+/// A transaction a live swap sent and saw mined
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub gas_used: U256,
+    pub effective_gas_price: Option<U256>,
+    /// A swap leg rather than an approval or wrap
+    pub swap: bool,
+}
+
+/// A quote execute_swap sent, with what it was re-quoted at and what was mined
+#[derive(Debug, Clone)]
+pub struct ExecutedSwap {
+    pub quote_id: String,
+    pub quote: StoredQuote,
+    pub wallet: Address,
+    /// Output of the re-quote made just before sending
+    pub sent_amount_out: U256,
+    /// Smallest output the sent swaps accepted
+    pub amount_out_min: U256,
+    /// Every transaction of the swap's plan, approvals included
+    pub transactions: Vec<SentTransaction>,
+}
+
+/// Another account bought from the pool right before a swap and sold right after it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sandwich {
+    pub pool: Address,
+    pub attacker: Address,
+    pub front_run_tx: H256,
+    pub back_run_tx: H256,
+}
+
+/// How a live swap went compared with its quote
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReport {
+    pub id: u64,
+    pub quote_id: String,
+    /// Unix seconds
+    pub recorded_at: u64,
+    pub from_token: Address,
+    pub to_token: Address,
+    pub amount_in: String,
+    pub quoted_amount_out: String,
+    /// Output of the re-quote made just before sending
+    pub sent_amount_out: String,
+    pub amount_out_min: String,
+    /// Output the wallet received, from the swaps' Transfer events; absent when they
+    /// could not be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub realized_amount_out: Option<String>,
+    /// Realized output against the quote, negative when it was worse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub realized_slippage_bps: Option<Decimal>,
+    /// Realized output against the re-quote made just before sending
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_since_send_bps: Option<Decimal>,
+    pub quote_block: u64,
+    /// Block the last swap leg was mined in
+    pub execution_block: u64,
+    /// A sandwich was found around one of the swap legs
+    pub mev_suspected: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sandwiches: Vec<Sandwich>,
+    /// Gas of every transaction sent, approvals included
+    pub gas_used: String,
+    /// Gas the quote estimated for the same transactions
+    pub gas_estimate: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used_percent_of_estimate: Option<Decimal>,
+    pub quoted_gas_price_gwei: Decimal,
+    /// Average price per gas paid, when every receipt reported it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price_gwei: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_eth: Option<Decimal>,
+    pub tx_hashes: Vec<H256>,
+}
+
+/// Reports on the swaps the server executed, newest kept. Shared by every session,
+/// since they all trade from the same wallet.
+pub struct ExecutionReports {
+    events: Arc<dyn TokenEventSource>,
+    reports: Mutex<VecDeque<ExecutionReport>>,
+    capacity: usize,
+    next_id: Mutex<u64>,
+}
+
+impl ExecutionReports {
+    /// Keep the latest `capacity` reports
+    pub fn new(events: Arc<dyn TokenEventSource>, capacity: usize) -> Self {
+        Self {
+            events,
+            reports: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            next_id: Mutex::new(1),
+        }
+    }
+
+    /// Compare `swap` with its quote and keep the report. Transfer events that cannot
+    /// be read leave the realized output unset rather than failing.
+    pub async fn record(&self, swap: ExecutedSwap) -> ExecutionReport {
+        let legs: Vec<&SentTransaction> = swap.transactions.iter().filter(|tx| tx.swap).collect();
+
+        let mut realized = Some(U256::zero());
+        let mut sandwiches = Vec::new();
+        for leg in &legs {
+            match self.inspect_leg(&swap, leg).await {
+                Ok((received, sandwich)) => {
+                    realized = realized.map(|total| total.saturating_add(received));
+                    sandwiches.extend(sandwich);
+                }
+                Err(e) => {
+                    warn!(tx_hash = ?leg.tx_hash, "Failed to read swap transfers: {:#}", e);
+                    realized = None;
+                }
+            }
+        }
+
+        let gas_used = swap
+            .transactions
+            .iter()
+            .fold(U256::zero(), |total, tx| total.saturating_add(tx.gas_used));
+        let fee = swap
+            .transactions
+            .iter()
+            .try_fold(U256::zero(), |total, tx| {
+                Some(total.saturating_add(tx.gas_used.saturating_mul(tx.effective_gas_price?)))
+            });
+        let effective_gas_price = fee
+            .filter(|_| !gas_used.is_zero())
+            .map(|fee| fee / gas_used);
+
+        let report = ExecutionReport {
+            id: 0,
+            quote_id: swap.quote_id,
+            recorded_at: unix_now(),
+            from_token: swap.quote.from_token,
+            to_token: swap.quote.to_token,
+            amount_in: swap.quote.amount_in.to_string(),
+            quoted_amount_out: swap.quote.amount_out.to_string(),
+            sent_amount_out: swap.sent_amount_out.to_string(),
+            amount_out_min: swap.amount_out_min.to_string(),
+            realized_amount_out: realized.map(|amount| amount.to_string()),
+            realized_slippage_bps: realized.and_then(|amount| bps(amount, swap.quote.amount_out)),
+            slippage_since_send_bps: realized.and_then(|amount| bps(amount, swap.sent_amount_out)),
+            quote_block: swap.quote.block_number,
+            execution_block: legs
+                .iter()
+                .map(|leg| leg.block_number)
+                .max()
+                .unwrap_or_default(),
+            mev_suspected: !sandwiches.is_empty(),
+            sandwiches,
+            gas_used: gas_used.to_string(),
+            gas_estimate: swap.quote.gas_estimate.to_string(),
+            gas_used_percent_of_estimate: percent(gas_used, swap.quote.gas_estimate),
+            quoted_gas_price_gwei: gwei(swap.quote.gas_price),
+            effective_gas_price_gwei: effective_gas_price.map(gwei),
+            fee_eth: fee
+                .and_then(|fee| from_base_units(fee, 18).ok())
+                .map(|fee| fee.normalize()),
+            tx_hashes: swap.transactions.iter().map(|tx| tx.tx_hash).collect(),
+        };
+        self.keep(report)
+    }
+
+    /// Output `leg` paid the wallet, and the sandwich around it if one is found in the
+    /// transfers of the pool that paid it
+    async fn inspect_leg(
+        &self,
+        swap: &ExecutedSwap,
+        leg: &SentTransaction,
+    ) -> Result<(U256, Option<Sandwich>)> {
+        let received: Vec<TokenTransfer> = self
+            .events
+            .incoming_transfers(
+                swap.quote.to_token,
+                swap.wallet,
+                leg.block_number,
+                leg.block_number,
+            )
+            .await?
+            .into_iter()
+            .filter(|transfer| transfer.tx_hash == leg.tx_hash)
+            .collect();
+        let amount = received.iter().fold(U256::zero(), |total, transfer| {
+            total.saturating_add(transfer.amount)
+        });
+        // The last hop's pool pays the wallet
+        let Some(pool) = received.last().map(|transfer| transfer.from) else {
+            return Ok((amount, None));
+        };
+
+        let transfers = self
+            .events
+            .address_transfers(pool, leg.block_number, leg.block_number)
+            .await?;
+        Ok((amount, find_sandwich(&transfers, pool, swap, leg.tx_hash)))
+    }
+
+    fn keep(&self, mut report: ExecutionReport) -> ExecutionReport {
+        let mut next_id = self.next_id.lock().unwrap_or_else(|e| e.into_inner());
+        report.id = *next_id;
+        *next_id += 1;
+        let mut reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        reports.push_back(report.clone());
+        while reports.len() > self.capacity {
+            reports.pop_front();
+        }
+        report
+    }
+
+    /// Kept reports, newest first
+    pub fn reports(&self) -> Vec<ExecutionReport> {
+        self.reports
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+}
+
+/// In `transfers` of `pool` within one block, oldest first, an account other than the
+/// wallet that took `to_token` from the pool before the swap and returned it after
+fn find_sandwich(
+    transfers: &[TokenTransfer],
+    pool: Address,
+    swap: &ExecutedSwap,
+    tx_hash: H256,
+) -> Option<Sandwich> {
+    let start = transfers.iter().position(|t| t.tx_hash == tx_hash)?;
+    let end = transfers.iter().rposition(|t| t.tx_hash == tx_hash)?;
+    let token = swap.quote.to_token;
+    transfers[..start]
+        .iter()
+        .filter(|t| t.token == token && t.from == pool && t.to != swap.wallet)
+        .find_map(|front| {
+            transfers[end + 1..]
+                .iter()
+                .find(|back| {
+                    back.token == token
+                        && back.from == front.to
+                        && back.to == pool
+                        && back.tx_hash != front.tx_hash
+                })
+                .map(|back| Sandwich {
+                    pool,
+                    attacker: front.to,
+                    front_run_tx: front.tx_hash,
+                    back_run_tx: back.tx_hash,
+                })
+        })
+}
+
+/// Change from `expected` to `actual` in basis points
+fn bps(actual: U256, expected: U256) -> Option<Decimal> {
+    let expected = Decimal::from_str(&expected.to_string()).ok()?;
+    let actual = Decimal::from_str(&actual.to_string()).ok()?;
+    if expected.is_zero() {
+        return None;
+    }
+    Some(
+        ((actual - expected) / expected * Decimal::from(10_000))
+            .round_dp(2)
+            .normalize(),
+    )
+}
+
+fn percent(part: U256, whole: U256) -> Option<Decimal> {
+    let whole = Decimal::from_str(&whole.to_string()).ok()?;
+    let part = Decimal::from_str(&part.to_string()).ok()?;
+    if whole.is_zero() {
+        return None;
+    }
+    Some((part / whole * Decimal::from(100)).round_dp(2).normalize())
+}
+
+fn gwei(wei: U256) -> Decimal {
+    from_base_units(wei, 9)
+        .unwrap_or_default()
+        .round_dp(4)
+        .normalize()
+}
@@ -0,0 +1,71 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Parameter every tool accepts to request an explanation
+const EXPLAIN_PARAM: &str = "explain";
+
+/// How a result was produced, attached as `explanation` when a call passes `explain: true`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    /// Plain-language account of the steps taken
+    pub narrative: String,
+    /// Intermediate values behind the numbers: inputs, pools and paths, decimals, formulas
+    pub data: Value,
+}
+
+impl Explanation {
+    pub fn new(narrative: impl Into<String>, data: Value) -> Self {
+        Self {
+            narrative: narrative.into(),
+            data,
+        }
+    }
+
+    /// Fallback for tools without a dedicated explanation: the inputs as interpreted
+    pub fn generic(tool: &str, params: &Value) -> Self {
+        Self::new(
+            format!(
+                "{} ran with the inputs below; each result field is reported as computed by the tool, with its description in the tool's documentation.",
+                tool
+            ),
+            json!({ "inputs": params }),
+        )
+    }
+}
+
+/// A result field as narrative text: strings without their JSON quotes
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Schema of the `explain` parameter, added to every tool's input schema
+pub fn explain_schema_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "Add an `explanation` to the result: a narrative of how it was computed and the intermediate data used (default: false)"
+    })
+}
+
+/// Add the `explain` parameter to a tool's input schema
+pub fn with_explain_property(mut schema: Value) -> Value {
+    if let Some(properties) = schema
+        .as_object_mut()
+        .map(|schema| schema.entry("properties").or_insert_with(|| json!({})))
+        .and_then(Value::as_object_mut)
+    {
+        properties.insert(EXPLAIN_PARAM.to_string(), explain_schema_property());
+    }
+    schema
+}
+
+/// Remove `explain` from a call's parameters, returning whether it was set
+pub fn take_explain_flag(params: &mut Value) -> bool {
+    params
+        .as_object_mut()
+        .and_then(|params| params.remove(EXPLAIN_PARAM))
+        .and_then(|flag| flag.as_bool())
+        .unwrap_or(false)
+}
@@ -0,0 +1,143 @@
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{PoolDiscoveryTrait, PoolLiquidity};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct FindPoolsTool<P: PoolDiscoveryTrait> {
+    discovery: Arc<P>,
+}
+
+impl<P: PoolDiscoveryTrait> FindPoolsTool<P> {
+    pub fn new(discovery: Arc<P>) -> Self {
+        Self { discovery }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FindPoolsParams {
+    token_a: String,
+    token_b: Option<String>,
+    from_block: Option<u64>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct PoolEntry {
+    dex: String,
+    pool_address: String,
+    token0: String,
+    token1: String,
+    fee_tier: u32,
+    creation_block: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reserve0: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reserve1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liquidity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liquidity_error: Option<String>,
+}
+
+#[async_trait]
+impl<P: PoolDiscoveryTrait + 'static> Tool for FindPoolsTool<P> {
+    fn name(&self) -> &str {
+        "find_pools"
+    }
+
+    fn description(&self) -> &str {
+        "Find existing pools for one token, or for a token pair, across Uniswap V2, SushiSwap and Uniswap V3. Returns each pool's address, fee tier, creation block and current reserves (V2) or in-range liquidity (V3)."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "token_a": {
+                "type": "string",
+                "description": "Address of the token to find pools for"
+            },
+            "token_b": {
+                "type": "string",
+                "description": "Optional address of the other token; omit to list every pool containing token_a"
+            },
+            "from_block": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Only report pools created at or after this block (default: each factory's deployment)"
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["token_a"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: FindPoolsParams =
+            serde_json::from_value(params).context("Invalid parameters for find_pools")?;
+
+        let token_a: Address = params.token_a.parse().context("Invalid token_a address")?;
+        let token_b: Option<Address> = params
+            .token_b
+            .as_deref()
+            .map(|addr| addr.parse().context("Invalid token_b address"))
+            .transpose()?;
+        if token_b == Some(token_a) {
+            anyhow::bail!("token_a and token_b must differ");
+        }
+
+        let pools = self
+            .discovery
+            .find_pools(token_a, token_b, params.from_block)
+            .await?;
+        let page = params.page.paginate(pools)?;
+
+        // Only the returned page is queried for depth
+        let mut items = Vec::with_capacity(page.items.len());
+        for pool in page.items {
+            let mut entry = PoolEntry {
+                dex: pool.dex.clone(),
+                pool_address: format!("{:?}", pool.pool_address),
+                token0: format!("{:?}", pool.token0),
+                token1: format!("{:?}", pool.token1),
+                fee_tier: pool.fee,
+                creation_block: pool.creation_block,
+                reserve0: None,
+                reserve1: None,
+                liquidity: None,
+                liquidity_error: None,
+            };
+            match self.discovery.get_liquidity(&pool).await {
+                Ok(PoolLiquidity::Reserves { reserve0, reserve1 }) => {
+                    entry.reserve0 = Some(reserve0.to_string());
+                    entry.reserve1 = Some(reserve1.to_string());
+                }
+                Ok(PoolLiquidity::InRange { liquidity }) => {
+                    entry.liquidity = Some(liquidity.to_string());
+                }
+                Err(e) => entry.liquidity_error = Some(e.to_string()),
+            }
+            items.push(entry);
+        }
+
+        let result = Page {
+            items,
+            total: page.total,
+            next_cursor: page.next_cursor,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,128 @@
+use crate::ethereum::EthereumClientTrait;
+use anyhow::Result;
+use ethers::types::BlockNumber;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `max_staleness` parameter shared by price and balance tools.
+///
+/// Flatten into a tool's params struct with `#[serde(flatten)]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FreshnessParams {
+    /// Reject answers when the node's latest block is older than this many seconds
+    #[serde(default)]
+    pub max_staleness: Option<u64>,
+}
+
+/// Which block a read tool answers from, for users who cannot act on state that a
+/// reorg might still undo
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finality {
+    /// The head block
+    #[default]
+    Latest,
+    /// The head the consensus layer considers unlikely to be reorged
+    Safe,
+    /// The latest finalized block, reorged only if a third of validators are slashed
+    Finalized,
+}
+
+impl Finality {
+    pub fn block_tag(self) -> BlockNumber {
+        match self {
+            Finality::Latest => BlockNumber::Latest,
+            Finality::Safe => BlockNumber::Safe,
+            Finality::Finalized => BlockNumber::Finalized,
+        }
+    }
+
+    /// Block to read at: `block` if given, else the block this finality tags. None
+    /// reads the head, as without a finality.
+    pub async fn resolve<C: EthereumClientTrait + ?Sized>(
+        self,
+        client: &C,
+        block: Option<u64>,
+    ) -> Result<Option<u64>> {
+        match (block, self) {
+            (Some(_), Finality::Safe | Finality::Finalized) => {
+                anyhow::bail!("Pass either block or finality, not both")
+            }
+            (Some(block), Finality::Latest) => Ok(Some(block)),
+            (None, Finality::Latest) => Ok(None),
+            (None, finality) => Ok(Some(client.get_block(finality.block_tag()).await?.number)),
+        }
+    }
+}
+
+/// Block context of a result, flattened into price and balance outputs
+#[derive(Debug, Clone, Serialize)]
+pub struct Freshness {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub finality: Finality,
+    pub rpc_endpoint: String,
+}
+
+impl FreshnessParams {
+    /// Read the latest block before answering, enforcing `max_staleness`
+    pub async fn check<C: EthereumClientTrait + ?Sized>(&self, client: &C) -> Result<Freshness> {
+        self.check_at(client, Finality::Latest).await
+    }
+
+    /// Read the block `finality` tags before answering, enforcing `max_staleness` on it
+    pub async fn check_at<C: EthereumClientTrait + ?Sized>(
+        &self,
+        client: &C,
+        finality: Finality,
+    ) -> Result<Freshness> {
+        let block = client.get_block(finality.block_tag()).await?;
+        if let Some(max_staleness) = self.max_staleness {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let age = now.saturating_sub(block.timestamp);
+            if age > max_staleness {
+                anyhow::bail!(
+                    "{} block {} from {} is {}s old, exceeding max_staleness of {}s",
+                    match finality {
+                        Finality::Latest => "Latest",
+                        Finality::Safe => "Safe",
+                        Finality::Finalized => "Finalized",
+                    },
+                    block.number,
+                    client.rpc_endpoint(),
+                    age,
+                    max_staleness
+                );
+            }
+        }
+        Ok(Freshness {
+            block_number: block.number,
+            block_timestamp: block.timestamp,
+            finality,
+            rpc_endpoint: client.rpc_endpoint(),
+        })
+    }
+}
+
+/// JSON schema property for `max_staleness`, merged into a tool's input schema
+pub fn freshness_schema_properties() -> Value {
+    json!({
+        "max_staleness": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Fail instead of answering if the node's latest block is older than this many seconds"
+        }
+    })
+}
+
+/// JSON schema property for `finality`, merged into a read tool's input schema
+pub fn finality_schema_properties() -> Value {
+    json!({
+        "finality": {
+            "type": "string",
+            "enum": ["latest", "safe", "finalized"],
+            "description": "Block to read from: the head (latest, default), the safe head, or the latest finalized block, which lags the head by about 13 minutes but cannot be reorged"
+        }
+    })
+}
@@ -0,0 +1,62 @@
+use super::units::{from_base_units, to_base_units};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::Result;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+
+/// ETH kept back for future gas by default
+pub const DEFAULT_GAS_RESERVE_ETH: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// Minimum ETH the wallet must still hold after a write, so it can pay for later ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GasReserve {
+    /// Wei left after a transaction's value and worst-case fee; zero disables the check
+    pub min_balance: U256,
+    /// Whether calls may pass `override_gas_reserve` to skip the check
+    pub allow_override: bool,
+}
+
+impl GasReserve {
+    /// Reject `override_gas_reserve` unless the server's policy allows it
+    pub fn check_override(&self, override_requested: bool) -> Result<()> {
+        if override_requested && !self.allow_override {
+            anyhow::bail!(
+                "override_gas_reserve is not allowed on this server; set ALLOW_GAS_RESERVE_OVERRIDE=true"
+            );
+        }
+        Ok(())
+    }
+
+    /// Error unless `balance` wei covers `spend` wei and still leaves the reserve
+    pub fn ensure(&self, balance: U256, spend: U256) -> Result<()> {
+        let remaining = balance.saturating_sub(spend);
+        if self.min_balance.is_zero() || (balance >= spend && remaining >= self.min_balance) {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Spending up to {} ETH would leave {} ETH, below the {} ETH reserve kept for gas",
+            from_base_units(spend, 18)?.normalize(),
+            from_base_units(remaining, 18)?.normalize(),
+            from_base_units(self.min_balance, 18)?.normalize()
+        )
+    }
+
+    /// Check that `wallet` keeps the reserve after spending `spend` wei
+    pub async fn check<C: EthereumClientTrait + ?Sized>(
+        &self,
+        client: &C,
+        wallet: Address,
+        spend: U256,
+    ) -> Result<()> {
+        if self.min_balance.is_zero() {
+            return Ok(());
+        }
+        let balance = to_base_units(client.get_eth_balance(wallet).await?, 18)?;
+        self.ensure(balance, spend)
+    }
+}
+
+/// Most a transaction can take from its sender: its value plus gas at the fee ceiling
+pub fn max_spend(value: U256, gas: U256, max_fee_per_gas: U256) -> U256 {
+    value.saturating_add(gas.saturating_mul(max_fee_per_gas))
+}
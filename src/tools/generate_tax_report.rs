@@ -0,0 +1,178 @@
+use super::trade_ledger::{cents, date, AccountingMethod, Disposal, HoldingTerm, TradeLedger};
+use super::Tool;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const CSV_HEADER: &str = "Description of property,Date acquired,Date sold or disposed of,Proceeds,Cost or other basis,Gain or (loss),Term,Transaction";
+
+pub struct GenerateTaxReportTool {
+    ledger: Arc<TradeLedger>,
+    method: AccountingMethod,
+}
+
+impl GenerateTaxReportTool {
+    /// `method` applies when a call does not choose one
+    pub fn new(ledger: Arc<TradeLedger>, method: AccountingMethod) -> Self {
+        Self { ledger, method }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ReportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateTaxReportParams {
+    year: i64,
+    #[serde(default)]
+    method: Option<AccountingMethod>,
+    #[serde(default)]
+    format: ReportFormat,
+}
+
+#[derive(Debug, Serialize)]
+struct GenerateTaxReportResult {
+    year: i64,
+    method: AccountingMethod,
+    disposals: usize,
+    total_proceeds: Decimal,
+    total_cost_basis: Decimal,
+    short_term_gain: Decimal,
+    long_term_gain: Decimal,
+    /// Rows left out of the totals for a missing price or acquisition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incomplete_rows: Option<usize>,
+    /// Form 8949-style rows, one per lot a disposal drew from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    csv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows: Option<Vec<Disposal>>,
+}
+
+/// Quote a CSV field when it needs it
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(disposals: &[Disposal]) -> String {
+    let amount = |value: Option<Decimal>| value.map(|v| v.to_string()).unwrap_or_default();
+    let mut out = String::from(CSV_HEADER);
+    for row in disposals {
+        let term = match row.term {
+            HoldingTerm::Short => "short",
+            HoldingTerm::Long => "long",
+            HoldingTerm::Unknown => "unknown",
+        };
+        let cells = [
+            field(&format!("{} {}", row.amount, row.symbol)),
+            row.acquired_at.map_or("UNKNOWN".to_string(), date),
+            date(row.disposed_at),
+            amount(row.proceeds),
+            amount(row.cost_basis),
+            amount(row.gain),
+            term.to_string(),
+            row.tx_hash
+                .map(|hash| format!("{:?}", hash))
+                .unwrap_or_default(),
+        ];
+        out.push('\n');
+        out.push_str(&cells.join(","));
+    }
+    out.push('\n');
+    out
+}
+
+#[async_trait]
+impl Tool for GenerateTaxReportTool {
+    fn name(&self) -> &str {
+        "generate_tax_report"
+    }
+
+    fn description(&self) -> &str {
+        "Tax lot report of the swaps the server executed for a calendar year (UTC), in the style of IRS Form 8949: one row per disposal and lot, with the date acquired, date sold, proceeds, cost basis, gain or loss and whether it was held more than a year. Lots are matched FIFO, LIFO or HIFO (default: the server's method). Trades are valued in dollars from their stablecoin side, or from an oracle price when they were recorded; dollar stablecoins count as cash. Amounts bought before the ledger started have no basis and are marked UNKNOWN. Returns CSV by default. Not tax advice."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "year": {
+                    "type": "integer",
+                    "description": "Calendar year of the disposals, e.g. 2025"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": ["fifo", "lifo", "hifo"],
+                    "description": "Which lots are sold first: oldest, newest or highest cost (default: the server's TAX_ACCOUNTING_METHOD)"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["csv", "json"],
+                    "description": "Rows as a CSV document or as JSON objects (default: csv)"
+                }
+            },
+            "required": ["year"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GenerateTaxReportParams =
+            serde_json::from_value(params).context("Invalid parameters for generate_tax_report")?;
+        if !(1970..=9999).contains(&params.year) {
+            anyhow::bail!("year must be between 1970 and 9999");
+        }
+        let method = params.method.unwrap_or(self.method);
+        let disposals = self.ledger.disposals(params.year, method);
+
+        let mut total_proceeds = Decimal::ZERO;
+        let mut total_cost_basis = Decimal::ZERO;
+        let mut short_term_gain = Decimal::ZERO;
+        let mut long_term_gain = Decimal::ZERO;
+        let mut incomplete = 0;
+        for row in &disposals {
+            let (Some(proceeds), Some(cost_basis), Some(gain)) =
+                (row.proceeds, row.cost_basis, row.gain)
+            else {
+                incomplete += 1;
+                continue;
+            };
+            total_proceeds += proceeds;
+            total_cost_basis += cost_basis;
+            match row.term {
+                HoldingTerm::Long => long_term_gain += gain,
+                _ => short_term_gain += gain,
+            }
+        }
+
+        let count = disposals.len();
+        let (csv, rows) = match params.format {
+            ReportFormat::Csv => (Some(csv(&disposals)), None),
+            ReportFormat::Json => (None, Some(disposals)),
+        };
+        let result = GenerateTaxReportResult {
+            year: params.year,
+            method,
+            disposals: count,
+            total_proceeds: cents(total_proceeds),
+            total_cost_basis: cents(total_cost_basis),
+            short_term_gain: cents(short_term_gain),
+            long_term_gain: cents(long_term_gain),
+            incomplete_rows: Some(incomplete).filter(|count| *count > 0),
+            csv,
+            rows,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -1,4 +1,11 @@
-use super::Tool;
+use super::address_book::{resolve_address, AddressBook};
+use super::explain::display;
+use super::freshness::{
+    finality_schema_properties, freshness_schema_properties, Finality, Freshness, FreshnessParams,
+};
+use super::interfaces::{detect_token_standard, erc1155_balance, erc721_balance, TokenStandard};
+use super::units::from_base_units;
+use super::{merge_schema_properties, Explanation, Tool};
 use crate::ethereum::EthereumClientTrait;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -9,11 +16,18 @@ use std::sync::Arc;
 
 pub struct GetBalanceTool<C: EthereumClientTrait> {
     client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
 }
 
 impl<C: EthereumClientTrait> GetBalanceTool<C> {
     pub fn new(client: Arc<C>) -> Self {
-        Self { client }
+        Self { client, book: None }
+    }
+
+    /// Accept contact names from `book` in place of the wallet address
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
     }
 }
 
@@ -21,6 +35,12 @@ impl<C: EthereumClientTrait> GetBalanceTool<C> {
 struct GetBalanceParams {
     address: String,
     token_address: Option<String>,
+    /// Required for ERC-1155 tokens
+    token_id: Option<String>,
+    #[serde(default)]
+    finality: Finality,
+    #[serde(flatten)]
+    freshness: FreshnessParams,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,6 +49,13 @@ struct GetBalanceResult {
     balance: String,
     symbol: String,
     decimals: u8,
+    /// Unset for ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    standard: Option<TokenStandard>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_id: Option<String>,
+    #[serde(flatten)]
+    freshness: Freshness,
 }
 
 #[async_trait]
@@ -38,40 +65,106 @@ impl<C: EthereumClientTrait + 'static> Tool for GetBalanceTool<C> {
     }
 
     fn description(&self) -> &str {
-        "Query ETH or ERC20 token balance for a given wallet address"
+        "Query ETH or token balance for a given wallet address. The token standard is detected and reported: ERC20 and ERC777 balances in whole tokens, ERC721 as the number of NFTs held, and ERC1155 for the token_id given. Set finality to safe or finalized to read the balance at a block that cannot be reorged instead of the head."
     }
 
     fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "address": {
+                "type": "string",
+                "description": "The wallet address to query, or a contact name"
+            },
+            "token_address": {
+                "type": "string",
+                "description": "Optional token contract address (ERC20, ERC777, ERC721 or ERC1155). If omitted, returns ETH balance"
+            },
+            "token_id": {
+                "type": "string",
+                "description": "Token id, required for ERC1155 tokens"
+            }
+        });
+        merge_schema_properties(&mut properties, freshness_schema_properties());
+        merge_schema_properties(&mut properties, finality_schema_properties());
+
         json!({
             "type": "object",
-            "properties": {
-                "address": {
-                    "type": "string",
-                    "description": "The wallet address to query"
-                },
-                "token_address": {
-                    "type": "string",
-                    "description": "Optional ERC20 token contract address. If omitted, returns ETH balance"
-                }
-            },
+            "properties": properties,
             "required": ["address"]
         })
     }
 
+    fn explain(&self, params: &Value, result: &Value) -> Explanation {
+        let source = match params.get("token_address") {
+            Some(token) => format!("balanceOf on token contract {}", token),
+            None => "eth_getBalance".to_string(),
+        };
+        Explanation::new(
+            format!(
+                "Read the raw balance of {} via {} and divided it by 10^{} to get {} {}.",
+                display(&result["address"]),
+                source,
+                display(&result["decimals"]),
+                display(&result["balance"]),
+                display(&result["symbol"])
+            ),
+            json!({
+                "inputs": params,
+                "source": source,
+                "decimals_applied": result["decimals"],
+                "formula": "balance = raw_balance / 10^decimals",
+            }),
+        )
+    }
+
     async fn execute(&self, params: Value) -> Result<Value> {
         let params: GetBalanceParams =
             serde_json::from_value(params).context("Invalid parameters for get_balance")?;
 
-        let address: Address = params.address.parse().context("Invalid wallet address")?;
+        let address = resolve_address(self.book.as_deref(), &params.address)
+            .context("Invalid wallet address")?;
+        let freshness = params
+            .freshness
+            .check_at(self.client.as_ref(), params.finality)
+            .await?;
+        // Behind the head, every read is made at the block reported
+        let block = (params.finality != Finality::Latest).then_some(freshness.block_number);
 
         let result = if let Some(token_addr_str) = params.token_address {
-            // Get ERC20 token balance
             let token_address: Address = token_addr_str.parse().context("Invalid token address")?;
+            let standard = detect_token_standard(self.client.as_ref(), token_address).await;
+            let token_id = match (&params.token_id, standard) {
+                (Some(id), TokenStandard::Erc1155) => {
+                    Some(U256::from_dec_str(id.trim()).context("Invalid token_id")?)
+                }
+                (None, TokenStandard::Erc1155) => {
+                    anyhow::bail!(
+                        "{:?} is an ERC-1155 token; pass its token_id",
+                        token_address
+                    )
+                }
+                (Some(_), _) => anyhow::bail!("token_id applies only to ERC-1155 tokens"),
+                (None, _) => None,
+            };
 
-            let (balance, decimals) = self
-                .client
-                .get_token_balance(token_address, address)
-                .await?;
+            let (balance, decimals) = match (standard, token_id) {
+                (TokenStandard::Erc1155, Some(id)) => {
+                    let raw =
+                        erc1155_balance(self.client.as_ref(), token_address, address, id, block)
+                            .await?;
+                    (from_base_units(raw, 0)?, 0)
+                }
+                (TokenStandard::Erc721, _) => {
+                    let raw =
+                        erc721_balance(self.client.as_ref(), token_address, address, block).await?;
+                    (from_base_units(raw, 0)?, 0)
+                }
+                // ERC-777 keeps the ERC-20 balanceOf and decimals
+                _ => {
+                    self.client
+                        .get_token_balance_at(token_address, address, block)
+                        .await?
+                }
+            };
             let symbol = self
                 .client
                 .get_token_symbol(token_address)
@@ -83,16 +176,22 @@ impl<C: EthereumClientTrait + 'static> Tool for GetBalanceTool<C> {
                 balance: balance.to_string(),
                 symbol,
                 decimals,
+                standard: Some(standard),
+                token_id: token_id.map(|id| id.to_string()),
+                freshness,
             }
         } else {
             // Get ETH balance
-            let balance = self.client.get_eth_balance(address).await?;
+            let balance = self.client.get_eth_balance_at(address, block).await?;
 
             GetBalanceResult {
                 address: params.address,
                 balance: balance.to_string(),
                 symbol: "ETH".to_string(),
                 decimals: 18,
+                standard: None,
+                token_id: None,
+                freshness,
             }
         };
 
@@ -0,0 +1,169 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::freshness::{finality_schema_properties, Finality};
+use super::multichain::{
+    chain_selection_schema_properties, fetch_holdings, for_each_chain, ChainSelection,
+    TokenBalance, DEFAULT_CHAIN_QUERY_CONCURRENCY,
+};
+use super::{merge_schema_properties, Tool};
+use crate::ethereum::chains::{chain_name, ChainClients};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub struct GetBalanceAllChainsTool {
+    chains: Arc<ChainClients>,
+    max_parallel: usize,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl GetBalanceAllChainsTool {
+    pub fn new(chains: Arc<ChainClients>) -> Self {
+        Self {
+            chains,
+            max_parallel: DEFAULT_CHAIN_QUERY_CONCURRENCY,
+            book: None,
+        }
+    }
+
+    /// Query at most `max_parallel` chains at once
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    /// Accept contact names from `book` in place of the wallet address
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetBalanceAllChainsParams {
+    address: String,
+    #[serde(default)]
+    chain_ids: Option<Vec<u64>>,
+    /// ERC20 contracts to include, keyed by chain id
+    #[serde(default)]
+    tokens: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    finality: Finality,
+}
+
+#[derive(Debug, Serialize)]
+struct ChainBalance {
+    chain_id: u64,
+    chain: String,
+    rpc_endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    native: Option<TokenBalance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tokens: Vec<TokenBalance>,
+    /// Why this chain could not be read; the other chains are unaffected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetBalanceAllChainsResult {
+    address: String,
+    finality: Finality,
+    chains: Vec<ChainBalance>,
+    succeeded: usize,
+    failed: usize,
+}
+
+#[async_trait]
+impl Tool for GetBalanceAllChainsTool {
+    fn name(&self) -> &str {
+        "get_balance_all_chains"
+    }
+
+    fn description(&self) -> &str {
+        "Query a wallet's native balance, and optionally ERC20 balances, on every configured chain at once. Chains are queried in parallel; a chain whose RPC fails or times out is reported with its error while the others still return balances. Set finality to safe or finalized to read each chain at a block that cannot be reorged."
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "address": {
+                "type": "string",
+                "description": "The wallet address to query, or a contact name"
+            }
+        });
+        merge_schema_properties(
+            &mut properties,
+            chain_selection_schema_properties(&self.chains),
+        );
+        merge_schema_properties(&mut properties, finality_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["address"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetBalanceAllChainsParams = serde_json::from_value(params)
+            .context("Invalid parameters for get_balance_all_chains")?;
+
+        let address = resolve_address(self.book.as_deref(), &params.address)
+            .context("Invalid wallet address")?;
+        let selection = ChainSelection::parse(&self.chains, params.chain_ids, &params.tokens)?;
+
+        let outcomes = for_each_chain(
+            &self.chains,
+            &selection.chain_ids,
+            self.max_parallel,
+            |chain_id, client| {
+                let tokens = selection.tokens_for(chain_id);
+                let finality = params.finality;
+                async move { fetch_holdings(client.as_ref(), address, &tokens, finality).await }
+            },
+        )
+        .await?;
+
+        let chains: Vec<ChainBalance> = outcomes
+            .into_iter()
+            .map(|(chain_id, outcome)| {
+                let mut chain = ChainBalance {
+                    chain_id,
+                    chain: chain_name(chain_id),
+                    rpc_endpoint: self
+                        .chains
+                        .get(chain_id)
+                        .map(|client| client.rpc_endpoint())
+                        .unwrap_or_default(),
+                    block_number: None,
+                    native: None,
+                    tokens: Vec::new(),
+                    error: None,
+                };
+                match outcome {
+                    Ok(holdings) => {
+                        chain.block_number = Some(holdings.block_number);
+                        chain.native = Some(holdings.native);
+                        chain.tokens = holdings.tokens;
+                    }
+                    Err(e) => chain.error = Some(format!("{:#}", e)),
+                }
+                chain
+            })
+            .collect();
+
+        let failed = chains.iter().filter(|chain| chain.error.is_some()).count();
+        let result = GetBalanceAllChainsResult {
+            address: params.address,
+            finality: params.finality,
+            succeeded: chains.len() - failed,
+            failed,
+            chains,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
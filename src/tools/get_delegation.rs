@@ -0,0 +1,155 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::{Tool, ToolCategory};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// How a holder's governance tokens are put to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DelegationStatus {
+    /// Never delegated: the tokens carry no votes
+    Undelegated,
+    /// Delegated to the holder itself, to vote directly
+    SelfDelegated,
+    /// Voted by someone else
+    Delegated,
+}
+
+impl DelegationStatus {
+    pub(crate) fn of(holder: Address, delegate: Option<Address>) -> Self {
+        match delegate {
+            None => Self::Undelegated,
+            Some(delegate) if delegate == holder => Self::SelfDelegated,
+            Some(_) => Self::Delegated,
+        }
+    }
+}
+
+pub struct GetDelegationTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetDelegationTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept a contact name as the wallet, and name known delegates
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetDelegationParams {
+    token: String,
+    #[serde(default)]
+    wallet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetDelegationResult {
+    token: Address,
+    symbol: String,
+    wallet: Address,
+    balance: String,
+    status: DelegationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate_name: Option<String>,
+    /// Votes delegated to the wallet, by itself or others
+    votes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetDelegationTool<C> {
+    fn name(&self) -> &str {
+        "get_delegation"
+    }
+
+    fn description(&self) -> &str {
+        "Show how a wallet's ERC20Votes governance tokens (UNI, ENS, COMP, ...) are delegated: undelegated (no votes), self-delegated or delegated to another address, with its balance and the votes delegated to it. Defaults to the server's wallet; change the delegation with delegate_votes."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "Governance token address"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Address or contact name (default: the server's wallet)"
+                }
+            },
+            "required": ["token"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetDelegationParams =
+            serde_json::from_value(params).context("Invalid parameters for get_delegation")?;
+        let token: Address = params
+            .token
+            .trim()
+            .parse()
+            .context("Invalid token address")?;
+        let wallet = match params.wallet.as_deref() {
+            Some(wallet) => {
+                resolve_address(self.book.as_deref(), wallet).context("Invalid wallet")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+
+        let delegation = self.client.get_delegation(token, wallet).await?;
+        let (balance, _) = self
+            .client
+            .get_token_balance(token, wallet)
+            .await
+            .context("Failed to read token balance")?;
+        let symbol = self
+            .client
+            .get_token_symbol(token)
+            .await
+            .unwrap_or_else(|_| "UNKNOWN".to_string());
+        let status = DelegationStatus::of(wallet, delegation.delegate);
+        let hint = (status == DelegationStatus::Undelegated && balance > Decimal::ZERO).then(|| {
+            format!(
+                "The wallet's {} carry no votes until delegated; delegate_votes with delegatee 'self' activates them",
+                symbol
+            )
+        });
+
+        let result = GetDelegationResult {
+            token,
+            symbol,
+            wallet,
+            balance: balance.normalize().to_string(),
+            status,
+            delegate: delegation.delegate,
+            delegate_name: delegation
+                .delegate
+                .and_then(|delegate| self.book.as_ref()?.name_of(delegate)),
+            votes: delegation.votes.normalize().to_string(),
+            hint,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,169 @@
+use super::dex_stats::{DexStats, PoolStats, DEX_STATS_WINDOW_BLOCKS};
+use super::{Tool, ToolCategory};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct GetDexStatsTool {
+    stats: Arc<DexStats>,
+}
+
+impl GetDexStatsTool {
+    pub fn new(stats: Arc<DexStats>) -> Self {
+        Self { stats }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetDexStatsParams {
+    #[serde(default)]
+    pools: Option<Vec<String>>,
+    /// `volume` or `tvl`
+    #[serde(default)]
+    sort_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PoolReport {
+    #[serde(flatten)]
+    stats: PoolStats,
+    age_secs: u64,
+    /// Set when the latest refresh failed and these figures are older
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PendingPool {
+    name: String,
+    pool: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetDexStatsResult {
+    pools: Vec<PoolReport>,
+    /// Pools not read yet, or that have never read successfully
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pending: Vec<PendingPool>,
+    /// Sums over pools whose figures are priced
+    total_tvl_usd: Decimal,
+    total_volume_24h_usd: Decimal,
+    window_blocks: u64,
+}
+
+#[async_trait]
+impl Tool for GetDexStatsTool {
+    fn name(&self) -> &str {
+        "get_dex_stats"
+    }
+
+    fn description(&self) -> &str {
+        "24h volume, swap count, fees and TVL for the configured Uniswap V2/V3 pools, computed from their Swap events and reserves on-chain (no external API) and valued with Chainlink prices. Figures are refreshed in the background, so this answers instantly; age_secs says how old they are. Read-only."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pools": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only these pools, by configured name or address (default: all)"
+                },
+                "sort_by": {
+                    "type": "string",
+                    "enum": ["volume", "tvl"],
+                    "description": "Order pools by 24h USD volume or by TVL, largest first (default: volume)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetDexStatsParams =
+            serde_json::from_value(params).context("Invalid parameters for get_dex_stats")?;
+        let by_tvl = match params.sort_by.as_deref().map(str::trim) {
+            None | Some("volume") => false,
+            Some("tvl") => true,
+            Some(other) => anyhow::bail!("Unknown sort_by {}: expected volume or tvl", other),
+        };
+
+        let mut selected: Vec<&(String, Address)> = self.stats.pools().iter().collect();
+        if let Some(wanted) = &params.pools {
+            let matches = |(name, pool): &(String, Address), wanted: &str| {
+                name.eq_ignore_ascii_case(wanted.trim())
+                    || wanted.trim().parse::<Address>().ok() == Some(*pool)
+            };
+            if let Some(unknown) = wanted
+                .iter()
+                .find(|w| !selected.iter().any(|entry| matches(entry, w)))
+            {
+                anyhow::bail!(
+                    "No configured pool named {}; configured pools are {}",
+                    unknown,
+                    self.stats
+                        .pools()
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            selected.retain(|entry| wanted.iter().any(|w| matches(entry, w)));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut pools = Vec::new();
+        let mut pending = Vec::new();
+        for (name, pool) in selected {
+            let error = self.stats.error(*pool);
+            match self.stats.stats(*pool) {
+                Some(stats) => pools.push(PoolReport {
+                    age_secs: now.saturating_sub(stats.refreshed_at),
+                    stats,
+                    refresh_error: error,
+                }),
+                None => pending.push(PendingPool {
+                    name: name.clone(),
+                    pool: *pool,
+                    error,
+                }),
+            }
+        }
+        pools.sort_by_key(|report| {
+            std::cmp::Reverse(if by_tvl {
+                report.stats.tvl_usd
+            } else {
+                report.stats.volume_24h_usd
+            })
+        });
+
+        let result = GetDexStatsResult {
+            total_tvl_usd: pools
+                .iter()
+                .filter_map(|report| report.stats.tvl_usd)
+                .sum::<Decimal>()
+                .normalize(),
+            total_volume_24h_usd: pools
+                .iter()
+                .filter_map(|report| report.stats.volume_24h_usd)
+                .sum::<Decimal>()
+                .normalize(),
+            pools,
+            pending,
+            window_blocks: DEX_STATS_WINDOW_BLOCKS,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,172 @@
+use super::address_book::AddressBook;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{normalize_name, EnsName, EnsSource, EthereumClientTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Registrations this close to expiry are flagged
+const EXPIRY_WARNING_SECS: u64 = 30 * 86_400;
+
+/// Where a name stands in its registration lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EnsStatus {
+    /// Registered and unexpired, or a subname that is owned or resolves
+    Active,
+    /// Expired but still renewable, and not yet open to anyone else
+    GracePeriod,
+    /// Never registered or released after its grace period: open to register
+    Available,
+}
+
+impl EnsStatus {
+    pub(crate) fn of(name: &EnsName, now: u64) -> Self {
+        match (name.expires, name.grace_ends()) {
+            (Some(expires), _) if expires > now => Self::Active,
+            (Some(_), Some(grace_ends)) if grace_ends > now => Self::GracePeriod,
+            (Some(_), _) => Self::Available,
+            (None, _) if name.owner.is_some() || name.address.is_some() => Self::Active,
+            (None, _) => Self::Available,
+        }
+    }
+}
+
+/// An ENS name as the ENS tools report it
+#[derive(Debug, Serialize)]
+pub(crate) struct EnsNameReport {
+    name: String,
+    status: EnsStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registrant: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolver: Option<Address>,
+    /// Address the name resolves to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grace_ends: Option<u64>,
+    /// Whole days until expiry, while active
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in_days: Option<u64>,
+    wrapped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl EnsNameReport {
+    pub(crate) fn new(name: EnsName, now: u64, book: Option<&AddressBook>) -> Self {
+        let status = EnsStatus::of(&name, now);
+        let grace_ends = name.grace_ends();
+        let expires = name.expires.filter(|expires| *expires > 0);
+        let expires_in_days = expires
+            .filter(|_| status == EnsStatus::Active)
+            .map(|expires| (expires - now) / 86_400);
+        let hint = match (status, expires) {
+            (EnsStatus::GracePeriod, _) => Some(format!(
+                "Expired: renew with renew_ens_name before {} or the name is released",
+                grace_ends.unwrap_or_default()
+            )),
+            (EnsStatus::Active, Some(expires)) if expires - now < EXPIRY_WARNING_SECS => {
+                Some("Expires within 30 days; renew with renew_ens_name".to_string())
+            }
+            _ => None,
+        };
+        Self {
+            status,
+            owner: name.owner,
+            owner_name: name.owner.and_then(|owner| book?.name_of(owner)),
+            registrant: name.registrant,
+            resolver: name.resolver,
+            address: name.address,
+            expires,
+            grace_ends,
+            expires_in_days,
+            wrapped: name.wrapped,
+            hint,
+            name: name.name,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn status(&self) -> EnsStatus {
+        self.status
+    }
+}
+
+pub struct GetEnsNameTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    ens: Arc<dyn EnsSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetEnsNameTool<C> {
+    pub fn new(client: Arc<C>, ens: Arc<dyn EnsSource>) -> Self {
+        Self {
+            client,
+            ens,
+            book: None,
+        }
+    }
+
+    /// Name owners found in the address book
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetEnsNameParams {
+    name: String,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetEnsNameTool<C> {
+    fn name(&self) -> &str {
+        "get_ens_name"
+    }
+
+    fn description(&self) -> &str {
+        "Look up an ENS name: its owner, registrant, resolver and resolved address (following CCIP-Read for names served off-chain, such as wildcard subnames), and for .eth names the registration expiry and the 90-day grace period after it. Status is active, grace_period (expired but still renewable by anyone) or available. Names expiring within 30 days are flagged."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "ENS name, e.g. 'vitalik.eth'; a bare label is taken as a .eth name"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetEnsNameParams =
+            serde_json::from_value(params).context("Invalid parameters for get_ens_name")?;
+        let name = normalize_name(&params.name)?;
+        let now = self.client.get_latest_block().await?.timestamp;
+        let found = self.ens.lookup(&name).await?;
+        let report = EnsNameReport::new(found, now, self.book.as_deref());
+        Ok(serde_json::to_value(report)?)
+    }
+}
@@ -0,0 +1,176 @@
+use super::execution_reports::{ExecutionReport, ExecutionReports};
+use super::Tool;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+
+// Realized output this far below the re-quote at sending is worth a note
+const SLIPPAGE_NOTE_BPS: i64 = -30;
+// Gas use this far above the estimate is worth a note
+const GAS_NOTE_PERCENT: i64 = 120;
+
+pub struct GetExecutionReportTool {
+    reports: Arc<ExecutionReports>,
+}
+
+impl GetExecutionReportTool {
+    pub fn new(reports: Arc<ExecutionReports>) -> Self {
+        Self { reports }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetExecutionReportParams {
+    #[serde(default)]
+    quote_id: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ExecutionSummary {
+    executions: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_slippage_bps: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worst_slippage_bps: Option<Decimal>,
+    /// Average shortfall against the re-quote made just before sending
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_slippage_since_send_bps: Option<Decimal>,
+    mev_suspected: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_gas_used_percent_of_estimate: Option<Decimal>,
+    /// What the reports suggest doing differently
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    observations: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetExecutionReportResult {
+    summary: ExecutionSummary,
+    /// Newest first
+    executions: Vec<ExecutionReport>,
+    /// Reports kept beyond those returned
+    truncated: bool,
+}
+
+fn average(values: &[Decimal]) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+    let total: Decimal = values.iter().sum();
+    Some(
+        (total / Decimal::from(values.len()))
+            .round_dp(2)
+            .normalize(),
+    )
+}
+
+fn summarize(reports: &[ExecutionReport]) -> ExecutionSummary {
+    let slippage: Vec<Decimal> = reports
+        .iter()
+        .filter_map(|report| report.realized_slippage_bps)
+        .collect();
+    let since_send: Vec<Decimal> = reports
+        .iter()
+        .filter_map(|report| report.slippage_since_send_bps)
+        .collect();
+    let gas: Vec<Decimal> = reports
+        .iter()
+        .filter_map(|report| report.gas_used_percent_of_estimate)
+        .collect();
+    let mut summary = ExecutionSummary {
+        executions: reports.len(),
+        average_slippage_bps: average(&slippage),
+        worst_slippage_bps: slippage.iter().min().copied(),
+        average_slippage_since_send_bps: average(&since_send),
+        mev_suspected: reports.iter().filter(|report| report.mev_suspected).count(),
+        average_gas_used_percent_of_estimate: average(&gas),
+        observations: Vec::new(),
+    };
+
+    if summary.mev_suspected > 0 {
+        summary.observations.push(format!(
+            "{} of {} swaps were sandwiched; send through a private mempool or lower the slippage tolerance",
+            summary.mev_suspected, summary.executions
+        ));
+    }
+    if let Some(since_send) = summary
+        .average_slippage_since_send_bps
+        .filter(|bps| *bps < Decimal::from(SLIPPAGE_NOTE_BPS))
+    {
+        summary.observations.push(format!(
+            "Swaps filled {} bps below the price at sending on average; trade smaller amounts or in quieter blocks",
+            since_send.abs()
+        ));
+    }
+    if let Some(gas) = summary
+        .average_gas_used_percent_of_estimate
+        .filter(|percent| *percent > Decimal::from(GAS_NOTE_PERCENT))
+    {
+        summary.observations.push(format!(
+            "Swaps used {}% of their estimated gas on average; budget more gas for them",
+            gas
+        ));
+    }
+    summary
+}
+
+#[async_trait]
+impl Tool for GetExecutionReportTool {
+    fn name(&self) -> &str {
+        "get_execution_report"
+    }
+
+    fn description(&self) -> &str {
+        "Report how swaps sent by execute_swap actually executed: the output received against the quote and against the re-quote made just before sending (realized slippage in bps), suspected MEV when another account traded the same pool right before and after the swap in its block, and gas used and paid against the estimate. Returns the latest executions with a summary and observations to adjust future trades by."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "quote_id": {
+                    "type": "string",
+                    "description": "Only report the execution of this quote"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_LIMIT,
+                    "description": "Most executions to return, newest first (default: 20)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetExecutionReportParams = serde_json::from_value(params)
+            .context("Invalid parameters for get_execution_report")?;
+
+        let mut reports = self.reports.reports();
+        if let Some(quote_id) = &params.quote_id {
+            reports.retain(|report| &report.quote_id == quote_id);
+            if reports.is_empty() {
+                anyhow::bail!("No execution recorded for quote {}", quote_id);
+            }
+        }
+        let summary = summarize(&reports);
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let truncated = reports.len() > limit;
+        reports.truncate(limit);
+
+        let result = GetExecutionReportResult {
+            summary,
+            executions: reports,
+            truncated,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,367 @@
+use super::alerts::{AlertSeverity, AlertSink};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{PerpMarket, PerpVenue, ReferencePriceSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_ASSETS: &[&str] = &["BTC", "ETH", "SOL"];
+const MAX_ASSETS: usize = 20;
+
+const HOURS_PER_YEAR: u32 = 365 * 24;
+
+/// Topic of notifications pushed for each threshold crossing
+pub const FUNDING_TOPIC: &str = "funding";
+
+// Mainnet tokens whose Chainlink feed gives each asset's spot price
+const SPOT_TOKENS: &[(&str, &str)] = &[
+    ("ETH", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+    ("BTC", "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+    ("LINK", "0x514910771AF9Ca656af840dff83E8264EcF986CA"),
+    ("UNI", "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"),
+    ("AAVE", "0x7Fc66500c84A76Ad7e9c93437bFc5Ac33E2DDaE9"),
+    ("MKR", "0x9f8F72aA9304c8B593d555F12eF6589cC3A579A2"),
+    ("SNX", "0xC011a73ee8576Fb46F5E1c5751cA3B9Fe0af2a6F"),
+];
+
+/// Which side of its threshold a (venue, asset, metric) was on at the last call
+type ThresholdState = HashMap<(String, String, &'static str), bool>;
+
+pub struct GetFundingRatesTool {
+    venues: Vec<Arc<dyn PerpVenue>>,
+    reference_prices: Option<Arc<dyn ReferencePriceSource>>,
+    alerts: Option<Arc<dyn AlertSink>>,
+    above: Mutex<ThresholdState>,
+}
+
+impl GetFundingRatesTool {
+    pub fn new(venues: Vec<Arc<dyn PerpVenue>>) -> Self {
+        Self {
+            venues,
+            reference_prices: None,
+            alerts: None,
+            above: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Measure basis against oracle spot prices rather than each venue's index
+    pub fn with_reference_prices(mut self, prices: Arc<dyn ReferencePriceSource>) -> Self {
+        self.reference_prices = Some(prices);
+        self
+    }
+
+    /// Push every threshold crossing to `alerts` as well as returning it
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Oracle spot price of `asset` and the feed it came from
+    async fn spot_price(&self, asset: &str) -> Option<(Decimal, String)> {
+        let prices = self.reference_prices.as_ref()?;
+        let (_, token) = SPOT_TOKENS
+            .iter()
+            .find(|(symbol, _)| symbol.eq_ignore_ascii_case(asset))?;
+        let price = prices.usd_price(token.parse().ok()?).await.ok()??;
+        Some((price.usd_price, price.source))
+    }
+
+    /// Record which side of `threshold` `value` is on, returning whether it just crossed
+    fn crossed(
+        &self,
+        key: (String, String, &'static str),
+        value: Decimal,
+        threshold: Decimal,
+    ) -> Option<bool> {
+        let above = value.abs() >= threshold;
+        let was_above = self
+            .above
+            .lock()
+            .unwrap()
+            .insert(key, above)
+            .unwrap_or(false);
+        (above != was_above).then_some(above)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetFundingRatesParams {
+    #[serde(default)]
+    assets: Option<Vec<String>>,
+    #[serde(default)]
+    alert_funding_apr_percent: Option<Decimal>,
+    #[serde(default)]
+    alert_basis_bps: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+struct VenueRate {
+    venue: String,
+    market: String,
+    funding_rate_hourly_percent: Decimal,
+    /// The hourly rate held for a year; positive when longs pay
+    funding_apr_percent: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mark_price: Option<Decimal>,
+    /// Mark against spot, in basis points; positive when the perp trades rich
+    #[serde(skip_serializing_if = "Option::is_none")]
+    basis_bps: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    open_interest_usd: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+struct AssetRates {
+    asset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spot_price: Option<Decimal>,
+    /// Oracle feed, or the venue whose index stood in for spot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spot_source: Option<String>,
+    /// Highest funding first
+    venues: Vec<VenueRate>,
+    /// Highest minus lowest funding APR, what a cross-venue funding trade would earn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    funding_spread_apr_percent: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+struct FundingAlert {
+    asset: String,
+    venue: String,
+    metric: String,
+    severity: AlertSeverity,
+    value: Decimal,
+    threshold: Decimal,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VenueError {
+    venue: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GetFundingRatesResult {
+    assets: Vec<AssetRates>,
+    /// Thresholds crossed since the previous call
+    alerts: Vec<FundingAlert>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed_venues: Vec<VenueError>,
+    /// Notifications delivered to connected clients
+    notifications_sent: usize,
+}
+
+/// The market for `asset` with the most open interest; GMX lists several per asset
+fn market_for<'a>(markets: &'a [PerpMarket], asset: &str) -> Option<&'a PerpMarket> {
+    markets
+        .iter()
+        .filter(|market| {
+            market.funding_rate_hourly.is_some()
+                && market
+                    .symbol
+                    .split('/')
+                    .next()
+                    .is_some_and(|base| base.trim().eq_ignore_ascii_case(asset))
+        })
+        .max_by_key(|market| market.open_interest_usd)
+}
+
+#[async_trait]
+impl Tool for GetFundingRatesTool {
+    fn name(&self) -> &str {
+        "get_funding_rates"
+    }
+
+    fn description(&self) -> &str {
+        "Compare perpetual funding rates for major assets across the configured perps exchanges (Hyperliquid, GMX): hourly and annualized funding, the spread between venues, and spot-perp basis against Chainlink spot (or the venue's index). Pass alert thresholds to be alerted when funding APR or basis crosses them between calls; alerts are returned and pushed to connected clients. Call repeatedly to poll."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "assets": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "maxItems": MAX_ASSETS,
+                    "description": "Assets to compare, such as ETH or BTC (default: BTC, ETH, SOL)"
+                },
+                "alert_funding_apr_percent": {
+                    "type": "string",
+                    "description": "Alert when a venue's annualized funding moves above or back below this magnitude, in percent"
+                },
+                "alert_basis_bps": {
+                    "type": "string",
+                    "description": "Alert when a venue's basis to spot moves above or back below this magnitude, in basis points"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetFundingRatesParams =
+            serde_json::from_value(params).context("Invalid parameters for get_funding_rates")?;
+        let assets: Vec<String> = match params.assets {
+            Some(assets) if assets.is_empty() => anyhow::bail!("Pass at least one asset"),
+            Some(assets) if assets.len() > MAX_ASSETS => {
+                anyhow::bail!("At most {} assets at once", MAX_ASSETS)
+            }
+            Some(assets) => assets
+                .iter()
+                .map(|asset| asset.trim().to_ascii_uppercase())
+                .collect(),
+            None => DEFAULT_ASSETS
+                .iter()
+                .map(|asset| asset.to_string())
+                .collect(),
+        };
+        for threshold in [params.alert_funding_apr_percent, params.alert_basis_bps]
+            .into_iter()
+            .flatten()
+        {
+            if threshold <= Decimal::ZERO {
+                anyhow::bail!("Alert thresholds must be positive");
+            }
+        }
+
+        let mut listed = Vec::new();
+        let mut failed_venues = Vec::new();
+        for venue in &self.venues {
+            match venue.markets().await {
+                Ok(markets) => listed.push((venue.name().to_string(), markets)),
+                Err(e) => failed_venues.push(VenueError {
+                    venue: venue.name().to_string(),
+                    error: format!("{:#}", e),
+                }),
+            }
+        }
+        if listed.is_empty() && !self.venues.is_empty() {
+            anyhow::bail!("No perps exchange could be read");
+        }
+
+        let mut reports = Vec::new();
+        let mut alerts = Vec::new();
+        for asset in assets {
+            let oracle = self.spot_price(&asset).await;
+            let mut spot = oracle.clone();
+            let mut venues = Vec::new();
+            for (venue, markets) in &listed {
+                let Some(market) = market_for(markets, &asset) else {
+                    continue;
+                };
+                let hourly = market.funding_rate_hourly.unwrap_or_default() * Decimal::ONE_HUNDRED;
+                // Without an oracle, the first venue's index stands in for spot
+                if spot.is_none() {
+                    spot = market
+                        .index_price
+                        .map(|index| (index, format!("{} index", venue)));
+                }
+                let basis_bps = market
+                    .mark_price
+                    .zip(spot.as_ref().map(|(price, _)| *price))
+                    .filter(|(_, spot)| !spot.is_zero())
+                    .map(|(mark, spot)| (mark - spot) / spot * Decimal::from(10_000));
+                venues.push(VenueRate {
+                    venue: venue.clone(),
+                    market: market.symbol.clone(),
+                    funding_rate_hourly_percent: hourly.round_dp(6).normalize(),
+                    funding_apr_percent: (hourly * Decimal::from(HOURS_PER_YEAR))
+                        .round_dp(2)
+                        .normalize(),
+                    mark_price: market.mark_price.map(|price| price.normalize()),
+                    basis_bps: basis_bps.map(|bps| bps.round_dp(2).normalize()),
+                    open_interest_usd: market
+                        .open_interest_usd
+                        .map(|usd| usd.round_dp(2).normalize()),
+                });
+            }
+            venues.sort_by_key(|rate| std::cmp::Reverse(rate.funding_apr_percent));
+
+            for rate in &venues {
+                let checks = [
+                    (
+                        "funding_apr_percent",
+                        Some(rate.funding_apr_percent),
+                        params.alert_funding_apr_percent,
+                        "%",
+                    ),
+                    ("basis_bps", rate.basis_bps, params.alert_basis_bps, " bps"),
+                ];
+                for (metric, value, threshold, unit) in checks {
+                    let (Some(value), Some(threshold)) = (value, threshold) else {
+                        continue;
+                    };
+                    let key = (rate.venue.clone(), asset.clone(), metric);
+                    let Some(above) = self.crossed(key, value, threshold) else {
+                        continue;
+                    };
+                    let message = if above {
+                        format!(
+                            "{} {} on {} is {}{}, beyond the {}{} threshold",
+                            asset, metric, rate.venue, value, unit, threshold, unit
+                        )
+                    } else {
+                        format!(
+                            "{} {} on {} is back within {}{} at {}{}",
+                            asset, metric, rate.venue, threshold, unit, value, unit
+                        )
+                    };
+                    alerts.push(FundingAlert {
+                        asset: asset.clone(),
+                        venue: rate.venue.clone(),
+                        metric: metric.to_string(),
+                        severity: if above {
+                            AlertSeverity::Warning
+                        } else {
+                            AlertSeverity::Info
+                        },
+                        value,
+                        threshold,
+                        message,
+                    });
+                }
+            }
+
+            let spread = match (venues.first(), venues.last()) {
+                (Some(high), Some(low)) if venues.len() > 1 => {
+                    Some((high.funding_apr_percent - low.funding_apr_percent).normalize())
+                }
+                _ => None,
+            };
+            reports.push(AssetRates {
+                asset,
+                spot_price: spot.as_ref().map(|(price, _)| price.normalize()),
+                spot_source: spot.map(|(_, source)| source),
+                venues,
+                funding_spread_apr_percent: spread,
+            });
+        }
+
+        let mut notifications_sent = 0;
+        if let Some(sink) = &self.alerts {
+            for alert in &alerts {
+                notifications_sent += sink
+                    .send(FUNDING_TOPIC, alert.severity, &json!(alert))
+                    .await;
+            }
+        }
+
+        let result = GetFundingRatesResult {
+            assets: reports,
+            alerts,
+            failed_venues,
+            notifications_sent,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
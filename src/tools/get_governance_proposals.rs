@@ -0,0 +1,229 @@
+use super::pagination::{bounded_page_schema_properties, Page, PageParams};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{GovernanceSource, Proposal, ProposalState, VoteClock, VoteTally};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_PROPOSAL_LIMIT: usize = 10;
+const MAX_PROPOSAL_LIMIT: usize = 50;
+
+// Descriptions can run to pages of Markdown; the title and a summary are enough to triage
+const MAX_DESCRIPTION_CHARS: usize = 500;
+
+/// The DAO a governance call targets: an on-chain governor or a Snapshot space
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Dao {
+    Governor(Address),
+    Space(String),
+}
+
+impl Dao {
+    /// Pick the DAO from `governor` (an address or a name in `governors`) or `space`
+    pub(crate) fn resolve(
+        governors: &[(String, Address)],
+        governor: Option<&str>,
+        space: Option<&str>,
+    ) -> Result<Self> {
+        match (governor.map(str::trim), space.map(str::trim)) {
+            (Some(governor), None) => {
+                if let Some((_, address)) = governors
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(governor))
+                {
+                    return Ok(Dao::Governor(*address));
+                }
+                governor.parse().map(Dao::Governor).with_context(|| {
+                    format!(
+                        "Unknown governor {}: pass an address or one of {}",
+                        governor,
+                        governors
+                            .iter()
+                            .map(|(name, _)| name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })
+            }
+            (None, Some(space)) if !space.is_empty() => Ok(Dao::Space(space.to_string())),
+            _ => anyhow::bail!("Pass either a governor or a Snapshot space"),
+        }
+    }
+
+    /// The identifier the matching source expects
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Dao::Governor(address) => format!("{:?}", address),
+            Dao::Space(space) => space.clone(),
+        }
+    }
+}
+
+pub struct GetGovernanceProposalsTool {
+    governor: Arc<dyn GovernanceSource>,
+    snapshot: Arc<dyn GovernanceSource>,
+    governors: Vec<(String, Address)>,
+}
+
+impl GetGovernanceProposalsTool {
+    pub fn new(governor: Arc<dyn GovernanceSource>, snapshot: Arc<dyn GovernanceSource>) -> Self {
+        Self {
+            governor,
+            snapshot,
+            governors: Vec::new(),
+        }
+    }
+
+    /// Governors that can be passed by name
+    pub fn with_governors(mut self, governors: Vec<(String, Address)>) -> Self {
+        self.governors = governors;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetGovernanceProposalsParams {
+    #[serde(default)]
+    governor: Option<String>,
+    #[serde(default)]
+    space: Option<String>,
+    #[serde(default)]
+    active_only: bool,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct GetGovernanceProposalsResult {
+    source: String,
+    dao: String,
+    /// Newest first
+    #[serde(flatten)]
+    proposals: Page<ProposalReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProposalReport {
+    id: String,
+    title: String,
+    state: ProposalState,
+    proposer: Address,
+    start: u64,
+    end: u64,
+    clock: VoteClock,
+    tallies: Vec<VoteTally>,
+    description: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    description_truncated: bool,
+}
+
+impl From<Proposal> for ProposalReport {
+    fn from(proposal: Proposal) -> Self {
+        let truncated = proposal.description.chars().count() > MAX_DESCRIPTION_CHARS;
+        Self {
+            description: proposal
+                .description
+                .chars()
+                .take(MAX_DESCRIPTION_CHARS)
+                .collect(),
+            description_truncated: truncated,
+            id: proposal.id,
+            title: proposal.title,
+            state: proposal.state,
+            proposer: proposal.proposer,
+            start: proposal.start,
+            end: proposal.end,
+            clock: proposal.clock,
+            tallies: proposal.tallies,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for GetGovernanceProposalsTool {
+    fn name(&self) -> &str {
+        "get_governance_proposals"
+    }
+
+    fn description(&self) -> &str {
+        "List a DAO's latest proposals, newest first: from an on-chain Governor (OpenZeppelin Governor or Compound GovernorBravo, by address or a known name such as uniswap, compound or ens) or a Snapshot space such as 'aave.eth'. Each proposal has its state, voting window, vote tallies per choice and the start of its description. Vote on Governor proposals with cast_vote."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "governor": {
+                "type": "string",
+                "description": format!(
+                    "Governor contract address, or one of: {}",
+                    self.governors
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            },
+            "space": {
+                "type": "string",
+                "description": "Snapshot space id, e.g. 'aave.eth'; instead of governor"
+            },
+            "active_only": {
+                "type": "boolean",
+                "description": "Only proposals open for voting now (default: false)"
+            }
+        });
+        merge_schema_properties(
+            &mut properties,
+            bounded_page_schema_properties(DEFAULT_PROPOSAL_LIMIT, MAX_PROPOSAL_LIMIT),
+        );
+
+        json!({
+            "type": "object",
+            "properties": properties
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetGovernanceProposalsParams = serde_json::from_value(params)
+            .context("Invalid parameters for get_governance_proposals")?;
+        let dao = Dao::resolve(
+            &self.governors,
+            params.governor.as_deref(),
+            params.space.as_deref(),
+        )?;
+        let page = params
+            .page
+            .bounded(DEFAULT_PROPOSAL_LIMIT, MAX_PROPOSAL_LIMIT);
+        let source = match dao {
+            Dao::Governor(_) => &self.governor,
+            Dao::Space(_) => &self.snapshot,
+        };
+
+        // Sources return the newest proposals, so fetch up to the end of this page
+        let proposals = source
+            .proposals(&dao.id(), params.active_only, page.fetch_limit()?)
+            .await?;
+        let proposals = page.paginate_head(proposals)?;
+
+        let result = GetGovernanceProposalsResult {
+            source: source.name().to_string(),
+            dao: dao.id(),
+            proposals: Page {
+                items: proposals
+                    .items
+                    .into_iter()
+                    .map(ProposalReport::from)
+                    .collect(),
+                total: proposals.total,
+                next_cursor: proposals.next_cursor,
+            },
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,285 @@
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::uniswap_v3::deepest_pool;
+use crate::ethereum::{EthereumClientTrait, PricePoint, UniswapRouterTrait, UniswapV3Trait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Mainnet produces a block every 12 seconds
+pub const BLOCKS_PER_HOUR: u64 = 300;
+
+// Longest volatility window, bounded to keep the log scan within provider limits
+const MAX_WINDOW_HOURS: u64 = 72;
+
+const VOLUME_WINDOW_HOURS: u64 = 24;
+
+pub struct GetMarketStatsTool<C: EthereumClientTrait, U: UniswapRouterTrait, V: UniswapV3Trait> {
+    client: Arc<C>,
+    uniswap: Arc<U>,
+    uniswap_v3: Arc<V>,
+}
+
+impl<C: EthereumClientTrait, U: UniswapRouterTrait, V: UniswapV3Trait> GetMarketStatsTool<C, U, V> {
+    pub fn new(client: Arc<C>, uniswap: Arc<U>, uniswap_v3: Arc<V>) -> Self {
+        Self {
+            client,
+            uniswap,
+            uniswap_v3,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetMarketStatsParams {
+    base_token: String,
+    quote_token: String,
+    #[serde(default = "default_windows")]
+    volatility_windows_hours: Vec<u64>,
+}
+
+fn default_windows() -> Vec<u64> {
+    vec![1, 24]
+}
+
+#[derive(Debug, Serialize)]
+struct VolatilityWindow {
+    window_hours: u64,
+    realized_volatility_percent: String,
+    price_updates: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct GetMarketStatsResult {
+    base_token: String,
+    quote_token: String,
+    block_number: u64,
+    volatility: Vec<VolatilityWindow>,
+    trade_count_24h: usize,
+    volume_24h_base: String,
+    volume_24h_quote: String,
+    average_trade_size_base: String,
+    v2_price: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v3_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v3_fee_tier: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v2_v3_spread_bps: Option<String>,
+}
+
+/// Realized volatility (square root of summed squared log returns) in percent
+pub fn realized_volatility_percent(prices: &[Decimal]) -> Decimal {
+    let sum_squares: f64 = prices
+        .windows(2)
+        .filter_map(|w| {
+            let (prev, next) = (w[0].to_f64()?, w[1].to_f64()?);
+            (prev > 0.0 && next > 0.0).then(|| (next / prev).ln().powi(2))
+        })
+        .sum();
+    Decimal::from_f64(sum_squares.sqrt() * 100.0)
+        .unwrap_or(Decimal::ZERO)
+        .round_dp(4)
+}
+
+/// Prices recorded at or after `from_block`
+fn prices_since(points: &[PricePoint], from_block: u64) -> Vec<Decimal> {
+    points
+        .iter()
+        .filter(|p| p.block_number >= from_block)
+        .map(|p| p.price)
+        .collect()
+}
+
+/// Convert a raw base/quote price into human units
+///
+/// Decimals come from the token contracts, so a gap too wide for `Decimal` is an error.
+fn adjust_for_decimals(
+    raw_price: Decimal,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Decimal> {
+    let exponent = base_decimals as i32 - quote_decimals as i32;
+    let scale = 10i128
+        .checked_pow(exponent.unsigned_abs())
+        .and_then(Decimal::from_i128)
+        .with_context(|| {
+            format!(
+                "Unsupported token decimals: base {} and quote {} are too far apart",
+                base_decimals, quote_decimals
+            )
+        })?;
+    let adjusted = if exponent >= 0 {
+        raw_price.checked_mul(scale)
+    } else {
+        raw_price.checked_div(scale)
+    };
+    adjusted.with_context(|| {
+        format!(
+            "Price overflows after adjusting for decimals (base {}, quote {})",
+            base_decimals, quote_decimals
+        )
+    })
+}
+
+#[async_trait]
+impl<C, U, V> Tool for GetMarketStatsTool<C, U, V>
+where
+    C: EthereumClientTrait + 'static,
+    U: UniswapRouterTrait + 'static,
+    V: UniswapV3Trait + 'static,
+{
+    fn name(&self) -> &str {
+        "get_market_stats"
+    }
+
+    fn description(&self) -> &str {
+        "Get market statistics for a token pair: realized volatility over configurable windows, 24h volume and average trade size from Uniswap V2 Swap events, and the Uniswap V2/V3 price spread."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "base_token": {
+                    "type": "string",
+                    "description": "Address of the base token"
+                },
+                "quote_token": {
+                    "type": "string",
+                    "description": "Address of the quote token"
+                },
+                "volatility_windows_hours": {
+                    "type": "array",
+                    "items": { "type": "integer", "minimum": 1, "maximum": MAX_WINDOW_HOURS },
+                    "description": "Windows, in hours, over which to measure realized volatility (default: [1, 24])"
+                }
+            },
+            "required": ["base_token", "quote_token"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetMarketStatsParams =
+            serde_json::from_value(params).context("Invalid parameters for get_market_stats")?;
+
+        let base: Address = params
+            .base_token
+            .parse()
+            .context("Invalid base_token address")?;
+        let quote: Address = params
+            .quote_token
+            .parse()
+            .context("Invalid quote_token address")?;
+
+        if let Some(bad) = params
+            .volatility_windows_hours
+            .iter()
+            .find(|h| **h == 0 || **h > MAX_WINDOW_HOURS)
+        {
+            anyhow::bail!(
+                "Volatility window of {} hours is out of range (1-{})",
+                bad,
+                MAX_WINDOW_HOURS
+            );
+        }
+
+        let base_decimals = self.client.get_token_decimals(base).await?;
+        let quote_decimals = self.client.get_token_decimals(quote).await?;
+        let latest = self.client.get_block_number().await?;
+
+        // One Sync scan covers every window
+        let longest = params
+            .volatility_windows_hours
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        let points = self
+            .uniswap
+            .get_recent_prices(base, quote, longest * BLOCKS_PER_HOUR)
+            .await?;
+        let volatility = params
+            .volatility_windows_hours
+            .iter()
+            .map(|hours| {
+                let prices = prices_since(&points, latest.saturating_sub(hours * BLOCKS_PER_HOUR));
+                VolatilityWindow {
+                    window_hours: *hours,
+                    realized_volatility_percent: realized_volatility_percent(&prices).to_string(),
+                    price_updates: prices.len(),
+                }
+            })
+            .collect();
+
+        let swaps = self
+            .uniswap
+            .get_recent_swaps(base, quote, VOLUME_WINDOW_HOURS * BLOCKS_PER_HOUR)
+            .await?;
+        let (volume_base, volume_quote) = swaps.iter().fold(
+            (U256::zero(), U256::zero()),
+            |(base_sum, quote_sum), swap| {
+                (
+                    base_sum.saturating_add(swap.base_amount),
+                    quote_sum.saturating_add(swap.quote_amount),
+                )
+            },
+        );
+        let volume_base = from_base_units(volume_base, base_decimals as u32)?;
+        let volume_quote = from_base_units(volume_quote, quote_decimals as u32)?;
+        let average_trade = if swaps.is_empty() {
+            Decimal::ZERO
+        } else {
+            volume_base / Decimal::from(swaps.len())
+        };
+
+        // V2 spot from reserves, V3 spot from the deepest pool
+        let reserves = self.uniswap.get_pool_reserves(base, quote).await?;
+        if reserves.reserve_in.is_zero() {
+            anyhow::bail!("Uniswap V2 pool has no liquidity");
+        }
+        let v2_raw =
+            from_base_units(reserves.reserve_out, 0)? / from_base_units(reserves.reserve_in, 0)?;
+        let v2_price = adjust_for_decimals(v2_raw, base_decimals, quote_decimals)?;
+
+        let v3_pools = self.uniswap_v3.get_pools(base, quote).await?;
+        let v3_spot =
+            deepest_pool(&v3_pools).and_then(|pool| Some((pool.spot_price(base)?, pool.fee)));
+        let v3 = match v3_spot {
+            Some((raw, fee)) => Some((
+                adjust_for_decimals(raw, base_decimals, quote_decimals)?,
+                fee,
+            )),
+            None => None,
+        };
+        let spread_bps = v3.filter(|_| !v2_price.is_zero()).map(|(v3_price, _)| {
+            ((v3_price - v2_price) / v2_price * Decimal::from(10_000)).round_dp(2)
+        });
+
+        let result = GetMarketStatsResult {
+            base_token: params.base_token,
+            quote_token: params.quote_token,
+            block_number: latest,
+            volatility,
+            trade_count_24h: swaps.len(),
+            volume_24h_base: volume_base.to_string(),
+            volume_24h_quote: volume_quote.to_string(),
+            average_trade_size_base: average_trade.round_dp(8).normalize().to_string(),
+            v2_price: v2_price.round_dp(12).normalize().to_string(),
+            v3_price: v3.map(|(price, _)| price.round_dp(12).normalize().to_string()),
+            v3_fee_tier: v3.map(|(_, fee)| fee),
+            v2_v3_spread_bps: spread_bps.map(|bps| bps.normalize().to_string()),
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
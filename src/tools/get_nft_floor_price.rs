@@ -0,0 +1,148 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::interfaces::erc721_balance;
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, NftMarketSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct GetNftFloorPriceTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    market: Arc<dyn NftMarketSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetNftFloorPriceTool<C> {
+    pub fn new(client: Arc<C>, market: Arc<dyn NftMarketSource>) -> Self {
+        Self {
+            client,
+            market,
+            book: None,
+        }
+    }
+
+    /// Accept contact names as the owner
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetNftFloorPriceParams {
+    collection: String,
+    #[serde(default)]
+    owner: Option<String>,
+}
+
+/// What `owner`'s ERC-721 tokens of the collection are worth at the floor
+#[derive(Debug, Serialize)]
+struct Holdings {
+    owner: Address,
+    count: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    floor_value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetNftFloorPriceResult {
+    collection: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Unset when nothing is listed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    floor_price: Option<String>,
+    /// Unset when there is no collection bid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_bid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume_24h: Option<String>,
+    currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    holdings: Option<Holdings>,
+    source: String,
+}
+
+fn text(value: Option<Decimal>) -> Option<String> {
+    value.map(|value| value.round_dp(6).normalize().to_string())
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetNftFloorPriceTool<C> {
+    fn name(&self) -> &str {
+        "get_nft_floor_price"
+    }
+
+    fn description(&self) -> &str {
+        "Get an NFT collection's floor price, top collection bid and 24h volume from the NFT market API. Pass owner to also count their ERC721 tokens in the collection and value them at the floor, for portfolio valuation."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "collection": {
+                    "type": "string",
+                    "description": "NFT collection contract address"
+                },
+                "owner": {
+                    "type": "string",
+                    "description": "Address or contact name whose ERC721 tokens to value at the floor"
+                }
+            },
+            "required": ["collection"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetNftFloorPriceParams =
+            serde_json::from_value(params).context("Invalid parameters for get_nft_floor_price")?;
+        let collection: Address = params
+            .collection
+            .trim()
+            .parse()
+            .context("Invalid collection address")?;
+
+        let stats = self.market.collection_stats(collection).await?;
+        let holdings = match params.owner.as_deref() {
+            Some(owner) => {
+                let owner = resolve_address(self.book.as_deref(), owner)
+                    .context("Invalid owner address")?;
+                let count = from_base_units(
+                    erc721_balance(self.client.as_ref(), collection, owner, None).await?,
+                    0,
+                )?;
+                Some(Holdings {
+                    owner,
+                    count: count.to_string(),
+                    floor_value: text(stats.floor_price.map(|floor| floor * count)),
+                })
+            }
+            None => None,
+        };
+
+        let result = GetNftFloorPriceResult {
+            collection,
+            name: stats.name,
+            floor_price: text(stats.floor_price),
+            top_bid: text(stats.top_bid),
+            volume_24h: text(stats.volume_24h),
+            currency: stats.currency,
+            token_count: stats.token_count,
+            holdings,
+            source: self.market.name().to_string(),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
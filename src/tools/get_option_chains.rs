@@ -0,0 +1,306 @@
+use super::trade_ledger::date;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{OptionKind, OptionQuote, OptionsVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_UNDERLYING: &str = "ETH";
+const DEFAULT_EXPIRIES: usize = 2;
+const MAX_EXPIRIES: usize = 8;
+const DEFAULT_STRIKE_RANGE_PERCENT: u32 = 20;
+
+// Delta of the wings the skew is read at
+const SKEW_DELTA: Decimal = Decimal::from_parts(25, 0, 0, false, 2);
+
+pub struct GetOptionChainsTool {
+    venue: Arc<dyn OptionsVenue>,
+}
+
+impl GetOptionChainsTool {
+    pub fn new(venue: Arc<dyn OptionsVenue>) -> Self {
+        Self { venue }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetOptionChainsParams {
+    #[serde(default)]
+    underlying: Option<String>,
+    /// `YYYY-MM-DD`
+    #[serde(default)]
+    expiry: Option<String>,
+    #[serde(default)]
+    expiries: Option<usize>,
+    #[serde(default)]
+    strike_range_percent: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OptionReport {
+    instrument: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mark_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bid: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ask: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iv_percent: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bid_iv_percent: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ask_iv_percent: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+struct StrikeReport {
+    strike: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    call: Option<OptionReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    put: Option<OptionReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExpiryReport {
+    expiry: String,
+    expiry_timestamp: u64,
+    days_to_expiry: Decimal,
+    /// Strike nearest the index price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    atm_strike: Option<Decimal>,
+    /// Mean of the call and put mark IVs at the ATM strike
+    #[serde(skip_serializing_if = "Option::is_none")]
+    atm_iv_percent: Option<Decimal>,
+    /// 25-delta put IV minus 25-delta call IV; positive when downside protection is dearer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skew_25d_percent: Option<Decimal>,
+    /// Lowest strike first
+    strikes: Vec<StrikeReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetOptionChainsResult {
+    venue: String,
+    underlying: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index_price: Option<Decimal>,
+    available_expiries: Vec<String>,
+    chains: Vec<ExpiryReport>,
+}
+
+fn percent(fraction: Option<Decimal>) -> Option<Decimal> {
+    fraction.map(|fraction| (fraction * Decimal::ONE_HUNDRED).round_dp(2).normalize())
+}
+
+fn report(quote: &OptionQuote) -> OptionReport {
+    OptionReport {
+        instrument: quote.instrument.clone(),
+        mark_price: quote.mark_price.map(|price| price.normalize()),
+        bid: quote.bid.map(|price| price.normalize()),
+        ask: quote.ask.map(|price| price.normalize()),
+        iv_percent: percent(quote.mark_iv),
+        bid_iv_percent: percent(quote.bid_iv),
+        ask_iv_percent: percent(quote.ask_iv),
+        delta: quote.delta.map(|delta| delta.round_dp(4).normalize()),
+    }
+}
+
+/// Mark IV of the option of `kind` whose delta is nearest `target`
+fn iv_at_delta(chain: &[OptionQuote], kind: OptionKind, target: Decimal) -> Option<Decimal> {
+    chain
+        .iter()
+        .filter(|quote| quote.kind == kind && quote.mark_iv.is_some())
+        .filter_map(|quote| Some(((quote.delta? - target).abs(), quote.mark_iv?)))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, iv)| iv)
+}
+
+fn summarize(
+    chain: Vec<OptionQuote>,
+    expiry: u64,
+    now: u64,
+    index_price: Option<Decimal>,
+    strike_range_percent: u32,
+) -> ExpiryReport {
+    let atm_strike = index_price.and_then(|index| {
+        chain
+            .iter()
+            .map(|quote| quote.strike)
+            .min_by_key(|strike| (*strike - index).abs())
+    });
+    let atm_ivs: Vec<Decimal> = chain
+        .iter()
+        .filter(|quote| Some(quote.strike) == atm_strike)
+        .filter_map(|quote| quote.mark_iv)
+        .collect();
+    let atm_iv = (!atm_ivs.is_empty())
+        .then(|| atm_ivs.iter().sum::<Decimal>() / Decimal::from(atm_ivs.len()));
+    let skew = iv_at_delta(&chain, OptionKind::Put, -SKEW_DELTA)
+        .zip(iv_at_delta(&chain, OptionKind::Call, SKEW_DELTA))
+        .map(|(put, call)| put - call);
+
+    let range = Decimal::from(strike_range_percent) / Decimal::ONE_HUNDRED;
+    let mut strikes: BTreeMap<Decimal, StrikeReport> = BTreeMap::new();
+    for quote in &chain {
+        let in_range = index_price
+            .is_none_or(|index| index.is_zero() || ((quote.strike - index) / index).abs() <= range);
+        if !in_range {
+            continue;
+        }
+        let strike = strikes.entry(quote.strike).or_insert(StrikeReport {
+            strike: quote.strike.normalize(),
+            call: None,
+            put: None,
+        });
+        match quote.kind {
+            OptionKind::Call => strike.call = Some(report(quote)),
+            OptionKind::Put => strike.put = Some(report(quote)),
+        }
+    }
+
+    ExpiryReport {
+        expiry: date(expiry),
+        expiry_timestamp: expiry,
+        days_to_expiry: (Decimal::from(expiry.saturating_sub(now)) / Decimal::from(86_400))
+            .round_dp(2)
+            .normalize(),
+        atm_strike: atm_strike.map(|strike| strike.normalize()),
+        atm_iv_percent: percent(atm_iv),
+        skew_25d_percent: percent(skew),
+        strikes: strikes.into_values().collect(),
+    }
+}
+
+#[async_trait]
+impl Tool for GetOptionChainsTool {
+    fn name(&self) -> &str {
+        "get_option_chains"
+    }
+
+    fn description(&self) -> &str {
+        "Read option chains for an underlying from the configured options exchange (Aevo or Lyra): for each expiry, calls and puts by strike around the index price with mark price, bid/ask where quoted, implied volatility and delta, plus ATM implied vol and 25-delta put-call skew. Useful context for sizing and pricing hedges. Read-only."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "underlying": {
+                    "type": "string",
+                    "description": "Underlying asset such as ETH or BTC (default: ETH)"
+                },
+                "expiry": {
+                    "type": "string",
+                    "description": "Only this expiry, as YYYY-MM-DD (default: the soonest ones)"
+                },
+                "expiries": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_EXPIRIES,
+                    "description": "How many of the soonest expiries to return when expiry is not given (default: 2)"
+                },
+                "strike_range_percent": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 100,
+                    "description": "Only strikes within this percent of the index price (default: 20)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetOptionChainsParams =
+            serde_json::from_value(params).context("Invalid parameters for get_option_chains")?;
+        let underlying = params
+            .underlying
+            .unwrap_or_else(|| DEFAULT_UNDERLYING.to_string())
+            .to_ascii_uppercase();
+        let strike_range_percent = params
+            .strike_range_percent
+            .unwrap_or(DEFAULT_STRIKE_RANGE_PERCENT);
+        if !(1..=100).contains(&strike_range_percent) {
+            anyhow::bail!("strike_range_percent must be between 1 and 100");
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        let available: Vec<u64> = self
+            .venue
+            .expiries(&underlying)
+            .await?
+            .into_iter()
+            .filter(|expiry| *expiry > now)
+            .collect();
+        if available.is_empty() {
+            anyhow::bail!("{} lists no {} options", self.venue.name(), underlying);
+        }
+        let selected: Vec<u64> = match &params.expiry {
+            Some(wanted) => {
+                let found: Vec<u64> = available
+                    .iter()
+                    .copied()
+                    .filter(|expiry| date(*expiry) == wanted.trim())
+                    .collect();
+                if found.is_empty() {
+                    anyhow::bail!(
+                        "No {} options expire on {}; listed expiries are {}",
+                        underlying,
+                        wanted,
+                        available
+                            .iter()
+                            .map(|expiry| date(*expiry))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+                found
+            }
+            None => available
+                .iter()
+                .copied()
+                .take(
+                    params
+                        .expiries
+                        .unwrap_or(DEFAULT_EXPIRIES)
+                        .clamp(1, MAX_EXPIRIES),
+                )
+                .collect(),
+        };
+
+        let mut index_price = None;
+        let mut chains = Vec::new();
+        for expiry in selected {
+            let chain = self.venue.option_chain(&underlying, expiry).await?;
+            index_price = index_price.or_else(|| chain.iter().find_map(|quote| quote.index_price));
+            chains.push(summarize(
+                chain,
+                expiry,
+                now,
+                index_price,
+                strike_range_percent,
+            ));
+        }
+
+        let result = GetOptionChainsResult {
+            venue: self.venue.name().to_string(),
+            underlying,
+            index_price: index_price.map(|price| price.normalize()),
+            available_expiries: available.iter().map(|expiry| date(*expiry)).collect(),
+            chains,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Tool, ToolCategory};
+use crate::ethereum::{PerpMarket, PerpVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+
+const HOURS_PER_YEAR: u32 = 365 * 24;
+
+pub struct GetPerpMarketsTool {
+    venue: Arc<dyn PerpVenue>,
+}
+
+impl GetPerpMarketsTool {
+    pub fn new(venue: Arc<dyn PerpVenue>) -> Self {
+        Self { venue }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPerpMarketsParams {
+    #[serde(default)]
+    markets: Option<Vec<String>>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct MarketReport {
+    symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mark_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index_price: Option<Decimal>,
+    /// Percent of notional longs pay shorts each hour; negative when shorts pay
+    #[serde(skip_serializing_if = "Option::is_none")]
+    funding_rate_hourly_percent: Option<Decimal>,
+    /// The hourly rate held for a year
+    #[serde(skip_serializing_if = "Option::is_none")]
+    funding_rate_apr_percent: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    open_interest_usd: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume_24h_usd: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_leverage: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetPerpMarketsResult {
+    venue: String,
+    /// Largest open interest first
+    markets: Vec<MarketReport>,
+    total_markets: usize,
+}
+
+fn report(market: PerpMarket) -> MarketReport {
+    let percent = market
+        .funding_rate_hourly
+        .map(|rate| rate * Decimal::ONE_HUNDRED);
+    MarketReport {
+        symbol: market.symbol,
+        mark_price: market.mark_price.map(|price| price.normalize()),
+        index_price: market.index_price.map(|price| price.normalize()),
+        funding_rate_hourly_percent: percent.map(|rate| rate.round_dp(6).normalize()),
+        funding_rate_apr_percent: percent.map(|rate| {
+            (rate * Decimal::from(HOURS_PER_YEAR))
+                .round_dp(2)
+                .normalize()
+        }),
+        open_interest_usd: market
+            .open_interest_usd
+            .map(|usd| usd.round_dp(2).normalize()),
+        volume_24h_usd: market.volume_24h_usd.map(|usd| usd.round_dp(2).normalize()),
+        max_leverage: market.max_leverage,
+    }
+}
+
+#[async_trait]
+impl Tool for GetPerpMarketsTool {
+    fn name(&self) -> &str {
+        "get_perp_markets"
+    }
+
+    fn description(&self) -> &str {
+        "List perpetual futures markets on the configured perps exchange (Hyperliquid or GMX): mark and index prices, hourly funding rate and its annualized equivalent, open interest, 24h volume and maximum leverage, largest open interest first. Read-only."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "markets": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only these markets, by symbol such as ETH or BTC (default: all)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_LIMIT,
+                    "description": "Most markets to list (default: 20)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetPerpMarketsParams =
+            serde_json::from_value(params).context("Invalid parameters for get_perp_markets")?;
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+        let mut markets = self.venue.markets().await?;
+        if let Some(wanted) = &params.markets {
+            // GMX names markets like "ETH/USD [WETH-USDC]", so match on the base too
+            let matches = |symbol: &str, wanted: &str| {
+                symbol.eq_ignore_ascii_case(wanted)
+                    || symbol
+                        .split('/')
+                        .next()
+                        .is_some_and(|base| base.eq_ignore_ascii_case(wanted))
+            };
+            markets.retain(|market| wanted.iter().any(|w| matches(&market.symbol, w)));
+            if markets.is_empty() {
+                anyhow::bail!(
+                    "{} lists no market named {}",
+                    self.venue.name(),
+                    wanted.join(", ")
+                );
+            }
+        }
+        markets.sort_by_key(|market| std::cmp::Reverse(market.open_interest_usd));
+        let total_markets = markets.len();
+        markets.truncate(limit);
+
+        let result = GetPerpMarketsResult {
+            venue: self.venue.name().to_string(),
+            markets: markets.into_iter().map(report).collect(),
+            total_markets,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
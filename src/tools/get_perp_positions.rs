@@ -0,0 +1,170 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PerpPosition, PerpSide, PerpVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// USD values are reported to the cent
+const USD_DECIMALS: u32 = 2;
+
+pub struct GetPerpPositionsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    venue: Arc<dyn PerpVenue>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetPerpPositionsTool<C> {
+    pub fn new(client: Arc<C>, venue: Arc<dyn PerpVenue>) -> Self {
+        Self {
+            client,
+            venue,
+            book: None,
+        }
+    }
+
+    /// Accept contact names in place of the account
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPerpPositionsParams {
+    #[serde(default)]
+    address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PositionReport {
+    market: String,
+    side: PerpSide,
+    size: Decimal,
+    size_usd: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mark_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unrealized_pnl_usd: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    margin_usd: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    leverage: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liquidation_price: Option<Decimal>,
+}
+
+/// An account's perp positions and what they add up to
+#[derive(Debug, Serialize)]
+pub(crate) struct PerpExposure {
+    pub(crate) venue: String,
+    pub(crate) positions: Vec<PositionReport>,
+    /// Long plus short notional
+    pub(crate) gross_exposure_usd: Decimal,
+    /// Long minus short notional
+    pub(crate) net_exposure_usd: Decimal,
+    pub(crate) unrealized_pnl_usd: Decimal,
+    pub(crate) margin_usd: Decimal,
+    /// Margin plus unrealized PnL, what closing everything would leave
+    pub(crate) equity_usd: Decimal,
+}
+
+pub(crate) fn perp_exposure(venue: &str, positions: Vec<PerpPosition>) -> PerpExposure {
+    let usd = |value: Decimal| value.round_dp(USD_DECIMALS).normalize();
+    let mut gross = Decimal::ZERO;
+    let mut net = Decimal::ZERO;
+    let mut pnl = Decimal::ZERO;
+    let mut margin = Decimal::ZERO;
+    for position in &positions {
+        gross += position.size_usd;
+        net += match position.side {
+            PerpSide::Long => position.size_usd,
+            PerpSide::Short => -position.size_usd,
+        };
+        pnl += position.unrealized_pnl_usd.unwrap_or_default();
+        margin += position.margin_usd.unwrap_or_default();
+    }
+    PerpExposure {
+        venue: venue.to_string(),
+        positions: positions
+            .into_iter()
+            .map(|position| PositionReport {
+                market: position.market,
+                side: position.side,
+                size: position.size.normalize(),
+                size_usd: usd(position.size_usd),
+                entry_price: position.entry_price.map(|price| price.normalize()),
+                mark_price: position.mark_price.map(|price| price.normalize()),
+                unrealized_pnl_usd: position.unrealized_pnl_usd.map(usd),
+                margin_usd: position.margin_usd.map(usd),
+                leverage: position
+                    .leverage
+                    .map(|leverage| leverage.round_dp(2).normalize()),
+                liquidation_price: position.liquidation_price.map(|price| price.normalize()),
+            })
+            .collect(),
+        gross_exposure_usd: usd(gross),
+        net_exposure_usd: usd(net),
+        unrealized_pnl_usd: usd(pnl),
+        margin_usd: usd(margin),
+        equity_usd: usd(margin + pnl),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GetPerpPositionsResult {
+    account: Address,
+    #[serde(flatten)]
+    exposure: PerpExposure,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetPerpPositionsTool<C> {
+    fn name(&self) -> &str {
+        "get_perp_positions"
+    }
+
+    fn description(&self) -> &str {
+        "List the wallet's (or another account's) open perpetual futures positions on the configured perps exchange (Hyperliquid or GMX): side, size, entry and mark price, unrealized PnL, margin, leverage and liquidation price, with gross and net exposure and equity across them. Read-only."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Account address or contact name (default: the server's wallet)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetPerpPositionsParams =
+            serde_json::from_value(params).context("Invalid parameters for get_perp_positions")?;
+        let account = match &params.address {
+            Some(address) => {
+                resolve_address(self.book.as_deref(), address).context("Invalid account address")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        let positions = self.venue.positions(account).await?;
+
+        let result = GetPerpPositionsResult {
+            account,
+            exposure: perp_exposure(self.venue.name(), positions),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
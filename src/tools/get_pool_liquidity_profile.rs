@@ -0,0 +1,248 @@
+use super::{Tool, ToolCategory};
+use crate::ethereum::uniswap_v3::{deepest_pool, liquidity_bands};
+use crate::ethereum::{EthereumClientTrait, LiquidityBand, UniswapV3Trait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_RANGE_PERCENT: f64 = 10.0;
+const MAX_RANGE_PERCENT: f64 = 50.0;
+
+// Price moves the depth table reports, when inside the scanned range
+const DEPTH_LEVELS_PERCENT: [f64; 5] = [0.5, 1.0, 2.0, 5.0, 10.0];
+
+pub struct GetPoolLiquidityProfileTool<C: EthereumClientTrait, V: UniswapV3Trait> {
+    client: Arc<C>,
+    uniswap_v3: Arc<V>,
+}
+
+impl<C: EthereumClientTrait, V: UniswapV3Trait> GetPoolLiquidityProfileTool<C, V> {
+    pub fn new(client: Arc<C>, uniswap_v3: Arc<V>) -> Self {
+        Self { client, uniswap_v3 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPoolLiquidityProfileParams {
+    token_a: String,
+    token_b: String,
+    #[serde(default)]
+    fee: Option<u32>,
+    #[serde(default)]
+    range_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Band {
+    tick_lower: i32,
+    tick_upper: i32,
+    price_lower: String,
+    price_upper: String,
+    liquidity: String,
+    amount0: String,
+    amount1: String,
+}
+
+/// What it takes to push the price by `price_change_percent`, before fees
+#[derive(Debug, Serialize)]
+struct DepthLevel {
+    price_change_percent: String,
+    token_in: String,
+    amount_in: String,
+    amount_out: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GetPoolLiquidityProfileResult {
+    pool_address: String,
+    fee: u32,
+    token0: String,
+    token1: String,
+    current_tick: i32,
+    /// token1 per token0
+    current_price: String,
+    in_range_liquidity: String,
+    range_percent: f64,
+    bands: Vec<Band>,
+    depth: Vec<DepthLevel>,
+}
+
+fn sqrt_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+fn human(raw: f64, decimals: u32) -> String {
+    Decimal::from_f64(raw / 10f64.powi(decimals as i32))
+        .map(|amount| amount.round_dp(6).normalize().to_string())
+        .unwrap_or_else(|| "0".to_string())
+}
+
+fn human_price(sqrt_price: f64, decimals0: u32, decimals1: u32) -> String {
+    let price = sqrt_price * sqrt_price * 10f64.powi(decimals0 as i32 - decimals1 as i32);
+    Decimal::from_f64(price)
+        .map(|price| price.round_sf(8).unwrap_or(price).normalize().to_string())
+        .unwrap_or_else(|| "0".to_string())
+}
+
+/// token0 and token1 swapped between sqrt prices `lo` and `hi` across `bands`
+fn swapped_between(bands: &[LiquidityBand], lo: f64, hi: f64) -> (f64, f64) {
+    bands.iter().fold((0.0, 0.0), |(amount0, amount1), band| {
+        let band_lo = sqrt_at_tick(band.tick_lower).max(lo);
+        let band_hi = sqrt_at_tick(band.tick_upper).min(hi);
+        if band_lo >= band_hi {
+            return (amount0, amount1);
+        }
+        let liquidity = band.liquidity as f64;
+        (
+            amount0 + liquidity * (1.0 / band_lo - 1.0 / band_hi),
+            amount1 + liquidity * (band_hi - band_lo),
+        )
+    })
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, V: UniswapV3Trait + 'static> Tool
+    for GetPoolLiquidityProfileTool<C, V>
+{
+    fn name(&self) -> &str {
+        "get_pool_liquidity_profile"
+    }
+
+    fn description(&self) -> &str {
+        "Show how a Uniswap V3 pool's liquidity is distributed across ticks near the current price: constant-liquidity bands with the token amounts they hold, and how much must be swapped to move the price by 0.5-10%. Use it to estimate slippage for large trades."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_a": {
+                    "type": "string",
+                    "description": "Address of one token of the pool"
+                },
+                "token_b": {
+                    "type": "string",
+                    "description": "Address of the other token"
+                },
+                "fee": {
+                    "type": "integer",
+                    "enum": [100, 500, 3000, 10000],
+                    "description": "Fee tier in hundredths of a bip (default: the pool with the most in-range liquidity)"
+                },
+                "range_percent": {
+                    "type": "number",
+                    "minimum": 0,
+                    "maximum": 50,
+                    "description": "How far above and below the current price to scan, in percent (default: 10)"
+                }
+            },
+            "required": ["token_a", "token_b"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetPoolLiquidityProfileParams = serde_json::from_value(params)
+            .context("Invalid parameters for get_pool_liquidity_profile")?;
+
+        let token_a: Address = params.token_a.parse().context("Invalid token_a address")?;
+        let token_b: Address = params.token_b.parse().context("Invalid token_b address")?;
+        let range_percent = params.range_percent.unwrap_or(DEFAULT_RANGE_PERCENT);
+        if !(range_percent > 0.0 && range_percent <= MAX_RANGE_PERCENT) {
+            anyhow::bail!(
+                "range_percent must be above 0 and at most {}",
+                MAX_RANGE_PERCENT
+            );
+        }
+
+        let pools = self.uniswap_v3.get_pools(token_a, token_b).await?;
+        let pool = match params.fee {
+            Some(fee) => pools.iter().find(|pool| pool.fee == fee),
+            None => deepest_pool(&pools),
+        }
+        .context("No Uniswap V3 pool for this pair and fee tier")?;
+        let token0 = pool.token0;
+        let token1 = if token0 == token_a { token_b } else { token_a };
+        let decimals0 = self.client.get_token_decimals(token0).await? as u32;
+        let decimals1 = self.client.get_token_decimals(token1).await? as u32;
+
+        // ln(1.0001) per tick
+        let tick_delta = |factor: f64| (factor.ln() / 1.0001f64.ln()).abs().ceil() as i32;
+        let tick_lower = pool.tick - tick_delta(1.0 - range_percent / 100.0);
+        let tick_upper = pool.tick + tick_delta(1.0 + range_percent / 100.0);
+        let ticks = self
+            .uniswap_v3
+            .get_initialized_ticks(pool, tick_lower, tick_upper)
+            .await?;
+        let bands = liquidity_bands(pool, &ticks, tick_lower, tick_upper);
+
+        let sqrt_price = pool.sqrt_price();
+        let band_reports = bands
+            .iter()
+            .map(|band| {
+                let lo = sqrt_at_tick(band.tick_lower);
+                let hi = sqrt_at_tick(band.tick_upper);
+                let single = [*band];
+                // token0 sits above the price, token1 below
+                let (amount0, _) = swapped_between(&single, sqrt_price.max(lo), hi);
+                let (_, amount1) = swapped_between(&single, lo, sqrt_price.min(hi));
+                Band {
+                    tick_lower: band.tick_lower,
+                    tick_upper: band.tick_upper,
+                    price_lower: human_price(lo, decimals0, decimals1),
+                    price_upper: human_price(hi, decimals0, decimals1),
+                    liquidity: band.liquidity.to_string(),
+                    amount0: human(amount0, decimals0),
+                    amount1: human(amount1, decimals1),
+                }
+            })
+            .collect();
+
+        let mut depth = Vec::new();
+        for level in DEPTH_LEVELS_PERCENT
+            .into_iter()
+            .filter(|level| *level <= range_percent)
+        {
+            // Pushing the price up buys token0 with token1
+            let target = sqrt_price * (1.0 + level / 100.0).sqrt();
+            let (out0, in1) = swapped_between(&bands, sqrt_price, target);
+            depth.push(DepthLevel {
+                price_change_percent: format!("+{}", level),
+                token_in: format!("{:?}", token1),
+                amount_in: human(in1, decimals1),
+                amount_out: human(out0, decimals0),
+            });
+            let target = sqrt_price * (1.0 - level / 100.0).sqrt();
+            let (in0, out1) = swapped_between(&bands, target, sqrt_price);
+            depth.push(DepthLevel {
+                price_change_percent: format!("-{}", level),
+                token_in: format!("{:?}", token0),
+                amount_in: human(in0, decimals0),
+                amount_out: human(out1, decimals1),
+            });
+        }
+
+        let result = GetPoolLiquidityProfileResult {
+            pool_address: format!("{:?}", pool.pool_address),
+            fee: pool.fee,
+            token0: format!("{:?}", token0),
+            token1: format!("{:?}", token1),
+            current_tick: pool.tick,
+            current_price: human_price(sqrt_price, decimals0, decimals1),
+            in_range_liquidity: pool.liquidity.to_string(),
+            range_percent,
+            bands: band_reports,
+            depth,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,416 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::freshness::{finality_schema_properties, Finality};
+use super::get_perp_positions::{perp_exposure, PerpExposure};
+use super::multichain::{
+    chain_selection_schema_properties, fetch_holdings, for_each_chain, ChainSelection,
+    TokenBalance, DEFAULT_CHAIN_QUERY_CONCURRENCY,
+};
+use super::units::to_base_units;
+use super::{merge_schema_properties, Tool};
+use crate::ethereum::chains::{chain_name, ChainClients, ChainPricing};
+use crate::ethereum::tokens::asset_of;
+use crate::ethereum::{AssetIdentity, EthereumClientTrait, PerpVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// USD values are reported to the cent; prices keep full precision
+const USD_DECIMALS: u32 = 2;
+
+pub struct GetPortfolioTool {
+    chains: Arc<ChainClients>,
+    max_parallel: usize,
+    book: Option<Arc<AddressBook>>,
+    perps: Option<Arc<dyn PerpVenue>>,
+}
+
+impl GetPortfolioTool {
+    pub fn new(chains: Arc<ChainClients>) -> Self {
+        Self {
+            chains,
+            max_parallel: DEFAULT_CHAIN_QUERY_CONCURRENCY,
+            book: None,
+            perps: None,
+        }
+    }
+
+    /// Value at most `max_parallel` chains at once
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    /// Accept contact names from `book` in place of the wallet address
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Include the wallet's perp positions on `venue`
+    pub fn with_perps(mut self, venue: Arc<dyn PerpVenue>) -> Self {
+        self.perps = Some(venue);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPortfolioParams {
+    address: String,
+    #[serde(default)]
+    chain_ids: Option<Vec<u64>>,
+    /// ERC20 contracts to include, keyed by chain id
+    #[serde(default)]
+    tokens: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    finality: Finality,
+}
+
+#[derive(Debug, Serialize)]
+struct Holding {
+    #[serde(flatten)]
+    balance: TokenBalance,
+    /// Asset the holding counts toward in `assets`; unknown tokens are not aggregated
+    #[serde(flatten)]
+    identity: Option<AssetIdentity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_usd: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_usd: Option<Decimal>,
+    /// Why the holding could not be priced; it is left out of the totals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChainValuation {
+    chain_id: u64,
+    chain: String,
+    /// Venue the chain's holdings were priced on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    venue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    total_usd: Decimal,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    holdings: Vec<Holding>,
+    /// Why this chain could not be read; the other chains are unaffected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A held variant of an asset, e.g. USDC.e on Arbitrum
+#[derive(Debug, Serialize)]
+struct AssetVariant {
+    chain_id: u64,
+    symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_address: Option<Address>,
+    balance: Decimal,
+    canonical: bool,
+}
+
+/// One logical asset summed across chains and its wrapped or bridged variants
+#[derive(Debug, Serialize)]
+struct AssetTotal {
+    asset: String,
+    balance: Decimal,
+    value_usd: Decimal,
+    /// Some of the balance could not be priced, so value_usd understates it
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    partially_priced: bool,
+    variants: Vec<AssetVariant>,
+}
+
+/// Perp positions held off the chains' balances, on a perps exchange
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Derivatives {
+    Exposure(PerpExposure),
+    /// The exchange could not be read; nothing from it is in the totals
+    Failed {
+        venue: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct GetPortfolioResult {
+    address: String,
+    /// Block the balances were read at; prices are always current
+    finality: Finality,
+    /// Includes the equity of any perp positions
+    total_usd: Decimal,
+    chains: Vec<ChainValuation>,
+    assets: Vec<AssetTotal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derivatives: Option<Derivatives>,
+    failed_chains: usize,
+    unpriced_holdings: usize,
+}
+
+/// Scale a base-unit price ratio to whole units: `ratio * 10^(decimals_in - decimals_out)`
+fn whole_unit_price(ratio: Decimal, decimals_in: u8, decimals_out: u8) -> Decimal {
+    let factor = |exp: u8| Decimal::from(10u64.pow(exp as u32));
+    if decimals_in >= decimals_out {
+        ratio * factor(decimals_in - decimals_out)
+    } else {
+        ratio / factor(decimals_out - decimals_in)
+    }
+}
+
+/// USD price of one whole `token`: quoted directly against the chain's dollar
+/// stablecoin, or through its wrapped native token when there is no direct pool
+pub(crate) async fn price_in_usd(
+    pricing: &ChainPricing,
+    token: Address,
+    decimals: u8,
+    native_usd: Option<Decimal>,
+) -> Result<Decimal> {
+    if token == pricing.usd_token {
+        return Ok(Decimal::ONE);
+    }
+    let one = to_base_units(Decimal::ONE, decimals as u32)?;
+    let direct = pricing
+        .router
+        .get_price(token, pricing.usd_token, one)
+        .await
+        .map(|ratio| whole_unit_price(ratio, decimals, pricing.usd_decimals));
+    let price = match (direct, native_usd) {
+        (Ok(price), _) if !price.is_zero() => price,
+        (_, Some(native_usd)) if token != pricing.wrapped_native => {
+            let ratio = pricing
+                .router
+                .get_price(token, pricing.wrapped_native, one)
+                .await?;
+            whole_unit_price(ratio, decimals, 18) * native_usd
+        }
+        (Err(e), _) => return Err(e),
+        (Ok(_), _) => Decimal::ZERO,
+    };
+    if price.is_zero() {
+        anyhow::bail!("No liquidity to price against on {}", pricing.venue);
+    }
+    Ok(price.normalize())
+}
+
+/// Nonzero holdings grouped by the asset they stand for, largest value first
+fn aggregate_assets(chains: &[ChainValuation]) -> Vec<AssetTotal> {
+    let mut assets: BTreeMap<String, AssetTotal> = BTreeMap::new();
+    for chain in chains {
+        for holding in &chain.holdings {
+            let Some(identity) = &holding.identity else {
+                continue;
+            };
+            if holding.balance.balance.is_zero() {
+                continue;
+            }
+            let total = assets
+                .entry(identity.asset.clone())
+                .or_insert_with(|| AssetTotal {
+                    asset: identity.asset.clone(),
+                    balance: Decimal::ZERO,
+                    value_usd: Decimal::ZERO,
+                    partially_priced: false,
+                    variants: Vec::new(),
+                });
+            total.balance += holding.balance.balance;
+            match holding.value_usd {
+                Some(value) => total.value_usd += value,
+                None => total.partially_priced = true,
+            }
+            total.variants.push(AssetVariant {
+                chain_id: chain.chain_id,
+                symbol: holding.balance.symbol.clone(),
+                token_address: holding.balance.token_address,
+                balance: holding.balance.balance,
+                canonical: identity.canonical,
+            });
+        }
+    }
+
+    let mut assets: Vec<AssetTotal> = assets
+        .into_values()
+        .map(|mut total| {
+            total.balance = total.balance.normalize();
+            total.value_usd = total.value_usd.normalize();
+            total
+        })
+        .collect();
+    assets.sort_by_key(|total| std::cmp::Reverse(total.value_usd));
+    assets
+}
+
+async fn value_chain(
+    client: Arc<dyn EthereumClientTrait>,
+    pricing: Option<ChainPricing>,
+    address: Address,
+    tokens: Vec<Address>,
+    finality: Finality,
+) -> Result<(u64, Vec<Holding>)> {
+    let chain_id = client.get_chain_id();
+    let holdings = fetch_holdings(client.as_ref(), address, &tokens, finality).await?;
+
+    let native_usd = match &pricing {
+        Some(pricing) => price_in_usd(pricing, pricing.wrapped_native, 18, None)
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let mut valued = Vec::new();
+    for balance in std::iter::once(holdings.native).chain(holdings.tokens) {
+        let price = match (&pricing, balance.token_address) {
+            (None, _) => Err(anyhow::anyhow!(
+                "No USD pricing venue is configured for this chain"
+            )),
+            // Nothing to value, so skip the quote
+            _ if balance.balance.is_zero() => Ok(Decimal::ZERO),
+            (Some(_), None) => {
+                native_usd.ok_or_else(|| anyhow::anyhow!("Native token price unavailable"))
+            }
+            (Some(pricing), Some(token)) => {
+                price_in_usd(pricing, token, balance.decimals, native_usd).await
+            }
+        };
+        let identity = asset_of(chain_id, balance.token_address);
+        let holding = match price {
+            Ok(price) => Holding {
+                identity,
+                value_usd: Some((balance.balance * price).round_dp(USD_DECIMALS).normalize()),
+                price_usd: (!price.is_zero()).then_some(price),
+                price_error: None,
+                balance,
+            },
+            Err(e) => Holding {
+                identity,
+                price_usd: None,
+                value_usd: None,
+                price_error: Some(format!("{:#}", e)),
+                balance,
+            },
+        };
+        valued.push(holding);
+    }
+    Ok((holdings.block_number, valued))
+}
+
+#[async_trait]
+impl Tool for GetPortfolioTool {
+    fn name(&self) -> &str {
+        "get_portfolio"
+    }
+
+    fn description(&self) -> &str {
+        "Value a wallet across every configured chain in USD: native balances plus any listed ERC20s, each priced on its own chain's Uniswap V2 deployment against a local dollar stablecoin. Returns a single total with a per-chain breakdown; chains or holdings that cannot be read or priced are reported and left out of the total. Wrapped and bridged forms of the same asset (ETH and WETH, USDC and USDC.e) are also summed across chains under assets. When a perps exchange is configured, open perp positions are summarized under derivatives and their equity (margin plus unrealized PnL) counts toward the total. Set finality to safe or finalized to read balances at a block that cannot be reorged; prices are still current."
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "address": {
+                "type": "string",
+                "description": "The wallet address to value, or a contact name"
+            }
+        });
+        merge_schema_properties(
+            &mut properties,
+            chain_selection_schema_properties(&self.chains),
+        );
+        merge_schema_properties(&mut properties, finality_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["address"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetPortfolioParams =
+            serde_json::from_value(params).context("Invalid parameters for get_portfolio")?;
+
+        let address = resolve_address(self.book.as_deref(), &params.address)
+            .context("Invalid wallet address")?;
+        let selection = ChainSelection::parse(&self.chains, params.chain_ids, &params.tokens)?;
+
+        let outcomes = for_each_chain(
+            &self.chains,
+            &selection.chain_ids,
+            self.max_parallel,
+            |chain_id, client| {
+                let pricing = self.chains.pricing(chain_id).cloned();
+                let tokens = selection.tokens_for(chain_id);
+                value_chain(client, pricing, address, tokens, params.finality)
+            },
+        )
+        .await?;
+
+        let chains: Vec<ChainValuation> = outcomes
+            .into_iter()
+            .map(|(chain_id, outcome)| {
+                let mut chain = ChainValuation {
+                    chain_id,
+                    chain: chain_name(chain_id),
+                    venue: self
+                        .chains
+                        .pricing(chain_id)
+                        .map(|pricing| pricing.venue.clone()),
+                    block_number: None,
+                    total_usd: Decimal::ZERO,
+                    holdings: Vec::new(),
+                    error: None,
+                };
+                match outcome {
+                    Ok((block_number, holdings)) => {
+                        chain.block_number = Some(block_number);
+                        chain.total_usd = holdings
+                            .iter()
+                            .filter_map(|holding| holding.value_usd)
+                            .sum::<Decimal>()
+                            .normalize();
+                        chain.holdings = holdings;
+                    }
+                    Err(e) => chain.error = Some(format!("{:#}", e)),
+                }
+                chain
+            })
+            .collect();
+
+        let derivatives = match &self.perps {
+            Some(venue) => Some(match venue.positions(address).await {
+                Ok(positions) => Derivatives::Exposure(perp_exposure(venue.name(), positions)),
+                Err(e) => Derivatives::Failed {
+                    venue: venue.name().to_string(),
+                    error: format!("{:#}", e),
+                },
+            }),
+            None => None,
+        };
+        let derivatives_usd = match &derivatives {
+            Some(Derivatives::Exposure(exposure)) => exposure.equity_usd,
+            _ => Decimal::ZERO,
+        };
+
+        let result = GetPortfolioResult {
+            address: params.address,
+            finality: params.finality,
+            total_usd: (chains.iter().map(|chain| chain.total_usd).sum::<Decimal>()
+                + derivatives_usd)
+                .normalize(),
+            failed_chains: chains.iter().filter(|chain| chain.error.is_some()).count(),
+            unpriced_holdings: chains
+                .iter()
+                .flat_map(|chain| &chain.holdings)
+                .filter(|holding| holding.price_error.is_some())
+                .count(),
+            assets: aggregate_assets(&chains),
+            chains,
+            derivatives,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,189 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, RestakingSource, StrategyShares};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct GetRestakingPositionsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    restaking: Arc<dyn RestakingSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetRestakingPositionsTool<C> {
+    pub fn new(client: Arc<C>, restaking: Arc<dyn RestakingSource>) -> Self {
+        Self {
+            client,
+            restaking,
+            book: None,
+        }
+    }
+
+    /// Accept contact names in place of the staker
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetRestakingPositionsParams {
+    #[serde(default)]
+    address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Holding {
+    strategy: Address,
+    /// Unset for natively restaked ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<Address>,
+    symbol: String,
+    /// Whole tokens the shares redeem for
+    amount: String,
+    shares: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Deposit {
+    #[serde(flatten)]
+    holding: Holding,
+    /// Shares deposited but taken by slashing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slashed_shares: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Withdrawal {
+    withdrawer: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegated_to: Option<Address>,
+    start_block: u64,
+    completable_at_block: u64,
+    /// Whether completeQueuedWithdrawal can be called now
+    completable: bool,
+    strategies: Vec<Holding>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetRestakingPositionsResult {
+    staker: Address,
+    protocol: String,
+    /// Unset when not delegated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operator: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eigen_pod: Option<Address>,
+    deposits: Vec<Deposit>,
+    queued_withdrawals: Vec<Withdrawal>,
+    withdrawal_delay_blocks: u64,
+    block_number: u64,
+}
+
+impl<C: EthereumClientTrait> GetRestakingPositionsTool<C> {
+    async fn holding(&self, shares: &StrategyShares) -> Result<Holding> {
+        let (symbol, decimals) = match shares.token {
+            Some(token) => (
+                self.client
+                    .get_token_symbol(token)
+                    .await
+                    .unwrap_or_else(|_| "UNKNOWN".to_string()),
+                self.client.get_token_decimals(token).await?,
+            ),
+            None => ("ETH".to_string(), 18),
+        };
+        Ok(Holding {
+            strategy: shares.strategy,
+            token: shares.token,
+            symbol,
+            amount: from_base_units(shares.underlying, decimals as u32)?
+                .normalize()
+                .to_string(),
+            shares: shares.shares.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetRestakingPositionsTool<C> {
+    fn name(&self) -> &str {
+        "get_restaking_positions"
+    }
+
+    fn description(&self) -> &str {
+        "Report the wallet's (or another staker's) EigenLayer restaking: deposits in each strategy with the tokens their shares redeem for and any shares lost to slashing, natively restaked ETH in its EigenPod, the operator it delegates to, and queued withdrawals with the block each can be completed at."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Staker address or contact name (default: the server's wallet)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetRestakingPositionsParams = serde_json::from_value(params)
+            .context("Invalid parameters for get_restaking_positions")?;
+        let staker = match &params.address {
+            Some(address) => {
+                resolve_address(self.book.as_deref(), address).context("Invalid staker address")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        let block_number = self.client.get_block_number().await?;
+        let position = self.restaking.position(staker).await?;
+
+        let mut deposits = Vec::new();
+        for deposit in &position.deposits {
+            let slashed = deposit
+                .deposit_shares
+                .saturating_sub(deposit.withdrawable.shares);
+            deposits.push(Deposit {
+                holding: self.holding(&deposit.withdrawable).await?,
+                slashed_shares: (!slashed.is_zero()).then(|| slashed.to_string()),
+            });
+        }
+        let mut queued_withdrawals = Vec::new();
+        for withdrawal in &position.queued_withdrawals {
+            let completable_at_block = withdrawal.start_block + position.withdrawal_delay_blocks;
+            let mut strategies = Vec::new();
+            for shares in &withdrawal.strategies {
+                strategies.push(self.holding(shares).await?);
+            }
+            queued_withdrawals.push(Withdrawal {
+                withdrawer: withdrawal.withdrawer,
+                delegated_to: withdrawal.delegated_to,
+                start_block: withdrawal.start_block,
+                completable_at_block,
+                completable: block_number >= completable_at_block,
+                strategies,
+            });
+        }
+
+        let result = GetRestakingPositionsResult {
+            staker,
+            protocol: self.restaking.name().to_string(),
+            operator: position.operator,
+            eigen_pod: position.eigen_pod,
+            deposits,
+            queued_withdrawals,
+            withdrawal_delay_blocks: position.withdrawal_delay_blocks,
+            block_number,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
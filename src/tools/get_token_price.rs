@@ -1,5 +1,13 @@
-use super::Tool;
-use crate::ethereum::{EthereumClientTrait, UniswapRouterTrait};
+use super::freshness::{freshness_schema_properties, Freshness, FreshnessParams};
+use super::explain::display;
+use super::price_sanity::PriceCheck;
+use super::units::{from_base_units, to_base_units};
+use super::{merge_schema_properties, Explanation, Tool, ToolCategory};
+use crate::ethereum::tokens::asset_of;
+use crate::ethereum::{
+    AssetIdentity, EthereumClientTrait, FxRateSource, ReferencePrice, ReferencePriceSource,
+    UniswapRouterTrait,
+};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::prelude::*;
@@ -34,11 +42,129 @@ fn get_token_address_from_symbol(symbol: &str) -> Option<&'static str> {
 pub struct GetTokenPriceTool<C: EthereumClientTrait, U: UniswapRouterTrait> {
     client: Arc<C>,
     uniswap: Arc<U>,
+    fx_rates: Option<Arc<dyn FxRateSource>>,
+    /// Tokens tried in turn as a middle hop when a token has no direct pool
+    intermediaries: Vec<Address>,
+    /// Oracle prices are checked against, with the largest deviation allowed in percent
+    reference_prices: Option<(Arc<dyn ReferencePriceSource>, Decimal)>,
 }
 
 impl<C: EthereumClientTrait, U: UniswapRouterTrait> GetTokenPriceTool<C, U> {
-    pub fn new(client: Arc<C>, uniswap: Arc<U>) -> Self {
-        Self { client, uniswap }
+    pub fn new(client: Arc<C>, uniswap: Arc<U>) -> Result<Self> {
+        Ok(Self {
+            client,
+            uniswap,
+            fx_rates: None,
+            intermediaries: vec![WETH_ADDRESS.parse().context("Invalid WETH address")?],
+            reference_prices: None,
+        })
+    }
+
+    /// Route through `intermediaries`, after WETH, when a token has no direct pool
+    pub fn with_intermediaries(mut self, intermediaries: Vec<Address>) -> Self {
+        for intermediary in intermediaries {
+            if !self.intermediaries.contains(&intermediary) {
+                self.intermediaries.push(intermediary);
+            }
+        }
+        self
+    }
+
+    /// Enable the `fiat_currency` parameter, converting USD prices with `fx_rates`
+    pub fn with_fx_rates(mut self, fx_rates: Arc<dyn FxRateSource>) -> Self {
+        self.fx_rates = Some(fx_rates);
+        self
+    }
+
+    /// Cross-check prices against `reference_prices`, flagging those more than
+    /// `max_deviation_percent` away
+    pub fn with_reference_prices(
+        mut self,
+        reference_prices: Arc<dyn ReferencePriceSource>,
+        max_deviation_percent: Decimal,
+    ) -> Self {
+        self.reference_prices = Some((reference_prices, max_deviation_percent));
+        self
+    }
+
+    /// Oracle price of `token` in the quote currency, or `None` when a feed is missing or
+    /// unreadable. ETH prices are the ratio of the token's and ETH's USD feeds.
+    async fn reference_price(&self, token: Address, in_eth: bool) -> Option<ReferencePrice> {
+        let (source, _) = self.reference_prices.as_ref()?;
+        let token_usd = source.usd_price(token).await.ok()??;
+        if !in_eth {
+            return Some(token_usd);
+        }
+        let weth: Address = WETH_ADDRESS.parse().ok()?;
+        let eth_usd = source.usd_price(weth).await.ok()??;
+        if eth_usd.usd_price.is_zero() {
+            return None;
+        }
+        Some(ReferencePrice {
+            usd_price: token_usd.usd_price / eth_usd.usd_price,
+            source: format!("{} / {}", token_usd.source, eth_usd.source),
+            updated_at: token_usd.updated_at.min(eth_usd.updated_at),
+        })
+    }
+
+    /// Price of one token in USD, read from the USDC pool or through an intermediary
+    async fn usd_price(
+        &self,
+        token_address: Address,
+        amount_in: U256,
+    ) -> Result<(Decimal, Vec<Address>)> {
+        let usdc_address: Address = USDC_ADDRESS.parse().context("Invalid USDC address")?;
+        let (price_ratio, path) = self
+            .routed_price(token_address, usdc_address, amount_in)
+            .await?;
+
+        // Adjust for USDC having 6 decimals vs assumed 18
+        Ok((price_ratio * Decimal::from(10u64.pow(12)), path))
+    }
+
+    /// Base-unit price ratio from the direct pool, or else multiplied across the hops of
+    /// the first intermediary with liquidity on both sides, with the path it was read along
+    async fn routed_price(
+        &self,
+        token: Address,
+        quote: Address,
+        amount_in: U256,
+    ) -> Result<(Decimal, Vec<Address>)> {
+        let direct_error = match self.uniswap.get_price(token, quote, amount_in).await {
+            Ok(ratio) if !ratio.is_zero() => return Ok((ratio, vec![token, quote])),
+            Ok(_) => anyhow::anyhow!("No liquidity between {:?} and {:?}", token, quote),
+            Err(e) => e,
+        };
+        for &intermediary in &self.intermediaries {
+            if intermediary == token || intermediary == quote {
+                continue;
+            }
+            let Ok(first) = self.uniswap.get_price(token, intermediary, amount_in).await else {
+                continue;
+            };
+            // The second hop is quoted for what the first one returns
+            let Some(hop_amount) = from_base_units(amount_in, 18)
+                .ok()
+                .and_then(|amount| amount.checked_mul(first))
+                .and_then(|amount| to_base_units(amount, 18).ok())
+                .filter(|amount| !amount.is_zero())
+            else {
+                continue;
+            };
+            if let Ok(second) = self
+                .uniswap
+                .get_price(intermediary, quote, hop_amount)
+                .await
+            {
+                if let Some(ratio) = first.checked_mul(second).filter(|ratio| !ratio.is_zero()) {
+                    return Ok((ratio, vec![token, intermediary, quote]));
+                }
+            }
+        }
+        Err(direct_error.context(format!(
+            "No direct pool between {:?} and {:?}, and no route through an intermediary",
+            token, quote
+        )))
     }
 }
 
@@ -50,6 +176,10 @@ struct GetTokenPriceParams {
     token_symbol: Option<String>,
     #[serde(default = "default_quote_currency")]
     quote_currency: String, // "ETH" or "USD"
+    #[serde(default)]
+    fiat_currency: Option<String>, // ISO 4217 code, e.g. "EUR"
+    #[serde(flatten)]
+    freshness: FreshnessParams,
 }
 
 fn default_quote_currency() -> String {
@@ -61,6 +191,32 @@ struct GetTokenPriceResult {
     token_address: String,
     price: String,
     quote_currency: String,
+    /// Tokens the price was read along; three entries when routed through an intermediary
+    path: Vec<Address>,
+    /// The price held against an oracle, when there is a feed for the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_check: Option<PriceCheck>,
+    /// Set when the price strays from its oracle reference
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    /// Asset the token stands for, when it is in the equivalence map
+    #[serde(flatten)]
+    identity: Option<AssetIdentity>,
+    #[serde(flatten)]
+    fiat: Option<FiatPrice>,
+    #[serde(flatten)]
+    freshness: Freshness,
+}
+
+/// The USD price converted to a fiat currency
+#[derive(Debug, Serialize)]
+struct FiatPrice {
+    price_usd: String,
+    fiat_currency: String,
+    fiat_price: String,
+    /// Units of fiat_currency per USD
+    fx_rate: String,
+    fx_updated_at: u64,
 }
 
 #[async_trait]
@@ -72,11 +228,15 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
     }
 
     fn description(&self) -> &str {
-        "Get the current price of a token in USD or ETH using Uniswap V2. You can specify the token by address or by symbol (e.g., WETH, USDC, DAI, USDT, UNI, LINK, WBTC, AAVE, MKR, SNX)."
+        "Get the current price of a token in USD or ETH using Uniswap V2. Tokens without a direct USDC (or WETH) pool are priced through WETH or another configured intermediary, and the path used is returned. Where a Chainlink feed exists the price is cross-checked against it, and prices deviating beyond the limit are flagged as anomalous (likely manipulated or illiquid) with a warning. You can specify the token by address or by symbol (e.g., WETH, USDC, DAI, USDT, UNI, LINK, WBTC, AAVE, MKR, SNX). Set fiat_currency (e.g. EUR, GBP, JPY) to also get the USD price converted with Chainlink FX rates. Known tokens are tagged with the asset they stand for and whether they are a bridged, non-canonical variant."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
     }
 
     fn input_schema(&self) -> Value {
-        json!({
+        let mut schema = json!({
             "type": "object",
             "properties": {
                 "token_address": {
@@ -91,13 +251,84 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
                     "type": "string",
                     "description": "Quote currency: 'USD' or 'ETH' (default: USD)",
                     "enum": ["USD", "ETH"]
+                },
+                "fiat_currency": {
+                    "type": "string",
+                    "description": "Also convert the USD price to this fiat currency (ISO 4217 code, e.g. EUR, GBP, JPY)"
                 }
             },
             "oneOf": [
                 {"required": ["token_address"]},
                 {"required": ["token_symbol"]}
             ]
-        })
+        });
+        merge_schema_properties(&mut schema["properties"], freshness_schema_properties());
+        schema
+    }
+
+    fn explain(&self, params: &Value, result: &Value) -> Explanation {
+        let in_eth = params
+            .get("quote_currency")
+            .and_then(Value::as_str)
+            .is_some_and(|quote| quote.eq_ignore_ascii_case("ETH"));
+        let path = result["path"].as_array().cloned().unwrap_or_default();
+        let mut formula = if in_eth {
+            "price = amount_out / 10^18".to_string()
+        } else {
+            "price = amount_out / 10^18 * 10^12 (USDC has 6 decimals, the token is assumed to have 18)".to_string()
+        };
+        if path.len() > 2 {
+            formula.push_str(", with amount_out / amount_in multiplied across both hops");
+        }
+        let mut narrative = format!(
+            "Quoted 1 token (assumed 18 decimals, so 10^18 base units) through the Uniswap V2 router's getAmountsOut along {} and scaled the output to {} {}.",
+            path.iter().map(display).collect::<Vec<_>>().join(" -> "),
+            display(&result["price"]),
+            display(&result["quote_currency"])
+        );
+        if path.len() > 2 {
+            narrative.push_str(&format!(
+                " There is no direct pool, so the second hop was quoted for the {} the first returned.",
+                display(&path[1])
+            ));
+        }
+        if let Some(check) = result.get("price_check") {
+            narrative.push_str(&format!(
+                " It was cross-checked against {} at {}: {}% apart, against a {}% limit{}.",
+                display(&check["reference_source"]),
+                display(&check["reference_price"]),
+                display(&check["deviation_percent"]),
+                display(&check["max_deviation_percent"]),
+                if check["anomalous"] == true {
+                    ", so it is flagged as anomalous"
+                } else {
+                    ""
+                }
+            ));
+        }
+        if result.get("fiat_currency").is_some() {
+            narrative.push_str(&format!(
+                " The USD price {} was divided by the Chainlink rate for {} (updated at {}) to get {}.",
+                display(&result["price_usd"]),
+                display(&result["fiat_currency"]),
+                display(&result["fx_updated_at"]),
+                display(&result["fiat_price"])
+            ));
+        }
+        Explanation::new(
+            narrative,
+            json!({
+                "inputs": params,
+                "venue": "Uniswap V2 router getAmountsOut",
+                "path": path,
+                "amount_in_base_units": "1000000000000000000",
+                "decimals_assumed": 18,
+                "formula": formula,
+                "fiat_formula": result
+                    .get("fiat_currency")
+                    .map(|_| "fiat_price = price_usd / usd_per_unit"),
+            }),
+        )
     }
 
     async fn execute(&self, params: Value) -> Result<Value> {
@@ -123,31 +354,64 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
             .parse()
             .context("Invalid token address")?;
 
+        let freshness = params.freshness.check(self.client.as_ref()).await?;
+
         // Use 1 token as the base amount (with proper decimals)
         let amount_in = U256::from(10u64.pow(18)); // Assume 18 decimals for simplicity
 
-        let price = if params.quote_currency.to_uppercase() == "ETH" {
+        let in_eth = params.quote_currency.to_uppercase() == "ETH";
+        let (price, path) = if in_eth {
             // Get price in WETH
-            let weth_address: Address = WETH_ADDRESS.parse().unwrap();
-            self.uniswap
-                .get_price(token_address, weth_address, amount_in)
+            let weth_address: Address = WETH_ADDRESS.parse().context("Invalid WETH address")?;
+            self.routed_price(token_address, weth_address, amount_in)
                 .await?
         } else {
             // Get price in USDC (which represents USD, 6 decimals)
-            let usdc_address: Address = USDC_ADDRESS.parse().unwrap();
-            let price_ratio = self
-                .uniswap
-                .get_price(token_address, usdc_address, amount_in)
-                .await?;
+            self.usd_price(token_address, amount_in).await?
+        };
+
+        let price_check = match &self.reference_prices {
+            Some((_, max_deviation)) => self
+                .reference_price(token_address, in_eth)
+                .await
+                .map(|reference| PriceCheck::new(price, reference, *max_deviation)),
+            None => None,
+        };
+        let warnings = price_check.iter().filter_map(PriceCheck::warning).collect();
 
-            // Adjust for USDC having 6 decimals vs assumed 18
-            price_ratio * Decimal::from(10u64.pow(12))
+        let fiat = match params.fiat_currency {
+            Some(currency) => {
+                let fx_rates = self
+                    .fx_rates
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("Fiat conversion is not available"))?;
+                let rate = fx_rates.usd_rate(&currency).await?;
+                let price_usd = if in_eth {
+                    self.usd_price(token_address, amount_in).await?.0
+                } else {
+                    price
+                };
+                Some(FiatPrice {
+                    price_usd: price_usd.to_string(),
+                    fiat_currency: rate.currency,
+                    fiat_price: (price_usd / rate.usd_per_unit).normalize().to_string(),
+                    fx_rate: (Decimal::ONE / rate.usd_per_unit).normalize().to_string(),
+                    fx_updated_at: rate.updated_at,
+                })
+            }
+            None => None,
         };
 
         let result = GetTokenPriceResult {
             token_address: token_address_str,
             price: price.to_string(),
             quote_currency: params.quote_currency,
+            path,
+            price_check,
+            warnings,
+            identity: asset_of(self.client.get_chain_id(), Some(token_address)),
+            fiat,
+            freshness,
         };
 
         Ok(serde_json::to_value(result)?)
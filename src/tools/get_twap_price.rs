@@ -0,0 +1,218 @@
+use super::{Tool, ToolCategory};
+use crate::ethereum::uniswap_v3::{deepest_pool, mean_tick, price_at_tick};
+use crate::ethereum::{EthereumClientTrait, UniswapV3Trait, V3PoolState};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_WINDOW_SECS: u32 = 30 * 60;
+// Pools keep at most 65535 observations; a day is beyond what most record
+const MAX_WINDOW_SECS: u32 = 24 * 60 * 60;
+
+// Spot prices further than this from the TWAP are called out
+const SPOT_DEVIATION_WARNING_PERCENT: Decimal = Decimal::from_parts(2, 0, 0, false, 0);
+
+/// Time-weighted average price of a V3 pool over a window ending at the latest block
+#[derive(Debug, Clone, Serialize)]
+pub struct Twap {
+    pub pool_address: Address,
+    pub fee: u32,
+    pub window_secs: u32,
+    pub twap_tick: i32,
+    /// Whole `token_out` per whole `token_in`, averaged over the window
+    pub twap_price: Decimal,
+    /// The pool's price right now, in the same units
+    pub spot_price: Decimal,
+    /// How far spot sits above (positive) or below the TWAP, in percent
+    pub spot_deviation_percent: Decimal,
+}
+
+/// TWAP of `token_in` in `token_out` over `window_secs`, read from the pool's oracle
+/// observations. Moving it takes holding the price for the whole window, so it serves
+/// as a manipulation-resistant reference where a spot price can be pushed in one block.
+pub async fn twap<C: EthereumClientTrait + ?Sized, V: UniswapV3Trait + ?Sized>(
+    client: &C,
+    uniswap_v3: &V,
+    token_in: Address,
+    token_out: Address,
+    fee: Option<u32>,
+    window_secs: u32,
+) -> Result<Twap> {
+    let pools = uniswap_v3.get_pools(token_in, token_out).await?;
+    let pool: &V3PoolState = match fee {
+        Some(fee) => pools.iter().find(|pool| pool.fee == fee),
+        None => deepest_pool(&pools),
+    }
+    .context("No Uniswap V3 pool for this pair and fee tier")?;
+
+    let cumulatives = uniswap_v3
+        .observe(pool.pool_address, &[window_secs, 0])
+        .await?;
+    let [then, now] = cumulatives[..] else {
+        anyhow::bail!(
+            "The pool returned {} observations, expected 2",
+            cumulatives.len()
+        );
+    };
+    let twap_tick = mean_tick(then, now, window_secs);
+
+    let decimals_in = client.get_token_decimals(token_in).await? as i32;
+    let decimals_out = client.get_token_decimals(token_out).await? as i32;
+    // Raw token1 per token0, turned into whole token_out per whole token_in
+    let whole = |raw_token1_per_token0: f64| -> Result<Decimal> {
+        let raw = if token_in == pool.token0 {
+            raw_token1_per_token0
+        } else {
+            1.0 / raw_token1_per_token0
+        };
+        let price = raw * 10f64.powi(decimals_in - decimals_out);
+        Decimal::from_f64(price)
+            .map(|price| price.round_sf(8).unwrap_or(price).normalize())
+            .context("Price is out of range")
+    };
+    let twap_price = whole(price_at_tick(twap_tick))?;
+    let sqrt_price = pool.sqrt_price();
+    let spot_price = whole(sqrt_price * sqrt_price)?;
+    let spot_deviation_percent = if twap_price.is_zero() {
+        Decimal::ZERO
+    } else {
+        ((spot_price - twap_price) / twap_price * Decimal::ONE_HUNDRED)
+            .round_dp(2)
+            .normalize()
+    };
+
+    Ok(Twap {
+        pool_address: pool.pool_address,
+        fee: pool.fee,
+        window_secs,
+        twap_tick,
+        twap_price,
+        spot_price,
+        spot_deviation_percent,
+    })
+}
+
+pub struct GetTwapPriceTool<C: EthereumClientTrait, V: UniswapV3Trait> {
+    client: Arc<C>,
+    uniswap_v3: Arc<V>,
+}
+
+impl<C: EthereumClientTrait, V: UniswapV3Trait> GetTwapPriceTool<C, V> {
+    pub fn new(client: Arc<C>, uniswap_v3: Arc<V>) -> Self {
+        Self { client, uniswap_v3 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetTwapPriceParams {
+    token_in: String,
+    token_out: String,
+    #[serde(default)]
+    fee: Option<u32>,
+    #[serde(default)]
+    window_secs: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetTwapPriceResult {
+    token_in: Address,
+    token_out: Address,
+    #[serde(flatten)]
+    twap: Twap,
+    /// Set when spot has moved away from the TWAP
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, V: UniswapV3Trait + 'static> Tool
+    for GetTwapPriceTool<C, V>
+{
+    fn name(&self) -> &str {
+        "get_twap_price"
+    }
+
+    fn description(&self) -> &str {
+        "Get a Uniswap V3 pool's time-weighted average price (TWAP) over a window, from the pool's own oracle observations. Unlike the spot price it cannot be moved within a single block, so prefer it as the reference when checking whether a price looks manipulated. Returns the TWAP, the current spot price and how far spot has strayed from it."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Pricing
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_in": {
+                    "type": "string",
+                    "description": "Address of the token to price"
+                },
+                "token_out": {
+                    "type": "string",
+                    "description": "Address of the token to price it in"
+                },
+                "fee": {
+                    "type": "integer",
+                    "enum": [100, 500, 3000, 10000],
+                    "description": "Fee tier in hundredths of a bip (default: the pool with the most in-range liquidity)"
+                },
+                "window_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_WINDOW_SECS,
+                    "description": "Seconds the average covers, ending now (default: 1800). The pool must have recorded observations that far back"
+                }
+            },
+            "required": ["token_in", "token_out"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetTwapPriceParams =
+            serde_json::from_value(params).context("Invalid parameters for get_twap_price")?;
+
+        let token_in: Address = params
+            .token_in
+            .parse()
+            .context("Invalid token_in address")?;
+        let token_out: Address = params
+            .token_out
+            .parse()
+            .context("Invalid token_out address")?;
+        let window_secs = params.window_secs.unwrap_or(DEFAULT_WINDOW_SECS);
+        if window_secs == 0 || window_secs > MAX_WINDOW_SECS {
+            anyhow::bail!("window_secs must be between 1 and {}", MAX_WINDOW_SECS);
+        }
+
+        let twap = twap(
+            self.client.as_ref(),
+            self.uniswap_v3.as_ref(),
+            token_in,
+            token_out,
+            params.fee,
+            window_secs,
+        )
+        .await?;
+        let mut warnings = Vec::new();
+        if twap.spot_deviation_percent.abs() > SPOT_DEVIATION_WARNING_PERCENT {
+            warnings.push(format!(
+                "The spot price is {}% away from the {}-second TWAP; it may have been pushed recently, so rely on the TWAP",
+                twap.spot_deviation_percent, window_secs
+            ));
+        }
+
+        let result = GetTwapPriceResult {
+            token_in,
+            token_out,
+            twap,
+            warnings,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
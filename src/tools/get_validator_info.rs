@@ -0,0 +1,229 @@
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{
+    BeaconSource, EthereumClientTrait, TransactionHistorySource, Validator, FAR_FUTURE_EPOCH,
+    SECONDS_PER_SLOT,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_APR_DAYS: u64 = 7;
+const MAX_APR_DAYS: u64 = 90;
+
+// Withdrawals read per APR window; a validator is swept about every week
+const MAX_WITHDRAWALS: usize = 1_000;
+
+pub struct GetValidatorInfoTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    beacon: Arc<dyn BeaconSource>,
+    history: Arc<dyn TransactionHistorySource>,
+}
+
+impl<C: EthereumClientTrait> GetValidatorInfoTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        beacon: Arc<dyn BeaconSource>,
+        history: Arc<dyn TransactionHistorySource>,
+    ) -> Self {
+        Self {
+            client,
+            beacon,
+            history,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetValidatorInfoParams {
+    /// Index or 0x pubkey
+    validator: Value,
+    #[serde(default)]
+    apr_days: Option<u64>,
+}
+
+/// Rewards earned over the APR window, counting balance swept out by withdrawals
+#[derive(Debug, Serialize)]
+struct Apr {
+    days: u64,
+    /// Balance change plus withdrawals, in ETH; negative after penalties
+    rewards: String,
+    withdrawn: String,
+    apr_percent: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GetValidatorInfoResult {
+    index: u64,
+    pubkey: String,
+    status: String,
+    balance: String,
+    effective_balance: String,
+    slashed: bool,
+    /// bls (0x00), execution (0x01) or compounding (0x02)
+    withdrawal_credentials_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    withdrawal_address: Option<Address>,
+    /// Whether withdrawals are credited to the server's wallet
+    withdraws_to_wallet: bool,
+    /// Unset while waiting to be activated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    activation_epoch: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_epoch: Option<u64>,
+    /// Unset when the validator was not active over the whole window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apr: Option<Apr>,
+    source: String,
+}
+
+fn gwei_to_eth(gwei: u64) -> Result<Decimal> {
+    from_base_units(U256::from(gwei), 9)
+}
+
+fn credentials_type(validator: &Validator) -> &'static str {
+    match validator.withdrawal_credentials.as_bytes()[0] {
+        0x01 => "execution",
+        0x02 => "compounding",
+        _ => "bls",
+    }
+}
+
+impl<C: EthereumClientTrait> GetValidatorInfoTool<C> {
+    /// Rewards of `now` over the `days` before it, or None when the validator was
+    /// not yet active at the start of the window
+    async fn apr(
+        &self,
+        id: &str,
+        now: &Validator,
+        head_slot: u64,
+        days: u64,
+    ) -> Result<Option<Apr>> {
+        let window_slots = days * 86_400 / SECONDS_PER_SLOT;
+        let Some(start_slot) = head_slot.checked_sub(window_slots) else {
+            return Ok(None);
+        };
+        let Ok(then) = self.beacon.validator(id, Some(start_slot)).await else {
+            return Ok(None);
+        };
+        if !then.status.starts_with("active") || then.effective_balance_gwei == 0 {
+            return Ok(None);
+        }
+
+        // Balance above the effective balance is swept out and no longer shows
+        let withdrawn_gwei: u64 = match now.withdrawal_address() {
+            Some(address) => {
+                let since = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)?
+                    .as_secs()
+                    .saturating_sub(days * 86_400);
+                self.history
+                    .withdrawals(address, since, MAX_WITHDRAWALS)
+                    .await
+                    .context("Failed to read the validator's withdrawals")?
+                    .iter()
+                    .filter(|withdrawal| withdrawal.validator_index == now.index)
+                    .map(|withdrawal| withdrawal.amount_gwei)
+                    .sum()
+            }
+            None => 0,
+        };
+        let rewards = gwei_to_eth(now.balance_gwei)? + gwei_to_eth(withdrawn_gwei)?
+            - gwei_to_eth(then.balance_gwei)?;
+        let apr = rewards / gwei_to_eth(then.effective_balance_gwei)? * Decimal::from(365)
+            / Decimal::from(days)
+            * Decimal::ONE_HUNDRED;
+        Ok(Some(Apr {
+            days,
+            rewards: rewards.normalize().to_string(),
+            withdrawn: gwei_to_eth(withdrawn_gwei)?.normalize().to_string(),
+            apr_percent: apr.round_dp(2).normalize().to_string(),
+        }))
+    }
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetValidatorInfoTool<C> {
+    fn name(&self) -> &str {
+        "get_validator_info"
+    }
+
+    fn description(&self) -> &str {
+        "Look up a beacon-chain validator by index or pubkey on the configured beacon node: status, balance, effective balance, slashing, activation and exit epochs, and where its withdrawals go, including whether that is the server's wallet. The APR is estimated from its balance change over the last apr_days plus the withdrawals swept to its withdrawal address meanwhile."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "validator": {
+                    "type": ["string", "integer"],
+                    "description": "Validator index or 0x BLS pubkey"
+                },
+                "apr_days": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_APR_DAYS,
+                    "description": "Days the APR is measured over (default: 7)"
+                }
+            },
+            "required": ["validator"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetValidatorInfoParams =
+            serde_json::from_value(params).context("Invalid parameters for get_validator_info")?;
+        let id = match &params.validator {
+            Value::Number(index) => index
+                .as_u64()
+                .context("Validator index must be a whole number")?
+                .to_string(),
+            Value::String(id) if id.trim().starts_with("0x") => id.trim().to_lowercase(),
+            Value::String(id) => id
+                .trim()
+                .parse::<u64>()
+                .context("Validator must be an index or a 0x pubkey")?
+                .to_string(),
+            _ => anyhow::bail!("Validator must be an index or a 0x pubkey"),
+        };
+        let days = params.apr_days.unwrap_or(DEFAULT_APR_DAYS);
+        if days == 0 || days > MAX_APR_DAYS {
+            anyhow::bail!("apr_days must be between 1 and {}", MAX_APR_DAYS);
+        }
+
+        let head_slot = self.beacon.head_slot().await?;
+        let validator = self.beacon.validator(&id, None).await?;
+        let apr = self.apr(&id, &validator, head_slot, days).await?;
+        let withdrawal_address = validator.withdrawal_address();
+
+        let result = GetValidatorInfoResult {
+            index: validator.index,
+            pubkey: validator.pubkey.clone(),
+            status: validator.status.clone(),
+            balance: gwei_to_eth(validator.balance_gwei)?.normalize().to_string(),
+            effective_balance: gwei_to_eth(validator.effective_balance_gwei)?
+                .normalize()
+                .to_string(),
+            slashed: validator.slashed,
+            withdrawal_credentials_type: credentials_type(&validator),
+            withdrawal_address,
+            withdraws_to_wallet: withdrawal_address == Some(self.client.get_wallet_address()),
+            activation_epoch: (validator.activation_epoch != FAR_FUTURE_EPOCH)
+                .then_some(validator.activation_epoch),
+            exit_epoch: (validator.exit_epoch != FAR_FUTURE_EPOCH).then_some(validator.exit_epoch),
+            apr,
+            source: self.beacon.name().to_string(),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
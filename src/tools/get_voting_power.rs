@@ -0,0 +1,161 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::get_governance_proposals::Dao;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GovernanceSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct GetVotingPowerTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    governor: Arc<dyn GovernanceSource>,
+    snapshot: Arc<dyn GovernanceSource>,
+    governors: Vec<(String, Address)>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> GetVotingPowerTool<C> {
+    pub fn new(
+        client: Arc<C>,
+        governor: Arc<dyn GovernanceSource>,
+        snapshot: Arc<dyn GovernanceSource>,
+    ) -> Self {
+        Self {
+            client,
+            governor,
+            snapshot,
+            governors: Vec::new(),
+            book: None,
+        }
+    }
+
+    /// Governors that can be passed by name
+    pub fn with_governors(mut self, governors: Vec<(String, Address)>) -> Self {
+        self.governors = governors;
+        self
+    }
+
+    /// Accept a contact name as the wallet
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GetVotingPowerParams {
+    #[serde(default)]
+    governor: Option<String>,
+    #[serde(default)]
+    space: Option<String>,
+    #[serde(default)]
+    wallet: Option<String>,
+    #[serde(default)]
+    proposal_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetVotingPowerResult {
+    source: String,
+    dao: String,
+    wallet: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proposal_id: Option<String>,
+    votes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    has_voted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for GetVotingPowerTool<C> {
+    fn name(&self) -> &str {
+        "get_voting_power"
+    }
+
+    fn description(&self) -> &str {
+        "Show a wallet's voting power in a DAO, now or at a proposal's snapshot: on an on-chain Governor, its delegated votes, token balance, current delegate and whether it already voted on the proposal; on a Snapshot space, its voting power under the space's strategies. Defaults to the server's wallet, and flags tokens that carry no votes because they were never delegated."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "governor": {
+                    "type": "string",
+                    "description": "Governor contract address or known name, as for get_governance_proposals"
+                },
+                "space": {
+                    "type": "string",
+                    "description": "Snapshot space id, e.g. 'aave.eth'; instead of governor"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Address or contact name (default: the server's wallet)"
+                },
+                "proposal_id": {
+                    "type": "string",
+                    "description": "Proposal to measure power for, at its snapshot, and check for an existing vote"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: GetVotingPowerParams =
+            serde_json::from_value(params).context("Invalid parameters for get_voting_power")?;
+        let dao = Dao::resolve(
+            &self.governors,
+            params.governor.as_deref(),
+            params.space.as_deref(),
+        )?;
+        let wallet = match params.wallet.as_deref() {
+            Some(wallet) => {
+                resolve_address(self.book.as_deref(), wallet).context("Invalid wallet")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        let source = match dao {
+            Dao::Governor(_) => &self.governor,
+            Dao::Space(_) => &self.snapshot,
+        };
+
+        let power = source
+            .voting_power(&dao.id(), wallet, params.proposal_id.as_deref())
+            .await?;
+        let hint = match (power.balance, power.delegate) {
+            (Some(balance), None) if balance > Decimal::ZERO => Some(
+                "The wallet's tokens are not delegated, so they carry no votes; delegate them (to the wallet itself to vote directly) before the next proposal's snapshot".to_string(),
+            ),
+            _ => None,
+        };
+        let result = GetVotingPowerResult {
+            source: source.name().to_string(),
+            dao: dao.id(),
+            wallet,
+            proposal_id: params.proposal_id,
+            votes: power.votes.normalize().to_string(),
+            symbol: power.symbol,
+            balance: power.balance.map(|balance| balance.normalize().to_string()),
+            delegate: power.delegate,
+            has_voted: power.has_voted,
+            hint,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
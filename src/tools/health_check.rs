@@ -0,0 +1,107 @@
+use super::Tool;
+use crate::ethereum::{EthereumClientTrait, NewHeads, NewHeadsStatus};
+use crate::supervisor::{Supervisor, TaskHealth};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct HealthCheckTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    supervisor: Arc<Supervisor>,
+    heads: Option<Arc<NewHeads>>,
+}
+
+impl<C: EthereumClientTrait> HealthCheckTool<C> {
+    pub fn new(client: Arc<C>, supervisor: Arc<Supervisor>) -> Self {
+        Self {
+            client,
+            supervisor,
+            heads: None,
+        }
+    }
+
+    /// Also report the WebSocket subscription new blocks are followed on
+    pub fn with_new_heads(mut self, heads: Arc<NewHeads>) -> Self {
+        self.heads = Some(heads);
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum HealthStatus {
+    Ok,
+    /// A background task is failing, but tool calls are served
+    Degraded,
+    /// The node cannot be reached
+    Unavailable,
+}
+
+#[derive(Debug, Serialize)]
+struct ChainHealth {
+    chain_id: u64,
+    rpc_endpoint: String,
+    block_number: Option<u64>,
+    error: Option<String>,
+    /// Absent when no WebSocket endpoint is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    websocket: Option<NewHeadsStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthCheckResult {
+    status: HealthStatus,
+    chain: ChainHealth,
+    background_tasks: Vec<TaskHealth>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for HealthCheckTool<C> {
+    fn name(&self) -> &str {
+        "health_check"
+    }
+
+    fn description(&self) -> &str {
+        "Check the server is working: whether the node answers and its latest block, and \
+         the state of each background task (scam list refresh, address watcher, payment \
+         scheduler, WebSocket block feed) with how often it has been restarted after \
+         crashing and why it last stopped. With a WebSocket endpoint, also whether it is \
+         connected and how often it has reconnected. Status is ok, degraded when a task is \
+         restarting or has been given up on, or unavailable when the node cannot be reached."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn execute(&self, _params: Value) -> Result<Value> {
+        let (block_number, error) = match self.client.get_block_number().await {
+            Ok(block) => (Some(block), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        let status = if block_number.is_none() {
+            HealthStatus::Unavailable
+        } else if self.supervisor.degraded() {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Ok
+        };
+        let result = HealthCheckResult {
+            status,
+            chain: ChainHealth {
+                chain_id: self.client.get_chain_id(),
+                rpc_endpoint: self.client.rpc_endpoint(),
+                block_number,
+                error,
+                websocket: self.heads.as_ref().map(|heads| heads.status()),
+            },
+            background_tasks: self.supervisor.health(),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
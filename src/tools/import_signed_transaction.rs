@@ -0,0 +1,240 @@
+use super::alerts::{AlertSeverity, AlertSink};
+use super::build_transaction::qr_decode;
+use super::execute_plan::TRANSACTION_TOPIC;
+use super::gas_reserve::{max_spend, GasReserve};
+use super::scam_list::ScamList;
+use super::{PermissionTier, Tool, ToolCategory};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::rlp::Rlp;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct ImportSignedTransactionTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    execution_enabled: bool,
+    gas_reserve: GasReserve,
+    scams: Option<Arc<ScamList>>,
+    alerts: Option<Arc<dyn AlertSink>>,
+}
+
+// Token calls whose first argument receives funds or control of them: ERC20 transfer
+// and approve, and setApprovalForAll
+const PAYING_SELECTORS: [[u8; 4]; 3] = [
+    [0xa9, 0x05, 0x9c, 0xbb],
+    [0x09, 0x5e, 0xa7, 0xb3],
+    [0xa2, 0x2c, 0xb4, 0x65],
+];
+
+impl<C: EthereumClientTrait> ImportSignedTransactionTool<C> {
+    /// `execution_enabled` gates broadcasting; without it transactions are only decoded
+    pub fn new(client: Arc<C>, execution_enabled: bool) -> Self {
+        Self {
+            client,
+            execution_enabled,
+            gas_reserve: GasReserve::default(),
+            scams: None,
+            alerts: None,
+        }
+    }
+
+    /// ETH a broadcast must leave with the signer for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.gas_reserve = gas_reserve;
+        self
+    }
+
+    /// Refuse to broadcast transactions that pay or approve addresses on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.scams = Some(scams);
+        self
+    }
+
+    /// Push broadcasts, and broadcasts the node rejected, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+}
+
+/// The recipient of `tx` and, for token transfers and approvals, the address its
+/// calldata pays or approves
+fn counterparties(tx: &TypedTransaction) -> Vec<Address> {
+    let mut found: Vec<Address> = tx.to_addr().into_iter().copied().collect();
+    if let Some(data) = tx.data() {
+        if data.len() >= 36
+            && PAYING_SELECTORS
+                .iter()
+                .any(|selector| data[..4] == selector[..])
+        {
+            found.push(Address::from_slice(&data[16..36]));
+        }
+    }
+    found
+}
+
+/// A hex string, or the frames of an animated QR code
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SignedPayload {
+    Single(String),
+    Frames(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportSignedTransactionParams {
+    signed_transaction: SignedPayload,
+    #[serde(default)]
+    broadcast: bool,
+    #[serde(default)]
+    override_gas_reserve: bool,
+    #[serde(default)]
+    override_scam_list: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportSignedTransactionResult {
+    tx_hash: String,
+    signer: String,
+    chain_id: u64,
+    nonce: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    value: String,
+    gas_limit: String,
+    broadcast: bool,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ImportSignedTransactionTool<C> {
+    fn name(&self) -> &str {
+        "import_signed_transaction"
+    }
+
+    fn description(&self) -> &str {
+        "Decode a transaction signed on an offline device (hex, or the QR frames of one) and optionally broadcast it. Reports the recovered signer, nonce and recipient; refuses transactions signed for another chain."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn moves_funds(&self, params: &Value) -> bool {
+        params.get("broadcast").and_then(Value::as_bool) == Some(true)
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "signed_transaction": {
+                    "description": "Signed transaction as hex (any case, 0x optional), or an array of QR frames `TAG/index/total/data`",
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ]
+                },
+                "broadcast": {
+                    "type": "boolean",
+                    "description": "Send the transaction to the network (default: false, decode only). Execution must be enabled on the server"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Broadcast even if the signer would be left below the ETH reserve kept for gas; only honored when the server's policy allows it (default: false)"
+                },
+                "override_scam_list": {
+                    "type": "boolean",
+                    "description": "Broadcast even if the transaction pays or approves an address on the scam list; only honored when the server's policy allows it (default: false)"
+                }
+            },
+            "required": ["signed_transaction"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ImportSignedTransactionParams = serde_json::from_value(params)
+            .context("Invalid parameters for import_signed_transaction")?;
+
+        let raw = match params.signed_transaction {
+            SignedPayload::Single(hex) => qr_decode(&[hex])?,
+            SignedPayload::Frames(frames) => qr_decode(&frames)?,
+        };
+        let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .context("Not a signed Ethereum transaction")?;
+        let signer = signature
+            .recover(tx.sighash())
+            .context("Failed to recover signer")?;
+
+        let chain_id = tx.chain_id().map(|id| id.as_u64());
+        let expected = self.client.get_chain_id();
+        if chain_id != Some(expected) {
+            anyhow::bail!(
+                "Transaction is signed for chain {}, but this server is on chain {}",
+                chain_id.map_or("none (pre-EIP-155)".to_string(), |id| id.to_string()),
+                expected
+            );
+        }
+
+        if params.broadcast && !self.execution_enabled {
+            anyhow::bail!(
+                "Broadcasting is disabled on this server; set ENABLE_EXECUTION=true or omit broadcast"
+            );
+        }
+        self.gas_reserve
+            .check_override(params.override_gas_reserve)?;
+        let tx_hash = if params.broadcast {
+            if let Some(scams) = &self.scams {
+                scams.ensure_unlisted(&counterparties(&tx), params.override_scam_list)?;
+            }
+            if !params.override_gas_reserve {
+                let spend = max_spend(
+                    tx.value().copied().unwrap_or_default(),
+                    tx.gas().copied().unwrap_or_default(),
+                    tx.gas_price().unwrap_or_default(),
+                );
+                self.gas_reserve
+                    .check(self.client.as_ref(), signer, spend)
+                    .await?;
+            }
+            let sent = self.client.send_raw_transaction(raw.into()).await;
+            if let Some(alerts) = &self.alerts {
+                let (event, severity) = match &sent {
+                    Ok(_) => ("transaction_broadcast", AlertSeverity::Info),
+                    Err(_) => ("transaction_failed", AlertSeverity::Warning),
+                };
+                let alert = json!({
+                    "event": event,
+                    "action": "import_signed_transaction",
+                    "tx_hash": sent.as_ref().ok().map(|hash| format!("{:?}", hash)),
+                    "signer": format!("{:?}", signer),
+                    "error": sent.as_ref().err().map(|e| e.to_string()),
+                });
+                alerts.send(TRANSACTION_TOPIC, severity, &alert).await;
+            }
+            sent?
+        } else {
+            H256::from(ethers::utils::keccak256(&raw))
+        };
+
+        let result = ImportSignedTransactionResult {
+            tx_hash: format!("{:?}", tx_hash),
+            signer: format!("{:?}", signer),
+            chain_id: expected,
+            nonce: tx.nonce().copied().unwrap_or_default().as_u64(),
+            to: tx.to_addr().map(|to| format!("{:?}", to)),
+            value: tx.value().copied().unwrap_or_default().to_string(),
+            gas_limit: tx.gas().copied().unwrap_or_default().to_string(),
+            broadcast: params.broadcast,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
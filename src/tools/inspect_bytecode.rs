@@ -0,0 +1,327 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::freshness::{finality_schema_properties, Finality};
+use super::read_storage::{detect_proxy, ProxyInfo};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::bytecode::{strip_metadata, OP_CALLCODE, OP_DELEGATECALL, OP_SELFDESTRUCT};
+use crate::ethereum::{
+    disassemble, function_selectors, minimal_proxy_target, EthereumClientTrait, SignatureSource,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::{hex, keccak256};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Instructions listed per contract when a disassembly is asked for
+const MAX_LISTED_INSTRUCTIONS: usize = 2_000;
+
+// Offsets reported per flagged opcode; the count covers the rest
+const MAX_LISTED_OFFSETS: usize = 10;
+
+// Function name fragments that give a token's owner power over holders, with the concern.
+// Matched against the lowercased name, so the first match wins.
+const PRIVILEGED_FUNCTIONS: &[(&str, &str)] = &[
+    ("upgradeto", "can replace the contract's code"),
+    ("mint", "can create new supply"),
+    ("unpause", "can freeze and unfreeze transfers"),
+    ("pause", "can freeze transfers"),
+    ("blacklist", "can block addresses from transferring"),
+    ("blocklist", "can block addresses from transferring"),
+    ("bot", "can block addresses from transferring"),
+    ("setfee", "can change the fee taken on transfers"),
+    ("settax", "can change the fee taken on transfers"),
+    ("updatefee", "can change the fee taken on transfers"),
+    ("setmaxtx", "can cap how much may be transferred"),
+    ("setmaxwallet", "can cap how much a holder may own"),
+    (
+        "excludefrom",
+        "can exempt chosen addresses from fees or limits",
+    ),
+    ("enabletrading", "controls when trading is allowed"),
+];
+
+pub struct InspectBytecodeTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    signatures: Arc<dyn SignatureSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> InspectBytecodeTool<C> {
+    pub fn new(client: Arc<C>, signatures: Arc<dyn SignatureSource>) -> Self {
+        Self {
+            client,
+            signatures,
+            book: None,
+        }
+    }
+
+    /// Accept contact names as the contract
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InspectBytecodeParams {
+    address: String,
+    #[serde(default)]
+    block: Option<u64>,
+    #[serde(default)]
+    finality: Finality,
+    #[serde(default = "default_follow_proxy")]
+    follow_proxy: bool,
+    #[serde(default)]
+    disassembly: bool,
+}
+
+fn default_follow_proxy() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct SelectorReport {
+    selector: String,
+    /// Known signatures, most likely first; empty when the selector is unknown
+    signatures: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FlaggedOpcode {
+    opcode: &'static str,
+    count: usize,
+    /// Code offsets of the first occurrences
+    offsets: Vec<usize>,
+    note: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct PrivilegedFunction {
+    signature: String,
+    concern: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct CodeReport {
+    address: Address,
+    size: usize,
+    code_hash: H256,
+    selectors: Vec<SelectorReport>,
+    flagged_opcodes: Vec<FlaggedOpcode>,
+    privileged_functions: Vec<PrivilegedFunction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disassembly: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disassembly_truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct InspectBytecodeResult {
+    #[serde(flatten)]
+    code: CodeReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<ProxyInfo>,
+    /// Target of an EIP-1167 minimal proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clone_of: Option<Address>,
+    /// The code the proxy or clone runs, when followed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    implementation: Option<CodeReport>,
+    /// Why selectors could not be named, when the signature lookup failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature_lookup_error: Option<String>,
+}
+
+/// Opcodes worth a look in code that holds funds, with what they allow
+fn flagged_opcodes(code: &[u8], is_proxy: bool) -> Vec<FlaggedOpcode> {
+    let delegatecall_note = if is_proxy {
+        "Expected in a proxy: calls are forwarded to the implementation"
+    } else {
+        "Runs another contract's code with this contract's storage and balance"
+    };
+    let mut offsets: HashMap<u8, Vec<usize>> = HashMap::new();
+    for instruction in disassemble(strip_metadata(code)) {
+        if matches!(
+            instruction.opcode,
+            OP_SELFDESTRUCT | OP_DELEGATECALL | OP_CALLCODE
+        ) {
+            offsets
+                .entry(instruction.opcode)
+                .or_default()
+                .push(instruction.pc);
+        }
+    }
+    [
+        (
+            OP_SELFDESTRUCT,
+            "SELFDESTRUCT",
+            "Can send away the contract's ETH, and delete it when run in its creation transaction",
+        ),
+        (OP_DELEGATECALL, "DELEGATECALL", delegatecall_note),
+        (
+            OP_CALLCODE,
+            "CALLCODE",
+            "Deprecated DELEGATECALL: runs another contract's code with this contract's storage",
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(opcode, name, note)| {
+        let found = offsets.remove(&opcode)?;
+        Some(FlaggedOpcode {
+            opcode: name,
+            count: found.len(),
+            offsets: found.into_iter().take(MAX_LISTED_OFFSETS).collect(),
+            note,
+        })
+    })
+    .collect()
+}
+
+fn privileged_functions(selectors: &[SelectorReport]) -> Vec<PrivilegedFunction> {
+    selectors
+        .iter()
+        .filter_map(|selector| {
+            let signature = selector.signatures.first()?;
+            let name = signature.split('(').next()?.to_lowercase();
+            let (_, concern) = PRIVILEGED_FUNCTIONS
+                .iter()
+                .find(|(fragment, _)| name.contains(fragment))?;
+            Some(PrivilegedFunction {
+                signature: signature.clone(),
+                concern,
+            })
+        })
+        .collect()
+}
+
+fn code_report(
+    address: Address,
+    code: &[u8],
+    is_proxy: bool,
+    names: &HashMap<[u8; 4], Vec<String>>,
+    disassembly: bool,
+) -> CodeReport {
+    let selectors: Vec<SelectorReport> = function_selectors(code)
+        .into_iter()
+        .map(|selector| SelectorReport {
+            selector: format!("0x{}", hex::encode(selector)),
+            signatures: names.get(&selector).cloned().unwrap_or_default(),
+        })
+        .collect();
+    let instructions = disassembly.then(|| disassemble(strip_metadata(code)));
+    CodeReport {
+        address,
+        size: code.len(),
+        code_hash: H256::from(keccak256(code)),
+        flagged_opcodes: flagged_opcodes(code, is_proxy),
+        privileged_functions: privileged_functions(&selectors),
+        selectors,
+        disassembly_truncated: instructions
+            .as_ref()
+            .is_some_and(|instructions| instructions.len() > MAX_LISTED_INSTRUCTIONS),
+        disassembly: instructions.map(|instructions| {
+            instructions
+                .iter()
+                .take(MAX_LISTED_INSTRUCTIONS)
+                .map(|instruction| format!("{:#06x} {}", instruction.pc, instruction.text()))
+                .collect()
+        }),
+    }
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for InspectBytecodeTool<C> {
+    fn name(&self) -> &str {
+        "inspect_bytecode"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect a contract's deployed bytecode, verified or not: extract the function selectors its dispatcher checks and name them from the 4byte directory, flag SELFDESTRUCT, DELEGATECALL and CALLCODE, and list functions that give an owner power over token holders (minting, pausing, blacklists, fee changes, upgrades). Proxies and minimal clones are detected and their implementation inspected too. Optionally returns a disassembly."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Contract address or contact name"
+                },
+                "block": {
+                    "type": "integer",
+                    "description": "Block number to read the code at (default: latest)"
+                },
+                "follow_proxy": {
+                    "type": "boolean",
+                    "description": "Also inspect the implementation behind a proxy or clone (default: true)"
+                },
+                "disassembly": {
+                    "type": "boolean",
+                    "description": "Include the disassembled instructions, at most 2000 per contract (default: false)"
+                }
+            },
+            "required": ["address"]
+        });
+        merge_schema_properties(&mut schema["properties"], finality_schema_properties());
+        schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: InspectBytecodeParams =
+            serde_json::from_value(params).context("Invalid parameters for inspect_bytecode")?;
+        let block = params
+            .finality
+            .resolve(self.client.as_ref(), params.block)
+            .await?;
+        let address =
+            resolve_address(self.book.as_deref(), &params.address).context("Invalid address")?;
+        let code = self.client.get_code(address, block).await?;
+        if code.is_empty() {
+            anyhow::bail!("No contract code at {:?}", address);
+        }
+
+        let clone_of = minimal_proxy_target(&code);
+        let proxy = match clone_of {
+            Some(_) => None,
+            None => detect_proxy(self.client.as_ref(), address, block).await?,
+        };
+        let target = clone_of.or(proxy.as_ref().map(|proxy| proxy.implementation));
+        let implementation_code = match target {
+            Some(target) if params.follow_proxy => {
+                let code = self.client.get_code(target, block).await?;
+                (!code.is_empty()).then_some((target, code))
+            }
+            _ => None,
+        };
+
+        let mut selectors = function_selectors(&code);
+        if let Some((_, code)) = &implementation_code {
+            selectors.extend(function_selectors(code));
+        }
+        let (names, signature_lookup_error) = match self.signatures.lookup(&selectors).await {
+            Ok(names) => (names, None),
+            Err(e) => (HashMap::new(), Some(format!("{:#}", e))),
+        };
+
+        let result = InspectBytecodeResult {
+            code: code_report(address, &code, target.is_some(), &names, params.disassembly),
+            block,
+            proxy,
+            clone_of,
+            implementation: implementation_code.map(|(target, code)| {
+                code_report(target, &code, false, &names, params.disassembly)
+            }),
+            signature_lookup_error,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use ethers::abi::Token;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use serde::Serialize;
+
+/// ERC-165 interface ids
+pub const ERC165_INTERFACE: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+pub const ERC721_INTERFACE: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+pub const ERC1155_INTERFACE: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+// ERC-1820 registry, where ERC-777 tokens register themselves
+const ERC1820_REGISTRY: &str = "0x1820a4B7618BdE71Dce8cdc73aAB6C95905faD24";
+
+/// Token standard a contract was detected to implement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc777,
+    Erc1155,
+}
+
+/// `eth_call` `signature` on `contract` with `args`
+pub(crate) async fn call<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    contract: Address,
+    signature: &str,
+    args: &[Token],
+) -> Result<Bytes> {
+    call_at(client, contract, signature, args, None).await
+}
+
+/// `eth_call` `signature` on `contract` with `args` at `block` (default: latest)
+pub(crate) async fn call_at<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    contract: Address,
+    signature: &str,
+    args: &[Token],
+    block: Option<u64>,
+) -> Result<Bytes> {
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(contract)
+        .data([&keccak256(signature)[..4], &ethers::abi::encode(args)[..]].concat())
+        .into();
+    client
+        .call_contract(
+            &tx,
+            block.map(|block| BlockId::from(U64::from(block))),
+            None,
+        )
+        .await
+}
+
+fn word_is_true(output: Result<Bytes>) -> bool {
+    output
+        .is_ok_and(|output| output.len() >= 32 && U256::from_big_endian(&output[..32]) == 1.into())
+}
+
+async fn supports<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    contract: Address,
+    interface_id: [u8; 4],
+) -> Result<Bytes> {
+    let args = [Token::FixedBytes(interface_id.to_vec())];
+    call(client, contract, "supportsInterface(bytes4)", &args).await
+}
+
+/// Whether `contract` reports `interface_id` through ERC-165. Contracts that answer
+/// yes to everything, or revert, count as not supporting it.
+pub async fn supports_interface<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    contract: Address,
+    interface_id: [u8; 4],
+) -> bool {
+    if !word_is_true(supports(client, contract, ERC165_INTERFACE).await) {
+        return false;
+    }
+    if interface_id == ERC165_INTERFACE {
+        return true;
+    }
+    !word_is_true(supports(client, contract, [0xff; 4]).await)
+        && word_is_true(supports(client, contract, interface_id).await)
+}
+
+/// Whether `token` registered itself as an ERC-777 token in the ERC-1820 registry
+pub async fn is_erc777<C: EthereumClientTrait + ?Sized>(client: &C, token: Address) -> bool {
+    let Ok(registry) = ERC1820_REGISTRY.parse::<Address>() else {
+        return false;
+    };
+    let output = call(
+        client,
+        registry,
+        "getInterfaceImplementer(address,bytes32)",
+        &[
+            Token::Address(token),
+            Token::FixedBytes(keccak256("ERC777Token").to_vec()),
+        ],
+    )
+    .await;
+    output.is_ok_and(|output| output.len() >= 32 && Address::from_slice(&output[12..32]) == token)
+}
+
+/// Standard of the token at `token`: ERC-1155 and ERC-721 by ERC-165, ERC-777 by its
+/// ERC-1820 registration, and ERC-20 otherwise
+pub async fn detect_token_standard<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+) -> TokenStandard {
+    if supports_interface(client, token, ERC1155_INTERFACE).await {
+        TokenStandard::Erc1155
+    } else if supports_interface(client, token, ERC721_INTERFACE).await {
+        TokenStandard::Erc721
+    } else if is_erc777(client, token).await {
+        TokenStandard::Erc777
+    } else {
+        TokenStandard::Erc20
+    }
+}
+
+/// Raw `balanceOf(owner, id)` of an ERC-1155 token at `block` (default: latest)
+pub async fn erc1155_balance<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+    owner: Address,
+    id: U256,
+    block: Option<u64>,
+) -> Result<U256> {
+    let output = call_at(
+        client,
+        token,
+        "balanceOf(address,uint256)",
+        &[Token::Address(owner), Token::Uint(id)],
+        block,
+    )
+    .await
+    .context("Failed to get ERC-1155 balance")?;
+    if output.len() < 32 {
+        anyhow::bail!("Malformed ERC-1155 balanceOf output");
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
+
+/// Raw `balanceOf(owner)` of an ERC-721 collection: the number of tokens held, at
+/// `block` (default: latest)
+pub async fn erc721_balance<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+    owner: Address,
+    block: Option<u64>,
+) -> Result<U256> {
+    let output = call_at(
+        client,
+        token,
+        "balanceOf(address)",
+        &[Token::Address(owner)],
+        block,
+    )
+    .await
+    .context("Failed to get ERC-721 balance")?;
+    if output.len() < 32 {
+        anyhow::bail!("Malformed ERC-721 balanceOf output");
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
+
+/// `ownerOf(id)` of an ERC-721 collection
+pub async fn erc721_owner<C: EthereumClientTrait + ?Sized>(
+    client: &C,
+    token: Address,
+    id: U256,
+) -> Result<Address> {
+    let output = call(client, token, "ownerOf(uint256)", &[Token::Uint(id)])
+        .await
+        .context("Failed to get ERC-721 owner; the token may not exist")?;
+    if output.len() < 32 {
+        anyhow::bail!("Malformed ERC-721 ownerOf output");
+    }
+    Ok(Address::from_slice(&output[12..32]))
+}
@@ -0,0 +1,59 @@
+use super::address_book::{AddressBook, Contact};
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::Tool;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct ListContactsTool {
+    book: Arc<AddressBook>,
+}
+
+impl ListContactsTool {
+    pub fn new(book: Arc<AddressBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListContactsParams {
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct ListContactsResult {
+    #[serde(flatten)]
+    contacts: Page<Contact>,
+    confirm_unknown_recipients: bool,
+}
+
+#[async_trait]
+impl Tool for ListContactsTool {
+    fn name(&self) -> &str {
+        "list_contacts"
+    }
+
+    fn description(&self) -> &str {
+        "List the address book: every contact name with its address and note."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": page_schema_properties()
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ListContactsParams =
+            serde_json::from_value(params).context("Invalid parameters for list_contacts")?;
+        let result = ListContactsResult {
+            contacts: params.page.paginate(self.book.list())?,
+            confirm_unknown_recipients: self.book.confirms_unknown_recipients(),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,118 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::get_ens_name::{EnsNameReport, EnsStatus};
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{EnsSource, EthereumClientTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct ListEnsNamesTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    ens: Arc<dyn EnsSource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> ListEnsNamesTool<C> {
+    pub fn new(client: Arc<C>, ens: Arc<dyn EnsSource>) -> Self {
+        Self {
+            client,
+            ens,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the wallet
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListEnsNamesParams {
+    #[serde(default)]
+    wallet: Option<String>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct ListEnsNamesResult {
+    wallet: Address,
+    /// The wallet's reverse record, when it resolves back to the wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    primary_name: Option<String>,
+    /// Soonest to expire first; subnames without an expiry last
+    #[serde(flatten)]
+    names: Page<EnsNameReport>,
+    /// Names in their grace period, which are lost unless renewed, across every page
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    in_grace_period: Vec<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ListEnsNamesTool<C> {
+    fn name(&self) -> &str {
+        "list_ens_names"
+    }
+
+    fn description(&self) -> &str {
+        "List the ENS names a wallet holds, .eth registrations and wrapped names, each with its status and expiry, soonest to expire first, along with the wallet's primary name. Names whose label cannot be recovered from registration events show as [labelhash].eth. Defaults to the server's wallet."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "wallet": {
+                "type": "string",
+                "description": "Address or contact name (default: the server's wallet)"
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ListEnsNamesParams =
+            serde_json::from_value(params).context("Invalid parameters for list_ens_names")?;
+        let wallet = match params.wallet.as_deref() {
+            Some(wallet) => {
+                resolve_address(self.book.as_deref(), wallet).context("Invalid wallet")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+
+        let now = self.client.get_latest_block().await?.timestamp;
+        let mut owned = self.ens.names_owned_by(wallet).await?;
+        owned.sort_by_key(|name| (name.expires.is_none(), name.expires, name.name.clone()));
+        let primary_name = self.ens.primary_name(wallet).await?;
+
+        let names: Vec<EnsNameReport> = owned
+            .into_iter()
+            .map(|name| EnsNameReport::new(name, now, self.book.as_deref()))
+            .collect();
+        let in_grace_period = names
+            .iter()
+            .filter(|name| name.status() == EnsStatus::GracePeriod)
+            .map(|name| name.name().to_string())
+            .collect();
+        let result = ListEnsNamesResult {
+            wallet,
+            primary_name,
+            names: params.page.paginate(names)?,
+            in_grace_period,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,112 @@
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::schedule_payment::DEFAULT_SCHEDULE_PREVIEW_RUNS;
+use super::schedules::{PaymentSchedules, ScheduleRun, ScheduleView};
+use super::{merge_schema_properties, Tool};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Bounds on how much one call can return
+const MAX_PREVIEW_RUNS: usize = 24;
+const DEFAULT_HISTORY: usize = 10;
+const MAX_HISTORY: usize = 100;
+
+pub struct ListScheduledPaymentsTool {
+    schedules: Arc<PaymentSchedules>,
+}
+
+impl ListScheduledPaymentsTool {
+    pub fn new(schedules: Arc<PaymentSchedules>) -> Self {
+        Self { schedules }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListScheduledPaymentsParams {
+    #[serde(default)]
+    schedule_id: Option<String>,
+    #[serde(default)]
+    preview_runs: Option<usize>,
+    #[serde(default)]
+    history: Option<usize>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct ListScheduledPaymentsResult {
+    #[serde(flatten)]
+    schedules: Page<ScheduleView>,
+    recent_runs: Vec<ScheduleRun>,
+}
+
+#[async_trait]
+impl Tool for ListScheduledPaymentsTool {
+    fn name(&self) -> &str {
+        "list_scheduled_payments"
+    }
+
+    fn description(&self) -> &str {
+        "List scheduled recurring payments with their status, limits, amount paid so far and next run times, plus the audit log of recent executions (sent or failed, with transaction hashes and errors)."
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "schedule_id": {
+                "type": "string",
+                "description": "Only this schedule and its executions"
+            },
+            "preview_runs": {
+                "type": "integer",
+                "minimum": 0,
+                "maximum": MAX_PREVIEW_RUNS,
+                "description": format!("Upcoming run times to show per schedule (default: {})", DEFAULT_SCHEDULE_PREVIEW_RUNS)
+            },
+            "history": {
+                "type": "integer",
+                "minimum": 0,
+                "maximum": MAX_HISTORY,
+                "description": format!("Most recent executions to include (default: {})", DEFAULT_HISTORY)
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ListScheduledPaymentsParams = serde_json::from_value(params)
+            .context("Invalid parameters for list_scheduled_payments")?;
+        let preview_runs = params
+            .preview_runs
+            .unwrap_or(DEFAULT_SCHEDULE_PREVIEW_RUNS)
+            .min(MAX_PREVIEW_RUNS);
+        let history = params.history.unwrap_or(DEFAULT_HISTORY).min(MAX_HISTORY);
+
+        let schedules = match &params.schedule_id {
+            Some(id) => vec![self.schedules.get(id)?],
+            None => self.schedules.list(),
+        };
+        // Only the returned page is previewed
+        let page = params.page.paginate(schedules)?;
+
+        let result = ListScheduledPaymentsResult {
+            schedules: Page {
+                items: page
+                    .items
+                    .iter()
+                    .map(|schedule| schedule.preview(preview_runs))
+                    .collect(),
+                total: page.total,
+                next_cursor: page.next_cursor,
+            },
+            recent_runs: self.schedules.runs(params.schedule_id.as_deref(), history),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,256 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::units::from_base_units;
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PaymentStream, StreamFlow, StreamSource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub struct ListStreamsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    sources: Vec<Arc<dyn StreamSource>>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> ListStreamsTool<C> {
+    pub fn new(client: Arc<C>, sources: Vec<Arc<dyn StreamSource>>) -> Self {
+        Self {
+            client,
+            sources,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the wallet
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListStreamsParams {
+    #[serde(default)]
+    wallet: Option<String>,
+    /// Both directions when omitted
+    #[serde(default)]
+    direction: Option<Direction>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum FlowReport {
+    Linear {
+        deposit: String,
+        withdrawn: String,
+        /// Streamed but not yet withdrawn
+        withdrawable: String,
+        /// Still to stream; what the sender gets back if it cancels now
+        remaining: String,
+        start: u64,
+        cliff: u64,
+        end: u64,
+        cancelable: bool,
+    },
+    Constant {
+        per_day: String,
+        since: u64,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct StreamReport {
+    protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<String>,
+    direction: Direction,
+    token: Address,
+    symbol: String,
+    sender: Address,
+    recipient: Address,
+    /// Streamed so far; for constant flows, since `since`
+    streamed: String,
+    #[serde(flatten)]
+    flow: FlowReport,
+}
+
+#[derive(Debug, Serialize)]
+struct SourceError {
+    protocol: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListStreamsResult {
+    wallet: Address,
+    /// Chain time the streams were evaluated at
+    timestamp: u64,
+    /// Incoming first
+    #[serde(flatten)]
+    streams: Page<StreamReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<SourceError>,
+}
+
+fn amount(value: U256, decimals: u8) -> Result<String> {
+    Ok(from_base_units(value, decimals as u32)?
+        .normalize()
+        .to_string())
+}
+
+fn flow_report(stream: &PaymentStream, now: u64, decimals: u8) -> Result<FlowReport> {
+    Ok(match stream.flow {
+        StreamFlow::Linear {
+            deposit,
+            withdrawn,
+            start,
+            cliff,
+            end,
+            cancelable,
+        } => {
+            let streamed = stream.streamed_at(now);
+            FlowReport::Linear {
+                deposit: amount(deposit, decimals)?,
+                withdrawn: amount(withdrawn, decimals)?,
+                withdrawable: amount(streamed.saturating_sub(withdrawn), decimals)?,
+                remaining: amount(deposit.saturating_sub(streamed), decimals)?,
+                start,
+                cliff,
+                end,
+                cancelable,
+            }
+        }
+        StreamFlow::Constant { rate, since } => FlowReport::Constant {
+            per_day: amount(rate * U256::from(SECS_PER_DAY), decimals)?,
+            since,
+        },
+    })
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ListStreamsTool<C> {
+    fn name(&self) -> &str {
+        "list_streams"
+    }
+
+    fn description(&self) -> &str {
+        "List the active payment streams a wallet receives or sends: Sablier V2 linear streams with their deposit, streamed, withdrawable and remaining amounts and vesting range, and Superfluid constant flows with their rate per day. Defaults to the server's wallet and both directions; cancel outgoing Sablier streams with cancel_stream."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "wallet": {
+                "type": "string",
+                "description": "Address or contact name whose streams to list (default: the server's wallet)"
+            },
+            "direction": {
+                "type": "string",
+                "enum": ["incoming", "outgoing"],
+                "description": "Only streams the wallet receives or only those it sends (default: both)"
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ListStreamsParams =
+            serde_json::from_value(params).context("Invalid parameters for list_streams")?;
+        let wallet = match params.wallet.as_deref() {
+            Some(wallet) => {
+                resolve_address(self.book.as_deref(), wallet).context("Invalid wallet")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+
+        let now = self.client.get_latest_block().await?.timestamp;
+        let mut streams = Vec::new();
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match source.streams_for(wallet).await {
+                Ok(found) => streams.extend(found),
+                Err(e) => errors.push(SourceError {
+                    protocol: source.name().to_string(),
+                    error: format!("{:#}", e),
+                }),
+            }
+        }
+
+        let mut metadata: HashMap<Address, (String, u8)> = HashMap::new();
+        for stream in &streams {
+            if metadata.contains_key(&stream.token) {
+                continue;
+            }
+            let symbol = self
+                .client
+                .get_token_symbol(stream.token)
+                .await
+                .unwrap_or_else(|_| "UNKNOWN".to_string());
+            let decimals = self
+                .client
+                .get_token_decimals(stream.token)
+                .await
+                .context("Failed to read token decimals")?;
+            metadata.insert(stream.token, (symbol, decimals));
+        }
+
+        let mut reports = Vec::with_capacity(streams.len());
+        for stream in &streams {
+            // A stream to oneself is listed in both directions
+            let directions = [
+                (stream.recipient == wallet).then_some(Direction::Incoming),
+                (stream.sender == wallet).then_some(Direction::Outgoing),
+            ];
+            for direction in directions.into_iter().flatten() {
+                if params.direction.is_some_and(|wanted| wanted != direction) {
+                    continue;
+                }
+                let (symbol, decimals) = &metadata[&stream.token];
+                reports.push(StreamReport {
+                    protocol: stream.protocol.clone(),
+                    stream_id: stream.stream_id.clone(),
+                    direction,
+                    token: stream.token,
+                    symbol: symbol.clone(),
+                    sender: stream.sender,
+                    recipient: stream.recipient,
+                    streamed: amount(stream.streamed_at(now), *decimals)?,
+                    flow: flow_report(stream, now, *decimals)?,
+                });
+            }
+        }
+        reports.sort_by_key(|report| report.direction == Direction::Outgoing);
+
+        let result = ListStreamsResult {
+            wallet,
+            timestamp: now,
+            streams: params.page.paginate(reports)?,
+            errors,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,211 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::units::from_base_units;
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, TransactionHistorySource};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_DAYS: u64 = 30;
+const MAX_DAYS: u64 = 365;
+/// Most withdrawals one call will total; a year of sweeps for a few hundred validators
+const MAX_WITHDRAWALS: usize = 20_000;
+
+pub struct ListWithdrawalsTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    history: Arc<dyn TransactionHistorySource>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> ListWithdrawalsTool<C> {
+    pub fn new(client: Arc<C>, history: Arc<dyn TransactionHistorySource>) -> Self {
+        Self {
+            client,
+            history,
+            book: None,
+        }
+    }
+
+    /// Accept contact names in place of the address
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListWithdrawalsParams {
+    #[serde(default)]
+    address: Option<String>,
+    #[serde(default)]
+    days: Option<u64>,
+    #[serde(default)]
+    validator_index: Option<u64>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct Withdrawal {
+    index: u64,
+    validator_index: u64,
+    amount: String,
+    block_number: u64,
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ValidatorTotal {
+    validator_index: u64,
+    withdrawals: usize,
+    amount: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListWithdrawalsResult {
+    address: Address,
+    days: u64,
+    /// Newest first; `total` counts every withdrawal in the period
+    #[serde(flatten)]
+    withdrawals: Page<Withdrawal>,
+    /// Over the whole period, not just this page
+    by_validator: Vec<ValidatorTotal>,
+    total_amount: String,
+    source: String,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ListWithdrawalsTool<C> {
+    fn name(&self) -> &str {
+        "list_withdrawals"
+    }
+
+    fn description(&self) -> &str {
+        "List beacon-chain withdrawals credited to the server's wallet (or another address) over the last days, newest first, with the ETH each validator has paid out and the total. Partial withdrawals sweep rewards above 32 ETH; a full withdrawal returns an exited validator's balance."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "address": {
+                "type": "string",
+                "description": "Withdrawal address or contact name (default: the server's wallet)"
+            },
+            "days": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": MAX_DAYS,
+                "description": "How far back to look (default: 30)"
+            },
+            "validator_index": {
+                "type": "integer",
+                "description": "Only withdrawals from this validator"
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ListWithdrawalsParams =
+            serde_json::from_value(params).context("Invalid parameters for list_withdrawals")?;
+        let address = match &params.address {
+            Some(address) => {
+                resolve_address(self.book.as_deref(), address).context("Invalid address")?
+            }
+            None => self.client.get_wallet_address(),
+        };
+        let days = params.days.unwrap_or(DEFAULT_DAYS);
+        if days == 0 || days > MAX_DAYS {
+            anyhow::bail!("days must be between 1 and {}", MAX_DAYS);
+        }
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_secs()
+            .saturating_sub(days * 86_400);
+
+        // One more than the cap tells whether the period holds too many to total
+        let found = self
+            .history
+            .withdrawals(address, since, MAX_WITHDRAWALS + 1)
+            .await?;
+        if found.len() > MAX_WITHDRAWALS {
+            anyhow::bail!(
+                "More than {} withdrawals in the last {} days; pass fewer days",
+                MAX_WITHDRAWALS,
+                days
+            );
+        }
+        let found: Vec<_> = found
+            .into_iter()
+            .filter(|withdrawal| {
+                params
+                    .validator_index
+                    .is_none_or(|index| withdrawal.validator_index == index)
+            })
+            .collect();
+
+        let mut totals: BTreeMap<u64, (usize, u64)> = BTreeMap::new();
+        for withdrawal in &found {
+            let total = totals.entry(withdrawal.validator_index).or_default();
+            total.0 += 1;
+            total.1 += withdrawal.amount_gwei;
+        }
+        let gwei = |amount: u64| -> Result<String> {
+            Ok(from_base_units(U256::from(amount), 9)?
+                .normalize()
+                .to_string())
+        };
+
+        let total_amount = gwei(found.iter().map(|withdrawal| withdrawal.amount_gwei).sum())?;
+        let page = params.page.paginate(found)?;
+
+        let result = ListWithdrawalsResult {
+            address,
+            days,
+            total_amount,
+            by_validator: totals
+                .into_iter()
+                .map(|(validator_index, (withdrawals, amount))| {
+                    Ok(ValidatorTotal {
+                        validator_index,
+                        withdrawals,
+                        amount: gwei(amount)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            withdrawals: Page {
+                items: page
+                    .items
+                    .into_iter()
+                    .map(|withdrawal| {
+                        Ok(Withdrawal {
+                            index: withdrawal.index,
+                            validator_index: withdrawal.validator_index,
+                            amount: gwei(withdrawal.amount_gwei)?,
+                            block_number: withdrawal.block_number,
+                            timestamp: withdrawal.timestamp,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                total: page.total,
+                next_cursor: page.next_cursor,
+            },
+            source: self.history.name().to_string(),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -1,18 +1,282 @@
+pub mod add_contact;
+pub mod address_book;
+pub mod alerts;
+pub mod analyze_gas_usage;
+pub mod approval_strategy;
+pub mod batch_transfer;
+pub mod build_transaction;
+pub mod call_contract;
+pub mod cancel_stream;
+pub mod cast_vote;
+pub mod check_payment;
+pub mod check_token_locks;
+pub mod compare_quotes;
+pub mod compare_lsts;
+pub mod compare_yields;
+pub mod connect_wallet;
+pub mod create_payment_request;
+pub mod create_stream;
+pub mod delegate_votes;
+pub mod detect_interfaces;
+pub mod dex_stats;
+pub mod diagnose_wallet;
+pub mod event_bus;
+pub mod execute_plan;
+pub mod execute_swap;
+pub mod execution_reports;
+pub mod explain;
+pub mod find_pools;
+pub mod freshness;
+pub mod gas_reserve;
+pub mod generate_tax_report;
 mod get_balance;
+pub mod get_balance_all_chains;
+pub mod get_delegation;
+pub mod get_dex_stats;
+pub mod get_ens_name;
+pub mod get_execution_report;
+pub mod get_funding_rates;
+pub mod get_governance_proposals;
+pub mod get_market_stats;
+pub mod get_nft_floor_price;
+pub mod get_option_chains;
+pub mod get_perp_markets;
+pub mod get_perp_positions;
+pub mod get_portfolio;
+pub mod get_restaking_positions;
+pub mod get_pool_liquidity_profile;
 mod get_token_price;
+pub mod get_twap_price;
+pub mod get_validator_info;
+pub mod get_voting_power;
+pub mod health_check;
+pub mod import_signed_transaction;
+pub mod interfaces;
+pub mod inspect_bytecode;
+pub mod list_contacts;
+pub mod list_ens_names;
+pub mod list_scheduled_payments;
+pub mod list_streams;
+pub mod list_withdrawals;
+pub mod monitor_new_pairs;
+pub mod monitor_token_risks;
+pub mod multichain;
+pub mod pagination;
+pub mod pause_scheduled_payment;
+pub mod pool_share;
+pub mod preview_transfer;
+pub mod price_sanity;
+pub mod payment_requests;
+pub mod quotes;
+pub mod read_storage;
+pub mod recipients;
+pub mod recommend_slippage;
+pub mod route_objective;
+pub mod renew_ens_name;
+pub mod resume_scheduled_payment;
+pub mod scam_list;
+pub mod scan_arbitrage;
+pub mod schedule_payment;
+pub mod scheduler;
+pub mod schedules;
+pub mod sessions;
+pub mod simulate_bundle;
+pub mod slippage;
+pub mod split;
+pub mod suggest_tx_timing;
 mod swap_tokens;
+pub mod trade_ledger;
+pub mod transfer_nft;
+pub mod units;
+pub mod watch_address;
+pub mod watch_list;
 
 #[cfg(test)]
 mod tests;
 
+pub use add_contact::AddContactTool;
+pub use address_book::{AddressBook, Contact};
+pub use alerts::{AlertSeverity, AlertSink};
+pub use analyze_gas_usage::AnalyzeGasUsageTool;
+pub use batch_transfer::{BatchLimits, BatchTransferTool};
+pub use build_transaction::BuildTransactionTool;
+pub use call_contract::CallContractTool;
+pub use cancel_stream::CancelStreamTool;
+pub use cast_vote::CastVoteTool;
+pub use check_payment::CheckPaymentTool;
+pub use check_token_locks::CheckTokenLocksTool;
+pub use compare_quotes::CompareQuotesTool;
+pub use compare_lsts::CompareLstsTool;
+pub use compare_yields::CompareYieldsTool;
+pub use connect_wallet::ConnectWalletTool;
+pub use create_payment_request::CreatePaymentRequestTool;
+pub use create_stream::CreateStreamTool;
+pub use delegate_votes::DelegateVotesTool;
+pub use detect_interfaces::DetectInterfacesTool;
+pub use dex_stats::{DexStats, PoolStats};
+pub use diagnose_wallet::DiagnoseWalletTool;
+pub use event_bus::{Event, EventBus, EventLog};
+pub use execute_plan::ExecutePlanTool;
+pub use execute_swap::ExecuteSwapTool;
+pub use execution_reports::{ExecutionReport, ExecutionReports};
+pub use explain::Explanation;
+pub use find_pools::FindPoolsTool;
+pub use gas_reserve::GasReserve;
+pub use generate_tax_report::GenerateTaxReportTool;
 pub use get_balance::GetBalanceTool;
+pub use get_balance_all_chains::GetBalanceAllChainsTool;
+pub use get_delegation::GetDelegationTool;
+pub use get_dex_stats::GetDexStatsTool;
+pub use get_ens_name::GetEnsNameTool;
+pub use get_execution_report::GetExecutionReportTool;
+pub use get_funding_rates::GetFundingRatesTool;
+pub use get_governance_proposals::GetGovernanceProposalsTool;
+pub use get_market_stats::GetMarketStatsTool;
+pub use get_nft_floor_price::GetNftFloorPriceTool;
+pub use get_option_chains::GetOptionChainsTool;
+pub use get_perp_markets::GetPerpMarketsTool;
+pub use get_perp_positions::GetPerpPositionsTool;
+pub use get_portfolio::GetPortfolioTool;
+pub use get_restaking_positions::GetRestakingPositionsTool;
+pub use get_pool_liquidity_profile::GetPoolLiquidityProfileTool;
 pub use get_token_price::GetTokenPriceTool;
+pub use get_twap_price::GetTwapPriceTool;
+pub use get_validator_info::GetValidatorInfoTool;
+pub use get_voting_power::GetVotingPowerTool;
+pub use health_check::HealthCheckTool;
+pub use import_signed_transaction::ImportSignedTransactionTool;
+pub use inspect_bytecode::InspectBytecodeTool;
+pub use list_contacts::ListContactsTool;
+pub use list_ens_names::ListEnsNamesTool;
+pub use list_scheduled_payments::ListScheduledPaymentsTool;
+pub use list_streams::ListStreamsTool;
+pub use list_withdrawals::ListWithdrawalsTool;
+pub use monitor_new_pairs::MonitorNewPairsTool;
+pub use monitor_token_risks::MonitorTokenRisksTool;
+pub use pagination::{Page, PageParams};
+pub use pause_scheduled_payment::PauseScheduledPaymentTool;
+pub use pool_share::{OverLimitAction, PoolShareLimit};
+pub use preview_transfer::PreviewTransferTool;
+pub use price_sanity::PriceCheck;
+pub use payment_requests::{PaymentRequest, PaymentRequests};
+pub use quotes::QuoteStore;
+pub use read_storage::ReadStorageTool;
+pub use recipients::RecipientHistory;
+pub use recommend_slippage::RecommendSlippageTool;
+pub use renew_ens_name::RenewEnsNameTool;
+pub use resume_scheduled_payment::ResumeScheduledPaymentTool;
+pub use route_objective::RouteObjective;
+pub use scam_list::{ScamFeed, ScamList, ScamWarning};
+pub use scan_arbitrage::ScanArbitrageTool;
+pub use schedule_payment::SchedulePaymentTool;
+pub use scheduler::PaymentScheduler;
+pub use schedules::{PaymentSchedules, Recurrence, ScheduledPayment};
+pub use sessions::{SessionMap, SessionState};
+pub use simulate_bundle::SimulateBundleTool;
+pub use slippage::{SlippageDefaults, SlippageSource};
+pub use suggest_tx_timing::SuggestTxTimingTool;
 pub use swap_tokens::SwapTokensTool;
+pub use trade_ledger::{AccountingMethod, Trade, TradeLedger};
+pub use transfer_nft::TransferNftTool;
+pub use watch_address::WatchAddressTool;
+pub use watch_list::{WatchEvent, WatchList, WatchedAddress};
 
 use anyhow::Result;
 use async_trait::async_trait;
+use ethers::types::Address;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Coarse cost class of a tool, used to budget concurrent RPC usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCategory {
+    /// Single-call reads such as balances
+    Read,
+    /// Router quotes and price lookups
+    Pricing,
+    /// Transaction simulation and gas estimation
+    Simulation,
+    /// Log scans, candles and other multi-request workloads
+    Heavy,
+}
+
+impl ToolCategory {
+    pub const ALL: [ToolCategory; 4] = [
+        ToolCategory::Read,
+        ToolCategory::Pricing,
+        ToolCategory::Simulation,
+        ToolCategory::Heavy,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolCategory::Read => "read",
+            ToolCategory::Pricing => "pricing",
+            ToolCategory::Simulation => "simulation",
+            ToolCategory::Heavy => "heavy",
+        }
+    }
+}
+
+/// Access level of an MCP client; each tier includes the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionTier {
+    /// Balances, prices, quotes and simulations
+    ReadOnly,
+    /// Adds live execution, bounded by the server's trade limits
+    Trade,
+    /// Adds server administration such as metrics
+    Admin,
+}
+
+impl PermissionTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PermissionTier::ReadOnly => "read_only",
+            PermissionTier::Trade => "trade",
+            PermissionTier::Admin => "admin",
+        }
+    }
+}
+
+impl std::str::FromStr for PermissionTier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read_only" | "readonly" | "read" => Ok(PermissionTier::ReadOnly),
+            "trade" | "trade_with_limits" => Ok(PermissionTier::Trade),
+            "admin" => Ok(PermissionTier::Admin),
+            other => anyhow::bail!("Unknown permission tier: {}", other),
+        }
+    }
+}
+
+/// What a call puts at stake, for policy rules on notional value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Notional {
+    /// ERC20 moved or sold; ETH when `None`
+    pub token: Option<Address>,
+    /// Whole tokens
+    pub amount: Decimal,
+}
+
+impl Notional {
+    /// `amount` of `token` read from a call's whole-token parameters; `None` when
+    /// either is missing or malformed, or the token is given but not an address
+    pub fn from_params(params: &Value, token_key: &str, amount_key: &str) -> Option<Self> {
+        let token = match params.get(token_key).and_then(Value::as_str) {
+            Some(token) => Some(token.trim().parse().ok()?),
+            None => None,
+        };
+        let amount = params.get(amount_key)?.as_str()?.trim().parse().ok()?;
+        Some(Self { token, amount })
+    }
+}
+
 // Keep the old Tool trait for backward compatibility with tests
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -20,4 +284,44 @@ pub trait Tool: Send + Sync {
     fn description(&self) -> &str;
     fn input_schema(&self) -> Value;
     async fn execute(&self, params: Value) -> Result<Value>;
+
+    /// Concurrency budget this tool draws from
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    /// Lowest client tier allowed to see and call this tool
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::ReadOnly
+    }
+
+    /// Question the user must explicitly confirm before `params` run, if any
+    fn confirmation_prompt(&self, _params: &Value) -> Option<String> {
+        None
+    }
+
+    /// Whether `params` sign or send a transaction, and so need the second factor when
+    /// one is configured. By default, live runs of write tools do.
+    fn moves_funds(&self, params: &Value) -> bool {
+        self.required_tier() >= PermissionTier::Trade
+            && params.get("dry_run").and_then(Value::as_bool) == Some(false)
+    }
+
+    /// Amount `params` move or trade, when the tool can tell before running
+    fn notional(&self, _params: &Value) -> Option<Notional> {
+        None
+    }
+
+    /// How `result` was derived from `params`, for calls made with `explain: true`
+    fn explain(&self, params: &Value, _result: &Value) -> Explanation {
+        Explanation::generic(self.name(), params)
+    }
+}
+
+/// Add shared parameters such as `freshness_schema_properties()` to a tool's schema
+/// `properties`; when either is not a JSON object, `properties` is left as it was
+pub fn merge_schema_properties(properties: &mut Value, extra: Value) {
+    if let (Some(properties), Value::Object(extra)) = (properties.as_object_mut(), extra) {
+        properties.extend(extra);
+    }
 }
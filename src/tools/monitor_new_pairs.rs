@@ -0,0 +1,288 @@
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PoolDiscoveryTrait, PoolInfo, PoolLiquidity};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+/// Base tokens watched when none are configured: WETH, USDC, USDT and DAI on mainnet
+pub const DEFAULT_BASE_TOKENS: [&str; 4] = [
+    "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+    "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+    "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+    "0x6B175474E89094C44Da98b954EedeAC495271d0F",
+];
+
+// About an hour of blocks, scanned by the first call
+pub const DEFAULT_LOOKBACK_BLOCKS: u64 = 300;
+
+// About a day of blocks, bounding each log scan
+pub const MAX_SCAN_BLOCKS: u64 = 7200;
+
+pub struct MonitorNewPairsTool<C: EthereumClientTrait, P: PoolDiscoveryTrait> {
+    client: Arc<C>,
+    discovery: Arc<P>,
+    base_tokens: Vec<Address>,
+    /// First block the next call scans when it does not pass from_block
+    cursor: Mutex<Option<u64>>,
+}
+
+impl<C: EthereumClientTrait, P: PoolDiscoveryTrait> MonitorNewPairsTool<C, P> {
+    /// With no `base_tokens`, `DEFAULT_BASE_TOKENS` are watched
+    pub fn new(client: Arc<C>, discovery: Arc<P>, base_tokens: Vec<Address>) -> Result<Self> {
+        let base_tokens = if base_tokens.is_empty() {
+            DEFAULT_BASE_TOKENS
+                .iter()
+                .map(|token| {
+                    token
+                        .parse()
+                        .with_context(|| format!("Invalid base token address {}", token))
+                })
+                .collect::<Result<_>>()?
+        } else {
+            base_tokens
+        };
+        Ok(Self {
+            client,
+            discovery,
+            base_tokens,
+            cursor: Mutex::new(None),
+        })
+    }
+
+    /// Screen the non-base token of a new pool, returning its report and flags
+    async fn screen(
+        &self,
+        pool: &PoolInfo,
+        base_token: Address,
+        min_base_liquidity: Option<Decimal>,
+    ) -> NewPool {
+        let new_token = if pool.token0 == base_token {
+            pool.token1
+        } else {
+            pool.token0
+        };
+        let mut report = NewPool {
+            dex: pool.dex.clone(),
+            pool_address: format!("{:?}", pool.pool_address),
+            fee_tier: pool.fee,
+            creation_block: pool.creation_block,
+            base_token: format!("{:?}", base_token),
+            new_token: format!("{:?}", new_token),
+            new_token_symbol: None,
+            new_token_decimals: None,
+            base_liquidity: None,
+            liquidity: None,
+            risk: Risk::Ok,
+            flags: Vec::new(),
+        };
+
+        match self.client.has_code(new_token).await {
+            Ok(true) => {}
+            Ok(false) => report.flag(Risk::Danger, "no_contract_code"),
+            Err(_) => report.flag(Risk::Caution, "code_unavailable"),
+        }
+        match (
+            self.client.get_token_symbol(new_token).await,
+            self.client.get_token_decimals(new_token).await,
+        ) {
+            (Ok(symbol), Ok(decimals)) => {
+                report.new_token_symbol = Some(symbol);
+                report.new_token_decimals = Some(decimals);
+            }
+            _ => report.flag(Risk::Danger, "metadata_unreadable"),
+        }
+
+        match self.discovery.get_liquidity(pool).await {
+            Ok(PoolLiquidity::Reserves { reserve0, reserve1 }) => {
+                let base_reserve = if pool.token0 == base_token {
+                    reserve0
+                } else {
+                    reserve1
+                };
+                if base_reserve.is_zero() {
+                    report.flag(Risk::Caution, "no_liquidity");
+                }
+                let base_decimals = self.client.get_token_decimals(base_token).await;
+                if let Ok(amount) = base_decimals
+                    .and_then(|decimals| from_base_units(base_reserve, decimals as u32))
+                {
+                    if !base_reserve.is_zero() && min_base_liquidity.is_some_and(|min| amount < min)
+                    {
+                        report.flag(Risk::Caution, "low_liquidity");
+                    }
+                    report.base_liquidity = Some(amount.normalize().to_string());
+                }
+            }
+            Ok(PoolLiquidity::InRange { liquidity }) => {
+                if liquidity == 0 {
+                    report.flag(Risk::Caution, "no_liquidity");
+                }
+                report.liquidity = Some(liquidity.to_string());
+            }
+            Err(_) => report.flag(Risk::Caution, "liquidity_unavailable"),
+        }
+
+        report
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MonitorNewPairsParams {
+    #[serde(default)]
+    from_block: Option<u64>,
+    #[serde(default)]
+    base_tokens: Option<Vec<String>>,
+    #[serde(default)]
+    min_base_liquidity: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Risk {
+    Ok,
+    Caution,
+    Danger,
+}
+
+#[derive(Debug, Serialize)]
+struct NewPool {
+    dex: String,
+    pool_address: String,
+    fee_tier: u32,
+    creation_block: u64,
+    base_token: String,
+    new_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_token_symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_token_decimals: Option<u8>,
+    /// V2 reserve of the base token, in human units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_liquidity: Option<String>,
+    /// V3 in-range liquidity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liquidity: Option<String>,
+    risk: Risk,
+    flags: Vec<String>,
+}
+
+impl NewPool {
+    fn flag(&mut self, risk: Risk, flag: &str) {
+        self.risk = self.risk.max(risk);
+        self.flags.push(flag.to_string());
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MonitorNewPairsResult {
+    from_block: u64,
+    to_block: u64,
+    /// Where the next call continues when from_block is omitted
+    next_from_block: u64,
+    base_tokens: Vec<String>,
+    new_pools: Vec<NewPool>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, P: PoolDiscoveryTrait + 'static> Tool
+    for MonitorNewPairsTool<C, P>
+{
+    fn name(&self) -> &str {
+        "monitor_new_pairs"
+    }
+
+    fn description(&self) -> &str {
+        "Report Uniswap V2, SushiSwap and Uniswap V3 pools created since the last call that pair a new token with a watched base token (WETH, USDC, USDT, DAI by default). Each new token is screened automatically (contract code, readable ERC20 metadata, pool liquidity) and given a risk of ok, caution or danger. Call repeatedly to poll; each call continues where the previous one stopped."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_block": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "First block to scan (default: where the previous call stopped, or about an hour ago on the first call). At most about a day of blocks is scanned"
+                },
+                "base_tokens": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Base token addresses to watch instead of the server's configured ones"
+                },
+                "min_base_liquidity": {
+                    "type": "string",
+                    "description": "Flag V2 pools holding less than this much of the base token, in human units"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: MonitorNewPairsParams =
+            serde_json::from_value(params).context("Invalid parameters for monitor_new_pairs")?;
+
+        let base_tokens: Vec<Address> = match &params.base_tokens {
+            Some(tokens) if !tokens.is_empty() => tokens
+                .iter()
+                .map(|token| token.parse().context("Invalid base token address"))
+                .collect::<Result<_>>()?,
+            _ => self.base_tokens.clone(),
+        };
+
+        let head = self.client.get_block_number().await?;
+        let cursor = *self.cursor.lock().unwrap();
+        let from_block = params
+            .from_block
+            .or(cursor)
+            .unwrap_or_else(|| head.saturating_sub(DEFAULT_LOOKBACK_BLOCKS))
+            .max(head.saturating_sub(MAX_SCAN_BLOCKS));
+
+        let mut new_pools: Vec<NewPool> = Vec::new();
+        for base_token in &base_tokens {
+            let pools = self
+                .discovery
+                .find_pools(*base_token, None, Some(from_block))
+                .await?;
+            for pool in pools {
+                // Blocks past head are left for the next call
+                if pool.creation_block > head
+                    || new_pools
+                        .iter()
+                        .any(|seen| seen.pool_address == format!("{:?}", pool.pool_address))
+                {
+                    continue;
+                }
+                new_pools.push(
+                    self.screen(&pool, *base_token, params.min_base_liquidity)
+                        .await,
+                );
+            }
+        }
+        new_pools.sort_by_key(|pool| pool.creation_block);
+
+        let next_from_block = head + 1;
+        *self.cursor.lock().unwrap() = Some(next_from_block);
+
+        let result = MonitorNewPairsResult {
+            from_block,
+            to_block: head,
+            next_from_block,
+            base_tokens: base_tokens
+                .iter()
+                .map(|token| format!("{:?}", token))
+                .collect(),
+            new_pools,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,348 @@
+use super::alerts::{AlertSeverity, AlertSink};
+use super::monitor_new_pairs::{DEFAULT_LOOKBACK_BLOCKS, MAX_SCAN_BLOCKS};
+use super::{Tool, ToolCategory};
+use crate::ethereum::{
+    AdminAction, EthereumClientTrait, FactoryKind, PoolDiscoveryTrait, PoolInfo, PoolLiquidity,
+    TokenEventSource,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_MIN_REMOVAL_PERCENT: u32 = 20;
+const DEFAULT_MIN_MINT_PERCENT: u32 = 1;
+
+// Removing at least this share of a pair's liquidity at once is treated as a likely rug
+const CRITICAL_REMOVAL_PERCENT: u32 = 50;
+
+/// Topic of notifications pushed for each alert
+pub const TOKEN_RISK_TOPIC: &str = "token_risk";
+
+pub struct MonitorTokenRisksTool<C: EthereumClientTrait, P: PoolDiscoveryTrait> {
+    client: Arc<C>,
+    discovery: Arc<P>,
+    events: Arc<dyn TokenEventSource>,
+    alerts: Option<Arc<dyn AlertSink>>,
+    /// First block the next call scans when it does not pass from_block
+    cursor: Mutex<Option<u64>>,
+    /// V2 pairs per token, and the block their discovery is complete through
+    pairs: Mutex<HashMap<Address, (u64, Vec<PoolInfo>)>>,
+}
+
+impl<C: EthereumClientTrait, P: PoolDiscoveryTrait> MonitorTokenRisksTool<C, P> {
+    pub fn new(client: Arc<C>, discovery: Arc<P>, events: Arc<dyn TokenEventSource>) -> Self {
+        Self {
+            client,
+            discovery,
+            events,
+            alerts: None,
+            cursor: Mutex::new(None),
+            pairs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Push every alert to `alerts` as well as returning it
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// V2 pairs of `token`, scanning only blocks not seen by earlier calls
+    async fn v2_pairs(&self, token: Address, head: u64) -> Result<Vec<PoolInfo>> {
+        let cached = self.pairs.lock().unwrap().get(&token).cloned();
+        let (from_block, mut pairs) = match cached {
+            Some((scanned_to, pairs)) => (Some(scanned_to + 1), pairs),
+            None => (None, Vec::new()),
+        };
+        let found = self.discovery.find_pools(token, None, from_block).await?;
+        pairs.extend(
+            found
+                .into_iter()
+                .filter(|pool| pool.kind == FactoryKind::V2 && pool.creation_block <= head)
+                .filter(|pool| !pairs.iter().any(|p| p.pool_address == pool.pool_address))
+                .collect::<Vec<_>>(),
+        );
+        self.pairs
+            .lock()
+            .unwrap()
+            .insert(token, (head, pairs.clone()));
+        Ok(pairs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MonitorTokenRisksParams {
+    tokens: Vec<String>,
+    #[serde(default)]
+    from_block: Option<u64>,
+    #[serde(default)]
+    min_removal_percent: Option<u32>,
+    #[serde(default)]
+    min_mint_percent: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct RiskAlert {
+    token: String,
+    kind: String,
+    severity: AlertSeverity,
+    block_number: u64,
+    tx_hash: String,
+    message: String,
+    /// Share of pair liquidity removed or of supply minted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MonitorTokenRisksResult {
+    from_block: u64,
+    to_block: u64,
+    /// Where the next call continues when from_block is omitted
+    next_from_block: u64,
+    alerts: Vec<RiskAlert>,
+    /// Tokens skipped because the wallet holds none
+    not_held: Vec<String>,
+    /// Notifications delivered to connected clients
+    notifications_sent: usize,
+}
+
+/// `part` as a percentage of `whole`, to two decimals
+pub(super) fn percent_of(part: U256, whole: U256) -> Decimal {
+    if whole.is_zero() {
+        return Decimal::ZERO;
+    }
+    let scaled = part.saturating_mul(U256::from(10_000u64)) / whole;
+    Decimal::new(scaled.min(U256::from(u64::MAX)).as_u64() as i64, 2)
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, P: PoolDiscoveryTrait + 'static> Tool
+    for MonitorTokenRisksTool<C, P>
+{
+    fn name(&self) -> &str {
+        "monitor_token_risks"
+    }
+
+    fn description(&self) -> &str {
+        "Watch tokens the wallet holds for rug-pull signals since the last call: large liquidity removals from their Uniswap V2/SushiSwap pairs, new supply minted, ownership transfers or renouncement, and blacklist updates. Alerts are returned and also pushed to connected clients as MCP logging notifications. Call repeatedly to poll; each call continues where the previous one stopped."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tokens": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Token addresses to watch; tokens the wallet holds none of are skipped"
+                },
+                "from_block": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "First block to scan (default: where the previous call stopped, or about an hour ago on the first call). At most about a day of blocks is scanned"
+                },
+                "min_removal_percent": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 100,
+                    "description": "Alert when one withdrawal removes at least this share of a pair's token liquidity (default: 20)"
+                },
+                "min_mint_percent": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Alert when one mint adds at least this share of the total supply (default: 1)"
+                }
+            },
+            "required": ["tokens"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: MonitorTokenRisksParams =
+            serde_json::from_value(params).context("Invalid parameters for monitor_token_risks")?;
+        let tokens: Vec<Address> = params
+            .tokens
+            .iter()
+            .map(|token| token.parse().context("Invalid token address"))
+            .collect::<Result<_>>()?;
+        let min_removal = Decimal::from(
+            params
+                .min_removal_percent
+                .unwrap_or(DEFAULT_MIN_REMOVAL_PERCENT),
+        );
+        let min_mint = Decimal::from(params.min_mint_percent.unwrap_or(DEFAULT_MIN_MINT_PERCENT));
+
+        let head = self.client.get_block_number().await?;
+        let cursor = *self.cursor.lock().unwrap();
+        let from_block = params
+            .from_block
+            .or(cursor)
+            .unwrap_or_else(|| head.saturating_sub(DEFAULT_LOOKBACK_BLOCKS))
+            .max(head.saturating_sub(MAX_SCAN_BLOCKS));
+        let wallet = self.client.get_wallet_address();
+
+        let mut alerts = Vec::new();
+        let mut not_held = Vec::new();
+        for token in tokens {
+            let (balance, _) = self.client.get_token_balance(token, wallet).await?;
+            if balance.is_zero() {
+                not_held.push(format!("{:?}", token));
+                continue;
+            }
+            let symbol = self
+                .client
+                .get_token_symbol(token)
+                .await
+                .unwrap_or_else(|_| format!("{:?}", token));
+
+            for pair in self.v2_pairs(token, head).await? {
+                let removals = self
+                    .events
+                    .liquidity_removals(pair.pool_address, from_block, head)
+                    .await?;
+                if removals.is_empty() {
+                    continue;
+                }
+                let PoolLiquidity::Reserves { reserve0, reserve1 } =
+                    self.discovery.get_liquidity(&pair).await?
+                else {
+                    continue;
+                };
+                let token_is_0 = pair.token0 == token;
+                let remaining = if token_is_0 { reserve0 } else { reserve1 };
+                for removal in removals {
+                    let removed = if token_is_0 {
+                        removal.amount0
+                    } else {
+                        removal.amount1
+                    };
+                    // Reserves are read after the withdrawal, so add it back
+                    let percent = percent_of(removed, remaining.saturating_add(removed));
+                    if percent < min_removal {
+                        continue;
+                    }
+                    let severity = if percent >= Decimal::from(CRITICAL_REMOVAL_PERCENT) {
+                        AlertSeverity::Critical
+                    } else {
+                        AlertSeverity::Warning
+                    };
+                    alerts.push(RiskAlert {
+                        token: format!("{:?}", token),
+                        kind: "liquidity_removed".to_string(),
+                        severity,
+                        block_number: removal.block_number,
+                        tx_hash: format!("{:?}", removal.tx_hash),
+                        message: format!(
+                            "{}% of the {} liquidity in {} pair {:?} was withdrawn",
+                            percent.normalize(),
+                            symbol,
+                            pair.dex,
+                            pair.pool_address
+                        ),
+                        percent: Some(percent.normalize().to_string()),
+                    });
+                }
+            }
+
+            for event in self.events.admin_events(token, from_block, head).await? {
+                let (kind, severity, message, percent) = match event.action {
+                    AdminAction::Mint { to, amount } => {
+                        let supply = self.events.total_supply(token).await?;
+                        let percent = percent_of(amount, supply);
+                        if percent < min_mint {
+                            continue;
+                        }
+                        (
+                            "mint",
+                            AlertSeverity::Warning,
+                            format!(
+                                "{}% of the {} supply was minted to {:?}",
+                                percent.normalize(),
+                                symbol,
+                                to
+                            ),
+                            Some(percent.normalize().to_string()),
+                        )
+                    }
+                    AdminAction::OwnershipTransferred { from, to } => (
+                        "ownership_transferred",
+                        AlertSeverity::Warning,
+                        format!("{} ownership moved from {:?} to {:?}", symbol, from, to),
+                        None,
+                    ),
+                    AdminAction::OwnershipRenounced { previous_owner } => (
+                        "ownership_renounced",
+                        AlertSeverity::Info,
+                        format!("{} ownership was renounced by {:?}", symbol, previous_owner),
+                        None,
+                    ),
+                    AdminAction::BlacklistUpdated {
+                        account,
+                        blacklisted,
+                    } => {
+                        // Being blacklisted freezes the wallet's balance
+                        let severity = if account == wallet && blacklisted {
+                            AlertSeverity::Critical
+                        } else {
+                            AlertSeverity::Info
+                        };
+                        let verb = if blacklisted {
+                            "added to"
+                        } else {
+                            "removed from"
+                        };
+                        (
+                            "blacklist_updated",
+                            severity,
+                            format!("{:?} was {} the {} blacklist", account, verb, symbol),
+                            None,
+                        )
+                    }
+                };
+                alerts.push(RiskAlert {
+                    token: format!("{:?}", token),
+                    kind: kind.to_string(),
+                    severity,
+                    block_number: event.block_number,
+                    tx_hash: format!("{:?}", event.tx_hash),
+                    message,
+                    percent,
+                });
+            }
+        }
+        alerts.sort_by_key(|alert| alert.block_number);
+
+        let mut notifications_sent = 0;
+        if let Some(sink) = &self.alerts {
+            for alert in &alerts {
+                notifications_sent += sink
+                    .send(TOKEN_RISK_TOPIC, alert.severity, &json!(alert))
+                    .await;
+            }
+        }
+
+        let next_from_block = head + 1;
+        *self.cursor.lock().unwrap() = Some(next_from_block);
+
+        let result = MonitorTokenRisksResult {
+            from_block,
+            to_block: head,
+            next_from_block,
+            alerts,
+            not_held,
+            notifications_sent,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
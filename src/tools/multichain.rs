@@ -0,0 +1,204 @@
+use super::freshness::Finality;
+use crate::ethereum::chains::{native_symbol, ChainClients};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Chains queried at once by default
+pub const DEFAULT_CHAIN_QUERY_CONCURRENCY: usize = 4;
+
+// A chain slower than this is reported as failed rather than holding up the rest
+const CHAIN_QUERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Schema of the `chain_ids` and `tokens` parameters shared by the cross-chain tools
+pub fn chain_selection_schema_properties(chains: &ChainClients) -> Value {
+    json!({
+        "chain_ids": {
+            "type": "array",
+            "items": { "type": "integer" },
+            "description": format!(
+                "Limit the query to these chains (default: all configured: {:?})",
+                chains.chain_ids()
+            )
+        },
+        "tokens": {
+            "type": "object",
+            "additionalProperties": {
+                "type": "array",
+                "items": { "type": "string" }
+            },
+            "description": "ERC20 token addresses to include, keyed by chain id, e.g. {\"1\": [\"0xA0b8...\"], \"42161\": [\"0xaf88...\"]}"
+        }
+    })
+}
+
+/// Chains a cross-chain call covers and the tokens to read on each
+#[derive(Debug, Clone, Default)]
+pub struct ChainSelection {
+    pub chain_ids: Vec<u64>,
+    tokens: BTreeMap<u64, Vec<Address>>,
+}
+
+impl ChainSelection {
+    /// Validate a call's `chain_ids` and `tokens` against the configured chains;
+    /// without `chain_ids`, every configured chain is selected
+    pub fn parse(
+        chains: &ChainClients,
+        chain_ids: Option<Vec<u64>>,
+        tokens: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Self> {
+        let mut chain_ids = chain_ids.unwrap_or_else(|| chains.chain_ids());
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
+
+        let mut parsed_tokens = BTreeMap::new();
+        for (chain_id, addresses) in tokens {
+            let chain_id: u64 = chain_id
+                .parse()
+                .with_context(|| format!("Invalid chain id in tokens: {}", chain_id))?;
+            let parsed = addresses
+                .iter()
+                .map(|token| {
+                    token
+                        .parse()
+                        .with_context(|| format!("Invalid token address: {}", token))
+                })
+                .collect::<Result<Vec<Address>>>()?;
+            parsed_tokens.insert(chain_id, parsed);
+        }
+
+        for chain_id in chain_ids.iter().chain(parsed_tokens.keys()) {
+            if chains.get(*chain_id).is_none() {
+                anyhow::bail!(
+                    "Chain {} is not configured (available: {:?})",
+                    chain_id,
+                    chains.chain_ids()
+                );
+            }
+        }
+        Ok(Self {
+            chain_ids,
+            tokens: parsed_tokens,
+        })
+    }
+
+    pub fn tokens_for(&self, chain_id: u64) -> Vec<Address> {
+        self.tokens.get(&chain_id).cloned().unwrap_or_default()
+    }
+}
+
+/// Run `query` against each selected chain, at most `max_parallel` at once. Each
+/// chain's failure or timeout is returned in its own slot; results are in chain id order.
+pub async fn for_each_chain<T, F, Fut>(
+    chains: &ChainClients,
+    chain_ids: &[u64],
+    max_parallel: usize,
+    query: F,
+) -> Result<Vec<(u64, Result<T>)>>
+where
+    T: Send + 'static,
+    F: Fn(u64, Arc<dyn EthereumClientTrait>) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(max_parallel.max(1)));
+    let mut queries = JoinSet::new();
+    for &chain_id in chain_ids {
+        let client = Arc::clone(
+            chains
+                .get(chain_id)
+                .with_context(|| format!("Chain {} is not configured", chain_id))?,
+        );
+        let permits = Arc::clone(&permits);
+        let task = query(chain_id, client);
+        queries.spawn(async move {
+            let outcome = match permits.acquire_owned().await {
+                Ok(_permit) => tokio::time::timeout(CHAIN_QUERY_TIMEOUT, task)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(anyhow::anyhow!(
+                            "Timed out after {}s",
+                            CHAIN_QUERY_TIMEOUT.as_secs()
+                        ))
+                    }),
+                Err(e) => Err(e.into()),
+            };
+            (chain_id, outcome)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = queries.join_next().await {
+        results.push(joined.context("Chain query panicked")?);
+    }
+    results.sort_by_key(|(chain_id, _)| *chain_id);
+    Ok(results)
+}
+
+/// A balance of the native token (no `token_address`) or an ERC20
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenBalance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<Address>,
+    pub symbol: String,
+    pub balance: Decimal,
+    pub decimals: u8,
+}
+
+/// Balances of one wallet on one chain
+#[derive(Debug, Clone)]
+pub struct ChainHoldings {
+    pub block_number: u64,
+    pub native: TokenBalance,
+    pub tokens: Vec<TokenBalance>,
+}
+
+/// Native and token balances of `address` on the client's chain, at the block `finality` tags
+pub async fn fetch_holdings(
+    client: &dyn EthereumClientTrait,
+    address: Address,
+    tokens: &[Address],
+    finality: Finality,
+) -> Result<ChainHoldings> {
+    let block = finality.resolve(client, None).await?;
+    let block_number = match block {
+        Some(block) => block,
+        None => client.get_block_number().await?,
+    };
+    let native = TokenBalance {
+        token_address: None,
+        symbol: native_symbol(client.get_chain_id()).to_string(),
+        balance: client.get_eth_balance_at(address, block).await?.normalize(),
+        decimals: 18,
+    };
+    let mut balances = Vec::new();
+    for &token in tokens {
+        let (balance, decimals) = client
+            .get_token_balance_at(token, address, block)
+            .await
+            .with_context(|| format!("Failed to read balance of token {:?}", token))?;
+        let symbol = client
+            .get_token_symbol(token)
+            .await
+            .unwrap_or_else(|_| "UNKNOWN".to_string());
+        balances.push(TokenBalance {
+            token_address: Some(token),
+            symbol,
+            balance: balance.normalize(),
+            decimals,
+        });
+    }
+    Ok(ChainHoldings {
+        block_number,
+        native,
+        tokens: balances,
+    })
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Page size used when a caller does not pass `limit`
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+
+/// Largest page a caller may request
+pub const MAX_PAGE_LIMIT: usize = 500;
+
+/// Cursor/limit parameters shared by list-returning tools.
+///
+/// Flatten into a tool's params struct with `#[serde(flatten)]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageParams {
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One page of a list result
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Size of the whole list; absent when the source only returned a prefix of it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl PageParams {
+    fn offset(&self) -> Result<usize> {
+        match &self.cursor {
+            Some(cursor) => cursor.parse().context("Invalid cursor"),
+            None => Ok(0),
+        }
+    }
+
+    fn page_limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    /// Use a smaller default and maximum page size, for tools whose items are costly to fetch
+    pub fn bounded(mut self, default: usize, max: usize) -> Self {
+        self.limit = Some(self.limit.unwrap_or(default).clamp(1, max));
+        self
+    }
+
+    /// How many items a source that returns the head of the list must fetch to
+    /// fill this page and tell whether another follows
+    pub fn fetch_limit(&self) -> Result<usize> {
+        Ok(self.offset()? + self.page_limit() + 1)
+    }
+
+    /// Like `paginate`, for the first `fetch_limit` items of a list whose size is unknown
+    pub fn paginate_head<T>(&self, items: Vec<T>) -> Result<Page<T>> {
+        let page = self.paginate(items)?;
+        Ok(Page {
+            total: None,
+            ..page
+        })
+    }
+
+    /// Slice `items`, ordered by increasing `key`, after the key held in the cursor.
+    ///
+    /// Unlike offsets, the cursor stays valid when the oldest items are dropped.
+    pub fn paginate_by_key<T>(&self, items: Vec<T>, key: impl Fn(&T) -> u64) -> Result<Page<T>> {
+        let total = items.len();
+        let after = match &self.cursor {
+            Some(cursor) => Some(cursor.parse::<u64>().context("Invalid cursor")?),
+            None => None,
+        };
+
+        let mut items: Vec<T> = items
+            .into_iter()
+            .filter(|item| after.is_none_or(|after| key(item) > after))
+            .collect();
+        let limit = self.page_limit();
+        let next_cursor = (items.len() > limit).then(|| key(&items[limit - 1]).to_string());
+        items.truncate(limit);
+
+        Ok(Page {
+            items,
+            total: Some(total),
+            next_cursor,
+        })
+    }
+
+    /// Slice `items` according to the cursor and limit
+    pub fn paginate<T>(&self, items: Vec<T>) -> Result<Page<T>> {
+        let total = items.len();
+        let offset = self.offset()?;
+        if offset > total {
+            anyhow::bail!(
+                "Cursor {} is past the end of the result ({} items)",
+                offset,
+                total
+            );
+        }
+
+        let end = (offset + self.page_limit()).min(total);
+        let next_cursor = (end < total).then(|| end.to_string());
+        let items = items.into_iter().skip(offset).take(end - offset).collect();
+
+        Ok(Page {
+            items,
+            total: Some(total),
+            next_cursor,
+        })
+    }
+}
+
+/// JSON schema properties for `cursor` and `limit`, merged into a tool's input schema
+pub fn page_schema_properties() -> Value {
+    bounded_page_schema_properties(DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT)
+}
+
+/// `page_schema_properties` for a tool that pages with `PageParams::bounded`
+pub fn bounded_page_schema_properties(default: usize, max: usize) -> Value {
+    json!({
+        "cursor": {
+            "type": "string",
+            "description": "Opaque cursor from a previous response's next_cursor"
+        },
+        "limit": {
+            "type": "integer",
+            "description": format!("Maximum items to return (default: {}, max: {})", default, max),
+            "minimum": 1,
+            "maximum": max
+        }
+    })
+}
@@ -0,0 +1,63 @@
+use super::payment_requests::unix_now;
+use super::schedule_payment::DEFAULT_SCHEDULE_PREVIEW_RUNS;
+use super::schedules::PaymentSchedules;
+use super::{PermissionTier, Tool};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct PauseScheduledPaymentTool {
+    schedules: Arc<PaymentSchedules>,
+}
+
+impl PauseScheduledPaymentTool {
+    pub fn new(schedules: Arc<PaymentSchedules>) -> Self {
+        Self { schedules }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ScheduleIdParams {
+    pub(crate) schedule_id: String,
+}
+
+#[async_trait]
+impl Tool for PauseScheduledPaymentTool {
+    fn name(&self) -> &str {
+        "pause_scheduled_payment"
+    }
+
+    fn description(&self) -> &str {
+        "Pause a scheduled recurring payment so no further runs are sent until it is resumed with resume_scheduled_payment."
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "schedule_id": {
+                    "type": "string",
+                    "description": "schedule_id returned by schedule_payment"
+                }
+            },
+            "required": ["schedule_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ScheduleIdParams = serde_json::from_value(params)
+            .context("Invalid parameters for pause_scheduled_payment")?;
+        let schedule = self
+            .schedules
+            .set_paused(&params.schedule_id, true, unix_now())?;
+        Ok(serde_json::to_value(
+            schedule.preview(DEFAULT_SCHEDULE_PREVIEW_RUNS),
+        )?)
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use ethers::prelude::*;
+use ethers::utils::to_checksum;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a payment request stays open by default
+pub const DEFAULT_PAYMENT_REQUEST_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest a payment request may stay open
+pub const MAX_PAYMENT_REQUEST_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Scheme of the MCP resource each request is published under
+pub const PAYMENT_REQUEST_RESOURCE_PREFIX: &str = "payment-request://";
+
+// Expired requests stay checkable this long before they are dropped
+const EXPIRED_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// EIP-681 URI asking for `amount` base units: a plain value transfer for ETH, or a
+/// call to the token's `transfer` otherwise
+pub fn eip681_uri(
+    chain_id: u64,
+    recipient: Address,
+    token: Option<Address>,
+    amount: U256,
+) -> String {
+    match token {
+        None => format!(
+            "ethereum:{}@{}?value={}",
+            to_checksum(&recipient, None),
+            chain_id,
+            amount
+        ),
+        Some(token) => format!(
+            "ethereum:{}@{}/transfer?address={}&uint256={}",
+            to_checksum(&token, None),
+            chain_id,
+            to_checksum(&recipient, None),
+            amount
+        ),
+    }
+}
+
+/// An invoice: who should be paid how much of what, and where to start looking for the payment
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentRequest {
+    pub request_id: String,
+    /// EIP-681 URI, for wallets that open payment links
+    pub uri: String,
+    /// Text to render as a QR code for wallets to scan; the same EIP-681 URI
+    pub qr_payload: String,
+    pub resource_uri: String,
+    pub chain_id: u64,
+    pub recipient: Address,
+    /// `None` for ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<Address>,
+    pub symbol: String,
+    pub decimals: u8,
+    pub amount: Decimal,
+    #[serde(skip)]
+    pub amount_base_units: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Payments are searched for from this block on
+    pub created_block: u64,
+    /// Recipient's ETH balance at creation; ETH payments are detected as growth above it
+    #[serde(skip)]
+    pub baseline_balance: Decimal,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl PaymentRequest {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Payment requests shared between the payment tools and the server's resources
+#[derive(Default)]
+pub struct PaymentRequests {
+    requests: Mutex<BTreeMap<String, PaymentRequest>>,
+}
+
+impl PaymentRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `request` under a fresh id, filling in its id and resource URI
+    pub fn insert(&self, mut request: PaymentRequest) -> PaymentRequest {
+        let id = format!("pr_{}", hex::encode(H128::random().as_bytes()));
+        request.resource_uri = format!("{}{}", PAYMENT_REQUEST_RESOURCE_PREFIX, id);
+        request.request_id = id.clone();
+
+        let now = unix_now();
+        let mut requests = self.requests.lock().unwrap();
+        requests.retain(|_, kept| kept.expires_at + EXPIRED_RETENTION_SECS > now);
+        requests.insert(id, request.clone());
+        request
+    }
+
+    pub fn get(&self, id: &str) -> Result<PaymentRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown payment request: {}", id))
+    }
+
+    /// The request published at `uri`, if `uri` is a payment request resource
+    pub fn by_resource_uri(&self, uri: &str) -> Option<PaymentRequest> {
+        let id = uri.strip_prefix(PAYMENT_REQUEST_RESOURCE_PREFIX)?;
+        self.get(id).ok()
+    }
+
+    /// Requests that have not expired, oldest first
+    pub fn open(&self) -> Vec<PaymentRequest> {
+        let now = unix_now();
+        let mut open: Vec<PaymentRequest> = self
+            .requests
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|request| !request.is_expired(now))
+            .cloned()
+            .collect();
+        open.sort_by_key(|request| request.created_at);
+        open
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,70 @@
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Largest share of a pool's input reserve one swap may sell by default, in percent
+pub const DEFAULT_MAX_POOL_SHARE_PERCENT: Decimal = Decimal::from_parts(10, 0, 0, false, 0);
+
+/// What happens to a live swap that sells more of a pool than the limit allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverLimitAction {
+    /// Refuse the swap
+    #[default]
+    Reject,
+    /// Ask the user to confirm before sending it
+    Confirm,
+}
+
+impl std::str::FromStr for OverLimitAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "reject" => Ok(OverLimitAction::Reject),
+            "confirm" => Ok(OverLimitAction::Confirm),
+            other => anyhow::bail!(
+                "Unknown pool share action: {} (expected reject or confirm)",
+                other
+            ),
+        }
+    }
+}
+
+/// Bound on a swap's size relative to the liquidity of the pool it trades through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolShareLimit {
+    /// Percent of the pool's input-side reserve one swap may sell
+    pub max_percent: Decimal,
+    pub action: OverLimitAction,
+}
+
+impl PoolShareLimit {
+    pub fn is_exceeded_by(&self, share_percent: Decimal) -> bool {
+        share_percent > self.max_percent
+    }
+
+    /// Why a swap selling `share_percent` of its pool's `token` reserve is over the limit
+    pub fn describe(&self, share_percent: Decimal, token: Address) -> String {
+        format!(
+            "The swap sells {}% of the pool's {:?} reserve, above the {}% limit",
+            share_percent.normalize(),
+            token,
+            self.max_percent.normalize()
+        )
+    }
+}
+
+/// `amount_in` as a percentage of `reserve_in`, both in the input token's base units.
+/// An empty pool counts as fully taken.
+pub fn pool_share_percent(amount_in: U256, reserve_in: U256) -> Decimal {
+    if reserve_in.is_zero() {
+        return Decimal::ONE_HUNDRED;
+    }
+    // Ten-thousandths of a percent
+    let scaled = amount_in.saturating_mul(U256::from(1_000_000u64)) / reserve_in;
+    if scaled > U256::from(u64::MAX) {
+        return Decimal::from(u64::MAX);
+    }
+    Decimal::from(scaled.low_u64()) / Decimal::from(10_000)
+}
@@ -0,0 +1,185 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::call_contract::balance_slot;
+use super::units::{from_base_units, to_base_units};
+use super::{Tool, ToolCategory};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::Token;
+use ethers::prelude::*;
+use ethers::providers::call_raw::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Runtime code placed at the sender for the preview. Called with
+/// `(token, recipient, amount)`, it reads the recipient's balance, sends the transfer
+/// from its own address, reads the balance again and returns `(before, after)`.
+/// A failed call reverts with the token's revert data.
+const TRANSFER_PROBE: &str = "6370a0823160e01b60005260203560045260206080602460006000355afa1560735763a9059cbb60e01b600052602035600452604035602452600060006044600060006000355af1156073576370a0823160e01b600052602035600452602060a0602460006000355afa1560735760406080f35b3d600060003e3d6000fd";
+
+pub struct PreviewTransferTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> PreviewTransferTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names as the recipient and sender
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewTransferParams {
+    token: String,
+    to: String,
+    /// Whole tokens
+    amount: Decimal,
+    #[serde(default)]
+    from: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PreviewTransferResult {
+    token: Address,
+    from: Address,
+    to: Address,
+    amount: String,
+    /// What the recipient's balance grows by
+    amount_received: String,
+    /// Share of the amount lost to transfer taxes, burns or hooks
+    transfer_fee_percent: String,
+    /// The sender held less than the amount, so its balance was overridden for the preview
+    balance_overridden: bool,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for PreviewTransferTool<C> {
+    fn name(&self) -> &str {
+        "preview_transfer"
+    }
+
+    fn description(&self) -> &str {
+        "Simulate an ERC20 transfer with eth_call and state overrides to see how much the recipient would actually receive after transfer taxes, burns or hooks, before sending it. Works even when the sender holds less than the amount. Nothing is sent."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token": {
+                    "type": "string",
+                    "description": "ERC20 token contract address"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Recipient address or contact name"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Whole tokens to send"
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Sender address or contact name (default: the server's wallet)"
+                }
+            },
+            "required": ["token", "to", "amount"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: PreviewTransferParams =
+            serde_json::from_value(params).context("Invalid parameters for preview_transfer")?;
+        let book = self.book.as_deref();
+        let token: Address = params
+            .token
+            .trim()
+            .parse()
+            .context("Invalid token address")?;
+        let to = resolve_address(book, &params.to).context("Invalid recipient address")?;
+        let from = match params.from.as_deref() {
+            Some(from) => resolve_address(book, from).context("Invalid from address")?,
+            None => self.client.get_wallet_address(),
+        };
+        if from == to {
+            anyhow::bail!("Sender and recipient must differ");
+        }
+        if params.amount <= Decimal::ZERO {
+            anyhow::bail!("Amount must be positive");
+        }
+
+        let decimals = self
+            .client
+            .get_token_decimals(token)
+            .await
+            .context("Failed to read token decimals")?;
+        let amount = to_base_units(params.amount, decimals as u32)?;
+
+        // The sender runs the probe, so the token sees it as msg.sender
+        let mut state = spoof::state();
+        state
+            .account(from)
+            .code(TRANSFER_PROBE.parse().context("Invalid transfer probe")?);
+        let (held, _) = self.client.get_token_balance(token, from).await?;
+        let balance_overridden = held < params.amount;
+        if balance_overridden {
+            let slot = balance_slot(self.client.as_ref(), token, from).await?;
+            let mut word = [0u8; 32];
+            amount.to_big_endian(&mut word);
+            state.account(token).store(slot, H256::from(word));
+        }
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(from)
+            .to(from)
+            .data(ethers::abi::encode(&[
+                Token::Address(token),
+                Token::Address(to),
+                Token::Uint(amount),
+            ]))
+            .into();
+        let output = self
+            .client
+            .call_contract(&tx, None, Some(&state))
+            .await
+            .context("The transfer would revert")?;
+        if output.len() != 64 {
+            anyhow::bail!("Unexpected transfer probe output: {}", output);
+        }
+        let before = U256::from_big_endian(&output[..32]);
+        let after = U256::from_big_endian(&output[32..]);
+        let received = after.saturating_sub(before);
+
+        let fee_percent = if received >= amount {
+            Decimal::ZERO
+        } else {
+            let received = from_base_units(received, decimals as u32)?;
+            (params.amount - received) / params.amount * Decimal::ONE_HUNDRED
+        };
+        let result = PreviewTransferResult {
+            token,
+            from,
+            to,
+            amount: params.amount.normalize().to_string(),
+            amount_received: from_base_units(received, decimals as u32)?
+                .normalize()
+                .to_string(),
+            transfer_fee_percent: fee_percent.round_dp(4).normalize().to_string(),
+            balance_overridden,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::ethereum::ReferencePrice;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Largest gap between a DEX price and its oracle reference before it is flagged, in percent
+pub const DEFAULT_MAX_PRICE_DEVIATION_PERCENT: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+
+/// A DEX-derived price held against an oracle reference for the same quote currency
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceCheck {
+    pub reference_price: Decimal,
+    pub reference_source: String,
+    /// Unix seconds of the oldest feed update the reference was built from
+    pub reference_updated_at: u64,
+    /// How far the DEX price sits above (positive) or below the reference, in percent
+    pub deviation_percent: Decimal,
+    pub max_deviation_percent: Decimal,
+    /// The deviation is beyond the limit, so the DEX price should not be relied on
+    pub anomalous: bool,
+}
+
+impl PriceCheck {
+    pub fn new(price: Decimal, reference: ReferencePrice, max_deviation_percent: Decimal) -> Self {
+        let deviation_percent = if reference.usd_price.is_zero() {
+            Decimal::ZERO
+        } else {
+            ((price - reference.usd_price) / reference.usd_price * Decimal::ONE_HUNDRED)
+                .round_dp(2)
+                .normalize()
+        };
+        Self {
+            reference_price: reference.usd_price.normalize(),
+            reference_source: reference.source,
+            reference_updated_at: reference.updated_at,
+            deviation_percent,
+            max_deviation_percent,
+            anomalous: deviation_percent.abs() > max_deviation_percent,
+        }
+    }
+
+    /// Warning for an anomalous price, naming the reference it strays from
+    pub fn warning(&self) -> Option<String> {
+        self.anomalous.then(|| {
+            format!(
+                "The DEX price is {}% away from {} ({}), beyond the {}% limit; the pool may be manipulated or too illiquid to price from. Do not rely on this price",
+                self.deviation_percent.normalize(),
+                self.reference_source,
+                self.reference_price,
+                self.max_deviation_percent.normalize()
+            )
+        })
+    }
+}
@@ -0,0 +1,118 @@
+use super::sessions::{SessionMap, SessionState};
+use crate::ethereum::Plan;
+use anyhow::Result;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long a swap quote can be executed by default
+pub const DEFAULT_QUOTE_TTL: Duration = Duration::from_secs(30);
+
+/// How long an expired quote stays readable by `compare_quotes`
+pub const QUOTE_RETENTION: Duration = Duration::from_secs(600);
+
+/// Largest price move since the quote that `execute_swap` re-quotes through, by default
+pub const DEFAULT_MAX_QUOTE_MOVE_BPS: u32 = 50;
+
+/// A swap simulation kept for later execution
+#[derive(Debug, Clone)]
+pub struct StoredQuote {
+    pub from_token: Address,
+    pub to_token: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// Percentage, as passed to `swap_tokens`
+    pub slippage_tolerance: f64,
+    /// Percent of the pool's input reserve the swap sells, when the reserves were read
+    pub pool_share_percent: Option<Decimal>,
+    /// Largest constant-product price impact of any leg, in percent, when the reserves
+    /// were read
+    pub price_impact_percent: Option<Decimal>,
+    /// Primary pool's `to_token` per `from_token` before the swap, when its reserves
+    /// were read
+    pub spot_price: Option<Decimal>,
+    /// Gas of the whole bundle, approvals included
+    pub gas_estimate: U256,
+    pub gas_price: U256,
+    /// Block the simulation ran against
+    pub block_number: u64,
+    /// Unix seconds the quote was made at
+    pub quoted_at: u64,
+    pub plan: Plan,
+}
+
+struct Entry {
+    quote: StoredQuote,
+    expires_at: Instant,
+}
+
+/// Short-lived swap quotes shared between `swap_tokens` and `execute_swap`. Each
+/// session only sees the quotes it was given.
+pub struct QuoteStore {
+    ttl: Duration,
+    quotes: SessionMap<HashMap<String, Entry>>,
+}
+
+impl Default for QuoteStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUOTE_TTL)
+    }
+}
+
+impl QuoteStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            quotes: SessionMap::new(),
+        }
+    }
+
+    /// Store `quote`, returning its id and expiry as Unix seconds
+    pub fn insert(&self, quote: StoredQuote) -> (String, u64) {
+        let now = Instant::now();
+        let id = format!("q_{}", hex::encode(H128::random().as_bytes()));
+        self.quotes.with(|quotes| {
+            quotes.retain(|_, entry| entry.expires_at + QUOTE_RETENTION > now);
+            quotes.insert(
+                id.clone(),
+                Entry {
+                    quote,
+                    expires_at: now + self.ttl,
+                },
+            );
+        });
+
+        let expires_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            + self.ttl;
+        (id, expires_at.as_secs())
+    }
+
+    /// Look up a quote of the current session that has not expired
+    pub fn get(&self, id: &str) -> Result<StoredQuote> {
+        self.quotes.with(|quotes| match quotes.get(id) {
+            Some(entry) if entry.expires_at > Instant::now() => Ok(entry.quote.clone()),
+            Some(_) => anyhow::bail!("Quote {} has expired; call swap_tokens again", id),
+            None => anyhow::bail!("Unknown quote_id: {}", id),
+        })
+    }
+
+    /// Look up a quote of the current session for reading, including one that expired
+    /// less than `QUOTE_RETENTION` ago
+    pub fn recall(&self, id: &str) -> Result<StoredQuote> {
+        self.quotes.with(|quotes| match quotes.get(id) {
+            Some(entry) if entry.expires_at + QUOTE_RETENTION > Instant::now() => {
+                Ok(entry.quote.clone())
+            }
+            _ => anyhow::bail!("Unknown quote_id: {}", id),
+        })
+    }
+}
+
+impl SessionState for QuoteStore {
+    fn end_session(&self, session: &str) {
+        self.quotes.end_session(session);
+    }
+}
@@ -0,0 +1,330 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::freshness::{finality_schema_properties, Finality};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::storage::{
+    EIP1822_PROXIABLE_SLOT, EIP1967_ADMIN_SLOT, EIP1967_BEACON_SLOT, EIP1967_IMPLEMENTATION_SLOT,
+    ZEPPELINOS_IMPLEMENTATION_SLOT,
+};
+use crate::ethereum::{array_data_slot, mapping_slot, EthereumClientTrait, MappingLayout};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Consecutive slots one call may read, e.g. the fields of a struct
+const MAX_SLOTS_PER_READ: u64 = 32;
+
+/// Which proxy convention a contract follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProxyStandard {
+    Eip1967,
+    /// EIP-1967 beacon proxy: the implementation comes from the beacon
+    Eip1967Beacon,
+    /// EIP-1822 UUPS
+    Eip1822,
+    /// Pre-EIP-1967 OpenZeppelin (zos) proxy
+    ZeppelinOs,
+}
+
+/// Where a proxy delegates to, read from its standard storage slots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ProxyInfo {
+    pub standard: ProxyStandard,
+    pub implementation: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beacon: Option<Address>,
+}
+
+/// The address a storage word holds, if it holds only an address
+fn word_address(word: H256) -> Option<Address> {
+    let bytes = word.as_bytes();
+    (bytes[..12].iter().all(|byte| *byte == 0) && !word.is_zero())
+        .then(|| Address::from_slice(&bytes[12..]))
+}
+
+/// Proxy slots of `address`, most specific standard first
+pub(crate) async fn detect_proxy<C: EthereumClientTrait>(
+    client: &C,
+    address: Address,
+    block: Option<u64>,
+) -> Result<Option<ProxyInfo>> {
+    let read = |slot: &'static str| async move {
+        let slot = slot
+            .parse()
+            .with_context(|| format!("Invalid storage slot {}", slot))?;
+        client.get_storage(address, slot, block).await
+    };
+
+    let admin = word_address(read(EIP1967_ADMIN_SLOT).await?);
+    if let Some(implementation) = word_address(read(EIP1967_IMPLEMENTATION_SLOT).await?) {
+        return Ok(Some(ProxyInfo {
+            standard: ProxyStandard::Eip1967,
+            implementation,
+            admin,
+            beacon: None,
+        }));
+    }
+    if let Some(beacon) = word_address(read(EIP1967_BEACON_SLOT).await?) {
+        let call: TypedTransaction = TransactionRequest::new()
+            .to(beacon)
+            .data(keccak256("implementation()")[..4].to_vec())
+            .into();
+        let output = client
+            .call_contract(
+                &call,
+                block.map(|block| BlockId::from(U64::from(block))),
+                None,
+            )
+            .await
+            .context("Failed to read the beacon's implementation")?;
+        let implementation = output
+            .get(..32)
+            .and_then(|word| word_address(H256::from_slice(word)))
+            .context("Beacon returned no implementation")?;
+        return Ok(Some(ProxyInfo {
+            standard: ProxyStandard::Eip1967Beacon,
+            implementation,
+            admin,
+            beacon: Some(beacon),
+        }));
+    }
+    for (slot, standard) in [
+        (EIP1822_PROXIABLE_SLOT, ProxyStandard::Eip1822),
+        (ZEPPELINOS_IMPLEMENTATION_SLOT, ProxyStandard::ZeppelinOs),
+    ] {
+        if let Some(implementation) = word_address(read(slot).await?) {
+            return Ok(Some(ProxyInfo {
+                standard,
+                implementation,
+                admin,
+                beacon: None,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+pub struct ReadStorageTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> ReadStorageTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names as the contract and as address mapping keys
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// A mapping key as its slot preimage: addresses and numbers padded to a word,
+    /// 32-byte hex as is, anything else as string bytes
+    fn key_bytes(&self, key: &Value) -> Result<Vec<u8>> {
+        let text = match key {
+            Value::Number(number) => number.to_string(),
+            Value::Bool(flag) => return Ok(H256::from_low_u64_be(*flag as u64).0.to_vec()),
+            Value::String(text) => text.trim().to_string(),
+            other => anyhow::bail!("Unsupported mapping key: {}", other),
+        };
+        if let Ok(address) = text.parse::<Address>() {
+            return Ok(H256::from(address).0.to_vec());
+        }
+        if let Ok(word) = text.parse::<H256>() {
+            return Ok(word.0.to_vec());
+        }
+        if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+            let mut word = [0u8; 32];
+            U256::from_dec_str(&text)
+                .context("Mapping key is too large")?
+                .to_big_endian(&mut word);
+            return Ok(word.to_vec());
+        }
+        if let Some(contact) = self.book.as_ref().and_then(|book| book.lookup(&text)) {
+            return Ok(H256::from(contact.address).0.to_vec());
+        }
+        Ok(text.into_bytes())
+    }
+}
+
+fn parse_slot(slot: &str) -> Result<H256> {
+    let slot = slot.trim();
+    Ok(match slot.to_lowercase().as_str() {
+        "eip1967_implementation" => EIP1967_IMPLEMENTATION_SLOT.parse()?,
+        "eip1967_admin" => EIP1967_ADMIN_SLOT.parse()?,
+        "eip1967_beacon" => EIP1967_BEACON_SLOT.parse()?,
+        _ if slot.starts_with("0x") => {
+            let value = U256::from_str_radix(&slot[2..], 16).context("Invalid hex slot")?;
+            H256::from_uint(&value)
+        }
+        _ => H256::from_uint(&U256::from_dec_str(slot).context("Invalid slot")?),
+    })
+}
+
+fn add_to_slot(slot: H256, amount: u64) -> H256 {
+    H256::from_uint(&slot.into_uint().overflowing_add(U256::from(amount)).0)
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadStorageParams {
+    address: String,
+    #[serde(default)]
+    slot: Option<String>,
+    /// Mapping keys, outermost first
+    #[serde(default)]
+    keys: Vec<Value>,
+    #[serde(default)]
+    layout: MappingLayout,
+    /// Element of the dynamic array at the slot reached so far
+    #[serde(default)]
+    array_index: Option<u64>,
+    /// Added last, e.g. to reach a struct field
+    #[serde(default)]
+    offset: u64,
+    #[serde(default = "default_count")]
+    count: u64,
+    #[serde(default)]
+    block: Option<u64>,
+    #[serde(default)]
+    finality: Finality,
+}
+
+fn default_count() -> u64 {
+    1
+}
+
+#[derive(Debug, Serialize)]
+struct StorageWord {
+    slot: H256,
+    value: H256,
+    /// The word as an unsigned integer
+    uint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<Address>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadStorageResult {
+    address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block: Option<u64>,
+    words: Vec<StorageWord>,
+    /// Always checked, so unverified proxies point to the code that actually runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<ProxyInfo>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ReadStorageTool<C> {
+    fn name(&self) -> &str {
+        "read_storage"
+    }
+
+    fn description(&self) -> &str {
+        "Read raw storage words of any contract, verified or not. Give a slot number, or a base slot with mapping keys (Solidity or Vyper layout), an array index and a field offset to have the final slot computed; count reads consecutive slots. Each word is shown as hex, an integer and, when it fits, an address. The contract's proxy slots (EIP-1967 implementation, admin and beacon, EIP-1822, legacy OpenZeppelin) are checked on every read to reveal the implementation behind a proxy."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Read
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Contract address or contact name"
+                },
+                "slot": {
+                    "type": "string",
+                    "description": "Slot as a decimal or 0x hex number, or eip1967_implementation, eip1967_admin or eip1967_beacon. Omit to only detect a proxy"
+                },
+                "keys": {
+                    "type": "array",
+                    "description": "Mapping keys applied to the slot in order, outermost first: addresses or contact names, numbers, 32-byte hex, or strings"
+                },
+                "layout": {
+                    "type": "string",
+                    "enum": ["solidity", "vyper"],
+                    "description": "Mapping slot layout (default: solidity)"
+                },
+                "array_index": {
+                    "type": "integer",
+                    "description": "Element of the dynamic array stored at the slot reached by the keys"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Slots to add last, e.g. a struct field's position (default: 0)"
+                },
+                "count": {
+                    "type": "integer",
+                    "description": "Consecutive slots to read (default: 1, at most 32)"
+                },
+                "block": {
+                    "type": "integer",
+                    "description": "Block number to read at (default: latest)"
+                }
+            },
+            "required": ["address"]
+        });
+        merge_schema_properties(&mut schema["properties"], finality_schema_properties());
+        schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ReadStorageParams =
+            serde_json::from_value(params).context("Invalid parameters for read_storage")?;
+        let block = params
+            .finality
+            .resolve(self.client.as_ref(), params.block)
+            .await?;
+        let address =
+            resolve_address(self.book.as_deref(), &params.address).context("Invalid address")?;
+        if params.count == 0 || params.count > MAX_SLOTS_PER_READ {
+            anyhow::bail!("count must be between 1 and {}", MAX_SLOTS_PER_READ);
+        }
+
+        let mut words = Vec::new();
+        if let Some(slot) = params.slot.as_deref() {
+            let mut slot = parse_slot(slot)?;
+            for key in &params.keys {
+                slot = mapping_slot(slot, &self.key_bytes(key)?, params.layout);
+            }
+            if let Some(index) = params.array_index {
+                slot = add_to_slot(array_data_slot(slot), index);
+            }
+            slot = add_to_slot(slot, params.offset);
+            for index in 0..params.count {
+                let slot = add_to_slot(slot, index);
+                let value = self.client.get_storage(address, slot, block).await?;
+                words.push(StorageWord {
+                    slot,
+                    value,
+                    uint: value.into_uint().to_string(),
+                    address: word_address(value),
+                });
+            }
+        } else if !params.keys.is_empty() || params.array_index.is_some() {
+            anyhow::bail!("Mapping keys and array indexes need a base slot");
+        }
+        let proxy = detect_proxy(self.client.as_ref(), address, block).await?;
+
+        let result = ReadStorageResult {
+            address,
+            block,
+            words,
+            proxy,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::ethereum::TokenEventSource;
+use anyhow::Result;
+use ethers::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Blocks of transfer logs searched for earlier payments by default, about a year
+pub const DEFAULT_RECIPIENT_LOOKBACK_BLOCKS: u64 = 2_600_000;
+
+/// Recipients the wallet has paid before, from the server's own sends plus a scan
+/// of the wallet's token transfer logs. ETH sent by other software leaves no logs,
+/// so such recipients only become known once this server has paid them.
+pub struct RecipientHistory {
+    events: Arc<dyn TokenEventSource>,
+    lookback_blocks: u64,
+    require_confirmation: bool,
+    known: Mutex<HashSet<Address>>,
+}
+
+impl RecipientHistory {
+    pub fn new(events: Arc<dyn TokenEventSource>, lookback_blocks: u64) -> Self {
+        Self {
+            events,
+            lookback_blocks,
+            require_confirmation: false,
+            known: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Refuse live payments to first-time recipients unless the call confirms them
+    pub fn with_required_confirmation(mut self, require: bool) -> Self {
+        self.require_confirmation = require;
+        self
+    }
+
+    pub fn requires_confirmation(&self) -> bool {
+        self.require_confirmation
+    }
+
+    /// Remember a recipient the server has paid
+    pub fn record(&self, to: Address) {
+        self.known.lock().unwrap().insert(to);
+    }
+
+    /// Whether `wallet` has paid `to` before, looking back from block `head`
+    pub async fn is_known(&self, wallet: Address, to: Address, head: u64) -> Result<bool> {
+        if to == wallet || self.known.lock().unwrap().contains(&to) {
+            return Ok(true);
+        }
+        let from_block = head.saturating_sub(self.lookback_blocks);
+        let found = self
+            .events
+            .has_token_transfers(wallet, to, from_block, head)
+            .await?;
+        if found {
+            self.record(to);
+        }
+        Ok(found)
+    }
+}
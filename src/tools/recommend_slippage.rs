@@ -0,0 +1,229 @@
+use super::explain::display;
+use super::units::to_base_units;
+use super::{Explanation, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, UniswapRouterTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Roughly one hour of mainnet blocks
+const DEFAULT_LOOKBACK_BLOCKS: u64 = 300;
+
+// Bounds for the recommendation, in percent
+const MIN_SLIPPAGE: Decimal = Decimal::from_parts(1, 0, 0, false, 1); // 0.1
+const MAX_SLIPPAGE: Decimal = Decimal::from_parts(5, 0, 0, false, 0); // 5
+
+// Fewer price samples than this and the volatility estimate is unreliable
+const MIN_CONFIDENT_SAMPLES: usize = 5;
+
+pub struct RecommendSlippageTool<C: EthereumClientTrait, U: UniswapRouterTrait> {
+    client: Arc<C>,
+    uniswap: Arc<U>,
+}
+
+impl<C: EthereumClientTrait, U: UniswapRouterTrait> RecommendSlippageTool<C, U> {
+    pub fn new(client: Arc<C>, uniswap: Arc<U>) -> Self {
+        Self { client, uniswap }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RecommendSlippageParams {
+    from_token: String,
+    to_token: String,
+    amount: String,
+    #[serde(default = "default_lookback_blocks")]
+    lookback_blocks: u64,
+}
+
+fn default_lookback_blocks() -> u64 {
+    DEFAULT_LOOKBACK_BLOCKS
+}
+
+#[derive(Debug, Serialize)]
+struct RecommendSlippageResult {
+    from_token: String,
+    to_token: String,
+    amount: String,
+    recommended_slippage_percent: String,
+    volatility_percent: String,
+    price_impact_percent: String,
+    price_samples: usize,
+    lookback_blocks: u64,
+    pool_address: String,
+    confidence: String,
+    rationale: String,
+}
+
+/// Standard deviation of consecutive price returns, in percent
+pub fn return_volatility_percent(prices: &[Decimal]) -> Decimal {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter_map(|w| {
+            let (prev, next) = (w[0].to_f64()?, w[1].to_f64()?);
+            (prev > 0.0).then(|| next / prev - 1.0)
+        })
+        .collect();
+    if returns.len() < 2 {
+        return Decimal::ZERO;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Decimal::from_f64(variance.sqrt() * 100.0)
+        .unwrap_or(Decimal::ZERO)
+        .round_dp(4)
+}
+
+/// Constant-product price impact of selling `amount_in` into `reserve_in`, in percent
+pub fn price_impact_percent(amount_in: U256, reserve_in: U256) -> Decimal {
+    let denominator = reserve_in.saturating_add(amount_in);
+    if denominator.is_zero() {
+        return Decimal::ZERO;
+    }
+    let bps = amount_in.saturating_mul(U256::from(1_000_000u64)) / denominator;
+    Decimal::from(bps.low_u64()) / Decimal::from(10_000)
+}
+
+/// Tolerance covering two standard deviations of price movement plus a buffer that grows
+/// with the trade's share of the pool, clamped to [0.1%, 5%] and rounded up to 0.05%
+pub fn recommend_slippage_percent(volatility_pct: Decimal, price_impact_pct: Decimal) -> Decimal {
+    let raw = MIN_SLIPPAGE + volatility_pct * Decimal::TWO + price_impact_pct / Decimal::from(4);
+    let step = Decimal::new(5, 2);
+    let rounded = (raw / step).ceil() * step;
+    rounded.clamp(MIN_SLIPPAGE, MAX_SLIPPAGE).normalize()
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
+    for RecommendSlippageTool<C, U>
+{
+    fn name(&self) -> &str {
+        "recommend_slippage"
+    }
+
+    fn description(&self) -> &str {
+        "Recommend a slippage tolerance for a Uniswap V2 swap based on the pair's recent price volatility and the trade's size relative to pool liquidity."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from_token": {
+                    "type": "string",
+                    "description": "Address of the token to swap from"
+                },
+                "to_token": {
+                    "type": "string",
+                    "description": "Address of the token to swap to"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount to swap (in human-readable format, e.g., '1.5' for 1.5 tokens)"
+                },
+                "lookback_blocks": {
+                    "type": "integer",
+                    "description": format!("Number of recent blocks used to measure volatility (default: {})", DEFAULT_LOOKBACK_BLOCKS),
+                    "minimum": 1
+                }
+            },
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    fn explain(&self, params: &Value, result: &Value) -> Explanation {
+        Explanation::new(
+            format!(
+                "Sampled {} prices of pool {} over the last {} blocks; their returns have a standard deviation of {}%. The trade's price impact is {}%. The recommendation {}% covers two standard deviations plus a quarter of the impact on top of a 0.1% floor, rounded up to 0.05% and capped at 5%.",
+                display(&result["price_samples"]),
+                display(&result["pool_address"]),
+                display(&result["lookback_blocks"]),
+                display(&result["volatility_percent"]),
+                display(&result["price_impact_percent"]),
+                display(&result["recommended_slippage_percent"])
+            ),
+            json!({
+                "inputs": params,
+                "pool": result["pool_address"],
+                "price_samples": result["price_samples"],
+                "lookback_blocks": result["lookback_blocks"],
+                "formula": "ceil_to_0.05(0.1 + 2 * volatility_percent + price_impact_percent / 4), clamped to [0.1, 5]",
+            }),
+        )
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: RecommendSlippageParams =
+            serde_json::from_value(params).context("Invalid parameters for recommend_slippage")?;
+
+        let from_token: Address = params
+            .from_token
+            .parse()
+            .context("Invalid from_token address")?;
+        let to_token: Address = params
+            .to_token
+            .parse()
+            .context("Invalid to_token address")?;
+
+        // Reserves are raw, so scale the amount by from_token's own decimals
+        let amount_decimal = Decimal::from_str(&params.amount).context("Invalid amount")?;
+        let decimals = self
+            .client
+            .get_token_decimals(from_token)
+            .await
+            .context("Failed to read from_token decimals")?;
+        let amount_in = to_base_units(amount_decimal, decimals as u32)?;
+
+        let reserves = self.uniswap.get_pool_reserves(from_token, to_token).await?;
+        let prices = self
+            .uniswap
+            .get_recent_prices(from_token, to_token, params.lookback_blocks)
+            .await?;
+
+        let prices: Vec<Decimal> = prices.into_iter().map(|p| p.price).collect();
+        let volatility = return_volatility_percent(&prices);
+        let impact = price_impact_percent(amount_in, reserves.reserve_in);
+        let recommended = recommend_slippage_percent(volatility, impact);
+
+        let confidence = if prices.len() >= MIN_CONFIDENT_SAMPLES {
+            "normal"
+        } else {
+            "low"
+        };
+        let rationale = format!(
+            "{}% = 0.1% base + 2 x {}% return volatility over {} price updates + 1/4 x {}% price impact, clamped to [0.1%, 5%]",
+            recommended,
+            volatility,
+            prices.len(),
+            impact
+        );
+
+        let result = RecommendSlippageResult {
+            from_token: params.from_token,
+            to_token: params.to_token,
+            amount: params.amount,
+            recommended_slippage_percent: recommended.to_string(),
+            volatility_percent: volatility.to_string(),
+            price_impact_percent: impact.to_string(),
+            price_samples: prices.len(),
+            lookback_blocks: params.lookback_blocks,
+            pool_address: format!("{:?}", reserves.pair_address),
+            confidence: confidence.to_string(),
+            rationale,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,200 @@
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::get_ens_name::EnsStatus;
+use super::units::from_base_units;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{
+    eth_label, normalize_name, EnsSource, EthereumClientTrait, GasEscalation, Plan, PlanStep,
+    ENS_ETH_REGISTRAR_CONTROLLER,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// The rent is priced in USD through an oracle, so the ETH sent carries headroom for
+// the price moving before the renewal is mined; the controller refunds the excess
+const PRICE_BUFFER_PERCENT: u64 = 5;
+
+pub struct RenewEnsNameTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    ens: Arc<dyn EnsSource>,
+}
+
+impl<C: EthereumClientTrait> RenewEnsNameTool<C> {
+    /// Without `execution_enabled` only dry runs are allowed
+    pub fn new(client: Arc<C>, ens: Arc<dyn EnsSource>, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            ens,
+        }
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live renewals must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RenewEnsNameParams {
+    name: String,
+    #[serde(default = "default_duration_days")]
+    duration_days: u64,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    override_gas_reserve: bool,
+}
+
+fn default_duration_days() -> u64 {
+    365
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct RenewEnsNameResult {
+    name: String,
+    status: EnsStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registrant: Option<Address>,
+    expires: u64,
+    new_expires: u64,
+    /// Current rent in ETH
+    price: String,
+    /// ETH sent with the renewal, including the buffer for price moves
+    max_cost: String,
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for RenewEnsNameTool<C> {
+    fn name(&self) -> &str {
+        "renew_ens_name"
+    }
+
+    fn description(&self) -> &str {
+        "Extend a .eth name's registration by paying the ENS registrar controller from the server wallet. Any name can be renewed, including one in its grace period, whoever owns it; subnames have no registration to renew. The ETH sent carries a 5% buffer over the current rent, and the controller refunds what is not needed. Dry run by default; live runs need execution enabled on the server."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": ".eth name to renew, e.g. 'example.eth'"
+                },
+                "duration_days": {
+                    "type": "integer",
+                    "description": "Days to extend the registration by (default: 365)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transaction without sending (default: true). Live execution must be enabled on the server"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: RenewEnsNameParams =
+            serde_json::from_value(params).context("Invalid parameters for renew_ens_name")?;
+        let name = normalize_name(&params.name)?;
+        let label = eth_label(&name)
+            .with_context(|| format!("{} is not a .eth name; only those are renewed", name))?
+            .to_string();
+        if params.duration_days == 0 {
+            anyhow::bail!("duration_days must be positive");
+        }
+        let duration_secs = params
+            .duration_days
+            .checked_mul(86_400)
+            .context("duration_days is too large")?;
+
+        let now = self.client.get_latest_block().await?.timestamp;
+        let found = self.ens.lookup(&name).await?;
+        let status = EnsStatus::of(&found, now);
+        if status == EnsStatus::Available {
+            anyhow::bail!(
+                "{} is not registered or has been released; it must be registered, not renewed",
+                name
+            );
+        }
+        let expires = found.expires.unwrap_or_default();
+
+        let price = self.ens.rent_price(&label, duration_secs).await?;
+        let value = price + price * U256::from(PRICE_BUFFER_PERCENT) / U256::from(100u64);
+        let mut plan = Plan::default();
+        plan.steps.push(PlanStep::RenewEns {
+            controller: ENS_ETH_REGISTRAR_CONTROLLER
+                .parse()
+                .context("Invalid ENS controller address")?,
+            label,
+            duration_secs,
+            value,
+        });
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "override_gas_reserve": params.override_gas_reserve,
+            }))
+            .await?;
+
+        let result = RenewEnsNameResult {
+            name,
+            status,
+            registrant: found.registrant,
+            expires,
+            new_expires: expires.saturating_add(duration_secs),
+            price: from_base_units(price, 18)?.normalize().to_string(),
+            max_cost: from_base_units(value, 18)?.normalize().to_string(),
+            plan,
+            execution,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
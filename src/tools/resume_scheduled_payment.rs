@@ -0,0 +1,63 @@
+use super::pause_scheduled_payment::ScheduleIdParams;
+use super::payment_requests::unix_now;
+use super::schedule_payment::DEFAULT_SCHEDULE_PREVIEW_RUNS;
+use super::schedules::PaymentSchedules;
+use super::{PermissionTier, Tool};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct ResumeScheduledPaymentTool {
+    schedules: Arc<PaymentSchedules>,
+}
+
+impl ResumeScheduledPaymentTool {
+    pub fn new(schedules: Arc<PaymentSchedules>) -> Self {
+        Self { schedules }
+    }
+}
+
+#[async_trait]
+impl Tool for ResumeScheduledPaymentTool {
+    fn name(&self) -> &str {
+        "resume_scheduled_payment"
+    }
+
+    fn description(&self) -> &str {
+        "Resume a paused scheduled payment. Runs that fell due while it was paused are skipped, not paid late; the result shows the next run times."
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    // Resuming lets the schedule's runs go out again
+    fn moves_funds(&self, _params: &Value) -> bool {
+        true
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "schedule_id": {
+                    "type": "string",
+                    "description": "schedule_id returned by schedule_payment"
+                }
+            },
+            "required": ["schedule_id"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ScheduleIdParams = serde_json::from_value(params)
+            .context("Invalid parameters for resume_scheduled_payment")?;
+        let schedule = self
+            .schedules
+            .set_paused(&params.schedule_id, false, unix_now())?;
+        Ok(serde_json::to_value(
+            schedule.preview(DEFAULT_SCHEDULE_PREVIEW_RUNS),
+        )?)
+    }
+}
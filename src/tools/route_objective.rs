@@ -0,0 +1,46 @@
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// What swap routes are ranked by when swap_tokens compares them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteObjective {
+    /// Output less the gas cost priced in the output token, so a better price that
+    /// needs far more gas can lose on small trades
+    #[default]
+    NetOfGas,
+    /// Raw output, ignoring gas
+    Output,
+}
+
+impl std::str::FromStr for RouteObjective {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "net_of_gas" => Ok(RouteObjective::NetOfGas),
+            "output" => Ok(RouteObjective::Output),
+            other => anyhow::bail!(
+                "Unknown route objective: {} (expected net_of_gas or output)",
+                other
+            ),
+        }
+    }
+}
+
+impl RouteObjective {
+    /// Score of a route paying out `amount_out` for `gas_cost` wei, both in base units.
+    /// `eth_in_out` is the price of one wei in output base units; without it gas cannot
+    /// be netted and the raw output is scored.
+    pub fn score(&self, amount_out: U256, gas_cost: U256, eth_in_out: Option<Decimal>) -> Decimal {
+        let out = Decimal::from_str_exact(&amount_out.to_string()).unwrap_or(Decimal::MAX);
+        match (self, eth_in_out) {
+            (RouteObjective::NetOfGas, Some(rate)) => {
+                let gas = Decimal::from_str_exact(&gas_cost.to_string()).unwrap_or(Decimal::MAX);
+                out - gas.checked_mul(rate).unwrap_or(Decimal::MAX)
+            }
+            _ => out,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// ScamSniffer's public list of drainer and phishing addresses
+pub const DEFAULT_SCAM_LIST_URL: &str =
+    "https://raw.githubusercontent.com/scamsniffer/scam-database/main/blacklist/address.json";
+
+/// How often feeds are fetched again by default
+pub const DEFAULT_SCAM_LIST_REFRESH: Duration = Duration::from_secs(6 * 60 * 60);
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A list of scam addresses fetched over HTTP. Any body works: every 0x-prefixed
+/// 40 hex digit address in it is listed, so JSON arrays, CSV and plain text feeds
+/// need no parser of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScamFeed {
+    /// Shown as the source of the addresses it lists
+    pub name: String,
+    pub url: String,
+}
+
+impl ScamFeed {
+    /// A feed named after `url`'s host
+    pub fn from_url(url: &str) -> Self {
+        let url = url.trim().to_string();
+        let name = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split(['/', ':', '?']).next())
+            .filter(|host| !host.is_empty())
+            .unwrap_or(&url)
+            .to_string();
+        Self { name, url }
+    }
+}
+
+/// An address in a tool's input or output that a scam list names
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScamWarning {
+    pub address: Address,
+    pub sources: Vec<String>,
+    /// `input` or `output`
+    pub found_in: &'static str,
+}
+
+/// Known scam and phishing addresses, from periodically refreshed feeds and any
+/// addresses added directly. A feed that fails to refresh keeps its last listing.
+pub struct ScamList {
+    http: reqwest::Client,
+    feeds: Vec<ScamFeed>,
+    allow_override: bool,
+    /// Addresses listed by each source
+    listed: RwLock<HashMap<String, HashSet<Address>>>,
+}
+
+impl ScamList {
+    pub fn new(feeds: Vec<ScamFeed>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(FEED_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            feeds,
+            allow_override: false,
+            listed: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Let calls pass `override_scam_list` to pay listed addresses anyway
+    pub fn with_override_allowed(mut self, allow: bool) -> Self {
+        self.allow_override = allow;
+        self
+    }
+
+    /// List `addresses` under `source`, alongside what the feeds list
+    pub fn with_addresses(
+        self,
+        source: &str,
+        addresses: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.listed
+            .write()
+            .unwrap()
+            .entry(source.to_string())
+            .or_default()
+            .extend(addresses);
+        self
+    }
+
+    /// Sources listing `address`; empty when it is not listed
+    pub fn sources(&self, address: Address) -> Vec<String> {
+        let mut sources: Vec<String> = self
+            .listed
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, addresses)| addresses.contains(&address))
+            .map(|(source, _)| source.clone())
+            .collect();
+        sources.sort();
+        sources
+    }
+
+    /// Addresses listed across all sources
+    pub fn len(&self) -> usize {
+        let listed = self.listed.read().unwrap();
+        listed.values().flatten().collect::<HashSet<_>>().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetch `feed` and replace its listing; returns how many addresses it lists
+    pub async fn refresh_feed(&self, feed: &ScamFeed) -> Result<usize> {
+        let body = self
+            .http
+            .get(&feed.url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch scam list {}", feed.name))?
+            .error_for_status()
+            .with_context(|| format!("Scam list {} request failed", feed.name))?
+            .text()
+            .await
+            .with_context(|| format!("Invalid scam list {} response", feed.name))?;
+        let addresses = addresses_in(&body);
+        let count = addresses.len();
+        self.listed
+            .write()
+            .unwrap()
+            .insert(feed.name.clone(), addresses);
+        Ok(count)
+    }
+
+    /// Fetch every feed, keeping the last listing of any that fail
+    pub async fn refresh(&self) {
+        for feed in &self.feeds {
+            match self.refresh_feed(feed).await {
+                Ok(count) => info!("Scam list {} lists {} addresses", feed.name, count),
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+    }
+
+    /// Refresh the feeds now and then every `every` until `stop` is set
+    pub fn spawn(
+        self: Arc<Self>,
+        every: Duration,
+        mut stop: watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => self.refresh().await,
+                    // Set, or the server that started the job is gone
+                    _ = stop.changed() => break,
+                }
+            }
+        })
+    }
+
+    /// Refuse to pay any listed address in `addresses`, unless the call overrides the
+    /// block and the server's policy allows that
+    pub fn ensure_unlisted(&self, addresses: &[Address], override_requested: bool) -> Result<()> {
+        if override_requested && !self.allow_override {
+            anyhow::bail!(
+                "override_scam_list is not allowed on this server; set ALLOW_SCAM_LIST_OVERRIDE=true"
+            );
+        }
+        if override_requested {
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        let listed: Vec<String> = addresses
+            .iter()
+            .filter(|address| seen.insert(**address))
+            .filter_map(|address| {
+                let sources = self.sources(*address);
+                (!sources.is_empty())
+                    .then(|| format!("{:?} (listed by {})", address, sources.join(", ")))
+            })
+            .collect();
+        if listed.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Blocked: {} {} on a scam address list",
+            listed.join(", "),
+            if listed.len() == 1 { "is" } else { "are" }
+        )
+    }
+
+    /// Listed addresses mentioned anywhere in a tool call's `input` or `output`
+    pub fn warnings(&self, input: &Value, output: &Value) -> Vec<ScamWarning> {
+        let mut warnings = Vec::new();
+        for (found_in, value) in [("input", input), ("output", output)] {
+            let mut found = HashSet::new();
+            collect_addresses(value, &mut found);
+            let mut found: Vec<Address> = found.into_iter().collect();
+            found.sort();
+            for address in found {
+                let sources = self.sources(address);
+                if !sources.is_empty() {
+                    warnings.push(ScamWarning {
+                        address,
+                        sources,
+                        found_in,
+                    });
+                }
+            }
+        }
+        warnings
+    }
+}
+
+/// Every 0x-prefixed 40 hex digit address in `text` that is not part of a longer hex string
+fn addresses_in(text: &str) -> HashSet<Address> {
+    let bytes = text.as_bytes();
+    let mut found = HashSet::new();
+    let mut start = 0;
+    while let Some(offset) = text[start..].find("0x") {
+        let at = start + offset;
+        start = at + 2;
+        if at > 0 && bytes[at - 1].is_ascii_alphanumeric() {
+            continue;
+        }
+        let digits = bytes[at + 2..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_hexdigit())
+            .count();
+        if digits == 40 {
+            if let Ok(address) = text[at..at + 42].parse() {
+                found.insert(address);
+            }
+        }
+        start += digits;
+    }
+    found
+}
+
+/// Addresses in `value`'s strings and object keys
+fn collect_addresses(value: &Value, found: &mut HashSet<Address>) {
+    match value {
+        Value::String(text) => found.extend(addresses_in(text)),
+        Value::Array(items) => items.iter().for_each(|item| collect_addresses(item, found)),
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                found.extend(addresses_in(key));
+                collect_addresses(field, found);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,243 @@
+use super::freshness::{freshness_schema_properties, Freshness, FreshnessParams};
+use super::units::{from_base_units, to_base_units};
+use super::{merge_schema_properties, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, PriceVenue};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Wrapped Ether on Ethereum mainnet, used as the default quote token and to price gas
+pub const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+const DEFAULT_MIN_SPREAD_BPS: u32 = 30;
+
+pub struct ScanArbitrageTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    venues: Vec<Arc<dyn PriceVenue>>,
+}
+
+impl<C: EthereumClientTrait> ScanArbitrageTool<C> {
+    pub fn new(client: Arc<C>, venues: Vec<Arc<dyn PriceVenue>>) -> Self {
+        Self { client, venues }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanArbitrageParams {
+    token: String,
+    quote_token: Option<String>,
+    amount: Option<String>,
+    min_spread_bps: Option<u32>,
+    #[serde(flatten)]
+    freshness: FreshnessParams,
+}
+
+#[derive(Debug, Serialize)]
+struct VenueQuote {
+    venue: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Opportunity {
+    buy_venue: String,
+    sell_venue: String,
+    spread_bps: String,
+    gross_profit: String,
+    gas_cost: String,
+    net_profit: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ScanArbitrageResult {
+    token: String,
+    quote_token: String,
+    amount: String,
+    min_spread_bps: u32,
+    gas_price_gwei: String,
+    quotes: Vec<VenueQuote>,
+    opportunities: Vec<Opportunity>,
+    #[serde(flatten)]
+    freshness: Freshness,
+}
+
+/// Spread between two quotes for the same input, in basis points of the lower one
+pub fn spread_bps(low: Decimal, high: Decimal) -> Decimal {
+    if low.is_zero() {
+        return Decimal::ZERO;
+    }
+    ((high - low) / low * Decimal::from(10_000)).round_dp(2)
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for ScanArbitrageTool<C> {
+    fn name(&self) -> &str {
+        "scan_arbitrage"
+    }
+
+    fn description(&self) -> &str {
+        "Compare what a token sells for across Uniswap V2, Uniswap V3, SushiSwap and Curve, and report venue pairs whose spread exceeds a threshold after estimated gas. Read-only analysis: no transaction is ever sent."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "token": {
+                "type": "string",
+                "description": "Address of the token to scan"
+            },
+            "quote_token": {
+                "type": "string",
+                "description": "Address of the token prices are quoted in (default: WETH)"
+            },
+            "amount": {
+                "type": "string",
+                "description": "Amount of the token to quote, in human units (default: 1)"
+            },
+            "min_spread_bps": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Smallest spread worth reporting, in basis points (default: 30)"
+            }
+        });
+        merge_schema_properties(&mut properties, freshness_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["token"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: ScanArbitrageParams =
+            serde_json::from_value(params).context("Invalid parameters for scan_arbitrage")?;
+
+        let token: Address = params.token.parse().context("Invalid token address")?;
+        let weth: Address = WETH.parse().context("Invalid WETH address")?;
+        let quote_token: Address = match &params.quote_token {
+            Some(addr) => addr.parse().context("Invalid quote_token address")?,
+            None => weth,
+        };
+        if token == quote_token {
+            anyhow::bail!("token and quote_token must differ");
+        }
+        let amount: Decimal = params
+            .amount
+            .as_deref()
+            .unwrap_or("1")
+            .parse()
+            .context("Invalid amount")?;
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("Amount must be positive");
+        }
+        let min_spread_bps = params.min_spread_bps.unwrap_or(DEFAULT_MIN_SPREAD_BPS);
+
+        let freshness = params.freshness.check(self.client.as_ref()).await?;
+        let token_decimals = self.client.get_token_decimals(token).await? as u32;
+        let quote_decimals = self.client.get_token_decimals(quote_token).await? as u32;
+        let amount_in = to_base_units(amount, token_decimals)?;
+
+        // A venue without a pool for the pair is reported, not fatal
+        let mut quotes = Vec::new();
+        let mut priced: Vec<(&Arc<dyn PriceVenue>, Decimal)> = Vec::new();
+        for venue in &self.venues {
+            let outcome = venue
+                .quote(token, quote_token, amount_in)
+                .await
+                .and_then(|out| from_base_units(out, quote_decimals));
+            match outcome {
+                Ok(out) if !out.is_zero() => {
+                    priced.push((venue, out));
+                    quotes.push(VenueQuote {
+                        venue: venue.name().to_string(),
+                        amount_out: Some(out.normalize().to_string()),
+                        error: None,
+                    });
+                }
+                Ok(_) => quotes.push(VenueQuote {
+                    venue: venue.name().to_string(),
+                    amount_out: None,
+                    error: Some("Venue returned a zero quote".to_string()),
+                }),
+                Err(e) => quotes.push(VenueQuote {
+                    venue: venue.name().to_string(),
+                    amount_out: None,
+                    error: Some(e.to_string()),
+                }),
+            }
+        }
+
+        // Gas is paid in ETH, so price one ETH in the quote token
+        let gas_price = self.client.get_gas_price().await?;
+        let eth_in_quote = if quote_token == weth {
+            Decimal::ONE
+        } else {
+            let one_eth = to_base_units(Decimal::ONE, 18)?;
+            let mut rate = None;
+            for venue in &self.venues {
+                if let Ok(out) = venue.quote(weth, quote_token, one_eth).await {
+                    rate = Some(from_base_units(out, quote_decimals)?);
+                    break;
+                }
+            }
+            rate.context("Could not price gas: no venue quotes WETH to the quote token")?
+        };
+
+        let mut opportunities = Vec::new();
+        for (buy, buy_out) in &priced {
+            for (sell, sell_out) in &priced {
+                if sell_out <= buy_out {
+                    continue;
+                }
+                let spread = spread_bps(*buy_out, *sell_out);
+                if spread < Decimal::from(min_spread_bps) {
+                    continue;
+                }
+                let gas_units = U256::from(buy.swap_gas_units() + sell.swap_gas_units());
+                let gas_cost =
+                    from_base_units(gas_units.saturating_mul(gas_price), 18)? * eth_in_quote;
+                let gross = *sell_out - *buy_out;
+                let net = gross - gas_cost;
+                if net <= Decimal::ZERO {
+                    continue;
+                }
+                opportunities.push((
+                    net,
+                    Opportunity {
+                        buy_venue: buy.name().to_string(),
+                        sell_venue: sell.name().to_string(),
+                        spread_bps: spread.normalize().to_string(),
+                        gross_profit: gross.normalize().to_string(),
+                        gas_cost: gas_cost.round_dp(8).normalize().to_string(),
+                        net_profit: net.round_dp(8).normalize().to_string(),
+                    },
+                ));
+            }
+        }
+        opportunities.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let result = ScanArbitrageResult {
+            token: params.token,
+            quote_token: format!("{:?}", quote_token),
+            amount: amount.to_string(),
+            min_spread_bps,
+            gas_price_gwei: from_base_units(gas_price, 9)?.normalize().to_string(),
+            quotes,
+            opportunities: opportunities.into_iter().map(|(_, o)| o).collect(),
+            freshness,
+        };
+
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,256 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::payment_requests::unix_now;
+use super::scam_list::ScamList;
+use super::schedules::{PaymentSchedules, Recurrence, ScheduleStatus, ScheduledPayment};
+use super::units::to_base_units;
+use super::{ExecutePlanTool, Notional, PermissionTier, Tool};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Upcoming runs shown with a new or listed schedule by default
+pub const DEFAULT_SCHEDULE_PREVIEW_RUNS: usize = 5;
+
+// A start this far in the past is a mistake rather than "now"
+const MAX_START_LAG_SECS: u64 = 60;
+
+pub struct SchedulePaymentTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    schedules: Arc<PaymentSchedules>,
+    execution_enabled: bool,
+    book: Option<Arc<AddressBook>>,
+    scams: Option<Arc<ScamList>>,
+    // Asks for the same go-ahead as a live transfer in execute_plan
+    confirmer: ExecutePlanTool<C>,
+}
+
+impl<C: EthereumClientTrait> SchedulePaymentTool<C> {
+    /// Without `execution_enabled` the scheduler cannot send, so nothing can be scheduled
+    pub fn new(client: Arc<C>, schedules: Arc<PaymentSchedules>, execution_enabled: bool) -> Self {
+        Self {
+            confirmer: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            schedules,
+            execution_enabled,
+            book: None,
+            scams: None,
+        }
+    }
+
+    /// Accept a contact name as the recipient
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.confirmer = self.confirmer.with_address_book(Arc::clone(&book));
+        self.book = Some(book);
+        self
+    }
+
+    /// Refuse to schedule payments to addresses on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.scams = Some(scams);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SchedulePaymentParams {
+    to: String,
+    amount: String,
+    /// ERC20 to pay in; ETH when omitted
+    #[serde(default)]
+    token: Option<String>,
+    recurrence: Recurrence,
+    /// First run, as a unix timestamp; the scheduler's next check when omitted
+    #[serde(default)]
+    start_at: Option<u64>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    max_runs: Option<u32>,
+    #[serde(default)]
+    max_total: Option<String>,
+    #[serde(default)]
+    end_at: Option<u64>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for SchedulePaymentTool<C> {
+    fn name(&self) -> &str {
+        "schedule_payment"
+    }
+
+    fn description(&self) -> &str {
+        "Schedule a recurring transfer of ETH or an ERC20, such as weekly payroll or a monthly subscription. The server sends each run when it falls due, until the schedule's max_runs, max_total or end_at limit is reached, and audits every execution. Returns the schedule with its next run times. Needs live execution enabled on the server; manage schedules with list_scheduled_payments, pause_scheduled_payment and resume_scheduled_payment."
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "to": {
+                    "type": "string",
+                    "description": "Recipient address or contact name"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Amount paid on every run in whole tokens, e.g. '1500'"
+                },
+                "token": {
+                    "type": "string",
+                    "description": "ERC20 token address to pay in; omit for ETH"
+                },
+                "recurrence": {
+                    "type": "string",
+                    "enum": ["daily", "weekly", "monthly"],
+                    "description": "How often to pay; monthly runs fall on the start's day of the month, or the month's last day when it is shorter"
+                },
+                "start_at": {
+                    "type": "integer",
+                    "description": "First run as a unix timestamp (default: now)"
+                },
+                "label": {
+                    "type": "string",
+                    "description": "Name for the schedule, e.g. 'Payroll: Alice'"
+                },
+                "max_runs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Stop after this many runs"
+                },
+                "max_total": {
+                    "type": "string",
+                    "description": "Stop before the schedule would pay more than this in total"
+                },
+                "end_at": {
+                    "type": "integer",
+                    "description": "No runs after this unix timestamp"
+                }
+            },
+            "required": ["to", "amount", "recurrence"]
+        })
+    }
+
+    // Scheduling commits to live transfers, so it gets their confirmation
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        self.confirmer.confirmation_prompt(&json!({
+            "dry_run": false,
+            "plan": {
+                "steps": [{ "action": "transfer", "to": params.get("to")? }]
+            }
+        }))
+    }
+
+    // Every run the schedule sends is authorized here
+    fn moves_funds(&self, _params: &Value) -> bool {
+        true
+    }
+
+    // Each run's amount
+    fn notional(&self, params: &Value) -> Option<Notional> {
+        Notional::from_params(params, "token", "amount")
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: SchedulePaymentParams =
+            serde_json::from_value(params).context("Invalid parameters for schedule_payment")?;
+        if !self.execution_enabled {
+            anyhow::bail!("Scheduled payments need live execution, which is disabled on this server (ENABLE_EXECUTION)");
+        }
+
+        let to = resolve_address(self.book.as_deref(), &params.to).context("Invalid recipient")?;
+        if to.is_zero() {
+            anyhow::bail!("The zero address cannot be paid");
+        }
+        if let Some(scams) = &self.scams {
+            scams.ensure_unlisted(&[to], false)?;
+        }
+        let token: Option<Address> = params
+            .token
+            .as_deref()
+            .map(|token| token.parse().context("Invalid token address"))
+            .transpose()?;
+        if Some(to) == token {
+            anyhow::bail!("A token contract cannot be paid in its own token");
+        }
+        let (symbol, decimals) = match token {
+            Some(token) => (
+                self.client
+                    .get_token_symbol(token)
+                    .await
+                    .unwrap_or_else(|_| "UNKNOWN".to_string()),
+                self.client
+                    .get_token_decimals(token)
+                    .await
+                    .context("Failed to read token decimals")?,
+            ),
+            None => ("ETH".to_string(), 18),
+        };
+
+        let amount = Decimal::from_str(params.amount.trim()).context("Invalid amount")?;
+        if amount <= Decimal::ZERO {
+            anyhow::bail!("amount must be positive");
+        }
+        if amount.normalize().scale() > decimals as u32 {
+            anyhow::bail!(
+                "amount has more than the {} decimals {} supports",
+                decimals,
+                symbol
+            );
+        }
+        to_base_units(amount, decimals as u32)?;
+
+        let now = unix_now();
+        let start_at = params.start_at.unwrap_or(now);
+        if start_at + MAX_START_LAG_SECS < now {
+            anyhow::bail!("start_at is in the past");
+        }
+        if params.max_runs == Some(0) {
+            anyhow::bail!("max_runs must be at least 1");
+        }
+        let max_total = params
+            .max_total
+            .as_deref()
+            .map(|total| Decimal::from_str(total.trim()).context("Invalid max_total"))
+            .transpose()?;
+        if max_total.is_some_and(|total| total < amount) {
+            anyhow::bail!("max_total is less than a single run's amount");
+        }
+        if params.end_at.is_some_and(|end_at| end_at < start_at) {
+            anyhow::bail!("end_at is before start_at");
+        }
+
+        let schedule = self.schedules.add(ScheduledPayment {
+            schedule_id: String::new(),
+            label: params.label,
+            to,
+            token,
+            symbol,
+            decimals,
+            amount: amount.normalize(),
+            recurrence: params.recurrence,
+            start_at,
+            max_runs: params.max_runs,
+            max_total: max_total.map(|total| total.normalize()),
+            end_at: params.end_at,
+            status: ScheduleStatus::Active,
+            runs: 0,
+            paid_total: Decimal::ZERO,
+            occurrence: 0,
+            next_run: None,
+            created_at: now,
+        })?;
+
+        Ok(serde_json::to_value(
+            schedule.preview(DEFAULT_SCHEDULE_PREVIEW_RUNS),
+        )?)
+    }
+}
@@ -0,0 +1,157 @@
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::payment_requests::unix_now;
+use super::recipients::RecipientHistory;
+use super::scam_list::ScamList;
+use super::schedules::{PaymentSchedules, RunOutcome, ScheduleRun, ScheduledPayment};
+use super::units::to_base_units;
+use super::{ExecutePlanTool, Tool};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, Plan, PlanStep};
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// How often the scheduler looks for due payments by default
+pub const DEFAULT_SCHEDULE_TICK: Duration = Duration::from_secs(30);
+
+/// Sends the scheduled payments that have fallen due, auditing every run
+pub struct PaymentScheduler<C: EthereumClientTrait> {
+    schedules: Arc<PaymentSchedules>,
+    executor: ExecutePlanTool<C>,
+}
+
+impl<C: EthereumClientTrait + 'static> PaymentScheduler<C> {
+    /// Schedules only send when `execution_enabled`; otherwise every run fails
+    pub fn new(client: Arc<C>, schedules: Arc<PaymentSchedules>, execution_enabled: bool) -> Self {
+        Self {
+            schedules,
+            executor: ExecutePlanTool::new(client, execution_enabled),
+        }
+    }
+
+    /// Record scheduled recipients as paid
+    pub fn with_recipient_history(mut self, recipients: Arc<RecipientHistory>) -> Self {
+        self.executor = self.executor.with_recipient_history(recipients);
+        self
+    }
+
+    /// Fail runs that would pay an address listed on `scams` since it was scheduled
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.executor = self.executor.with_scam_list(scams);
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH scheduled payments must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+
+    /// Send every payment due by `now`, one run per schedule, and return the audited runs
+    pub async fn run_due(&self, now: u64) -> Vec<ScheduleRun> {
+        let mut runs = Vec::new();
+        for schedule in self.schedules.due(now) {
+            let outcome = self.pay(&schedule).await;
+            let mut run = ScheduleRun {
+                schedule_id: schedule.schedule_id.clone(),
+                scheduled_for: schedule.next_run.unwrap_or(now),
+                executed_at: now,
+                to: schedule.to,
+                token: schedule.token,
+                symbol: schedule.symbol.clone(),
+                amount: schedule.amount,
+                outcome: RunOutcome::Sent,
+                tx_hash: None,
+                error: None,
+                missed: 0,
+            };
+            match outcome {
+                Ok(tx_hash) => {
+                    info!(
+                        "Scheduled payment {} sent {} {} to {:?}",
+                        schedule.schedule_id, schedule.amount, schedule.symbol, schedule.to
+                    );
+                    run.tx_hash = Some(tx_hash);
+                }
+                Err(e) => {
+                    warn!("Scheduled payment {} failed: {:#}", schedule.schedule_id, e);
+                    run.outcome = RunOutcome::Failed;
+                    run.error = Some(format!("{:#}", e));
+                }
+            }
+            match self.schedules.record(run, now) {
+                Ok(run) => runs.push(run),
+                Err(e) => warn!(
+                    "Failed to record scheduled payment {}: {:#}",
+                    schedule.schedule_id, e
+                ),
+            }
+        }
+        runs
+    }
+
+    // Send one run's transfer, returning its transaction hash
+    async fn pay(&self, schedule: &ScheduledPayment) -> Result<String> {
+        let mut plan = Plan::default();
+        plan.steps.push(PlanStep::Transfer {
+            token: schedule.token,
+            to: schedule.to,
+            amount: to_base_units(schedule.amount, schedule.decimals as u32)?,
+        });
+        // The recipient was confirmed when the schedule was created
+        let result = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": false,
+                "confirm_new_recipients": true,
+            }))
+            .await?;
+        let step = &result["steps"][0];
+        match step["tx_hash"].as_str() {
+            Some(tx_hash) if step["status"] == "confirmed" => Ok(tx_hash.to_string()),
+            _ => anyhow::bail!(
+                "{}",
+                step["error"]
+                    .as_str()
+                    .unwrap_or("transfer was not confirmed")
+            ),
+        }
+    }
+
+    /// Check for due payments every `tick` until `stop` is set. A check in progress
+    /// finishes first, so no payment is left half sent.
+    pub fn spawn(
+        self: Arc<Self>,
+        tick: Duration,
+        mut stop: watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        self.run_due(unix_now()).await;
+                    }
+                    // Set, or the server that started the job is gone
+                    _ = stop.changed() => break,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,373 @@
+use crate::time::{civil_from_days, days_from_civil};
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Runs kept in memory for list_scheduled_payments; the audit log file keeps them all
+const MAX_RECENT_RUNS: usize = 500;
+
+/// How often a scheduled payment repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    /// Same day of the month, or the month's last day when it is shorter
+    Monthly,
+}
+
+impl Recurrence {
+    /// Time of the `n`th run of a schedule whose first run is at `start`
+    pub fn occurrence(self, start: u64, n: u32) -> u64 {
+        match self {
+            Recurrence::Daily => start + n as u64 * SECS_PER_DAY,
+            Recurrence::Weekly => start + n as u64 * 7 * SECS_PER_DAY,
+            Recurrence::Monthly => add_months(start, n),
+        }
+    }
+}
+
+fn add_months(at: u64, months: u32) -> u64 {
+    let (year, month, day) = civil_from_days((at / SECS_PER_DAY) as i64);
+    let index = year * 12 + (month as i64 - 1) + months as i64;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    let day = day.min(days_in_month(year, month));
+    days_from_civil(year, month, day) as u64 * SECS_PER_DAY + at % SECS_PER_DAY
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    Active,
+    Paused,
+    /// A limit was reached; the schedule will not run again
+    Completed,
+}
+
+/// A transfer repeated on a calendar until one of its limits is reached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledPayment {
+    pub schedule_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub to: Address,
+    /// `None` for ETH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<Address>,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Paid on every run
+    pub amount: Decimal,
+    pub recurrence: Recurrence,
+    pub start_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runs: Option<u32>,
+    /// Most the schedule may pay over its lifetime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total: Option<Decimal>,
+    /// No runs after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<u64>,
+    pub status: ScheduleStatus,
+    /// Executions so far, failed ones included
+    pub runs: u32,
+    pub paid_total: Decimal,
+    /// Index of the next run in the recurrence
+    pub occurrence: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<u64>,
+    pub created_at: u64,
+}
+
+impl ScheduledPayment {
+    /// Times of up to `count` upcoming runs, within the schedule's limits
+    pub fn upcoming(&self, count: usize) -> Vec<u64> {
+        if self.status == ScheduleStatus::Completed {
+            return Vec::new();
+        }
+        let mut remaining = match self.max_runs {
+            Some(max_runs) => max_runs.saturating_sub(self.runs) as usize,
+            None => usize::MAX,
+        };
+        if let Some(max_total) = self.max_total {
+            let affordable = ((max_total - self.paid_total) / self.amount).floor();
+            remaining = remaining.min(affordable.to_usize().unwrap_or(0));
+        }
+        (self.occurrence..)
+            .map(|n| self.recurrence.occurrence(self.start_at, n))
+            .take_while(|at| self.end_at.is_none_or(|end_at| *at <= end_at))
+            .take(remaining.min(count))
+            .collect()
+    }
+
+    /// The schedule with its next `count` run times
+    pub fn preview(&self, count: usize) -> ScheduleView {
+        ScheduleView {
+            next_runs: self.upcoming(count),
+            schedule: self.clone(),
+        }
+    }
+
+    // Move past every run due by `now`, returning how many were missed besides
+    // the one just executed, and complete the schedule once its limits are hit
+    fn advance(&mut self, now: u64) -> u32 {
+        let first = self.occurrence;
+        while self.recurrence.occurrence(self.start_at, self.occurrence) <= now {
+            self.occurrence += 1;
+        }
+        self.refresh();
+        (self.occurrence - first).saturating_sub(1)
+    }
+
+    fn refresh(&mut self) {
+        self.next_run = self.upcoming(1).first().copied();
+        if self.next_run.is_none() {
+            self.status = ScheduleStatus::Completed;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleView {
+    #[serde(flatten)]
+    pub schedule: ScheduledPayment,
+    pub next_runs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Sent,
+    Failed,
+}
+
+/// Audit record of one execution of a schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub schedule_id: String,
+    pub scheduled_for: u64,
+    pub executed_at: u64,
+    pub to: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<Address>,
+    pub symbol: String,
+    pub amount: Decimal,
+    pub outcome: RunOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Earlier runs that fell due while the server was down; they are not paid
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub missed: u32,
+}
+
+fn is_zero(missed: &u32) -> bool {
+    *missed == 0
+}
+
+/// Recurring payments shared between the scheduling tools and the scheduler.
+///
+/// With a backing file, schedules survive restarts. Every execution is appended to
+/// the audit log file when one is set, and the latest are kept in memory.
+#[derive(Debug, Default)]
+pub struct PaymentSchedules {
+    path: Option<PathBuf>,
+    schedules: RwLock<Vec<ScheduledPayment>>,
+    audit_path: Option<PathBuf>,
+    recent_runs: Mutex<VecDeque<ScheduleRun>>,
+}
+
+impl PaymentSchedules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the schedules saved at `path`, if it exists
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let mut schedules = Self::new();
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read schedules {}", path.display()))?;
+            schedules.schedules = RwLock::new(
+                serde_json::from_str(&raw)
+                    .with_context(|| format!("Invalid schedules {}", path.display()))?,
+            );
+        }
+        schedules.path = Some(path);
+        Ok(schedules)
+    }
+
+    /// Append every run to the JSON lines file at `path`, starting from the runs
+    /// already recorded there
+    pub fn with_audit_log(mut self, path: PathBuf) -> Result<Self> {
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read audit log {}", path.display()))?;
+            let mut recent = VecDeque::new();
+            for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+                let run: ScheduleRun = serde_json::from_str(line)
+                    .with_context(|| format!("Invalid audit log {}", path.display()))?;
+                if recent.len() == MAX_RECENT_RUNS {
+                    recent.pop_front();
+                }
+                recent.push_back(run);
+            }
+            self.recent_runs = Mutex::new(recent);
+        }
+        self.audit_path = Some(path);
+        Ok(self)
+    }
+
+    /// Store `schedule` under a fresh id
+    pub fn add(&self, mut schedule: ScheduledPayment) -> Result<ScheduledPayment> {
+        schedule.schedule_id = format!("sch_{}", hex::encode(H64::random().as_bytes()));
+        schedule.refresh();
+        self.write().push(schedule.clone());
+        self.save()?;
+        Ok(schedule)
+    }
+
+    pub fn get(&self, id: &str) -> Result<ScheduledPayment> {
+        self.read()
+            .iter()
+            .find(|schedule| schedule.schedule_id == id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown schedule: {}", id))
+    }
+
+    /// All schedules, oldest first
+    pub fn list(&self) -> Vec<ScheduledPayment> {
+        self.read().clone()
+    }
+
+    /// Pause or resume a schedule. Runs that fell due while it was paused are skipped.
+    pub fn set_paused(&self, id: &str, paused: bool, now: u64) -> Result<ScheduledPayment> {
+        let schedule = {
+            let mut schedules = self.write();
+            let schedule = schedules
+                .iter_mut()
+                .find(|schedule| schedule.schedule_id == id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown schedule: {}", id))?;
+            match (schedule.status, paused) {
+                (ScheduleStatus::Completed, _) => {
+                    anyhow::bail!("Schedule {} has completed", id)
+                }
+                (ScheduleStatus::Active, true) => schedule.status = ScheduleStatus::Paused,
+                (ScheduleStatus::Paused, false) => {
+                    schedule.status = ScheduleStatus::Active;
+                    while schedule
+                        .recurrence
+                        .occurrence(schedule.start_at, schedule.occurrence)
+                        < now
+                    {
+                        schedule.occurrence += 1;
+                    }
+                    schedule.refresh();
+                }
+                _ => {}
+            }
+            schedule.clone()
+        };
+        self.save()?;
+        Ok(schedule)
+    }
+
+    /// Active schedules with a run due by `now`
+    pub fn due(&self, now: u64) -> Vec<ScheduledPayment> {
+        self.read()
+            .iter()
+            .filter(|schedule| {
+                schedule.status == ScheduleStatus::Active
+                    && schedule.next_run.is_some_and(|at| at <= now)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Count `run` against its schedule, advance the schedule past `now` and audit the run
+    pub fn record(&self, mut run: ScheduleRun, now: u64) -> Result<ScheduleRun> {
+        {
+            let mut schedules = self.write();
+            let schedule = schedules
+                .iter_mut()
+                .find(|schedule| schedule.schedule_id == run.schedule_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown schedule: {}", run.schedule_id))?;
+            schedule.runs += 1;
+            if run.outcome == RunOutcome::Sent {
+                schedule.paid_total += run.amount;
+            }
+            run.missed = schedule.advance(now);
+        }
+
+        {
+            let mut recent = self.recent_runs.lock().unwrap_or_else(|e| e.into_inner());
+            if recent.len() == MAX_RECENT_RUNS {
+                recent.pop_front();
+            }
+            recent.push_back(run.clone());
+        }
+        if let Some(path) = &self.audit_path {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+            writeln!(file, "{}", serde_json::to_string(&run)?)
+                .with_context(|| format!("Failed to write audit log {}", path.display()))?;
+        }
+        self.save()?;
+        Ok(run)
+    }
+
+    /// Latest runs first, of one schedule or all of them
+    pub fn runs(&self, schedule_id: Option<&str>, limit: usize) -> Vec<ScheduleRun> {
+        self.recent_runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .filter(|run| schedule_id.is_none_or(|id| run.schedule_id == id))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.list())?;
+        // Write beside the file and rename so a crash never leaves it truncated
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, json)
+            .with_context(|| format!("Failed to write schedules {}", temp.display()))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("Failed to save schedules {}", path.display()))
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<ScheduledPayment>> {
+        self.schedules.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<ScheduledPayment>> {
+        self.schedules.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// Session that tool calls made outside of any MCP session belong to, e.g. calling
+/// `execute` directly
+pub const LOCAL_SESSION: &str = "local";
+
+tokio::task_local! {
+    static CURRENT_SESSION: String;
+}
+
+/// Session of the tool call running on this task
+pub fn current_session() -> String {
+    CURRENT_SESSION
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| LOCAL_SESSION.to_string())
+}
+
+/// Run `future` as part of `session`, so session-scoped state it touches is that session's
+pub async fn in_session<F: Future>(session: &str, future: F) -> F::Output {
+    CURRENT_SESSION.scope(session.to_string(), future).await
+}
+
+/// Tool state kept per session, dropped when the session ends
+pub trait SessionState: Send + Sync {
+    fn end_session(&self, session: &str);
+}
+
+/// One `T` per session, created on first use
+pub struct SessionMap<T> {
+    values: Mutex<HashMap<String, T>>,
+}
+
+impl<T> Default for SessionMap<T> {
+    fn default() -> Self {
+        Self {
+            values: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Default> SessionMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` on the current session's value
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut values = self.values.lock().unwrap();
+        f(values.entry(current_session()).or_default())
+    }
+
+    /// Sessions holding a value
+    pub fn sessions(&self) -> usize {
+        self.values.lock().unwrap().len()
+    }
+}
+
+impl<T: Send> SessionState for SessionMap<T> {
+    fn end_session(&self, session: &str) {
+        self.values.lock().unwrap().remove(session);
+    }
+}
@@ -0,0 +1,340 @@
+use super::address_book::{resolve_address, resolve_plan_addresses, AddressBook};
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::{
+    asset_transfers, balance_changes, event_signature, native_transfer_address,
+    EthereumClientTrait, Plan,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Swap steps get the same deadline execute_plan would give them
+const SWAP_DEADLINE_SECS: u64 = 20 * 60;
+
+// Bundles longer than this are refused
+const MAX_BUNDLE_TRANSACTIONS: usize = 32;
+
+pub struct SimulateBundleTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> SimulateBundleTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, book: None }
+    }
+
+    /// Accept contact names as senders, targets and plan recipients
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Symbol and decimals of `token`, or None when it does not report them
+    async fn asset(&self, token: Address) -> Option<(String, u8)> {
+        let symbol = self.client.get_token_symbol(token).await.ok()?;
+        let decimals = self.client.get_token_decimals(token).await.ok()?;
+        Some((symbol, decimals))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulateBundleParams {
+    #[serde(default)]
+    transactions: Option<Vec<BundleTransaction>>,
+    #[serde(default)]
+    plan: Option<Plan>,
+    /// Only report balance changes of these accounts
+    #[serde(default)]
+    accounts: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundleTransaction {
+    /// Defaults to the wallet
+    #[serde(default)]
+    from: Option<String>,
+    to: String,
+    #[serde(default)]
+    data: Option<Bytes>,
+    /// Wei, as a decimal or `0x` hex string
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SimulatedEvent {
+    address: Address,
+    /// Signature of well-known events, such as `Transfer(address,address,uint256)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<&'static str>,
+    topics: Vec<H256>,
+    data: Bytes,
+}
+
+#[derive(Debug, Serialize)]
+struct TransactionReport {
+    index: usize,
+    /// Action of a plan step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<Address>,
+    success: bool,
+    gas_used: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Events the transaction emitted, traced ETH transfers excluded
+    events: Vec<SimulatedEvent>,
+}
+
+#[derive(Debug, Serialize)]
+struct BalanceDiff {
+    account: Address,
+    /// Token contract; absent for ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
+    /// Signed change in whole tokens; absent when the token's decimals are unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change: Option<String>,
+    /// Signed change in base units
+    change_raw: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SimulateBundleResult {
+    /// Every transaction succeeded
+    success: bool,
+    transactions: Vec<TransactionReport>,
+    /// Net balance changes of every account the bundle touches, the wallet's first.
+    /// Gas fees are not included.
+    balance_diffs: Vec<BalanceDiff>,
+    total_gas_used: String,
+    /// `total_gas_used` at the current gas price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_fee_eth: Option<String>,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for SimulateBundleTool<C> {
+    fn name(&self) -> &str {
+        "simulate_bundle"
+    }
+
+    fn description(&self) -> &str {
+        "Simulate a list of transactions, or the steps of a plan, in order on top of the latest block without sending anything. Returns each transaction's success, gas used and emitted events, and the net ETH and ERC20 balance changes of every account involved, the clearest way to show exactly what a bundle will do before running it."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "transactions": {
+                    "type": "array",
+                    "description": "Transactions to run in order. Give this or plan",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "from": { "type": "string", "description": "Sender address or contact name (default: the wallet)" },
+                            "to": { "type": "string", "description": "Target address or contact name" },
+                            "data": { "type": "string", "description": "Calldata as 0x hex (default: empty)" },
+                            "value": { "type": "string", "description": "ETH to send in wei, decimal or 0x hex (default: 0)" }
+                        },
+                        "required": ["to"]
+                    }
+                },
+                "plan": {
+                    "type": "object",
+                    "description": "Plan object with a `steps` array, as taken by execute_plan; its steps are sent from the wallet",
+                    "properties": {
+                        "steps": { "type": "array", "items": { "type": "object" } }
+                    },
+                    "required": ["steps"]
+                },
+                "accounts": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only report balance changes of these addresses or contact names (default: every account)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, mut params: Value) -> Result<Value> {
+        if let Some(plan) = params.get_mut("plan") {
+            resolve_plan_addresses(self.book.as_deref(), plan)?;
+        }
+        let params: SimulateBundleParams =
+            serde_json::from_value(params).context("Invalid parameters for simulate_bundle")?;
+        let book = self.book.as_deref();
+        let wallet = self.client.get_wallet_address();
+
+        let (txs, actions): (Vec<TypedTransaction>, Vec<Option<String>>) =
+            match (params.transactions, params.plan) {
+                (Some(transactions), None) => transactions
+                    .into_iter()
+                    .map(|tx| {
+                        let from = match &tx.from {
+                            Some(from) => resolve_address(book, from)?,
+                            None => wallet,
+                        };
+                        let value = match tx.value.as_deref().map(str::trim) {
+                            None | Some("") => U256::zero(),
+                            Some(hex) if hex.starts_with("0x") => {
+                                U256::from_str_radix(&hex[2..], 16)
+                                    .with_context(|| format!("Invalid value: {}", hex))?
+                            }
+                            Some(dec) => U256::from_dec_str(dec)
+                                .with_context(|| format!("Invalid value: {}", dec))?,
+                        };
+                        let request = TransactionRequest::new()
+                            .from(from)
+                            .to(resolve_address(book, &tx.to)?)
+                            .data(tx.data.unwrap_or_default())
+                            .value(value);
+                        Ok((request.into(), None))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .unzip(),
+                (None, Some(plan)) => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                    let deadline = U256::from(now + SWAP_DEADLINE_SECS);
+                    plan.steps
+                        .iter()
+                        .map(|step| {
+                            Ok((
+                                step.to_transaction(wallet, deadline)?,
+                                Some(step.action().to_string()),
+                            ))
+                        })
+                        .collect::<Result<Vec<_>>>()?
+                        .into_iter()
+                        .unzip()
+                }
+                _ => anyhow::bail!("Give either transactions or plan"),
+            };
+        if txs.is_empty() {
+            anyhow::bail!("The bundle has no transactions");
+        }
+        if txs.len() > MAX_BUNDLE_TRANSACTIONS {
+            anyhow::bail!(
+                "The bundle has {} transactions; at most {} can be simulated at once",
+                txs.len(),
+                MAX_BUNDLE_TRANSACTIONS
+            );
+        }
+        let accounts = params
+            .accounts
+            .map(|accounts| {
+                accounts
+                    .iter()
+                    .map(|account| resolve_address(book, account))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let calls = self.client.simulate_bundle(&txs).await?;
+        if calls.len() != txs.len() {
+            anyhow::bail!(
+                "The simulation returned {} results for {} transactions",
+                calls.len(),
+                txs.len()
+            );
+        }
+
+        let mut transfers = Vec::new();
+        let mut total_gas = U256::zero();
+        let mut reports = Vec::with_capacity(calls.len());
+        for (index, ((tx, action), call)) in txs.iter().zip(actions).zip(&calls).enumerate() {
+            total_gas = total_gas.saturating_add(call.gas_used);
+            transfers.extend(asset_transfers(&call.logs));
+            reports.push(TransactionReport {
+                index,
+                action,
+                from: tx.from().copied().unwrap_or(wallet),
+                to: tx.to_addr().copied(),
+                success: call.success,
+                gas_used: call.gas_used.to_string(),
+                error: call.error.clone(),
+                events: call
+                    .logs
+                    .iter()
+                    .filter(|log| log.address != native_transfer_address())
+                    .map(|log| SimulatedEvent {
+                        address: log.address,
+                        event: log.topics.first().copied().and_then(event_signature),
+                        topics: log.topics.clone(),
+                        data: log.data.clone(),
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut changes = balance_changes(&transfers);
+        if let Some(accounts) = &accounts {
+            changes.retain(|change| accounts.contains(&change.account));
+        }
+        // Stable sort: the wallet's changes first, the rest by account
+        changes.sort_by_key(|change| change.account != wallet);
+
+        let mut assets: HashMap<Address, Option<(String, u8)>> = HashMap::new();
+        let mut balance_diffs = Vec::with_capacity(changes.len());
+        for change in changes {
+            let (falls, amount) = change.net();
+            let sign = if falls { "-" } else { "" };
+            let asset = match change.token {
+                None => Some(("ETH".to_string(), 18)),
+                Some(token) => match assets.get(&token) {
+                    Some(asset) => asset.clone(),
+                    None => {
+                        let asset = self.asset(token).await;
+                        assets.insert(token, asset.clone());
+                        asset
+                    }
+                },
+            };
+            let change_whole = asset
+                .as_ref()
+                .and_then(|(_, decimals)| from_base_units(amount, *decimals as u32).ok())
+                .map(|whole| format!("{}{}", sign, whole));
+            balance_diffs.push(BalanceDiff {
+                account: change.account,
+                token: change.token,
+                symbol: asset.map(|(symbol, _)| symbol),
+                change: change_whole,
+                change_raw: format!("{}{}", sign, amount),
+            });
+        }
+
+        let estimated_fee_eth = match self.client.get_gas_price().await {
+            Ok(price) => from_base_units(total_gas.saturating_mul(price), 18)
+                .ok()
+                .map(|fee| fee.to_string()),
+            Err(_) => None,
+        };
+
+        let result = SimulateBundleResult {
+            success: calls.iter().all(|call| call.success),
+            transactions: reports,
+            balance_diffs,
+            total_gas_used: total_gas.to_string(),
+            estimated_fee_eth,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,72 @@
+use ethers::prelude::*;
+use serde::Serialize;
+
+/// Slippage tolerance, in percent, when neither the call nor an override sets one
+pub const DEFAULT_SLIPPAGE_PERCENT: f64 = 0.5;
+
+/// Where a swap's slippage tolerance came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlippageSource {
+    /// Passed as `slippage_tolerance`
+    Requested,
+    /// Configured for this token pair
+    Pair,
+    /// Configured for one of the two tokens
+    Token,
+    /// The server-wide default
+    Default,
+}
+
+/// Slippage tolerances swaps fall back to when the call names none
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlippageDefaults {
+    /// Percent used when no override matches
+    pub default_percent: f64,
+    /// Percent for swaps between the two tokens, in either direction
+    pub pairs: Vec<(Address, Address, f64)>,
+    /// Percent for any swap into or out of the token, e.g. long-tail tokens
+    pub tokens: Vec<(Address, f64)>,
+}
+
+impl Default for SlippageDefaults {
+    fn default() -> Self {
+        Self {
+            default_percent: DEFAULT_SLIPPAGE_PERCENT,
+            pairs: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+impl SlippageDefaults {
+    /// Tolerance for swapping `from` into `to` when the call passed `requested`. A pair
+    /// override beats token overrides; when both tokens have one, the looser applies.
+    pub fn resolve(
+        &self,
+        requested: Option<f64>,
+        from: Address,
+        to: Address,
+    ) -> (f64, SlippageSource) {
+        if let Some(percent) = requested {
+            return (percent, SlippageSource::Requested);
+        }
+        let pair = self
+            .pairs
+            .iter()
+            .find(|(a, b, _)| (*a == from && *b == to) || (*a == to && *b == from));
+        if let Some((_, _, percent)) = pair {
+            return (*percent, SlippageSource::Pair);
+        }
+        let token = self
+            .tokens
+            .iter()
+            .filter(|(token, _)| *token == from || *token == to)
+            .map(|(_, percent)| *percent)
+            .reduce(f64::max);
+        match token {
+            Some(percent) => (percent, SlippageSource::Token),
+            None => (self.default_percent, SlippageSource::Default),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use ethers::prelude::*;
+
+/// Split `amount_in` across pools in proportion to their input-side `reserves`. The
+/// rounding remainder goes to the deepest pool, so the parts always add up to
+/// `amount_in`; pools without reserves get nothing.
+pub fn split_by_depth(amount_in: U256, reserves: &[U256]) -> Vec<U256> {
+    let total = reserves
+        .iter()
+        .fold(U512::zero(), |total, reserve| total + U512::from(*reserve));
+    if total.is_zero() {
+        return vec![U256::zero(); reserves.len()];
+    }
+
+    let mut parts: Vec<U256> = reserves
+        .iter()
+        .map(|reserve| {
+            // Each part is at most amount_in, so it always fits
+            U256::try_from(amount_in.full_mul(*reserve) / total).unwrap_or(amount_in)
+        })
+        .collect();
+    let assigned = parts
+        .iter()
+        .fold(U256::zero(), |sum, part| sum.saturating_add(*part));
+    if let Some(deepest) = (0..reserves.len()).max_by_key(|&i| reserves[i]) {
+        parts[deepest] += amount_in.saturating_sub(assigned);
+    }
+    parts
+}
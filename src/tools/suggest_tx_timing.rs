@@ -0,0 +1,341 @@
+use super::units::from_base_units;
+use super::{Tool, ToolCategory};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Post-merge mainnet produces a block every 12 second slot
+const BLOCKS_PER_HOUR: u64 = 300;
+const BLOCKS_PER_DAY: u64 = 24 * BLOCKS_PER_HOUR;
+
+// Blocks the recent trend is read from, about ten minutes
+const RECENT_BLOCKS: u64 = 50;
+// Blocks averaged into each sample of an earlier day
+const SAMPLE_BLOCKS: u64 = 10;
+
+const DEFAULT_GAS_UNITS: u64 = 150_000;
+const DEFAULT_MAX_WAIT_HOURS: u32 = 6;
+const MAX_WAIT_HOURS: u32 = 24;
+const DEFAULT_LOOKBACK_DAYS: u32 = 3;
+const MAX_LOOKBACK_DAYS: u32 = 7;
+
+// Waiting is only suggested when it is expected to save at least this much
+const MIN_SAVINGS_PERCENT: Decimal = Decimal::from_parts(10, 0, 0, false, 0);
+// A base fee this far over the recent median is a spike that usually passes in minutes
+const SPIKE_PERCENT: Decimal = Decimal::from_parts(25, 0, 0, false, 0);
+// Recent change beyond which the base fee counts as rising or falling
+const TREND_PERCENT: Decimal = Decimal::from_parts(5, 0, 0, false, 0);
+
+pub struct SuggestTxTimingTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+}
+
+impl<C: EthereumClientTrait> SuggestTxTimingTool<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+
+    /// Median base fee, in gwei, of the `SAMPLE_BLOCKS` blocks from `block` on
+    async fn sample(&self, block: u64) -> Option<Decimal> {
+        let history = self
+            .client
+            .get_base_fee_history(block + SAMPLE_BLOCKS - 1, SAMPLE_BLOCKS)
+            .await
+            .ok()?;
+        median(
+            history
+                .base_fees
+                .iter()
+                .filter_map(|fee| gwei(*fee))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SuggestTxTimingParams {
+    #[serde(default)]
+    gas_units: Option<u64>,
+    #[serde(default)]
+    max_wait_hours: Option<u32>,
+    #[serde(default)]
+    lookback_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Recommendation {
+    SendNow,
+    /// The base fee is spiking; it usually settles within minutes
+    WaitForSpike,
+    /// The base fee is usually lower a few hours from now
+    Wait,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Trend {
+    Rising,
+    Falling,
+    Flat,
+}
+
+#[derive(Debug, Serialize)]
+struct RecentBaseFees {
+    blocks: u64,
+    median_gwei: Decimal,
+    min_gwei: Decimal,
+    max_gwei: Decimal,
+    trend: Trend,
+    /// Change between the first and last tenth of the blocks, in percent
+    change_percent: Decimal,
+}
+
+/// Base fee expected `hours_from_now`, from how it moved at the same time on earlier days
+#[derive(Debug, Serialize)]
+struct HourOutlook {
+    hours_from_now: u32,
+    expected_base_fee_gwei: Decimal,
+    days_sampled: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SuggestTxTimingResult {
+    recommendation: Recommendation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wait_hours: Option<u32>,
+    reason: String,
+    /// Base fee the next block charges
+    current_base_fee_gwei: Decimal,
+    /// Base fee expected when sending as recommended
+    expected_base_fee_gwei: Decimal,
+    estimated_savings_percent: Decimal,
+    /// Native token saved on `gas_units` of gas, before priority fees
+    estimated_savings: Decimal,
+    gas_units: u64,
+    recent: RecentBaseFees,
+    outlook: Vec<HourOutlook>,
+    block_number: u64,
+}
+
+fn gwei(wei: U256) -> Option<Decimal> {
+    from_base_units(wei, 9).ok()
+}
+
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    values.sort();
+    values.get(values.len() / 2).copied()
+}
+
+fn percent_change(from: Decimal, to: Decimal) -> Decimal {
+    if from.is_zero() {
+        return Decimal::ZERO;
+    }
+    ((to - from) / from * Decimal::ONE_HUNDRED)
+        .round_dp(2)
+        .normalize()
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    values.iter().sum::<Decimal>() / Decimal::from(values.len())
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for SuggestTxTimingTool<C> {
+    fn name(&self) -> &str {
+        "suggest_tx_timing"
+    }
+
+    fn description(&self) -> &str {
+        "Advise whether to send a transaction now or wait, for trades that are not time-critical. Reads the recent base fee trend and how the base fee moved over the coming hours on earlier days, then recommends send_now, wait_for_spike (a short-lived spike) or wait (with wait_hours), with the expected base fee and estimated savings for the given gas units. Patterns are historical tendencies, not guarantees."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Heavy
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "gas_units": {
+                    "type": "integer",
+                    "minimum": 21000,
+                    "description": "Gas the transaction uses, to size the savings (default: 150000, a typical swap)"
+                },
+                "max_wait_hours": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_WAIT_HOURS,
+                    "description": "Longest the user is willing to wait, in hours (default: 6)"
+                },
+                "lookback_days": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_LOOKBACK_DAYS,
+                    "description": "Earlier days the daily pattern is read from (default: 3)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: SuggestTxTimingParams =
+            serde_json::from_value(params).context("Invalid parameters for suggest_tx_timing")?;
+        let gas_units = params.gas_units.unwrap_or(DEFAULT_GAS_UNITS);
+        let max_wait_hours = params.max_wait_hours.unwrap_or(DEFAULT_MAX_WAIT_HOURS);
+        if max_wait_hours == 0 || max_wait_hours > MAX_WAIT_HOURS {
+            anyhow::bail!("max_wait_hours must be between 1 and {}", MAX_WAIT_HOURS);
+        }
+        let lookback_days = params.lookback_days.unwrap_or(DEFAULT_LOOKBACK_DAYS);
+        if lookback_days == 0 || lookback_days > MAX_LOOKBACK_DAYS {
+            anyhow::bail!("lookback_days must be between 1 and {}", MAX_LOOKBACK_DAYS);
+        }
+
+        let latest = self.client.get_block_number().await?;
+        let history = self
+            .client
+            .get_base_fee_history(latest, RECENT_BLOCKS)
+            .await?;
+        let recent_fees: Vec<Decimal> = history
+            .base_fees
+            .iter()
+            .filter_map(|fee| gwei(*fee))
+            .collect();
+        let current = gwei(history.next_base_fee).context("Base fee is out of range")?;
+        let recent_median =
+            median(recent_fees.clone()).context("The node returned no base fees")?;
+        let edge = (recent_fees.len() / 10).max(1);
+        let change_percent = percent_change(
+            mean(&recent_fees[..edge]),
+            mean(&recent_fees[recent_fees.len() - edge..]),
+        );
+        let trend = if change_percent > TREND_PERCENT {
+            Trend::Rising
+        } else if change_percent < -TREND_PERCENT {
+            Trend::Falling
+        } else {
+            Trend::Flat
+        };
+        let recent = RecentBaseFees {
+            blocks: recent_fees.len() as u64,
+            median_gwei: recent_median.round_dp(4).normalize(),
+            min_gwei: recent_fees
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or_default()
+                .round_dp(4)
+                .normalize(),
+            max_gwei: recent_fees
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or_default()
+                .round_dp(4)
+                .normalize(),
+            trend,
+            change_percent,
+        };
+
+        // On each earlier day, how the base fee moved from this time of day onwards,
+        // relative to where it started
+        let days = (1..=lookback_days as u64).filter(|day| day * BLOCKS_PER_DAY < latest);
+        let samples = join_all(days.map(|day| async move {
+            let start = latest - day * BLOCKS_PER_DAY;
+            join_all(
+                (0..=max_wait_hours as u64).map(|hour| self.sample(start + hour * BLOCKS_PER_HOUR)),
+            )
+            .await
+        }))
+        .await;
+        let outlook: Vec<HourOutlook> = (1..=max_wait_hours)
+            .filter_map(|hour| {
+                let ratios: Vec<Decimal> = samples
+                    .iter()
+                    .filter_map(|day| match (day[0], day[hour as usize]) {
+                        (Some(start), Some(later)) if !start.is_zero() => Some(later / start),
+                        _ => None,
+                    })
+                    .collect();
+                let days_sampled = ratios.len();
+                // Projected from the recent median, so a momentary spike is not carried forward
+                median(ratios).map(|ratio| HourOutlook {
+                    hours_from_now: hour,
+                    expected_base_fee_gwei: (recent_median * ratio).round_dp(4).normalize(),
+                    days_sampled,
+                })
+            })
+            .collect();
+
+        let spike = current > recent_median * (Decimal::ONE + SPIKE_PERCENT / Decimal::ONE_HUNDRED);
+        let best_hour = outlook
+            .iter()
+            .min_by_key(|hour| hour.expected_base_fee_gwei)
+            .filter(|hour| {
+                percent_change(current, hour.expected_base_fee_gwei) <= -MIN_SAVINGS_PERCENT
+            });
+        let (recommendation, wait_hours, expected, reason) = match best_hour {
+            Some(hour) if !spike || hour.expected_base_fee_gwei < recent_median => (
+                Recommendation::Wait,
+                Some(hour.hours_from_now),
+                hour.expected_base_fee_gwei,
+                format!(
+                    "Over the last {} days the base fee was typically lowest about {} hours after this time of day",
+                    hour.days_sampled, hour.hours_from_now
+                ),
+            ),
+            _ if spike => (
+                Recommendation::WaitForSpike,
+                None,
+                recent_median,
+                format!(
+                    "The base fee is more than {}% above its median over the last {} blocks; spikes like this usually settle within minutes",
+                    SPIKE_PERCENT, recent.blocks
+                ),
+            ),
+            _ => (
+                Recommendation::SendNow,
+                None,
+                current,
+                if outlook.is_empty() {
+                    "No earlier days could be sampled, and the base fee is not spiking".to_string()
+                } else {
+                    format!(
+                        "Waiting up to {} hours is not expected to save {}% or more",
+                        max_wait_hours, MIN_SAVINGS_PERCENT
+                    )
+                },
+            ),
+        };
+        let saved_gwei = (current - expected).max(Decimal::ZERO);
+        let estimated_savings = (saved_gwei * Decimal::from(gas_units)
+            / Decimal::from(1_000_000_000u64))
+        .round_dp(9)
+        .normalize();
+
+        let result = SuggestTxTimingResult {
+            recommendation,
+            wait_hours,
+            reason,
+            current_base_fee_gwei: current.round_dp(4).normalize(),
+            expected_base_fee_gwei: expected.round_dp(4).normalize(),
+            estimated_savings_percent: (-percent_change(current, expected)).max(Decimal::ZERO),
+            estimated_savings,
+            gas_units,
+            recent,
+            outlook,
+            block_number: latest,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
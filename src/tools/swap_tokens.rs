@@ -1,23 +1,187 @@
-use super::Tool;
-use crate::ethereum::{EthereumClientTrait, UniswapRouterTrait};
+use super::approval_strategy::{approval_steps, ApprovalDefaults, ApprovalStrategy};
+use super::explain::display;
+use super::freshness::{freshness_schema_properties, Freshness, FreshnessParams};
+use super::pool_share::{pool_share_percent, OverLimitAction, PoolShareLimit};
+use super::quotes::{QuoteStore, StoredQuote};
+use super::recommend_slippage::price_impact_percent;
+use super::route_objective::RouteObjective;
+use super::scan_arbitrage::WETH;
+use super::slippage::{SlippageDefaults, SlippageSource};
+use super::split::split_by_depth;
+use super::units::{from_base_units, minimum_amount_out, to_base_units};
+use super::{merge_schema_properties, Explanation, Notional, Tool, ToolCategory};
+use crate::ethereum::tokens::bridged_token_warning;
+use crate::ethereum::{EthereumClientTrait, Plan, PlanStep, SwapSimulation, UniswapRouterTrait};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::prelude::*;
-use rust_decimal::prelude::FromPrimitive;
+use futures::future::{join_all, try_join_all};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Candidate routes returned by include_alternatives unless the call asks otherwise
+const DEFAULT_ALTERNATIVES_LIMIT: usize = 3;
+const MAX_ALTERNATIVES_LIMIT: usize = 10;
 
 pub struct SwapTokensTool<C: EthereumClientTrait, U: UniswapRouterTrait> {
     client: Arc<C>,
     uniswap: Arc<U>,
+    quotes: Option<Arc<QuoteStore>>,
+    slippage: SlippageDefaults,
+    pool_share_limit: Option<PoolShareLimit>,
+    /// Other V2 venues a `split` order is spread over
+    split_routers: Vec<Arc<U>>,
+    route_objective: RouteObjective,
+    approvals: ApprovalDefaults,
 }
 
 impl<C: EthereumClientTrait, U: UniswapRouterTrait> SwapTokensTool<C, U> {
     pub fn new(client: Arc<C>, uniswap: Arc<U>) -> Self {
-        Self { client, uniswap }
+        Self {
+            client,
+            uniswap,
+            quotes: None,
+            slippage: SlippageDefaults::default(),
+            pool_share_limit: None,
+            split_routers: Vec::new(),
+            route_objective: RouteObjective::default(),
+            approvals: ApprovalDefaults::default(),
+        }
+    }
+
+    /// Keep each simulation in `quotes` and return its quote_id for execute_swap
+    pub fn with_quote_store(mut self, quotes: Arc<QuoteStore>) -> Self {
+        self.quotes = Some(quotes);
+        self
+    }
+
+    /// Fall back to `slippage` when a call passes no slippage_tolerance
+    pub fn with_slippage_defaults(mut self, slippage: SlippageDefaults) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    /// Warn when a swap sells more of its pool than execute_swap will allow
+    pub fn with_pool_share_limit(mut self, limit: Option<PoolShareLimit>) -> Self {
+        self.pool_share_limit = limit;
+        self
+    }
+
+    /// Spread `split` orders over these V2 routers' pools as well as the main router's
+    pub fn with_split_routers(mut self, routers: Vec<Arc<U>>) -> Self {
+        self.split_routers = routers;
+        self
+    }
+
+    /// What routes are ranked by when calls don't choose
+    pub fn with_route_objective(mut self, objective: RouteObjective) -> Self {
+        self.route_objective = objective;
+        self
+    }
+
+    /// How much routers are approved for when calls don't choose
+    pub fn with_approval_defaults(mut self, approvals: ApprovalDefaults) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    /// Value of one wei in `token` base units, or `None` when the router cannot price it
+    async fn eth_price_in(&self, token: Address) -> Option<Decimal> {
+        let weth: Address = WETH.parse().ok()?;
+        if token == weth {
+            return Some(Decimal::ONE);
+        }
+        // Both sides are taken as 18 decimals, so the raw ratio is the price
+        self.uniswap
+            .get_price(weth, token, U256::exp10(18))
+            .await
+            .ok()
+            .filter(|price| !price.is_zero())
+    }
+
+    /// The order split over every venue with a pool for the pair, in proportion to
+    /// each pool's `from` reserve. Fewer than two pools leave nothing to split.
+    async fn split_legs(
+        &self,
+        from: Address,
+        to: Address,
+        amount_in: U256,
+        wallet: Address,
+    ) -> Result<Vec<Leg<'_, U>>> {
+        let routers: Vec<&U> = std::iter::once(self.uniswap.as_ref())
+            .chain(self.split_routers.iter().map(AsRef::as_ref))
+            .collect();
+        let reserves = join_all(
+            routers
+                .iter()
+                .map(|router| router.get_pool_reserves(from, to)),
+        )
+        .await;
+        let pools: Vec<(&U, U256)> = routers
+            .into_iter()
+            .zip(reserves)
+            .filter_map(|(router, reserves)| Some((router, reserves.ok()?.reserve_in)))
+            .filter(|(_, reserve_in)| !reserve_in.is_zero())
+            .collect();
+        if pools.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let depths: Vec<U256> = pools.iter().map(|(_, reserve_in)| *reserve_in).collect();
+        let parts = split_by_depth(amount_in, &depths);
+        try_join_all(
+            pools
+                .into_iter()
+                .zip(parts)
+                .filter(|(_, part)| !part.is_zero())
+                .map(|((router, _), part)| async move {
+                    let simulation = router
+                        .simulate_swap(from, to, part, wallet)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to simulate the {} part", router.venue_name())
+                        })?;
+                    Ok::<_, anyhow::Error>(Leg {
+                        router,
+                        amount_in: part,
+                        simulation,
+                    })
+                }),
+        )
+        .await
+    }
+}
+
+/// The part of an order sent through one router
+struct Leg<'a, U> {
+    router: &'a U,
+    amount_in: U256,
+    simulation: SwapSimulation,
+}
+
+/// Totals of a set of legs that fill the whole order
+struct Route {
+    venues: Vec<String>,
+    amount_out: U256,
+    gas_estimate: U256,
+    gas_cost: U256,
+}
+
+impl Route {
+    fn of<U: UniswapRouterTrait>(legs: &[Leg<'_, U>]) -> Self {
+        Self {
+            venues: legs
+                .iter()
+                .map(|leg| leg.router.venue_name().to_string())
+                .collect(),
+            amount_out: sum(legs.iter().map(|leg| leg.simulation.amount_out)),
+            gas_estimate: sum(legs.iter().map(|leg| leg.simulation.gas_estimate)),
+            gas_cost: sum(legs.iter().map(|leg| leg.simulation.gas_cost)),
+        }
     }
 }
 
@@ -26,12 +190,72 @@ struct SwapTokensParams {
     from_token: String,
     to_token: String,
     amount: String,
-    #[serde(default = "default_slippage")]
-    slippage_tolerance: f64, // Percentage (e.g., 0.5 for 0.5%)
+    slippage_tolerance: Option<f64>, // Percentage (e.g., 0.5 for 0.5%)
+    #[serde(default)]
+    split: bool,
+    #[serde(default)]
+    include_alternatives: bool,
+    alternatives_limit: Option<usize>,
+    route_objective: Option<RouteObjective>,
+    approval_strategy: Option<ApprovalStrategy>,
+    #[serde(flatten)]
+    freshness: FreshnessParams,
+}
+
+/// Whether gas figures were measured against the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum GasEstimateConfidence {
+    Estimated,
+    Fallback,
+}
+
+/// One transaction in the approve+swap bundle
+#[derive(Debug, Serialize)]
+struct BundleStep {
+    action: String,
+    target: String,
+    method: String,
+    gas_estimate: String,
+    estimated_gas_cost_eth: String,
+}
+
+/// How a `split` order compared with sending it all through one pool
+#[derive(Debug, Serialize)]
+struct SplitReport {
+    /// Whether the split returned more than the single route and was used
+    applied: bool,
+    single_route_amount_out: String,
+    /// Unset when fewer than two venues have a pool for the pair
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split_amount_out: Option<String>,
+    legs: Vec<SplitLeg>,
 }
 
-fn default_slippage() -> f64 {
-    0.5
+#[derive(Debug, Serialize)]
+struct SplitLeg {
+    venue: String,
+    router: Address,
+    amount_in: String,
+    estimated_amount_out: String,
+    /// Percent of the order sent this way
+    share_percent: String,
+}
+
+/// One way to fill the whole order, offered with include_alternatives
+#[derive(Debug, Serialize)]
+struct RouteAlternative {
+    /// More than one for a split across venues
+    venues: Vec<String>,
+    path: Vec<Address>,
+    expected_amount_out: String,
+    gas_estimate: String,
+    gas_cost_eth: String,
+    /// Output less the gas cost priced in to_token, unset when ETH has no price in it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_amount_out: Option<String>,
+    /// Whether this is the route the quote uses
+    selected: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,9 +266,57 @@ struct SwapTokensResult {
     estimated_amount_out: String,
     minimum_amount_out: String,
     gas_estimate: String,
+    /// `fallback` when a swap's gas could not be estimated and a default stands in
+    gas_estimate_confidence: GasEstimateConfidence,
+    /// Gas estimation reverted although the router's allowance was in place
+    will_likely_revert: bool,
+    /// Decoded reason of that revert
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revert_reason: Option<String>,
     gas_price_gwei: String,
     estimated_gas_cost_eth: String,
     slippage_tolerance: f64,
+    /// Whether slippage_tolerance was passed or taken from a configured default
+    slippage_source: SlippageSource,
+    router_method: String,
+    fee_on_transfer: bool,
+    /// Percent of the pool's from_token reserve the swap sells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool_share_percent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detected_transfer_fee_percent: Option<String>,
+    approval_required: bool,
+    /// What approve steps grant the router
+    approval_strategy: ApprovalStrategy,
+    /// An approve step zeroes the allowance first, as tokens such as USDT require
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    allowance_reset: bool,
+    current_allowance: String,
+    permit_supported: bool,
+    steps: Vec<BundleStep>,
+    total_gas_estimate: String,
+    total_gas_cost_eth: String,
+    /// The same bundle as input for execute_plan
+    plan: Plan,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split: Option<SplitReport>,
+    /// Candidate routes for the whole order, best first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alternatives: Option<Vec<RouteAlternative>>,
+    /// What split and alternatives were ranked by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    route_objective: Option<RouteObjective>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote_id: Option<String>,
+    /// Unix seconds after which quote_id is rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote_expires_at: Option<u64>,
+    /// Set when either side is a bridged copy of an asset rather than its canonical token,
+    /// or when the swap sells more of the pool than the server's limit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(flatten)]
+    freshness: Freshness,
 }
 
 #[async_trait]
@@ -56,32 +328,117 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
     }
 
     fn description(&self) -> &str {
-        "Simulate a token swap on Uniswap V2. Returns estimated output and gas costs without executing the transaction."
+        "Simulate a token swap on Uniswap V2. Returns estimated output and gas costs without executing the transaction. Fee-on-transfer tokens are detected automatically and routed through the supporting router method. When the router lacks allowance, an approve step is included (for the exact amount, the amount plus a buffer, or unlimited, per approval_strategy) and gas is totalled across the bundle; tokens such as USDT get their allowance zeroed first. Swaps through a bridged token variant (e.g. USDC.e) carry a warning naming the canonical token. With split: true, large orders are spread over the pair's Uniswap V2 and SushiSwap pools in proportion to their depth when that returns more than a single pool. include_alternatives lists the top candidate routes with their gas and net-of-gas output. Routes are ranked by output net of gas by default. When gas estimation reverts, the decoded reason is returned with will_likely_revert: true instead of a silent default."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn notional(&self, params: &Value) -> Option<Notional> {
+        Notional::from_params(params, "from_token", "amount")
     }
 
     fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "from_token": {
+                "type": "string",
+                "description": "Address of the token to swap from"
+            },
+            "to_token": {
+                "type": "string",
+                "description": "Address of the token to swap to"
+            },
+            "amount": {
+                "type": "string",
+                "description": "Amount to swap (in human-readable format, e.g., '1.5' for 1.5 tokens)"
+            },
+            "slippage_tolerance": {
+                "type": "number",
+                "description": "Slippage tolerance in percentage (default: the server's default for the pair, 0.5 unless configured)"
+            },
+            "split": {
+                "type": "boolean",
+                "description": "Spread the order over every V2 venue's pool for the pair in proportion to depth, used when it returns more than one pool (default: false)"
+            },
+            "include_alternatives": {
+                "type": "boolean",
+                "description": "Also return the best candidate routes (venue, path, expected output, gas and output net of gas) so the user can choose (default: false)"
+            },
+            "alternatives_limit": {
+                "type": "integer",
+                "description": "How many candidate routes include_alternatives returns (default: 3, max: 10)"
+            },
+            "route_objective": {
+                "type": "string",
+                "enum": ["net_of_gas", "output"],
+                "description": "What split and alternatives rank routes by: output net of gas, or raw output (default: the server's, net_of_gas unless configured)"
+            },
+            "approval_strategy": {
+                "type": "string",
+                "enum": ["exact", "exact_plus_buffer", "unlimited"],
+                "description": "How much to approve the router for when its allowance falls short: the exact amount, the amount plus the server's buffer, or unlimited (default: the server's, exact unless configured)"
+            }
+        });
+        merge_schema_properties(&mut properties, freshness_schema_properties());
+
         json!({
             "type": "object",
-            "properties": {
-                "from_token": {
-                    "type": "string",
-                    "description": "Address of the token to swap from"
-                },
-                "to_token": {
-                    "type": "string",
-                    "description": "Address of the token to swap to"
-                },
-                "amount": {
-                    "type": "string",
-                    "description": "Amount to swap (in human-readable format, e.g., '1.5' for 1.5 tokens)"
+            "properties": properties,
+            "required": ["from_token", "to_token", "amount"]
+        })
+    }
+
+    fn explain(&self, params: &Value, result: &Value) -> Explanation {
+        let path = json!([result["from_token"], result["to_token"]]);
+        Explanation::new(
+            format!(
+                "Simulated swapping {} tokens (assumed 18 decimals) along {} -> {} with the Uniswap V2 router's {}, giving an estimated {} out. With {}% slippage tolerance ({}) the minimum output is {}. {} gas at {} gwei costs {} ETH{}.",
+                display(&result["amount_in"]),
+                display(&path[0]),
+                display(&path[1]),
+                display(&result["router_method"]),
+                display(&result["estimated_amount_out"]),
+                display(&result["slippage_tolerance"]),
+                match result["slippage_source"].as_str() {
+                    Some("requested") => "as requested",
+                    Some("pair") => "the configured default for this pair",
+                    Some("token") => "the configured default for one of the tokens",
+                    _ => "the server default",
                 },
-                "slippage_tolerance": {
-                    "type": "number",
-                    "description": "Slippage tolerance in percentage (default: 0.5)"
+                display(&result["minimum_amount_out"]),
+                display(&result["total_gas_estimate"]),
+                display(&result["gas_price_gwei"]),
+                display(&result["total_gas_cost_eth"]),
+                if result["approval_required"] == true {
+                    ", including the approve step because the router's allowance is short"
+                } else {
+                    ""
                 }
+            ) + &if result["split"]["applied"] == true {
+                format!(
+                    " The order was split over {} pools by depth, returning {} instead of {} through one pool.",
+                    result["split"]["legs"].as_array().map_or(0, Vec::len),
+                    display(&result["split"]["split_amount_out"]),
+                    display(&result["split"]["single_route_amount_out"])
+                )
+            } else {
+                String::new()
             },
-            "required": ["from_token", "to_token", "amount"]
-        })
+            json!({
+                "inputs": params,
+                "venue": "Uniswap V2 router",
+                "path": path,
+                "router_method": result["router_method"],
+                "split": result["split"],
+                "decimals_assumed": 18,
+                "formulas": {
+                    "minimum_amount_out": "estimated_amount_out * (1 - slippage_tolerance / 100)",
+                    "gas_cost_eth": "gas_estimate * gas_price / 10^18",
+                    "total_gas_estimate": "swap gas + approve gas when approval_required",
+                },
+            }),
+        )
     }
 
     async fn execute(&self, params: Value) -> Result<Value> {
@@ -101,11 +458,18 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
         // Parse amount - assume 18 decimals for simplicity
         // In production, you'd query the token's decimals
         let amount_decimal = Decimal::from_str(&params.amount).context("Invalid amount")?;
-        let amount_wei = amount_decimal * Decimal::from(10u64.pow(18));
-        // Round to remove any decimal places and convert to integer string
-        let amount_wei_rounded = amount_wei.round();
-        let amount_in = U256::from_dec_str(&amount_wei_rounded.to_string())
-            .context("Failed to convert amount to U256")?;
+        let amount_in = to_base_units(amount_decimal, 18)?;
+        let alternatives_limit = params
+            .alternatives_limit
+            .unwrap_or(DEFAULT_ALTERNATIVES_LIMIT);
+        if !(1..=MAX_ALTERNATIVES_LIMIT).contains(&alternatives_limit) {
+            anyhow::bail!(
+                "alternatives_limit must be between 1 and {}",
+                MAX_ALTERNATIVES_LIMIT
+            );
+        }
+
+        let freshness = params.freshness.check(self.client.as_ref()).await?;
 
         // Get wallet address
         let wallet_address = self.client.get_wallet_address();
@@ -115,24 +479,289 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
             .uniswap
             .simulate_swap(from_token, to_token, amount_in, wallet_address)
             .await?;
+        let mut legs = vec![Leg {
+            router: self.uniswap.as_ref(),
+            amount_in,
+            simulation,
+        }];
+        let single_route = Route::of(&legs);
+
+        // Split legs are also worked out to compare routes, but only `split` uses them
+        let split_legs = if params.split {
+            self.split_legs(from_token, to_token, amount_in, wallet_address)
+                .await?
+        } else if params.include_alternatives {
+            self.split_legs(from_token, to_token, amount_in, wallet_address)
+                .await
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let split_route = (!split_legs.is_empty()).then(|| Route::of(&split_legs));
+
+        // Routes are ranked net of gas when ETH has a price in to_token, else by output
+        let compares_routes = params.split || params.include_alternatives;
+        let eth_in_to = if compares_routes {
+            self.eth_price_in(to_token).await
+        } else {
+            None
+        };
+        let objective = match params.route_objective.unwrap_or(self.route_objective) {
+            RouteObjective::NetOfGas if eth_in_to.is_none() => RouteObjective::Output,
+            objective => objective,
+        };
+        let score = |route: &Route| objective.score(route.amount_out, route.gas_cost, eth_in_to);
+
+        // A split is only worth its extra swaps when it ranks above one pool
+        let split = if params.split {
+            let report = SplitReport {
+                applied: split_route
+                    .as_ref()
+                    .is_some_and(|route| score(route) > score(&single_route)),
+                single_route_amount_out: from_base_units(single_route.amount_out, 18)?.to_string(),
+                split_amount_out: split_route
+                    .as_ref()
+                    .map(|route| from_base_units(route.amount_out, 18).map(|out| out.to_string()))
+                    .transpose()?,
+                legs: split_legs
+                    .iter()
+                    .map(|leg| {
+                        Ok(SplitLeg {
+                            venue: leg.router.venue_name().to_string(),
+                            router: leg.router.router_address(),
+                            amount_in: from_base_units(leg.amount_in, 18)?.to_string(),
+                            estimated_amount_out: from_base_units(leg.simulation.amount_out, 18)?
+                                .to_string(),
+                            share_percent: (pool_share_percent(leg.amount_in, amount_in)
+                                .round_dp(2))
+                            .normalize()
+                            .to_string(),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            };
+            if report.applied {
+                legs = split_legs;
+            }
+            Some(report)
+        } else {
+            None
+        };
+
+        let (slippage_tolerance, slippage_source) =
+            self.slippage
+                .resolve(params.slippage_tolerance, from_token, to_token);
+
+        // Each router needs its own allowance and gets its own swap and minimum output
+        let gas_price = legs[0].simulation.gas_price;
+        let mut steps = Vec::new();
+        let mut plan = Plan::default();
+        let mut approvals = Vec::new();
+        let mut approval_gas = U256::zero();
+        // Swaps whose estimate reverted with the allowance already in place
+        let mut revert_reasons: Vec<String> = Vec::new();
+        let mut estimate_notes: Vec<String> = Vec::new();
+        // The largest share any leg takes of its pool; pools that cannot be read are
+        // not reported
+        let mut pool_share: Option<Decimal> = None;
+        let mut price_impact: Option<Decimal> = None;
+        let mut spot_price: Option<Decimal> = None;
+        for leg in &legs {
+            let router_address = leg.router.router_address();
+            let approval = self
+                .client
+                .simulate_approval(from_token, wallet_address, router_address, leg.amount_in)
+                .await?;
+            if let Some(warning) = &leg.simulation.fee_detection_warning {
+                estimate_notes.push(warning.clone());
+            }
+            if let Some(reason) = &leg.simulation.gas_estimate_error {
+                // Without the allowance the router cannot pull the tokens, so the
+                // estimate cannot pass until the approve step is mined
+                if approval.approval_required {
+                    estimate_notes.push(format!(
+                        "Gas for the {} swap could not be estimated before its approval ({}); {} is assumed",
+                        leg.router.venue_name(),
+                        reason,
+                        leg.simulation.gas_estimate
+                    ));
+                } else {
+                    revert_reasons.push(reason.clone());
+                }
+            }
+            if approval.approval_required {
+                let approves = approval_steps(
+                    self.client.as_ref(),
+                    from_token,
+                    wallet_address,
+                    router_address,
+                    approval.current_allowance,
+                    self.approvals
+                        .allowance(params.approval_strategy, leg.amount_in),
+                )
+                .await;
+                for approve in approves {
+                    steps.push(BundleStep {
+                        action: "approve".to_string(),
+                        target: format!("{:?}", from_token),
+                        method: "approve".to_string(),
+                        gas_estimate: approval.gas_estimate.to_string(),
+                        estimated_gas_cost_eth: from_base_units(
+                            approval.gas_estimate * gas_price,
+                            18,
+                        )?
+                        .to_string(),
+                    });
+                    approval_gas += approval.gas_estimate;
+                    plan.steps.push(approve);
+                }
+            }
+            steps.push(BundleStep {
+                action: "swap".to_string(),
+                target: format!("{:?}", router_address),
+                method: leg.simulation.router_method().to_string(),
+                gas_estimate: leg.simulation.gas_estimate.to_string(),
+                estimated_gas_cost_eth: from_base_units(leg.simulation.gas_cost, 18)?.to_string(),
+            });
+            plan.steps.push(PlanStep::Swap {
+                router: router_address,
+                path: vec![from_token, to_token],
+                amount_in: leg.amount_in,
+                amount_out_min: minimum_amount_out(leg.simulation.amount_out, slippage_tolerance)?,
+                fee_on_transfer: leg.simulation.fee_on_transfer,
+            });
+            approvals.push(approval);
+
+            if let Ok(reserves) = leg.router.get_pool_reserves(from_token, to_token).await {
+                let share = pool_share_percent(leg.amount_in, reserves.reserve_in);
+                pool_share = Some(pool_share.map_or(share, |max| max.max(share)));
+                let impact = price_impact_percent(leg.amount_in, reserves.reserve_in);
+                price_impact = Some(price_impact.map_or(impact, |max| max.max(impact)));
+                if spot_price.is_none() && !reserves.reserve_in.is_zero() {
+                    // Both sides are taken as 18 decimals, so the raw ratio is the price
+                    spot_price = from_base_units(reserves.reserve_out, 18)
+                        .ok()
+                        .zip(from_base_units(reserves.reserve_in, 18).ok())
+                        .and_then(|(out, reserve_in)| out.checked_div(reserve_in));
+                }
+            }
+        }
 
-        // Calculate minimum amount out with slippage
-        let slippage_multiplier = 1.0 - (params.slippage_tolerance / 100.0);
-        let amount_out_decimal = Decimal::from_str(&simulation.amount_out.to_string())?;
-        let min_amount_out =
-            amount_out_decimal * Decimal::from_f64(slippage_multiplier).unwrap_or(Decimal::ONE);
+        let amount_out = sum(legs.iter().map(|leg| leg.simulation.amount_out));
+        let min_amount_out = sum(plan.steps.iter().filter_map(|step| match step {
+            PlanStep::Swap { amount_out_min, .. } => Some(*amount_out_min),
+            _ => None,
+        }));
+        let swap_gas = sum(legs.iter().map(|leg| leg.simulation.gas_estimate));
+        let total_gas = swap_gas + approval_gas;
+        let primary = &legs[0].simulation;
 
         // Convert amounts to human-readable format (assuming 18 decimals)
-        let estimated_out = amount_out_decimal / Decimal::from(10u64.pow(18));
-        let minimum_out = min_amount_out / Decimal::from(10u64.pow(18));
+        let estimated_out = from_base_units(amount_out, 18)?;
+        let minimum_out = from_base_units(min_amount_out, 18)?;
 
         // Convert gas price to Gwei
         let gas_price_gwei =
-            Decimal::from_str(&simulation.gas_price.to_string())? / Decimal::from(10u64.pow(9));
+            Decimal::from_str(&gas_price.to_string())? / Decimal::from(10u64.pow(9));
 
         // Convert gas cost to ETH
         let gas_cost_eth =
-            Decimal::from_str(&simulation.gas_cost.to_string())? / Decimal::from(10u64.pow(18));
+            from_base_units(sum(legs.iter().map(|leg| leg.simulation.gas_cost)), 18)?;
+
+        let (quote_id, quote_expires_at) = match &self.quotes {
+            Some(quotes) => {
+                let (id, expires_at) = quotes.insert(StoredQuote {
+                    from_token,
+                    to_token,
+                    amount_in,
+                    amount_out,
+                    slippage_tolerance,
+                    pool_share_percent: pool_share,
+                    price_impact_percent: price_impact,
+                    spot_price,
+                    gas_estimate: total_gas,
+                    gas_price,
+                    block_number: freshness.block_number,
+                    quoted_at: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|elapsed| elapsed.as_secs())
+                        .unwrap_or_default(),
+                    plan: plan.clone(),
+                });
+                (Some(id), Some(expires_at))
+            }
+            None => (None, None),
+        };
+
+        let chain_id = self.client.get_chain_id();
+        let mut warnings: Vec<String> = [from_token, to_token]
+            .into_iter()
+            .filter_map(|token| bridged_token_warning(chain_id, token))
+            .collect();
+        if let Some(reason) = revert_reasons.first() {
+            warnings.push(format!(
+                "Gas estimation reverted: {}. The swap would likely fail as simulated; do not present it as viable",
+                reason
+            ));
+        }
+        warnings.extend(estimate_notes);
+        if let (Some(limit), Some(share)) = (&self.pool_share_limit, pool_share) {
+            if limit.is_exceeded_by(share) {
+                warnings.push(format!(
+                    "{}; execute_swap will {} it",
+                    limit.describe(share, from_token),
+                    match limit.action {
+                        OverLimitAction::Reject => "refuse",
+                        OverLimitAction::Confirm => "ask for confirmation before sending",
+                    }
+                ));
+            }
+        }
+
+        let alternatives = if params.include_alternatives {
+            let selected = Route::of(&legs).venues;
+            let mut routes = vec![single_route];
+            for router in &self.split_routers {
+                if let Ok(simulation) = router
+                    .simulate_swap(from_token, to_token, amount_in, wallet_address)
+                    .await
+                {
+                    routes.push(Route::of(&[Leg {
+                        router: router.as_ref(),
+                        amount_in,
+                        simulation,
+                    }]));
+                }
+            }
+            routes.extend(split_route);
+            routes.sort_by_key(|route| std::cmp::Reverse(score(route)));
+            routes.truncate(alternatives_limit);
+
+            let alternatives = routes
+                .into_iter()
+                .map(|route| {
+                    let gas_cost = from_base_units(route.gas_cost, 18)?;
+                    let amount_out = from_base_units(route.amount_out, 18)?;
+                    Ok(RouteAlternative {
+                        selected: route.venues == selected,
+                        path: vec![from_token, to_token],
+                        expected_amount_out: amount_out.to_string(),
+                        gas_estimate: route.gas_estimate.to_string(),
+                        gas_cost_eth: gas_cost.to_string(),
+                        net_amount_out: eth_in_to.map(|rate| {
+                            (amount_out - gas_cost * rate)
+                                .max(Decimal::ZERO)
+                                .normalize()
+                                .to_string()
+                        }),
+                        venues: route.venues,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Some(alternatives)
+        } else {
+            None
+        };
 
         let result = SwapTokensResult {
             from_token: params.from_token,
@@ -140,12 +769,55 @@ impl<C: EthereumClientTrait + 'static, U: UniswapRouterTrait + 'static> Tool
             amount_in: params.amount,
             estimated_amount_out: estimated_out.to_string(),
             minimum_amount_out: minimum_out.to_string(),
-            gas_estimate: simulation.gas_estimate.to_string(),
+            gas_estimate: swap_gas.to_string(),
+            gas_estimate_confidence: if legs
+                .iter()
+                .any(|leg| leg.simulation.gas_estimate_error.is_some())
+            {
+                GasEstimateConfidence::Fallback
+            } else {
+                GasEstimateConfidence::Estimated
+            },
+            will_likely_revert: !revert_reasons.is_empty(),
+            revert_reason: revert_reasons.into_iter().next(),
             gas_price_gwei: gas_price_gwei.to_string(),
             estimated_gas_cost_eth: gas_cost_eth.to_string(),
-            slippage_tolerance: params.slippage_tolerance,
+            slippage_tolerance,
+            slippage_source,
+            router_method: primary.router_method().to_string(),
+            fee_on_transfer: primary.fee_on_transfer,
+            pool_share_percent: pool_share.map(|share| share.normalize().to_string()),
+            detected_transfer_fee_percent: primary
+                .transfer_fee_bps
+                .map(|bps| (Decimal::from(bps) / Decimal::from(100)).to_string()),
+            approval_required: approvals.iter().any(|approval| approval.approval_required),
+            approval_strategy: params.approval_strategy.unwrap_or(self.approvals.strategy),
+            allowance_reset: plan
+                .steps
+                .iter()
+                .any(|step| matches!(step, PlanStep::Approve { amount, .. } if amount.is_zero())),
+            // Infinite approvals (2^256 - 1) are beyond Decimal's range
+            current_allowance: from_base_units(approvals[0].current_allowance, 18)
+                .map(|allowance| allowance.to_string())
+                .unwrap_or_else(|_| "unlimited".to_string()),
+            permit_supported: approvals[0].supports_permit,
+            steps,
+            total_gas_estimate: total_gas.to_string(),
+            total_gas_cost_eth: from_base_units(total_gas * gas_price, 18)?.to_string(),
+            plan,
+            split,
+            alternatives,
+            route_objective: compares_routes.then_some(objective),
+            quote_id,
+            quote_expires_at,
+            warnings,
+            freshness,
         };
 
         Ok(serde_json::to_value(result)?)
     }
 }
+
+fn sum(amounts: impl Iterator<Item = U256>) -> U256 {
+    amounts.fold(U256::zero(), |total, amount| total.saturating_add(amount))
+}
@@ -1,8 +1,8 @@
-use crate::ethereum::{MockEthereumClient, MockUniswapRouter, SwapSimulation};
+use crate::ethereum::{MockEthereumClient, MockUniswapRouter, SwapSimulation, UniswapRouterTrait};
 use crate::tools::*;
 use ethers::prelude::*;
 use rust_decimal::Decimal;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -61,6 +61,525 @@ async fn test_get_balance_tool_with_token() {
     assert_eq!(result["balance"], "1000");
     assert_eq!(result["symbol"], "USDC");
     assert_eq!(result["decimals"], 6);
+    assert_eq!(result["standard"], "erc20");
+}
+
+/// Calldata for `signature` with `args`
+fn calldata(signature: &str, args: &[ethers::abi::Token]) -> Bytes {
+    Bytes::from(
+        [
+            &ethers::utils::keccak256(signature)[..4],
+            &ethers::abi::encode(args)[..],
+        ]
+        .concat(),
+    )
+}
+
+fn word(value: U256) -> Bytes {
+    Bytes::from(ethers::abi::encode(&[ethers::abi::Token::Uint(value)]))
+}
+
+/// Answer ERC-165 `supportsInterface` on `contract` with yes for each of `interfaces`
+fn with_interfaces(
+    mut client: MockEthereumClient,
+    contract: Address,
+    interfaces: &[[u8; 4]],
+) -> MockEthereumClient {
+    for id in [&[0x01, 0xff, 0xc9, 0xa7]].into_iter().chain(interfaces) {
+        client = client.with_call_result(
+            contract,
+            calldata(
+                "supportsInterface(bytes4)",
+                &[ethers::abi::Token::FixedBytes(id.to_vec())],
+            ),
+            word(U256::one()),
+        );
+    }
+    client
+}
+
+#[tokio::test]
+async fn test_get_balance_detects_token_standards() {
+    use ethers::abi::Token;
+
+    let wallet = Address::repeat_byte(0x11);
+    let multi = Address::repeat_byte(0x15);
+    let erc777 = Address::repeat_byte(0x77);
+    let registry: Address = "0x1820a4B7618BdE71Dce8cdc73aAB6C95905faD24"
+        .parse()
+        .unwrap();
+    let client = with_interfaces(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_token_balance(erc777, wallet, Decimal::new(25, 1), 18),
+        multi,
+        &[[0xd9, 0xb6, 0x7a, 0x26]],
+    )
+    .with_call_result(
+        multi,
+        calldata(
+            "balanceOf(address,uint256)",
+            &[Token::Address(wallet), Token::Uint(U256::from(7))],
+        ),
+        word(U256::from(3)),
+    )
+    .with_call_result(
+        registry,
+        calldata(
+            "getInterfaceImplementer(address,bytes32)",
+            &[
+                Token::Address(erc777),
+                Token::FixedBytes(ethers::utils::keccak256("ERC777Token").to_vec()),
+            ],
+        ),
+        Bytes::from(ethers::abi::encode(&[Token::Address(erc777)])),
+    );
+    let tool = GetBalanceTool::new(Arc::new(client));
+
+    let result = tool
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "token_address": format!("{:?}", multi),
+            "token_id": "7"
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["standard"], "erc1155");
+    assert_eq!(result["balance"], "3");
+    assert_eq!(result["decimals"], 0);
+    assert_eq!(result["token_id"], "7");
+
+    let err = tool
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "token_address": format!("{:?}", multi)
+        }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("pass its token_id"), "{}", err);
+
+    let result = tool
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "token_address": format!("{:?}", erc777)
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["standard"], "erc777");
+    assert_eq!(result["balance"], "2.5");
+}
+
+#[tokio::test]
+async fn test_balance_reports_block_freshness() {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mock_client = MockEthereumClient::new()
+        .with_block_number(19_000_000)
+        .with_block_timestamp(now - 30);
+    let tool = GetBalanceTool::new(Arc::new(mock_client));
+    let address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+    let result = tool.execute(json!({ "address": address })).await.unwrap();
+    assert_eq!(result["block_number"], 19_000_000);
+    assert_eq!(result["block_timestamp"], now - 30);
+    assert_eq!(result["rpc_endpoint"], "mock");
+
+    let fresh = tool
+        .execute(json!({ "address": address, "max_staleness": 60 }))
+        .await;
+    assert!(fresh.is_ok());
+
+    let stale = tool
+        .execute(json!({ "address": address, "max_staleness": 10 }))
+        .await;
+    assert!(stale
+        .unwrap_err()
+        .to_string()
+        .contains("exceeding max_staleness"));
+}
+
+#[tokio::test]
+async fn test_balance_reads_at_the_requested_finality() {
+    let address: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let mock_client = MockEthereumClient::new()
+        .with_block_number(19_000_100)
+        .with_finalized_blocks(19_000_090, 19_000_036)
+        .with_eth_balance(address, Decimal::from(5))
+        .with_eth_balance_at(address, 19_000_036, Decimal::from(3));
+    let tool = GetBalanceTool::new(Arc::new(mock_client));
+
+    let latest = tool
+        .execute(json!({ "address": format!("{:?}", address) }))
+        .await
+        .unwrap();
+    assert_eq!(latest["balance"], "5");
+    assert_eq!(latest["block_number"], 19_000_100);
+    assert_eq!(latest["finality"], "latest");
+
+    let finalized = tool
+        .execute(json!({ "address": format!("{:?}", address), "finality": "finalized" }))
+        .await
+        .unwrap();
+    assert_eq!(finalized["balance"], "3");
+    assert_eq!(finalized["block_number"], 19_000_036);
+    assert_eq!(finalized["finality"], "finalized");
+
+    let safe = tool
+        .execute(json!({ "address": format!("{:?}", address), "finality": "safe" }))
+        .await
+        .unwrap();
+    assert_eq!(safe["block_number"], 19_000_090);
+
+    let invalid = tool
+        .execute(json!({ "address": format!("{:?}", address), "finality": "pending" }))
+        .await;
+    assert!(invalid.is_err());
+}
+
+#[tokio::test]
+async fn test_read_storage_finality_resolves_the_block() {
+    let token = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_block_number(200)
+            .with_finalized_blocks(190, 136),
+    );
+    let tool = ReadStorageTool::new(client);
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", token), "slot": "0", "finality": "finalized" }))
+        .await
+        .unwrap();
+    assert_eq!(result["block"], 136);
+
+    let both = tool
+        .execute(json!({
+            "address": format!("{:?}", token),
+            "slot": "0",
+            "block": 150,
+            "finality": "safe"
+        }))
+        .await;
+    assert!(both
+        .unwrap_err()
+        .to_string()
+        .contains("either block or finality"));
+}
+
+#[tokio::test]
+async fn test_get_balance_all_chains_isolates_failures() {
+    use crate::ethereum::ChainClients;
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let usdc: Address = "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"
+        .parse()
+        .unwrap();
+    let chains = ChainClients::new()
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_eth_balance(wallet, Decimal::new(15, 1))
+                .with_block_number(100),
+        ))
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_chain_id(10)
+                .with_unavailable_rpc(),
+        ))
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_chain_id(42161)
+                .with_eth_balance(wallet, Decimal::new(2, 2))
+                .with_token_balance(usdc, wallet, Decimal::new(250, 0), 6)
+                .with_token_symbol(usdc, "USDC".to_string())
+                .with_block_number(200),
+        ));
+    let tool = GetBalanceAllChainsTool::new(Arc::new(chains)).with_max_parallel(2);
+
+    let result = tool
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "tokens": { "42161": [format!("{:?}", usdc)] }
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["succeeded"], 2);
+    assert_eq!(result["failed"], 1);
+    let chains = result["chains"].as_array().unwrap();
+    assert_eq!(chains[0]["chain"], "ethereum");
+    assert_eq!(chains[0]["native"]["balance"], "1.5");
+    assert_eq!(chains[0]["block_number"], 100);
+    assert_eq!(chains[1]["chain"], "optimism");
+    assert!(chains[1]["error"]
+        .as_str()
+        .unwrap()
+        .contains("connection refused"));
+    assert!(chains[1].get("native").is_none());
+    assert_eq!(chains[2]["native"]["balance"], "0.02");
+    assert_eq!(chains[2]["tokens"][0]["symbol"], "USDC");
+    assert_eq!(chains[2]["tokens"][0]["balance"], "250");
+
+    let only_arbitrum = tool
+        .execute(json!({ "address": format!("{:?}", wallet), "chain_ids": [42161] }))
+        .await
+        .unwrap();
+    assert_eq!(only_arbitrum["chains"].as_array().unwrap().len(), 1);
+
+    let unknown = tool
+        .execute(json!({ "address": format!("{:?}", wallet), "chain_ids": [137] }))
+        .await;
+    assert!(unknown.unwrap_err().to_string().contains("not configured"));
+}
+
+#[tokio::test]
+async fn test_multichain_balances_read_at_the_requested_finality() {
+    use crate::ethereum::{ChainClients, ChainPricing};
+
+    let wallet = Address::repeat_byte(0x42);
+    let (weth, usdc) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+    let chains = Arc::new(
+        ChainClients::new()
+            .with_client(Arc::new(
+                MockEthereumClient::new()
+                    .with_block_number(1_100)
+                    .with_finalized_blocks(1_090, 1_036)
+                    .with_eth_balance(wallet, Decimal::from(5))
+                    .with_eth_balance_at(wallet, 1_036, Decimal::from(3)),
+            ))
+            .with_pricing(
+                1,
+                ChainPricing {
+                    venue: "uniswap_v2".to_string(),
+                    router: Arc::new(
+                        MockUniswapRouter::new().with_price(weth, usdc, Decimal::new(3, 9)),
+                    ),
+                    wrapped_native: weth,
+                    usd_token: usdc,
+                    usd_decimals: 6,
+                },
+            ),
+    );
+
+    let balances = GetBalanceAllChainsTool::new(chains.clone());
+    let latest = balances
+        .execute(json!({ "address": format!("{:?}", wallet) }))
+        .await
+        .unwrap();
+    assert_eq!(latest["finality"], "latest");
+    assert_eq!(latest["chains"][0]["native"]["balance"], "5");
+    assert_eq!(latest["chains"][0]["block_number"], 1_100);
+
+    let finalized = balances
+        .execute(json!({ "address": format!("{:?}", wallet), "finality": "finalized" }))
+        .await
+        .unwrap();
+    assert_eq!(finalized["finality"], "finalized");
+    assert_eq!(finalized["chains"][0]["native"]["balance"], "3");
+    assert_eq!(finalized["chains"][0]["block_number"], 1_036);
+
+    let portfolio = GetPortfolioTool::new(chains)
+        .execute(json!({ "address": format!("{:?}", wallet), "finality": "finalized" }))
+        .await
+        .unwrap();
+    assert_eq!(portfolio["finality"], "finalized");
+    assert_eq!(portfolio["chains"][0]["block_number"], 1_036);
+    assert_eq!(portfolio["total_usd"], "9000");
+}
+
+#[tokio::test]
+async fn test_get_portfolio_values_each_chain_on_its_own_venue() {
+    use crate::ethereum::{ChainClients, ChainPricing};
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let pricing = |weth: Address, usdc: Address, router: MockUniswapRouter| ChainPricing {
+        venue: "uniswap_v2".to_string(),
+        router: Arc::new(router),
+        wrapped_native: weth,
+        usd_token: usdc,
+        usd_decimals: 6,
+    };
+
+    // Mainnet: 2 ETH at $3000 and 10 UNI, which only has a WETH pool, at 0.002 ETH
+    let (weth, usdc) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+    let uni = Address::repeat_byte(0x03);
+    let mainnet_router = MockUniswapRouter::new()
+        .with_price(weth, usdc, Decimal::new(3, 9))
+        .with_price(uni, weth, Decimal::new(2, 3));
+    // Arbitrum: 0.5 ETH at $3100 and 250 USDC
+    let (arb_weth, arb_usdc) = (Address::repeat_byte(0x11), Address::repeat_byte(0x12));
+    let arbitrum_router =
+        MockUniswapRouter::new().with_price(arb_weth, arb_usdc, Decimal::new(31, 10));
+
+    let chains = ChainClients::new()
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_eth_balance(wallet, Decimal::new(2, 0))
+                .with_token_balance(uni, wallet, Decimal::new(10, 0), 18),
+        ))
+        .with_pricing(1, pricing(weth, usdc, mainnet_router))
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_chain_id(42161)
+                .with_eth_balance(wallet, Decimal::new(5, 1))
+                .with_token_balance(arb_usdc, wallet, Decimal::new(250, 0), 6),
+        ))
+        .with_pricing(42161, pricing(arb_weth, arb_usdc, arbitrum_router))
+        .with_client(Arc::new(
+            MockEthereumClient::new()
+                .with_chain_id(10)
+                .with_eth_balance(wallet, Decimal::ONE),
+        ));
+    let tool = GetPortfolioTool::new(Arc::new(chains));
+
+    let result = tool
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "tokens": {
+                "1": [format!("{:?}", uni)],
+                "42161": [format!("{:?}", arb_usdc)]
+            }
+        }))
+        .await
+        .unwrap();
+
+    let chains = result["chains"].as_array().unwrap();
+    assert_eq!(chains[0]["chain"], "ethereum");
+    assert_eq!(chains[0]["holdings"][0]["price_usd"], "3000");
+    assert_eq!(chains[0]["holdings"][1]["price_usd"], "6");
+    assert_eq!(chains[0]["total_usd"], "6060");
+
+    // Optimism has no pricing venue: its ETH is listed but not counted
+    assert_eq!(chains[1]["chain"], "optimism");
+    assert_eq!(chains[1]["total_usd"], "0");
+    assert!(chains[1]["holdings"][0]["price_error"]
+        .as_str()
+        .unwrap()
+        .contains("No USD pricing venue"));
+
+    assert_eq!(chains[2]["venue"], "uniswap_v2");
+    assert_eq!(chains[2]["holdings"][0]["value_usd"], "1550");
+    assert_eq!(chains[2]["holdings"][1]["price_usd"], "1");
+    assert_eq!(chains[2]["total_usd"], "1800");
+
+    assert_eq!(result["total_usd"], "7860");
+    assert_eq!(result["unpriced_holdings"], 1);
+    assert_eq!(result["failed_chains"], 0);
+}
+
+#[tokio::test]
+async fn test_bridged_variants_aggregate_and_warn() {
+    use crate::ethereum::{ChainClients, ChainPricing};
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    // Arbitrum's native USDC, the bridged USDC.e and WETH
+    let usdc: Address = "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"
+        .parse()
+        .unwrap();
+    let usdc_e: Address = "0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8"
+        .parse()
+        .unwrap();
+    let weth: Address = "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"
+        .parse()
+        .unwrap();
+    let lookalike = Address::repeat_byte(0x42);
+
+    let router = || {
+        MockUniswapRouter::new()
+            .with_price(weth, usdc, Decimal::new(3, 9))
+            .with_price(usdc_e, usdc, Decimal::new(999, 3))
+    };
+    let client = || {
+        MockEthereumClient::new()
+            .with_chain_id(42161)
+            .with_eth_balance(wallet, Decimal::ONE)
+            .with_token_balance(usdc, wallet, Decimal::new(100, 0), 6)
+            .with_token_balance(usdc_e, wallet, Decimal::new(50, 0), 6)
+            .with_token_balance(weth, wallet, Decimal::new(5, 1), 18)
+            .with_token_balance(lookalike, wallet, Decimal::new(7, 0), 6)
+    };
+    let chains = ChainClients::new()
+        .with_client(Arc::new(client()))
+        .with_pricing(
+            42161,
+            ChainPricing {
+                venue: "uniswap_v2".to_string(),
+                router: Arc::new(router()),
+                wrapped_native: weth,
+                usd_token: usdc,
+                usd_decimals: 6,
+            },
+        );
+    let tokens: Vec<String> = [usdc, usdc_e, weth, lookalike]
+        .iter()
+        .map(|token| format!("{:?}", token))
+        .collect();
+    let portfolio = GetPortfolioTool::new(Arc::new(chains))
+        .execute(json!({
+            "address": format!("{:?}", wallet),
+            "tokens": {
+                "42161": tokens
+            }
+        }))
+        .await
+        .unwrap();
+
+    let holdings = &portfolio["chains"][0]["holdings"];
+    assert_eq!(holdings[2]["asset"], "USDC");
+    assert_eq!(holdings[2]["canonical"], false);
+    assert_eq!(holdings[2]["bridge"], "Arbitrum bridge");
+    assert_eq!(holdings[2]["canonical_address"], format!("{:?}", usdc));
+    assert!(holdings[4].get("asset").is_none());
+
+    // ETH and WETH count as one asset, as do USDC and USDC.e; the lookalike is left out
+    let assets = portfolio["assets"].as_array().unwrap();
+    assert_eq!(assets.len(), 2);
+    assert_eq!(assets[0]["asset"], "ETH");
+    assert_eq!(assets[0]["balance"], "1.5");
+    assert_eq!(assets[0]["value_usd"], "4500");
+    assert_eq!(assets[1]["asset"], "USDC");
+    assert_eq!(assets[1]["balance"], "150");
+    assert_eq!(assets[1]["value_usd"], "149.95");
+    assert_eq!(assets[1]["variants"].as_array().unwrap().len(), 2);
+
+    // Swapping into USDC.e warns and points at the canonical token
+    let simulation = SwapSimulation {
+        amount_in: U256::exp10(18),
+        amount_out: U256::exp10(18),
+        gas_estimate: U256::from(200000),
+        gas_price: U256::from(1_000_000_000u64),
+        gas_cost: U256::from(200_000_000_000_000u64),
+        ..Default::default()
+    };
+    let swap = SwapTokensTool::new(
+        Arc::new(client()),
+        Arc::new(router().with_swap_simulation(weth, usdc_e, simulation)),
+    );
+    let result = swap
+        .execute(json!({
+            "from_token": format!("{:?}", weth),
+            "to_token": format!("{:?}", usdc_e),
+            "amount": "1"
+        }))
+        .await
+        .unwrap();
+    let warnings = result["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].as_str().unwrap().contains("USDC.e"));
+    assert!(warnings[0]
+        .as_str()
+        .unwrap()
+        .contains(&format!("{:?}", usdc)));
 }
 
 #[tokio::test]
@@ -83,7 +602,7 @@ async fn test_get_token_price_tool_with_mock() {
         MockUniswapRouter::new().with_price(token_addr, usdc_addr, Decimal::new(10, 0));
 
     // Create tool with mocks
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     // Execute the tool
     let params = json!({
@@ -103,6 +622,193 @@ async fn test_get_token_price_tool_with_mock() {
     assert!(result["price"].as_str().unwrap().contains("10000000000000"));
 }
 
+#[tokio::test]
+async fn test_get_token_price_converts_to_fiat() {
+    use crate::ethereum::MockFxRates;
+
+    let uni_addr: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let usdc_addr: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth_addr: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    // 1 UNI = 12.5 USD = 0.005 ETH; 1 EUR = 1.25 USD
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_price(uni_addr, usdc_addr, Decimal::new(125, 13))
+        .with_price(uni_addr, weth_addr, Decimal::new(5, 3));
+    let fx_rates = MockFxRates::new().with_rate("EUR", Decimal::new(125, 2));
+    let tool = GetTokenPriceTool::new(Arc::new(MockEthereumClient::new()), Arc::new(mock_uniswap))
+        .unwrap()
+        .with_fx_rates(Arc::new(fx_rates));
+
+    let result = tool
+        .execute(json!({ "token_symbol": "UNI", "fiat_currency": "eur" }))
+        .await
+        .unwrap();
+    let decimal = |value: &serde_json::Value| value.as_str().unwrap().parse::<Decimal>().unwrap();
+    assert_eq!(decimal(&result["price"]), Decimal::new(125, 1));
+    assert_eq!(result["fiat_currency"], "EUR");
+    assert_eq!(result["fiat_price"], "10");
+    assert_eq!(result["fx_rate"], "0.8");
+
+    // The ETH quote is kept and the USD price is fetched for conversion
+    let result = tool
+        .execute(json!({ "token_symbol": "UNI", "quote_currency": "ETH", "fiat_currency": "EUR" }))
+        .await
+        .unwrap();
+    assert_eq!(result["price"], "0.005");
+    assert_eq!(decimal(&result["price_usd"]), Decimal::new(125, 1));
+    assert_eq!(result["fiat_price"], "10");
+
+    let err = tool
+        .execute(json!({ "token_symbol": "UNI", "fiat_currency": "XYZ" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Unsupported fiat currency"));
+
+    // Without fiat_currency the price alone is returned
+    let result = tool
+        .execute(json!({ "token_symbol": "UNI" }))
+        .await
+        .unwrap();
+    assert!(result.get("fiat_price").is_none());
+}
+
+#[tokio::test]
+async fn test_get_token_price_routes_through_intermediaries() {
+    let token: Address = "0x1111111111111111111111111111111111111111"
+        .parse()
+        .unwrap();
+    let other: Address = "0x2222222222222222222222222222222222222222"
+        .parse()
+        .unwrap();
+    let dai: Address = "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+        .parse()
+        .unwrap();
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    // 1 TOKEN = 0.01 WETH, 1 WETH = 2000 USDC; OTHER only trades against DAI
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_price(token, weth, Decimal::new(1, 2))
+        .with_price(weth, usdc, Decimal::new(2000, 12))
+        .with_price(other, dai, Decimal::new(3, 0))
+        .with_price(dai, usdc, Decimal::new(1, 12));
+    let tool = GetTokenPriceTool::new(Arc::new(MockEthereumClient::new()), Arc::new(mock_uniswap))
+        .unwrap()
+        .with_intermediaries(vec![dai]);
+
+    let result = tool
+        .execute(json!({ "token_address": format!("{:?}", token), "explain": true }))
+        .await
+        .unwrap();
+    let price: Decimal = result["price"].as_str().unwrap().parse().unwrap();
+    assert_eq!(price, Decimal::from(20));
+    assert_eq!(result["path"], json!([token, weth, usdc]));
+
+    // Configured intermediaries are tried after WETH
+    let result = tool
+        .execute(json!({ "token_address": format!("{:?}", other) }))
+        .await
+        .unwrap();
+    let price: Decimal = result["price"].as_str().unwrap().parse().unwrap();
+    assert_eq!(price, Decimal::from(3));
+    assert_eq!(result["path"], json!([other, dai, usdc]));
+
+    // A direct pool is used when there is one
+    let result = tool
+        .execute(json!({ "token_address": format!("{:?}", token), "quote_currency": "ETH" }))
+        .await
+        .unwrap();
+    assert_eq!(result["path"], json!([token, weth]));
+
+    let unknown = "0x3333333333333333333333333333333333333333";
+    let err = tool
+        .execute(json!({ "token_address": unknown }))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("no route through an intermediary"));
+}
+
+#[tokio::test]
+async fn test_get_token_price_flags_prices_straying_from_oracle() {
+    use crate::ethereum::MockReferencePrices;
+
+    let uni: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let link: Address = "0x514910771AF9Ca656af840dff83E8264EcF986CA"
+        .parse()
+        .unwrap();
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    // UNI trades at 10.2 USD against an oracle at 10; LINK's pool reads 30 against 15
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_price(uni, usdc, Decimal::new(102, 13))
+        .with_price(link, usdc, Decimal::new(30, 12))
+        .with_price(link, weth, Decimal::new(15, 3));
+    let oracle = MockReferencePrices::new()
+        .with_price(uni, Decimal::from(10))
+        .with_price(link, Decimal::from(15))
+        .with_price(weth, Decimal::from(2000));
+    let tool = GetTokenPriceTool::new(Arc::new(MockEthereumClient::new()), Arc::new(mock_uniswap))
+        .unwrap()
+        .with_reference_prices(Arc::new(oracle), Decimal::from(5));
+
+    let result = tool
+        .execute(json!({ "token_symbol": "UNI" }))
+        .await
+        .unwrap();
+    assert_eq!(result["price_check"]["deviation_percent"], "2");
+    assert_eq!(result["price_check"]["anomalous"], false);
+    assert!(result.get("warnings").is_none());
+
+    let result = tool
+        .execute(json!({ "token_symbol": "LINK" }))
+        .await
+        .unwrap();
+    assert_eq!(result["price_check"]["deviation_percent"], "100");
+    assert_eq!(result["price_check"]["anomalous"], true);
+    assert!(result["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("manipulated or too illiquid"));
+
+    // ETH quotes are checked against the ratio of the USD feeds
+    let result = tool
+        .execute(json!({ "token_symbol": "LINK", "quote_currency": "ETH" }))
+        .await
+        .unwrap();
+    assert_eq!(result["price_check"]["reference_price"], "0.0075");
+    assert_eq!(result["price_check"]["anomalous"], true);
+
+    // Tokens without a feed are returned unchecked
+    let tool = GetTokenPriceTool::new(
+        Arc::new(MockEthereumClient::new()),
+        Arc::new(MockUniswapRouter::new().with_price(uni, usdc, Decimal::new(102, 13))),
+    )
+    .unwrap()
+    .with_reference_prices(Arc::new(MockReferencePrices::new()), Decimal::from(5));
+    let result = tool
+        .execute(json!({ "token_symbol": "UNI" }))
+        .await
+        .unwrap();
+    assert!(result.get("price_check").is_none());
+}
+
 #[tokio::test]
 async fn test_get_token_price_tool_with_symbol() {
     // Setup mock clients
@@ -123,7 +829,7 @@ async fn test_get_token_price_tool_with_symbol() {
         MockUniswapRouter::new().with_price(uni_addr, usdc_addr, Decimal::new(15, 0));
 
     // Create tool with mocks
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     // Execute the tool using symbol instead of address
     let params = json!({
@@ -154,7 +860,7 @@ async fn test_get_token_price_tool_with_invalid_symbol() {
     let mock_uniswap = MockUniswapRouter::new();
 
     // Create tool with mocks
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     // Execute the tool with an invalid symbol
     let params = json!({
@@ -193,6 +899,7 @@ async fn test_swap_tokens_tool_with_mock() {
         gas_estimate: U256::from(200000),
         gas_price: U256::from(50_000_000_000u64), // 50 gwei
         gas_cost: U256::from(10_000_000_000_000_000u64), // 0.01 ETH
+        ..Default::default()
     };
 
     let mock_uniswap =
@@ -225,57 +932,345 @@ async fn test_swap_tokens_tool_with_mock() {
     let estimated_out = result["estimated_amount_out"].as_str().unwrap();
     assert!(estimated_out == "0.5" || estimated_out == "0.50");
     assert_eq!(result["gas_estimate"], "200000");
+    assert_eq!(result["fee_on_transfer"], false);
+    assert_eq!(result["router_method"], "swapExactTokensForTokens");
 }
 
-// Tests for Tool trait methods: name, description, input_schema
+#[tokio::test]
+async fn test_swap_tokens_includes_approval_step() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap(); // USDC
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap(); // WETH
 
-#[test]
-fn test_get_balance_tool_name() {
-    let wallet_addr: Address = Address::zero();
-    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
-    let tool = GetBalanceTool::new(Arc::new(mock_client));
+    let simulation = SwapSimulation {
+        amount_in: U256::from_dec_str("1000000000000000000").unwrap(),
+        amount_out: U256::from_dec_str("500000000000000000").unwrap(),
+        gas_estimate: U256::from(200000),
+        gas_price: U256::from(50_000_000_000u64), // 50 gwei
+        gas_cost: U256::from(10_000_000_000_000_000u64), // 0.01 ETH
+        ..Default::default()
+    };
+    let mock_uniswap =
+        MockUniswapRouter::new().with_swap_simulation(from_token, to_token, simulation);
+    let router = mock_uniswap.router_address();
 
-    assert_eq!(tool.name(), "get_balance");
-}
+    let params = json!({
+        "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "1.0"
+    });
 
-#[test]
-fn test_get_balance_tool_description() {
-    let wallet_addr: Address = Address::zero();
-    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
-    let tool = GetBalanceTool::new(Arc::new(mock_client));
+    // No allowance: approve (46k gas) + swap (200k gas)
+    let mock_client = MockEthereumClient::new()
+        .with_wallet_address(wallet_addr)
+        .with_permit_support(from_token);
+    let mock_uniswap = Arc::new(mock_uniswap);
+    let tool = SwapTokensTool::new(Arc::new(mock_client), mock_uniswap.clone());
+    let result = tool.execute(params.clone()).await.unwrap();
 
-    let description = tool.description();
-    assert!(!description.is_empty());
-    assert!(description.contains("balance") || description.contains("Balance"));
-}
+    assert_eq!(result["approval_required"], true);
+    assert_eq!(result["permit_supported"], true);
+    assert_eq!(result["steps"].as_array().unwrap().len(), 2);
+    assert_eq!(result["steps"][0]["action"], "approve");
+    assert_eq!(result["steps"][0]["gas_estimate"], "46000");
+    assert_eq!(result["steps"][0]["estimated_gas_cost_eth"], "0.0023");
+    assert_eq!(result["steps"][1]["action"], "swap");
+    assert_eq!(result["total_gas_estimate"], "246000");
+    assert_eq!(result["total_gas_cost_eth"], "0.0123");
+    assert_eq!(result["plan"]["steps"][0]["action"], "approve");
+    assert_eq!(result["plan"]["steps"][1]["action"], "swap");
+    // 0.5 out with 0.5% slippage
+    assert_eq!(
+        result["plan"]["steps"][1]["amount_out_min"],
+        "497500000000000000"
+    );
 
-#[test]
-fn test_get_balance_tool_input_schema() {
-    let wallet_addr: Address = Address::zero();
-    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
-    let tool = GetBalanceTool::new(Arc::new(mock_client));
+    // Sufficient allowance: the swap stands alone
+    let mock_client = MockEthereumClient::new()
+        .with_wallet_address(wallet_addr)
+        .with_allowance(from_token, wallet_addr, router, U256::MAX);
+    let tool = SwapTokensTool::new(Arc::new(mock_client), mock_uniswap);
+    let result = tool.execute(params).await.unwrap();
 
-    let schema = tool.input_schema();
+    assert_eq!(result["approval_required"], false);
+    assert_eq!(result["current_allowance"], "unlimited");
+    assert_eq!(result["steps"].as_array().unwrap().len(), 1);
+    assert_eq!(result["total_gas_estimate"], "200000");
+}
 
-    // Verify it's an object type
-    assert_eq!(schema["type"], "object");
+#[tokio::test]
+async fn test_swap_tokens_approval_strategies() {
+    use crate::tools::approval_strategy::{ApprovalDefaults, ApprovalStrategy};
 
-    // Verify it has properties
-    assert!(schema["properties"].is_object());
-    assert!(schema["properties"]["address"].is_object());
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let usdt: Address = "0xdAC17F958D2ee523a2206206994597C13D831ec7"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let simulation = SwapSimulation {
+        amount_in: U256::exp10(18),
+        amount_out: U256::from_dec_str("500000000000000000").unwrap(),
+        gas_estimate: U256::from(200000),
+        gas_price: U256::from(50_000_000_000u64),
+        gas_cost: U256::from(10_000_000_000_000_000u64),
+        ..Default::default()
+    };
+    let mock_uniswap =
+        Arc::new(MockUniswapRouter::new().with_swap_simulation(usdt, weth, simulation));
+    let router = mock_uniswap.router_address();
+    let params = json!({
+        "from_token": "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "1"
+    });
 
-    // Verify required fields
-    assert!(schema["required"].is_array());
-    let required = schema["required"].as_array().unwrap();
-    assert!(required.contains(&json!("address")));
+    // From no allowance, the server default buffer applies and no reset is needed
+    let tool = SwapTokensTool::new(
+        Arc::new(MockEthereumClient::new().with_wallet_address(wallet_addr)),
+        mock_uniswap.clone(),
+    )
+    .with_approval_defaults(ApprovalDefaults {
+        strategy: ApprovalStrategy::ExactPlusBuffer,
+        buffer_percent: Decimal::new(25, 1),
+    });
+    let result = tool.execute(params.clone()).await.unwrap();
+    assert_eq!(result["approval_strategy"], "exact_plus_buffer");
+    assert!(result.get("allowance_reset").is_none());
+    assert_eq!(result["plan"]["steps"][0]["amount"], "1025000000000000000");
+
+    // USDT with a leftover allowance is zeroed before the new one is set
+    let tool = SwapTokensTool::new(
+        Arc::new(
+            MockEthereumClient::new()
+                .with_wallet_address(wallet_addr)
+                .with_allowance(usdt, wallet_addr, router, U256::from(5u64)),
+        ),
+        mock_uniswap,
+    );
+    let mut unlimited = params;
+    unlimited["approval_strategy"] = json!("unlimited");
+    let result = tool.execute(unlimited).await.unwrap();
+    assert_eq!(result["allowance_reset"], true);
+    let steps = result["plan"]["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[0]["amount"], "0");
+    assert_eq!(steps[1]["amount"], U256::MAX.to_string());
+    assert_eq!(steps[2]["action"], "swap");
+    assert_eq!(result["total_gas_estimate"], "292000");
 }
 
-#[test]
-fn test_get_token_price_tool_name() {
-    let wallet_addr: Address = Address::zero();
+#[tokio::test]
+async fn test_swap_tokens_flags_reverting_estimate() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let simulation = SwapSimulation {
+        amount_in: U256::exp10(18),
+        amount_out: U256::from_dec_str("500000000000000000").unwrap(),
+        gas_estimate: U256::from(200000),
+        gas_price: U256::from(50_000_000_000u64),
+        gas_cost: U256::from(10_000_000_000_000_000u64),
+        gas_estimate_error: Some("UniswapV2: K".to_string()),
+        ..Default::default()
+    };
+    let mock_uniswap =
+        Arc::new(MockUniswapRouter::new().with_swap_simulation(from_token, to_token, simulation));
+    let router = mock_uniswap.router_address();
+    let params = json!({
+        "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "1"
+    });
+
+    // The router may already spend the tokens, so the revert is real
+    let approved = MockEthereumClient::new()
+        .with_wallet_address(wallet_addr)
+        .with_allowance(from_token, wallet_addr, router, U256::MAX);
+    let result = SwapTokensTool::new(Arc::new(approved), mock_uniswap.clone())
+        .execute(params.clone())
+        .await
+        .unwrap();
+    assert_eq!(result["will_likely_revert"], true);
+    assert_eq!(result["revert_reason"], "UniswapV2: K");
+    assert_eq!(result["gas_estimate_confidence"], "fallback");
+    assert!(result["warnings"][0]
+        .as_str()
+        .unwrap()
+        .starts_with("Gas estimation reverted: UniswapV2: K"));
+
+    // Before the approval the estimate cannot pass, which is no sign of a doomed swap
+    let unapproved = MockEthereumClient::new().with_wallet_address(wallet_addr);
+    let result = SwapTokensTool::new(Arc::new(unapproved), mock_uniswap)
+        .execute(params)
+        .await
+        .unwrap();
+    assert_eq!(result["will_likely_revert"], false);
+    assert!(result.get("revert_reason").is_none());
+    assert_eq!(result["gas_estimate_confidence"], "fallback");
+    assert!(result["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("could not be estimated before its approval"));
+}
+
+#[tokio::test]
+async fn test_swap_tokens_applies_configured_slippage() {
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap(); // USDC
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap(); // WETH
+    let tool = SwapTokensTool::new(
+        Arc::new(MockEthereumClient::new()),
+        Arc::new(quoted_router("500000000000000000")),
+    )
+    .with_slippage_defaults(SlippageDefaults {
+        default_percent: 0.3,
+        pairs: vec![(to_token, from_token, 1.0)],
+        tokens: Vec::new(),
+    });
+    let params = json!({
+        "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "1"
+    });
+
+    let result = tool.execute(params.clone()).await.unwrap();
+    assert_eq!(result["slippage_tolerance"], 1.0);
+    assert_eq!(result["slippage_source"], "pair");
+    assert_eq!(result["minimum_amount_out"], "0.495");
+
+    let mut requested = params;
+    requested["slippage_tolerance"] = json!(0.5);
+    let result = tool.execute(requested).await.unwrap();
+    assert_eq!(result["slippage_tolerance"], 0.5);
+    assert_eq!(result["slippage_source"], "requested");
+}
+
+#[tokio::test]
+async fn test_swap_tokens_tool_fee_on_transfer() {
+    let wallet_addr: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let from_token: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap(); // WETH
+
+    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
+
+    // Token with a 5% transfer tax: 0.5 quoted, 0.475 realized
+    let simulation = SwapSimulation {
+        amount_in: U256::from_dec_str("1000000000000000000").unwrap(),
+        amount_out: U256::from_dec_str("475000000000000000").unwrap(),
+        gas_estimate: U256::from(250000),
+        gas_price: U256::from(50_000_000_000u64),
+        gas_cost: U256::from(12_500_000_000_000_000u64),
+        fee_on_transfer: true,
+        transfer_fee_bps: Some(500),
+        ..Default::default()
+    };
+
+    let mock_uniswap =
+        MockUniswapRouter::new().with_swap_simulation(from_token, to_token, simulation);
+    let tool = SwapTokensTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+
+    let params = json!({
+        "from_token": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "1.0"
+    });
+
+    let result = tool.execute(params).await.unwrap();
+
+    assert_eq!(result["fee_on_transfer"], true);
+    assert_eq!(result["detected_transfer_fee_percent"], "5");
+    assert_eq!(
+        result["router_method"],
+        "swapExactTokensForTokensSupportingFeeOnTransferTokens"
+    );
+    assert_eq!(result["estimated_amount_out"], "0.475");
+}
+
+#[test]
+fn test_transfer_fee_bps() {
+    use crate::ethereum::uniswap::transfer_fee_bps;
+
+    let quoted = U256::from(1_000_000u64);
+    assert_eq!(transfer_fee_bps(quoted, U256::from(950_000u64)), 500);
+    assert_eq!(transfer_fee_bps(quoted, quoted), 0);
+    assert_eq!(transfer_fee_bps(quoted, U256::from(2_000_000u64)), 0);
+    assert_eq!(transfer_fee_bps(U256::zero(), U256::zero()), 0);
+}
+
+// Tests for Tool trait methods: name, description, input_schema
+
+#[test]
+fn test_get_balance_tool_name() {
+    let wallet_addr: Address = Address::zero();
+    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
+    let tool = GetBalanceTool::new(Arc::new(mock_client));
+
+    assert_eq!(tool.name(), "get_balance");
+}
+
+#[test]
+fn test_get_balance_tool_description() {
+    let wallet_addr: Address = Address::zero();
+    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
+    let tool = GetBalanceTool::new(Arc::new(mock_client));
+
+    let description = tool.description();
+    assert!(!description.is_empty());
+    assert!(description.contains("balance") || description.contains("Balance"));
+}
+
+#[test]
+fn test_get_balance_tool_input_schema() {
+    let wallet_addr: Address = Address::zero();
+    let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
+    let tool = GetBalanceTool::new(Arc::new(mock_client));
+
+    let schema = tool.input_schema();
+
+    // Verify it's an object type
+    assert_eq!(schema["type"], "object");
+
+    // Verify it has properties
+    assert!(schema["properties"].is_object());
+    assert!(schema["properties"]["address"].is_object());
+
+    // Verify required fields
+    assert!(schema["required"].is_array());
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&json!("address")));
+}
+
+#[test]
+fn test_get_token_price_tool_name() {
+    let wallet_addr: Address = Address::zero();
     let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
     let mock_uniswap = MockUniswapRouter::new();
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     assert_eq!(tool.name(), "get_token_price");
 }
@@ -285,7 +1280,7 @@ fn test_get_token_price_tool_description() {
     let wallet_addr: Address = Address::zero();
     let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
     let mock_uniswap = MockUniswapRouter::new();
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     let description = tool.description();
     assert!(!description.is_empty());
@@ -297,7 +1292,7 @@ fn test_get_token_price_tool_input_schema() {
     let wallet_addr: Address = Address::zero();
     let mock_client = MockEthereumClient::new().with_wallet_address(wallet_addr);
     let mock_uniswap = MockUniswapRouter::new();
-    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap));
+    let tool = GetTokenPriceTool::new(Arc::new(mock_client), Arc::new(mock_uniswap)).unwrap();
 
     let schema = tool.input_schema();
 
@@ -326,6 +1321,26 @@ fn test_get_token_price_tool_input_schema() {
     }
 }
 
+#[test]
+fn test_merge_schema_properties() {
+    use crate::tools::merge_schema_properties;
+
+    let mut properties = json!({ "address": { "type": "string" } });
+    merge_schema_properties(
+        &mut properties,
+        json!({ "max_staleness": { "type": "integer" } }),
+    );
+    assert!(properties["address"].is_object());
+    assert!(properties["max_staleness"].is_object());
+
+    // Malformed schemas are left alone instead of aborting the server
+    let mut not_an_object = json!("address");
+    merge_schema_properties(&mut not_an_object, json!({ "limit": {} }));
+    assert_eq!(not_an_object, json!("address"));
+    merge_schema_properties(&mut properties, json!(null));
+    assert_eq!(properties.as_object().unwrap().len(), 2);
+}
+
 #[test]
 fn test_swap_tokens_tool_name() {
     let wallet_addr: Address = Address::zero();
@@ -374,3 +1389,5133 @@ fn test_swap_tokens_tool_input_schema() {
     assert!(required.contains(&json!("to_token")));
     assert!(required.contains(&json!("amount")));
 }
+
+#[test]
+fn test_page_params_paginate() {
+    use crate::tools::PageParams;
+
+    let items: Vec<u32> = (0..120).collect();
+
+    let first = PageParams::default().paginate(items.clone()).unwrap();
+    assert_eq!(first.items.len(), 50);
+    assert_eq!(first.total, Some(120));
+    assert_eq!(first.next_cursor.as_deref(), Some("50"));
+
+    let last = PageParams {
+        cursor: Some("100".to_string()),
+        limit: Some(50),
+    }
+    .paginate(items.clone())
+    .unwrap();
+    assert_eq!(last.items, (100..120).collect::<Vec<u32>>());
+    assert!(last.next_cursor.is_none());
+
+    let bad_cursor = PageParams {
+        cursor: Some("500".to_string()),
+        limit: None,
+    };
+    assert!(bad_cursor.paginate(items.clone()).is_err());
+
+    // A source that returns only the head of the list
+    let bounded = PageParams {
+        cursor: Some("10".to_string()),
+        limit: None,
+    }
+    .bounded(10, 20);
+    assert_eq!(bounded.fetch_limit().unwrap(), 21);
+    let head = bounded.paginate_head(items[..21].to_vec()).unwrap();
+    assert_eq!(head.items, (10..20).collect::<Vec<u32>>());
+    assert!(head.total.is_none());
+    assert_eq!(head.next_cursor.as_deref(), Some("20"));
+}
+
+#[test]
+fn test_page_params_paginate_by_key() {
+    use crate::tools::PageParams;
+
+    // Keys 5..25; the cursor still works after the oldest items are dropped
+    let items: Vec<u64> = (5..25).collect();
+    let first = PageParams {
+        cursor: None,
+        limit: Some(8),
+    }
+    .paginate_by_key(items.clone(), |item| *item)
+    .unwrap();
+    assert_eq!(first.items, (5..13).collect::<Vec<u64>>());
+    assert_eq!(first.next_cursor.as_deref(), Some("12"));
+
+    let rest = PageParams {
+        cursor: first.next_cursor,
+        limit: Some(8),
+    }
+    .paginate_by_key(items[4..].to_vec(), |item| *item)
+    .unwrap();
+    assert_eq!(rest.items, (13..21).collect::<Vec<u64>>());
+    assert_eq!(rest.next_cursor.as_deref(), Some("20"));
+}
+
+#[tokio::test]
+async fn test_recommend_slippage_tool_with_mock() {
+    use crate::ethereum::{PoolReserves, PricePoint};
+
+    let from_token: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap(); // UNI
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap(); // WETH
+
+    // 1,000 UNI in the pool; trading 10 UNI moves the price ~1%
+    let reserves = PoolReserves {
+        pair_address: Address::repeat_byte(0x11),
+        reserve_in: U256::exp10(21),
+        reserve_out: U256::exp10(19),
+    };
+    let prices = [100, 101, 100, 101, 100, 101]
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| PricePoint {
+            block_number: i as u64,
+            price: Decimal::new(p, 4),
+        })
+        .collect();
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_pool_reserves(from_token, to_token, reserves)
+        .with_recent_prices(from_token, to_token, prices);
+    let tool =
+        RecommendSlippageTool::new(Arc::new(MockEthereumClient::new()), Arc::new(mock_uniswap));
+
+    let params = json!({
+        "from_token": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "10"
+    });
+
+    let result = tool.execute(params).await.unwrap();
+
+    assert_eq!(result["price_impact_percent"], "0.99");
+    assert_eq!(result["price_samples"], 6);
+    assert_eq!(result["confidence"], "normal");
+    let recommended =
+        Decimal::from_str_exact(result["recommended_slippage_percent"].as_str().unwrap()).unwrap();
+    assert!(recommended > Decimal::new(5, 1) && recommended <= Decimal::new(5, 0));
+}
+
+#[tokio::test]
+async fn test_recommend_slippage_scales_by_token_decimals() {
+    use crate::ethereum::{PoolReserves, PricePoint};
+
+    let usdc = Address::repeat_byte(0xcc);
+    let weth = Address::repeat_byte(0xee);
+
+    // 10M USDC in the pool; a 10,000 USDC trade is a 0.1% impact, not a pinned cap
+    let reserves = PoolReserves {
+        pair_address: Address::repeat_byte(0x11),
+        reserve_in: U256::from(10_000_000u64) * U256::exp10(6),
+        reserve_out: U256::exp10(22),
+    };
+    let prices = vec![PricePoint {
+        block_number: 1,
+        price: Decimal::new(4, 4),
+    }];
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_pool_reserves(usdc, weth, reserves)
+        .with_recent_prices(usdc, weth, prices);
+    let client = MockEthereumClient::new().with_token_decimals(usdc, 6);
+    let tool = RecommendSlippageTool::new(Arc::new(client), Arc::new(mock_uniswap));
+
+    let result = tool
+        .execute(json!({
+            "from_token": format!("{:?}", usdc),
+            "to_token": format!("{:?}", weth),
+            "amount": "10000"
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["price_impact_percent"], "0.0999");
+    assert_eq!(result["recommended_slippage_percent"], "0.15");
+}
+
+#[test]
+fn test_recommend_slippage_bounds() {
+    use crate::tools::recommend_slippage::recommend_slippage_percent;
+
+    // Calm, deep pool bottoms out at the minimum
+    assert_eq!(
+        recommend_slippage_percent(Decimal::ZERO, Decimal::ZERO).to_string(),
+        "0.1"
+    );
+    // Rounded up to the next 0.05%
+    assert_eq!(
+        recommend_slippage_percent(Decimal::new(12, 2), Decimal::ZERO).to_string(),
+        "0.35"
+    );
+    // Wild markets are capped
+    assert_eq!(
+        recommend_slippage_percent(Decimal::from(10), Decimal::from(50)).to_string(),
+        "5"
+    );
+}
+
+#[tokio::test]
+async fn test_get_market_stats_tool_with_mock() {
+    use crate::ethereum::{MockUniswapV3, PoolReserves, PoolSwap, PricePoint, V3PoolState};
+
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+
+    let mock_client = MockEthereumClient::new()
+        .with_token_decimals(usdc, 6)
+        .with_block_number(10_000);
+
+    // 100 WETH / 200,000 USDC => 2,000 USDC per WETH
+    let reserves = PoolReserves {
+        pair_address: Address::repeat_byte(0x22),
+        reserve_in: U256::exp10(20),
+        reserve_out: U256::from(200_000u64) * U256::exp10(6),
+    };
+    // Raw price of 2,000 USDC per WETH is 2e-9; one update falls outside the 1h window
+    let points = [(5_000, 2000), (9_800, 2000), (9_900, 2020), (10_000, 2000)]
+        .into_iter()
+        .map(|(block_number, price)| PricePoint {
+            block_number,
+            price: Decimal::new(price, 12),
+        })
+        .collect();
+    let swaps = vec![
+        PoolSwap {
+            block_number: 9_900,
+            base_amount: U256::exp10(18),
+            quote_amount: U256::from(2_000u64) * U256::exp10(6),
+        },
+        PoolSwap {
+            block_number: 9_950,
+            base_amount: U256::exp10(18) * 3,
+            quote_amount: U256::from(6_000u64) * U256::exp10(6),
+        },
+    ];
+    let mock_uniswap = MockUniswapRouter::new()
+        .with_pool_reserves(weth, usdc, reserves)
+        .with_recent_prices(weth, usdc, points)
+        .with_recent_swaps(weth, usdc, swaps);
+
+    // USDC (0xA0..) sorts before WETH, so it is token0; sqrtPriceX96 for 1/2000e12
+    let v3_pool = V3PoolState {
+        pool_address: Address::repeat_byte(0x33),
+        token0: usdc,
+        fee: 500,
+        sqrt_price_x96: U256::from_dec_str("1771595571142957166518320255467520").unwrap(),
+        tick: 0,
+        liquidity: 1_000_000,
+    };
+    let mock_v3 = MockUniswapV3::new().with_pool(usdc, weth, v3_pool);
+
+    let tool = GetMarketStatsTool::new(
+        Arc::new(mock_client),
+        Arc::new(mock_uniswap),
+        Arc::new(mock_v3),
+    );
+
+    let params = json!({
+        "base_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "quote_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "volatility_windows_hours": [1, 24]
+    });
+
+    let result = tool.execute(params).await.unwrap();
+
+    assert_eq!(result["trade_count_24h"], 2);
+    assert_eq!(result["volume_24h_base"], "4");
+    assert_eq!(result["volume_24h_quote"], "8000");
+    assert_eq!(result["average_trade_size_base"], "2");
+    assert_eq!(result["v2_price"], "2000");
+    assert_eq!(result["v3_fee_tier"], 500);
+    assert_eq!(result["volatility"][0]["price_updates"], 3);
+    assert_eq!(result["volatility"][1]["price_updates"], 4);
+    let v3_price: f64 = result["v3_price"].as_str().unwrap().parse().unwrap();
+    assert!((v3_price - 2000.0).abs() < 1.0);
+    let spread: f64 = result["v2_v3_spread_bps"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(spread.abs() < 5.0);
+}
+
+#[tokio::test]
+async fn test_get_market_stats_rejects_unsupported_decimals() {
+    use crate::ethereum::{MockUniswapV3, PoolReserves};
+
+    // A token reporting 40 decimals against 6-decimal USDC must not overflow the scale
+    let hostile = Address::repeat_byte(0x66);
+    let usdc = Address::repeat_byte(0xcc);
+    let mock_client = MockEthereumClient::new()
+        .with_token_decimals(hostile, 40)
+        .with_token_decimals(usdc, 6)
+        .with_block_number(10_000);
+    let reserves = PoolReserves {
+        pair_address: Address::repeat_byte(0x22),
+        reserve_in: U256::exp10(20),
+        reserve_out: U256::exp10(20),
+    };
+    let mock_uniswap = MockUniswapRouter::new().with_pool_reserves(hostile, usdc, reserves);
+    let tool = GetMarketStatsTool::new(
+        Arc::new(mock_client),
+        Arc::new(mock_uniswap),
+        Arc::new(MockUniswapV3::new()),
+    );
+
+    let err = tool
+        .execute(json!({
+            "base_token": format!("{:?}", hostile),
+            "quote_token": format!("{:?}", usdc),
+        }))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("decimals"));
+}
+
+#[tokio::test]
+async fn test_get_market_stats_rejects_bad_window() {
+    use crate::ethereum::MockUniswapV3;
+
+    let tool = GetMarketStatsTool::new(
+        Arc::new(MockEthereumClient::new()),
+        Arc::new(MockUniswapRouter::new()),
+        Arc::new(MockUniswapV3::new()),
+    );
+
+    let params = json!({
+        "base_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "quote_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "volatility_windows_hours": [0]
+    });
+
+    let err = tool.execute(params).await.unwrap_err().to_string();
+    assert!(err.contains("out of range"));
+}
+
+#[tokio::test]
+async fn test_get_twap_price_averages_pool_observations() {
+    use crate::ethereum::uniswap_v3::mean_tick;
+    use crate::ethereum::{MockUniswapV3, V3PoolState};
+
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let pool_address = Address::repeat_byte(0x33);
+
+    // Spot is 2000 USDC per WETH; tick 200824 held for the last hour is about 1900
+    let pool = V3PoolState {
+        pool_address,
+        token0: usdc,
+        fee: 500,
+        sqrt_price_x96: U256::from_dec_str("1771595571142957166518320255467520").unwrap(),
+        tick: 200311,
+        liquidity: 1_000_000,
+    };
+    let mock_v3 = MockUniswapV3::new()
+        .with_pool(usdc, weth, pool)
+        .with_observations(pool_address, 200824, 3600);
+    let client = MockEthereumClient::new().with_token_decimals(usdc, 6);
+    let tool = GetTwapPriceTool::new(Arc::new(client), Arc::new(mock_v3));
+
+    let result = tool
+        .execute(json!({ "token_in": format!("{:?}", weth), "token_out": format!("{:?}", usdc) }))
+        .await
+        .unwrap();
+    let decimal = |value: &Value| value.as_str().unwrap().parse::<Decimal>().unwrap();
+    assert_eq!(result["window_secs"], 1800);
+    assert_eq!(result["twap_tick"], 200824);
+    let twap = decimal(&result["twap_price"]);
+    assert!(twap > Decimal::from(1899) && twap < Decimal::from(1901));
+    assert_eq!(decimal(&result["spot_price"]), Decimal::from(2000));
+    assert!(decimal(&result["spot_deviation_percent"]) > Decimal::from(5));
+    assert!(result["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("rely on the TWAP"));
+
+    let err = tool
+        .execute(json!({
+            "token_in": format!("{:?}", weth),
+            "token_out": format!("{:?}", usdc),
+            "window_secs": 7200
+        }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not reach back 7200 seconds"));
+
+    let err = tool
+        .execute(json!({
+            "token_in": format!("{:?}", weth),
+            "token_out": format!("{:?}", usdc),
+            "fee": 3000
+        }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No Uniswap V3 pool"));
+
+    // Negative averages round down, as the V3 oracle library does
+    assert_eq!(mean_tick(0, -7, 2), -4);
+    assert_eq!(mean_tick(0, 7, 2), 3);
+}
+
+#[tokio::test]
+async fn test_suggest_tx_timing_follows_daily_pattern_and_spikes() {
+    let decimal = |value: &Value| value.as_str().unwrap().parse::<Decimal>().unwrap();
+
+    // Block 28800 starts a day; every day the base fee drops to 10 gwei three hours in
+    let daily = |block: u64| {
+        let hour = (block % 7200) / 300;
+        let fee = if (3..5).contains(&hour) { 10u64 } else { 30 };
+        U256::from(fee) * U256::exp10(9)
+    };
+    let client = MockEthereumClient::new()
+        .with_block_number(28800)
+        .with_base_fee_curve(daily);
+    let tool = SuggestTxTimingTool::new(Arc::new(client));
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["recommendation"], "wait");
+    assert_eq!(result["wait_hours"], 3);
+    assert_eq!(decimal(&result["current_base_fee_gwei"]), Decimal::from(30));
+    assert_eq!(
+        decimal(&result["expected_base_fee_gwei"]),
+        Decimal::from(10)
+    );
+    assert_eq!(decimal(&result["estimated_savings"]), Decimal::new(3, 3));
+    assert_eq!(result["outlook"].as_array().unwrap().len(), 6);
+    assert_eq!(result["outlook"][2]["days_sampled"], 3);
+
+    // A jump over the recent median is waited out rather than projected forward
+    let spike = |block: u64| {
+        let fee = if block > 28800 { 60u64 } else { 30 };
+        U256::from(fee) * U256::exp10(9)
+    };
+    let client = MockEthereumClient::new()
+        .with_block_number(28800)
+        .with_base_fee_curve(spike);
+    let result = SuggestTxTimingTool::new(Arc::new(client))
+        .execute(json!({ "gas_units": 21000 }))
+        .await
+        .unwrap();
+    assert_eq!(result["recommendation"], "wait_for_spike");
+    assert_eq!(
+        decimal(&result["estimated_savings_percent"]),
+        Decimal::from(50)
+    );
+
+    let client = MockEthereumClient::new()
+        .with_block_number(28800)
+        .with_base_fee_curve(|_| U256::from(30) * U256::exp10(9));
+    let result = SuggestTxTimingTool::new(Arc::new(client))
+        .execute(json!({}))
+        .await
+        .unwrap();
+    assert_eq!(result["recommendation"], "send_now");
+    assert_eq!(result["recent"]["trend"], "flat");
+    assert_eq!(decimal(&result["estimated_savings"]), Decimal::ZERO);
+
+    let err = SuggestTxTimingTool::new(Arc::new(MockEthereumClient::new()))
+        .execute(json!({ "max_wait_hours": 48 }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("max_wait_hours"));
+}
+
+#[tokio::test]
+async fn test_scan_arbitrage_tool_with_mock() {
+    use crate::ethereum::{MockVenue, PriceVenue};
+
+    let uni: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    // 30 gwei * 300k gas = 0.009 ETH to buy on one venue and sell on the other
+    let mock_client = MockEthereumClient::new();
+    let venues: Vec<Arc<dyn PriceVenue>> = vec![
+        Arc::new(MockVenue::new("uniswap_v2").with_rate(uni, weth, Decimal::new(40, 4))),
+        Arc::new(MockVenue::new("sushiswap").with_rate(uni, weth, Decimal::new(42, 4))),
+        Arc::new(MockVenue::new("curve")),
+    ];
+
+    let tool = ScanArbitrageTool::new(Arc::new(mock_client), venues);
+
+    let params = json!({
+        "token": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984",
+        "amount": "100"
+    });
+
+    let result = tool.execute(params).await.unwrap();
+
+    assert_eq!(result["gas_price_gwei"], "30");
+    assert_eq!(result["quotes"][0]["amount_out"], "0.4");
+    assert_eq!(result["quotes"][1]["amount_out"], "0.42");
+    assert!(result["quotes"][2]["error"].is_string());
+
+    let opportunities = result["opportunities"].as_array().unwrap();
+    assert_eq!(opportunities.len(), 1);
+    assert_eq!(opportunities[0]["buy_venue"], "uniswap_v2");
+    assert_eq!(opportunities[0]["sell_venue"], "sushiswap");
+    assert_eq!(opportunities[0]["spread_bps"], "500");
+    assert_eq!(opportunities[0]["gross_profit"], "0.02");
+    assert_eq!(opportunities[0]["gas_cost"], "0.009");
+    assert_eq!(opportunities[0]["net_profit"], "0.011");
+}
+
+#[tokio::test]
+async fn test_scan_arbitrage_filters_unprofitable_spreads() {
+    use crate::ethereum::{MockVenue, PriceVenue};
+
+    let uni: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    let venues: Vec<Arc<dyn PriceVenue>> = vec![
+        Arc::new(MockVenue::new("uniswap_v2").with_rate(uni, weth, Decimal::new(40, 4))),
+        Arc::new(MockVenue::new("sushiswap").with_rate(uni, weth, Decimal::new(42, 4))),
+    ];
+    let tool = ScanArbitrageTool::new(Arc::new(MockEthereumClient::new()), venues);
+
+    // A 5% spread on 1 UNI is worth far less than the gas
+    let result = tool
+        .execute(json!({
+            "token": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        }))
+        .await
+        .unwrap();
+    assert!(result["opportunities"].as_array().unwrap().is_empty());
+
+    // Spreads below the threshold are dropped even when profitable
+    let result = tool
+        .execute(json!({
+            "token": "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984",
+            "amount": "100",
+            "min_spread_bps": 600
+        }))
+        .await
+        .unwrap();
+    assert!(result["opportunities"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_find_pools_tool_with_mock() {
+    use crate::ethereum::{FactoryKind, MockPoolDiscovery, PoolInfo, PoolLiquidity};
+
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let uni: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+
+    let v2_pool = PoolInfo {
+        dex: "uniswap_v2".to_string(),
+        kind: FactoryKind::V2,
+        pool_address: Address::repeat_byte(0x01),
+        token0: usdc,
+        token1: weth,
+        fee: 3000,
+        creation_block: 10_008_355,
+    };
+    let v3_pool = PoolInfo {
+        dex: "uniswap_v3".to_string(),
+        kind: FactoryKind::V3,
+        pool_address: Address::repeat_byte(0x02),
+        token0: usdc,
+        token1: weth,
+        fee: 500,
+        creation_block: 12_376_729,
+    };
+    let other_pool = PoolInfo {
+        dex: "sushiswap".to_string(),
+        kind: FactoryKind::V2,
+        pool_address: Address::repeat_byte(0x03),
+        token0: uni,
+        token1: weth,
+        fee: 3000,
+        creation_block: 10_829_000,
+    };
+    let discovery = MockPoolDiscovery::new()
+        .with_pool(
+            v3_pool,
+            PoolLiquidity::InRange {
+                liquidity: 1_000_000,
+            },
+        )
+        .with_pool(
+            v2_pool,
+            PoolLiquidity::Reserves {
+                reserve0: U256::from(2_000u64),
+                reserve1: U256::from(1u64),
+            },
+        )
+        .with_pool(
+            other_pool,
+            PoolLiquidity::Reserves {
+                reserve0: U256::from(5u64),
+                reserve1: U256::from(7u64),
+            },
+        );
+
+    let tool = FindPoolsTool::new(Arc::new(discovery));
+
+    let result = tool
+        .execute(json!({
+            "token_a": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "token_b": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["total"], 2);
+    assert_eq!(result["items"][0]["dex"], "uniswap_v2");
+    assert_eq!(result["items"][0]["reserve0"], "2000");
+    assert_eq!(result["items"][0]["creation_block"], 10_008_355);
+    assert_eq!(result["items"][1]["dex"], "uniswap_v3");
+    assert_eq!(result["items"][1]["fee_tier"], 500);
+    assert_eq!(result["items"][1]["liquidity"], "1000000");
+    assert!(result["items"][1].get("reserve0").is_none());
+
+    // One token lists every pool it trades in, paginated
+    let result = tool
+        .execute(json!({
+            "token_a": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "limit": 2
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["total"], 3);
+    assert_eq!(result["items"].as_array().unwrap().len(), 2);
+    assert_eq!(result["next_cursor"], "2");
+}
+
+fn sample_plan() -> serde_json::Value {
+    json!({
+        "steps": [
+            {
+                "action": "approve",
+                "token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                "spender": "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D",
+                "amount": "1000000"
+            },
+            {
+                "action": "swap",
+                "router": "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D",
+                "path": [
+                    "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                ],
+                "amount_in": "1000000",
+                "amount_out_min": "0"
+            },
+            {
+                "action": "transfer",
+                "to": "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+                "amount": "0x2386f26fc10000"
+            }
+        ]
+    })
+}
+
+#[tokio::test]
+async fn test_execute_plan_dry_run() {
+    let mock_client = Arc::new(MockEthereumClient::new());
+    let tool = ExecutePlanTool::new(mock_client.clone(), false);
+
+    let result = tool
+        .execute(json!({ "plan": sample_plan() }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["dry_run"], true);
+    assert_eq!(result["steps"][0]["status"], "simulated");
+    assert_eq!(result["steps"][2]["status"], "simulated");
+    assert_eq!(result["total_gas_estimate"], "300000");
+    assert!(result.get("resume_from").is_none());
+    assert!(mock_client.sent_transactions().is_empty());
+
+    // Live runs are refused unless the server enables execution
+    let err = tool
+        .execute(json!({ "plan": sample_plan(), "dry_run": false }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("disabled"));
+}
+
+#[tokio::test]
+async fn test_execute_plan_partial_failure_and_resume() {
+    let router: Address = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"
+        .parse()
+        .unwrap();
+
+    // The swap reverts: the approve is confirmed and the transfer never runs
+    let mock_client = Arc::new(MockEthereumClient::new().with_failing_target(router));
+    let tool = ExecutePlanTool::new(mock_client.clone(), true);
+    let result = tool
+        .execute(json!({ "plan": sample_plan(), "dry_run": false }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["steps"][0]["status"], "confirmed");
+    assert!(result["steps"][0]["tx_hash"].is_string());
+    assert_eq!(result["steps"][1]["status"], "failed");
+    assert_eq!(result["steps"][2]["status"], "not_run");
+    assert_eq!(result["completed_steps"], 1);
+    assert_eq!(result["resume_from"], 1);
+    assert_eq!(mock_client.sent_transactions().len(), 1);
+
+    // Resuming skips the confirmed approve
+    let mock_client = Arc::new(MockEthereumClient::new());
+    let tool = ExecutePlanTool::new(mock_client.clone(), true);
+    let result = tool
+        .execute(json!({ "plan": sample_plan(), "dry_run": false, "start_step": 1 }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["steps"][0]["status"], "skipped");
+    assert_eq!(result["steps"][1]["status"], "confirmed");
+    assert_eq!(result["steps"][2]["status"], "confirmed");
+    assert_eq!(result["completed_steps"], 3);
+    let sent = mock_client.sent_transactions();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].to_addr(), Some(&router));
+    assert_eq!(
+        sent[1].value().copied(),
+        Some(U256::from(10_000_000_000_000_000u64))
+    );
+}
+
+#[tokio::test]
+async fn test_execute_plan_alerts_sent_transactions() {
+    use crate::tools::execute_plan::TRANSACTION_TOPIC;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(String, AlertSeverity, Value)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), severity, alert.clone()));
+            1
+        }
+    }
+
+    let sink = Arc::new(RecordingSink::default());
+    let tool = ExecutePlanTool::new(Arc::new(MockEthereumClient::new()), true)
+        .with_alert_sink(sink.clone());
+
+    // Dry runs send nothing to alert about
+    tool.execute(json!({ "plan": sample_plan(), "dry_run": true }))
+        .await
+        .unwrap();
+    assert!(sink.sent.lock().unwrap().is_empty());
+
+    let result = tool
+        .execute(json!({ "plan": sample_plan(), "dry_run": false }))
+        .await
+        .unwrap();
+    let sent = sink.sent.lock().unwrap();
+    assert_eq!(sent.len(), 3);
+    for (index, (topic, severity, alert)) in sent.iter().enumerate() {
+        assert_eq!(topic, TRANSACTION_TOPIC);
+        assert_eq!(*severity, AlertSeverity::Info);
+        assert_eq!(alert["event"], "transaction_confirmed");
+        assert_eq!(alert["step"], index);
+        assert_eq!(alert["tx_hash"], result["steps"][index]["tx_hash"]);
+    }
+}
+
+#[tokio::test]
+async fn test_health_check_reports_node_and_background_tasks() {
+    use crate::supervisor::{RestartPolicy, Supervisor};
+    use std::time::Duration;
+
+    let mut supervisor = Supervisor::new();
+    supervisor.add("payment_scheduler", RestartPolicy::default(), |mut stop| {
+        tokio::spawn(async move {
+            let _ = stop.changed().await;
+        })
+    });
+    supervisor.add("watch_list", RestartPolicy::never(), |_stop| {
+        tokio::spawn(async { panic!("poll failed") })
+    });
+    let supervisor = Arc::new(supervisor);
+
+    let tool = HealthCheckTool::new(Arc::new(MockEthereumClient::new()), supervisor.clone());
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["status"], "ok");
+    assert_eq!(result["chain"]["chain_id"], 1);
+    assert!(result["chain"]["block_number"].is_u64());
+    assert_eq!(result["background_tasks"], json!([]));
+
+    supervisor.start();
+    let mut result = Value::Null;
+    for _ in 0..200 {
+        result = tool.execute(json!({})).await.unwrap();
+        if result["status"] == "degraded" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(result["status"], "degraded");
+    let tasks = result["background_tasks"].as_array().unwrap();
+    assert_eq!(tasks[0]["name"], "payment_scheduler");
+    assert_eq!(tasks[0]["state"], "running");
+    assert_eq!(tasks[1]["name"], "watch_list");
+    assert_eq!(tasks[1]["state"], "failed");
+    assert_eq!(tasks[1]["last_error"], "panicked: poll failed");
+    supervisor.stop().await;
+
+    let tool = HealthCheckTool::new(
+        Arc::new(MockEthereumClient::new().with_unavailable_rpc()),
+        supervisor,
+    );
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["status"], "unavailable");
+    assert!(result["chain"]["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_event_bus_delivers_to_every_subscriber() {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(String, Value)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send(&self, topic: &str, _severity: AlertSeverity, alert: &Value) -> usize {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), alert.clone()));
+            1
+        }
+    }
+
+    let bus = EventBus::new(16);
+    // Nobody is listening yet
+    assert_eq!(
+        bus.publish("transactions", AlertSeverity::Info, json!({})),
+        0
+    );
+
+    let (first, second) = (
+        Arc::new(RecordingSink::default()),
+        Arc::new(RecordingSink::default()),
+    );
+    let handles = [
+        bus.subscribe_sink("first", first.clone()),
+        bus.subscribe_sink("second", second.clone()),
+    ];
+    assert_eq!(bus.subscribers(), 2);
+
+    // Published through the sink interface the tools use
+    let alerts: Arc<dyn AlertSink> = Arc::new(bus.clone());
+    for step in 0..3 {
+        let sent = alerts
+            .send(
+                "transactions",
+                AlertSeverity::Info,
+                &json!({ "step": step }),
+            )
+            .await;
+        assert_eq!(sent, 2);
+    }
+    bus.publish(
+        "policy",
+        AlertSeverity::Warning,
+        json!({ "tool": "execute_swap" }),
+    );
+
+    for _ in 0..200 {
+        if first.sent.lock().unwrap().len() == 4 && second.sent.lock().unwrap().len() == 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    for sink in [&first, &second] {
+        let sent = sink.sent.lock().unwrap();
+        let topics: Vec<&str> = sent.iter().map(|(topic, _)| topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            ["transactions", "transactions", "transactions", "policy"]
+        );
+        assert_eq!(sent[2].1["step"], 2);
+    }
+
+    // Subscribers stop once every publisher is gone
+    drop((bus, alerts));
+    for handle in handles {
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_event_bus_lagging_subscriber_skips_missed_events() {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let bus = EventBus::new(2);
+    let mut events = bus.subscribe();
+    for index in 0..5 {
+        bus.publish(
+            "wallet_watch",
+            AlertSeverity::Info,
+            json!({ "index": index }),
+        );
+    }
+    assert!(matches!(events.recv().await, Err(RecvError::Lagged(3))));
+    assert_eq!(events.recv().await.unwrap().data["index"], 3);
+    assert_eq!(events.recv().await.unwrap().data["index"], 4);
+}
+
+#[tokio::test]
+async fn test_execute_plan_gas_escalation_overrides() {
+    use crate::ethereum::GasEscalation;
+
+    let server_policy = GasEscalation {
+        after_blocks: 3,
+        bump_percent: 15,
+        max_fee_per_gas: U256::from(100_000_000_000u64), // 100 gwei
+    };
+    let mock_client = Arc::new(MockEthereumClient::new());
+    let tool =
+        ExecutePlanTool::new(mock_client.clone(), true).with_gas_escalation(Some(server_policy));
+    let transfer = json!({ "steps": [sample_plan()["steps"][2].clone()] });
+
+    tool.execute(json!({ "plan": transfer, "dry_run": false }))
+        .await
+        .unwrap();
+
+    // A call may tighten the policy but not raise the server's ceiling
+    tool.execute(json!({
+        "plan": transfer,
+        "dry_run": false,
+        "gas_escalation": { "after_blocks": 1, "max_fee_gwei": "500" }
+    }))
+    .await
+    .unwrap();
+
+    tool.execute(json!({
+        "plan": transfer,
+        "dry_run": false,
+        "gas_escalation": { "enabled": false }
+    }))
+    .await
+    .unwrap();
+
+    assert_eq!(
+        mock_client.escalations(),
+        vec![
+            Some(server_policy),
+            Some(GasEscalation {
+                after_blocks: 1,
+                ..server_policy
+            }),
+            None,
+        ]
+    );
+
+    // Without a server ceiling the call must bring its own
+    let bare = ExecutePlanTool::new(mock_client, true);
+    let err = bare
+        .execute(json!({
+            "plan": transfer,
+            "dry_run": false,
+            "gas_escalation": { "bump_percent": 20 }
+        }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("max_fee_gwei"));
+}
+
+fn quoted_router(amount_out: &str) -> MockUniswapRouter {
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let simulation = SwapSimulation {
+        amount_in: U256::exp10(18),
+        amount_out: U256::from_dec_str(amount_out).unwrap(),
+        gas_estimate: U256::from(200000),
+        gas_price: U256::from(50_000_000_000u64),
+        gas_cost: U256::from(10_000_000_000_000_000u64),
+        ..Default::default()
+    };
+    MockUniswapRouter::new().with_swap_simulation(from_token, to_token, simulation)
+}
+
+async fn quote_id(quotes: &Arc<QuoteStore>, client: &Arc<MockEthereumClient>) -> String {
+    let tool = SwapTokensTool::new(
+        Arc::clone(client),
+        Arc::new(quoted_router("500000000000000000")),
+    )
+    .with_quote_store(Arc::clone(quotes));
+    let result = tool
+        .execute(json!({
+            "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "amount": "1",
+            "slippage_tolerance": 1.0
+        }))
+        .await
+        .unwrap();
+    assert!(result["quote_expires_at"].as_u64().is_some());
+    result["quote_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_execute_swap_requotes_within_bound() {
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let id = quote_id(&quotes, &client).await;
+
+    // Output dropped 0.2% since the quote, inside the 50 bps bound
+    let tool = ExecuteSwapTool::new(
+        client,
+        Arc::new(quoted_router("499000000000000000")),
+        quotes,
+        false,
+        50,
+    );
+    let result = tool.execute(json!({ "quote_id": id })).await.unwrap();
+
+    assert_eq!(result["requoted"], true);
+    assert_eq!(result["price_move_bps"], "-20");
+    assert_eq!(result["execution"]["dry_run"], true);
+    let steps = result["execution"]["steps"].as_array().unwrap();
+    assert_eq!(steps.last().unwrap()["status"], "simulated");
+}
+
+#[tokio::test]
+async fn test_execution_report_flags_sandwiched_swap() {
+    use crate::ethereum::{MockTokenEvents, TokenTransfer};
+
+    let wallet = Address::from_low_u64_be(0xa11ce);
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_block_number(100),
+    );
+    let id = quote_id(&quotes, &client).await;
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let pool = Address::from_low_u64_be(0x9001);
+    let attacker = Address::from_low_u64_be(0xbad);
+    // The approval is the first transaction sent, the swap the second
+    let swap_tx = H256::from_low_u64_be(2);
+    let transfer = |from, to, amount: &str, tx_hash| TokenTransfer {
+        token: weth,
+        from,
+        to,
+        amount: U256::from_dec_str(amount).unwrap(),
+        block_number: 100,
+        tx_hash,
+    };
+    let events = MockTokenEvents::new()
+        .with_token_transfer(transfer(
+            pool,
+            attacker,
+            "3000000000000000000",
+            H256::repeat_byte(1),
+        ))
+        .with_token_transfer(transfer(pool, wallet, "497000000000000000", swap_tx))
+        .with_token_transfer(transfer(
+            attacker,
+            pool,
+            "3000000000000000000",
+            H256::repeat_byte(2),
+        ));
+    let reports = Arc::new(ExecutionReports::new(Arc::new(events), 10));
+
+    let tool = ExecuteSwapTool::new(
+        client,
+        Arc::new(quoted_router("499000000000000000")),
+        quotes,
+        true,
+        50,
+    )
+    .with_execution_reports(Arc::clone(&reports));
+    let result = tool
+        .execute(json!({ "quote_id": id, "dry_run": false }))
+        .await
+        .unwrap();
+    let report = &result["execution_report"];
+    assert_eq!(report["realized_amount_out"], "497000000000000000");
+    assert_eq!(report["realized_slippage_bps"], "-60");
+    assert_eq!(report["slippage_since_send_bps"], "-40.08");
+    assert_eq!(report["mev_suspected"], true);
+    assert_eq!(report["sandwiches"][0]["attacker"], json!(attacker));
+    assert_eq!(report["execution_block"], 100);
+
+    let tool = GetExecutionReportTool::new(reports);
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["summary"]["executions"], 1);
+    assert_eq!(result["summary"]["mev_suspected"], 1);
+    assert_eq!(result["summary"]["worst_slippage_bps"], "-60");
+    let observations = result["summary"]["observations"].as_array().unwrap();
+    assert!(observations[0]
+        .as_str()
+        .unwrap()
+        .contains("1 of 1 swaps were sandwiched"));
+    assert!(observations[1]
+        .as_str()
+        .unwrap()
+        .contains("40.08 bps below"));
+    assert_eq!(result["executions"][0]["quote_id"], id);
+
+    let err = tool
+        .execute(json!({ "quote_id": "q_unknown" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No execution recorded"));
+}
+
+#[tokio::test]
+async fn test_generate_tax_report_matches_lots_by_method() {
+    use crate::ethereum::MockReferencePrices;
+
+    let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let uni: Address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"
+        .parse()
+        .unwrap();
+    let client = MockEthereumClient::new()
+        .with_token_symbol(usdc, "USDC".to_string())
+        .with_token_decimals(usdc, 6)
+        .with_token_symbol(weth, "WETH".to_string())
+        .with_token_symbol(uni, "UNI".to_string());
+    // Only trades without a stablecoin side use the oracle
+    let ledger = Arc::new(TradeLedger::new(1).with_reference_prices(Arc::new(
+        MockReferencePrices::new().with_price(uni, Decimal::new(10, 0)),
+    )));
+    let usd = |amount: u64| (usdc, U256::from(amount) * U256::exp10(6));
+    let eth = |tenths: u64| (weth, U256::from(tenths) * U256::exp10(17));
+
+    // Bought at $2000 and $3000, then 1.5 sold at $4000 and the rest swapped for UNI
+    ledger
+        .record(&client, usd(2000), eth(10), None, 1_704_888_000)
+        .await
+        .unwrap();
+    ledger
+        .record(&client, usd(3000), eth(10), None, 1_717_243_200)
+        .await
+        .unwrap();
+    ledger
+        .record(&client, eth(15), usd(6000), None, 1_740_830_400)
+        .await
+        .unwrap();
+    let trade = ledger
+        .record(
+            &client,
+            eth(5),
+            (uni, U256::from(250) * U256::exp10(18)),
+            Some(H256::repeat_byte(7)),
+            1_751_371_200,
+        )
+        .await
+        .unwrap();
+    assert_eq!(trade.usd_value, Some(Decimal::new(2500, 0)));
+
+    let tool = GenerateTaxReportTool::new(Arc::clone(&ledger), AccountingMethod::Fifo);
+    let result = tool.execute(json!({ "year": 2025 })).await.unwrap();
+    assert_eq!(result["method"], "fifo");
+    assert_eq!(result["disposals"], 3);
+    // 1 WETH from January 2024 held over a year, then the June lot, held over a year
+    // by the July sale
+    let csv = result["csv"].as_str().unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].starts_with("Description of property,Date acquired"));
+    assert_eq!(
+        lines[1],
+        "1 WETH,2024-01-10,2025-03-01,4000.00,2000.00,2000.00,long,"
+    );
+    assert_eq!(
+        lines[2],
+        "0.5 WETH,2024-06-01,2025-03-01,2000.00,1500.00,500.00,short,"
+    );
+    assert!(
+        lines[3].starts_with("0.5 WETH,2024-06-01,2025-07-01,2500.00,1500.00,1000.00,long,0x0707")
+    );
+    assert_eq!(result["long_term_gain"], "3000.00");
+    assert_eq!(result["short_term_gain"], "500.00");
+
+    // Highest cost first sells the June lot before the January one
+    let result = tool
+        .execute(json!({ "year": 2025, "method": "hifo", "format": "json" }))
+        .await
+        .unwrap();
+    let rows = result["rows"].as_array().unwrap();
+    assert_eq!(rows[0]["amount"], "1");
+    assert_eq!(rows[0]["cost_basis"], "3000.00");
+    assert_eq!(rows[0]["term"], "short");
+    assert_eq!(rows[1]["cost_basis"], "1000.00");
+    assert_eq!(rows[1]["term"], "long");
+    assert_eq!(result["total_proceeds"], "8500.00");
+
+    // Selling more than the ledger saw bought leaves the rest without a basis
+    ledger
+        .record(
+            &client,
+            (uni, U256::from(300) * U256::exp10(18)),
+            usd(3000),
+            None,
+            1_751_371_300,
+        )
+        .await
+        .unwrap();
+    let result = tool
+        .execute(json!({ "year": 2025, "format": "json" }))
+        .await
+        .unwrap();
+    let last = result["rows"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["amount"], "50");
+    assert_eq!(last["term"], "unknown");
+    assert!(last.get("acquired_at").is_none());
+    assert_eq!(result["incomplete_rows"], 1);
+
+    let result = tool.execute(json!({ "year": 2024 })).await.unwrap();
+    assert_eq!(result["disposals"], 0);
+}
+
+#[tokio::test]
+async fn test_execute_swap_refuses_moved_market() {
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let id = quote_id(&quotes, &client).await;
+
+    let tool = ExecuteSwapTool::new(
+        client,
+        Arc::new(quoted_router("490000000000000000")),
+        quotes,
+        false,
+        50,
+    );
+    let err = tool
+        .execute(json!({ "quote_id": id }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("-200 bps"), "{}", err);
+
+    let unknown = tool.execute(json!({ "quote_id": "q_missing" })).await;
+    assert!(unknown
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown quote_id"));
+}
+
+#[tokio::test]
+async fn test_compare_quotes_explains_price_move() {
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let before = quote_id(&quotes, &client).await;
+
+    // The planned trade re-quoted after the pool moved 2% against it
+    let tool = CompareQuotesTool::new(
+        SwapTokensTool::new(
+            Arc::clone(&client),
+            Arc::new(quoted_router("490000000000000000")),
+        ),
+        Arc::clone(&quotes),
+    );
+    let result = tool
+        .execute(json!({
+            "before": before,
+            "after": {
+                "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+                "amount": "1",
+                "slippage_tolerance": 1.0
+            }
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["same_trade"], true);
+    assert_eq!(result["before"]["expected_amount_out"], "0.5");
+    assert_eq!(result["after"]["expected_amount_out"], "0.49");
+    assert!(result["after"]["quote_id"]
+        .as_str()
+        .unwrap()
+        .starts_with("q_"));
+    assert_eq!(result["changes"]["expected_amount_out"]["change"], "-0.01");
+    assert_eq!(
+        result["changes"]["expected_amount_out"]["change_percent"],
+        "-2.00"
+    );
+    assert_eq!(result["changes"]["minimum_amount_out"]["before"], "0.495");
+    assert_eq!(result["changes"]["gas_price_gwei"]["change"], "0");
+    let explanation = result["explanation"].as_array().unwrap();
+    assert_eq!(explanation.len(), 1);
+    assert!(explanation[0]
+        .as_str()
+        .unwrap()
+        .starts_with("Execution price moved -2%"));
+
+    let err = tool
+        .execute(json!({ "before": "q_missing", "after": before }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Unknown quote_id"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_execute_swap_enforces_pool_share_limit() {
+    use crate::ethereum::PoolReserves;
+
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    // Selling 1 token into a pool holding 5 takes 20% of it
+    let thin_router = || {
+        Arc::new(quoted_router("500000000000000000").with_pool_reserves(
+            from_token,
+            to_token,
+            PoolReserves {
+                reserve_in: U256::exp10(18) * 5,
+                reserve_out: U256::exp10(18) * 3,
+                ..Default::default()
+            },
+        ))
+    };
+    let limit = |action| {
+        Some(PoolShareLimit {
+            max_percent: Decimal::from(10),
+            action,
+        })
+    };
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let quote = SwapTokensTool::new(Arc::clone(&client), thin_router())
+        .with_quote_store(Arc::clone(&quotes))
+        .with_pool_share_limit(limit(OverLimitAction::Reject))
+        .execute(json!({
+            "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "amount": "1"
+        }))
+        .await
+        .unwrap();
+    assert_eq!(quote["pool_share_percent"], "20");
+    assert!(quote["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("above the 10% limit; execute_swap will refuse it"));
+    let id = quote["quote_id"].as_str().unwrap().to_string();
+
+    let rejecting = ExecuteSwapTool::new(
+        Arc::clone(&client),
+        thin_router(),
+        Arc::clone(&quotes),
+        false,
+        50,
+    )
+    .with_pool_share_limit(limit(OverLimitAction::Reject));
+    let err = rejecting
+        .execute(json!({ "quote_id": id }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("sells 20% of the pool"), "{}", err);
+
+    // In confirm mode live sends ask first, and dry runs go ahead
+    let confirming = ExecuteSwapTool::new(client, thin_router(), quotes, true, 50)
+        .with_pool_share_limit(limit(OverLimitAction::Confirm));
+    assert_eq!(
+        confirming.confirmation_prompt(&json!({ "quote_id": id })),
+        None
+    );
+    let prompt = confirming
+        .confirmation_prompt(&json!({ "quote_id": id, "dry_run": false }))
+        .unwrap();
+    assert!(prompt.ends_with("above the 10% limit. Send it anyway?"));
+    let result = confirming.execute(json!({ "quote_id": id })).await.unwrap();
+    assert_eq!(result["pool_share_percent"], "20");
+}
+
+#[test]
+fn test_split_by_depth() {
+    use crate::tools::split::split_by_depth;
+
+    let parts = split_by_depth(
+        U256::from(100),
+        &[U256::from(1), U256::from(2), U256::zero()],
+    );
+    // 33.3 and 66.6 round down; the remainder goes to the deeper pool
+    assert_eq!(parts, vec![U256::from(33), U256::from(67), U256::zero()]);
+    assert_eq!(
+        split_by_depth(U256::from(5), &[U256::zero(), U256::zero()]),
+        vec![U256::zero(); 2]
+    );
+}
+
+#[tokio::test]
+async fn test_swap_tokens_splits_across_venues() {
+    use crate::ethereum::PoolReserves;
+
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let sushiswap_router: Address = "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F"
+        .parse()
+        .unwrap();
+    let pool = |depth: u64| PoolReserves {
+        reserve_in: U256::exp10(18) * depth,
+        reserve_out: U256::exp10(18) * depth,
+        ..Default::default()
+    };
+    let uniswap = Arc::new(
+        quoted_router("0")
+            .with_constant_product()
+            .with_pool_reserves(from_token, to_token, pool(300)),
+    );
+    let sushiswap = Arc::new(
+        quoted_router("0")
+            .with_venue("sushiswap", sushiswap_router)
+            .with_constant_product()
+            .with_pool_reserves(from_token, to_token, pool(100)),
+    );
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let quote = SwapTokensTool::new(Arc::clone(&client), Arc::clone(&uniswap))
+        .with_quote_store(Arc::clone(&quotes))
+        .with_split_routers(vec![Arc::clone(&sushiswap)])
+        .execute(json!({
+            "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "amount": "40",
+            "split": true
+        }))
+        .await
+        .unwrap();
+
+    // Selling 40 into one pool of 300 returns less than 30 and 10 into pools of 300 and 100
+    let split = &quote["split"];
+    assert_eq!(split["applied"], true);
+    assert_eq!(split["legs"][0]["venue"], "uniswap_v2");
+    assert_eq!(split["legs"][0]["amount_in"], "30");
+    assert_eq!(split["legs"][1]["venue"], "sushiswap");
+    assert_eq!(split["legs"][1]["share_percent"], "25");
+    assert_eq!(quote["estimated_amount_out"], split["split_amount_out"]);
+    let swaps: Vec<&Value> = quote["plan"]["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|step| step["action"] == "swap")
+        .collect();
+    assert_eq!(swaps.len(), 2);
+    assert_eq!(swaps[1]["router"], json!(sushiswap_router));
+
+    // Each leg is re-quoted through its own router
+    let id = quote["quote_id"].as_str().unwrap().to_string();
+    let result = ExecuteSwapTool::new(
+        Arc::clone(&client),
+        Arc::clone(&uniswap),
+        Arc::clone(&quotes),
+        false,
+        50,
+    )
+    .with_split_routers(vec![sushiswap])
+    .execute(json!({ "quote_id": id }))
+    .await
+    .unwrap();
+    assert_eq!(result["requoted"], false);
+    let err = ExecuteSwapTool::new(client, uniswap, quotes, false, 50)
+        .execute(json!({ "quote_id": id }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("unknown router"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_swap_tokens_lists_alternative_routes() {
+    use crate::ethereum::PoolReserves;
+
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let pool = |depth: u64| PoolReserves {
+        reserve_in: U256::exp10(18) * depth,
+        reserve_out: U256::exp10(18) * depth,
+        ..Default::default()
+    };
+    let sushiswap = quoted_router("0")
+        .with_venue(
+            "sushiswap",
+            "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F"
+                .parse()
+                .unwrap(),
+        )
+        .with_constant_product()
+        .with_pool_reserves(from_token, to_token, pool(100));
+    let tool = SwapTokensTool::new(
+        Arc::new(MockEthereumClient::new()),
+        Arc::new(
+            quoted_router("0")
+                .with_constant_product()
+                .with_pool_reserves(from_token, to_token, pool(300)),
+        ),
+    )
+    .with_split_routers(vec![Arc::new(sushiswap)]);
+    let params = json!({
+        "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "40",
+        "include_alternatives": true
+    });
+
+    let result = tool.execute(params.clone()).await.unwrap();
+    let routes = result["alternatives"].as_array().unwrap();
+    let venues: Vec<&Value> = routes.iter().map(|route| &route["venues"]).collect();
+    assert_eq!(
+        venues,
+        vec![
+            &json!(["uniswap_v2", "sushiswap"]),
+            &json!(["uniswap_v2"]),
+            &json!(["sushiswap"])
+        ]
+    );
+    // Without split the quote stays on the main router
+    assert_eq!(routes[1]["selected"], true);
+    assert_eq!(routes[0]["selected"], false);
+    assert_eq!(
+        routes[1]["expected_amount_out"],
+        result["estimated_amount_out"]
+    );
+    // Output is WETH, so the 0.01 ETH of gas comes straight off it
+    let out: Decimal = routes[1]["expected_amount_out"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let net: Decimal = routes[1]["net_amount_out"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(out - net, Decimal::new(1, 2));
+
+    let mut limited = params;
+    limited["alternatives_limit"] = json!(1);
+    let result = tool.execute(limited).await.unwrap();
+    assert_eq!(result["alternatives"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_swap_tokens_ranks_routes_net_of_gas() {
+    use crate::ethereum::PoolReserves;
+
+    let from_token: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        .parse()
+        .unwrap();
+    let to_token: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let pool = |depth: u64| PoolReserves {
+        reserve_in: U256::exp10(18) * depth,
+        reserve_out: U256::exp10(18) * depth,
+        ..Default::default()
+    };
+    let sushiswap = || {
+        quoted_router("0")
+            .with_venue(
+                "sushiswap",
+                "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F"
+                    .parse()
+                    .unwrap(),
+            )
+            .with_constant_product()
+            .with_pool_reserves(from_token, to_token, pool(100))
+    };
+    let tool = || {
+        SwapTokensTool::new(
+            Arc::new(MockEthereumClient::new()),
+            Arc::new(
+                quoted_router("0")
+                    .with_constant_product()
+                    .with_pool_reserves(from_token, to_token, pool(300)),
+            ),
+        )
+        .with_split_routers(vec![Arc::new(sushiswap())])
+    };
+    let params = json!({
+        "from_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        "to_token": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "amount": "2",
+        "split": true
+    });
+
+    // Splitting 2 gains about 0.0033 WETH of output but costs another 0.01 ETH swap
+    let result = tool().execute(params.clone()).await.unwrap();
+    assert_eq!(result["route_objective"], "net_of_gas");
+    assert_eq!(result["split"]["applied"], false);
+
+    let mut by_output = params.clone();
+    by_output["route_objective"] = json!("output");
+    let result = tool().execute(by_output).await.unwrap();
+    assert_eq!(result["route_objective"], "output");
+    assert_eq!(result["split"]["applied"], true);
+
+    let result = tool()
+        .with_route_objective(RouteObjective::Output)
+        .execute(params)
+        .await
+        .unwrap();
+    assert_eq!(result["split"]["applied"], true);
+}
+
+#[tokio::test]
+async fn test_quote_store_expires_quotes() {
+    let quotes = Arc::new(QuoteStore::new(std::time::Duration::ZERO));
+    let client = Arc::new(MockEthereumClient::new());
+    let id = quote_id(&quotes, &client).await;
+
+    let err = quotes.get(&id).unwrap_err().to_string();
+    assert!(err.contains("expired"));
+}
+
+#[tokio::test]
+async fn test_quote_store_scopes_quotes_to_sessions() {
+    use crate::tools::sessions::in_session;
+
+    let quotes = Arc::new(QuoteStore::default());
+    let client = Arc::new(MockEthereumClient::new());
+    let id = in_session("s1", quote_id(&quotes, &client)).await;
+
+    assert!(in_session("s1", async { quotes.get(&id) }).await.is_ok());
+    let err = in_session("s2", async { quotes.get(&id) })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Unknown quote_id"));
+
+    // Ending the session drops its quotes
+    quotes.end_session("s1");
+    assert!(in_session("s1", async { quotes.get(&id) }).await.is_err());
+}
+
+#[tokio::test]
+async fn test_diagnose_wallet_flags_underpriced_and_gaps() {
+    use crate::ethereum::PendingTransaction;
+
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+    let pool = vec![
+        PendingTransaction {
+            hash: H256::from_low_u64_be(1),
+            nonce: 7,
+            to: Some(wallet),
+            max_fee_per_gas: gwei(10),
+            max_priority_fee_per_gas: Some(gwei(1)),
+            ..Default::default()
+        },
+        PendingTransaction {
+            hash: H256::from_low_u64_be(2),
+            nonce: 9,
+            max_fee_per_gas: gwei(50),
+            queued: true,
+            ..Default::default()
+        },
+    ];
+    let mock_client = MockEthereumClient::new()
+        .with_wallet_address(wallet)
+        .with_gas_price(gwei(30))
+        .with_nonces(wallet, 7, 8)
+        .with_pending_transactions(wallet, pool);
+    let tool = DiagnoseWalletTool::new(Arc::new(mock_client));
+
+    let result = tool.execute(json!({})).await.unwrap();
+
+    assert_eq!(result["status"], "stuck");
+    assert_eq!(result["pending_count"], 1);
+    assert_eq!(result["mempool_available"], true);
+    assert_eq!(result["transactions"][0]["state"], "underpriced");
+    assert_eq!(result["transactions"][1]["state"], "queued");
+
+    let recommendations = result["recommendations"].as_array().unwrap();
+    assert_eq!(recommendations.len(), 2);
+    assert_eq!(recommendations[0]["action"], "speed_up");
+    let speed_up = &recommendations[0]["plan"]["steps"][0];
+    assert_eq!(speed_up["action"], "speed_up");
+    assert_eq!(speed_up["nonce"], 7);
+    // 30 gwei plus 25% headroom beats a 15% bump of the original 10 gwei
+    assert_eq!(speed_up["max_fee_per_gas"], "37500000000");
+    assert_eq!(recommendations[1]["action"], "fill_gap");
+    assert_eq!(recommendations[1]["nonce"], 8);
+    assert_eq!(recommendations[1]["plan"]["steps"][0]["action"], "cancel");
+}
+
+#[tokio::test]
+async fn test_diagnose_wallet_without_mempool() {
+    let wallet: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let other = "0x1111111111111111111111111111111111111111";
+    let mock_client = MockEthereumClient::new()
+        .with_wallet_address(wallet)
+        .with_nonces(wallet, 3, 5)
+        .with_nonces(other.parse().unwrap(), 4, 4);
+    let tool = DiagnoseWalletTool::new(Arc::new(mock_client));
+
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["status"], "pending");
+    assert_eq!(result["mempool_available"], false);
+    let recommendations = result["recommendations"].as_array().unwrap();
+    assert_eq!(recommendations.len(), 2);
+    assert_eq!(recommendations[0]["action"], "cancel");
+
+    let healthy = tool.execute(json!({ "address": other })).await.unwrap();
+    assert_eq!(healthy["status"], "healthy");
+    assert!(healthy["recommendations"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_offline_signing_round_trip() {
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::utils::rlp::Rlp;
+
+    let signer: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        .parse()
+        .unwrap();
+    let signer = signer.with_chain_id(1u64);
+    let recipient = "0x1111111111111111111111111111111111111111";
+    let mock_client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(signer.address())
+            .with_nonces(signer.address(), 4, 5),
+    );
+
+    let build = BuildTransactionTool::new(mock_client.clone());
+    let built = build
+        .execute(json!({
+            "plan": { "steps": [
+                { "action": "transfer", "to": recipient, "amount": "1000" },
+                { "action": "transfer", "to": recipient, "amount": "2000" }
+            ]}
+        }))
+        .await
+        .unwrap();
+    let transactions = built["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0]["nonce"], 5);
+    assert_eq!(transactions[1]["nonce"], 6);
+
+    // Sign the first transaction the way an offline device would
+    let unsigned = build_transaction::qr_decode(&[transactions[0]["qr_payload"]
+        .as_str()
+        .unwrap()
+        .to_string()])
+    .unwrap();
+    let tx: TypedTransaction = ethers::utils::rlp::decode(&unsigned).unwrap();
+    assert_eq!(
+        format!("{:?}", tx.sighash()),
+        transactions[0]["signing_hash"].as_str().unwrap()
+    );
+    let signature = signer.sign_transaction_sync(&tx).unwrap();
+    let signed = tx.rlp_signed(&signature);
+
+    let import = ImportSignedTransactionTool::new(mock_client.clone(), false);
+    let decoded = import
+        .execute(json!({ "signed_transaction": format!("{}", signed) }))
+        .await
+        .unwrap();
+    assert_eq!(decoded["signer"], format!("{:?}", signer.address()));
+    assert_eq!(decoded["nonce"], 5);
+    assert_eq!(decoded["value"], "1000");
+    assert_eq!(decoded["broadcast"], false);
+
+    let err = import
+        .execute(json!({ "signed_transaction": format!("{}", signed), "broadcast": true }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Broadcasting is disabled"));
+
+    let import = ImportSignedTransactionTool::new(mock_client.clone(), true);
+    let (payload, _) = build_transaction::qr_encode(&signed, "STX");
+    let sent = import
+        .execute(json!({ "signed_transaction": payload, "broadcast": true }))
+        .await
+        .unwrap();
+    assert_eq!(sent["broadcast"], true);
+    assert_eq!(mock_client.sent_raw_transactions(), vec![signed.clone()]);
+    let (tx_back, _) = TypedTransaction::decode_signed(&Rlp::new(&signed)).unwrap();
+    assert_eq!(tx_back.nonce(), Some(&U256::from(5)));
+}
+
+#[tokio::test]
+async fn test_import_signed_transaction_rejects_other_chain() {
+    let signer: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        .parse()
+        .unwrap();
+    let signer = signer.with_chain_id(10u64);
+    let tx: ethers::types::transaction::eip2718::TypedTransaction =
+        Eip1559TransactionRequest::new()
+            .to(signer.address())
+            .nonce(0)
+            .gas(21_000)
+            .max_fee_per_gas(1)
+            .max_priority_fee_per_gas(1)
+            .chain_id(10)
+            .into();
+    let signed = tx.rlp_signed(&signer.sign_transaction_sync(&tx).unwrap());
+
+    let import = ImportSignedTransactionTool::new(Arc::new(MockEthereumClient::new()), true);
+    let err = import
+        .execute(json!({ "signed_transaction": format!("{}", signed), "broadcast": true }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("signed for chain 10"));
+}
+
+#[test]
+fn test_qr_frames_round_trip() {
+    let bytes: Vec<u8> = (0..=255u8).cycle().take(1500).collect();
+    let (payload, mut frames) = build_transaction::qr_encode(&bytes, "UTX");
+    assert_eq!(payload.len(), 3000);
+    assert_eq!(frames.len(), 3);
+    assert!(frames[0].starts_with("UTX/1/3/"));
+
+    frames.reverse();
+    assert_eq!(build_transaction::qr_decode(&frames).unwrap(), bytes);
+    frames.pop();
+    assert!(build_transaction::qr_decode(&frames).is_err());
+}
+
+#[tokio::test]
+async fn test_compare_yields_ranks_net_of_gas() {
+    use crate::ethereum::{MockVenue, MockYieldSource, YieldSource};
+
+    let dai: Address = "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+        .parse()
+        .unwrap();
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+
+    // At 30 gwei and 2000 DAI/ETH, each 500k gas costs 30 DAI
+    let sources: Vec<Arc<dyn YieldSource>> = vec![
+        Arc::new(MockYieldSource::new("aave_v3", 500_000).with_apy(dai, Decimal::new(5, 0))),
+        Arc::new(MockYieldSource::new("sdai", 1_000_000).with_apy(dai, Decimal::new(6, 0))),
+        Arc::new(MockYieldSource::new("compound_v3", 300_000)),
+    ];
+    let venue = MockVenue::new("uniswap_v2").with_rate(weth, dai, Decimal::new(2000, 0));
+    let tool = CompareYieldsTool::new(
+        Arc::new(MockEthereumClient::new()),
+        sources,
+        Arc::new(venue),
+    );
+
+    // A small deposit favours the cheaper source
+    let result = tool
+        .execute(json!({ "token": format!("{:?}", dai), "amount": "1000" }))
+        .await
+        .unwrap();
+    let yields = result["yields"].as_array().unwrap();
+    assert_eq!(result["holding_days"], 365);
+    assert_eq!(yields[0]["protocol"], "aave_v3");
+    assert_eq!(yields[0]["gas_cost"], "30");
+    assert_eq!(yields[0]["expected_earnings"], "50");
+    assert_eq!(yields[0]["net_earnings"], "20");
+    assert_eq!(yields[0]["net_apy_percent"], "2");
+    assert_eq!(yields[1]["protocol"], "sdai");
+    assert_eq!(yields[1]["net_earnings"], "0");
+    assert_eq!(yields[2]["protocol"], "compound_v3");
+    assert!(yields[2]["error"].is_string());
+
+    // A large one favours the higher rate
+    let result = tool
+        .execute(json!({ "token": format!("{:?}", dai), "amount": "100000" }))
+        .await
+        .unwrap();
+    assert_eq!(result["yields"][0]["protocol"], "sdai");
+    assert_eq!(result["yields"][0]["net_earnings"], "5940");
+}
+
+#[tokio::test]
+async fn test_pool_liquidity_profile_bands_and_depth() {
+    use crate::ethereum::{MockUniswapV3, V3InitializedTick, V3PoolState};
+
+    let token0: Address = "0x1111111111111111111111111111111111111111"
+        .parse()
+        .unwrap();
+    let token1: Address = "0x2222222222222222222222222222222222222222"
+        .parse()
+        .unwrap();
+    let pool_address: Address = "0x3333333333333333333333333333333333333333"
+        .parse()
+        .unwrap();
+    let e18 = 10u128.pow(18);
+
+    // 1e18 in range at price 1, half of it concentrated within ±600 ticks
+    let pool = V3PoolState {
+        pool_address,
+        token0,
+        fee: 3000,
+        sqrt_price_x96: U256::one() << 96,
+        tick: 0,
+        liquidity: e18,
+    };
+    let ticks = vec![
+        V3InitializedTick {
+            tick: -600,
+            liquidity_net: (e18 / 2) as i128,
+        },
+        V3InitializedTick {
+            tick: 600,
+            liquidity_net: -((e18 / 2) as i128),
+        },
+    ];
+    let uniswap_v3 = MockUniswapV3::new()
+        .with_pool(token0, token1, pool)
+        .with_ticks(pool_address, ticks);
+    let tool =
+        GetPoolLiquidityProfileTool::new(Arc::new(MockEthereumClient::new()), Arc::new(uniswap_v3));
+
+    let result = tool
+        .execute(json!({
+            "token_a": format!("{:?}", token1),
+            "token_b": format!("{:?}", token0),
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(result["token0"], format!("{:?}", token0));
+    assert_eq!(result["current_price"], "1");
+    let bands = result["bands"].as_array().unwrap();
+    assert_eq!(bands.len(), 3);
+    assert_eq!(bands[0]["tick_upper"], -600);
+    assert_eq!(bands[0]["liquidity"], (e18 / 2).to_string());
+    assert_eq!(bands[1]["liquidity"], e18.to_string());
+    assert_eq!(bands[2]["tick_lower"], 600);
+    // token1 below the price, token0 above
+    assert_eq!(bands[0]["amount0"], "0");
+    assert_eq!(bands[2]["amount1"], "0");
+
+    let depth = result["depth"].as_array().unwrap();
+    assert_eq!(depth.len(), 10);
+    assert_eq!(depth[2]["price_change_percent"], "+1");
+    assert_eq!(depth[2]["token_in"], format!("{:?}", token1));
+    assert_eq!(depth[2]["amount_in"], "0.004988");
+    assert_eq!(depth[2]["amount_out"], "0.004963");
+
+    // Past tick 600 only half the liquidity remains: at 1e18 throughout, +10% would take 0.0488
+    assert_eq!(depth[8]["price_change_percent"], "+10");
+    assert_eq!(depth[8]["amount_in"], "0.039631");
+
+    let err = tool
+        .execute(json!({
+            "token_a": format!("{:?}", token0),
+            "token_b": format!("{:?}", token1),
+            "fee": 500
+        }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No Uniswap V3 pool"));
+}
+
+#[tokio::test]
+async fn test_monitor_new_pairs_screens_and_advances() {
+    use crate::ethereum::{FactoryKind, MockPoolDiscovery, PoolInfo, PoolLiquidity};
+
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let fresh = Address::repeat_byte(0xaa);
+    let codeless = Address::repeat_byte(0xbb);
+    let pool = |address: u8, token: Address, block: u64| PoolInfo {
+        dex: "uniswap_v2".to_string(),
+        kind: FactoryKind::V2,
+        pool_address: Address::repeat_byte(address),
+        token0: token,
+        token1: weth,
+        fee: 3000,
+        creation_block: block,
+    };
+    let reserves = |weth_reserve: u64| PoolLiquidity::Reserves {
+        reserve0: U256::from(1_000_000u64),
+        reserve1: U256::from(weth_reserve) * U256::exp10(17),
+    };
+    let discovery = MockPoolDiscovery::new()
+        .with_pool(pool(0x01, fresh, 120), reserves(5))
+        .with_pool(pool(0x02, codeless, 140), reserves(50))
+        .with_pool(pool(0x03, fresh, 160), reserves(50));
+    let client = MockEthereumClient::new()
+        .with_block_number(150)
+        .with_token_symbol(fresh, "NEW".to_string())
+        .with_no_code(codeless);
+    let tool = MonitorNewPairsTool::new(Arc::new(client), Arc::new(discovery), vec![weth]).unwrap();
+
+    let result = tool
+        .execute(json!({ "min_base_liquidity": "1" }))
+        .await
+        .unwrap();
+    assert_eq!(result["from_block"], 0);
+    assert_eq!(result["next_from_block"], 151);
+    let pools = result["new_pools"].as_array().unwrap();
+    // The pool created after the head block waits for the next call
+    assert_eq!(pools.len(), 2);
+    assert_eq!(pools[0]["new_token_symbol"], "NEW");
+    assert_eq!(pools[0]["base_liquidity"], "0.5");
+    assert_eq!(pools[0]["risk"], "caution");
+    assert_eq!(pools[0]["flags"], json!(["low_liquidity"]));
+    assert_eq!(pools[1]["risk"], "danger");
+    assert_eq!(pools[1]["flags"], json!(["no_contract_code"]));
+
+    // The next poll continues after the last scanned block
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["from_block"], 151);
+    assert_eq!(result["new_pools"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn test_monitor_token_risks_alerts_and_notifies() {
+    use crate::ethereum::{
+        AdminAction, AdminEvent, FactoryKind, LiquidityRemoval, MockPoolDiscovery, MockTokenEvents,
+        PoolInfo, PoolLiquidity,
+    };
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(String, AlertSeverity)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send(&self, topic: &str, severity: AlertSeverity, _alert: &Value) -> usize {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), severity));
+            1
+        }
+    }
+
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let wallet = Address::repeat_byte(0x11);
+    let held = Address::repeat_byte(0xaa);
+    let not_held = Address::repeat_byte(0xbb);
+    let pair = PoolInfo {
+        dex: "uniswap_v2".to_string(),
+        kind: FactoryKind::V2,
+        pool_address: Address::repeat_byte(0x01),
+        token0: held,
+        token1: weth,
+        fee: 3000,
+        creation_block: 10,
+    };
+    // 400 of 1000 tokens left after 600 were withdrawn
+    let discovery = MockPoolDiscovery::new().with_pool(
+        pair.clone(),
+        PoolLiquidity::Reserves {
+            reserve0: U256::from(400u64),
+            reserve1: U256::exp10(18),
+        },
+    );
+    let removal = |block: u64, amount: u64| LiquidityRemoval {
+        pair: pair.pool_address,
+        block_number: block,
+        tx_hash: H256::repeat_byte(block as u8),
+        amount0: U256::from(amount),
+        amount1: U256::zero(),
+    };
+    let admin = |block: u64, action: AdminAction| AdminEvent {
+        token: held,
+        block_number: block,
+        tx_hash: H256::repeat_byte(block as u8),
+        action,
+    };
+    let events = MockTokenEvents::new()
+        .with_removal(removal(120, 600))
+        .with_removal(removal(130, 10))
+        .with_total_supply(held, U256::from(1_000_000u64))
+        .with_admin_event(admin(
+            125,
+            AdminAction::Mint {
+                to: Address::repeat_byte(0x22),
+                amount: U256::from(50_000u64),
+            },
+        ))
+        .with_admin_event(admin(
+            126,
+            AdminAction::Mint {
+                to: Address::repeat_byte(0x22),
+                amount: U256::from(10u64),
+            },
+        ))
+        .with_admin_event(admin(
+            140,
+            AdminAction::BlacklistUpdated {
+                account: wallet,
+                blacklisted: true,
+            },
+        ));
+    let client = MockEthereumClient::new()
+        .with_wallet_address(wallet)
+        .with_block_number(150)
+        .with_token_balance(held, wallet, Decimal::new(5, 0), 18);
+    let sink = Arc::new(RecordingSink::default());
+    let tool = MonitorTokenRisksTool::new(Arc::new(client), Arc::new(discovery), Arc::new(events))
+        .with_alert_sink(sink.clone());
+
+    let result = tool
+        .execute(json!({
+            "tokens": [format!("{:?}", held), format!("{:?}", not_held)]
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["next_from_block"], 151);
+    assert_eq!(result["not_held"], json!([format!("{:?}", not_held)]));
+    let alerts = result["alerts"].as_array().unwrap();
+    // The small removal and the dust mint stay below the thresholds
+    assert_eq!(alerts.len(), 3);
+    assert_eq!(alerts[0]["kind"], "liquidity_removed");
+    assert_eq!(alerts[0]["percent"], "60");
+    assert_eq!(alerts[0]["severity"], "critical");
+    assert_eq!(alerts[1]["kind"], "mint");
+    assert_eq!(alerts[1]["percent"], "5");
+    assert_eq!(alerts[1]["severity"], "warning");
+    assert_eq!(alerts[2]["kind"], "blacklist_updated");
+    assert_eq!(alerts[2]["severity"], "critical");
+    assert_eq!(result["notifications_sent"], 3);
+    assert_eq!(
+        sink.sent.lock().unwrap()[0],
+        ("token_risk".to_string(), AlertSeverity::Critical)
+    );
+
+    // The next poll starts after the scanned range and finds nothing new
+    let result = tool
+        .execute(json!({ "tokens": [format!("{:?}", held)] }))
+        .await
+        .unwrap();
+    assert_eq!(result["from_block"], 151);
+    assert_eq!(result["alerts"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn test_address_book_resolves_contact_names() {
+    let cold: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        .parse()
+        .unwrap();
+    let book = Arc::new(AddressBook::new(Vec::new()).unwrap());
+    let add = AddContactTool::new(book.clone());
+    add.execute(json!({ "name": "Cold Wallet", "address": format!("{:?}", cold) }))
+        .await
+        .unwrap();
+
+    // Re-pointing a name needs replace, and names cannot pose as addresses
+    let err = add
+        .execute(json!({ "name": "cold wallet", "address": format!("{:?}", Address::zero()) }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already points to"));
+    assert!(add
+        .execute(json!({ "name": "0xdead", "address": format!("{:?}", cold) }))
+        .await
+        .is_err());
+
+    let listed = ListContactsTool::new(book.clone())
+        .execute(json!({}))
+        .await
+        .unwrap();
+    assert_eq!(listed["items"][0]["name"], "Cold Wallet");
+    assert_eq!(listed["total"], 1);
+
+    let client = MockEthereumClient::new().with_eth_balance(cold, Decimal::new(3, 0));
+    let balance = GetBalanceTool::new(Arc::new(client))
+        .with_address_book(book.clone())
+        .execute(json!({ "address": "COLD WALLET" }))
+        .await
+        .unwrap();
+    assert_eq!(balance["balance"], "3");
+
+    let err = GetBalanceTool::new(Arc::new(MockEthereumClient::new()))
+        .with_address_book(book)
+        .execute(json!({ "address": "hot wallet" }))
+        .await
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("Unknown contact: hot wallet"));
+}
+
+#[tokio::test]
+async fn test_execute_plan_confirms_unknown_recipients() {
+    let friend = Address::repeat_byte(0x22);
+    let stranger = Address::repeat_byte(0x33);
+    let book = AddressBook::new(vec![Contact {
+        name: "friend".to_string(),
+        address: friend,
+        note: None,
+    }])
+    .unwrap()
+    .with_confirm_unknown_recipients(true);
+    let tool = ExecutePlanTool::new(Arc::new(MockEthereumClient::new()), true)
+        .with_address_book(Arc::new(book));
+    let plan = |to: String| {
+        json!({
+            "plan": { "steps": [{ "action": "transfer", "to": to, "amount": "1000" }] },
+            "dry_run": false
+        })
+    };
+
+    assert_eq!(tool.confirmation_prompt(&plan("friend".to_string())), None);
+    assert_eq!(
+        tool.confirmation_prompt(&plan(format!("{:?}", friend))),
+        None
+    );
+    let prompt = tool
+        .confirmation_prompt(&plan(format!("{:?}", stranger)))
+        .unwrap();
+    assert!(prompt.contains(&format!("{:?}", stranger)));
+
+    // Dry runs never send, so they need no confirmation
+    let mut dry_run = plan(format!("{:?}", stranger));
+    dry_run["dry_run"] = json!(true);
+    assert_eq!(tool.confirmation_prompt(&dry_run), None);
+
+    // Contact names are resolved before the plan is run
+    let result = tool
+        .execute(json!({ "plan": { "steps": [{ "action": "transfer", "to": "friend", "amount": "1000" }] } }))
+        .await
+        .unwrap();
+    assert_eq!(result["steps"][0]["status"], "simulated");
+}
+
+#[tokio::test]
+async fn test_execute_plan_flags_first_time_recipients() {
+    use crate::ethereum::MockTokenEvents;
+
+    let wallet = Address::repeat_byte(0x11);
+    let paid_before = Address::repeat_byte(0x22);
+    let stranger = Address::repeat_byte(0x33);
+    let events = MockTokenEvents::new().with_transfer(wallet, paid_before, 900);
+    let history =
+        Arc::new(RecipientHistory::new(Arc::new(events), 500).with_required_confirmation(true));
+    let client = MockEthereumClient::new()
+        .with_wallet_address(wallet)
+        .with_block_number(1000);
+    let tool = ExecutePlanTool::new(Arc::new(client), true).with_recipient_history(history);
+    let plan = json!({
+        "steps": [
+            { "action": "transfer", "to": format!("{:?}", paid_before), "amount": "1" },
+            { "action": "transfer", "to": format!("{:?}", stranger), "amount": "1" }
+        ]
+    });
+
+    let result = tool.execute(json!({ "plan": plan.clone() })).await.unwrap();
+    assert_eq!(result["steps"][0].get("first_time_recipient"), None);
+    assert_eq!(result["steps"][1]["first_time_recipient"], true);
+    assert_eq!(
+        result["first_time_recipients"],
+        json!([format!("{:?}", stranger)])
+    );
+
+    // Sending needs the new recipient acknowledged
+    let err = tool
+        .execute(json!({ "plan": plan.clone(), "dry_run": false }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("confirm_new_recipients"));
+    let result = tool
+        .execute(json!({ "plan": plan.clone(), "dry_run": false, "confirm_new_recipients": true }))
+        .await
+        .unwrap();
+    assert_eq!(result["completed_steps"], 2);
+
+    // Once paid, the recipient is no longer new
+    let result = tool.execute(json!({ "plan": plan })).await.unwrap();
+    assert_eq!(result.get("first_time_recipients"), None);
+}
+
+#[tokio::test]
+async fn test_batch_transfer_validates_and_disperses() {
+    use crate::ethereum::plan::DisperseEtherCall;
+    use ethers::abi::AbiDecode;
+
+    let wallet = Address::repeat_byte(0x11);
+    let disperse = Address::repeat_byte(0xd1);
+    let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb2));
+    let token = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(2, 0))
+            .with_token_balance(token, wallet, Decimal::new(100, 0), 6),
+    );
+    let tool = BatchTransferTool::new(client.clone(), disperse, true).with_limits(BatchLimits {
+        max_recipients: 2,
+        max_eth_per_recipient: Some(Decimal::ONE),
+    });
+    let pay = |payments: &[(Address, &str)]| -> Value {
+        payments
+            .iter()
+            .map(|(to, amount)| json!({ "to": format!("{:?}", to), "amount": amount }))
+            .collect()
+    };
+
+    // ETH goes out in one disperseEther call carrying the total as value
+    let result = tool
+        .execute(json!({
+            "payments": pay(&[(alice, "0.5"), (bob, "0.25")]),
+            "dry_run": false
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["total_amount"], "0.75");
+    assert_eq!(result["execution"]["completed_steps"], 1);
+    let sent = client.sent_transactions();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to_addr(), Some(&disperse));
+    assert_eq!(
+        sent[0].value().copied(),
+        Some(U256::from(750_000_000_000_000_000u64))
+    );
+    let call = DisperseEtherCall::decode(sent[0].data().unwrap()).unwrap();
+    assert_eq!(call.recipients, vec![alice, bob]);
+    assert_eq!(call.values[1], U256::from(250_000_000_000_000_000u64));
+
+    // Tokens are approved to the contract first
+    let result = tool
+        .execute(json!({ "token": format!("{:?}", token), "payments": pay(&[(alice, "40.5")]) }))
+        .await
+        .unwrap();
+    assert_eq!(result["plan"]["steps"][0]["action"], "approve");
+    assert_eq!(result["plan"]["steps"][0]["amount"], "40500000");
+    assert_eq!(result["plan"]["steps"][1]["action"], "batch_transfer");
+    assert_eq!(result["execution"]["dry_run"], true);
+
+    let rejected = [
+        (
+            json!({ "payments": pay(&[(alice, "1.5")]) }),
+            "per-recipient limit",
+        ),
+        (
+            json!({ "payments": pay(&[(alice, "0.1"), (alice, "0.2")]) }),
+            "more than once",
+        ),
+        (
+            json!({ "payments": pay(&[(alice, "0"), (bob, "0.2")]) }),
+            "positive amount",
+        ),
+        (
+            json!({ "payments": pay(&[(alice, "0.1"), (bob, "0.1"), (wallet, "0.1")]) }),
+            "limit of 2",
+        ),
+        (
+            json!({ "payments": pay(&[(disperse, "0.1")]) }),
+            "cannot receive",
+        ),
+        (
+            json!({ "token": format!("{:?}", token), "payments": pay(&[(alice, "0.0000001")]) }),
+            "decimals",
+        ),
+        (
+            json!({ "token": format!("{:?}", token), "payments": pay(&[(alice, "60"), (bob, "50")]) }),
+            "wallet holds 100",
+        ),
+    ];
+    for (params, expected) in rejected {
+        let err = tool.execute(params).await.unwrap_err().to_string();
+        assert!(err.contains(expected), "{}", err);
+    }
+    assert_eq!(client.sent_transactions().len(), 1);
+}
+
+#[tokio::test]
+async fn test_execute_plan_keeps_gas_reserve() {
+    use crate::tools::gas_reserve::GasReserve;
+
+    let wallet = Address::repeat_byte(0x11);
+    let recipient = Address::repeat_byte(0x22);
+    // Each step costs at most 100k gas at the mock's 60 gwei fee ceiling: 0.006 ETH
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::ONE),
+    );
+    let reserve = GasReserve {
+        min_balance: U256::exp10(17),
+        allow_override: false,
+    };
+    let transfer = |wei: u64| json!({ "action": "transfer", "to": format!("{:?}", recipient), "amount": wei.to_string() });
+    let tool = ExecutePlanTool::new(client.clone(), true).with_gas_reserve(reserve);
+
+    // Each step fits on its own, but together they dip into the reserve
+    let result = tool
+        .execute(json!({ "plan": { "steps": [transfer(500_000_000_000_000_000), transfer(400_000_000_000_000_000)] } }))
+        .await
+        .unwrap();
+    assert_eq!(result["steps"][1]["status"], "simulated");
+    assert!(result["gas_reserve_warning"]
+        .as_str()
+        .unwrap()
+        .contains("below the 0.1 ETH reserve"));
+
+    let drain = json!({ "steps": [transfer(950_000_000_000_000_000)] });
+    let result = tool
+        .execute(json!({ "plan": drain.clone(), "dry_run": false }))
+        .await
+        .unwrap();
+    assert_eq!(result["steps"][0]["status"], "failed");
+    assert_eq!(result["resume_from"], 0);
+    assert!(client.sent_transactions().is_empty());
+
+    // Overriding needs the server's policy to allow it
+    let err = tool
+        .execute(json!({ "plan": drain.clone(), "dry_run": false, "override_gas_reserve": true }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ALLOW_GAS_RESERVE_OVERRIDE"));
+
+    let tool = ExecutePlanTool::new(client.clone(), true).with_gas_reserve(GasReserve {
+        allow_override: true,
+        ..reserve
+    });
+    let result = tool
+        .execute(json!({ "plan": drain, "dry_run": false, "override_gas_reserve": true }))
+        .await
+        .unwrap();
+    assert_eq!(result["steps"][0]["status"], "confirmed");
+}
+
+#[tokio::test]
+async fn test_execute_plan_reports_gas_sponsorship() {
+    use crate::ethereum::{SponsorKind, Sponsorship};
+    use crate::tools::gas_reserve::GasReserve;
+
+    let wallet = Address::repeat_byte(0x11);
+    let recipient = Address::repeat_byte(0x22);
+    let sponsorship = Sponsorship {
+        kind: SponsorKind::Erc4337Paymaster,
+        sponsor: Address::repeat_byte(0x33),
+        via: Address::repeat_byte(0x44),
+        gas_cost: U256::exp10(15),
+        user_op_hash: Some(H256::repeat_byte(0x55)),
+    };
+    // Exactly the transfer amount and the reserve, with nothing left for gas
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(6, 1))
+            .with_sponsorship(sponsorship),
+    );
+    let tool = ExecutePlanTool::new(client.clone(), true).with_gas_reserve(GasReserve {
+        min_balance: U256::exp10(17),
+        allow_override: false,
+    });
+    let plan = json!({ "steps": [{ "action": "transfer", "to": format!("{:?}", recipient), "amount": "500000000000000000" }] });
+
+    let result = tool.execute(json!({ "plan": plan.clone() })).await.unwrap();
+    assert_eq!(result["steps"][0]["gas_sponsored"], true);
+    assert!(result.get("gas_reserve_warning").is_none());
+
+    let result = tool
+        .execute(json!({ "plan": plan, "dry_run": false }))
+        .await
+        .unwrap();
+    let step = &result["steps"][0];
+    assert_eq!(step["status"], "confirmed");
+    assert_eq!(step["sponsorship"]["kind"], "erc4337_paymaster");
+    assert_eq!(
+        step["sponsorship"]["sponsor"],
+        format!("{:?}", Address::repeat_byte(0x33))
+    );
+    assert!(step["sponsorship"]["user_op_hash"].is_string());
+    assert_eq!(client.sent_transactions().len(), 1);
+}
+
+#[tokio::test]
+async fn test_payment_request_uri_and_check() {
+    use crate::ethereum::{MockTokenEvents, TokenTransfer};
+
+    let wallet = Address::repeat_byte(0x11);
+    let payer = Address::repeat_byte(0x22);
+    let usdc = Address::repeat_byte(0x70);
+    let requests = Arc::new(PaymentRequests::new());
+    let client = |balance: i64, block: u64| {
+        Arc::new(
+            MockEthereumClient::new()
+                .with_wallet_address(wallet)
+                .with_eth_balance(wallet, Decimal::new(balance, 1))
+                .with_token_symbol(usdc, "USDC".to_string())
+                .with_token_decimals(usdc, 6)
+                .with_block_number(block),
+        )
+    };
+    let transfer = |amount: u64, block_number: u64| TokenTransfer {
+        token: usdc,
+        from: payer,
+        to: wallet,
+        amount: U256::from(amount),
+        block_number,
+        tx_hash: H256::from_low_u64_be(block_number),
+    };
+
+    let create = CreatePaymentRequestTool::new(client(10, 100), requests.clone());
+    let token_request = create
+        .execute(json!({ "amount": "25.5", "token": format!("{:?}", usdc), "memo": "INV-7" }))
+        .await
+        .unwrap();
+    assert_eq!(
+        token_request["uri"],
+        format!(
+            "ethereum:{}@1/transfer?address={}&uint256=25500000",
+            ethers::utils::to_checksum(&usdc, None),
+            ethers::utils::to_checksum(&wallet, None)
+        )
+    );
+    assert_eq!(token_request["qr_payload"], token_request["uri"]);
+    let id = token_request["request_id"].as_str().unwrap();
+    assert_eq!(
+        token_request["resource_uri"],
+        format!("payment-request://{}", id)
+    );
+    assert!(requests
+        .by_resource_uri(&format!("payment-request://{}", id))
+        .is_some());
+    assert!(create
+        .execute(json!({ "amount": "1.0000001", "token": format!("{:?}", usdc) }))
+        .await
+        .is_err());
+    assert!(create.execute(json!({ "amount": "0" })).await.is_err());
+
+    // Transfers from before the request don't count; the one completing it is reported
+    let events = MockTokenEvents::new()
+        .with_token_transfer(transfer(99_000_000, 90))
+        .with_token_transfer(transfer(20_000_000, 101))
+        .with_token_transfer(transfer(5_500_000, 104));
+    let check = CheckPaymentTool::new(client(10, 105), Arc::new(events), requests.clone());
+    let result = check.execute(json!({ "request_id": id })).await.unwrap();
+    assert_eq!(result["status"], "paid");
+    assert_eq!(result["received"], "25.5");
+    assert_eq!(result["remaining"], "0");
+    assert_eq!(result["detection"], "transfer_logs");
+    assert_eq!(result["payment_tx"], json!(H256::from_low_u64_be(104)));
+    assert_eq!(result["confirmations"], 2);
+    assert_eq!(result["transfers"].as_array().unwrap().len(), 2);
+
+    let partial = MockTokenEvents::new().with_token_transfer(transfer(20_000_000, 101));
+    let check = CheckPaymentTool::new(client(10, 105), Arc::new(partial), requests.clone());
+    let result = check.execute(json!({ "request_id": id })).await.unwrap();
+    assert_eq!(result["status"], "partially_paid");
+    assert_eq!(result["remaining"], "5.5");
+
+    // ETH leaves no logs, so payment shows up as the recipient's balance growing
+    let eth_request = create.execute(json!({ "amount": "0.5" })).await.unwrap();
+    assert_eq!(
+        eth_request["uri"],
+        format!(
+            "ethereum:{}@1?value=500000000000000000",
+            ethers::utils::to_checksum(&wallet, None)
+        )
+    );
+    let id = eth_request["request_id"].as_str().unwrap();
+    let events = || Arc::new(MockTokenEvents::new());
+    let check = CheckPaymentTool::new(client(10, 101), events(), requests.clone());
+    let result = check.execute(json!({ "request_id": id })).await.unwrap();
+    assert_eq!(result["status"], "pending");
+    let check = CheckPaymentTool::new(client(16, 102), events(), requests.clone());
+    let result = check.execute(json!({ "request_id": id })).await.unwrap();
+    assert_eq!(result["status"], "paid");
+    assert_eq!(result["received"], "0.6");
+    assert_eq!(result["detection"], "balance_increase");
+
+    assert_eq!(requests.open().len(), 2);
+    assert!(check
+        .execute(json!({ "request_id": "pr_missing" }))
+        .await
+        .is_err());
+    assert!(check
+        .execute(json!({ "request_id": id, "wait_secs": 3600 }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_scheduled_payments_run_pause_and_audit() {
+    use crate::tools::payment_requests::unix_now;
+    use crate::tools::schedules::ScheduleStatus;
+
+    let wallet = Address::repeat_byte(0x11);
+    let alice = Address::repeat_byte(0xa1);
+    let usdc = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(10, 0))
+            .with_token_balance(usdc, wallet, Decimal::new(10_000, 0), 6)
+            .with_token_symbol(usdc, "USDC".to_string())
+            .with_token_decimals(usdc, 6),
+    );
+    let dir = std::env::temp_dir().join(format!("schedules-{}", H64::random()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let audit_log = dir.join("audit.jsonl");
+    let schedules = Arc::new(
+        PaymentSchedules::open(dir.join("schedules.json"))
+            .unwrap()
+            .with_audit_log(audit_log.clone())
+            .unwrap(),
+    );
+
+    let disabled = SchedulePaymentTool::new(client.clone(), schedules.clone(), false);
+    let params = |start_at: u64| {
+        json!({
+            "to": format!("{:?}", alice),
+            "amount": "1500",
+            "token": format!("{:?}", usdc),
+            "recurrence": "weekly",
+            "start_at": start_at,
+            "max_total": "4000",
+            "label": "Payroll: Alice"
+        })
+    };
+    let start = unix_now() + 3600;
+    assert!(disabled.execute(params(start)).await.is_err());
+
+    let tool = SchedulePaymentTool::new(client.clone(), schedules.clone(), true);
+    assert!(tool.execute(params(start - 7200)).await.is_err());
+    let created = tool.execute(params(start)).await.unwrap();
+    let id = created["schedule_id"].as_str().unwrap().to_string();
+    // max_total of 4000 affords two runs of 1500
+    let week = 7 * 24 * 60 * 60;
+    assert_eq!(created["next_runs"], json!([start, start + week]));
+
+    let scheduler = PaymentScheduler::new(client.clone(), schedules.clone(), true);
+    assert!(scheduler.run_due(start - 1).await.is_empty());
+    let runs = scheduler.run_due(start).await;
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].outcome, schedules::RunOutcome::Sent);
+    assert!(runs[0].tx_hash.is_some());
+    assert_eq!(client.sent_transactions().len(), 1);
+    assert_eq!(client.sent_transactions()[0].to_addr(), Some(&usdc));
+
+    // Paused schedules don't run, and runs missed while paused are skipped
+    let pause = PauseScheduledPaymentTool::new(schedules.clone());
+    let paused = pause.execute(json!({ "schedule_id": id })).await.unwrap();
+    assert_eq!(paused["status"], "paused");
+    assert!(scheduler.run_due(start + week).await.is_empty());
+    let resume = ResumeScheduledPaymentTool::new(schedules.clone());
+    let resumed = resume.execute(json!({ "schedule_id": id })).await.unwrap();
+    assert_eq!(resumed["status"], "active");
+
+    // The second run uses up max_total and completes the schedule
+    let due = schedules.get(&id).unwrap().next_run.unwrap();
+    let runs = scheduler.run_due(due).await;
+    assert_eq!(runs.len(), 1);
+    let schedule = schedules.get(&id).unwrap();
+    assert_eq!(schedule.status, ScheduleStatus::Completed);
+    assert_eq!(schedule.paid_total, Decimal::new(3000, 0));
+    assert!(schedule.upcoming(5).is_empty());
+    assert!(resume.execute(json!({ "schedule_id": id })).await.is_err());
+
+    let list = ListScheduledPaymentsTool::new(schedules.clone());
+    let listed = list.execute(json!({})).await.unwrap();
+    assert_eq!(listed["items"][0]["label"], "Payroll: Alice");
+    assert_eq!(listed["recent_runs"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(&audit_log).unwrap().lines().count(),
+        2
+    );
+
+    // Schedules and their audit trail survive a restart
+    let reopened = PaymentSchedules::open(dir.join("schedules.json"))
+        .unwrap()
+        .with_audit_log(audit_log)
+        .unwrap();
+    assert_eq!(reopened.get(&id).unwrap(), schedule);
+    assert_eq!(reopened.runs(Some(&id), 10).len(), 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_payment_scheduler_stops_when_asked() {
+    let client = Arc::new(MockEthereumClient::new());
+    let schedules = Arc::new(PaymentSchedules::new());
+    let scheduler = Arc::new(PaymentScheduler::new(client, schedules, true));
+
+    let (stop, stopped) = tokio::sync::watch::channel(false);
+    let running = scheduler.spawn(std::time::Duration::from_millis(10), stopped);
+    tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+    assert!(!running.is_finished());
+
+    stop.send(true).unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(1), running)
+        .await
+        .expect("scheduler did not stop")
+        .unwrap();
+}
+
+#[test]
+fn test_monthly_recurrence_keeps_day_of_month() {
+    // 2025-01-31 09:00 UTC
+    let start = 1_738_314_000;
+    let day = 24 * 60 * 60;
+    assert_eq!(Recurrence::Monthly.occurrence(start, 0), start);
+    // 2025-02-28, then back to the 31st in March
+    assert_eq!(Recurrence::Monthly.occurrence(start, 1), start + 28 * day);
+    assert_eq!(Recurrence::Monthly.occurrence(start, 2), start + 59 * day);
+    // 2026-01-31
+    assert_eq!(Recurrence::Monthly.occurrence(start, 12), start + 365 * day);
+    assert_eq!(Recurrence::Weekly.occurrence(start, 2), start + 14 * day);
+}
+
+#[tokio::test]
+async fn test_check_token_locks_reports_locked_amounts_and_unlocks() {
+    use crate::ethereum::{LockSource, MockLockSource, MockTokenEvents, Release, TokenLock};
+
+    let token = Address::repeat_byte(0x70);
+    let team = Address::repeat_byte(0x7e);
+    let investor = Address::repeat_byte(0x1a);
+    let now = 1_700_000_000;
+    let units = |whole: u64| U256::from(whole) * U256::exp10(18);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_block_timestamp(now)
+            .with_token_symbol(token, "VEST".to_string())
+            .with_token_decimals(token, 18),
+    );
+    let lock = |locker: &str, id: &str, owner, deposited, withdrawn, release| TokenLock {
+        locker: locker.to_string(),
+        lock_id: id.to_string(),
+        token,
+        owner,
+        deposited,
+        withdrawn,
+        release,
+    };
+    let sources: Vec<Arc<dyn LockSource>> = vec![
+        Arc::new(
+            MockLockSource::new("team_finance")
+                .with_lock(lock(
+                    "team_finance",
+                    "7",
+                    team,
+                    units(100_000),
+                    U256::zero(),
+                    Release::Cliff {
+                        unlock_at: now + 1000,
+                    },
+                ))
+                // Withdrawn in full, so no longer open
+                .with_lock(lock(
+                    "team_finance",
+                    "3",
+                    team,
+                    units(5),
+                    units(5),
+                    Release::Cliff { unlock_at: now - 1 },
+                )),
+        ),
+        // Half vested: 400 of 1000 withdrawn, 100 more claimable, 500 locked
+        Arc::new(MockLockSource::new("sablier").with_lock(lock(
+            "sablier",
+            "42",
+            investor,
+            units(1000),
+            units(400),
+            Release::Linear {
+                start: now - 500,
+                cliff: now - 400,
+                end: now + 500,
+            },
+        ))),
+        Arc::new(MockLockSource::new("unicrypt").with_unavailable()),
+    ];
+    let events = MockTokenEvents::new().with_total_supply(token, units(1_000_000));
+    let tool = CheckTokenLocksTool::new(client, sources, Arc::new(events));
+
+    let result = tool
+        .execute(json!({ "token": format!("{:?}", token) }))
+        .await
+        .unwrap();
+    assert_eq!(result["timestamp"], now);
+    let summary = &result["tokens"][0];
+    assert_eq!(summary["symbol"], "VEST");
+    assert_eq!(summary["locks"], 2);
+    assert_eq!(summary["locked"], "100500");
+    assert_eq!(summary["locked_percent_of_supply"], "10.05");
+    assert_eq!(summary["next_unlock_at"], now);
+    assert_eq!(
+        summary["unlock_schedule"],
+        json!([
+            { "from": now, "to": now + 500, "amount": "500" },
+            { "from": now + 1000, "to": now + 1000, "amount": "100000" }
+        ])
+    );
+
+    // Soonest to fully unlock first
+    let stream = &result["locks"][0];
+    assert_eq!(stream["locker"], "sablier");
+    assert_eq!(stream["locked"], "500");
+    assert_eq!(stream["claimable"], "100");
+    assert_eq!(stream["release"]["kind"], "linear");
+    assert_eq!(result["locks"][1]["release"]["unlock_at"], now + 1000);
+    assert_eq!(result["errors"][0]["locker"], "unicrypt");
+
+    let result = tool
+        .execute(json!({ "wallet": format!("{:?}", investor) }))
+        .await
+        .unwrap();
+    assert_eq!(result["locks"].as_array().unwrap().len(), 1);
+    assert_eq!(result["tokens"][0]["locked"], "500");
+
+    assert!(tool.execute(json!({})).await.is_err());
+}
+
+#[tokio::test]
+async fn test_list_streams_reports_incoming_and_outgoing() {
+    use crate::ethereum::{MockStreamSource, PaymentStream, StreamFlow, StreamSource};
+
+    let wallet = Address::repeat_byte(0x11);
+    let employer = Address::repeat_byte(0xe1);
+    let contractor = Address::repeat_byte(0xc2);
+    let usdc = Address::repeat_byte(0x0c);
+    let now = 1_700_000_000;
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_block_timestamp(now)
+            .with_token_symbol(usdc, "USDC".to_string())
+            .with_token_decimals(usdc, 6),
+    );
+    let stream = |protocol: &str, id: Option<&str>, sender, recipient, flow| PaymentStream {
+        protocol: protocol.to_string(),
+        stream_id: id.map(str::to_string),
+        token: usdc,
+        sender,
+        recipient,
+        flow,
+    };
+    let sources: Vec<Arc<dyn StreamSource>> = vec![
+        // A quarter streamed: 250 of 1000 USDC, 100 already withdrawn
+        Arc::new(MockStreamSource::new("sablier").with_stream(stream(
+            "sablier",
+            Some("42"),
+            wallet,
+            contractor,
+            StreamFlow::Linear {
+                deposit: U256::from(1_000_000_000u64),
+                withdrawn: U256::from(100_000_000u64),
+                start: now - 250,
+                cliff: now - 250,
+                end: now + 750,
+                cancelable: true,
+            },
+        ))),
+        // 8.64 USDC a day for the last two hours
+        Arc::new(MockStreamSource::new("superfluid").with_stream(stream(
+            "superfluid",
+            None,
+            employer,
+            wallet,
+            StreamFlow::Constant {
+                rate: U256::from(100),
+                since: now - 7200,
+            },
+        ))),
+        Arc::new(MockStreamSource::new("offline").with_unavailable()),
+    ];
+    let tool = ListStreamsTool::new(client, sources);
+
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["wallet"], format!("{:?}", wallet));
+    let incoming = &result["items"][0];
+    assert_eq!(incoming["direction"], "incoming");
+    assert_eq!(incoming["protocol"], "superfluid");
+    assert_eq!(incoming["kind"], "constant");
+    assert_eq!(incoming["per_day"], "8.64");
+    assert_eq!(incoming["streamed"], "0.72");
+    assert!(incoming.get("stream_id").is_none());
+
+    let outgoing = &result["items"][1];
+    assert_eq!(outgoing["direction"], "outgoing");
+    assert_eq!(outgoing["stream_id"], "42");
+    assert_eq!(outgoing["kind"], "linear");
+    assert_eq!(outgoing["streamed"], "250");
+    assert_eq!(outgoing["withdrawable"], "150");
+    assert_eq!(outgoing["remaining"], "750");
+    assert_eq!(outgoing["cancelable"], true);
+    assert_eq!(result["errors"][0]["protocol"], "offline");
+
+    let result = tool
+        .execute(json!({ "wallet": format!("{:?}", contractor) }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"].as_array().unwrap().len(), 1);
+    assert_eq!(result["items"][0]["direction"], "incoming");
+
+    let result = tool
+        .execute(json!({ "direction": "outgoing" }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"].as_array().unwrap().len(), 1);
+    assert_eq!(result["items"][0]["recipient"], format!("{:?}", contractor));
+}
+
+#[tokio::test]
+async fn test_create_and_cancel_stream() {
+    use crate::ethereum::plan::{CancelCall, CreateWithDurationsCall};
+    use ethers::abi::AbiDecode;
+    use ethers::utils::keccak256;
+
+    let wallet = Address::repeat_byte(0x11);
+    let sablier = Address::repeat_byte(0x5a);
+    let recipient = Address::repeat_byte(0xa1);
+    let token = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::ONE)
+            .with_token_balance(token, wallet, Decimal::new(50_000, 0), 6)
+            .with_token_symbol(token, "USDC".to_string())
+            .with_token_decimals(token, 6),
+    );
+    let tool = CreateStreamTool::new(client.clone(), sablier, true);
+
+    // A year of payroll, approved to the contract first
+    let result = tool
+        .execute(json!({
+            "to": format!("{:?}", recipient),
+            "token": format!("{:?}", token),
+            "amount": "36500",
+            "duration_secs": 365 * 24 * 60 * 60,
+            "cliff_secs": 30 * 24 * 60 * 60,
+            "dry_run": false
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["per_day"], "100");
+    assert_eq!(result["plan"]["steps"][0]["action"], "approve");
+    assert_eq!(
+        result["plan"]["steps"][0]["spender"],
+        format!("{:?}", sablier)
+    );
+    assert_eq!(result["plan"]["steps"][1]["action"], "create_stream");
+    assert_eq!(result["execution"]["completed_steps"], 2);
+
+    let sent = client.sent_transactions();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].to_addr(), Some(&sablier));
+    let data = sent[1].data().unwrap();
+    assert_eq!(
+        data[..4],
+        keccak256("createWithDurations((address,address,uint128,address,bool,bool,(uint40,uint40),(address,uint256)))")[..4]
+    );
+    let call = CreateWithDurationsCall::decode(data).unwrap();
+    assert_eq!(call.params.sender, wallet);
+    assert_eq!(call.params.recipient, recipient);
+    assert_eq!(call.params.total_amount, 36_500_000_000);
+    assert_eq!(call.params.asset, token);
+    assert!(call.params.cancelable);
+    assert_eq!(call.params.durations.cliff, 30 * 24 * 60 * 60);
+    assert_eq!(call.params.durations.total, 365 * 24 * 60 * 60);
+
+    let rejected = [
+        (
+            json!({ "amount": "60000", "duration_secs": 100 }),
+            "wallet holds 50000",
+        ),
+        (
+            json!({ "amount": "1", "duration_secs": 0 }),
+            "duration_secs",
+        ),
+        (
+            json!({ "amount": "1", "duration_secs": 100, "cliff_secs": 100 }),
+            "cliff_secs",
+        ),
+        (
+            json!({ "amount": "0.0000001", "duration_secs": 100 }),
+            "decimals",
+        ),
+    ];
+    for (mut params, expected) in rejected {
+        params["to"] = json!(format!("{:?}", recipient));
+        params["token"] = json!(format!("{:?}", token));
+        let err = tool.execute(params).await.unwrap_err().to_string();
+        assert!(err.contains(expected), "{}", err);
+    }
+
+    // Cancelling is a single call, dry run unless asked otherwise
+    let cancel = CancelStreamTool::new(client.clone(), sablier, true);
+    let result = cancel.execute(json!({ "stream_id": "42" })).await.unwrap();
+    assert_eq!(result["plan"]["steps"][0]["action"], "cancel_stream");
+    assert_eq!(result["execution"]["dry_run"], true);
+    assert_eq!(client.sent_transactions().len(), 2);
+    cancel
+        .execute(json!({ "stream_id": "42", "dry_run": false }))
+        .await
+        .unwrap();
+    let sent = client.sent_transactions();
+    let call = CancelCall::decode(sent[2].data().unwrap()).unwrap();
+    assert_eq!(call.stream_id, U256::from(42));
+
+    // Without execution only dry runs are possible
+    let disabled = CreateStreamTool::new(client, sablier, false);
+    assert!(disabled
+        .execute(json!({
+            "to": format!("{:?}", recipient),
+            "token": format!("{:?}", token),
+            "amount": "1",
+            "duration_secs": 100,
+            "dry_run": false
+        }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_governance_proposals_voting_power_and_votes() {
+    use crate::ethereum::plan::CastVoteWithReasonCall;
+    use crate::ethereum::{
+        GovernanceSource, MockGovernance, Proposal, ProposalState, VoteClock, VoteTally,
+        VotingPower,
+    };
+    use ethers::abi::AbiDecode;
+
+    let wallet = Address::repeat_byte(0x11);
+    let holder = Address::repeat_byte(0x22);
+    let governor = Address::repeat_byte(0x90);
+    let dao = format!("{:?}", governor);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::ONE),
+    );
+    let proposal = |id: &str, state, description: &str| Proposal {
+        id: id.to_string(),
+        proposer: holder,
+        title: format!("Proposal {}", id),
+        description: description.to_string(),
+        state,
+        start: 100,
+        end: 200,
+        clock: VoteClock::Block,
+        tallies: vec![
+            VoteTally {
+                choice: "against".to_string(),
+                votes: Decimal::new(10, 0),
+            },
+            VoteTally {
+                choice: "for".to_string(),
+                votes: Decimal::new(90, 0),
+            },
+        ],
+    };
+    let governor_source: Arc<dyn GovernanceSource> = Arc::new(
+        MockGovernance::new("governor")
+            .with_proposal(&dao, proposal("1", ProposalState::Executed, "Done"))
+            .with_proposal(&dao, proposal("2", ProposalState::Active, &"x".repeat(800)))
+            .with_voting_power(
+                &dao,
+                wallet,
+                VotingPower {
+                    votes: Decimal::new(1500, 0),
+                    symbol: Some("GOV".to_string()),
+                    delegate: Some(wallet),
+                    balance: Some(Decimal::new(1500, 0)),
+                    has_voted: Some(false),
+                },
+            )
+            .with_voting_power(
+                &dao,
+                holder,
+                VotingPower {
+                    votes: Decimal::ZERO,
+                    balance: Some(Decimal::new(20, 0)),
+                    ..VotingPower::default()
+                },
+            ),
+    );
+    let snapshot: Arc<dyn GovernanceSource> = Arc::new(MockGovernance::new("snapshot"));
+    let governors = vec![("gov".to_string(), governor)];
+
+    let proposals = GetGovernanceProposalsTool::new(governor_source.clone(), snapshot.clone())
+        .with_governors(governors.clone());
+    let result = proposals
+        .execute(json!({ "governor": "GOV" }))
+        .await
+        .unwrap();
+    assert_eq!(result["source"], "governor");
+    assert_eq!(result["items"][0]["id"], "2");
+    assert_eq!(result["items"][0]["state"], "active");
+    assert_eq!(result["items"][0]["tallies"][1]["choice"], "for");
+    assert_eq!(result["items"][0]["description_truncated"], true);
+    assert_eq!(
+        result["items"][0]["description"].as_str().unwrap().len(),
+        500
+    );
+    assert!(result["items"][1].get("description_truncated").is_none());
+    let result = proposals
+        .execute(json!({ "governor": dao, "active_only": true }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"].as_array().unwrap().len(), 1);
+    let result = proposals
+        .execute(json!({ "governor": "gov", "limit": 1 }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"][0]["id"], "2");
+    assert_eq!(result["next_cursor"], "1");
+    let result = proposals
+        .execute(json!({ "governor": "gov", "limit": 1, "cursor": "1" }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"][0]["id"], "1");
+    assert!(result.get("next_cursor").is_none());
+    let result = proposals
+        .execute(json!({ "space": "aave.eth" }))
+        .await
+        .unwrap();
+    assert_eq!(result["source"], "snapshot");
+    assert!(proposals.execute(json!({})).await.is_err());
+    assert!(proposals
+        .execute(json!({ "governor": "unknown" }))
+        .await
+        .is_err());
+
+    let power = GetVotingPowerTool::new(client.clone(), governor_source.clone(), snapshot)
+        .with_governors(governors.clone());
+    let result = power.execute(json!({ "governor": "gov" })).await.unwrap();
+    assert_eq!(result["votes"], "1500");
+    assert_eq!(result["symbol"], "GOV");
+    assert!(result.get("hint").is_none());
+    // Undelegated tokens carry no votes
+    let result = power
+        .execute(json!({ "governor": "gov", "wallet": format!("{:?}", holder) }))
+        .await
+        .unwrap();
+    assert_eq!(result["votes"], "0");
+    assert!(result["hint"].as_str().unwrap().contains("not delegated"));
+
+    let vote = CastVoteTool::new(client.clone(), governor_source, true).with_governors(governors);
+    let result = vote
+        .execute(json!({
+            "governor": "gov",
+            "proposal_id": "2",
+            "support": "against",
+            "reason": "Too risky",
+            "dry_run": false
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["support"], 0);
+    assert_eq!(result["votes"], "1500");
+    assert_eq!(result["plan"]["steps"][0]["action"], "vote");
+    let sent = client.sent_transactions();
+    assert_eq!(sent[0].to_addr(), Some(&governor));
+    let call = CastVoteWithReasonCall::decode(sent[0].data().unwrap()).unwrap();
+    assert_eq!(call.proposal_id, U256::from(2));
+    assert_eq!(call.support, 0);
+    assert_eq!(call.reason, "Too risky");
+
+    let err = vote
+        .execute(json!({ "space": "aave.eth", "proposal_id": "2", "support": "for" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("off-chain"));
+
+    let voted: Arc<dyn GovernanceSource> =
+        Arc::new(MockGovernance::new("governor").with_voting_power(
+            &dao,
+            wallet,
+            VotingPower {
+                votes: Decimal::ONE,
+                has_voted: Some(true),
+                ..VotingPower::default()
+            },
+        ));
+    let err = CastVoteTool::new(client, voted, true)
+        .execute(json!({ "governor": dao, "proposal_id": "2", "support": "for" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already voted"));
+}
+
+#[tokio::test]
+async fn test_delegation_read_and_delegate_votes() {
+    use crate::ethereum::plan::DelegateCall;
+    use crate::ethereum::Delegation;
+    use ethers::abi::AbiDecode;
+
+    let wallet = Address::repeat_byte(0x11);
+    let delegate = Address::repeat_byte(0x22);
+    let token = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::ONE)
+            .with_token_balance(token, wallet, Decimal::new(250, 0), 18)
+            .with_token_symbol(token, "GOV".to_string())
+            .with_delegation(
+                token,
+                delegate,
+                Delegation {
+                    delegate: Some(delegate),
+                    votes: Decimal::new(250, 0),
+                },
+            ),
+    );
+
+    let read = GetDelegationTool::new(client.clone());
+    let result = read
+        .execute(json!({ "token": format!("{:?}", token) }))
+        .await
+        .unwrap();
+    assert_eq!(result["status"], "undelegated");
+    assert_eq!(result["balance"], "250");
+    assert_eq!(result["votes"], "0");
+    assert!(result["hint"].as_str().unwrap().contains("delegate_votes"));
+    let result = read
+        .execute(json!({ "token": format!("{:?}", token), "wallet": format!("{:?}", delegate) }))
+        .await
+        .unwrap();
+    assert_eq!(result["status"], "self_delegated");
+    assert_eq!(result["votes"], "250");
+    assert!(read
+        .execute(json!({ "token": format!("{:?}", Address::repeat_byte(0x71)) }))
+        .await
+        .is_err());
+
+    let delegate_votes = DelegateVotesTool::new(client.clone(), true);
+    let result = delegate_votes
+        .execute(json!({ "token": format!("{:?}", token), "delegatee": "self", "dry_run": false }))
+        .await
+        .unwrap();
+    assert_eq!(result["current_status"], "undelegated");
+    assert_eq!(result["delegatee"], json!(wallet));
+    assert_eq!(result["plan"]["steps"][0]["action"], "delegate");
+    let sent = client.sent_transactions();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to_addr(), Some(&token));
+    let call = DelegateCall::decode(sent[0].data().unwrap()).unwrap();
+    assert_eq!(call.delegatee, wallet);
+
+    assert!(delegate_votes
+        .execute(json!({ "token": format!("{:?}", token), "delegatee": format!("{:?}", Address::zero()) }))
+        .await
+        .is_err());
+    // The delegate already votes its own tokens
+    let err = DelegateVotesTool::new(
+        Arc::new(
+            MockEthereumClient::new()
+                .with_wallet_address(delegate)
+                .with_delegation(
+                    token,
+                    delegate,
+                    Delegation {
+                        delegate: Some(delegate),
+                        votes: Decimal::ONE,
+                    },
+                ),
+        ),
+        true,
+    )
+    .execute(json!({ "token": format!("{:?}", token), "delegatee": "self" }))
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("already delegates"));
+}
+
+#[tokio::test]
+async fn test_ens_names_lookup_listing_and_renewal() {
+    use crate::ethereum::plan::RenewCall;
+    use crate::ethereum::{EnsName, EnsSource, MockEns};
+    use ethers::abi::AbiDecode;
+
+    let wallet = Address::repeat_byte(0x11);
+    let now = 1_700_000_000u64;
+    let day = 86_400u64;
+    let name = |name: &str, expires: u64| EnsName {
+        name: name.to_string(),
+        owner: Some(wallet),
+        registrant: Some(wallet),
+        address: Some(wallet),
+        expires: Some(expires),
+        ..EnsName::default()
+    };
+    let ens: Arc<dyn EnsSource> = Arc::new(
+        MockEns::new()
+            .with_name(name("main.eth", now + 400 * day))
+            .with_name(name("soon.eth", now + 10 * day))
+            .with_name(name("lapsed.eth", now - 5 * day))
+            .with_primary_name(wallet, "main.eth")
+            .with_yearly_rent(U256::exp10(16)),
+    );
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::ONE)
+            .with_block_timestamp(now),
+    );
+
+    let lookup = GetEnsNameTool::new(client.clone(), ens.clone());
+    let result = lookup.execute(json!({ "name": "Main" })).await.unwrap();
+    assert_eq!(result["name"], "main.eth");
+    assert_eq!(result["status"], "active");
+    assert_eq!(result["expires_in_days"], 400);
+    assert!(result.get("hint").is_none());
+    let result = lookup.execute(json!({ "name": "soon.eth" })).await.unwrap();
+    assert!(result["hint"].as_str().unwrap().contains("30 days"));
+    let result = lookup
+        .execute(json!({ "name": "lapsed.eth" }))
+        .await
+        .unwrap();
+    assert_eq!(result["status"], "grace_period");
+    assert_eq!(result["grace_ends"], now + 85 * day);
+    let result = lookup.execute(json!({ "name": "free.eth" })).await.unwrap();
+    assert_eq!(result["status"], "available");
+
+    let result = ListEnsNamesTool::new(client.clone(), ens.clone())
+        .execute(json!({}))
+        .await
+        .unwrap();
+    assert_eq!(result["primary_name"], "main.eth");
+    let names: Vec<&str> = result["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|name| name["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["lapsed.eth", "soon.eth", "main.eth"]);
+    assert_eq!(result["in_grace_period"], json!(["lapsed.eth"]));
+
+    let renew = RenewEnsNameTool::new(client.clone(), ens, true);
+    let result = renew
+        .execute(json!({ "name": "lapsed.eth", "duration_days": 365, "dry_run": false }))
+        .await
+        .unwrap();
+    assert_eq!(result["price"], "0.01");
+    assert_eq!(result["max_cost"], "0.0105");
+    assert_eq!(result["new_expires"], now + 360 * day);
+    assert_eq!(result["plan"]["steps"][0]["action"], "renew_ens");
+    let sent = client.sent_transactions();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(
+        sent[0].value(),
+        Some(&U256::from(10_500_000_000_000_000u64))
+    );
+    let call = RenewCall::decode(sent[0].data().unwrap()).unwrap();
+    assert_eq!(call.name, "lapsed");
+    assert_eq!(call.duration, U256::from(365 * day));
+
+    let err = renew
+        .execute(json!({ "name": "free.eth" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not registered"));
+    assert!(renew
+        .execute(json!({ "name": "pay.main.eth" }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_call_contract_resolves_proxy_implementation() {
+    use crate::ethereum::storage::EIP1967_IMPLEMENTATION_SLOT;
+    use crate::ethereum::MockSignatures;
+    use ethers::utils::hex;
+
+    let implementation = Address::repeat_byte(0x70);
+    let proxy = Address::repeat_byte(0x80);
+    // Dispatcher for totalSupply() only
+    let implementation_code =
+        Bytes::from(hex::decode("60003560e01c806318160ddd14601757005b").unwrap());
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_code(implementation, implementation_code)
+            .with_code(proxy, Bytes::from(hex::decode("363d3d37f4").unwrap()))
+            .with_storage(
+                proxy,
+                EIP1967_IMPLEMENTATION_SLOT.parse().unwrap(),
+                H256::from(implementation),
+            )
+            .with_call_result(
+                proxy,
+                Bytes::from(vec![0x18, 0x16, 0x0d, 0xdd]),
+                Bytes::from([0u8; 31].iter().chain(&[9u8]).copied().collect::<Vec<_>>()),
+            )
+            .with_call_result(
+                proxy,
+                Bytes::from(vec![0x06, 0xfd, 0xde, 0x03]),
+                Bytes::new(),
+            ),
+    );
+    let tool = CallContractTool::new(client).with_signature_source(Arc::new(
+        MockSignatures::new().with_signature("totalSupply()"),
+    ));
+
+    let result = tool
+        .execute(json!({ "to": format!("{:?}", proxy), "data": "0x18160ddd" }))
+        .await
+        .unwrap();
+    assert_eq!(result["function"], "totalSupply()");
+    assert_eq!(result["proxy"]["standard"], "eip1967");
+    assert_eq!(
+        result["proxy"]["implementation"],
+        format!("{:?}", implementation)
+    );
+    assert_eq!(result["selector_found"], true);
+    assert!(result.get("warnings").is_none());
+
+    // A selector the implementation lacks is flagged
+    let result = tool
+        .execute(json!({ "to": format!("{:?}", proxy), "data": "0x06fdde03" }))
+        .await
+        .unwrap();
+    assert_eq!(result["selector_found"], false);
+    assert!(result["warnings"][0]
+        .as_str()
+        .unwrap()
+        .starts_with("0x06fdde03 is not among the functions of"));
+    let result = tool
+        .execute(json!({
+            "to": format!("{:?}", proxy),
+            "data": "0x18160ddd",
+            "follow_proxy": false
+        }))
+        .await
+        .unwrap();
+    assert!(result.get("proxy").is_none());
+    assert!(result.get("selector_found").is_none());
+}
+
+#[tokio::test]
+async fn test_call_contract_tolerates_usdt_style_returns() {
+    use crate::ethereum::erc20_call_succeeded;
+    use ethers::abi::{HumanReadableParser, Token};
+
+    assert!(erc20_call_succeeded(&[]));
+    assert!(erc20_call_succeeded(&[[0u8; 31].as_slice(), &[1]].concat()));
+    assert!(!erc20_call_succeeded(&[0u8; 32]));
+    assert!(!erc20_call_succeeded(&[1]));
+
+    // USDT's transfer returns no data at all
+    let usdt: Address = "0xdAC17F958D2ee523a2206206994597C13D831ec7"
+        .parse()
+        .unwrap();
+    let recipient = Address::repeat_byte(0x22);
+    let signature = "transfer(address to, uint256 amount) returns (bool)";
+    let data = HumanReadableParser::parse_function(&format!("function {}", signature))
+        .unwrap()
+        .encode_input(&[Token::Address(recipient), Token::Uint(U256::one())])
+        .unwrap();
+    let client =
+        Arc::new(MockEthereumClient::new().with_call_result(usdt, Bytes::from(data), Bytes::new()));
+    let result = CallContractTool::new(client)
+        .execute(json!({
+            "to": format!("{:?}", usdt),
+            "function": signature,
+            "args": [format!("{:?}", recipient), "1"]
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["decoded"], json!([true]));
+}
+
+#[tokio::test]
+async fn test_call_contract_decodes_and_applies_state_overrides() {
+    let wallet = Address::repeat_byte(0x11);
+    let token = Address::repeat_byte(0x70);
+    let oracle = Address::repeat_byte(0x71);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_token_balance(token, wallet, Decimal::new(5, 0), 18)
+            .with_token_decimals(token, 18)
+            .with_balance_slot(token, 3)
+            .with_call_result(
+                oracle,
+                Bytes::from(vec![0xfe, 0xaf, 0x96, 0x8c]),
+                Bytes::from([0u8; 31].iter().chain(&[7u8]).copied().collect::<Vec<_>>()),
+            ),
+    );
+    let tool = CallContractTool::new(client.clone());
+
+    let call = json!({
+        "to": format!("{:?}", token),
+        "function": "balanceOf(address owner) view returns (uint256)",
+        "args": [format!("{:?}", wallet)]
+    });
+    let result = tool.execute(call.clone()).await.unwrap();
+    assert_eq!(result["function"], "balanceOf(address)");
+    assert_eq!(result["decoded"], json!(["5000000000000000000"]));
+    assert_eq!(result["overridden"], false);
+
+    // What would the wallet's balance read with 1000 tokens
+    let mut what_if = call;
+    what_if["token_balances"] = json!([{ "token": format!("{:?}", token), "amount": "1000" }]);
+    let result = tool.execute(what_if).await.unwrap();
+    assert_eq!(result["decoded"], json!(["1000000000000000000000"]));
+    assert_eq!(result["overridden"], true);
+    assert_eq!(result["token_balances"][0]["amount"], "1000");
+    let (_, overrides) = client.calls().pop().unwrap();
+    assert!(overrides.is_some());
+
+    let result = tool
+        .execute(json!({
+            "to": format!("{:?}", oracle),
+            "data": "0xfeaf968c",
+            "state_overrides": { format!("{:?}", wallet): { "balance": "0xde0b6b3a7640000" } }
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["output"].as_str().unwrap().len(), 66);
+    assert!(result.get("decoded").is_none());
+    let (_, overrides) = client.calls().pop().unwrap();
+    let overrides = serde_json::to_value(overrides.unwrap()).unwrap();
+    assert_eq!(
+        overrides[format!("{:?}", wallet)]["balance"],
+        "0xde0b6b3a7640000"
+    );
+
+    assert!(tool
+        .execute(json!({ "to": format!("{:?}", oracle), "function": "latestRoundData()", "data": "0xfeaf968c" }))
+        .await
+        .is_err());
+    assert!(tool
+        .execute(json!({ "to": format!("{:?}", token), "function": "balanceOf(address) returns (uint256)" }))
+        .await
+        .is_err());
+    // Reverts surface as errors
+    assert!(tool
+        .execute(json!({ "to": format!("{:?}", oracle), "data": "0x12345678" }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_detect_interfaces_reports_standards_and_owner() {
+    use ethers::abi::Token;
+
+    let collection = Address::repeat_byte(0x72);
+    let owner = Address::repeat_byte(0x0a);
+    let client = with_interfaces(
+        MockEthereumClient::new().with_code(collection, Bytes::from(vec![0x60, 0x80])),
+        collection,
+        &[[0x80, 0xac, 0x58, 0xcd]],
+    )
+    .with_call_result(
+        collection,
+        calldata("owner()", &[]),
+        Bytes::from(ethers::abi::encode(&[Token::Address(owner)])),
+    )
+    .with_call_result(
+        collection,
+        calldata("totalSupply()", &[]),
+        word(U256::from(10)),
+    );
+    let tool = DetectInterfacesTool::new(Arc::new(client));
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", collection) }))
+        .await
+        .unwrap();
+    // totalSupply alone does not make an ERC-20
+    assert_eq!(result["supported"], json!(["erc165", "erc721", "ownable"]));
+    assert_eq!(result["owner"], json!(owner));
+    assert_eq!(result["interfaces"][2]["via"], "erc165");
+    assert!(result.get("proxy").is_none());
+
+    let err = tool
+        .execute(json!({ "address": format!("{:?}", Address::repeat_byte(0x01)) }))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("No contract code"));
+}
+
+#[tokio::test]
+async fn test_get_nft_floor_price_values_holdings() {
+    use crate::ethereum::{CollectionStats, MockNftMarket};
+
+    let collection = Address::repeat_byte(0x72);
+    let owner = Address::repeat_byte(0x0a);
+    let market = MockNftMarket::new().with_collection(
+        collection,
+        CollectionStats {
+            name: Some("Apes".to_string()),
+            floor_price: Some(Decimal::new(125, 1)),
+            top_bid: Some(Decimal::new(12, 0)),
+            volume_24h: Some(Decimal::new(3405, 1)),
+            currency: "ETH".to_string(),
+            token_count: Some(10_000),
+        },
+    );
+    let client = MockEthereumClient::new().with_call_result(
+        collection,
+        calldata("balanceOf(address)", &[ethers::abi::Token::Address(owner)]),
+        word(U256::from(3)),
+    );
+    let tool = GetNftFloorPriceTool::new(Arc::new(client), Arc::new(market));
+
+    let result = tool
+        .execute(json!({ "collection": format!("{:?}", collection) }))
+        .await
+        .unwrap();
+    assert_eq!(result["floor_price"], "12.5");
+    assert_eq!(result["top_bid"], "12");
+    assert_eq!(result["volume_24h"], "340.5");
+    assert_eq!(result["source"], "mock");
+    assert!(result.get("holdings").is_none());
+
+    let result = tool
+        .execute(json!({
+            "collection": format!("{:?}", collection),
+            "owner": format!("{:?}", owner),
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["holdings"]["count"], "3");
+    assert_eq!(result["holdings"]["floor_value"], "37.5");
+
+    let unknown = format!("{:?}", Address::repeat_byte(0x01));
+    assert!(tool
+        .execute(json!({ "collection": unknown }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_transfer_nft_checks_ownership_and_simulates() {
+    use ethers::abi::Token;
+
+    let wallet = Address::repeat_byte(0x11);
+    let friend = Address::repeat_byte(0x22);
+    let vault = Address::repeat_byte(0x33);
+    let collection = Address::repeat_byte(0x72);
+    let items = Address::repeat_byte(0x73);
+    let owner_of = |id: u64, owner: Address| {
+        (
+            calldata("ownerOf(uint256)", &[Token::Uint(id.into())]),
+            Bytes::from(ethers::abi::encode(&[Token::Address(owner)])),
+        )
+    };
+    let (mine, wallet_word) = owner_of(7, wallet);
+    let (theirs, friend_word) = owner_of(8, friend);
+    let client = with_interfaces(
+        with_interfaces(
+            MockEthereumClient::new().with_wallet_address(wallet),
+            collection,
+            &[[0x80, 0xac, 0x58, 0xcd]],
+        ),
+        items,
+        &[[0xd9, 0xb6, 0x7a, 0x26]],
+    )
+    .with_call_result(collection, mine, wallet_word)
+    .with_call_result(collection, theirs, friend_word)
+    // Only the friend can take the token; the vault has no receiver hook
+    .with_call_result(
+        collection,
+        calldata(
+            "safeTransferFrom(address,address,uint256)",
+            &[
+                Token::Address(wallet),
+                Token::Address(friend),
+                Token::Uint(7.into()),
+            ],
+        ),
+        Bytes::new(),
+    )
+    .with_call_result(
+        items,
+        calldata(
+            "balanceOf(address,uint256)",
+            &[Token::Address(wallet), Token::Uint(1.into())],
+        ),
+        word(U256::from(2)),
+    );
+    let tool = TransferNftTool::new(Arc::new(client), false);
+    let transfer = |collection: Address, id: &str, to: Address, amount: &str| {
+        json!({
+            "collection": format!("{:?}", collection),
+            "token_id": id,
+            "to": format!("{:?}", to),
+            "amount": amount,
+        })
+    };
+
+    let result = tool
+        .execute(transfer(collection, "7", friend, "1"))
+        .await
+        .unwrap();
+    assert_eq!(result["standard"], "erc721");
+    assert_eq!(result["plan"]["steps"][0]["action"], "transfer_nft");
+    assert_eq!(result["plan"]["steps"][0]["token_id"], "7");
+    assert_eq!(result["execution"]["dry_run"], true);
+
+    let rejected = [
+        (transfer(collection, "8", friend, "1"), "owned by"),
+        (transfer(collection, "7", vault, "1"), "would revert"),
+        (transfer(collection, "7", friend, "2"), "amount must be 1"),
+        (transfer(items, "1", friend, "3"), "holds 2"),
+        (transfer(collection, "7", wallet, "1"), "cannot receive"),
+    ];
+    for (params, expected) in rejected {
+        let err = format!("{:#}", tool.execute(params).await.unwrap_err());
+        assert!(err.contains(expected), "{}", err);
+    }
+}
+
+#[tokio::test]
+async fn test_preview_transfer_reports_amount_received() {
+    let wallet = Address::repeat_byte(0x11);
+    let recipient = Address::repeat_byte(0x22);
+    let token = Address::repeat_byte(0x70);
+    let probe_call = |amount: u64| {
+        Bytes::from(ethers::abi::encode(&[
+            ethers::abi::Token::Address(token),
+            ethers::abi::Token::Address(recipient),
+            ethers::abi::Token::Uint(U256::exp10(18) * amount),
+        ]))
+    };
+    // The recipient goes from 10 to 105 tokens: 95 of the 100 sent arrive
+    let balances = |before: u64, after: u64| {
+        Bytes::from(ethers::abi::encode(&[
+            ethers::abi::Token::Uint(U256::exp10(18) * before),
+            ethers::abi::Token::Uint(U256::exp10(18) * after),
+        ]))
+    };
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_token_balance(token, wallet, Decimal::new(150, 0), 18)
+            .with_token_decimals(token, 18)
+            .with_balance_slot(token, 3)
+            .with_call_result(wallet, probe_call(100), balances(10, 105))
+            .with_call_result(wallet, probe_call(200), balances(0, 200)),
+    );
+    let tool = PreviewTransferTool::new(client.clone());
+
+    let result = tool
+        .execute(json!({
+            "token": format!("{:?}", token),
+            "to": format!("{:?}", recipient),
+            "amount": "100"
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["amount_received"], "95");
+    assert_eq!(result["transfer_fee_percent"], "5");
+    assert_eq!(result["balance_overridden"], false);
+    let (_, overrides) = client.calls().pop().unwrap();
+    let overrides = serde_json::to_value(overrides.unwrap()).unwrap();
+    assert!(overrides[format!("{:?}", wallet)]["code"].is_string());
+
+    // More than the wallet holds: its balance is overridden to the amount
+    let result = tool
+        .execute(json!({
+            "token": format!("{:?}", token),
+            "to": format!("{:?}", recipient),
+            "amount": "200"
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["amount_received"], "200");
+    assert_eq!(result["transfer_fee_percent"], "0");
+    assert_eq!(result["balance_overridden"], true);
+    let (_, overrides) = client.calls().pop().unwrap();
+    let overrides = serde_json::to_value(overrides.unwrap()).unwrap();
+    assert!(overrides[format!("{:?}", token)]["stateDiff"].is_object());
+}
+
+#[tokio::test]
+async fn test_read_storage_mapping_slots_and_proxy_detection() {
+    use crate::ethereum::storage::{
+        EIP1967_ADMIN_SLOT, EIP1967_BEACON_SLOT, EIP1967_IMPLEMENTATION_SLOT,
+    };
+    use crate::ethereum::{mapping_slot, MappingLayout};
+
+    let holder = Address::repeat_byte(0x11);
+    let token = Address::repeat_byte(0x70);
+    let proxy = Address::repeat_byte(0x80);
+    let implementation = Address::repeat_byte(0x81);
+    let admin = Address::repeat_byte(0x82);
+    let beacon_proxy = Address::repeat_byte(0x90);
+    let beacon = Address::repeat_byte(0x91);
+
+    let balance_slot = mapping_slot(
+        H256::from_low_u64_be(3),
+        H256::from(holder).as_bytes(),
+        MappingLayout::Solidity,
+    );
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_storage(token, H256::zero(), H256::from(admin))
+            .with_storage(token, balance_slot, H256::from_low_u64_be(1000))
+            .with_storage(
+                proxy,
+                EIP1967_IMPLEMENTATION_SLOT.parse().unwrap(),
+                H256::from(implementation),
+            )
+            .with_storage(
+                proxy,
+                EIP1967_ADMIN_SLOT.parse().unwrap(),
+                H256::from(admin),
+            )
+            .with_storage(
+                beacon_proxy,
+                EIP1967_BEACON_SLOT.parse().unwrap(),
+                H256::from(beacon),
+            )
+            .with_call_result(
+                beacon,
+                Bytes::from(vec![0x5c, 0x60, 0xda, 0x1b]),
+                Bytes::from(H256::from(implementation).as_bytes().to_vec()),
+            ),
+    );
+    let tool = ReadStorageTool::new(client);
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", token), "slot": "0", "count": 2 }))
+        .await
+        .unwrap();
+    assert_eq!(result["words"].as_array().unwrap().len(), 2);
+    assert_eq!(result["words"][0]["address"], json!(admin));
+    assert_eq!(result["words"][1]["uint"], "0");
+    assert!(result["words"][1].get("address").is_none());
+    assert!(result.get("proxy").is_none());
+
+    let result = tool
+        .execute(json!({
+            "address": format!("{:?}", token),
+            "slot": "0x3",
+            "keys": [format!("{:?}", holder)]
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["words"][0]["slot"], json!(balance_slot));
+    assert_eq!(result["words"][0]["uint"], "1000");
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", proxy), "slot": "eip1967_implementation" }))
+        .await
+        .unwrap();
+    assert_eq!(result["words"][0]["address"], json!(implementation));
+    assert_eq!(result["proxy"]["standard"], "eip1967");
+    assert_eq!(result["proxy"]["implementation"], json!(implementation));
+    assert_eq!(result["proxy"]["admin"], json!(admin));
+
+    // Beacon proxies delegate to whatever the beacon reports
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", beacon_proxy) }))
+        .await
+        .unwrap();
+    assert!(result["words"].as_array().unwrap().is_empty());
+    assert_eq!(result["proxy"]["standard"], "eip1967_beacon");
+    assert_eq!(result["proxy"]["beacon"], json!(beacon));
+    assert_eq!(result["proxy"]["implementation"], json!(implementation));
+
+    assert!(tool
+        .execute(json!({ "address": format!("{:?}", token), "keys": ["1"] }))
+        .await
+        .is_err());
+    assert!(tool
+        .execute(json!({ "address": format!("{:?}", token), "slot": "0", "count": 33 }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_inspect_bytecode_selectors_flags_and_proxies() {
+    use crate::ethereum::storage::EIP1967_IMPLEMENTATION_SLOT;
+    use crate::ethereum::MockSignatures;
+    use ethers::utils::hex;
+
+    let token = Address::repeat_byte(0x70);
+    let proxy = Address::repeat_byte(0x80);
+    let clone = Address::repeat_byte(0x81);
+    let wallet = Address::repeat_byte(0x11);
+
+    // Dispatcher for transfer and mint, a masked PUSH4 that is no selector, PUSH data and
+    // metadata holding opcode bytes that must not be flagged, then DELEGATECALL and SELFDESTRUCT
+    let token_code = Bytes::from(
+        hex::decode(format!(
+            "600035 60e01c 80 63a9059cbb 14 602057 80 6340c10f19 14 602057 63ffffffff 16 7f{} 5b f4 ff 00 a1fff4 0003",
+            "fff4".repeat(16)
+        )
+        .replace(' ', ""))
+        .unwrap(),
+    );
+    let clone_code = Bytes::from(
+        hex::decode(format!(
+            "363d3d373d3d3d363d73{}5af43d82803e903d91602b57fd5bf3",
+            hex::encode(token)
+        ))
+        .unwrap(),
+    );
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_code(token, token_code)
+            .with_code(proxy, Bytes::from(hex::decode("363d3d37f4").unwrap()))
+            .with_storage(
+                proxy,
+                EIP1967_IMPLEMENTATION_SLOT.parse().unwrap(),
+                H256::from(token),
+            )
+            .with_code(clone, clone_code),
+    );
+    let signatures = Arc::new(
+        MockSignatures::new()
+            .with_signature("transfer(address,uint256)")
+            .with_signature("mint(address,uint256)"),
+    );
+    let tool = InspectBytecodeTool::new(client.clone(), signatures);
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", token), "disassembly": true }))
+        .await
+        .unwrap();
+    assert_eq!(
+        result["selectors"],
+        json!([
+            { "selector": "0xa9059cbb", "signatures": ["transfer(address,uint256)"] },
+            { "selector": "0x40c10f19", "signatures": ["mint(address,uint256)"] }
+        ])
+    );
+    assert_eq!(
+        result["privileged_functions"],
+        json!([{ "signature": "mint(address,uint256)", "concern": "can create new supply" }])
+    );
+    let flagged = result["flagged_opcodes"].as_array().unwrap();
+    assert_eq!(flagged.len(), 2);
+    assert_eq!(flagged[0]["opcode"], "SELFDESTRUCT");
+    assert_eq!(flagged[0]["count"], 1);
+    assert_eq!(flagged[1]["opcode"], "DELEGATECALL");
+    assert!(flagged[1]["note"]
+        .as_str()
+        .unwrap()
+        .contains("another contract"));
+    let disassembly = result["disassembly"].as_array().unwrap();
+    assert_eq!(disassembly[0], "0x0000 PUSH1 0x00");
+    assert_eq!(disassembly[5], "0x0007 PUSH4 0xa9059cbb");
+    assert!(result.get("proxy").is_none());
+    assert!(result.get("implementation").is_none());
+
+    // The implementation behind an EIP-1967 proxy is inspected too
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", proxy) }))
+        .await
+        .unwrap();
+    assert_eq!(result["proxy"]["standard"], "eip1967");
+    assert!(result["flagged_opcodes"][0]["note"]
+        .as_str()
+        .unwrap()
+        .starts_with("Expected in a proxy"));
+    assert_eq!(result["implementation"]["address"], json!(token));
+    assert_eq!(
+        result["implementation"]["selectors"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", clone), "follow_proxy": false }))
+        .await
+        .unwrap();
+    assert_eq!(result["clone_of"], json!(token));
+    assert!(result.get("implementation").is_none());
+
+    // Selectors are still listed when the signature directory is down
+    let offline =
+        InspectBytecodeTool::new(client, Arc::new(MockSignatures::new().with_unavailable()));
+    let result = offline
+        .execute(json!({ "address": format!("{:?}", token) }))
+        .await
+        .unwrap();
+    assert_eq!(result["selectors"][0]["signatures"], json!([]));
+    assert!(result["signature_lookup_error"].is_string());
+    assert!(result["privileged_functions"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    let err = offline
+        .execute(json!({ "address": format!("{:?}", wallet) }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No contract code"));
+}
+
+#[tokio::test]
+async fn test_scam_list_blocks_payments_and_approvals() {
+    let wallet = Address::repeat_byte(0x11);
+    let scammer = Address::repeat_byte(0x5c);
+    let alice = Address::repeat_byte(0xa1);
+    let token = Address::repeat_byte(0x70);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_eth_balance(wallet, Decimal::new(2, 0)),
+    );
+    let scams = Arc::new(ScamList::new(Vec::new()).with_addresses("scamsniffer", [scammer]));
+    let executor = ExecutePlanTool::new(client.clone(), true).with_scam_list(scams.clone());
+    let transfer =
+        |to: Address| json!({ "action": "transfer", "to": format!("{:?}", to), "amount": "1000" });
+    let approve = json!({
+        "action": "approve",
+        "token": format!("{:?}", token),
+        "spender": format!("{:?}", scammer),
+        "amount": "1000"
+    });
+
+    // Dry runs are refused too, and approvals count as much as transfers
+    let err = executor
+        .execute(json!({ "plan": { "steps": [transfer(alice), transfer(scammer)] } }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("listed by scamsniffer"));
+    assert!(executor
+        .execute(json!({ "plan": { "steps": [approve] } }))
+        .await
+        .is_err());
+    // Steps already run are not checked again
+    assert!(executor
+        .execute(
+            json!({ "plan": { "steps": [transfer(scammer), transfer(alice)] }, "start_step": 1 })
+        )
+        .await
+        .is_ok());
+
+    let err = executor
+        .execute(json!({ "plan": { "steps": [transfer(scammer)] }, "override_scam_list": true }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ALLOW_SCAM_LIST_OVERRIDE"));
+    let permissive = Arc::new(
+        ScamList::new(Vec::new())
+            .with_override_allowed(true)
+            .with_addresses("scamsniffer", [scammer]),
+    );
+    let batch = BatchTransferTool::new(client.clone(), Address::repeat_byte(0xd1), true)
+        .with_scam_list(permissive.clone());
+    let payments = json!([{ "to": format!("{:?}", scammer), "amount": "0.1" }]);
+    assert!(batch
+        .execute(json!({ "payments": payments.clone() }))
+        .await
+        .is_err());
+    let result = batch
+        .execute(json!({ "payments": payments, "override_scam_list": true }))
+        .await
+        .unwrap();
+    assert_eq!(result["execution"]["dry_run"], true);
+
+    let schedule =
+        SchedulePaymentTool::new(client.clone(), Arc::new(PaymentSchedules::new()), true)
+            .with_scam_list(permissive);
+    let err = schedule
+        .execute(json!({ "to": format!("{:?}", scammer), "amount": "0.1", "recurrence": "weekly" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("scam address list"));
+
+    // A signed ERC20 transfer to a listed address is not broadcast
+    let signer: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        .parse()
+        .unwrap();
+    let data = [
+        &ethers::utils::keccak256("transfer(address,uint256)")[..4],
+        &ethers::abi::encode(&[
+            ethers::abi::Token::Address(scammer),
+            ethers::abi::Token::Uint(U256::from(1000)),
+        ])[..],
+    ]
+    .concat();
+    let tx: ethers::types::transaction::eip2718::TypedTransaction =
+        Eip1559TransactionRequest::new()
+            .to(token)
+            .data(data)
+            .chain_id(1u64)
+            .nonce(0u64)
+            .gas(60_000u64)
+            .max_fee_per_gas(1u64)
+            .max_priority_fee_per_gas(1u64)
+            .into();
+    let signature = signer.sign_transaction_sync(&tx).unwrap();
+    let raw = format!(
+        "0x{}",
+        ethers::utils::hex::encode(tx.rlp_signed(&signature))
+    );
+    let import = ImportSignedTransactionTool::new(client.clone(), true).with_scam_list(scams);
+    assert!(import
+        .execute(json!({ "signed_transaction": raw.clone() }))
+        .await
+        .is_ok());
+    let err = import
+        .execute(json!({ "signed_transaction": raw, "broadcast": true }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains(&format!("{:?}", scammer)));
+    assert!(client.sent_raw_transactions().is_empty());
+}
+
+#[tokio::test]
+async fn test_simulate_bundle_reports_balance_diffs_and_events() {
+    let wallet = Address::repeat_byte(0x11);
+    let recipient = Address::repeat_byte(0x22);
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let usdc = Address::repeat_byte(0x70);
+    let log = |address: Address, signature: &str, indexed: &[Address], amount: U256| {
+        let mut data = [0u8; 32];
+        amount.to_big_endian(&mut data);
+        Log {
+            address,
+            topics: std::iter::once(H256::from(ethers::utils::keccak256(signature)))
+                .chain(indexed.iter().map(|address| H256::from(*address)))
+                .collect(),
+            data: Bytes::from(data.to_vec()),
+            ..Default::default()
+        }
+    };
+    let one_eth = U256::exp10(18);
+    let client = Arc::new(
+        MockEthereumClient::new()
+            .with_wallet_address(wallet)
+            .with_token_symbol(weth, "WETH".to_string())
+            .with_token_decimals(weth, 18)
+            .with_token_symbol(usdc, "USDC".to_string())
+            .with_token_decimals(usdc, 6)
+            .with_simulated_logs(
+                weth,
+                vec![log(weth, "Deposit(address,uint256)", &[wallet], one_eth)],
+            )
+            .with_simulated_logs(
+                usdc,
+                vec![log(
+                    usdc,
+                    "Transfer(address,address,uint256)",
+                    &[wallet, recipient],
+                    U256::from(2_500_000),
+                )],
+            )
+            .with_failing_target(Address::repeat_byte(0x99)),
+    );
+    let tool = SimulateBundleTool::new(client.clone());
+
+    let plan = json!({ "steps": [
+        { "action": "wrap", "amount": one_eth.to_string() },
+        { "action": "transfer", "token": format!("{:?}", usdc), "to": format!("{:?}", recipient), "amount": "2500000" }
+    ]});
+    let result = tool.execute(json!({ "plan": plan })).await.unwrap();
+    assert_eq!(result["success"], true);
+    assert_eq!(result["transactions"][0]["action"], "wrap");
+    assert_eq!(
+        result["transactions"][0]["events"][0]["event"],
+        "Deposit(address,uint256)"
+    );
+    assert_eq!(result["total_gas_used"], "42000");
+
+    // The wallet's changes come first; the ETH it wrapped lands in WETH
+    let diffs = result["balance_diffs"].as_array().unwrap();
+    let diff = |account: Address, symbol: &str| {
+        diffs
+            .iter()
+            .find(|diff| diff["account"] == format!("{:?}", account) && diff["symbol"] == symbol)
+            .unwrap_or_else(|| panic!("no {} diff for {:?}", symbol, account))
+    };
+    assert_eq!(diffs[0]["account"], format!("{:?}", wallet));
+    assert_eq!(diff(wallet, "ETH")["change"], "-1");
+    assert_eq!(diff(wallet, "WETH")["change"], "1");
+    assert_eq!(diff(wallet, "USDC")["change"], "-2.5");
+    assert_eq!(diff(wallet, "USDC")["change_raw"], "-2500000");
+    assert_eq!(diff(recipient, "USDC")["change"], "2.5");
+    assert_eq!(diff(weth, "ETH")["change"], "1");
+    assert_eq!(diffs.len(), 5);
+
+    // Raw transactions, filtered to one account; a revert is reported, not raised
+    let result = tool
+        .execute(json!({
+            "transactions": [
+                { "to": format!("{:?}", recipient), "value": "0xde0b6b3a7640000" },
+                { "to": format!("{:?}", Address::repeat_byte(0x99)), "data": "0x1234" }
+            ],
+            "accounts": [format!("{:?}", recipient)]
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["success"], false);
+    assert_eq!(result["transactions"][1]["success"], false);
+    assert_eq!(result["transactions"][1]["error"], "execution reverted");
+    assert_eq!(result["balance_diffs"].as_array().unwrap().len(), 1);
+    assert_eq!(result["balance_diffs"][0]["change"], "1");
+
+    assert!(tool.execute(json!({})).await.is_err());
+    assert!(tool
+        .execute(json!({ "transactions": [], "plan": plan }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_watch_address_reports_transfers_and_balance_changes() {
+    use crate::ethereum::{MockTokenEvents, TokenTransfer};
+    use crate::tools::watch_list::WATCH_TOPIC;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(String, Value)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send(&self, topic: &str, _severity: AlertSeverity, alert: &Value) -> usize {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), alert.clone()));
+            1
+        }
+    }
+
+    let watched = Address::repeat_byte(0x42);
+    let other = Address::repeat_byte(0x77);
+    let usdc = Address::repeat_byte(0xcc);
+    let transfer = |from, to, amount: u64, block_number| TokenTransfer {
+        token: usdc,
+        from,
+        to,
+        amount: U256::from(amount),
+        block_number,
+        tx_hash: H256::repeat_byte(block_number as u8),
+    };
+    let events = MockTokenEvents::new()
+        // Before the watch started
+        .with_token_transfer(transfer(other, watched, 5_000_000, 99))
+        .with_token_transfer(transfer(other, watched, 2_000_000, 103))
+        .with_token_transfer(transfer(watched, other, 500_000, 104));
+    let sink = Arc::new(RecordingSink::default());
+    let watches = Arc::new(WatchList::new(Arc::new(events), 10).with_alert_sink(sink.clone()));
+
+    let now = MockEthereumClient::new()
+        .with_block_number(100)
+        .with_eth_balance(watched, Decimal::new(15, 1));
+    let tool = WatchAddressTool::new(Arc::new(now), watches.clone());
+    let result = tool
+        .execute(
+            json!({ "action": "add", "address": format!("{:?}", watched), "label": "treasury" }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["items"][0]["label"], "treasury");
+    assert_eq!(result["items"][0]["eth_balance"], "1.5");
+    assert_eq!(result["resource_uri"], "watch://events");
+
+    let later = MockEthereumClient::new()
+        .with_block_number(105)
+        .with_eth_balance(watched, Decimal::new(125, 2))
+        .with_token_symbol(usdc, "USDC".to_string())
+        .with_token_decimals(usdc, 6);
+    let found = watches.poll(&later).await.unwrap();
+    assert_eq!(found.len(), 3);
+
+    let sent = sink.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 3);
+    assert!(sent.iter().all(|(topic, _)| topic == WATCH_TOPIC));
+    let (_, incoming) = &sent[0];
+    assert_eq!(incoming["kind"], "incoming_transfer");
+    assert_eq!(incoming["label"], "treasury");
+    assert_eq!(incoming["symbol"], "USDC");
+    assert_eq!(incoming["amount"], "2");
+    assert_eq!(incoming["amount_raw"], "2000000");
+    assert_eq!(incoming["counterparty"], json!(other));
+    assert_eq!(incoming["block_number"], 103);
+    assert_eq!(sent[1].1["kind"], "outgoing_transfer");
+    assert_eq!(sent[1].1["amount"], "0.5");
+    assert_eq!(sent[2].1["kind"], "balance_change");
+    assert_eq!(sent[2].1["amount"], "-0.25");
+    assert_eq!(sent[2].1["balance"], "1.25");
+
+    // Nothing new on the next poll
+    assert!(watches.poll(&later).await.unwrap().is_empty());
+
+    let page = tool
+        .execute(json!({ "action": "events", "cursor": "1", "limit": 1 }))
+        .await
+        .unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["items"][0]["kind"], "outgoing_transfer");
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["next_cursor"], "2");
+
+    let removed = tool
+        .execute(json!({ "action": "remove", "address": format!("{:?}", watched) }))
+        .await
+        .unwrap();
+    assert!(removed["items"].as_array().unwrap().is_empty());
+    assert!(tool
+        .execute(json!({ "action": "remove", "address": format!("{:?}", watched) }))
+        .await
+        .is_err());
+    // The feed outlives the watch
+    assert_eq!(watches.events_since(0, Some(watched)).len(), 3);
+}
+
+#[tokio::test]
+async fn test_analyze_gas_usage_groups_spending_and_suggests_savings() {
+    use crate::ethereum::{HistoricalTransaction, MockSignatures, MockTransactionHistory};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let wallet = Address::repeat_byte(0x11);
+    let token = Address::repeat_byte(0xaa);
+    let router = Address::repeat_byte(0xbb);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let gwei = U256::exp10(9);
+    let tx = |n: u8, to: Address, selector: Option<[u8; 4]>, gas_used: u64, success| {
+        HistoricalTransaction {
+            hash: H256::repeat_byte(n),
+            block_number: n as u64,
+            timestamp: now - n as u64 * 3_600,
+            from: wallet,
+            to: Some(to),
+            value: U256::zero(),
+            gas_used: U256::from(gas_used),
+            gas_price: gwei * 10,
+            selector,
+            function_name: None,
+            success,
+        }
+    };
+    let transfer = Some([0xa9, 0x05, 0x9c, 0xbb]);
+    let approve = Some([0x09, 0x5e, 0xa7, 0xb3]);
+    let swap = Some([0x38, 0xed, 0x17, 0x39]);
+    let mut history = MockTransactionHistory::new();
+    for n in 1..=4 {
+        history = history.with_transaction(tx(n, token, transfer, 50_000, true));
+    }
+    for n in 5..=7 {
+        history = history.with_transaction(tx(n, token, approve, 46_000, true));
+    }
+    history = history
+        .with_transaction(tx(8, router, swap, 250_000, true))
+        .with_transaction(tx(9, router, swap, 150_000, false))
+        // Received, so paid by someone else
+        .with_transaction(HistoricalTransaction {
+            from: Address::repeat_byte(0x99),
+            to: Some(wallet),
+            ..tx(10, wallet, None, 21_000, true)
+        })
+        // Before the period
+        .with_transaction(HistoricalTransaction {
+            timestamp: now - 40 * 86_400,
+            ..tx(11, router, swap, 900_000, true)
+        });
+
+    let client = MockEthereumClient::new().with_wallet_address(wallet);
+    let tool =
+        AnalyzeGasUsageTool::new(Arc::new(client), Arc::new(history)).with_signatures(Arc::new(
+            MockSignatures::new().with_signature(
+                "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+            ),
+        ));
+    let result = tool.execute(json!({ "days": 30 })).await.unwrap();
+
+    assert_eq!(result["transaction_count"], 9);
+    assert_eq!(result["failed_count"], 1);
+    assert_eq!(result["total_gas_used"], "738000");
+    assert_eq!(result["total_fee_eth"], "0.00738");
+    assert_eq!(result["average_gas_price_gwei"], "10");
+
+    let methods = result["by_contract_method"].as_array().unwrap();
+    assert_eq!(methods.len(), 3);
+    // The swaps cost most
+    assert_eq!(
+        methods[0]["method"],
+        "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)"
+    );
+    assert_eq!(methods[0]["count"], 2);
+    assert_eq!(methods[0]["failed"], 1);
+    assert_eq!(methods[0]["average_gas_used"], "200000");
+    assert_eq!(methods[1]["method"], "0xa9059cbb");
+    assert_eq!(methods[1]["fee_percent"], "27.10");
+
+    let suggestions = result["suggestions"].as_array().unwrap();
+    let kinds: Vec<&str> = suggestions
+        .iter()
+        .map(|s| s["kind"].as_str().unwrap())
+        .collect();
+    // Largest savings first: the swaps' gas above a plain swap, the failed swap's
+    // fee, two repeat approvals, and three folded base costs
+    assert_eq!(
+        kinds,
+        ["venues", "failed_transactions", "approvals", "batching"]
+    );
+    assert_eq!(suggestions[0]["estimated_gas_saved"], "160000");
+    assert_eq!(suggestions[1]["estimated_fee_saved_eth"], "0.0015");
+    assert_eq!(suggestions[2]["estimated_gas_saved"], "92000");
+    assert_eq!(suggestions[3]["estimated_gas_saved"], "63000");
+    assert_eq!(suggestions[3]["estimated_fee_saved_eth"], "0.00063");
+    // Too little spent to suggest an L2
+    assert!(!kinds.contains(&"l2_migration"));
+
+    assert!(tool.execute(json!({ "days": 0 })).await.is_err());
+}
+
+fn validator(
+    index: u64,
+    balance_gwei: u64,
+    withdrawal_address: Address,
+) -> crate::ethereum::Validator {
+    let mut credentials = [0u8; 32];
+    credentials[0] = 0x01;
+    credentials[12..].copy_from_slice(withdrawal_address.as_bytes());
+    crate::ethereum::Validator {
+        index,
+        pubkey: "0xa1d1".to_string(),
+        status: "active_ongoing".to_string(),
+        balance_gwei,
+        effective_balance_gwei: 32_000_000_000,
+        slashed: false,
+        withdrawal_credentials: H256::from(credentials),
+        activation_epoch: 100,
+        exit_epoch: crate::ethereum::FAR_FUTURE_EPOCH,
+    }
+}
+
+fn withdrawal(
+    index: u64,
+    validator_index: u64,
+    address: Address,
+    amount_gwei: u64,
+    days_ago: u64,
+) -> crate::ethereum::BeaconWithdrawal {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    crate::ethereum::BeaconWithdrawal {
+        index,
+        validator_index,
+        address,
+        amount_gwei,
+        block_number: 20_000_000 - days_ago * 7_200,
+        timestamp: now - days_ago * 86_400,
+    }
+}
+
+#[tokio::test]
+async fn test_get_validator_info_counts_withdrawals_in_the_apr() {
+    use crate::ethereum::{MockBeacon, MockTransactionHistory};
+
+    let wallet = Address::repeat_byte(0x11);
+    let head = 8_000_000;
+    // Seven days of slots before the head
+    let week_ago = head - 50_400;
+    let beacon = MockBeacon::new(head)
+        .with_validator(week_ago, validator(42, 32_010_000_000, wallet))
+        .with_validator(head, validator(42, 32_004_000_000, wallet))
+        .with_validator(
+            head,
+            crate::ethereum::Validator {
+                pubkey: "0xb2b2".to_string(),
+                ..validator(7, 32_000_000_000, wallet)
+            },
+        );
+    // Swept in between, and by another validator and before the window
+    let history = MockTransactionHistory::new()
+        .with_withdrawal(withdrawal(2, 42, wallet, 16_000_000, 3))
+        .with_withdrawal(withdrawal(3, 43, wallet, 15_000_000, 3))
+        .with_withdrawal(withdrawal(1, 42, wallet, 14_000_000, 10));
+    let client = MockEthereumClient::new().with_wallet_address(wallet);
+    let tool = GetValidatorInfoTool::new(Arc::new(client), Arc::new(beacon), Arc::new(history));
+
+    let result = tool.execute(json!({ "validator": 42 })).await.unwrap();
+    assert_eq!(result["status"], "active_ongoing");
+    assert_eq!(result["balance"], "32.004");
+    assert_eq!(result["effective_balance"], "32");
+    assert_eq!(result["withdrawal_credentials_type"], "execution");
+    assert_eq!(result["withdraws_to_wallet"], true);
+    assert_eq!(result["activation_epoch"], 100);
+    assert!(result.get("exit_epoch").is_none());
+    // 32.004 + 0.016 - 32.01 = 0.01 ETH a week on 32 ETH
+    assert_eq!(result["apr"]["withdrawn"], "0.016");
+    assert_eq!(result["apr"]["rewards"], "0.01");
+    assert_eq!(result["apr"]["apr_percent"], "1.63");
+
+    let by_pubkey = tool
+        .execute(json!({ "validator": "0xA1D1", "apr_days": 1 }))
+        .await
+        .unwrap();
+    assert_eq!(by_pubkey["index"], 42);
+    assert_eq!(by_pubkey["apr"]["days"], 1);
+
+    // Activated within the window
+    let new = tool.execute(json!({ "validator": "7" })).await.unwrap();
+    assert_eq!(new["index"], 7);
+    assert!(new.get("apr").is_none());
+
+    assert!(tool
+        .execute(json!({ "validator": "forty-two" }))
+        .await
+        .is_err());
+    assert!(tool
+        .execute(json!({ "validator": 42, "apr_days": 0 }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_list_withdrawals_totals_by_validator() {
+    use crate::ethereum::MockTransactionHistory;
+
+    let wallet = Address::repeat_byte(0x11);
+    let other = Address::repeat_byte(0x22);
+    let history = MockTransactionHistory::new()
+        .with_withdrawal(withdrawal(1, 42, wallet, 16_000_000, 20))
+        .with_withdrawal(withdrawal(2, 43, wallet, 15_000_000, 12))
+        .with_withdrawal(withdrawal(3, 42, wallet, 17_000_000, 5))
+        .with_withdrawal(withdrawal(4, 42, wallet, 32_000_000_000, 40))
+        .with_withdrawal(withdrawal(5, 44, other, 18_000_000, 5));
+    let client = MockEthereumClient::new().with_wallet_address(wallet);
+    let tool = ListWithdrawalsTool::new(Arc::new(client), Arc::new(history));
+
+    let result = tool.execute(json!({})).await.unwrap();
+    let withdrawals = result["items"].as_array().unwrap();
+    assert_eq!(withdrawals.len(), 3);
+    assert_eq!(withdrawals[0]["index"], 3);
+    assert_eq!(withdrawals[0]["amount"], "0.017");
+    assert_eq!(result["total"], 3);
+    assert_eq!(result["total_amount"], "0.048");
+    assert_eq!(result["by_validator"][0]["validator_index"], 42);
+    assert_eq!(result["by_validator"][0]["withdrawals"], 2);
+    assert_eq!(result["by_validator"][0]["amount"], "0.033");
+    assert!(result.get("next_cursor").is_none());
+
+    // Totals cover the whole period, not just the page
+    let result = tool
+        .execute(json!({ "days": 60, "validator_index": 42, "limit": 2 }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"].as_array().unwrap().len(), 2);
+    assert_eq!(result["total"], 3);
+    assert_eq!(result["total_amount"], "32.033");
+    assert_eq!(result["by_validator"][0]["withdrawals"], 3);
+    assert_eq!(result["next_cursor"], "2");
+
+    let result = tool
+        .execute(json!({ "days": 60, "validator_index": 42, "limit": 2, "cursor": "2" }))
+        .await
+        .unwrap();
+    assert_eq!(result["items"].as_array().unwrap().len(), 1);
+    assert_eq!(result["items"][0]["index"], 4);
+    assert!(result.get("next_cursor").is_none());
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", other) }))
+        .await
+        .unwrap();
+    assert_eq!(result["total_amount"], "0.018");
+}
+
+#[tokio::test]
+async fn test_get_restaking_positions_reports_slashing_and_withdrawal_queue() {
+    use crate::ethereum::{
+        MockRestaking, QueuedWithdrawal, RestakedDeposit, RestakingPosition, StrategyShares,
+    };
+
+    let wallet = Address::repeat_byte(0x11);
+    let steth = Address::repeat_byte(0x5e);
+    let steth_strategy = Address::repeat_byte(0x93);
+    let beacon_strategy = Address::repeat_byte(0xbe);
+    let operator = Address::repeat_byte(0x0b);
+    let shares = |strategy, token, shares: u64, underlying: u64| StrategyShares {
+        strategy,
+        token,
+        shares: U256::from(shares) * U256::exp10(15),
+        underlying: U256::from(underlying) * U256::exp10(15),
+    };
+    let position = RestakingPosition {
+        deposits: vec![
+            RestakedDeposit {
+                withdrawable: shares(steth_strategy, Some(steth), 900, 990),
+                deposit_shares: U256::from(1_000u64) * U256::exp10(15),
+            },
+            RestakedDeposit {
+                withdrawable: shares(beacon_strategy, None, 32_000, 32_000),
+                deposit_shares: U256::from(32_000u64) * U256::exp10(15),
+            },
+        ],
+        operator: Some(operator),
+        eigen_pod: Some(Address::repeat_byte(0x90)),
+        queued_withdrawals: vec![
+            QueuedWithdrawal {
+                withdrawer: wallet,
+                delegated_to: Some(operator),
+                start_block: 1_000,
+                strategies: vec![shares(steth_strategy, Some(steth), 100, 110)],
+            },
+            QueuedWithdrawal {
+                withdrawer: wallet,
+                delegated_to: None,
+                start_block: 1_800,
+                strategies: vec![shares(beacon_strategy, None, 1_000, 1_000)],
+            },
+        ],
+        withdrawal_delay_blocks: 500,
+    };
+    let client = MockEthereumClient::new()
+        .with_wallet_address(wallet)
+        .with_block_number(2_000)
+        .with_token_symbol(steth, "stETH".to_string())
+        .with_token_decimals(steth, 18);
+    let restaking = MockRestaking::new().with_position(wallet, position);
+    let tool = GetRestakingPositionsTool::new(Arc::new(client), Arc::new(restaking));
+
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["protocol"], "mock");
+    assert_eq!(result["operator"], json!(operator));
+    let deposits = result["deposits"].as_array().unwrap();
+    assert_eq!(deposits[0]["symbol"], "stETH");
+    assert_eq!(deposits[0]["amount"], "0.99");
+    assert_eq!(deposits[0]["slashed_shares"], "100000000000000000");
+    assert_eq!(deposits[1]["symbol"], "ETH");
+    assert_eq!(deposits[1]["amount"], "32");
+    assert!(deposits[1].get("token").is_none());
+    assert!(deposits[1].get("slashed_shares").is_none());
+
+    let queued = result["queued_withdrawals"].as_array().unwrap();
+    assert_eq!(queued[0]["completable_at_block"], 1_500);
+    assert_eq!(queued[0]["completable"], true);
+    assert_eq!(queued[0]["strategies"][0]["amount"], "0.11");
+    assert_eq!(queued[1]["completable_at_block"], 2_300);
+    assert_eq!(queued[1]["completable"], false);
+    assert!(queued[1].get("delegated_to").is_none());
+
+    // A staker with nothing restaked
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", Address::repeat_byte(0x22)) }))
+        .await
+        .unwrap();
+    assert!(result["deposits"].as_array().unwrap().is_empty());
+    assert!(result.get("operator").is_none());
+}
+
+#[tokio::test]
+async fn test_compare_lsts_ranks_by_apr_and_reports_peg() {
+    use crate::ethereum::{LiquidStakingToken, MockLst, MockVenue, PriceVenue};
+
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let steth = Address::repeat_byte(0x5e);
+    let reth = Address::repeat_byte(0x4e);
+    let cbeth = Address::repeat_byte(0xcb);
+    let lsts: Vec<Arc<dyn LiquidStakingToken>> = vec![
+        Arc::new(MockLst::new("stETH", steth, Decimal::ONE).with_apr(Decimal::new(29, 1))),
+        Arc::new(MockLst::new("rETH", reth, Decimal::new(1_12, 2)).with_apr(Decimal::new(31, 1))),
+        Arc::new(MockLst::new("cbETH", cbeth, Decimal::new(1_08, 2))),
+    ];
+    let venues: Vec<Arc<dyn PriceVenue>> = vec![
+        Arc::new(
+            MockVenue::new("curve")
+                .with_rate(steth, weth, Decimal::new(9_990, 4))
+                .with_rate(reth, weth, Decimal::new(1_1144, 4)),
+        ),
+        Arc::new(MockVenue::new("uniswap_v3").with_rate(reth, weth, Decimal::new(1_1256, 4))),
+    ];
+    let tool = CompareLstsTool::new(Arc::new(MockEthereumClient::new()), lsts, venues);
+
+    let result = tool.execute(json!({ "amount": "10" })).await.unwrap();
+    let lsts = result["lsts"].as_array().unwrap();
+    assert_eq!(lsts[0]["symbol"], "rETH");
+    assert_eq!(lsts[0]["apr_percent"], "3.1");
+    assert_eq!(lsts[0]["exchange_rate"], "1.12");
+    assert_eq!(lsts[0]["market"]["venue"], "uniswap_v3");
+    assert_eq!(lsts[0]["market"]["rate"], "1.1256");
+    assert_eq!(lsts[0]["market"]["peg_deviation_bps"], "50");
+    assert_eq!(lsts[0]["market"]["amount_out"], "11.256");
+    assert_eq!(lsts[1]["symbol"], "stETH");
+    assert_eq!(lsts[1]["market"]["peg_deviation_bps"], "-10");
+    // No APR and no market, but still listed
+    assert_eq!(lsts[2]["symbol"], "cbETH");
+    assert!(lsts[2].get("market").is_none());
+    assert_eq!(lsts[2]["errors"].as_array().unwrap().len(), 2);
+
+    let result = tool.execute(json!({ "tokens": ["steth"] })).await.unwrap();
+    assert_eq!(result["lsts"].as_array().unwrap().len(), 1);
+    assert!(tool
+        .execute(json!({ "tokens": ["ankrETH"] }))
+        .await
+        .is_err());
+    assert!(tool.execute(json!({ "amount": "0" })).await.is_err());
+}
+
+fn perp_position(
+    market: &str,
+    side: crate::ethereum::PerpSide,
+    size_usd: i64,
+) -> crate::ethereum::PerpPosition {
+    crate::ethereum::PerpPosition {
+        market: market.to_string(),
+        side,
+        size: Decimal::ONE,
+        size_usd: Decimal::from(size_usd),
+        entry_price: None,
+        mark_price: Some(Decimal::from(size_usd)),
+        unrealized_pnl_usd: Some(Decimal::from(size_usd / 10)),
+        margin_usd: Some(Decimal::from(size_usd / 5)),
+        leverage: Some(Decimal::from(5)),
+        liquidation_price: None,
+    }
+}
+
+#[tokio::test]
+async fn test_get_perp_markets_sorts_by_open_interest() {
+    use crate::ethereum::{MockPerpVenue, PerpMarket};
+
+    let market = |symbol: &str, open_interest: i64, funding: Decimal| PerpMarket {
+        symbol: symbol.to_string(),
+        funding_rate_hourly: Some(funding),
+        open_interest_usd: Some(Decimal::from(open_interest)),
+        ..Default::default()
+    };
+    let venue = MockPerpVenue::new("hyperliquid")
+        .with_market(market("SOL", 500_000, Decimal::ZERO))
+        .with_market(market("BTC", 9_000_000, Decimal::new(125, 7)))
+        .with_market(market(
+            "ETH/USD [WETH-USDC]",
+            4_000_000,
+            Decimal::new(-1, 5),
+        ));
+    let tool = GetPerpMarketsTool::new(Arc::new(venue));
+
+    let result = tool.execute(json!({ "limit": 2 })).await.unwrap();
+    assert_eq!(result["venue"], "hyperliquid");
+    assert_eq!(result["total_markets"], 3);
+    let markets = result["markets"].as_array().unwrap();
+    assert_eq!(markets.len(), 2);
+    assert_eq!(markets[0]["symbol"], "BTC");
+    assert_eq!(markets[0]["funding_rate_hourly_percent"], "0.00125");
+    assert_eq!(markets[0]["funding_rate_apr_percent"], "10.95");
+
+    // GMX-style names match on their base asset
+    let result = tool.execute(json!({ "markets": ["eth"] })).await.unwrap();
+    assert_eq!(result["markets"][0]["symbol"], "ETH/USD [WETH-USDC]");
+    assert_eq!(
+        result["markets"][0]["funding_rate_hourly_percent"],
+        "-0.001"
+    );
+    assert!(tool.execute(json!({ "markets": ["DOGE"] })).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_perp_positions_sums_exposure() {
+    use crate::ethereum::{MockPerpVenue, PerpSide};
+
+    let wallet = Address::repeat_byte(0x11);
+    let venue = MockPerpVenue::new("gmx")
+        .with_position(wallet, perp_position("ETH", PerpSide::Long, 10_000))
+        .with_position(wallet, perp_position("BTC", PerpSide::Short, 4_000));
+    let client = MockEthereumClient::new().with_wallet_address(wallet);
+    let tool = GetPerpPositionsTool::new(Arc::new(client), Arc::new(venue));
+
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["venue"], "gmx");
+    assert_eq!(result["positions"].as_array().unwrap().len(), 2);
+    assert_eq!(result["positions"][1]["side"], "short");
+    assert_eq!(result["gross_exposure_usd"], "14000");
+    assert_eq!(result["net_exposure_usd"], "6000");
+    assert_eq!(result["unrealized_pnl_usd"], "1400");
+    assert_eq!(result["margin_usd"], "2800");
+    assert_eq!(result["equity_usd"], "4200");
+
+    let result = tool
+        .execute(json!({ "address": format!("{:?}", Address::repeat_byte(0x22)) }))
+        .await
+        .unwrap();
+    assert!(result["positions"].as_array().unwrap().is_empty());
+    assert_eq!(result["equity_usd"], "0");
+}
+
+#[tokio::test]
+async fn test_get_portfolio_counts_perp_equity() {
+    use crate::ethereum::{ChainClients, ChainPricing, MockPerpVenue, PerpSide};
+
+    let wallet = Address::repeat_byte(0x11);
+    let (weth, usdc) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+    let chains = Arc::new(
+        ChainClients::new()
+            .with_client(Arc::new(
+                MockEthereumClient::new().with_eth_balance(wallet, Decimal::ONE),
+            ))
+            .with_pricing(
+                1,
+                ChainPricing {
+                    venue: "uniswap_v2".to_string(),
+                    router: Arc::new(MockUniswapRouter::new().with_price(
+                        weth,
+                        usdc,
+                        Decimal::new(3, 9),
+                    )),
+                    wrapped_native: weth,
+                    usd_token: usdc,
+                    usd_decimals: 6,
+                },
+            ),
+    );
+    let venue = MockPerpVenue::new("hyperliquid")
+        .with_position(wallet, perp_position("ETH", PerpSide::Long, 10_000));
+    let tool = GetPortfolioTool::new(chains.clone()).with_perps(Arc::new(venue));
+    let params = json!({ "address": format!("{:?}", wallet) });
+
+    let result = tool.execute(params.clone()).await.unwrap();
+    assert_eq!(result["derivatives"]["venue"], "hyperliquid");
+    assert_eq!(result["derivatives"]["equity_usd"], "3000");
+    assert_eq!(result["total_usd"], "6000");
+
+    // An unreachable exchange is reported and left out of the total
+    let tool = GetPortfolioTool::new(chains).with_perps(Arc::new(
+        MockPerpVenue::new("hyperliquid").with_unavailable(),
+    ));
+    let result = tool.execute(params).await.unwrap();
+    assert!(result["derivatives"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("unavailable"));
+    assert_eq!(result["total_usd"], "3000");
+}
+
+#[tokio::test]
+async fn test_get_option_chains_groups_strikes_and_reads_skew() {
+    use crate::ethereum::{MockOptionsVenue, OptionKind, OptionQuote};
+    use crate::tools::trade_ledger::date;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let near = now + 7 * 86_400;
+    let far = now + 30 * 86_400;
+    let quote = |expiry: u64, kind: OptionKind, strike: i64, iv: i64, delta: i64| OptionQuote {
+        instrument: format!("ETH-{}-{}-{:?}", expiry, strike, kind),
+        kind,
+        strike: Decimal::from(strike),
+        expiry,
+        mark_price: Some(Decimal::from(100)),
+        bid: None,
+        ask: None,
+        mark_iv: Some(Decimal::new(iv, 2)),
+        bid_iv: None,
+        ask_iv: None,
+        delta: Some(Decimal::new(delta, 2)),
+        index_price: Some(Decimal::from(3_050)),
+    };
+    let venue = MockOptionsVenue::new("aevo")
+        .with_quote("ETH", quote(near, OptionKind::Call, 3_000, 60, 55))
+        .with_quote("ETH", quote(near, OptionKind::Put, 3_000, 64, -45))
+        .with_quote("ETH", quote(near, OptionKind::Call, 3_400, 58, 24))
+        .with_quote("ETH", quote(near, OptionKind::Put, 2_700, 70, -26))
+        .with_quote("ETH", quote(near, OptionKind::Put, 1_500, 95, -2))
+        .with_quote("ETH", quote(far, OptionKind::Call, 3_000, 55, 52));
+    let tool = GetOptionChainsTool::new(Arc::new(venue));
+
+    let result = tool.execute(json!({})).await.unwrap();
+    assert_eq!(result["venue"], "aevo");
+    assert_eq!(result["underlying"], "ETH");
+    assert_eq!(result["index_price"], "3050");
+    assert_eq!(result["available_expiries"].as_array().unwrap().len(), 2);
+    let chain = &result["chains"][0];
+    assert_eq!(chain["expiry"], date(near));
+    assert_eq!(chain["atm_strike"], "3000");
+    assert_eq!(chain["atm_iv_percent"], "62");
+    assert_eq!(chain["skew_25d_percent"], "12");
+    // 1500 is outside the default 20% strike range
+    let strikes = chain["strikes"].as_array().unwrap();
+    assert_eq!(strikes.len(), 3);
+    assert_eq!(strikes[0]["strike"], "2700");
+    assert!(strikes[0].get("call").is_none());
+    assert_eq!(strikes[1]["call"]["iv_percent"], "60");
+    assert_eq!(strikes[1]["put"]["delta"], "-0.45");
+
+    let result = tool
+        .execute(json!({ "expiry": date(far), "strike_range_percent": 100 }))
+        .await
+        .unwrap();
+    assert_eq!(result["chains"].as_array().unwrap().len(), 1);
+    assert_eq!(result["chains"][0]["atm_iv_percent"], "55");
+    assert!(tool
+        .execute(json!({ "expiry": "2001-01-01" }))
+        .await
+        .is_err());
+    assert!(tool.execute(json!({ "underlying": "SOL" })).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_funding_rates_compares_venues_and_alerts_on_crossings() {
+    use crate::ethereum::{MockPerpVenue, MockReferencePrices, PerpMarket};
+    use crate::tools::get_funding_rates::FUNDING_TOPIC;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(String, AlertSeverity, Value)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send(&self, topic: &str, severity: AlertSeverity, alert: &Value) -> usize {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), severity, alert.clone()));
+            1
+        }
+    }
+
+    let market =
+        |symbol: &str, funding: Decimal, mark: i64, index: i64, open_interest: i64| PerpMarket {
+            symbol: symbol.to_string(),
+            funding_rate_hourly: Some(funding),
+            mark_price: Some(Decimal::from(mark)),
+            index_price: Some(Decimal::from(index)),
+            open_interest_usd: Some(Decimal::from(open_interest)),
+            ..Default::default()
+        };
+    let hyperliquid = MockPerpVenue::new("hyperliquid")
+        .with_market(market("ETH", Decimal::new(125, 7), 2_010, 2_005, 5_000_000))
+        .with_market(market("BTC", Decimal::new(1, 5), 60_030, 60_000, 9_000_000));
+    let gmx = MockPerpVenue::new("gmx")
+        .with_market(market(
+            "ETH/USD [WETH-USDC]",
+            Decimal::new(5, 6),
+            1_999,
+            2_000,
+            3_000_000,
+        ))
+        // A thinner pool for the same asset is passed over
+        .with_market(market(
+            "ETH/USD [WETH-WETH]",
+            Decimal::new(1, 3),
+            2_100,
+            2_000,
+            100_000,
+        ));
+    let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        .parse()
+        .unwrap();
+    let oracle = MockReferencePrices::new().with_price(weth, Decimal::from(2_000));
+    let sink = Arc::new(RecordingSink::default());
+    let tool = GetFundingRatesTool::new(vec![
+        Arc::new(hyperliquid),
+        Arc::new(gmx),
+        Arc::new(MockPerpVenue::new("down").with_unavailable()),
+    ])
+    .with_reference_prices(Arc::new(oracle))
+    .with_alert_sink(sink.clone());
+
+    let params = json!({
+        "assets": ["eth", "BTC"],
+        "alert_funding_apr_percent": "10",
+        "alert_basis_bps": "20",
+    });
+    let result = tool.execute(params.clone()).await.unwrap();
+    let eth = &result["assets"][0];
+    assert_eq!(eth["asset"], "ETH");
+    assert_eq!(eth["spot_price"], "2000");
+    let venues = eth["venues"].as_array().unwrap();
+    assert_eq!(venues.len(), 2);
+    assert_eq!(venues[0]["venue"], "hyperliquid");
+    assert_eq!(venues[0]["funding_apr_percent"], "10.95");
+    assert_eq!(venues[0]["basis_bps"], "50");
+    assert_eq!(venues[1]["market"], "ETH/USD [WETH-USDC]");
+    assert_eq!(venues[1]["funding_apr_percent"], "4.38");
+    assert_eq!(venues[1]["basis_bps"], "-5");
+    assert_eq!(eth["funding_spread_apr_percent"], "6.57");
+
+    // No oracle for BTC here, so the venue's index stands in for spot
+    let btc = &result["assets"][1];
+    assert_eq!(btc["spot_source"], "hyperliquid index");
+    assert_eq!(btc["venues"][0]["basis_bps"], "5");
+    assert!(btc.get("funding_spread_apr_percent").is_none());
+    assert_eq!(result["failed_venues"][0]["venue"], "down");
+
+    let alerts = result["alerts"].as_array().unwrap();
+    assert_eq!(alerts.len(), 2);
+    assert!(alerts
+        .iter()
+        .all(|alert| alert["venue"] == "hyperliquid" && alert["severity"] == "warning"));
+    assert_eq!(result["notifications_sent"], 2);
+    assert_eq!(sink.sent.lock().unwrap()[0].0, FUNDING_TOPIC);
+
+    // Nothing has crossed since the last call
+    let result = tool.execute(params).await.unwrap();
+    assert!(result["alerts"].as_array().unwrap().is_empty());
+
+    // A higher threshold puts hyperliquid's funding back within it
+    let result = tool
+        .execute(json!({
+            "assets": ["ETH"],
+            "alert_funding_apr_percent": "20",
+            "alert_basis_bps": "20",
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["alerts"].as_array().unwrap().len(), 1);
+    assert_eq!(result["alerts"][0]["metric"], "funding_apr_percent");
+    assert_eq!(result["alerts"][0]["severity"], "info");
+    assert_eq!(sink.sent.lock().unwrap().len(), 3);
+    assert_eq!(sink.sent.lock().unwrap()[2].1, AlertSeverity::Info);
+
+    assert!(tool.execute(json!({ "assets": [] })).await.is_err());
+    assert!(tool
+        .execute(json!({ "alert_basis_bps": "-1" }))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_get_dex_stats_serves_background_refreshed_pool_figures() {
+    use crate::ethereum::{
+        FactoryKind, MockPoolActivity, MockReferencePrices, PoolState, SwapAmounts,
+    };
+
+    let usdc = Address::repeat_byte(0xaa);
+    let weth = Address::repeat_byte(0xee);
+    let pair = Address::repeat_byte(0x01);
+    let missing = Address::repeat_byte(0x02);
+    let swap = |block_number, usdc_amount: u64, weth_milli: u64| SwapAmounts {
+        block_number,
+        amount0: U256::from(usdc_amount) * U256::exp10(6),
+        amount1: U256::from(weth_milli) * U256::exp10(15),
+    };
+    let source = Arc::new(
+        MockPoolActivity::new()
+            .with_pool(
+                pair,
+                PoolState {
+                    kind: FactoryKind::V2,
+                    token0: usdc,
+                    token1: weth,
+                    fee: 3000,
+                    balance0: U256::from(2_000_000u64) * U256::exp10(6),
+                    balance1: U256::from(1_000u64) * U256::exp10(18),
+                    sqrt_price_x96: None,
+                },
+            )
+            // Older than the 24h window
+            .with_swap(pair, swap(12_000, 50_000, 25_000))
+            .with_swap(pair, swap(15_000, 1_000, 500))
+            .with_swap(pair, swap(19_000, 3_000, 1_500)),
+    );
+    let client = MockEthereumClient::new()
+        .with_block_number(20_000)
+        .with_token_symbol(usdc, "USDC".to_string())
+        .with_token_decimals(usdc, 6)
+        .with_token_symbol(weth, "WETH".to_string());
+    // Only WETH has a feed; USDC is priced through the pair
+    let oracle = MockReferencePrices::new().with_price(weth, Decimal::from(2_000));
+    let stats = Arc::new(
+        DexStats::new(
+            Arc::new(client),
+            source.clone(),
+            vec![
+                ("usdc-weth".to_string(), pair),
+                ("gone".to_string(), missing),
+            ],
+        )
+        .with_reference_prices(Arc::new(oracle)),
+    );
+    let tool = GetDexStatsTool::new(stats.clone());
+
+    // Nothing is read on the call itself
+    let result = tool.execute(json!({})).await.unwrap();
+    assert!(result["pools"].as_array().unwrap().is_empty());
+    assert_eq!(result["pending"].as_array().unwrap().len(), 2);
+    assert!(source.scanned().is_empty());
+
+    stats.refresh().await;
+    let result = tool.execute(json!({})).await.unwrap();
+    let pool = &result["pools"][0];
+    assert_eq!(pool["name"], "usdc-weth");
+    assert_eq!(pool["pair"], "USDC/WETH");
+    assert_eq!(pool["version"], "v2");
+    assert_eq!(pool["fee_percent"], "0.3");
+    assert_eq!(pool["price"], "0.0005");
+    assert_eq!(pool["tvl_usd"], "4000000");
+    assert_eq!(pool["swaps_24h"], 2);
+    assert_eq!(pool["volume0_24h"], "4000");
+    assert_eq!(pool["volume1_24h"], "2");
+    assert_eq!(pool["volume_24h_usd"], "4000");
+    assert_eq!(pool["fees_24h_usd"], "12");
+    assert_eq!(result["total_tvl_usd"], "4000000");
+    assert_eq!(result["pending"][0]["name"], "gone");
+    assert!(result["pending"][0]["error"].is_string());
+
+    // A refresh at the same block has no new blocks to scan
+    stats.refresh().await;
+    assert_eq!(source.scanned(), vec![(pair, 12_801, 20_000)]);
+
+    let result = tool
+        .execute(json!({ "pools": [format!("{:?}", pair)], "sort_by": "tvl" }))
+        .await
+        .unwrap();
+    assert_eq!(result["pools"].as_array().unwrap().len(), 1);
+    assert!(result.get("pending").is_none());
+    assert!(tool.execute(json!({ "pools": ["unknown"] })).await.is_err());
+}
@@ -0,0 +1,365 @@
+use super::units::from_base_units;
+use crate::ethereum::tokens::asset_of;
+use crate::ethereum::{EthereumClientTrait, ReferencePriceSource};
+use crate::time::{civil_from_days, days_from_civil};
+use anyhow::{Context, Result};
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Assets counted as dollars: trading them is neither an acquisition nor a disposal
+const USD_ASSETS: [&str; 3] = ["USDC", "USDT", "DAI"];
+
+/// Which lots a disposal is matched against first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingMethod {
+    /// Oldest lots first
+    #[default]
+    Fifo,
+    /// Newest lots first
+    Lifo,
+    /// Lots with the highest cost per unit first, realizing the smallest gains
+    Hifo,
+}
+
+impl std::str::FromStr for AccountingMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fifo" => Ok(AccountingMethod::Fifo),
+            "lifo" => Ok(AccountingMethod::Lifo),
+            "hifo" => Ok(AccountingMethod::Hifo),
+            other => anyhow::bail!(
+                "Unknown accounting method: {} (expected fifo, lifo or hifo)",
+                other
+            ),
+        }
+    }
+}
+
+/// One side of a trade, in whole tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeLeg {
+    pub token: Address,
+    pub symbol: String,
+    pub amount: Decimal,
+}
+
+/// A swap the wallet made, valued in dollars when it was recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    /// Unix seconds
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    pub sold: TradeLeg,
+    pub bought: TradeLeg,
+    /// Dollar value of the trade; absent when neither side could be priced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<Decimal>,
+    /// Where the dollar value came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_source: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingTerm {
+    /// Held a year or less
+    Short,
+    /// Held more than a year
+    Long,
+    /// Acquired before the ledger started
+    Unknown,
+}
+
+/// Part of a disposal matched against a single lot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Disposal {
+    pub token: Address,
+    pub symbol: String,
+    pub amount: Decimal,
+    /// Unix seconds the lot was acquired; absent for amounts the ledger never saw bought
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquired_at: Option<u64>,
+    pub disposed_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proceeds: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_basis: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<Decimal>,
+    pub term: HoldingTerm,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+}
+
+struct Lot {
+    acquired_at: u64,
+    amount: Decimal,
+    unit_cost: Option<Decimal>,
+}
+
+/// Swaps the wallet executed, the basis of tax lot reports.
+///
+/// With a backing file, trades survive restarts.
+#[derive(Default)]
+pub struct TradeLedger {
+    chain_id: u64,
+    path: Option<PathBuf>,
+    trades: RwLock<Vec<Trade>>,
+    prices: Option<Arc<dyn ReferencePriceSource>>,
+}
+
+impl TradeLedger {
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            ..Self::default()
+        }
+    }
+
+    /// Load the trades saved at `path`, if it exists
+    pub fn open(path: PathBuf, chain_id: u64) -> Result<Self> {
+        let mut ledger = Self::new(chain_id);
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read trade ledger {}", path.display()))?;
+            ledger.trades = RwLock::new(
+                serde_json::from_str(&raw)
+                    .with_context(|| format!("Invalid trade ledger {}", path.display()))?,
+            );
+        }
+        ledger.path = Some(path);
+        Ok(ledger)
+    }
+
+    /// Value trades without a dollar side at the oracle's price
+    pub fn with_reference_prices(mut self, prices: Arc<dyn ReferencePriceSource>) -> Self {
+        self.prices = Some(prices);
+        self
+    }
+
+    /// Record a swap of `sold` for `bought`, as (token, base units), made at
+    /// `timestamp`, valuing it in dollars now
+    pub async fn record<C: EthereumClientTrait>(
+        &self,
+        client: &C,
+        sold: (Address, U256),
+        bought: (Address, U256),
+        tx_hash: Option<H256>,
+        timestamp: u64,
+    ) -> Result<Trade> {
+        let sold = self.leg(client, sold).await?;
+        let bought = self.leg(client, bought).await?;
+        let (usd_value, usd_source) = match self.usd_value(&sold, &bought).await {
+            Some((value, source)) => (Some(cents(value)), Some(source)),
+            None => (None, None),
+        };
+        let trade = Trade {
+            timestamp,
+            tx_hash,
+            sold,
+            bought,
+            usd_value,
+            usd_source,
+        };
+        self.trades
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(trade.clone());
+        self.save()?;
+        Ok(trade)
+    }
+
+    async fn leg<C: EthereumClientTrait>(
+        &self,
+        client: &C,
+        (token, amount): (Address, U256),
+    ) -> Result<TradeLeg> {
+        let decimals = client.get_token_decimals(token).await?;
+        Ok(TradeLeg {
+            token,
+            symbol: client
+                .get_token_symbol(token)
+                .await
+                .unwrap_or_else(|_| format!("{:?}", token)),
+            amount: from_base_units(amount, decimals as u32)?,
+        })
+    }
+
+    fn is_usd(&self, token: Address) -> bool {
+        asset_of(self.chain_id, Some(token))
+            .is_some_and(|identity| USD_ASSETS.contains(&identity.asset.as_str()))
+    }
+
+    /// A dollar side's amount, or else the oracle value of what was bought, or sold
+    async fn usd_value(&self, sold: &TradeLeg, bought: &TradeLeg) -> Option<(Decimal, String)> {
+        for leg in [sold, bought] {
+            if self.is_usd(leg.token) {
+                return Some((leg.amount, leg.symbol.clone()));
+            }
+        }
+        let prices = self.prices.as_ref()?;
+        for leg in [bought, sold] {
+            match prices.usd_price(leg.token).await {
+                Ok(Some(price)) => return Some((leg.amount * price.usd_price, price.source)),
+                Ok(None) => {}
+                Err(e) => warn!(token = ?leg.token, "Failed to price trade: {:#}", e),
+            }
+        }
+        None
+    }
+
+    /// Every trade, oldest first
+    pub fn trades(&self) -> Vec<Trade> {
+        let mut trades = self
+            .trades
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        trades.sort_by_key(|trade| trade.timestamp);
+        trades
+    }
+
+    /// Disposals made in calendar `year` (UTC), each matched against the lots bought
+    /// before it by `method`. Dollar stablecoins are cash and never form lots.
+    pub fn disposals(&self, year: i64, method: AccountingMethod) -> Vec<Disposal> {
+        let start = year_start(year);
+        let end = year_start(year + 1);
+        let mut lots: HashMap<Address, Vec<Lot>> = HashMap::new();
+        let mut disposals = Vec::new();
+
+        for trade in self.trades() {
+            if trade.timestamp >= end {
+                break;
+            }
+            if !self.is_usd(trade.sold.token) && !trade.sold.amount.is_zero() {
+                let unit_proceeds = trade.usd_value.map(|value| value / trade.sold.amount);
+                let held = lots.entry(trade.sold.token).or_default();
+                let matched = take_lots(held, trade.sold.amount, method);
+                if trade.timestamp >= start {
+                    for (amount, lot) in matched {
+                        let proceeds = unit_proceeds.map(|unit| cents(unit * amount));
+                        let cost_basis = lot
+                            .as_ref()
+                            .and_then(|lot| lot.unit_cost)
+                            .map(|unit| cents(unit * amount));
+                        disposals.push(Disposal {
+                            token: trade.sold.token,
+                            symbol: trade.sold.symbol.clone(),
+                            amount: amount.normalize(),
+                            acquired_at: lot.as_ref().map(|lot| lot.acquired_at),
+                            disposed_at: trade.timestamp,
+                            proceeds,
+                            cost_basis,
+                            gain: proceeds.zip(cost_basis).map(|(p, c)| p - c),
+                            term: lot.as_ref().map_or(HoldingTerm::Unknown, |lot| {
+                                holding_term(lot.acquired_at, trade.timestamp)
+                            }),
+                            tx_hash: trade.tx_hash,
+                        });
+                    }
+                }
+            }
+            if !self.is_usd(trade.bought.token) && !trade.bought.amount.is_zero() {
+                lots.entry(trade.bought.token).or_default().push(Lot {
+                    acquired_at: trade.timestamp,
+                    amount: trade.bought.amount,
+                    unit_cost: trade.usd_value.map(|value| value / trade.bought.amount),
+                });
+            }
+        }
+        disposals
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.trades())?;
+        // Write beside the file and rename so a crash never leaves it truncated
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, json)
+            .with_context(|| format!("Failed to write trade ledger {}", temp.display()))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("Failed to save trade ledger {}", path.display()))
+    }
+}
+
+/// Remove `amount` from `lots` in `method` order, returning each piece with the lot it
+/// came from; an amount beyond the lots comes back without one
+fn take_lots(
+    lots: &mut Vec<Lot>,
+    mut amount: Decimal,
+    method: AccountingMethod,
+) -> Vec<(Decimal, Option<Lot>)> {
+    // Lots are kept in acquisition order
+    let mut order: Vec<usize> = (0..lots.len()).collect();
+    match method {
+        AccountingMethod::Fifo => {}
+        AccountingMethod::Lifo => order.reverse(),
+        AccountingMethod::Hifo => order.sort_by(|a, b| {
+            let cost = |index: usize| lots[index].unit_cost.unwrap_or(Decimal::MIN);
+            cost(*b).cmp(&cost(*a))
+        }),
+    }
+
+    let mut taken = Vec::new();
+    for index in order {
+        if amount.is_zero() {
+            break;
+        }
+        let lot = &mut lots[index];
+        let piece = amount.min(lot.amount);
+        lot.amount -= piece;
+        amount -= piece;
+        taken.push((
+            piece,
+            Some(Lot {
+                acquired_at: lot.acquired_at,
+                amount: piece,
+                unit_cost: lot.unit_cost,
+            }),
+        ));
+    }
+    lots.retain(|lot| !lot.amount.is_zero());
+    if !amount.is_zero() {
+        taken.push((amount, None));
+    }
+    taken
+}
+
+/// Dollars to the cent, always with two decimals
+pub fn cents(mut value: Decimal) -> Decimal {
+    value.rescale(2);
+    value
+}
+
+fn year_start(year: i64) -> u64 {
+    (days_from_civil(year, 1, 1).max(0) as u64) * SECS_PER_DAY
+}
+
+/// Long term when sold after the anniversary of the acquisition date
+fn holding_term(acquired_at: u64, disposed_at: u64) -> HoldingTerm {
+    let (year, month, day) = civil_from_days((acquired_at / SECS_PER_DAY) as i64);
+    if civil_from_days((disposed_at / SECS_PER_DAY) as i64) > (year + 1, month, day) {
+        HoldingTerm::Long
+    } else {
+        HoldingTerm::Short
+    }
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC
+pub fn date(at: u64) -> String {
+    let (year, month, day) = civil_from_days((at / SECS_PER_DAY) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
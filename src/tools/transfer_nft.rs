@@ -0,0 +1,278 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::alerts::AlertSink;
+use super::gas_reserve::GasReserve;
+use super::interfaces::{detect_token_standard, erc1155_balance, erc721_owner, TokenStandard};
+use super::recipients::RecipientHistory;
+use super::scam_list::ScamList;
+use super::{ExecutePlanTool, PermissionTier, Tool, ToolCategory};
+use crate::ethereum::{EthereumClientTrait, GasEscalation, NftStandard, Plan, PlanStep};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct TransferNftTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    executor: ExecutePlanTool<C>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> TransferNftTool<C> {
+    /// Without `execution_enabled` only dry runs are allowed
+    pub fn new(client: Arc<C>, execution_enabled: bool) -> Self {
+        Self {
+            executor: ExecutePlanTool::new(Arc::clone(&client), execution_enabled),
+            client,
+            book: None,
+        }
+    }
+
+    /// Accept a contact name as the recipient
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.executor = self.executor.with_address_book(Arc::clone(&book));
+        self.book = Some(book);
+        self
+    }
+
+    /// Flag recipients the wallet has never paid, and record new ones
+    pub fn with_recipient_history(mut self, recipients: Arc<RecipientHistory>) -> Self {
+        self.executor = self.executor.with_recipient_history(recipients);
+        self
+    }
+
+    /// Refuse recipients on `scams`
+    pub fn with_scam_list(mut self, scams: Arc<ScamList>) -> Self {
+        self.executor = self.executor.with_scam_list(scams);
+        self
+    }
+
+    pub fn with_gas_escalation(mut self, gas_escalation: Option<GasEscalation>) -> Self {
+        self.executor = self.executor.with_gas_escalation(gas_escalation);
+        self
+    }
+
+    /// ETH that live runs must leave in the wallet for future gas
+    pub fn with_gas_reserve(mut self, gas_reserve: GasReserve) -> Self {
+        self.executor = self.executor.with_gas_reserve(gas_reserve);
+        self
+    }
+
+    /// Push the transactions it sends, once mined or failed, to `alerts`
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.executor = self.executor.with_alert_sink(alerts);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferNftParams {
+    collection: String,
+    token_id: String,
+    to: String,
+    /// ERC-1155 units; ERC-721 tokens are always moved whole
+    #[serde(default)]
+    amount: Option<String>,
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+    /// Passed through to execute_plan
+    #[serde(default)]
+    gas_escalation: Option<Value>,
+    #[serde(default)]
+    confirm_new_recipients: bool,
+    #[serde(default)]
+    override_gas_reserve: bool,
+    #[serde(default)]
+    override_scam_list: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct TransferNftResult {
+    collection: Address,
+    standard: NftStandard,
+    token_id: String,
+    to: Address,
+    amount: String,
+    /// Units the wallet holds before the transfer; 1 for an ERC-721 it owns
+    held: String,
+    /// The transfer step, as input for execute_plan
+    plan: Plan,
+    execution: Value,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for TransferNftTool<C> {
+    fn name(&self) -> &str {
+        "transfer_nft"
+    }
+
+    fn description(&self) -> &str {
+        "Send an ERC721 or ERC1155 NFT with safeTransferFrom. The standard is detected through ERC-165, the wallet's ownership (or ERC1155 balance) is checked first, and the transfer is simulated before anything is built, so recipients that cannot receive NFTs are caught. Dry run by default; live runs follow execute_plan's gas reserve, recipient and scam list checks."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Simulation
+    }
+
+    fn required_tier(&self) -> PermissionTier {
+        PermissionTier::Trade
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "collection": {
+                    "type": "string",
+                    "description": "NFT contract address"
+                },
+                "token_id": {
+                    "type": "string",
+                    "description": "Token id as a decimal string"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Recipient address or contact name"
+                },
+                "amount": {
+                    "type": "string",
+                    "description": "Units of an ERC1155 token to send (default: 1); must be 1 for ERC721"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Estimate the transaction without sending (default: true). Live execution must be enabled on the server"
+                },
+                "confirm_new_recipients": {
+                    "type": "boolean",
+                    "description": "Send to a recipient the wallet has never paid, as for execute_plan (default: false)"
+                },
+                "gas_escalation": {
+                    "type": "object",
+                    "description": "Fee escalation override, as for execute_plan"
+                },
+                "override_gas_reserve": {
+                    "type": "boolean",
+                    "description": "Spend into the ETH reserve kept for gas, as for execute_plan (default: false)"
+                },
+                "override_scam_list": {
+                    "type": "boolean",
+                    "description": "Send to an address on the scam list anyway, as for execute_plan (default: false)"
+                }
+            },
+            "required": ["collection", "token_id", "to"]
+        })
+    }
+
+    // The same go-ahead execute_plan asks for before paying addresses outside the book
+    fn confirmation_prompt(&self, params: &Value) -> Option<String> {
+        self.executor.confirmation_prompt(&json!({
+            "dry_run": params.get("dry_run").cloned().unwrap_or(Value::Bool(true)),
+            "plan": {
+                "steps": [{ "action": "transfer_nft", "to": params.get("to")? }]
+            }
+        }))
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: TransferNftParams =
+            serde_json::from_value(params).context("Invalid parameters for transfer_nft")?;
+
+        let wallet = self.client.get_wallet_address();
+        let collection: Address = params
+            .collection
+            .trim()
+            .parse()
+            .context("Invalid collection address")?;
+        let to = resolve_address(self.book.as_deref(), &params.to).context("Invalid recipient")?;
+        if to.is_zero() || to == collection || to == wallet {
+            anyhow::bail!("{:?} cannot receive this NFT", to);
+        }
+        let token_id = U256::from_dec_str(params.token_id.trim()).context("Invalid token_id")?;
+        let amount = match params.amount.as_deref() {
+            Some(amount) => U256::from_dec_str(amount.trim()).context("Invalid amount")?,
+            None => U256::one(),
+        };
+        if amount.is_zero() {
+            anyhow::bail!("amount must be positive");
+        }
+
+        let client = self.client.as_ref();
+        let (standard, held) = match detect_token_standard(client, collection).await {
+            TokenStandard::Erc721 => {
+                if amount != U256::one() {
+                    anyhow::bail!("ERC721 tokens are unique; amount must be 1");
+                }
+                let owner = erc721_owner(client, collection, token_id).await?;
+                if owner != wallet {
+                    anyhow::bail!(
+                        "Token {} of {:?} is owned by {:?}, not the wallet",
+                        token_id,
+                        collection,
+                        owner
+                    );
+                }
+                (NftStandard::Erc721, U256::one())
+            }
+            TokenStandard::Erc1155 => {
+                let held = erc1155_balance(client, collection, wallet, token_id, None).await?;
+                if held < amount {
+                    anyhow::bail!(
+                        "The wallet holds {} of token {} but the transfer sends {}",
+                        held,
+                        token_id,
+                        amount
+                    );
+                }
+                (NftStandard::Erc1155, held)
+            }
+            _ => anyhow::bail!(
+                "{:?} does not report ERC721 or ERC1155 through ERC-165; use a token transfer instead",
+                collection
+            ),
+        };
+
+        let step = PlanStep::TransferNft {
+            contract: collection,
+            standard,
+            to,
+            token_id,
+            amount,
+        };
+        // Reverts such as a recipient contract without a receiver hook surface here,
+        // before a plan is handed out
+        client
+            .call_contract(&step.to_transaction(wallet, U256::zero())?, None, None)
+            .await
+            .context("The transfer would revert")?;
+
+        let plan = Plan { steps: vec![step] };
+        let execution = self
+            .executor
+            .execute(json!({
+                "plan": plan,
+                "dry_run": params.dry_run,
+                "gas_escalation": params.gas_escalation,
+                "confirm_new_recipients": params.confirm_new_recipients,
+                "override_gas_reserve": params.override_gas_reserve,
+                "override_scam_list": params.override_scam_list,
+            }))
+            .await?;
+
+        let result = TransferNftResult {
+            collection,
+            standard,
+            token_id: token_id.to_string(),
+            to,
+            amount: amount.to_string(),
+            held: held.to_string(),
+            plan,
+            execution,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use ethers::types::{U256, U512};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+/// Most decimal places a `Decimal` keeps; finer digits of an amount are rounded off
+const MAX_SCALE: u32 = 28;
+
+/// Largest mantissa a `Decimal` holds, in bits
+const MAX_MANTISSA_BITS: usize = 96;
+
+fn pow10(exponent: u32) -> Result<U256> {
+    U256::from(10)
+        .checked_pow(U256::from(exponent))
+        .context("Token decimals are too large")
+}
+
+/// Convert a human-readable amount into integer base units
+pub fn to_base_units(amount: Decimal, decimals: u32) -> Result<U256> {
+    if amount.is_sign_negative() {
+        anyhow::bail!("Amount must not be negative");
+    }
+    // Round to whole base units first, then scale up in U256 so tokens with more
+    // decimals than Decimal can hold still convert exactly
+    let rounded = amount.round_dp(decimals);
+    U256::from(rounded.mantissa().unsigned_abs())
+        .checked_mul(pow10(decimals - rounded.scale())?)
+        .context("Amount is too large")
+}
+
+/// Convert integer base units into a human-readable amount.
+///
+/// Digits beyond what a `Decimal` holds (28 decimal places, 96 bits) are rounded
+/// half up, so dust below 1e-28 of a token reads as zero.
+pub fn from_base_units(amount: U256, decimals: u32) -> Result<Decimal> {
+    let mut dropped = decimals.saturating_sub(MAX_SCALE);
+    loop {
+        let divisor = pow10(dropped)?;
+        let (mut mantissa, remainder) = amount.div_mod(divisor);
+        if remainder >= divisor - remainder {
+            mantissa += U256::one();
+        }
+        if mantissa.bits() <= MAX_MANTISSA_BITS {
+            let value =
+                Decimal::from_i128_with_scale(mantissa.as_u128() as i128, decimals - dropped);
+            return Ok(value.normalize());
+        }
+        if dropped >= decimals {
+            anyhow::bail!("Amount is too large");
+        }
+        dropped += 1;
+    }
+}
+
+/// Least acceptable output, in base units, for `amount_out` at `slippage_percent`
+/// tolerance (e.g. 0.5 for 0.5%), rounded down
+pub fn minimum_amount_out(amount_out: U256, slippage_percent: f64) -> Result<U256> {
+    let slippage = Decimal::from_f64(slippage_percent)
+        .filter(|slippage| (Decimal::ZERO..=Decimal::ONE_HUNDRED).contains(slippage))
+        .with_context(|| {
+            format!(
+                "Slippage tolerance must be between 0 and 100 percent, got {}",
+                slippage_percent
+            )
+        })?;
+    let kept = Decimal::ONE - slippage / Decimal::ONE_HUNDRED;
+    let scaled = amount_out.full_mul(U256::from(kept.mantissa().unsigned_abs()));
+    let minimum = scaled / U512::from(pow10(kept.scale())?);
+    U256::try_from(minimum).ok().context("Amount is too large")
+}
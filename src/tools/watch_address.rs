@@ -0,0 +1,142 @@
+use super::address_book::{resolve_address, AddressBook};
+use super::pagination::{page_schema_properties, Page, PageParams};
+use super::watch_list::{WatchEvent, WatchList, WatchedAddress, WATCH_EVENTS_RESOURCE_URI};
+use super::{merge_schema_properties, Tool};
+use crate::ethereum::EthereumClientTrait;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub struct WatchAddressTool<C: EthereumClientTrait> {
+    client: Arc<C>,
+    watches: Arc<WatchList>,
+    book: Option<Arc<AddressBook>>,
+}
+
+impl<C: EthereumClientTrait> WatchAddressTool<C> {
+    pub fn new(client: Arc<C>, watches: Arc<WatchList>) -> Self {
+        Self {
+            client,
+            watches,
+            book: None,
+        }
+    }
+
+    /// Accept contact names in place of addresses, and label them by name
+    pub fn with_address_book(mut self, book: Arc<AddressBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WatchAction {
+    Add,
+    Remove,
+    List,
+    Events,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchAddressParams {
+    action: WatchAction,
+    #[serde(default)]
+    address: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Debug, Serialize)]
+struct WatchListResult {
+    #[serde(flatten)]
+    watched: Page<WatchedAddress>,
+    /// Resource holding every address's recent events
+    resource_uri: &'static str,
+}
+
+#[async_trait]
+impl<C: EthereumClientTrait + 'static> Tool for WatchAddressTool<C> {
+    fn name(&self) -> &str {
+        "watch_address"
+    }
+
+    fn description(&self) -> &str {
+        "Watch any address, not just the server's wallet, for incoming and outgoing ERC20 transfers and ETH balance changes. The server polls watched addresses in the background and pushes each event to connected clients as an MCP logging notification, and keeps a feed of recent events in the watch://events resource. Actions: add (optionally with a label), remove, list, and events to page through the feed oldest first, continuing from a previous call's next_cursor."
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut properties = json!({
+            "action": {
+                "type": "string",
+                "enum": ["add", "remove", "list", "events"],
+                "description": "add or remove a watched address, list them, or read recent events"
+            },
+            "address": {
+                "type": "string",
+                "description": "Address or contact name; required for add and remove, filters events"
+            },
+            "label": {
+                "type": "string",
+                "description": "Name shown with the address's events (default: its contact name)"
+            }
+        });
+        merge_schema_properties(&mut properties, page_schema_properties());
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let params: WatchAddressParams =
+            serde_json::from_value(params).context("Invalid parameters for watch_address")?;
+        let book = self.book.as_deref();
+        let address = params
+            .address
+            .as_deref()
+            .map(|address| resolve_address(book, address))
+            .transpose()?;
+
+        match params.action {
+            WatchAction::Add => {
+                let address = address.context("address is required to add a watch")?;
+                let label = params
+                    .label
+                    .filter(|label| !label.trim().is_empty())
+                    .or_else(|| book.and_then(|book| book.name_of(address)));
+                // Start from now, so only activity after this call is reported
+                let head = self.client.get_block_number().await?;
+                let balance = self.client.get_eth_balance(address).await?;
+                self.watches
+                    .add(address, label, Some((balance, head + 1)))?;
+            }
+            WatchAction::Remove => {
+                let address = address.context("address is required to remove a watch")?;
+                if self.watches.remove(address).is_none() {
+                    anyhow::bail!("{:?} is not being watched", address);
+                }
+            }
+            WatchAction::List => {}
+            WatchAction::Events => {
+                // Keyed by event id, so the cursor survives old events leaving the feed
+                let events: Page<WatchEvent> = params
+                    .page
+                    .paginate_by_key(self.watches.events_since(0, address), |event| event.id)?;
+                return Ok(serde_json::to_value(events)?);
+            }
+        }
+
+        let result = WatchListResult {
+            watched: params.page.paginate(self.watches.watched())?,
+            resource_uri: WATCH_EVENTS_RESOURCE_URI,
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
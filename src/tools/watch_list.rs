@@ -0,0 +1,370 @@
+use super::alerts::{AlertSeverity, AlertSink};
+use super::monitor_new_pairs::MAX_SCAN_BLOCKS;
+use super::units::from_base_units;
+use crate::ethereum::{EthereumClientTrait, NewHeads, TokenEventSource};
+use anyhow::Result;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::warn;
+
+pub const DEFAULT_WATCH_POLL: Duration = Duration::from_secs(15);
+pub const DEFAULT_WATCH_FEED_SIZE: usize = 1000;
+
+/// Addresses watched at once, each costing a few RPC calls per poll
+pub const MAX_WATCHED_ADDRESSES: usize = 100;
+
+/// Topic of notifications pushed for each event
+pub const WATCH_TOPIC: &str = "wallet_watch";
+
+/// Resource publishing the event feed
+pub const WATCH_EVENTS_RESOURCE_URI: &str = "watch://events";
+
+/// An address the server polls for activity
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WatchedAddress {
+    pub address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// ETH balance at the last poll
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eth_balance: Option<Decimal>,
+    /// First block the next poll scans for transfers; unset until the first poll
+    #[serde(skip)]
+    pub next_block: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchEventKind {
+    /// An ERC20 transfer to the address
+    IncomingTransfer,
+    /// An ERC20 transfer from the address
+    OutgoingTransfer,
+    /// The ETH balance moved, by plain transfers, contract calls or gas fees
+    BalanceChange,
+}
+
+/// Activity seen on a watched address
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WatchEvent {
+    /// Increases with every event, so readers can ask for what they have not seen
+    pub id: u64,
+    pub address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub kind: WatchEventKind,
+    /// Token contract; absent for ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The other side of a transfer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<Address>,
+    /// Whole tokens, signed for balance changes; absent when the token's decimals
+    /// are unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    /// Base units of a transfer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_raw: Option<String>,
+    /// ETH balance after a balance change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    /// Block of a transfer, or the block a balance change was seen at
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+}
+
+/// Addresses watched for transfers and balance changes, and the bounded feed of what
+/// was seen. Shared by every session; polled in the background.
+pub struct WatchList {
+    events: Arc<dyn TokenEventSource>,
+    alerts: Option<Arc<dyn AlertSink>>,
+    watched: Mutex<BTreeMap<Address, WatchedAddress>>,
+    feed: Mutex<VecDeque<WatchEvent>>,
+    capacity: usize,
+    next_id: Mutex<u64>,
+    /// Symbol and decimals per token, or None when it does not report them
+    assets: Mutex<HashMap<Address, Option<(String, u8)>>>,
+    /// New blocks that trigger a poll besides the interval
+    heads: Option<Arc<NewHeads>>,
+}
+
+impl WatchList {
+    /// Keep the latest `capacity` events
+    pub fn new(events: Arc<dyn TokenEventSource>, capacity: usize) -> Self {
+        Self {
+            events,
+            alerts: None,
+            watched: Mutex::new(BTreeMap::new()),
+            feed: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            next_id: Mutex::new(1),
+            assets: Mutex::new(HashMap::new()),
+            heads: None,
+        }
+    }
+
+    /// Also poll on every block `heads` announces, including the head it announces
+    /// after reconnecting, which scans the blocks missed while it was down
+    pub fn with_new_heads(mut self, heads: Arc<NewHeads>) -> Self {
+        self.heads = Some(heads);
+        self
+    }
+
+    /// Push every event to `alerts` as well as keeping it in the feed
+    pub fn with_alert_sink(mut self, alerts: Arc<dyn AlertSink>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Start watching `address`, or relabel it when it is watched already. Its
+    /// balance and scan position are taken at the next poll unless given.
+    pub fn add(
+        &self,
+        address: Address,
+        label: Option<String>,
+        baseline: Option<(Decimal, u64)>,
+    ) -> Result<WatchedAddress> {
+        let mut watched = self.watched.lock().unwrap_or_else(|e| e.into_inner());
+        if !watched.contains_key(&address) && watched.len() >= MAX_WATCHED_ADDRESSES {
+            anyhow::bail!(
+                "Already watching {} addresses; remove one first",
+                MAX_WATCHED_ADDRESSES
+            );
+        }
+        let entry = watched.entry(address).or_insert_with(|| WatchedAddress {
+            address,
+            label: None,
+            eth_balance: baseline.map(|(balance, _)| balance),
+            next_block: baseline.map(|(_, block)| block),
+        });
+        if label.is_some() {
+            entry.label = label;
+        }
+        Ok(entry.clone())
+    }
+
+    /// Stop watching `address`; its events stay in the feed
+    pub fn remove(&self, address: Address) -> Option<WatchedAddress> {
+        self.watched
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&address)
+    }
+
+    pub fn watched(&self) -> Vec<WatchedAddress> {
+        self.watched
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Events with an id above `since_id`, of `address` when given, oldest first
+    pub fn events_since(&self, since_id: u64, address: Option<Address>) -> Vec<WatchEvent> {
+        self.feed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|event| event.id > since_id)
+            .filter(|event| address.is_none_or(|address| event.address == address))
+            .cloned()
+            .collect()
+    }
+
+    /// Check every watched address for transfers and balance changes since the last
+    /// poll, adding what is found to the feed and pushing it to the alert sink.
+    /// Returns the new events.
+    pub async fn poll<C: EthereumClientTrait>(&self, client: &C) -> Result<Vec<WatchEvent>> {
+        let watched = self.watched();
+        if watched.is_empty() {
+            return Ok(Vec::new());
+        }
+        let head = client.get_block_number().await?;
+
+        let mut found = Vec::new();
+        for entry in watched {
+            match self.check(client, &entry, head).await {
+                Ok((balance, events)) => {
+                    found.extend(events);
+                    // Skip addresses removed while the poll ran
+                    if let Some(current) = self
+                        .watched
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get_mut(&entry.address)
+                    {
+                        current.eth_balance = Some(balance);
+                        current.next_block = Some(head + 1);
+                    }
+                }
+                Err(e) => warn!(
+                    address = ?entry.address,
+                    "Failed to check watched address: {:#}", e
+                ),
+            }
+        }
+
+        let found = self.record(found);
+        if let Some(alerts) = &self.alerts {
+            for event in &found {
+                alerts
+                    .send(WATCH_TOPIC, AlertSeverity::Info, &json!(event))
+                    .await;
+            }
+        }
+        Ok(found)
+    }
+
+    /// The address's ETH balance at `head`, and its activity since the last poll
+    async fn check<C: EthereumClientTrait>(
+        &self,
+        client: &C,
+        entry: &WatchedAddress,
+        head: u64,
+    ) -> Result<(Decimal, Vec<WatchEvent>)> {
+        let balance = client.get_eth_balance(entry.address).await?;
+        let event = |kind| WatchEvent {
+            id: 0,
+            address: entry.address,
+            label: entry.label.clone(),
+            kind,
+            token: None,
+            symbol: None,
+            counterparty: None,
+            amount: None,
+            amount_raw: None,
+            balance: None,
+            block_number: head,
+            tx_hash: None,
+        };
+
+        let mut events = Vec::new();
+        // The first poll of an address only takes its starting point
+        if let Some(next_block) = entry.next_block {
+            let from_block = next_block.max(head.saturating_sub(MAX_SCAN_BLOCKS));
+            if from_block <= head {
+                let transfers = self
+                    .events
+                    .address_transfers(entry.address, from_block, head)
+                    .await?;
+                for transfer in transfers {
+                    let (kind, counterparty) = if transfer.to == entry.address {
+                        (WatchEventKind::IncomingTransfer, transfer.from)
+                    } else {
+                        (WatchEventKind::OutgoingTransfer, transfer.to)
+                    };
+                    let asset = self.asset(client, transfer.token).await;
+                    events.push(WatchEvent {
+                        token: Some(transfer.token),
+                        counterparty: Some(counterparty),
+                        amount: asset.as_ref().and_then(|(_, decimals)| {
+                            from_base_units(transfer.amount, *decimals as u32)
+                                .ok()
+                                .map(|amount| amount.to_string())
+                        }),
+                        symbol: asset.map(|(symbol, _)| symbol),
+                        amount_raw: Some(transfer.amount.to_string()),
+                        block_number: transfer.block_number,
+                        tx_hash: Some(transfer.tx_hash),
+                        ..event(kind)
+                    });
+                }
+            }
+        }
+        if let Some(previous) = entry.eth_balance.filter(|previous| *previous != balance) {
+            events.push(WatchEvent {
+                symbol: Some("ETH".to_string()),
+                amount: Some((balance - previous).normalize().to_string()),
+                balance: Some(balance.normalize().to_string()),
+                ..event(WatchEventKind::BalanceChange)
+            });
+        }
+        Ok((balance, events))
+    }
+
+    async fn asset<C: EthereumClientTrait>(
+        &self,
+        client: &C,
+        token: Address,
+    ) -> Option<(String, u8)> {
+        let cached = self
+            .assets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&token)
+            .cloned();
+        if let Some(asset) = cached {
+            return asset;
+        }
+        let asset = match (
+            client.get_token_symbol(token).await,
+            client.get_token_decimals(token).await,
+        ) {
+            (Ok(symbol), Ok(decimals)) => Some((symbol, decimals)),
+            _ => None,
+        };
+        self.assets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(token, asset.clone());
+        asset
+    }
+
+    /// Number `events` and add them to the feed, dropping the oldest beyond capacity
+    fn record(&self, mut events: Vec<WatchEvent>) -> Vec<WatchEvent> {
+        let mut next_id = self.next_id.lock().unwrap_or_else(|e| e.into_inner());
+        let mut feed = self.feed.lock().unwrap_or_else(|e| e.into_inner());
+        for event in &mut events {
+            event.id = *next_id;
+            *next_id += 1;
+            feed.push_back(event.clone());
+        }
+        while feed.len() > self.capacity {
+            feed.pop_front();
+        }
+        events
+    }
+
+    /// Poll every `every`, and on each new head when following them, until `stop`
+    /// is set
+    pub fn spawn<C: EthereumClientTrait + 'static>(
+        self: Arc<Self>,
+        client: Arc<C>,
+        every: Duration,
+        mut stop: watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            let mut heads = self.heads.as_ref().map(|heads| heads.subscribe());
+            loop {
+                let new_head = async {
+                    match heads.as_mut() {
+                        Some(heads) => heads.changed().await.is_ok(),
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    true = new_head => {}
+                    // Set, or the server that started the job is gone
+                    _ = stop.changed() => break,
+                }
+                if let Err(e) = self.poll(client.as_ref()).await {
+                    warn!("Failed to poll watched addresses: {:#}", e);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,356 @@
+use crate::config::{env_var, secret_var};
+use crate::secrets::{register_secret, SecretString};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub const DEFAULT_VAULT_APPROLE_MOUNT: &str = "approle";
+
+const VAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Tokens are renewed once this share of their TTL has passed
+const VAULT_RENEW_FRACTION: f64 = 2.0 / 3.0;
+// How soon a failed renewal is retried
+const VAULT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+// Fields of the secret read at startup, by upper-case name, consulted by `secret_var`
+static SECRETS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+// The client that read them, kept logged in and reused by reloads
+static CLIENT: Mutex<Option<Arc<VaultClient>>> = Mutex::new(None);
+
+/// How the server logs in to Vault
+#[derive(Debug, Clone, PartialEq)]
+pub enum VaultAuth {
+    /// A token issued out of band
+    Token(SecretString),
+    /// An AppRole login, which is repeated when the token can no longer be renewed
+    AppRole {
+        role_id: String,
+        secret_id: SecretString,
+        mount: String,
+    },
+}
+
+/// Where configuration secrets are read from in Vault
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultConfig {
+    /// Server address, such as `https://vault.internal:8200`
+    pub addr: String,
+    pub namespace: Option<String>,
+    pub auth: VaultAuth,
+    /// API path of a KV secret, such as `secret/data/trading` for KV version 2
+    pub secret_path: String,
+}
+
+impl VaultConfig {
+    /// Settings from VAULT_* variables, or None when VAULT_ADDR is not set
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(addr) = env_var("VAULT_ADDR")
+            .ok()
+            .map(|addr| addr.trim().trim_end_matches('/').to_string())
+            .filter(|addr| !addr.is_empty())
+        else {
+            return Ok(None);
+        };
+        let auth = match (env_var("VAULT_ROLE_ID"), secret_var("VAULT_TOKEN")?) {
+            (Ok(role_id), _) => {
+                let secret_id = SecretString::new(
+                    secret_var("VAULT_SECRET_ID")?
+                        .context("VAULT_SECRET_ID is required with VAULT_ROLE_ID")?,
+                );
+                register_secret(&secret_id);
+                VaultAuth::AppRole {
+                    role_id: role_id.trim().to_string(),
+                    secret_id,
+                    mount: env_var("VAULT_APPROLE_MOUNT")
+                        .unwrap_or_else(|_| DEFAULT_VAULT_APPROLE_MOUNT.to_string()),
+                }
+            }
+            (Err(_), Some(token)) => {
+                let token = SecretString::new(token.trim());
+                register_secret(&token);
+                VaultAuth::Token(token)
+            }
+            (Err(_), None) => {
+                anyhow::bail!("VAULT_ADDR is set but neither VAULT_TOKEN nor VAULT_ROLE_ID is")
+            }
+        };
+        let secret_path = env_var("VAULT_SECRET_PATH")
+            .ok()
+            .map(|path| path.trim().trim_matches('/').to_string())
+            .filter(|path| !path.is_empty())
+            .context("VAULT_SECRET_PATH is required with VAULT_ADDR")?;
+
+        Ok(Some(Self {
+            addr,
+            namespace: env_var("VAULT_NAMESPACE")
+                .ok()
+                .filter(|namespace| !namespace.trim().is_empty()),
+            auth,
+            secret_path,
+        }))
+    }
+}
+
+/// The token the client holds and how long it lives
+#[derive(Debug, Clone)]
+struct VaultToken {
+    token: SecretString,
+    /// Zero for a token that never expires
+    ttl: Duration,
+    renewable: bool,
+}
+
+/// A logged-in Vault client
+#[derive(Debug)]
+pub struct VaultClient {
+    config: VaultConfig,
+    http: reqwest::Client,
+    token: tokio::sync::Mutex<VaultToken>,
+}
+
+impl VaultClient {
+    /// Log in, or look up the given token to learn its TTL
+    pub async fn login(config: &VaultConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(VAULT_REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to build the Vault HTTP client")?;
+        let mut client = Self {
+            config: config.clone(),
+            http,
+            token: tokio::sync::Mutex::new(VaultToken {
+                token: SecretString::default(),
+                ttl: Duration::ZERO,
+                renewable: false,
+            }),
+        };
+        *client.token.get_mut() = client.authenticate().await?;
+        Ok(client)
+    }
+
+    async fn authenticate(&self) -> Result<VaultToken> {
+        match &self.config.auth {
+            VaultAuth::Token(token) => {
+                let response = self
+                    .request(reqwest::Method::GET, "auth/token/lookup-self", token, None)
+                    .await
+                    .context("Vault rejected VAULT_TOKEN")?;
+                Ok(VaultToken {
+                    token: token.clone(),
+                    ttl: Duration::from_secs(response["data"]["ttl"].as_u64().unwrap_or(0)),
+                    renewable: response["data"]["renewable"].as_bool().unwrap_or(false),
+                })
+            }
+            VaultAuth::AppRole {
+                role_id,
+                secret_id,
+                mount,
+            } => {
+                let response = self
+                    .request(
+                        reqwest::Method::POST,
+                        &format!("auth/{}/login", mount.trim_matches('/')),
+                        &SecretString::default(),
+                        Some(json!({
+                            "role_id": role_id,
+                            "secret_id": secret_id.expose_secret(),
+                        })),
+                    )
+                    .await
+                    .context("Vault AppRole login failed")?;
+                token_from_auth(&response)
+            }
+        }
+    }
+
+    /// The string fields of the configured secret, by upper-case name. Reads KV
+    /// version 1 and 2 secrets alike.
+    pub async fn read_secrets(&self) -> Result<BTreeMap<String, String>> {
+        let token = self.token.lock().await.token.clone();
+        let response = self
+            .request(reqwest::Method::GET, &self.config.secret_path, &token, None)
+            .await
+            .with_context(|| format!("Failed to read {} from Vault", self.config.secret_path))?;
+        let data = &response["data"];
+        // KV version 2 nests the fields beside the version's metadata
+        let fields = match (&data["data"], &data["metadata"]) {
+            (Value::Object(fields), Value::Object(_)) => fields,
+            _ => data
+                .as_object()
+                .context("Vault returned a secret without fields")?,
+        };
+        Ok(fields
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Number(value) => value.to_string(),
+                    Value::Bool(value) => value.to_string(),
+                    _ => return None,
+                };
+                Some((key.to_ascii_uppercase(), value))
+            })
+            .collect())
+    }
+
+    /// Extend the token's lease, or log in again once it can no longer be extended.
+    /// Returns how long to wait before the next renewal, or None when there is
+    /// nothing left to renew.
+    pub async fn renew(&self) -> Result<Option<Duration>> {
+        let mut token = self.token.lock().await;
+        if token.ttl.is_zero() {
+            return Ok(None);
+        }
+        let renewed = if token.renewable {
+            self.request(
+                reqwest::Method::POST,
+                "auth/token/renew-self",
+                &token.token,
+                Some(json!({})),
+            )
+            .await
+            .and_then(|response| token_from_auth(&response))
+        } else {
+            Err(anyhow::anyhow!("the token is not renewable"))
+        };
+        *token = match (renewed, &self.config.auth) {
+            (Ok(renewed), _) => renewed,
+            (Err(e), VaultAuth::AppRole { .. }) => {
+                warn!("Logging in to Vault again: {:#}", e);
+                self.authenticate().await?
+            }
+            (Err(e), VaultAuth::Token(_)) if token.renewable => return Err(e),
+            (Err(_), VaultAuth::Token(_)) => {
+                warn!(
+                    "VAULT_TOKEN is not renewable and expires in {}s; reloads will fail after that",
+                    token.ttl.as_secs()
+                );
+                return Ok(None);
+            }
+        };
+        Ok(renew_after(&token))
+    }
+
+    /// How long after the last login or renewal the token should be renewed
+    pub async fn next_renewal(&self) -> Option<Duration> {
+        renew_after(&*self.token.lock().await)
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        token: &SecretString,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        let mut request = self
+            .http
+            .request(method, format!("{}/v1/{}", self.config.addr, path));
+        if !token.expose_secret().is_empty() {
+            request = request.header("X-Vault-Token", token.expose_secret());
+        }
+        if let Some(namespace) = &self.config.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.context("Vault request failed")?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let errors = body["errors"]
+                .as_array()
+                .map(|errors| {
+                    errors
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .filter(|errors| !errors.is_empty())
+                .unwrap_or_else(|| "no details".to_string());
+            anyhow::bail!("Vault refused the request ({}): {}", status, errors);
+        }
+        Ok(body)
+    }
+}
+
+fn token_from_auth(response: &Value) -> Result<VaultToken> {
+    let auth = &response["auth"];
+    let token = SecretString::new(
+        auth["client_token"]
+            .as_str()
+            .context("Vault returned no client token")?,
+    );
+    register_secret(&token);
+    Ok(VaultToken {
+        token,
+        ttl: Duration::from_secs(auth["lease_duration"].as_u64().unwrap_or(0)),
+        renewable: auth["renewable"].as_bool().unwrap_or(false),
+    })
+}
+
+fn renew_after(token: &VaultToken) -> Option<Duration> {
+    (!token.ttl.is_zero()).then(|| token.ttl.mul_f64(VAULT_RENEW_FRACTION))
+}
+
+/// The value Vault holds for the configuration variable `key`, if any
+pub fn secret(key: &str) -> Option<String> {
+    SECRETS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&key.to_ascii_uppercase())
+        .cloned()
+}
+
+/// Replace the values `secret` returns, keeping each out of logs
+pub fn store_secrets(secrets: BTreeMap<String, String>) {
+    for value in secrets.values() {
+        register_secret(&SecretString::new(value.as_str()));
+    }
+    *SECRETS.write().unwrap_or_else(|e| e.into_inner()) = secrets;
+}
+
+/// Read configuration secrets from Vault when VAULT_ADDR is set. The first call logs
+/// in and keeps the token renewed in the background; later ones, such as on reload,
+/// read the secret again with the same login.
+pub async fn load_secrets() -> Result<()> {
+    let existing = CLIENT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let client = match existing {
+        Some(client) => client,
+        None => {
+            let Some(config) = VaultConfig::from_env()? else {
+                return Ok(());
+            };
+            let client = Arc::new(VaultClient::login(&config).await?);
+            tokio::spawn(keep_renewed(client.clone()));
+            *CLIENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(client.clone());
+            client
+        }
+    };
+    let secrets = client.read_secrets().await?;
+    info!(
+        count = secrets.len(),
+        "Read configuration secrets from Vault at {}", client.config.secret_path
+    );
+    store_secrets(secrets);
+    Ok(())
+}
+
+async fn keep_renewed(client: Arc<VaultClient>) {
+    let mut next = client.next_renewal().await;
+    while let Some(delay) = next {
+        tokio::time::sleep(delay).await;
+        next = match client.renew().await {
+            Ok(next) => next,
+            Err(e) => {
+                warn!("Failed to renew the Vault token: {:#}", e);
+                Some(VAULT_RETRY_INTERVAL)
+            }
+        };
+    }
+}
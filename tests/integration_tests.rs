@@ -1,6 +1,5 @@
+use ethereum::EthereumClientTrait;
 use ethereum_trading_mcp_server::*;
-use ethers::signers::Signer;
-use tokio;
 
 // Integration tests that query real Ethereum data
 // These tests require an internet connection and working RPC endpoint
@@ -11,14 +10,20 @@ async fn test_get_balance_real_eth() {
     // This test queries Vitalik's real ETH balance
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
-    let client =
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client");
+    let client = ethereum::EthereumClient::new(
+        &config.eth_rpc_url,
+        config.private_key.as_ref().unwrap().expose_secret(),
+        config.chain_id,
+    )
+    .await
+    .expect("Failed to create Ethereum client");
 
     // Vitalik's address
     let vitalik_address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
@@ -41,14 +46,20 @@ async fn test_get_balance_real_usdc() {
     // This test queries a real USDC balance
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
-    let client =
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client");
+    let client = ethereum::EthereumClient::new(
+        &config.eth_rpc_url,
+        config.private_key.as_ref().unwrap().expose_secret(),
+        config.chain_id,
+    )
+    .await
+    .expect("Failed to create Ethereum client");
 
     // Binance hot wallet
     let binance_address = "0x28C6c06298d514Db089934071355E5743bf21d60"
@@ -78,14 +89,20 @@ async fn test_get_balance_real_usdc() {
 async fn test_get_token_symbol_real() {
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
-    let client =
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client");
+    let client = ethereum::EthereumClient::new(
+        &config.eth_rpc_url,
+        config.private_key.as_ref().unwrap().expose_secret(),
+        config.chain_id,
+    )
+    .await
+    .expect("Failed to create Ethereum client");
 
     // USDC contract
     let usdc_address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
@@ -106,16 +123,22 @@ async fn test_get_token_symbol_real() {
 async fn test_uniswap_price_real() {
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
-    let client =
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client");
+    let client = ethereum::EthereumClient::new(
+        &config.eth_rpc_url,
+        config.private_key.as_ref().unwrap().expose_secret(),
+        config.chain_id,
+    )
+    .await
+    .expect("Failed to create Ethereum client");
 
-    let uniswap = ethereum::UniswapV2Router::new(client.get_provider());
+    let uniswap = ethereum::UniswapV2Router::new(client.get_provider()).unwrap();
 
     // Get WETH price in USDC (should be around current ETH price)
     let weth_address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
@@ -148,17 +171,24 @@ async fn test_uniswap_price_real() {
 async fn test_uniswap_swap_simulation_real() {
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
     let client = std::sync::Arc::new(
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client"),
+        ethereum::EthereumClient::new(
+            &config.eth_rpc_url,
+            config.private_key.as_ref().unwrap().expose_secret(),
+            config.chain_id,
+        )
+        .await
+        .expect("Failed to create Ethereum client"),
     );
 
-    let uniswap = ethereum::UniswapV2Router::new(client.get_provider());
+    let uniswap = ethereum::UniswapV2Router::new(client.get_provider()).unwrap();
 
     // Simulate swapping 1 WETH to USDC
     let weth_address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
@@ -170,7 +200,7 @@ async fn test_uniswap_swap_simulation_real() {
         .expect("Invalid USDC address");
 
     let one_weth = ethers::types::U256::from(10u64.pow(18));
-    let wallet_address = client.get_wallet().address();
+    let wallet_address = client.get_wallet_address();
 
     let simulation = uniswap
         .simulate_swap(weth_address, usdc_address, one_weth, wallet_address)
@@ -206,14 +236,21 @@ async fn test_mcp_get_balance_tool_real() {
 
     let config = config::Config {
         eth_rpc_url: "https://eth.llamarpc.com".to_string(),
-        private_key: "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        private_key: Some(
+            "0000000000000000000000000000000000000000000000000000000000000001".into(),
+        ),
         chain_id: 1,
+        ..Default::default()
     };
 
     let client = std::sync::Arc::new(
-        ethereum::EthereumClient::new(&config.eth_rpc_url, &config.private_key, config.chain_id)
-            .await
-            .expect("Failed to create Ethereum client"),
+        ethereum::EthereumClient::new(
+            &config.eth_rpc_url,
+            config.private_key.as_ref().unwrap().expose_secret(),
+            config.chain_id,
+        )
+        .await
+        .expect("Failed to create Ethereum client"),
     );
 
     let tool = tools::GetBalanceTool::new(client);